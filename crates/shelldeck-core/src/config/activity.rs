@@ -34,6 +34,10 @@ pub enum ActivityAction {
     OpenTerminal,
     OpenConnection,
     ConnectConnection,
+    /// Run the SSH doctor on the target connection.
+    DiagnoseConnection,
+    /// Open the host metrics panel of the target connection.
+    OpenHostMetrics,
     OpenForward,
     OpenScript,
     OpenSupport,
@@ -56,6 +56,22 @@ pub struct AppConfig {
     /// written before the AI Dock continue to parse unchanged.
     #[serde(default)]
     pub companion: CompanionConfig,
+    /// `[site_health]` — probe interval and certificate warning threshold
+    /// for monitored sites. Defaulted so older configs parse unchanged.
+    #[serde(default)]
+    pub site_health: crate::config::site_health::SiteHealthConfig,
+    /// `[tunnels]` — when a supervised forward that stays down is worth a
+    /// notification. Defaulted so older configs parse unchanged.
+    #[serde(default)]
+    pub tunnels: crate::config::tunnel_history::TunnelsConfig,
+    /// `[host_metrics]` — hosts sampled over SSH and their alert
+    /// thresholds. Defaulted so older configs parse unchanged.
+    #[serde(default)]
+    pub host_metrics: crate::config::host_metrics::HostMetricsConfig,
+    /// `[log_viewer]` — sources tailed together in the log viewer.
+    /// Defaulted so older configs parse unchanged.
+    #[serde(default)]
+    pub log_viewer: crate::config::log_viewer::LogViewerConfig,
     /// Connection ids shown in the sidebar and system-tray quick-access
     /// sections. Order is user-defined and preserved across sessions.
     #[serde(default)]
@@ -265,6 +281,18 @@ pub struct TrayConfig {
     /// Show an OS notification when an AI task completes while the
     /// ShellDeck window is not active.
     pub notify_ai_tasks: bool,
+    /// Show an OS notification when a scheduled or event-triggered script
+    /// run fails.
+    pub notify_scheduled_failures: bool,
+    /// Show an OS notification when a monitored site goes down or its
+    /// certificate is about to expire.
+    pub notify_site_health: bool,
+    /// Show an OS notification when a supervised port forward has been
+    /// down longer than `[tunnels] down_alert_secs`.
+    pub notify_tunnel_down: bool,
+    /// Show an OS notification when a monitored host's disk fills up or
+    /// its load climbs past `[host_metrics]` thresholds.
+    pub notify_host_metrics: bool,
 }
 
 impl Default for TrayConfig {
@@ -279,6 +307,10 @@ impl Default for TrayConfig {
             notify_ssh_disconnect: true,
             notify_fleet_done: true,
             notify_ai_tasks: true,
+            notify_scheduled_failures: true,
+            notify_site_health: true,
+            notify_tunnel_down: true,
+            notify_host_metrics: true,
         }
     }
 }
@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::error::{Result, ShellDeckError};
+use crate::models::db_backup::{BackupEntry, BackupPlan};
+
+/// Backup plans of databases and the archives they produced.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupCatalog {
+    #[serde(default)]
+    pub plans: Vec<BackupPlan>,
+    #[serde(default)]
+    pub entries: Vec<BackupEntry>,
+}
+
+impl BackupCatalog {
+    fn catalog_path() -> PathBuf {
+        super::app_config::AppConfig::config_dir().join("backups.json")
+    }
+
+    /// Where archives kept on this computer go unless a plan says otherwise.
+    pub fn default_archive_dir() -> PathBuf {
+        super::app_config::AppConfig::config_dir().join("backups")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::catalog_path())
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::catalog_path())
+    }
+
+    /// The plan of `database` on `connection_id`, if it has one.
+    pub fn plan_for(&self, connection_id: Uuid, database: &str) -> Option<&BackupPlan> {
+        self.plans
+            .iter()
+            .find(|p| p.connection_id == connection_id && p.database == database)
+    }
+
+    pub fn plan(&self, plan_id: Uuid) -> Option<&BackupPlan> {
+        self.plans.iter().find(|p| p.id == plan_id)
+    }
+
+    /// Add `plan`, or replace the plan with the same id.
+    pub fn upsert_plan(&mut self, plan: BackupPlan) {
+        match self.plans.iter_mut().find(|p| p.id == plan.id) {
+            Some(existing) => *existing = plan,
+            None => self.plans.push(plan),
+        }
+    }
+
+    /// Archives of a plan, newest first.
+    pub fn entries_for(&self, plan_id: Uuid) -> Vec<&BackupEntry> {
+        let mut entries: Vec<&BackupEntry> = self
+            .entries
+            .iter()
+            .filter(|e| e.plan_id == plan_id)
+            .collect();
+        entries.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        entries
+    }
+
+    pub fn entry(&self, entry_id: Uuid) -> Option<&BackupEntry> {
+        self.entries.iter().find(|e| e.id == entry_id)
+    }
+
+    pub fn record(&mut self, entry: BackupEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn remove_entry(&mut self, entry_id: Uuid) -> Option<BackupEntry> {
+        let index = self.entries.iter().position(|e| e.id == entry_id)?;
+        Some(self.entries.remove(index))
+    }
+
+    /// Archives of a plan its retention policy no longer keeps.
+    pub fn expired(&self, plan_id: Uuid) -> Vec<BackupEntry> {
+        let Some(plan) = self.plan(plan_id) else {
+            return Vec::new();
+        };
+        plan.retention
+            .expired(&self.entries_for(plan_id))
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            if !dir.as_os_str().is_empty() && !dir.exists() {
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            ShellDeckError::Serialization(format!("Failed to serialize backup catalog: {}", e))
+        })?;
+        crate::util::atomic_write(path, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Load from a specific path, returning an empty catalog if missing.
+    pub(crate) fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            ShellDeckError::Serialization(format!("Failed to parse backup catalog: {}", e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::db_backup::{BackupDestination, RetentionPolicy};
+    use crate::models::server_sync::{DatabaseEngine, DiscoveredDatabase};
+    use chrono::{Duration, TimeZone, Utc};
+
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-backups-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir.join(name)
+    }
+
+    fn plan() -> BackupPlan {
+        let db = DiscoveredDatabase {
+            name: "shop".into(),
+            engine: DatabaseEngine::Mysql,
+            size_bytes: None,
+            table_count: None,
+        };
+        let mut plan = BackupPlan::new(
+            Uuid::new_v4(),
+            &db,
+            BackupDestination::Local {
+                dir: PathBuf::from("/backups"),
+            },
+        );
+        plan.retention = RetentionPolicy {
+            keep_last: 2,
+            keep_daily: 0,
+            keep_weekly: 0,
+        };
+        plan.schedule = Some("0 3 * * *".into());
+        plan
+    }
+
+    fn entry(plan: &BackupPlan, minutes: i64) -> BackupEntry {
+        let at = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap() + Duration::minutes(minutes);
+        BackupEntry {
+            id: Uuid::new_v4(),
+            plan_id: plan.id,
+            connection_id: plan.connection_id,
+            source_name: "web1".into(),
+            database: plan.database.clone(),
+            engine: plan.engine,
+            created_at: at,
+            size_bytes: 2048,
+            sha256: "ab".repeat(32),
+            destination: plan.destination.clone(),
+            path: format!("/backups/shop-{}.sql.gz", minutes),
+            scheduled: false,
+        }
+    }
+
+    #[test]
+    fn round_trip_and_missing_file() {
+        let path = temp_path("backups.json");
+        assert_eq!(
+            BackupCatalog::load_from(&path).unwrap(),
+            BackupCatalog::default()
+        );
+
+        let plan = plan();
+        let mut catalog = BackupCatalog::default();
+        catalog.upsert_plan(plan.clone());
+        catalog.record(entry(&plan, 0));
+        catalog.save_to(&path).unwrap();
+
+        let loaded = BackupCatalog::load_from(&path).unwrap();
+        assert_eq!(loaded, catalog);
+        assert_eq!(loaded.plan_for(plan.connection_id, "shop"), Some(&plan));
+        assert_eq!(loaded.plan_for(plan.connection_id, "blog"), None);
+    }
+
+    #[test]
+    fn expired_follows_the_plan_retention() {
+        let mut plan = plan();
+        let mut catalog = BackupCatalog::default();
+        catalog.upsert_plan(plan.clone());
+        let entries: Vec<BackupEntry> = (0..4).map(|i| entry(&plan, i * 10)).collect();
+        for e in &entries {
+            catalog.record(e.clone());
+        }
+        assert_eq!(catalog.entries_for(plan.id)[0], &entries[3]);
+        assert_eq!(
+            catalog.expired(plan.id),
+            vec![entries[1].clone(), entries[0].clone()]
+        );
+
+        plan.retention.keep_last = 10;
+        catalog.upsert_plan(plan.clone());
+        assert_eq!(catalog.plans.len(), 1);
+        assert!(catalog.expired(plan.id).is_empty());
+
+        assert_eq!(
+            catalog.remove_entry(entries[0].id),
+            Some(entries[0].clone())
+        );
+        assert_eq!(catalog.entries.len(), 3);
+    }
+}
@@ -0,0 +1,908 @@
+//! Import connections saved by other SSH clients.
+//!
+//! Each reader maps the client's folders to `Connection.group`, its jump
+//! host to `proxy_jump` and its private key to `identity_file`. Nothing is
+//! written here: [`preview`] flags hosts the store already has so the user
+//! can pick what to keep before the store is touched.
+
+use crate::error::{Result, ShellDeckError};
+use crate::models::Connection;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Registry key holding PuTTY's saved sessions.
+const PUTTY_SESSIONS_KEY: &str = "\\Software\\SimonTatham\\PuTTY\\Sessions\\";
+
+/// `ProxyMethod` value PuTTY uses for "SSH to proxy and use port forwarding".
+const PUTTY_PROXY_SSH: u32 = 6;
+
+/// MobaXterm's session type for SSH bookmarks.
+const MOBAXTERM_SSH: &str = "109";
+
+/// The export formats understood by [`read_import_path`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// PuTTY sessions exported from the registry as a `.reg` file.
+    Putty,
+    /// Termius JSON export.
+    Termius,
+    /// MobaXterm `.mxtsessions` bookmarks.
+    MobaXterm,
+    /// Remmina `.remmina` profiles.
+    Remmina,
+    /// CSV with a header row.
+    Csv,
+}
+
+impl ImportFormat {
+    /// Short, human-friendly label for badges and UI.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImportFormat::Putty => "PuTTY",
+            ImportFormat::Termius => "Termius",
+            ImportFormat::MobaXterm => "MobaXterm",
+            ImportFormat::Remmina => "Remmina",
+            ImportFormat::Csv => "CSV",
+        }
+    }
+
+    /// Guess the format from the file extension, then from the content.
+    pub fn detect(path: &Path, content: &str) -> Option<Self> {
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("reg") => return Some(ImportFormat::Putty),
+            Some("mxtsessions") => return Some(ImportFormat::MobaXterm),
+            Some("remmina") => return Some(ImportFormat::Remmina),
+            Some("csv") => return Some(ImportFormat::Csv),
+            Some("json") => return Some(ImportFormat::Termius),
+            _ => {}
+        }
+        let trimmed = content.trim_start();
+        if trimmed.starts_with("Windows Registry Editor") || trimmed.starts_with("REGEDIT") {
+            Some(ImportFormat::Putty)
+        } else if trimmed.starts_with('{')
+            || (trimmed.starts_with('[') && trimmed[1..].trim_start().starts_with('{'))
+        {
+            Some(ImportFormat::Termius)
+        } else if trimmed.starts_with("[Bookmarks") {
+            Some(ImportFormat::MobaXterm)
+        } else if trimmed.starts_with("[remmina]") {
+            Some(ImportFormat::Remmina)
+        } else if trimmed.lines().next().is_some_and(|l| l.contains(',')) {
+            Some(ImportFormat::Csv)
+        } else {
+            None
+        }
+    }
+}
+
+/// A connection read from an export, as offered in the import preview.
+#[derive(Debug, Clone)]
+pub struct ImportCandidate {
+    pub connection: Connection,
+    /// Alias of the connection that already points at the same
+    /// `user@host:port`, in the store or earlier in the same file.
+    pub duplicate_of: Option<String>,
+}
+
+impl ImportCandidate {
+    /// Candidates are pre-selected unless they duplicate a known host.
+    pub fn selected_by_default(&self) -> bool {
+        self.duplicate_of.is_none()
+    }
+}
+
+/// Read an export file, or a directory of Remmina profiles.
+pub fn read_import_path(path: &Path) -> Result<(ImportFormat, Vec<Connection>)> {
+    if path.is_dir() {
+        let mut files: Vec<PathBuf> = std::fs::read_dir(path)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.extension()
+                    .is_some_and(|e| e.eq_ignore_ascii_case("remmina"))
+            })
+            .collect();
+        files.sort();
+        let mut connections = Vec::new();
+        for file in files {
+            connections.extend(parse_remmina(&read_text(&file)?));
+        }
+        return Ok((ImportFormat::Remmina, connections));
+    }
+    let content = read_text(path)?;
+    let format = ImportFormat::detect(path, &content).ok_or_else(|| {
+        ShellDeckError::Config(format!(
+            "Unrecognised connection export: {}",
+            path.display()
+        ))
+    })?;
+    Ok((format, parse_import(format, &content)?))
+}
+
+/// Parse the content of an export in a known format.
+pub fn parse_import(format: ImportFormat, content: &str) -> Result<Vec<Connection>> {
+    match format {
+        ImportFormat::Putty => Ok(parse_putty_reg(content)),
+        ImportFormat::Termius => parse_termius(content),
+        ImportFormat::MobaXterm => Ok(parse_mobaxterm(content)),
+        ImportFormat::Remmina => Ok(parse_remmina(content)),
+        ImportFormat::Csv => parse_csv(content),
+    }
+}
+
+/// Flag the imported connections that repeat a host of `existing` or of an
+/// earlier import, and rename the rest so no alias is taken twice.
+pub fn preview(imported: Vec<Connection>, existing: &[Connection]) -> Vec<ImportCandidate> {
+    let mut known: HashMap<(String, String, u16), String> = existing
+        .iter()
+        .map(|c| (dedupe_key(c), c.alias.clone()))
+        .collect();
+    let mut aliases: HashSet<String> = existing.iter().map(|c| c.alias.clone()).collect();
+    imported
+        .into_iter()
+        .map(|mut connection| {
+            let key = dedupe_key(&connection);
+            let duplicate_of = known.get(&key).cloned();
+            if duplicate_of.is_none() {
+                connection.alias = unique_alias(&connection.alias, &aliases);
+                known.insert(key, connection.alias.clone());
+            }
+            aliases.insert(connection.alias.clone());
+            ImportCandidate {
+                connection,
+                duplicate_of,
+            }
+        })
+        .collect()
+}
+
+fn dedupe_key(connection: &Connection) -> (String, String, u16) {
+    (
+        connection.user.clone(),
+        connection.hostname.to_ascii_lowercase(),
+        connection.port,
+    )
+}
+
+/// `alias`, or `alias (2)`, `alias (3)`… when it is already taken.
+fn unique_alias(alias: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(alias) {
+        return alias.to_string();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", alias, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("an unused suffix exists")
+}
+
+/// Read a file as text. `regedit` writes UTF-16LE with a byte order mark.
+fn read_text(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)?;
+    Ok(decode_text(&bytes))
+}
+
+fn decode_text(bytes: &[u8]) -> String {
+    if let Some(rest) = bytes.strip_prefix(&[0xFF, 0xFE]) {
+        let units: Vec<u16> = rest
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        return String::from_utf16_lossy(&units);
+    }
+    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Build a connection from the fields every format provides. The alias
+/// falls back to the hostname and the user to the local account.
+fn imported_connection(alias: &str, hostname: &str, port: Option<u16>, user: &str) -> Connection {
+    let alias = if alias.trim().is_empty() {
+        hostname.trim()
+    } else {
+        alias.trim()
+    };
+    let user = if user.trim().is_empty() {
+        super::ssh_config::default_user()
+    } else {
+        user.trim().to_string()
+    };
+    let mut connection =
+        Connection::new_manual(alias.to_string(), hostname.trim().to_string(), user);
+    connection.port = port.unwrap_or(22);
+    connection
+}
+
+/// `user@host:port` for `proxy_jump`, leaving out what is unset.
+fn jump_spec(host: &str, port: Option<u16>, user: &str) -> Option<String> {
+    let host = host.trim();
+    if host.is_empty() {
+        return None;
+    }
+    let mut spec = String::new();
+    if !user.trim().is_empty() {
+        spec.push_str(user.trim());
+        spec.push('@');
+    }
+    spec.push_str(host);
+    if let Some(port) = port.filter(|p| *p != 22) {
+        spec.push_str(&format!(":{}", port));
+    }
+    Some(spec)
+}
+
+/// Split `host:port`, keeping `host` alone when there is no valid port.
+fn split_host_port(value: &str) -> (String, Option<u16>) {
+    match value.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => match port.parse() {
+            Ok(port) => (host.to_string(), Some(port)),
+            Err(_) => (value.to_string(), None),
+        },
+        _ => (value.to_string(), None),
+    }
+}
+
+fn non_empty(value: &str) -> Option<&str> {
+    let value = value.trim();
+    (!value.is_empty()).then_some(value)
+}
+
+fn key_path(value: &str) -> Option<PathBuf> {
+    non_empty(value).map(|v| super::ssh_config::expand_tilde(Path::new(v)))
+}
+
+// ---------------------------------------------------------------------------
+// PuTTY
+// ---------------------------------------------------------------------------
+
+/// Sessions of a `regedit` export of PuTTY's registry key. Only SSH
+/// sessions are kept, and `Default Settings` is skipped.
+pub fn parse_putty_reg(content: &str) -> Vec<Connection> {
+    let mut connections = Vec::new();
+    let mut session: Option<(String, HashMap<String, String>)> = None;
+    let mut finish = |session: Option<(String, HashMap<String, String>)>| {
+        if let Some((name, values)) = session {
+            connections.extend(putty_session(&name, &values));
+        }
+    };
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            finish(session.take());
+            session = section
+                .find(PUTTY_SESSIONS_KEY)
+                .map(|at| &section[at + PUTTY_SESSIONS_KEY.len()..])
+                .filter(|name| !name.is_empty() && !name.contains('\\'))
+                .map(|name| (percent_decode(name), HashMap::new()));
+        } else if let Some((_, values)) = session.as_mut() {
+            if let Some((name, value)) = putty_value(line) {
+                values.insert(name, value);
+            }
+        }
+    }
+    finish(session);
+    connections
+}
+
+fn putty_session(name: &str, values: &HashMap<String, String>) -> Option<Connection> {
+    if name == "Default Settings" {
+        return None;
+    }
+    if values.get("Protocol").is_some_and(|p| p != "ssh") {
+        return None;
+    }
+    let hostname = non_empty(values.get("HostName")?)?;
+    // PuTTY accepts `user@host` in the host field.
+    let (user, hostname) = match hostname.split_once('@') {
+        Some((user, host)) => (user, host),
+        None => (
+            values.get("UserName").map(String::as_str).unwrap_or(""),
+            hostname,
+        ),
+    };
+    let port = values.get("PortNumber").and_then(|p| p.parse().ok());
+    let mut connection = imported_connection(name, hostname, port, user);
+    connection.identity_file = values.get("PublicKeyFile").and_then(|k| key_path(k));
+    connection.forward_agent = values.get("AgentFwd").is_some_and(|v| v == "1");
+    let proxy_method = values
+        .get("ProxyMethod")
+        .and_then(|m| m.parse::<u32>().ok());
+    if proxy_method == Some(PUTTY_PROXY_SSH) {
+        connection.proxy_jump = jump_spec(
+            values.get("ProxyHost").map(String::as_str).unwrap_or(""),
+            values.get("ProxyPort").and_then(|p| p.parse().ok()),
+            values
+                .get("ProxyUsername")
+                .map(String::as_str)
+                .unwrap_or(""),
+        );
+    }
+    Some(connection)
+}
+
+/// `"Name"="string"` or `"Name"=dword:0000002a`, with dwords as decimal.
+fn putty_value(line: &str) -> Option<(String, String)> {
+    let rest = line.strip_prefix('"')?;
+    let (name, value) = rest.split_once("\"=")?;
+    let value = if let Some(dword) = value.strip_prefix("dword:") {
+        u32::from_str_radix(dword.trim(), 16).ok()?.to_string()
+    } else {
+        let quoted = value.strip_prefix('"')?.strip_suffix('"')?;
+        quoted.replace("\\\"", "\"").replace("\\\\", "\\")
+    };
+    Some((name.to_string(), value))
+}
+
+/// Session names are stored with `%XX` escapes for spaces and symbols.
+fn percent_decode(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+// ---------------------------------------------------------------------------
+// Termius
+// ---------------------------------------------------------------------------
+
+/// Hosts of a Termius JSON export. The export is read loosely since its
+/// shape has changed between releases: hosts come from a `hosts` array or
+/// a top-level array, and groups may be inline or referenced by id.
+pub fn parse_termius(content: &str) -> Result<Vec<Connection>> {
+    let root: Value = serde_json::from_str(content)
+        .map_err(|e| ShellDeckError::Serialization(format!("Invalid Termius export: {}", e)))?;
+    let hosts = match &root {
+        Value::Array(hosts) => hosts.as_slice(),
+        Value::Object(map) => map
+            .get("hosts")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default(),
+        _ => &[],
+    };
+    let groups = termius_groups(&root);
+    let keys = termius_keys(&root);
+    let by_id: HashMap<String, &Value> = hosts
+        .iter()
+        .filter_map(|h| Some((json_id(h.get("id")?)?, h)))
+        .collect();
+
+    Ok(hosts
+        .iter()
+        .filter_map(|host| {
+            let hostname = json_str(host, &["address", "hostname", "host"])?;
+            let ssh = host.get("ssh_config").unwrap_or(&Value::Null);
+            let user = json_str(host, &["username", "user"])
+                .or_else(|| ssh.pointer("/identity/username").and_then(Value::as_str))
+                .unwrap_or("");
+            let port = json_port(host).or_else(|| json_port(ssh));
+            let label = json_str(host, &["label", "name"]).unwrap_or("");
+            let mut connection = imported_connection(label, hostname, port, user);
+            connection.group = host.get("group").and_then(|g| termius_group(g, &groups));
+            connection.identity_file = termius_key(host, &keys).or_else(|| termius_key(ssh, &keys));
+            connection.proxy_jump = termius_jump(host, &by_id);
+            connection.tags = host
+                .get("tags")
+                .and_then(Value::as_array)
+                .map(|tags| {
+                    tags.iter()
+                        .filter_map(|t| t.as_str().or_else(|| json_str(t, &["label"])))
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(connection)
+        })
+        .collect())
+}
+
+/// Group id → (label, parent id).
+fn termius_groups(root: &Value) -> HashMap<String, (String, Option<String>)> {
+    root.get("groups")
+        .and_then(Value::as_array)
+        .map(|groups| {
+            groups
+                .iter()
+                .filter_map(|g| {
+                    let id = json_id(g.get("id")?)?;
+                    let label = json_str(g, &["label", "name"])?.to_string();
+                    let parent = g
+                        .get("parent_group")
+                        .and_then(|p| json_id(p).or_else(|| json_id(p.get("id")?)));
+                    Some((id, (label, parent)))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Resolve a host's group to a `Parent/Child` path.
+fn termius_group(
+    group: &Value,
+    groups: &HashMap<String, (String, Option<String>)>,
+) -> Option<String> {
+    if let Some(label) = group.as_str().filter(|g| !groups.contains_key(*g)) {
+        return non_empty(label).map(str::to_string);
+    }
+    if let Some(label) = json_str(group, &["label", "name"]) {
+        return non_empty(label).map(str::to_string);
+    }
+    let mut id = json_id(group);
+    let mut path = Vec::new();
+    while let Some((label, parent)) = id.as_ref().and_then(|id| groups.get(id)) {
+        if path.len() >= groups.len() {
+            break; // A parent cycle.
+        }
+        path.push(label.clone());
+        id = parent.clone();
+    }
+    path.reverse();
+    (!path.is_empty()).then(|| path.join("/"))
+}
+
+/// Key id → private key path, for keys referenced by id.
+fn termius_keys(root: &Value) -> HashMap<String, PathBuf> {
+    ["ssh_keys", "keys"]
+        .iter()
+        .filter_map(|name| root.get(*name)?.as_array())
+        .flatten()
+        .filter_map(|k| {
+            let id = json_id(k.get("id")?)?;
+            let path = key_path(json_str(k, &["path", "private_key_path", "file"])?)?;
+            Some((id, path))
+        })
+        .collect()
+}
+
+/// A key given inline, as a path, or by id, on the value or its `identity`.
+fn termius_key(value: &Value, keys: &HashMap<String, PathBuf>) -> Option<PathBuf> {
+    let holders = [Some(value), value.get("identity")];
+    let key = holders
+        .into_iter()
+        .flatten()
+        .flat_map(|holder| ["ssh_key", "key", "identity_file"].map(|name| holder.get(name)))
+        .flatten()
+        .next()?;
+    json_str(key, &["path", "private_key_path", "file"])
+        .and_then(key_path)
+        .or_else(|| keys.get(&json_id(key)?).cloned())
+        .or_else(|| key.as_str().and_then(key_path))
+}
+
+/// Jump hosts from `host_chain` (ids or inline hosts) or a plain string.
+fn termius_jump(host: &Value, by_id: &HashMap<String, &Value>) -> Option<String> {
+    if let Some(jump) = json_str(host, &["proxy_jump", "jump_host"]) {
+        return non_empty(jump).map(str::to_string);
+    }
+    let chain = host.get("host_chain")?;
+    let chain = chain
+        .get("hosts")
+        .or_else(|| chain.get("chain_hosts"))
+        .unwrap_or(chain)
+        .as_array()?;
+    let hops: Vec<String> = chain
+        .iter()
+        .filter_map(|hop| {
+            let hop = json_id(hop)
+                .and_then(|id| by_id.get(&id).copied())
+                .unwrap_or(hop);
+            let user = json_str(hop, &["username", "user"])
+                .or_else(|| {
+                    hop.pointer("/ssh_config/identity/username")
+                        .and_then(Value::as_str)
+                })
+                .unwrap_or("");
+            jump_spec(
+                json_str(hop, &["address", "hostname", "host"])?,
+                json_port(hop).or_else(|| json_port(hop.get("ssh_config")?)),
+                user,
+            )
+        })
+        .collect();
+    (!hops.is_empty()).then(|| hops.join(","))
+}
+
+fn json_str<'a>(value: &'a Value, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|name| value.get(*name)?.as_str())
+        .and_then(non_empty)
+}
+
+/// Ids are numbers in some exports and strings in others.
+fn json_id(value: &Value) -> Option<String> {
+    match value {
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn json_port(value: &Value) -> Option<u16> {
+    match value.get("port")? {
+        Value::Number(n) => n.as_u64().and_then(|p| u16::try_from(p).ok()),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// MobaXterm
+// ---------------------------------------------------------------------------
+
+/// SSH bookmarks of a MobaXterm `.mxtsessions` export. `SubRep` holds the
+/// folder, with `\` between levels.
+pub fn parse_mobaxterm(content: &str) -> Vec<Connection> {
+    let mut connections = Vec::new();
+    let mut in_bookmarks = false;
+    let mut group: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_bookmarks = section.starts_with("Bookmarks");
+            group = None;
+            continue;
+        }
+        if !in_bookmarks {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if name == "SubRep" {
+            group = non_empty(value).map(|v| v.replace('\\', "/"));
+        } else if name != "ImgNum" {
+            if let Some(mut connection) = mobaxterm_session(name, value) {
+                connection.group = group.clone();
+                connections.push(connection);
+            }
+        }
+    }
+    connections
+}
+
+fn mobaxterm_session(name: &str, value: &str) -> Option<Connection> {
+    let session = value.strip_prefix('#')?;
+    let (kind, rest) = session.split_once('#')?;
+    if kind != MOBAXTERM_SSH {
+        return None;
+    }
+    let settings = rest.split('#').next().unwrap_or("");
+    let fields: Vec<&str> = settings.split('%').collect();
+    let field = |i: usize| fields.get(i).copied().unwrap_or("");
+    let hostname = non_empty(field(1))?;
+    let mut connection = imported_connection(name, hostname, field(2).parse().ok(), field(3));
+    // Several gateways are joined with `__PIPE__`; keep them as a chain.
+    let gateways: Vec<String> = field(8)
+        .split("__PIPE__")
+        .zip(field(9).split("__PIPE__").chain(std::iter::repeat("")))
+        .zip(field(10).split("__PIPE__").chain(std::iter::repeat("")))
+        .filter_map(|((host, port), user)| jump_spec(host, port.parse().ok(), user))
+        .collect();
+    if !gateways.is_empty() {
+        connection.proxy_jump = Some(gateways.join(","));
+    }
+    connection.identity_file = non_empty(field(14)).and_then(|key| {
+        let key = match (key.strip_prefix("_ProfileDir_"), crate::util::home_dir()) {
+            (Some(rest), Some(home)) => {
+                let rest = rest.trim_start_matches(['\\', '/']).replace('\\', "/");
+                home.join(rest).to_string_lossy().into_owned()
+            }
+            _ => key.to_string(),
+        };
+        key_path(&key)
+    });
+    Some(connection)
+}
+
+// ---------------------------------------------------------------------------
+// Remmina
+// ---------------------------------------------------------------------------
+
+/// The SSH profile of a `.remmina` file, if it is one.
+pub fn parse_remmina(content: &str) -> Vec<Connection> {
+    let mut values: HashMap<&str, &str> = HashMap::new();
+    let mut in_profile = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_profile = section == "remmina";
+        } else if in_profile {
+            if let Some((name, value)) = line.split_once('=') {
+                values.insert(name.trim(), value.trim());
+            }
+        }
+    }
+    remmina_profile(&values).into_iter().collect()
+}
+
+fn remmina_profile(values: &HashMap<&str, &str>) -> Option<Connection> {
+    let get = |name: &str| values.get(name).copied().unwrap_or("");
+    if !get("protocol").eq_ignore_ascii_case("SSH") {
+        return None;
+    }
+    let (hostname, port) = split_host_port(non_empty(get("server"))?);
+    let user = non_empty(get("username")).unwrap_or(get("ssh_username"));
+    let mut connection = imported_connection(get("name"), &hostname, port, user);
+    connection.group = non_empty(get("group")).map(str::to_string);
+    connection.identity_file = key_path(get("ssh_privatekey"));
+    if get("ssh_tunnel_enabled") == "1" {
+        let (host, port) = split_host_port(get("ssh_tunnel_server"));
+        connection.proxy_jump = jump_spec(&host, port, get("ssh_tunnel_username"));
+    }
+    Some(connection)
+}
+
+// ---------------------------------------------------------------------------
+// CSV
+// ---------------------------------------------------------------------------
+
+/// Rows of a CSV file with a header naming its columns. `hostname` (or
+/// `host`) is required; `alias`, `port`, `user`, `group`, `proxy_jump`,
+/// `identity_file` and `tags` (separated by `;`) are optional.
+pub fn parse_csv(content: &str) -> Result<Vec<Connection>> {
+    let mut rows = csv_rows(content).into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|h| h.trim().to_ascii_lowercase().replace([' ', '-'], "_"))
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    let hostname_col = column(&["hostname", "host", "address"])
+        .ok_or_else(|| ShellDeckError::Config("CSV import needs a hostname column".to_string()))?;
+    let alias_col = column(&["alias", "name", "label"]);
+    let port_col = column(&["port"]);
+    let user_col = column(&["user", "username"]);
+    let group_col = column(&["group", "folder"]);
+    let jump_col = column(&["proxy_jump", "proxyjump", "jump", "jump_host"]);
+    let key_col = column(&["identity_file", "identityfile", "key", "private_key"]);
+    let tags_col = column(&["tags"]);
+
+    Ok(rows
+        .filter_map(|row| {
+            let cell = |col: Option<usize>| {
+                col.and_then(|c| row.get(c))
+                    .map(String::as_str)
+                    .unwrap_or("")
+            };
+            let hostname = non_empty(cell(Some(hostname_col)))?;
+            let mut connection = imported_connection(
+                cell(alias_col),
+                hostname,
+                cell(port_col).trim().parse().ok(),
+                cell(user_col),
+            );
+            connection.group = non_empty(cell(group_col)).map(str::to_string);
+            connection.proxy_jump = non_empty(cell(jump_col)).map(str::to_string);
+            connection.identity_file = key_path(cell(key_col));
+            connection.tags = cell(tags_col)
+                .split(';')
+                .filter_map(non_empty)
+                .map(str::to_string)
+                .collect();
+            Some(connection)
+        })
+        .collect())
+}
+
+/// Split CSV into rows of fields, honouring quotes, doubled quotes and
+/// line breaks inside quotes. Blank lines are skipped.
+fn csv_rows(content: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                } else {
+                    row.clear();
+                }
+            }
+            c => field.push(c),
+        }
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn putty_sessions_map_port_key_and_jump_host() {
+        let reg = r#"Windows Registry Editor Version 5.00
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Default%20Settings]
+"HostName"=""
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Prod%20DB]
+"HostName"="db.example.com"
+"PortNumber"=dword:00000874
+"UserName"="postgres"
+"Protocol"="ssh"
+"PublicKeyFile"="C:\\Users\\me\\db.ppk"
+"AgentFwd"=dword:00000001
+"ProxyMethod"=dword:00000006
+"ProxyHost"="bastion.example.com"
+"ProxyPort"=dword:00000016
+"ProxyUsername"="ops"
+
+[HKEY_CURRENT_USER\Software\SimonTatham\PuTTY\Sessions\Router]
+"HostName"="192.168.1.1"
+"Protocol"="telnet"
+"#;
+        let conns = parse_putty_reg(reg);
+        assert_eq!(conns.len(), 1);
+        let db = &conns[0];
+        assert_eq!(db.alias, "Prod DB");
+        assert_eq!(db.hostname, "db.example.com");
+        assert_eq!(db.port, 2164);
+        assert_eq!(db.user, "postgres");
+        assert_eq!(
+            db.identity_file,
+            Some(PathBuf::from("C:\\Users\\me\\db.ppk"))
+        );
+        assert!(db.forward_agent);
+        assert_eq!(db.proxy_jump.as_deref(), Some("ops@bastion.example.com"));
+    }
+
+    #[test]
+    fn putty_export_in_utf16_is_decoded() {
+        let text = "[HKEY_CURRENT_USER\\Software\\SimonTatham\\PuTTY\\Sessions\\web]\r\n\"HostName\"=\"web.example\"\r\n";
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in text.encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        let conns = parse_putty_reg(&decode_text(&bytes));
+        assert_eq!(conns.len(), 1);
+        assert_eq!(conns[0].hostname, "web.example");
+        assert_eq!(conns[0].port, 22);
+    }
+
+    #[test]
+    fn termius_groups_keys_and_host_chains_resolve() {
+        let json = r#"{
+            "groups": [
+                {"id": 1, "label": "Production"},
+                {"id": 2, "label": "EU", "parent_group": 1}
+            ],
+            "ssh_keys": [{"id": 7, "label": "deploy", "path": "/keys/deploy"}],
+            "hosts": [
+                {"id": 10, "label": "bastion", "address": "jump.example.com",
+                 "ssh_config": {"port": 2200, "identity": {"username": "ops"}}},
+                {"id": 11, "label": "api", "address": "10.0.0.5", "group": 2,
+                 "ssh_config": {"identity": {"username": "deploy", "ssh_key": 7}},
+                 "host_chain": {"hosts": [10]}, "tags": ["api", {"label": "eu"}]}
+            ]
+        }"#;
+        let conns = parse_termius(json).unwrap();
+        assert_eq!(conns.len(), 2);
+        assert_eq!(conns[0].port, 2200);
+        assert_eq!(conns[0].user, "ops");
+        let api = &conns[1];
+        assert_eq!(api.group.as_deref(), Some("Production/EU"));
+        assert_eq!(api.identity_file, Some(PathBuf::from("/keys/deploy")));
+        assert_eq!(api.proxy_jump.as_deref(), Some("ops@jump.example.com:2200"));
+        assert_eq!(api.tags, vec!["api".to_string(), "eu".to_string()]);
+    }
+
+    #[test]
+    fn mobaxterm_bookmarks_keep_folder_gateway_and_key() {
+        let mxt = "[Bookmarks]\r\nSubRep=\r\nImgNum=42\r\n\
+            [Bookmarks_1]\r\nSubRep=Clients\\Acme\r\nImgNum=41\r\n\
+            web=#109#0%web.acme.io%2222%deploy%%-1%-1%%gw.acme.io%22%jump%0%0%0%/keys/acme%%-1%0%0%0%%1080%%0%0%1#MobaFont%10#0# #-1\r\n\
+            desk=#91#4%desk.acme.io%3389%admin#MobaFont#0# #-1\r\n";
+        let conns = parse_mobaxterm(mxt);
+        assert_eq!(conns.len(), 1);
+        let web = &conns[0];
+        assert_eq!(web.alias, "web");
+        assert_eq!(web.hostname, "web.acme.io");
+        assert_eq!(web.port, 2222);
+        assert_eq!(web.user, "deploy");
+        assert_eq!(web.group.as_deref(), Some("Clients/Acme"));
+        assert_eq!(web.proxy_jump.as_deref(), Some("jump@gw.acme.io"));
+        assert_eq!(web.identity_file, Some(PathBuf::from("/keys/acme")));
+    }
+
+    #[test]
+    fn remmina_ssh_profile_with_tunnel() {
+        let profile = "[remmina]\nname=Build box\nprotocol=SSH\nserver=build.lan:2022\n\
+            username=ci\ngroup=Infra\nssh_privatekey=/keys/ci\nssh_tunnel_enabled=1\n\
+            ssh_tunnel_server=gate.lan\nssh_tunnel_username=me\n";
+        let conns = parse_remmina(profile);
+        assert_eq!(conns.len(), 1);
+        let c = &conns[0];
+        assert_eq!(c.alias, "Build box");
+        assert_eq!((c.hostname.as_str(), c.port), ("build.lan", 2022));
+        assert_eq!(c.group.as_deref(), Some("Infra"));
+        assert_eq!(c.proxy_jump.as_deref(), Some("me@gate.lan"));
+
+        assert!(parse_remmina("[remmina]\nprotocol=RDP\nserver=win.lan\n").is_empty());
+    }
+
+    #[test]
+    fn csv_columns_are_matched_by_header() {
+        let csv = "Name,Host,Port,User,Folder,Tags,Jump\n\
+            api,10.0.0.5,2222,deploy,Prod,\"web;eu\",bastion\n\
+            \n\
+            \"db, primary\",db.internal,,postgres,,,\n";
+        let conns = parse_csv(csv).unwrap();
+        assert_eq!(conns.len(), 2);
+        assert_eq!(conns[0].port, 2222);
+        assert_eq!(conns[0].group.as_deref(), Some("Prod"));
+        assert_eq!(conns[0].tags, vec!["web".to_string(), "eu".to_string()]);
+        assert_eq!(conns[0].proxy_jump.as_deref(), Some("bastion"));
+        assert_eq!(conns[1].alias, "db, primary");
+        assert_eq!(conns[1].port, 22);
+
+        assert!(parse_csv("name,port\nx,22\n").is_err());
+    }
+
+    #[test]
+    fn preview_flags_duplicates_and_renames_taken_aliases() {
+        let mut existing =
+            Connection::new_manual("api".into(), "API.example".into(), "deploy".into());
+        existing.port = 2222;
+        let mut same_host =
+            Connection::new_manual("api-copy".into(), "api.example".into(), "deploy".into());
+        same_host.port = 2222;
+        let other = Connection::new_manual("api".into(), "api2.example".into(), "deploy".into());
+        let repeat =
+            Connection::new_manual("api-again".into(), "api2.example".into(), "deploy".into());
+
+        let candidates = preview(vec![same_host, other, repeat], &[existing]);
+        assert_eq!(candidates[0].duplicate_of.as_deref(), Some("api"));
+        assert!(!candidates[0].selected_by_default());
+        assert!(candidates[1].duplicate_of.is_none());
+        assert_eq!(candidates[1].connection.alias, "api (2)");
+        assert_eq!(candidates[2].duplicate_of.as_deref(), Some("api (2)"));
+    }
+
+    #[test]
+    fn detect_uses_extension_then_content() {
+        assert_eq!(
+            ImportFormat::detect(Path::new("sessions.reg"), ""),
+            Some(ImportFormat::Putty)
+        );
+        assert_eq!(
+            ImportFormat::detect(Path::new("export"), "[Bookmarks]\nSubRep=\n"),
+            Some(ImportFormat::MobaXterm)
+        );
+        assert_eq!(
+            ImportFormat::detect(Path::new("export"), "{\"hosts\": []}"),
+            Some(ImportFormat::Termius)
+        );
+        assert_eq!(ImportFormat::detect(Path::new("notes.txt"), "hello"), None);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::config::app_config::AppConfig;
+use crate::error::{Result, ShellDeckError};
+use crate::models::execution::ExecutionRecord;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Runs kept per (script, connection) pair; older ones are pruned on save.
+const MAX_RUNS_PER_TARGET: usize = 50;
+/// Output kept per run. Longer logs keep their tail, which is where errors land.
+const MAX_OUTPUT_BYTES: usize = 512 * 1024;
+const TRUNCATION_MARKER: &str = "[… earlier output truncated …]\n";
+
+/// On-disk history of script runs.
+///
+/// Layout: `script-history/<script_id>/<record_id>.json` holds the record
+/// metadata (with an empty `output_log`) and `<record_id>.log` the output, so
+/// listing a script's runs never reads the logs. Output is stored with the
+/// run's secret values masked (see [`ExecutionRecord::redacted_output`]).
+pub struct ExecutionHistoryStore;
+
+impl ExecutionHistoryStore {
+    pub fn history_dir() -> PathBuf {
+        AppConfig::config_dir().join("script-history")
+    }
+
+    /// Persist a run (typically once it finished) and prune old runs of the
+    /// same script on the same connection.
+    pub fn save(record: &ExecutionRecord) -> Result<()> {
+        Self::save_in(&Self::history_dir(), record)
+    }
+
+    /// Runs of a script, newest first, without their output.
+    pub fn list(script_id: Uuid) -> Result<Vec<ExecutionRecord>> {
+        Self::list_in(&Self::history_dir(), script_id)
+    }
+
+    /// Full (redacted) output of one run.
+    pub fn load_output(script_id: Uuid, record_id: Uuid) -> Result<String> {
+        Self::load_output_in(&Self::history_dir(), script_id, record_id)
+    }
+
+    /// Runs of a script whose variables or output contain `query`
+    /// (case-insensitive), newest first. An empty query lists everything.
+    pub fn search(script_id: Uuid, query: &str) -> Result<Vec<ExecutionRecord>> {
+        Self::search_in(&Self::history_dir(), script_id, query)
+    }
+
+    /// Drop the whole history of a deleted script.
+    pub fn remove_script(script_id: Uuid) -> Result<()> {
+        Self::remove_script_in(&Self::history_dir(), script_id)
+    }
+
+    pub(crate) fn save_in(dir: &Path, record: &ExecutionRecord) -> Result<()> {
+        let script_dir = dir.join(record.script_id.to_string());
+        std::fs::create_dir_all(&script_dir)?;
+
+        let output = truncate_output(&record.redacted_output());
+        crate::util::atomic_write(
+            &script_dir.join(format!("{}.log", record.id)),
+            output.as_bytes(),
+        )?;
+
+        let mut meta = record.clone();
+        meta.output_log.clear();
+        let json = serde_json::to_vec_pretty(&meta)
+            .map_err(|e| ShellDeckError::Serialization(format!("history serialize: {}", e)))?;
+        crate::util::atomic_write(&script_dir.join(format!("{}.json", record.id)), &json)?;
+
+        Self::prune(&script_dir, record.connection_id)
+    }
+
+    pub(crate) fn list_in(dir: &Path, script_id: Uuid) -> Result<Vec<ExecutionRecord>> {
+        let script_dir = dir.join(script_id.to_string());
+        if !script_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut records = Vec::new();
+        for entry in std::fs::read_dir(&script_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path)?;
+            match serde_json::from_str::<ExecutionRecord>(&content) {
+                Ok(record) => records.push(record),
+                Err(e) => {
+                    tracing::warn!(
+                        "Skipping malformed execution record {}: {}",
+                        path.display(),
+                        e
+                    );
+                }
+            }
+        }
+        records.sort_by_key(|record| std::cmp::Reverse(record.started_at));
+        Ok(records)
+    }
+
+    pub(crate) fn load_output_in(dir: &Path, script_id: Uuid, record_id: Uuid) -> Result<String> {
+        let path = dir
+            .join(script_id.to_string())
+            .join(format!("{}.log", record_id));
+        if !path.exists() {
+            return Err(ShellDeckError::NotFound(format!(
+                "execution output {}",
+                record_id
+            )));
+        }
+        Ok(std::fs::read_to_string(path)?)
+    }
+
+    pub(crate) fn search_in(
+        dir: &Path,
+        script_id: Uuid,
+        query: &str,
+    ) -> Result<Vec<ExecutionRecord>> {
+        let records = Self::list_in(dir, script_id)?;
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Ok(records);
+        }
+
+        let mut matches = Vec::new();
+        for record in records {
+            let in_variables = record.variables.iter().any(|(name, value)| {
+                name.to_lowercase().contains(&query) || value.to_lowercase().contains(&query)
+            });
+            let in_exit_code = record
+                .exit_code
+                .is_some_and(|code| code.to_string() == query);
+            let hit = in_variables
+                || in_exit_code
+                || Self::load_output_in(dir, script_id, record.id)
+                    .map(|output| output.to_lowercase().contains(&query))
+                    .unwrap_or(false);
+            if hit {
+                matches.push(record);
+            }
+        }
+        Ok(matches)
+    }
+
+    pub(crate) fn remove_script_in(dir: &Path, script_id: Uuid) -> Result<()> {
+        let script_dir = dir.join(script_id.to_string());
+        if script_dir.exists() {
+            std::fs::remove_dir_all(script_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Keep only the newest [`MAX_RUNS_PER_TARGET`] runs for `connection_id`.
+    fn prune(script_dir: &Path, connection_id: Option<Uuid>) -> Result<()> {
+        let Some(dir) = script_dir.parent() else {
+            return Ok(());
+        };
+        let Some(script_id) = script_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| Uuid::parse_str(name).ok())
+        else {
+            return Ok(());
+        };
+
+        let stale = Self::list_in(dir, script_id)?
+            .into_iter()
+            .filter(|record| record.connection_id == connection_id)
+            .skip(MAX_RUNS_PER_TARGET);
+        for record in stale {
+            for ext in ["json", "log"] {
+                let path = script_dir.join(format!("{}.{}", record.id, ext));
+                if let Err(e) = std::fs::remove_file(&path) {
+                    tracing::warn!("Failed to prune {}: {}", path.display(), e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Keep the last [`MAX_OUTPUT_BYTES`] of a log, cut on a char boundary.
+fn truncate_output(output: &str) -> String {
+    if output.len() <= MAX_OUTPUT_BYTES {
+        return output.to_string();
+    }
+    let mut start = output.len() - MAX_OUTPUT_BYTES;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("{}{}", TRUNCATION_MARKER, &output[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use std::collections::HashMap;
+
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-history-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    fn run(
+        script_id: Uuid,
+        connection_id: Option<Uuid>,
+        age_secs: i64,
+        output: &str,
+    ) -> ExecutionRecord {
+        let mut r = ExecutionRecord::new(script_id, connection_id);
+        r.started_at = Utc::now() - Duration::seconds(age_secs);
+        r.append_output(output);
+        r.finish(0);
+        r
+    }
+
+    #[test]
+    fn save_then_list_newest_first_without_output() {
+        let dir = temp_dir();
+        let script = Uuid::new_v4();
+        let old = run(script, None, 60, "old\n");
+        let new = run(script, None, 1, "new\n");
+        ExecutionHistoryStore::save_in(&dir, &old).unwrap();
+        ExecutionHistoryStore::save_in(&dir, &new).unwrap();
+
+        let listed = ExecutionHistoryStore::list_in(&dir, script).unwrap();
+        assert_eq!(
+            listed.iter().map(|r| r.id).collect::<Vec<_>>(),
+            vec![new.id, old.id]
+        );
+        assert!(listed.iter().all(|r| r.output_log.is_empty()));
+        assert_eq!(
+            ExecutionHistoryStore::load_output_in(&dir, script, old.id).unwrap(),
+            "old\n"
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn prune_is_per_connection() {
+        let dir = temp_dir();
+        let script = Uuid::new_v4();
+        let conn = Uuid::new_v4();
+        let remote = run(script, Some(conn), 10_000, "remote\n");
+        ExecutionHistoryStore::save_in(&dir, &remote).unwrap();
+        for i in 0..(MAX_RUNS_PER_TARGET as i64 + 3) {
+            ExecutionHistoryStore::save_in(&dir, &run(script, None, i, "local\n")).unwrap();
+        }
+
+        let listed = ExecutionHistoryStore::list_in(&dir, script).unwrap();
+        let local = listed.iter().filter(|r| r.connection_id.is_none()).count();
+        assert_eq!(local, MAX_RUNS_PER_TARGET);
+        assert!(listed.iter().any(|r| r.id == remote.id));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn search_matches_output_variables_and_exit_code() {
+        let dir = temp_dir();
+        let script = Uuid::new_v4();
+        let mut failed = run(script, None, 5, "disk full on /var\n");
+        failed.finish(28);
+        let values = HashMap::from([("env".to_string(), "production".to_string())]);
+        let prod = run(script, None, 4, "ok\n").with_variables(&values);
+        ExecutionHistoryStore::save_in(&dir, &failed).unwrap();
+        ExecutionHistoryStore::save_in(&dir, &prod).unwrap();
+
+        let ids = |q: &str| {
+            ExecutionHistoryStore::search_in(&dir, script, q)
+                .unwrap()
+                .into_iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids("DISK FULL"), vec![failed.id]);
+        assert_eq!(ids("production"), vec![prod.id]);
+        assert_eq!(ids("28"), vec![failed.id]);
+        assert_eq!(ids(""), vec![prod.id, failed.id]);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn secrets_never_reach_disk() {
+        let dir = temp_dir();
+        let script = Uuid::new_v4();
+        let values = HashMap::from([("api_token".to_string(), "s3cr3t-value".to_string())]);
+        let mut r = ExecutionRecord::new(script, None).with_variables(&values);
+        r.append_output("curl -H 'Authorization: s3cr3t-value'\n");
+        r.finish(0);
+        ExecutionHistoryStore::save_in(&dir, &r).unwrap();
+
+        for entry in std::fs::read_dir(dir.join(script.to_string())).unwrap() {
+            let content = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            assert!(!content.contains("s3cr3t-value"));
+        }
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn long_output_keeps_its_tail() {
+        let long = format!("{}END", "é".repeat(MAX_OUTPUT_BYTES));
+        let truncated = truncate_output(&long);
+        assert!(truncated.starts_with(TRUNCATION_MARKER));
+        assert!(truncated.ends_with("END"));
+        assert!(truncated.len() <= MAX_OUTPUT_BYTES + TRUNCATION_MARKER.len());
+    }
+
+    #[test]
+    fn remove_script_drops_its_directory() {
+        let dir = temp_dir();
+        let script = Uuid::new_v4();
+        ExecutionHistoryStore::save_in(&dir, &run(script, None, 0, "x")).unwrap();
+        ExecutionHistoryStore::remove_script_in(&dir, script).unwrap();
+        assert!(ExecutionHistoryStore::list_in(&dir, script)
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::host_metrics::MetricsThresholds;
+
+/// `[host_metrics]` — which hosts are sampled over SSH, how often, and
+/// when a sample is worth an alert.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostMetricsConfig {
+    /// Seconds between two reads of the same host.
+    pub interval_secs: u64,
+    /// Samples kept in memory per host for the sparklines.
+    pub history_samples: usize,
+    /// Alert when a filesystem is at least this full, in percent.
+    pub disk_alert_percent: f32,
+    /// Alert when the 1-minute load exceeds this much per core.
+    pub load_alert_per_core: f32,
+    /// Connections sampled while the app runs.
+    pub connections: Vec<Uuid>,
+}
+
+impl Default for HostMetricsConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            history_samples: 90,
+            disk_alert_percent: 90.0,
+            load_alert_per_core: 1.0,
+            connections: Vec::new(),
+        }
+    }
+}
+
+impl HostMetricsConfig {
+    /// Intervals offered in the panel, in seconds.
+    pub const INTERVALS: [u64; 4] = [5, 10, 30, 60];
+
+    pub fn is_monitored(&self, connection_id: Uuid) -> bool {
+        self.connections.contains(&connection_id)
+    }
+
+    /// Add or remove a connection; returns whether anything changed.
+    pub fn set_monitored(&mut self, connection_id: Uuid, monitored: bool) -> bool {
+        if monitored == self.is_monitored(connection_id) {
+            return false;
+        }
+        if monitored {
+            self.connections.push(connection_id);
+        } else {
+            self.connections.retain(|id| *id != connection_id);
+        }
+        true
+    }
+
+    pub fn thresholds(&self) -> MetricsThresholds {
+        MetricsThresholds {
+            disk_percent: self.disk_alert_percent,
+            load_per_core: self.load_alert_per_core,
+        }
+    }
+
+    /// The interval, never below one second so a hand-edited `0` cannot
+    /// spin the collector.
+    pub fn interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_keys_take_defaults() {
+        let config: HostMetricsConfig = toml::from_str("interval_secs = 30").unwrap();
+        assert_eq!(config.interval_secs, 30);
+        assert_eq!(config.history_samples, 90);
+        assert_eq!(config.disk_alert_percent, 90.0);
+        assert!(config.connections.is_empty());
+    }
+
+    #[test]
+    fn monitored_set_toggles_once() {
+        let id = Uuid::new_v4();
+        let mut config = HostMetricsConfig::default();
+        assert!(config.set_monitored(id, true));
+        assert!(!config.set_monitored(id, true));
+        assert_eq!(config.connections, vec![id]);
+        assert!(config.set_monitored(id, false));
+        assert!(!config.is_monitored(id));
+        config.interval_secs = 0;
+        assert_eq!(config.interval(), std::time::Duration::from_secs(1));
+    }
+}
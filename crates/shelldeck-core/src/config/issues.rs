@@ -302,7 +302,36 @@ pub struct IssueAttachmentUpload {
 }
 
 impl IssueAttachmentUpload {
+    /// A plain-text attachment, e.g. an exported log window. Text past the
+    /// size limit is cut at a line boundary, keeping the newest lines.
+    pub fn log_text(filename: impl Into<String>, text: &str) -> Self {
+        let mut start = text.len().saturating_sub(ISSUE_ATTACHMENT_MAX_BYTES);
+        if start > 0 {
+            let bytes = &text.as_bytes()[start..];
+            start = bytes
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(text.len(), |newline| start + newline + 1);
+        }
+        Self {
+            filename: filename.into(),
+            content_type: "text/plain".to_string(),
+            bytes: text.as_bytes()[start..].to_vec(),
+        }
+    }
+
     pub fn validate(&self) -> Result<()> {
+        if self.content_type == "text/plain" {
+            if self.bytes.is_empty() || self.bytes.len() > ISSUE_ATTACHMENT_MAX_BYTES {
+                return Err(ShellDeckError::Connection(
+                    "taille texte invalide".to_string(),
+                ));
+            }
+            if std::str::from_utf8(&self.bytes).is_err() {
+                return Err(ShellDeckError::Connection("texte non UTF-8".to_string()));
+            }
+            return Ok(());
+        }
         if !matches!(
             self.content_type.as_str(),
             "image/png" | "image/jpeg" | "image/webp"
@@ -1090,6 +1119,35 @@ mod tests {
         assert!(upload.validate().is_err());
     }
 
+    #[test]
+    fn log_text_attachment_keeps_the_newest_lines_within_the_limit() {
+        let upload = IssueAttachmentUpload::log_text("logs.txt", "a\nb\n");
+        assert_eq!(upload.content_type, "text/plain");
+        assert!(upload.validate().is_ok());
+        assert!(IssueAttachmentUpload::log_text("logs.txt", "")
+            .validate()
+            .is_err());
+
+        let line = "x".repeat(1023) + "\n";
+        let text = line.repeat(ISSUE_ATTACHMENT_MAX_BYTES / 1024) + "last\n";
+        let upload = IssueAttachmentUpload::log_text("logs.txt", &text);
+        assert!(upload.bytes.len() <= ISSUE_ATTACHMENT_MAX_BYTES);
+        assert!(upload.bytes.starts_with(b"xxx"));
+        assert!(upload.bytes.ends_with(b"last\n"));
+        assert!(upload.validate().is_ok());
+
+        let wide = "é".repeat(ISSUE_ATTACHMENT_MAX_BYTES / 2) + "\nlast\n";
+        let upload = IssueAttachmentUpload::log_text("logs.txt", &wide);
+        assert_eq!(upload.bytes, b"last\n");
+
+        let invalid = IssueAttachmentUpload {
+            filename: "logs.txt".into(),
+            content_type: "text/plain".into(),
+            bytes: vec![0xff, 0xfe],
+        };
+        assert!(invalid.validate().is_err());
+    }
+
     #[test]
     fn attachment_limit_keeps_multipart_below_bext_request_cap() {
         const BEXT_REQUEST_CAP: usize = 10 * 1024 * 1024;
@@ -1,8 +1,12 @@
 use crate::error::{Result, ShellDeckError};
 use tracing::debug;
+use uuid::Uuid;
 
 const SERVICE_NAME: &str = "shelldeck-ssh";
 const AI_SERVICE_NAME: &str = "shelldeck-ai";
+const SCRIPT_SERVICE_NAME: &str = "shelldeck-scripts";
+const DB_SERVICE_NAME: &str = "shelldeck-databases";
+const PROXY_SERVICE_NAME: &str = "shelldeck-forwards";
 
 /// Build a keyring entry key from host and user.
 fn entry_key(host: &str, user: &str) -> String {
@@ -108,6 +112,138 @@ pub fn delete_ai_api_key(provider: &str) -> Result<()> {
     }
 }
 
+fn script_secret_key(script_id: Uuid, variable: &str) -> String {
+    format!("script:{}:{}", script_id, variable)
+}
+
+/// Store the last value of a secret script variable. Secret values stay out
+/// of the script body and the execution history.
+pub fn store_script_secret(script_id: Uuid, variable: &str, value: &str) -> Result<()> {
+    let key = script_secret_key(script_id, variable);
+    let entry = keyring::Entry::new(SCRIPT_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    entry
+        .set_password(value)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to store script secret: {}", e)))?;
+    debug!("Stored secret {} for script {}", variable, script_id);
+    Ok(())
+}
+
+pub fn get_script_secret(script_id: Uuid, variable: &str) -> Result<Option<String>> {
+    let key = script_secret_key(script_id, variable);
+    let entry = keyring::Entry::new(SCRIPT_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    match entry.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(ShellDeckError::Keychain(format!(
+            "Failed to retrieve script secret: {}",
+            e
+        ))),
+    }
+}
+
+pub fn delete_script_secret(script_id: Uuid, variable: &str) -> Result<()> {
+    let key = script_secret_key(script_id, variable);
+    let entry = keyring::Entry::new(SCRIPT_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(ShellDeckError::Keychain(format!(
+            "Failed to delete script secret: {}",
+            e
+        ))),
+    }
+}
+
+fn db_password_key(credentials_id: Uuid) -> String {
+    format!("db:{}", credentials_id)
+}
+
+/// Store the password of a set of database credentials. Sync profiles only
+/// keep the credentials' id, user and host.
+pub fn store_db_password(credentials_id: Uuid, password: &str) -> Result<()> {
+    let key = db_password_key(credentials_id);
+    let entry = keyring::Entry::new(DB_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    entry.set_password(password).map_err(|e| {
+        ShellDeckError::Keychain(format!("Failed to store database password: {}", e))
+    })?;
+    debug!("Stored database password {}", credentials_id);
+    Ok(())
+}
+
+pub fn get_db_password(credentials_id: Uuid) -> Result<Option<String>> {
+    let key = db_password_key(credentials_id);
+    let entry = keyring::Entry::new(DB_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    match entry.get_password() {
+        Ok(password) => Ok(Some(password)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(ShellDeckError::Keychain(format!(
+            "Failed to retrieve database password: {}",
+            e
+        ))),
+    }
+}
+
+pub fn delete_db_password(credentials_id: Uuid) -> Result<()> {
+    let key = db_password_key(credentials_id);
+    let entry = keyring::Entry::new(DB_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(ShellDeckError::Keychain(format!(
+            "Failed to delete database password: {}",
+            e
+        ))),
+    }
+}
+
+fn proxy_password_key(forward_id: Uuid) -> String {
+    format!("proxy:{}", forward_id)
+}
+
+/// Store the password clients give to the proxy of a dynamic forward. The
+/// forward itself only keeps the username.
+pub fn store_proxy_password(forward_id: Uuid, password: &str) -> Result<()> {
+    let key = proxy_password_key(forward_id);
+    let entry = keyring::Entry::new(PROXY_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    entry
+        .set_password(password)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to store proxy password: {}", e)))?;
+    debug!("Stored proxy password for forward {}", forward_id);
+    Ok(())
+}
+
+pub fn get_proxy_password(forward_id: Uuid) -> Result<Option<String>> {
+    let key = proxy_password_key(forward_id);
+    let entry = keyring::Entry::new(PROXY_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    match entry.get_password() {
+        Ok(password) => Ok(Some(password)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(ShellDeckError::Keychain(format!(
+            "Failed to retrieve proxy password: {}",
+            e
+        ))),
+    }
+}
+
+pub fn delete_proxy_password(forward_id: Uuid) -> Result<()> {
+    let key = proxy_password_key(forward_id);
+    let entry = keyring::Entry::new(PROXY_SERVICE_NAME, &key)
+        .map_err(|e| ShellDeckError::Keychain(format!("Failed to create keyring entry: {}", e)))?;
+    match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(ShellDeckError::Keychain(format!(
+            "Failed to delete proxy password: {}",
+            e
+        ))),
+    }
+}
+
 /// Store a private key passphrase in the OS keychain, keyed by the key file path.
 pub fn store_key_passphrase(key_path: &str, passphrase: &str) -> Result<()> {
     let key = passphrase_entry_key(key_path);
@@ -196,6 +332,30 @@ mod tests {
         assert_ne!(ai_provider_key("openai"), entry_key("openai", "provider"));
     }
 
+    #[test]
+    fn script_secret_keys_are_scoped_to_script_and_variable() {
+        let a = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        assert_eq!(script_secret_key(a, "token"), format!("script:{}:token", a));
+        assert_ne!(script_secret_key(a, "token"), script_secret_key(b, "token"));
+        assert_ne!(script_secret_key(a, "token"), script_secret_key(a, "pass"));
+    }
+
+    #[test]
+    fn db_password_keys_are_scoped_to_the_credentials() {
+        let a = Uuid::new_v4();
+        assert_eq!(db_password_key(a), format!("db:{}", a));
+        assert_ne!(db_password_key(a), db_password_key(Uuid::new_v4()));
+        assert_ne!(db_password_key(a), script_secret_key(a, "db"));
+    }
+
+    #[test]
+    fn proxy_password_keys_are_scoped_to_the_forward() {
+        let a = Uuid::new_v4();
+        assert_eq!(proxy_password_key(a), format!("proxy:{}", a));
+        assert_ne!(proxy_password_key(a), db_password_key(a));
+    }
+
     // ── SDTEST-120/123 — live keychain smoke (opt-in) ──────────────
     //
     // Gated by `SHELLDECK_LIVE_KEYCHAIN=1` so `cargo test` in CI (or
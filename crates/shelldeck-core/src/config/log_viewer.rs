@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::log_tail::LogSource;
+
+/// `[log_viewer]` — the sources last tailed together and how much of
+/// each is kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogViewerConfig {
+    /// Lines read back from each source when tailing starts.
+    pub backlog_lines: usize,
+    /// Lines kept in the merged view; the oldest go first.
+    pub max_lines: usize,
+    /// Sources reopened with the viewer.
+    pub sources: Vec<LogSource>,
+}
+
+impl Default for LogViewerConfig {
+    fn default() -> Self {
+        Self {
+            backlog_lines: 100,
+            max_lines: 20_000,
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl LogViewerConfig {
+    /// Drop the sources of a deleted connection; returns whether any were
+    /// dropped.
+    pub fn forget_connection(&mut self, connection_id: Uuid) -> bool {
+        let before = self.sources.len();
+        self.sources.retain(|s| s.connection_id != connection_id);
+        self.sources.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::log_tail::LogSourceKind;
+
+    #[test]
+    fn sources_round_trip_and_forget_their_connection() {
+        let kept = Uuid::new_v4();
+        let dropped = Uuid::new_v4();
+        let mut config = LogViewerConfig {
+            sources: vec![
+                LogSource::new(kept, LogSourceKind::Journald, "nginx"),
+                LogSource::new(dropped, LogSourceKind::File, "/var/log/app.log"),
+            ],
+            ..Default::default()
+        };
+        let text = toml::to_string(&config).unwrap();
+        assert!(text.contains("kind = \"journald\""));
+        let parsed: LogViewerConfig = toml::from_str(&text).unwrap();
+        assert_eq!(parsed, config);
+
+        assert!(config.forget_connection(dropped));
+        assert!(!config.forget_connection(dropped));
+        assert_eq!(config.sources.len(), 1);
+
+        let empty: LogViewerConfig = toml::from_str("").unwrap();
+        assert_eq!(empty.backlog_lines, 100);
+    }
+}
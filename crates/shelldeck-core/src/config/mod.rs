@@ -1,22 +1,32 @@
 pub mod activity;
 pub mod app_config;
 pub mod autostart;
+pub mod backup_catalog;
 pub mod bext_cloud;
 pub mod bext_instance;
 pub mod cloud_account;
 pub mod cloud_sync;
+pub mod connection_import;
 pub mod deep_link;
+pub mod execution_history;
+pub mod host_metrics;
 pub mod issues;
 pub mod keychain;
+pub mod log_viewer;
 pub mod manage_directory;
 pub mod manage_sites;
 pub mod manage_support;
 pub mod monique;
 pub mod monique_fleet;
+pub mod scheduler_state;
 pub mod single_instance;
+pub mod site_health;
 pub mod ssh_config;
+pub mod ssh_config_write;
+pub mod ssh_keys;
 pub mod store;
 pub mod themes;
+pub mod tunnel_history;
 pub mod watcher;
 pub mod workspace_state;
 
@@ -26,10 +36,12 @@ pub use bext_cloud::BextCloudConfig;
 pub use cloud_account::{AccountInfo, AppMode};
 pub use cloud_sync::CloudSyncConfig;
 pub use deep_link::DeepLink;
+pub use execution_history::ExecutionHistoryStore;
 pub use issues::{Issue, IssueComment, IssueList};
 pub use manage_sites::{ManageArea, ManagedSiteInfo, SitesPayload};
 pub use monique::MoniqueConfig;
 pub use monique_fleet::{MoniqueInstance, MoniqueJob, MoniqueRuntimeConfig};
+pub use scheduler_state::SchedulerState;
 pub use store::ConnectionStore;
 pub use themes::TerminalTheme;
 pub use watcher::ConfigWatcher;
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::error::{Result, ShellDeckError};
+
+/// When the scheduler last looked at each schedule trigger. Persisted so that
+/// slots missed while ShellDeck was closed or the machine slept can be caught
+/// up on the next start.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchedulerState {
+    #[serde(default)]
+    pub last_checked: HashMap<Uuid, DateTime<Utc>>,
+}
+
+impl SchedulerState {
+    fn state_path() -> PathBuf {
+        super::app_config::AppConfig::config_dir().join("scheduler.json")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::state_path())
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::state_path())
+    }
+
+    /// Forget triggers that no longer exist.
+    pub fn retain_triggers(&mut self, live: &[Uuid]) {
+        self.last_checked.retain(|id, _| live.contains(id));
+    }
+
+    pub(crate) fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            if !dir.as_os_str().is_empty() && !dir.exists() {
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            ShellDeckError::Serialization(format!("Failed to serialize scheduler state: {}", e))
+        })?;
+        crate::util::atomic_write(path, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Load from a specific path, returning an empty state if missing.
+    pub(crate) fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            ShellDeckError::Serialization(format!("Failed to parse scheduler state: {}", e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-scheduler-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir.join(name)
+    }
+
+    #[test]
+    fn round_trip_and_missing_file() {
+        let path = temp_path("scheduler.json");
+        assert_eq!(
+            SchedulerState::load_from(&path).unwrap(),
+            SchedulerState::default()
+        );
+
+        let kept = Uuid::new_v4();
+        let dropped = Uuid::new_v4();
+        let at = Utc.with_ymd_and_hms(2026, 3, 10, 9, 0, 0).unwrap();
+        let mut state = SchedulerState::default();
+        state.last_checked.insert(kept, at);
+        state.last_checked.insert(dropped, at);
+        state.retain_triggers(&[kept]);
+        state.save_to(&path).unwrap();
+
+        let loaded = SchedulerState::load_from(&path).unwrap();
+        assert_eq!(loaded.last_checked.len(), 1);
+        assert_eq!(loaded.last_checked[&kept], at);
+    }
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::error::{Result, ShellDeckError};
+use crate::models::site_health::HealthSample;
+
+/// Probes kept per site — a day of history at the default interval.
+const MAX_SAMPLES_PER_SITE: usize = 288;
+
+/// `[site_health]` — how often monitored sites are probed and when a
+/// certificate is close enough to expiry to warn about.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SiteHealthConfig {
+    /// Seconds between two probes of the same site.
+    pub interval_secs: u64,
+    /// Seconds before a probe gives up on a site.
+    pub timeout_secs: u64,
+    /// Warn when a certificate expires in fewer than this many days.
+    pub cert_warning_days: u32,
+}
+
+impl Default for SiteHealthConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,
+            timeout_secs: 15,
+            cert_warning_days: 14,
+        }
+    }
+}
+
+/// Probe history of monitored sites, oldest sample first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SiteHealthHistory {
+    #[serde(default)]
+    pub sites: HashMap<Uuid, Vec<HealthSample>>,
+}
+
+impl SiteHealthHistory {
+    fn history_path() -> PathBuf {
+        super::app_config::AppConfig::config_dir().join("site-health.json")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::history_path())
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::history_path())
+    }
+
+    /// Append a probe, dropping the oldest beyond [`MAX_SAMPLES_PER_SITE`].
+    pub fn record(&mut self, site_id: Uuid, sample: HealthSample) {
+        let samples = self.sites.entry(site_id).or_default();
+        samples.push(sample);
+        if samples.len() > MAX_SAMPLES_PER_SITE {
+            let excess = samples.len() - MAX_SAMPLES_PER_SITE;
+            samples.drain(..excess);
+        }
+    }
+
+    pub fn latest(&self, site_id: Uuid) -> Option<&HealthSample> {
+        self.sites.get(&site_id).and_then(|samples| samples.last())
+    }
+
+    /// Forget sites that are no longer monitored.
+    pub fn retain_sites(&mut self, live: &[Uuid]) {
+        self.sites.retain(|id, _| live.contains(id));
+    }
+
+    pub(crate) fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            if !dir.as_os_str().is_empty() && !dir.exists() {
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+        let content = serde_json::to_string(self).map_err(|e| {
+            ShellDeckError::Serialization(format!("Failed to serialize site health: {}", e))
+        })?;
+        crate::util::atomic_write(path, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Load from a specific path, returning an empty history if missing.
+    pub(crate) fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            ShellDeckError::Serialization(format!("Failed to parse site health: {}", e))
+        })
+    }
+}
+
+/// Share of `samples` that were up, as a percentage.
+pub fn uptime_percent(samples: &[HealthSample]) -> Option<f32> {
+    if samples.is_empty() {
+        return None;
+    }
+    let up = samples.iter().filter(|s| s.is_up()).count();
+    Some(up as f32 * 100.0 / samples.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::site_health::HealthFailure;
+    use chrono::{Duration, TimeZone, Utc};
+
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-site-health-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir.join(name)
+    }
+
+    fn sample(minutes: i64, failure: Option<HealthFailure>) -> HealthSample {
+        let at = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap() + Duration::minutes(minutes);
+        HealthSample {
+            checked_at: at,
+            addresses: vec!["127.0.0.1".parse().unwrap()],
+            http_status: Some(200),
+            latency_ms: Some(42),
+            keyword_found: None,
+            cert_expires_at: None,
+            chain_valid: None,
+            failure,
+        }
+    }
+
+    #[test]
+    fn history_is_capped_and_round_trips() {
+        let path = temp_path("site-health.json");
+        assert_eq!(
+            SiteHealthHistory::load_from(&path).unwrap(),
+            SiteHealthHistory::default()
+        );
+
+        let kept = Uuid::new_v4();
+        let dropped = Uuid::new_v4();
+        let mut history = SiteHealthHistory::default();
+        for minute in 0..(MAX_SAMPLES_PER_SITE as i64 + 10) {
+            history.record(kept, sample(minute, None));
+        }
+        history.record(dropped, sample(0, None));
+        history.retain_sites(&[kept]);
+        history.save_to(&path).unwrap();
+
+        let loaded = SiteHealthHistory::load_from(&path).unwrap();
+        assert_eq!(loaded.sites.len(), 1);
+        let samples = &loaded.sites[&kept];
+        assert_eq!(samples.len(), MAX_SAMPLES_PER_SITE);
+        assert_eq!(samples[0], sample(10, None), "oldest samples dropped");
+        assert_eq!(loaded.latest(kept), samples.last());
+    }
+
+    #[test]
+    fn uptime_counts_failed_probes() {
+        assert_eq!(uptime_percent(&[]), None);
+        let samples = [
+            sample(0, None),
+            sample(5, Some(HealthFailure::HttpStatus(502))),
+            sample(10, None),
+            sample(15, None),
+        ];
+        assert_eq!(uptime_percent(&samples), Some(75.0));
+    }
+}
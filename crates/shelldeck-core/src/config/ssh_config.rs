@@ -1,28 +1,76 @@
 use crate::error::{Result, ShellDeckError};
+use crate::models::port_forward::{ForwardDirection, PortForward};
 use crate::models::{Connection, ConnectionSource, ConnectionStatus};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
+/// How deep `Include` directives are followed, as in OpenSSH.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Hosts of an SSH config and the files it includes, with their
+/// `LocalForward` / `RemoteForward` lines as port forwards. Each connection
+/// lists its forwards in `auto_forwards`.
+#[derive(Debug, Default)]
+pub struct SshConfigImport {
+    pub connections: Vec<Connection>,
+    pub forwards: Vec<PortForward>,
+}
+
+/// Path of the user's SSH config, whether or not it exists.
+pub fn default_config_path() -> Option<PathBuf> {
+    crate::util::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
 /// Parse the default ~/.ssh/config file.
 pub fn parse_ssh_config() -> Result<Vec<Connection>> {
-    let Some(home) = crate::util::home_dir() else {
+    import_ssh_config().map(|import| import.connections)
+}
+
+/// Parse a specific SSH config file into Connection structs.
+pub fn parse_ssh_config_file(path: &Path) -> Result<Vec<Connection>> {
+    import_ssh_config_file(path).map(|import| import.connections)
+}
+
+/// Import hosts and forwards from the default ~/.ssh/config file.
+pub fn import_ssh_config() -> Result<SshConfigImport> {
+    let Some(config_path) = default_config_path() else {
         // No resolvable home means no ~/.ssh/config to parse.
-        return Ok(Vec::new());
+        return Ok(SshConfigImport::default());
     };
-    let config_path = home.join(".ssh").join("config");
     if !config_path.exists() {
-        return Ok(Vec::new());
+        return Ok(SshConfigImport::default());
     }
-    parse_ssh_config_file(&config_path)
+    import_ssh_config_file(&config_path)
 }
 
-/// Parse a specific SSH config file into Connection structs.
-pub fn parse_ssh_config_file(path: &Path) -> Result<Vec<Connection>> {
-    let content = std::fs::read_to_string(path).map_err(|e| {
-        ShellDeckError::SshConfigParse(format!("Failed to read {}: {}", path.display(), e))
-    })?;
+/// Import hosts and forwards from `path` and the files it includes. A host
+/// defined in several places keeps its first definition, as with ssh.
+pub fn import_ssh_config_file(path: &Path) -> Result<SshConfigImport> {
+    let files = config_files(path);
+    if files.is_empty() {
+        // Not a readable file; report why.
+        std::fs::read_to_string(path).map_err(|e| {
+            ShellDeckError::SshConfigParse(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+    }
+    let mut import = SshConfigImport::default();
+    let mut seen = HashSet::new();
+    for file in &files {
+        let content = std::fs::read_to_string(file).map_err(|e| {
+            ShellDeckError::SshConfigParse(format!("Failed to read {}: {}", file.display(), e))
+        })?;
+        import_hosts(&content, &mut seen, &mut import)?;
+    }
+    Ok(import)
+}
 
+fn import_hosts(
+    content: &str,
+    seen: &mut HashSet<String>,
+    import: &mut SshConfigImport,
+) -> Result<()> {
     // Use the ssh2_config crate for basic field extraction
     let mut reader = std::io::BufReader::new(content.as_bytes());
     let ssh_config = ssh2_config::SshConfig::default()
@@ -31,9 +79,7 @@ pub fn parse_ssh_config_file(path: &Path) -> Result<Vec<Connection>> {
 
     // Also do a manual parse for fields not exposed by ssh2_config
     // (ProxyJump, ForwardAgent, LocalForward, RemoteForward)
-    let extra_fields = parse_extra_fields(&content);
-
-    let mut connections = Vec::new();
+    let extra_fields = parse_extra_fields(content);
 
     for host in ssh_config.get_hosts() {
         // Skip hosts with wildcard-only patterns
@@ -50,12 +96,13 @@ pub fn parse_ssh_config_file(path: &Path) -> Result<Vec<Connection>> {
         }
 
         let alias = aliases[0].clone();
+        if !seen.insert(alias.clone()) {
+            continue;
+        }
         let params = &host.params;
 
         let hostname = params.host_name.clone().unwrap_or_else(|| alias.clone());
-        let user = params.user.clone().unwrap_or_else(|| {
-            crate::util::current_username().unwrap_or_else(|| "root".to_string())
-        });
+        let user = params.user.clone().unwrap_or_else(default_user);
         let port = params.port.unwrap_or(22);
         let identity_file = params
             .identity_file
@@ -68,8 +115,13 @@ pub fn parse_ssh_config_file(path: &Path) -> Result<Vec<Connection>> {
         let proxy_jump = extras.and_then(|e| e.proxy_jump.clone());
         let forward_agent = extras.map(|e| e.forward_agent).unwrap_or(false);
 
+        let id = connection_id(&alias);
+        let forwards = extras
+            .map(|e| host_forwards(id, &alias, e))
+            .unwrap_or_default();
+
         let conn = Connection {
-            id: Uuid::new_v4(),
+            id,
             alias,
             hostname,
             port,
@@ -78,7 +130,7 @@ pub fn parse_ssh_config_file(path: &Path) -> Result<Vec<Connection>> {
             proxy_jump,
             group: None,
             tags: Vec::new(),
-            auto_forwards: Vec::new(),
+            auto_forwards: forwards.iter().map(|f| f.id).collect(),
             auto_scripts: Vec::new(),
             source: ConnectionSource::SshConfig,
             forward_agent,
@@ -87,10 +139,155 @@ pub fn parse_ssh_config_file(path: &Path) -> Result<Vec<Connection>> {
             status: ConnectionStatus::Disconnected,
         };
 
-        connections.push(conn);
+        import.connections.push(conn);
+        import.forwards.extend(forwards);
     }
 
-    Ok(connections)
+    Ok(())
+}
+
+/// User ssh logs in as when a host sets none.
+pub(crate) fn default_user() -> String {
+    crate::util::current_username().unwrap_or_else(|| "root".to_string())
+}
+
+/// Port forwards for the `LocalForward` / `RemoteForward` lines of a host.
+fn host_forwards(connection_id: Uuid, alias: &str, extras: &ExtraHostFields) -> Vec<PortForward> {
+    let local = extras
+        .local_forwards
+        .iter()
+        .map(|(bind_host, bind_port, host, port)| {
+            let mut forward = PortForward::new_local(connection_id, *bind_port, host, *port);
+            forward.local_host = bind_host.clone();
+            forward
+        });
+    let remote = extras
+        .remote_forwards
+        .iter()
+        .map(|(bind_host, bind_port, host, port)| {
+            let mut forward = PortForward::new_remote(connection_id, *bind_port, host, *port);
+            forward.remote_host = bind_host.clone();
+            forward
+        });
+    local
+        .chain(remote)
+        .map(|mut forward| {
+            forward.id = forward_id(alias, &forward);
+            forward.from_ssh_config = true;
+            forward
+        })
+        .collect()
+}
+
+/// Id of the connection for a `Host` alias. It is derived from the alias
+/// so forwards, pins and history stay attached across restarts.
+pub fn connection_id(alias: &str) -> Uuid {
+    stable_id(&format!("ssh-config-host:{}", alias))
+}
+
+/// Id of a forward read from a host's config, derived from the host and
+/// the address it listens on, which ssh allows only once per host.
+fn forward_id(alias: &str, forward: &PortForward) -> Uuid {
+    let (kind, host, port) = match forward.direction {
+        ForwardDirection::RemoteToLocal => ("R", &forward.remote_host, forward.remote_port),
+        _ => ("L", &forward.local_host, forward.local_port),
+    };
+    stable_id(&format!(
+        "ssh-config-forward:{}:{}:{}:{}",
+        alias, kind, host, port
+    ))
+}
+
+fn stable_id(key: &str) -> Uuid {
+    let digest = Sha256::digest(key.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes).into_uuid()
+}
+
+/// The config file at `path` followed by the files its `Include`
+/// directives name, in the order ssh reads them. Relative includes are
+/// looked up next to `path`, and wildcards are expanded in the file name.
+/// Missing files are skipped, as ssh does.
+pub fn config_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_config_files(path, 0, &mut files);
+    files
+}
+
+fn collect_config_files(path: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    if depth > MAX_INCLUDE_DEPTH || files.iter().any(|f| f == path) || !path.is_file() {
+        return;
+    }
+    files.push(path.to_path_buf());
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return;
+    };
+    let base = path.parent().unwrap_or(Path::new(""));
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        let Some(value) = strip_keyword(trimmed, "Include") else {
+            continue;
+        };
+        for pattern in value.split_whitespace() {
+            for include in expand_include(base, pattern.trim_matches('"')) {
+                collect_config_files(&include, depth + 1, files);
+            }
+        }
+    }
+}
+
+/// Files matching one `Include` argument, sorted like a shell glob.
+fn expand_include(base: &Path, pattern: &str) -> Vec<PathBuf> {
+    let pattern = expand_tilde(Path::new(pattern));
+    let pattern = if pattern.is_relative() {
+        base.join(pattern)
+    } else {
+        pattern
+    };
+    let name = pattern
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !name.contains(['*', '?']) {
+        return vec![pattern];
+    }
+    let dir = pattern.parent().unwrap_or(Path::new(""));
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut matches: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|p| {
+            p.file_name()
+                .is_some_and(|n| wildcard_match(&name, &n.to_string_lossy()))
+        })
+        .collect();
+    matches.sort();
+    matches
+}
+
+/// Match `name` against a pattern of `*` and `?` wildcards. Like a shell
+/// glob, wildcards do not match a leading dot.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some(('*', rest)) => (0..=name.len()).any(|skip| matches(rest, &name[skip..])),
+            Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches(&pattern, &name)
 }
 
 /// Returns true if the pattern is wildcard-only (e.g., "*", "?", "*.*")
@@ -99,7 +296,7 @@ fn is_wildcard_only(pattern: &str) -> bool {
 }
 
 /// Expand ~ in paths to the user's home directory.
-fn expand_tilde(path: &Path) -> PathBuf {
+pub(super) fn expand_tilde(path: &Path) -> PathBuf {
     let s = path.to_string_lossy();
     if let Some(stripped) = s.strip_prefix("~/") {
         match crate::util::home_dir() {
@@ -208,7 +405,7 @@ fn parse_extra_fields(content: &str) -> HashMap<String, ExtraHostFields> {
 }
 
 /// Strip a keyword (case-insensitive) from a line and return the value part.
-fn strip_keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
+pub(super) fn strip_keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
     // Handle both "Keyword value" and "Keyword=value"
     let lower = line.to_lowercase();
     let kw_lower = keyword.to_lowercase();
@@ -228,7 +425,7 @@ fn strip_keyword<'a>(line: &'a str, keyword: &str) -> Option<&'a str> {
 }
 
 /// Parse a forward directive value like "8080 localhost:80" or "127.0.0.1:8080 localhost:80".
-fn parse_forward_directive(value: &str) -> Option<(String, u16, String, u16)> {
+pub(super) fn parse_forward_directive(value: &str) -> Option<(String, u16, String, u16)> {
     let parts: Vec<&str> = value.split_whitespace().collect();
     if parts.len() != 2 {
         return None;
@@ -396,10 +593,11 @@ Host bastion
         std::fs::remove_dir_all(&dir).ok();
     }
 
-    // SDTEST-108 — the parser MUST NOT modify the file it reads. This
-    // is the AGENTS.md § "Critical Rules" guarantee: ShellDeck never
-    // writes to ~/.ssh/config. Sensor: capture the file's mtime + size
-    // before parse, and assert both are unchanged after.
+    // SDTEST-108 — the parser MUST NOT modify the file it reads. The
+    // only writes to ~/.ssh/config are the explicit edits of
+    // `ssh_config_write`; loading and live reloads stay read-only.
+    // Sensor: capture the file's mtime + size before parse, and assert
+    // both are unchanged after.
     #[test]
     fn parse_never_mutates_the_input_file() {
         let dir = std::env::temp_dir().join(format!(
@@ -434,4 +632,93 @@ Host bastion
 
         std::fs::remove_dir_all(&dir).ok();
     }
+
+    #[test]
+    fn forwards_are_imported_and_linked_with_stable_ids() {
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-ssh-forwards-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos(),
+        ));
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        let cfg = dir.join("config");
+        std::fs::write(
+            &cfg,
+            "\
+Host web
+    HostName 10.0.0.5
+    LocalForward 8080 localhost:80
+    RemoteForward 0.0.0.0:9222 127.0.0.1:9223
+Include conf.d/*
+",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("conf.d").join("lab"),
+            "Host lab\n    HostName lab.example\nHost web\n    HostName ignored\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("conf.d").join(".hidden"), "Host hidden\n").unwrap();
+
+        let import = import_ssh_config_file(&cfg).unwrap();
+        let aliases: Vec<&str> = import
+            .connections
+            .iter()
+            .map(|c| c.alias.as_str())
+            .collect();
+        assert_eq!(aliases, ["web", "lab"]);
+        let web = &import.connections[0];
+        assert_eq!(web.hostname, "10.0.0.5", "first definition wins");
+        assert_eq!(web.id, connection_id("web"));
+
+        assert_eq!(import.forwards.len(), 2);
+        assert_eq!(
+            web.auto_forwards,
+            import.forwards.iter().map(|f| f.id).collect::<Vec<_>>()
+        );
+        let local = &import.forwards[0];
+        assert_eq!(local.direction, ForwardDirection::LocalToRemote);
+        assert_eq!(
+            (local.local_host.as_str(), local.local_port),
+            ("127.0.0.1", 8080)
+        );
+        assert_eq!(
+            (local.remote_host.as_str(), local.remote_port),
+            ("localhost", 80)
+        );
+        let remote = &import.forwards[1];
+        assert_eq!(remote.direction, ForwardDirection::RemoteToLocal);
+        assert_eq!(
+            (remote.remote_host.as_str(), remote.remote_port),
+            ("0.0.0.0", 9222)
+        );
+        assert_eq!(
+            (remote.local_host.as_str(), remote.local_port),
+            ("127.0.0.1", 9223)
+        );
+        assert!(import
+            .forwards
+            .iter()
+            .all(|f| f.from_ssh_config && f.connection_id == web.id));
+
+        let again = import_ssh_config_file(&cfg).unwrap();
+        assert_eq!(
+            again.forwards.iter().map(|f| f.id).collect::<Vec<_>>(),
+            web.auto_forwards,
+            "ids must survive a reload"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.conf", "lab.conf"));
+        assert!(wildcard_match("host?", "host1"));
+        assert!(!wildcard_match("*.conf", "lab.conf.bak"));
+        assert!(!wildcard_match("*", ".hidden"));
+        assert!(wildcard_match(".*", ".hidden"));
+    }
 }
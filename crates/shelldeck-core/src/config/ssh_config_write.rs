@@ -0,0 +1,597 @@
+//! Writes edits of SSH-config connections back to the `Host` block they
+//! were read from. Only the lines of settings that changed are touched;
+//! comments, ordering and every other directive stay as they are.
+
+use super::ssh_config::{
+    config_files, default_user, expand_tilde, parse_forward_directive, strip_keyword,
+};
+use crate::error::{Result, ShellDeckError};
+use crate::models::port_forward::{ForwardDirection, PortForward};
+use crate::models::Connection;
+use std::path::{Path, PathBuf};
+
+/// Update the `Host` block of `previous_alias` in the config at `root` or
+/// the first of its included files that has one, and return the file
+/// written. A host without a block gets one appended to `root`.
+///
+/// `forwards` are the connection's linked forwards (its `auto_forwards`);
+/// they become the block's `LocalForward` / `RemoteForward` lines.
+pub fn write_connection(
+    root: &Path,
+    previous_alias: &str,
+    connection: &Connection,
+    forwards: &[PortForward],
+) -> Result<PathBuf> {
+    for file in config_files(root) {
+        let content = read_config(&file)?;
+        if let Some(updated) = update_host_block(&content, previous_alias, connection, forwards) {
+            if updated != content {
+                write_atomically(&file, &updated)?;
+            }
+            return Ok(file);
+        }
+    }
+    let content = if root.exists() {
+        read_config(root)?
+    } else {
+        String::new()
+    };
+    write_atomically(root, &append_host_block(&content, connection, forwards))?;
+    Ok(root.to_path_buf())
+}
+
+fn read_config(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|e| {
+        ShellDeckError::SshConfigParse(format!("Failed to read {}: {}", path.display(), e))
+    })
+}
+
+/// `content` with the `Host` block naming `alias` brought in line with
+/// `connection`, or `None` when no block names it. A block shared with
+/// other patterns is left to them: `alias` moves to a copy of its own.
+pub fn update_host_block(
+    content: &str,
+    alias: &str,
+    connection: &Connection,
+    forwards: &[PortForward],
+) -> Option<String> {
+    let newline = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let (start, end) = find_host_block(&lines, alias)?;
+    let (head, others) = {
+        let line = split_directive(&lines[start])?;
+        let others: Vec<&str> = line
+            .value
+            .split_whitespace()
+            .filter(|p| *p != alias)
+            .collect();
+        (line.head.to_string(), others.join(" "))
+    };
+
+    if others.is_empty() {
+        lines[start] = format!("{}{}", head, connection.alias);
+        let mut block = lines[start + 1..end].to_vec();
+        edit_block(&mut block, connection, forwards);
+        lines.splice(start + 1..end, block);
+    } else {
+        // The copy goes right before the shared block so it keeps the
+        // precedence the shared settings had for this host.
+        let last_directive = lines[start + 1..end]
+            .iter()
+            .rposition(|line| split_directive(line).is_some())
+            .map_or(start + 1, |index| start + 2 + index);
+        let mut block = lines[start + 1..last_directive].to_vec();
+        edit_block(&mut block, connection, forwards);
+        lines[start] = format!("{}{}", head, others);
+        let own = std::iter::once(format!("{}{}", head, connection.alias))
+            .chain(block)
+            .chain(std::iter::once(String::new()));
+        lines.splice(start..start, own);
+    }
+
+    let mut updated = lines.join(newline);
+    if content.ends_with('\n') {
+        updated.push_str(newline);
+    }
+    Some(updated)
+}
+
+/// `content` with a new `Host` block for `connection` at the end.
+fn append_host_block(content: &str, connection: &Connection, forwards: &[PortForward]) -> String {
+    let mut block = Vec::new();
+    edit_block(&mut block, connection, forwards);
+
+    let mut updated = content.to_string();
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    if !updated.trim().is_empty() && !updated.ends_with("\n\n") {
+        updated.push('\n');
+    }
+    updated.push_str(&format!("Host {}\n", connection.alias));
+    for line in block {
+        updated.push_str(&line);
+        updated.push('\n');
+    }
+    updated
+}
+
+/// Lines `[start, end)` of the first `Host` block listing `alias`, from
+/// its `Host` line up to the next `Host` or `Match`.
+fn find_host_block(lines: &[String], alias: &str) -> Option<(usize, usize)> {
+    let mut start = None;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(patterns) = strip_keyword(trimmed, "Host") {
+            if start.is_some() {
+                return start.map(|start| (start, index));
+            }
+            if patterns.split_whitespace().any(|p| p == alias) {
+                start = Some(index);
+            }
+        } else if start.is_some() && strip_keyword(trimmed, "Match").is_some() {
+            return start.map(|start| (start, index));
+        }
+    }
+    start.map(|start| (start, lines.len()))
+}
+
+/// A directive line cut into the text before its value, its keyword and
+/// its value.
+struct DirectiveLine<'a> {
+    head: &'a str,
+    keyword: &'a str,
+    value: &'a str,
+}
+
+fn split_directive(line: &str) -> Option<DirectiveLine<'_>> {
+    let keyword_start = line.len() - line.trim_start().len();
+    let rest = &line[keyword_start..];
+    let keyword_len = rest
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(rest.len());
+    let keyword = &rest[..keyword_len];
+    if keyword.is_empty() || keyword.starts_with('#') {
+        return None;
+    }
+    let after = &rest[keyword_len..];
+    let separator_len = after.len()
+        - after
+            .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+            .len();
+    let value_start = keyword_start + keyword_len + separator_len;
+    Some(DirectiveLine {
+        head: &line[..value_start],
+        keyword,
+        value: line[value_start..].trim_end(),
+    })
+}
+
+/// One single-valued setting of a `Host` block.
+struct Setting {
+    keyword: &'static str,
+    /// Value to write; `None` drops the line.
+    value: Option<String>,
+    /// Whether a missing line must be added. False when ssh would use the
+    /// value anyway, so untouched defaults do not grow the block.
+    required: bool,
+    /// Whether a value already in the file says the same.
+    same: fn(&str, &str) -> bool,
+}
+
+fn settings(connection: &Connection) -> Vec<Setting> {
+    vec![
+        Setting {
+            keyword: "HostName",
+            value: Some(connection.hostname.clone()),
+            required: connection.hostname != connection.alias,
+            same: |a, b| a == b,
+        },
+        Setting {
+            keyword: "User",
+            value: Some(connection.user.clone()),
+            required: connection.user != default_user(),
+            same: |a, b| a == b,
+        },
+        Setting {
+            keyword: "Port",
+            value: Some(connection.port.to_string()),
+            required: connection.port != 22,
+            same: |a, b| a.parse::<u16>().ok() == b.parse::<u16>().ok(),
+        },
+        Setting {
+            keyword: "IdentityFile",
+            value: connection.identity_file.as_deref().map(collapse_home),
+            required: true,
+            same: |a, b| expand_tilde(Path::new(a)) == expand_tilde(Path::new(b)),
+        },
+        Setting {
+            keyword: "ProxyJump",
+            value: connection.proxy_jump.clone(),
+            required: true,
+            same: |a, b| a == b,
+        },
+        Setting {
+            keyword: "ForwardAgent",
+            value: Some(
+                if connection.forward_agent {
+                    "yes"
+                } else {
+                    "no"
+                }
+                .to_string(),
+            ),
+            required: connection.forward_agent,
+            same: |a, b| a.eq_ignore_ascii_case(b),
+        },
+    ]
+}
+
+/// Bring the directive lines of a block (without its `Host` line) in line
+/// with `connection` and `forwards`.
+fn edit_block(block: &mut Vec<String>, connection: &Connection, forwards: &[PortForward]) {
+    let indent = block
+        .iter()
+        .find(|line| split_directive(line).is_some())
+        .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+        .unwrap_or_else(|| "    ".to_string());
+    let mut added = Vec::new();
+
+    for setting in settings(connection) {
+        // ssh uses the first occurrence of a setting, so only that one is
+        // edited.
+        let position = block.iter().position(|line| {
+            split_directive(line).is_some_and(|d| d.keyword.eq_ignore_ascii_case(setting.keyword))
+        });
+        match (position, setting.value) {
+            (Some(index), None) => {
+                block.remove(index);
+            }
+            (Some(index), Some(value)) => {
+                let replacement = split_directive(&block[index]).and_then(|line| {
+                    let current = line.value.trim_matches('"');
+                    if (setting.same)(current, &value) {
+                        None
+                    } else {
+                        Some(format!("{}{}", line.head, quote(&value)))
+                    }
+                });
+                if let Some(replacement) = replacement {
+                    block[index] = replacement;
+                }
+            }
+            (None, Some(value)) if setting.required => {
+                added.push(format!("{}{} {}", indent, setting.keyword, quote(&value)));
+            }
+            _ => {}
+        }
+    }
+
+    let mut wanted: Vec<(&str, ForwardSpec)> =
+        forwards.iter().filter_map(forward_directive).collect();
+    block.retain(|line| {
+        let Some(directive) = split_directive(line) else {
+            return true;
+        };
+        let Some(keyword) = ["LocalForward", "RemoteForward"]
+            .into_iter()
+            .find(|k| directive.keyword.eq_ignore_ascii_case(k))
+        else {
+            return true;
+        };
+        // Lines ShellDeck cannot express (Unix sockets, say) were never
+        // imported and are left alone.
+        let Some(spec) = parse_forward_directive(directive.value) else {
+            return true;
+        };
+        match wanted.iter().position(|w| w.0 == keyword && w.1 == spec) {
+            Some(index) => {
+                wanted.remove(index);
+                true
+            }
+            None => false,
+        }
+    });
+    for (keyword, (bind_host, bind_port, host, port)) in wanted {
+        let listen = if bind_host == "127.0.0.1" {
+            bind_port.to_string()
+        } else {
+            host_port(&bind_host, bind_port)
+        };
+        added.push(format!(
+            "{}{} {} {}",
+            indent,
+            keyword,
+            listen,
+            host_port(&host, port)
+        ));
+    }
+
+    // New lines go after the last directive, ahead of any blank lines or
+    // comments that lead into the next block.
+    let insert_at = block
+        .iter()
+        .rposition(|line| split_directive(line).is_some())
+        .map_or(0, |index| index + 1);
+    block.splice(insert_at..insert_at, added);
+}
+
+/// `(bind host, bind port, host, port)` of a forward line.
+type ForwardSpec = (String, u16, String, u16);
+
+/// The keyword and spec of the line for a forward, as
+/// `parse_forward_directive` reads it back. Dynamic forwards are
+/// ShellDeck's own and have no line.
+fn forward_directive(forward: &PortForward) -> Option<(&'static str, ForwardSpec)> {
+    match forward.direction {
+        ForwardDirection::LocalToRemote => Some((
+            "LocalForward",
+            (
+                forward.local_host.clone(),
+                forward.local_port,
+                forward.remote_host.clone(),
+                forward.remote_port,
+            ),
+        )),
+        ForwardDirection::RemoteToLocal => Some((
+            "RemoteForward",
+            (
+                forward.remote_host.clone(),
+                forward.remote_port,
+                forward.local_host.clone(),
+                forward.local_port,
+            ),
+        )),
+        ForwardDirection::Dynamic => None,
+    }
+}
+
+fn host_port(host: &str, port: u16) -> String {
+    if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+fn quote(value: &str) -> String {
+    if value.contains(char::is_whitespace) {
+        format!("\"{}\"", value)
+    } else {
+        value.to_string()
+    }
+}
+
+/// A path under the home directory written as `~/...`, as people write
+/// them in their config.
+fn collapse_home(path: &Path) -> String {
+    crate::util::home_dir()
+        .and_then(|home| path.strip_prefix(home).ok().map(Path::to_path_buf))
+        .map(|rest| format!("~/{}", rest.display()))
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Replace `path` in one step, so ssh never reads a half-written config.
+/// A symlinked config is written through to its target, and the file
+/// keeps its permissions; a new one is private to the user.
+fn write_atomically(path: &Path, content: &str) -> Result<()> {
+    let target = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if let Some(dir) = target.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mode = match std::fs::metadata(&target) {
+        #[cfg(unix)]
+        Ok(metadata) => {
+            use std::os::unix::fs::PermissionsExt;
+            metadata.permissions().mode() & 0o7777
+        }
+        _ => 0o600,
+    };
+    crate::util::atomic_write_with_mode(&target, content.as_bytes(), mode)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ssh_config::import_ssh_config_file;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-ssh-write-{}-{}-{}",
+            name,
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos(),
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    const CONFIG: &str = "\
+# work hosts
+Host web web-alias
+    HostName 10.0.0.5
+    # deploy user, see wiki
+    User deploy
+    Port 2222
+    LocalForward 8080 localhost:80
+    LocalForward 5432 db.internal:5432
+    ServerAliveInterval 30
+
+Host *
+    User fallback
+";
+
+    #[test]
+    fn edits_only_the_changed_lines_of_the_block() {
+        let dir = temp_dir("edit");
+        let path = dir.join("config");
+        std::fs::write(&path, CONFIG).unwrap();
+        let import = import_ssh_config_file(&path).unwrap();
+        let mut web = import.connections[0].clone();
+        assert_eq!(web.alias, "web");
+
+        web.hostname = "10.0.0.6".into();
+        web.port = 22;
+        web.proxy_jump = Some("bastion".into());
+        let forwards: Vec<PortForward> = import
+            .forwards
+            .iter()
+            .filter(|f| f.local_port == 8080)
+            .cloned()
+            .collect();
+
+        let written = write_connection(&path, "web", &web, &forwards).unwrap();
+        assert_eq!(written, path);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "\
+# work hosts
+Host web
+    HostName 10.0.0.6
+    # deploy user, see wiki
+    User deploy
+    Port 22
+    LocalForward 8080 localhost:80
+    ServerAliveInterval 30
+    ProxyJump bastion
+
+Host web-alias
+    HostName 10.0.0.5
+    # deploy user, see wiki
+    User deploy
+    Port 2222
+    LocalForward 8080 localhost:80
+    LocalForward 5432 db.internal:5432
+    ServerAliveInterval 30
+
+Host *
+    User fallback
+"
+        );
+
+        // Writing the same state again leaves the file alone.
+        let before = std::fs::read_to_string(&path).unwrap();
+        write_connection(&path, "web", &web, &forwards).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    // `web1` was imported from a block it shares with `web2`; editing and
+    // renaming it must leave `web2` with the settings it had.
+    #[test]
+    fn editing_one_pattern_of_a_shared_block_splits_it() {
+        let dir = temp_dir("shared");
+        let path = dir.join("config");
+        std::fs::write(
+            &path,
+            "Host web1 web2 web3\n    HostName 10.0.0.5\n    User deploy\n# cache hosts\nHost cache\n    HostName 10.0.0.9\n",
+        )
+        .unwrap();
+        let import = import_ssh_config_file(&path).unwrap();
+        let mut web = import.connections[0].clone();
+        assert_eq!(web.alias, "web1");
+
+        web.alias = "web-primary".into();
+        web.hostname = "10.0.0.6".into();
+        write_connection(&path, "web1", &web, &[]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "Host web-primary\n    HostName 10.0.0.6\n    User deploy\n\nHost web2 web3\n    HostName 10.0.0.5\n    User deploy\n# cache hosts\nHost cache\n    HostName 10.0.0.9\n"
+        );
+
+        // Its own block now, so a second edit stays inside it.
+        let before = std::fs::read_to_string(&path).unwrap();
+        write_connection(&path, "web-primary", &web, &[]).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), before);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rewrites_keep_the_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("mode");
+        let path = dir.join("config");
+        std::fs::write(&path, "Host a\n    HostName a.example\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+        let conn = Connection::new_manual("a".into(), "a2.example".into(), default_user());
+
+        write_connection(&path, "a", &conn, &[]).unwrap();
+        let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&path), 0o640);
+        let fresh = dir.join("fresh");
+        write_connection(&fresh, "a", &conn, &[]).unwrap();
+        assert_eq!(mode(&fresh), 0o600);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn renames_and_adds_forwards_in_the_included_file() {
+        let dir = temp_dir("include");
+        let root = dir.join("config");
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        std::fs::write(&root, "Include conf.d/*.conf\n\nHost top\n    User me\n").unwrap();
+        let included = dir.join("conf.d").join("lab.conf");
+        std::fs::write(&included, "Host lab\r\n  HostName lab.example\r\n").unwrap();
+
+        let import = import_ssh_config_file(&root).unwrap();
+        let mut lab = import
+            .connections
+            .iter()
+            .find(|c| c.alias == "lab")
+            .unwrap()
+            .clone();
+        lab.alias = "lab2".into();
+        let mut remote = PortForward::new_remote(lab.id, 9222, "127.0.0.1", 9222);
+        remote.remote_host = "0.0.0.0".into();
+
+        let written = write_connection(&root, "lab", &lab, &[remote]).unwrap();
+        assert_eq!(written, included);
+        let content = std::fs::read_to_string(&included).unwrap();
+        assert!(content.starts_with("Host lab2\r\n  HostName lab.example\r\n"));
+        assert!(content.contains("  RemoteForward 0.0.0.0:9222 127.0.0.1:9222\r\n"));
+        assert_eq!(
+            std::fs::read_to_string(&root).unwrap(),
+            "Include conf.d/*.conf\n\nHost top\n    User me\n"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn appends_a_block_for_a_host_the_file_lacks() {
+        let dir = temp_dir("append");
+        let path = dir.join("config");
+        std::fs::write(&path, "Host a\n    HostName a.example").unwrap();
+        let mut conn =
+            Connection::new_manual("b".into(), "b.example".into(), "user with space".into());
+        conn.forward_agent = true;
+
+        write_connection(&path, "b", &conn, &[]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "Host a\n    HostName a.example\n\nHost b\n    HostName b.example\n    User \"user with space\"\n    ForwardAgent yes\n"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn split_directive_keeps_the_separator() {
+        let line = split_directive("  Port = 22 ").unwrap();
+        assert_eq!(line.head, "  Port = ");
+        assert_eq!(line.keyword, "Port");
+        assert_eq!(line.value, "22");
+        assert!(split_directive("   # comment").is_none());
+        assert!(split_directive("").is_none());
+    }
+}
@@ -0,0 +1,578 @@
+//! SSH key pairs under ~/.ssh: inventory with fingerprints, generation via
+//! `ssh-keygen`, and the remote commands that add or remove a public key in
+//! a host's `authorized_keys`.
+//!
+//! The remote commands are idempotent and print a marker line so the caller
+//! can tell "added" from "already there" without parsing shell output.
+
+use crate::error::{Result, ShellDeckError};
+use crate::models::Connection;
+use crate::util::shell_escape;
+use base64::engine::general_purpose::{STANDARD as BASE64, STANDARD_NO_PAD};
+use base64::Engine as _;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Private keys OpenSSH (and `SshClient`) tries when a connection names no
+/// `IdentityFile`, in probe order.
+pub const DEFAULT_KEY_NAMES: [&str; 3] = ["id_ed25519", "id_rsa", "id_ecdsa"];
+
+/// Prefix of the marker line printed by the remote commands.
+const MARKER: &str = "shelldeck-key:";
+
+/// Key types offered when generating a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAlgorithm {
+    Ed25519,
+    /// 4096-bit RSA, for hosts too old for ed25519.
+    Rsa,
+}
+
+impl KeyAlgorithm {
+    pub fn label(&self) -> &'static str {
+        match self {
+            KeyAlgorithm::Ed25519 => "Ed25519",
+            KeyAlgorithm::Rsa => "RSA 4096",
+        }
+    }
+
+    /// File name `ssh-keygen` would suggest.
+    pub fn default_file_name(&self) -> &'static str {
+        match self {
+            KeyAlgorithm::Ed25519 => "id_ed25519",
+            KeyAlgorithm::Rsa => "id_rsa",
+        }
+    }
+
+    fn keygen_args(&self) -> &'static [&'static str] {
+        match self {
+            KeyAlgorithm::Ed25519 => &["-t", "ed25519"],
+            KeyAlgorithm::Rsa => &["-t", "rsa", "-b", "4096"],
+        }
+    }
+
+    fn from_key_type(key_type: &str) -> Self {
+        if key_type == "ssh-rsa" {
+            KeyAlgorithm::Rsa
+        } else {
+            KeyAlgorithm::Ed25519
+        }
+    }
+}
+
+/// One public key in OpenSSH format: `type base64 [comment]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+    pub key_type: String,
+    pub blob: String,
+    pub comment: String,
+}
+
+impl PublicKey {
+    /// Parse a `.pub` file or an `authorized_keys` line. Options in front
+    /// of the key type (`from="…" ssh-ed25519 …`) are skipped.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let mut tokens = line.split_whitespace();
+        let key_type = tokens.find(|t| is_key_type(t))?;
+        let blob = tokens.next()?;
+        BASE64.decode(blob).ok()?;
+        let comment = tokens.collect::<Vec<_>>().join(" ");
+        Some(Self {
+            key_type: key_type.to_string(),
+            blob: blob.to_string(),
+            comment,
+        })
+    }
+
+    /// `SHA256:…` fingerprint, as printed by `ssh-keygen -l`.
+    pub fn fingerprint(&self) -> String {
+        let raw = BASE64.decode(&self.blob).unwrap_or_default();
+        format!("SHA256:{}", STANDARD_NO_PAD.encode(Sha256::digest(raw)))
+    }
+
+    /// Short algorithm name for badges.
+    pub fn algorithm_label(&self) -> &str {
+        match self.key_type.as_str() {
+            "ssh-ed25519" => "ED25519",
+            "ssh-rsa" => "RSA",
+            "ssh-dss" => "DSA",
+            t if t.starts_with("ecdsa-") => "ECDSA",
+            t if t.starts_with("sk-ssh-ed25519") => "ED25519-SK",
+            t if t.starts_with("sk-ecdsa") => "ECDSA-SK",
+            t => t,
+        }
+    }
+
+    /// The key as an `authorized_keys` line.
+    pub fn to_line(&self) -> String {
+        if self.comment.is_empty() {
+            format!("{} {}", self.key_type, self.blob)
+        } else {
+            format!("{} {} {}", self.key_type, self.blob, self.comment)
+        }
+    }
+
+    /// What identifies the key in `authorized_keys`, whatever its options
+    /// and comment.
+    fn needle(&self) -> String {
+        format!("{} {}", self.key_type, self.blob)
+    }
+}
+
+fn is_key_type(token: &str) -> bool {
+    token.starts_with("ssh-") || token.starts_with("ecdsa-sha2-") || token.starts_with("sk-")
+}
+
+/// A private key with its `.pub` companion.
+#[derive(Debug, Clone)]
+pub struct SshKey {
+    pub private_path: PathBuf,
+    pub public: PublicKey,
+}
+
+impl SshKey {
+    /// Read the public half of the key at `private_path`.
+    pub fn load(private_path: &Path) -> Result<Self> {
+        let public_path = public_path(private_path);
+        let content = std::fs::read_to_string(&public_path)?;
+        let public = content.lines().find_map(PublicKey::parse).ok_or_else(|| {
+            ShellDeckError::Config(format!("No public key in {}", public_path.display()))
+        })?;
+        Ok(Self {
+            private_path: private_path.to_path_buf(),
+            public,
+        })
+    }
+
+    pub fn name(&self) -> String {
+        self.private_path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    pub fn fingerprint(&self) -> String {
+        self.public.fingerprint()
+    }
+
+    pub fn algorithm(&self) -> KeyAlgorithm {
+        KeyAlgorithm::from_key_type(&self.public.key_type)
+    }
+
+    /// Whether `connection` signs in with this key: it names it as its
+    /// identity file, or names none and this is one of the default keys.
+    pub fn is_used_by(&self, connection: &Connection) -> bool {
+        match &connection.identity_file {
+            Some(path) => super::ssh_config::expand_tilde(path) == self.private_path,
+            None => self.is_default_key(),
+        }
+    }
+
+    fn is_default_key(&self) -> bool {
+        let Some(dir) = ssh_dir() else {
+            return false;
+        };
+        DEFAULT_KEY_NAMES
+            .iter()
+            .any(|name| dir.join(name) == self.private_path)
+    }
+}
+
+/// `~/.ssh`, whether or not it exists.
+pub fn ssh_dir() -> Option<PathBuf> {
+    crate::util::home_dir().map(|home| home.join(".ssh"))
+}
+
+fn public_path(private_path: &Path) -> PathBuf {
+    let mut name = private_path.as_os_str().to_owned();
+    name.push(".pub");
+    PathBuf::from(name)
+}
+
+/// Key pairs in `dir`: every `*.pub` file whose private half sits next to
+/// it, sorted by name. Unreadable files are skipped.
+pub fn list_keys(dir: &Path) -> Vec<SshKey> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut keys: Vec<SshKey> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| {
+            let private = path.to_str()?.strip_suffix(".pub").map(PathBuf::from)?;
+            private.is_file().then(|| SshKey::load(&private).ok())?
+        })
+        .collect();
+    keys.sort_by_key(|k| k.name());
+    keys
+}
+
+/// The connections that sign in with `key`.
+pub fn connections_using<'a>(key: &SshKey, connections: &'a [Connection]) -> Vec<&'a Connection> {
+    connections.iter().filter(|c| key.is_used_by(c)).collect()
+}
+
+/// Generate a key pair at `path` with `ssh-keygen`. A non-empty passphrase
+/// encrypts the key and is saved in the keychain, where `SshClient` looks
+/// for it. Existing files are never overwritten.
+pub fn generate_key(
+    path: &Path,
+    algorithm: KeyAlgorithm,
+    passphrase: &str,
+    comment: &str,
+) -> Result<SshKey> {
+    if path.exists() || public_path(path).exists() {
+        return Err(ShellDeckError::Config(format!(
+            "{} already exists",
+            path.display()
+        )));
+    }
+    if let Some(dir) = path.parent() {
+        create_private_dir(dir)?;
+    }
+    let mut command = std::process::Command::new("ssh-keygen");
+    command.arg("-q").args(algorithm.keygen_args());
+    #[cfg(unix)]
+    let _askpass = if passphrase.is_empty() {
+        command.arg("-N").arg("");
+        None
+    } else {
+        Some(AskpassHelper::install(&mut command, passphrase)?)
+    };
+    // Other users cannot read a process's command line on Windows.
+    #[cfg(not(unix))]
+    command.arg("-N").arg(passphrase);
+    let output = command
+        .arg("-C")
+        .arg(comment)
+        .arg("-f")
+        .arg(path)
+        .stdin(std::process::Stdio::null())
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                ShellDeckError::Config("ssh-keygen was not found on PATH".to_string())
+            }
+            _ => ShellDeckError::Io(e),
+        })?;
+    if !output.status.success() {
+        return Err(ShellDeckError::Config(format!(
+            "ssh-keygen failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    if !passphrase.is_empty() {
+        super::keychain::store_key_passphrase(&path.to_string_lossy(), passphrase)?;
+    }
+    SshKey::load(path)
+}
+
+/// A throwaway `SSH_ASKPASS` program that answers ssh-keygen's passphrase
+/// prompts from ssh-keygen's own environment. `-N` would show the
+/// passphrase to every local user in the process list. Removed on drop.
+#[cfg(unix)]
+struct AskpassHelper {
+    dir: PathBuf,
+}
+
+#[cfg(unix)]
+impl AskpassHelper {
+    fn install(command: &mut std::process::Command, passphrase: &str) -> Result<Self> {
+        use std::io::Write;
+        use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt};
+        static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-askpass-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::DirBuilder::new().mode(0o700).create(&dir)?;
+        let helper = AskpassHelper { dir };
+        let script = helper.dir.join("askpass");
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o700)
+            .open(&script)?
+            .write_all(b"#!/bin/sh\nprintf '%s\\n' \"$SHELLDECK_KEYGEN_PASSPHRASE\"\n")?;
+        command
+            .env("SSH_ASKPASS", &script)
+            .env("SSH_ASKPASS_REQUIRE", "force")
+            .env("SHELLDECK_KEYGEN_PASSPHRASE", passphrase);
+        // OpenSSH before 8.4 ignores SSH_ASKPASS_REQUIRE and only asks the
+        // helper when a display is set.
+        if std::env::var_os("DISPLAY").is_none() {
+            command.env("DISPLAY", ":0");
+        }
+        Ok(helper)
+    }
+}
+
+#[cfg(unix)]
+impl Drop for AskpassHelper {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    if dir.is_dir() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+/// Where the replacement of `old` goes: `<old>-<stamp>`, with a counter
+/// when that is taken too.
+pub fn rotated_key_path(old: &Path, stamp: &str, exists: impl Fn(&Path) -> bool) -> PathBuf {
+    let base = old.to_string_lossy();
+    let taken = |p: &Path| exists(p) || exists(&public_path(p));
+    let first = PathBuf::from(format!("{}-{}", base, stamp));
+    if !taken(&first) {
+        return first;
+    }
+    (2..)
+        .map(|n| PathBuf::from(format!("{}-{}-{}", base, stamp, n)))
+        .find(|p| !taken(p))
+        .expect("an unused suffix exists")
+}
+
+/// What a remote `authorized_keys` command did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorizedKeysChange {
+    Added,
+    AlreadyPresent,
+    Removed,
+    NotPresent,
+}
+
+impl AuthorizedKeysChange {
+    /// Read the marker line printed by [`authorize_command`] or
+    /// [`revoke_command`]. Anything else (shell banners…) is ignored.
+    pub fn from_output(stdout: &str) -> Option<Self> {
+        stdout
+            .lines()
+            .rev()
+            .find_map(|line| match line.trim().strip_prefix(MARKER)? {
+                "added" => Some(AuthorizedKeysChange::Added),
+                "present" => Some(AuthorizedKeysChange::AlreadyPresent),
+                "removed" => Some(AuthorizedKeysChange::Removed),
+                "absent" => Some(AuthorizedKeysChange::NotPresent),
+                _ => None,
+            })
+    }
+}
+
+/// Shell command appending `key` to `~/.ssh/authorized_keys` unless it is
+/// already there, creating the directory and file with private modes.
+pub fn authorize_command(key: &PublicKey) -> String {
+    let needle = shell_escape(&key.needle());
+    let line = shell_escape(&key.to_line());
+    format!(
+        "umask 077; [ -d ~/.ssh ] || mkdir ~/.ssh || exit 1; \
+         f=~/.ssh/authorized_keys; \
+         if [ -f \"$f\" ] && grep -qF -- {needle} \"$f\"; then echo {m}present; \
+         else \
+         if [ -s \"$f\" ] && [ -n \"$(tail -c 1 \"$f\")\" ]; then echo >> \"$f\"; fi; \
+         printf '%s\\n' {line} >> \"$f\" && echo {m}added; \
+         fi",
+        m = MARKER,
+    )
+}
+
+/// Shell command removing every `authorized_keys` line for `key`. The file
+/// is rewritten in place so its owner and mode are kept, and only once the
+/// filtered copy was written in full: `grep` exits 1 when no line is left
+/// and 2 on errors, a copy that could not be created is missing, and a
+/// copy that could not be written back stays next to the file.
+pub fn revoke_command(key: &PublicKey) -> String {
+    let needle = shell_escape(&key.needle());
+    format!(
+        "umask 077; f=~/.ssh/authorized_keys; t=\"$f.shelldeck\"; \
+         if [ -f \"$f\" ] && grep -qF -- {needle} \"$f\"; then \
+         rm -f \"$t\"; grep -vF -- {needle} \"$f\" > \"$t\"; \
+         if [ $? -gt 1 ] || [ ! -f \"$t\" ]; then rm -f \"$t\"; exit 1; fi; \
+         cat \"$t\" > \"$f\" && rm -f \"$t\" && echo {m}removed; \
+         else echo {m}absent; \
+         fi",
+        m = MARKER,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ED25519: &str = "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIFkyjfoNbrPKDq1qNbY4CyO4lzxZtabc41pmerNb2Ove test@shelldeck";
+
+    #[test]
+    fn fingerprint_matches_ssh_keygen() {
+        let key = PublicKey::parse(ED25519).unwrap();
+        assert_eq!(key.algorithm_label(), "ED25519");
+        assert_eq!(key.comment, "test@shelldeck");
+        // `ssh-keygen -lf` on the same key.
+        assert_eq!(
+            key.fingerprint(),
+            "SHA256:T0b0Vn5hwwEK4k+/FkXRUVSfVKx+L9u4DJCpBdeJ5rA"
+        );
+    }
+
+    #[test]
+    fn authorized_keys_options_and_junk_are_handled() {
+        let with_options = format!("from=\"10.0.0.0/8\",no-pty {}", ED25519);
+        let key = PublicKey::parse(&with_options).unwrap();
+        assert_eq!(key.key_type, "ssh-ed25519");
+        assert_eq!(key.to_line(), ED25519);
+
+        assert!(PublicKey::parse("# comment").is_none());
+        assert!(PublicKey::parse("ssh-ed25519 not*base64").is_none());
+    }
+
+    #[test]
+    fn keys_are_listed_with_their_users() {
+        let dir = std::env::temp_dir().join(format!("shelldeck-keys-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("deploy"), "private").unwrap();
+        std::fs::write(dir.join("deploy.pub"), format!("{}\n", ED25519)).unwrap();
+        // A public key alone is not a key pair we can use.
+        std::fs::write(dir.join("orphan.pub"), ED25519).unwrap();
+
+        let keys = list_keys(&dir);
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].name(), "deploy");
+
+        let mut uses = Connection::new_manual("a".into(), "a.example".into(), "u".into());
+        uses.identity_file = Some(dir.join("deploy"));
+        let mut other = Connection::new_manual("b".into(), "b.example".into(), "u".into());
+        other.identity_file = Some(dir.join("other"));
+        let conns = [uses, other];
+        let users = connections_using(&keys[0], &conns);
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].alias, "a");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotated_path_skips_taken_names() {
+        let old = Path::new("/keys/id_ed25519");
+        let taken = [PathBuf::from("/keys/id_ed25519-2026-10-18.pub")];
+        let next = rotated_key_path(old, "2026-10-18", |p| taken.iter().any(|t| t == p));
+        assert_eq!(next, PathBuf::from("/keys/id_ed25519-2026-10-18-2"));
+    }
+
+    #[test]
+    fn marker_line_is_found_after_noise() {
+        assert_eq!(
+            AuthorizedKeysChange::from_output("Welcome!\nshelldeck-key:added\n"),
+            Some(AuthorizedKeysChange::Added)
+        );
+        assert_eq!(AuthorizedKeysChange::from_output("Welcome!\n"), None);
+    }
+
+    #[cfg(unix)]
+    // The passphrase reaches ssh-keygen through the askpass helper, and
+    // the helper is gone once the key exists.
+    #[cfg(unix)]
+    #[test]
+    fn askpass_helper_encrypts_the_key_with_the_passphrase() {
+        if !crate::util::executable_on_path("ssh-keygen") {
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("shelldeck-keygen-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key = dir.join("id_ed25519");
+        let passphrase = "it's a \"long\" $passphrase";
+
+        let mut command = std::process::Command::new("ssh-keygen");
+        command
+            .args(["-q", "-t", "ed25519", "-C", "test", "-f"])
+            .arg(&key);
+        let helper = AskpassHelper::install(&mut command, passphrase).unwrap();
+        let helper_dir = helper.dir.clone();
+        let status = command.stdin(std::process::Stdio::null()).status().unwrap();
+        drop(helper);
+        assert!(status.success());
+        assert!(!helper_dir.exists());
+
+        let decrypts = |passphrase: &str| {
+            std::process::Command::new("ssh-keygen")
+                .args(["-y", "-P", passphrase, "-f"])
+                .arg(&key)
+                .output()
+                .unwrap()
+                .status
+                .success()
+        };
+        assert!(decrypts(passphrase));
+        assert!(!decrypts(""));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn remote_commands_are_idempotent() {
+        let home = std::env::temp_dir().join(format!("shelldeck-akeys-{}", std::process::id()));
+        std::fs::create_dir_all(&home).unwrap();
+        let run = |command: String| {
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("HOME", &home)
+                .output()
+                .unwrap();
+            AuthorizedKeysChange::from_output(&String::from_utf8_lossy(&output.stdout))
+        };
+        let key = PublicKey::parse(ED25519).unwrap();
+        let authorized = home.join(".ssh").join("authorized_keys");
+
+        assert_eq!(
+            run(revoke_command(&key)),
+            Some(AuthorizedKeysChange::NotPresent)
+        );
+        assert_eq!(
+            run(authorize_command(&key)),
+            Some(AuthorizedKeysChange::Added)
+        );
+        assert_eq!(
+            run(authorize_command(&key)),
+            Some(AuthorizedKeysChange::AlreadyPresent)
+        );
+
+        // A file without a trailing newline keeps its last line intact.
+        std::fs::write(&authorized, "ssh-rsa AAAA other").unwrap();
+        assert_eq!(
+            run(authorize_command(&key)),
+            Some(AuthorizedKeysChange::Added)
+        );
+        let content = std::fs::read_to_string(&authorized).unwrap();
+        assert_eq!(content, format!("ssh-rsa AAAA other\n{}\n", ED25519));
+
+        assert_eq!(
+            run(revoke_command(&key)),
+            Some(AuthorizedKeysChange::Removed)
+        );
+        let content = std::fs::read_to_string(&authorized).unwrap();
+        assert_eq!(content, "ssh-rsa AAAA other\n");
+
+        // A filtered copy that cannot be written leaves the file alone.
+        let before = format!("ssh-rsa AAAA other\n{}\n", ED25519);
+        std::fs::write(&authorized, &before).unwrap();
+        let copy = home.join(".ssh").join("authorized_keys.shelldeck");
+        std::fs::create_dir(&copy).unwrap();
+        assert_eq!(run(revoke_command(&key)), None);
+        assert_eq!(std::fs::read_to_string(&authorized).unwrap(), before);
+        std::fs::remove_dir(&copy).unwrap();
+
+        std::fs::remove_dir_all(&home).unwrap();
+    }
+}
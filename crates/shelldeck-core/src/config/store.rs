@@ -1,9 +1,10 @@
 use crate::config::app_config::AppConfig;
+use crate::config::keychain;
 use crate::error::{Result, ShellDeckError};
-use crate::models::{Connection, ManagedSite, ManagedSiteType, PortForward, Script, SyncProfile};
+use crate::models::{Connection, ManagedSite, PortForward, Script, SyncProfile};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -25,7 +26,34 @@ impl ConnectionStore {
 
     /// Load store from disk, or return empty defaults.
     pub fn load() -> Result<Self> {
-        Self::load_from(&Self::store_path())
+        let mut store = Self::load_from(&Self::store_path())?;
+        store.migrate_db_passwords();
+        Ok(store)
+    }
+
+    /// Older sync profiles kept database logins as client argument strings,
+    /// passwords included. Move the passwords to the keychain and rewrite
+    /// the store once, so the migrated credentials keep their id. Logins
+    /// whose password the keychain refused stay in their legacy form.
+    fn migrate_db_passwords(&mut self) {
+        let mut migrated = false;
+        for profile in &mut self.sync_profiles {
+            if profile.db_credentials().all(|c| c.legacy_args.is_none()) {
+                continue;
+            }
+            migrated = true;
+            if let Err(e) = stash_db_passwords(profile) {
+                warn!(
+                    "Could not move database passwords of sync profile {} to the keychain: {}",
+                    profile.name, e
+                );
+            }
+        }
+        if migrated {
+            if let Err(e) = self.save() {
+                warn!("Failed to save migrated sync profiles: {}", e);
+            }
+        }
     }
 
     /// Save store to disk.
@@ -79,6 +107,15 @@ impl ConnectionStore {
         self.save()
     }
 
+    /// Add several connections with a single save, as after an import.
+    pub fn add_connections_bulk(&mut self, connections: Vec<Connection>) -> Result<()> {
+        if connections.is_empty() {
+            return Ok(());
+        }
+        self.connections.extend(connections);
+        self.save()
+    }
+
     /// Remove a connection by ID, also removing associated port forwards. Returns true if found.
     pub fn remove_connection(&mut self, id: Uuid) -> Result<bool> {
         let original_len = self.connections.len();
@@ -117,18 +154,20 @@ impl ConnectionStore {
 
     // --- PortForward methods ---
 
-    /// Add a port forward and save.
-    pub fn add_port_forward(&mut self, forward: PortForward) -> Result<()> {
+    /// Add a port forward and save. A proxy password goes to the keychain.
+    pub fn add_port_forward(&mut self, mut forward: PortForward) -> Result<()> {
+        stash_proxy_password(&mut forward)?;
         self.port_forwards.push(forward);
         self.save()
     }
 
-    /// Remove a port forward by ID. Returns true if found.
+    /// Remove a port forward by ID, with its proxy password. Returns true if found.
     pub fn remove_port_forward(&mut self, id: Uuid) -> Result<bool> {
         let original_len = self.port_forwards.len();
         self.port_forwards.retain(|pf| pf.id != id);
 
         if self.port_forwards.len() != original_len {
+            let _ = keychain::delete_proxy_password(id);
             self.save()?;
             Ok(true)
         } else {
@@ -136,9 +175,15 @@ impl ConnectionStore {
         }
     }
 
-    /// Update a port forward in place. Returns true if found.
-    pub fn update_port_forward(&mut self, forward: PortForward) -> Result<bool> {
+    /// Update a port forward in place. Returns true if found. A forward
+    /// without a new proxy password keeps the stored one, unless its proxy
+    /// no longer asks for one.
+    pub fn update_port_forward(&mut self, mut forward: PortForward) -> Result<bool> {
         if let Some(existing) = self.port_forwards.iter_mut().find(|pf| pf.id == forward.id) {
+            stash_proxy_password(&mut forward)?;
+            if !forward.proxy.requires_auth() {
+                let _ = keychain::delete_proxy_password(forward.id);
+            }
             *existing = forward;
             self.save()?;
             Ok(true)
@@ -155,6 +200,56 @@ impl ConnectionStore {
             .collect()
     }
 
+    /// Bring the forwards read from the SSH config in line with a fresh
+    /// import, and save if anything changed. Forwards whose line is still
+    /// there keep what was set in ShellDeck (label, auto start, probe);
+    /// those whose line is gone are removed. Returns the removed ids.
+    pub fn sync_ssh_config_forwards(&mut self, imported: &[PortForward]) -> Result<Vec<Uuid>> {
+        let (changed, removed) = self.merge_ssh_config_forwards(imported);
+        if changed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    fn merge_ssh_config_forwards(&mut self, imported: &[PortForward]) -> (bool, Vec<Uuid>) {
+        let removed: Vec<Uuid> = self
+            .port_forwards
+            .iter()
+            .filter(|pf| pf.from_ssh_config && !imported.iter().any(|i| i.id == pf.id))
+            .map(|pf| pf.id)
+            .collect();
+        self.port_forwards.retain(|pf| !removed.contains(&pf.id));
+        let mut changed = !removed.is_empty();
+
+        for forward in imported {
+            let Some(existing) = self.port_forwards.iter_mut().find(|pf| pf.id == forward.id)
+            else {
+                self.port_forwards.push(forward.clone());
+                changed = true;
+                continue;
+            };
+            let same = existing.connection_id == forward.connection_id
+                && existing.direction == forward.direction
+                && existing.local_host == forward.local_host
+                && existing.local_port == forward.local_port
+                && existing.remote_host == forward.remote_host
+                && existing.remote_port == forward.remote_port
+                && existing.from_ssh_config;
+            if !same {
+                existing.connection_id = forward.connection_id;
+                existing.direction = forward.direction;
+                existing.local_host = forward.local_host.clone();
+                existing.local_port = forward.local_port;
+                existing.remote_host = forward.remote_host.clone();
+                existing.remote_port = forward.remote_port;
+                existing.from_ssh_config = true;
+                changed = true;
+            }
+        }
+        (changed, removed)
+    }
+
     // --- Script methods ---
 
     /// Add a script and save.
@@ -194,16 +289,23 @@ impl ConnectionStore {
 
     // --- SyncProfile methods ---
 
-    /// Add a sync profile and save.
-    pub fn add_sync_profile(&mut self, profile: SyncProfile) -> Result<()> {
+    /// Add a sync profile and save. Database passwords go to the keychain.
+    pub fn add_sync_profile(&mut self, mut profile: SyncProfile) -> Result<()> {
+        stash_db_passwords(&mut profile)?;
         self.sync_profiles.push(profile);
         self.save()
     }
 
-    /// Remove a sync profile by ID. Returns true if found.
+    /// Remove a sync profile by ID, with its database passwords. Returns true if found.
     pub fn remove_sync_profile(&mut self, id: Uuid) -> Result<bool> {
         let original_len = self.sync_profiles.len();
-        self.sync_profiles.retain(|p| p.id != id);
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.sync_profiles)
+            .into_iter()
+            .partition(|p| p.id == id);
+        self.sync_profiles = kept;
+        for creds in removed.iter().flat_map(|p| p.db_credentials()) {
+            let _ = keychain::delete_db_password(creds.id);
+        }
 
         if self.sync_profiles.len() != original_len {
             self.save()?;
@@ -214,8 +316,14 @@ impl ConnectionStore {
     }
 
     /// Update a sync profile in place. Returns true if found.
-    pub fn update_sync_profile(&mut self, profile: SyncProfile) -> Result<bool> {
+    pub fn update_sync_profile(&mut self, mut profile: SyncProfile) -> Result<bool> {
         if let Some(existing) = self.sync_profiles.iter_mut().find(|p| p.id == profile.id) {
+            stash_db_passwords(&mut profile)?;
+            for creds in existing.db_credentials() {
+                if !profile.db_credentials().any(|c| c.id == creds.id) {
+                    let _ = keychain::delete_db_password(creds.id);
+                }
+            }
             *existing = profile;
             self.save()?;
             Ok(true)
@@ -235,16 +343,13 @@ impl ConnectionStore {
     pub fn add_managed_site(&mut self, site: ManagedSite) -> Result<()> {
         let name = site.name().to_string();
         let conn_id = site.connection_id;
-        let is_nginx = matches!(site.site_type, ManagedSiteType::NginxSite(_));
+        let kind = std::mem::discriminant(&site.site_type);
 
         // Dedup: skip if same connection + name + type already exists
         let exists = self.managed_sites.iter().any(|s| {
             s.connection_id == conn_id
                 && s.name() == name
-                && matches!(
-                    (&s.site_type, is_nginx),
-                    (ManagedSiteType::NginxSite(_), true) | (ManagedSiteType::Database(_), false)
-                )
+                && std::mem::discriminant(&s.site_type) == kind
         });
 
         if !exists {
@@ -263,18 +368,36 @@ impl ConnectionStore {
         if sites.is_empty() {
             return Ok(());
         }
+        self.replace_managed_sites(sites);
+        self.save()
+    }
 
+    /// The in-memory half of [`Self::add_managed_sites_bulk`]. A site found
+    /// again keeps its id and monitoring settings, so its health history
+    /// survives a rescan.
+    fn replace_managed_sites(&mut self, mut sites: Vec<ManagedSite>) {
         // Collect which connections are being refreshed
         let refreshed_conns: std::collections::HashSet<Uuid> =
             sites.iter().map(|s| s.connection_id).collect();
 
+        for site in &mut sites {
+            let kind = std::mem::discriminant(&site.site_type);
+            if let Some(old) = self.managed_sites.iter().find(|s| {
+                s.connection_id == site.connection_id
+                    && s.name() == site.name()
+                    && std::mem::discriminant(&s.site_type) == kind
+            }) {
+                site.id = old.id;
+                site.monitor = old.monitor.clone();
+            }
+        }
+
         // Remove old entries for those connections
         self.managed_sites
             .retain(|s| !refreshed_conns.contains(&s.connection_id));
 
         // Add all fresh results
         self.managed_sites.extend(sites);
-        self.save()
     }
 
     /// Remove a managed site by ID. Returns true if found.
@@ -309,6 +432,29 @@ impl ConnectionStore {
     }
 }
 
+/// Store the passwords held in a profile's database credentials in the
+/// keychain and drop them from the profile. Credentials read from a legacy
+/// string are written in the current form once their password is stored.
+fn stash_db_passwords(profile: &mut SyncProfile) -> Result<()> {
+    for creds in profile.db_credentials_mut() {
+        if let Some(password) = &creds.password {
+            keychain::store_db_password(creds.id, password)?;
+            creds.password = None;
+        }
+        creds.legacy_args = None;
+    }
+    Ok(())
+}
+
+/// Store the proxy password held in a forward in the keychain and drop it
+/// from the forward.
+fn stash_proxy_password(forward: &mut PortForward) -> Result<()> {
+    if let Some(password) = forward.proxy.password.take() {
+        keychain::store_proxy_password(forward.id, &password)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -401,6 +547,96 @@ mod tests {
         std::fs::remove_dir_all(path.parent().unwrap()).ok();
     }
 
+    #[test]
+    fn sync_profiles_never_write_database_passwords() {
+        use crate::models::db_credentials::DbCredentials;
+        use crate::models::{DatabaseEngine, SyncItem, SyncItemKind, SyncOptions};
+
+        let path = temp_path("connections.json");
+        let mut store = ConnectionStore::default();
+        store.sync_profiles.push(SyncProfile {
+            id: Uuid::new_v4(),
+            name: "db".to_string(),
+            description: None,
+            source_connection_id: Uuid::new_v4(),
+            dest_connection_id: Uuid::new_v4(),
+            items: vec![SyncItem {
+                id: Uuid::new_v4(),
+                kind: SyncItemKind::Database {
+                    name: "app".to_string(),
+                    engine: DatabaseEngine::Mysql,
+                    source_credentials: DbCredentials::from_args("-u app -ps3cret"),
+                    dest_credentials: None,
+                },
+                enabled: true,
+            }],
+            options: SyncOptions::default(),
+            created_at: chrono::Utc::now(),
+            last_synced: None,
+        });
+        let id = store.sync_profiles[0].db_credentials().next().unwrap().id;
+
+        store.save_to(&path).expect("save_to");
+        let written = std::fs::read_to_string(&path).expect("read store");
+        assert!(!written.contains("s3cret"));
+
+        let loaded = ConnectionStore::load_from(&path).expect("load_from");
+        let creds: Vec<_> = loaded.sync_profiles[0].db_credentials().collect();
+        assert_eq!(creds.len(), 1);
+        assert_eq!(creds[0].id, id);
+        assert_eq!(creds[0].user, "app");
+        assert_eq!(creds[0].password, None);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn rescanned_sites_keep_their_id_and_monitor() {
+        use crate::models::managed_site::{ManagedSite, ManagedSiteType};
+        use crate::models::server_sync::DiscoveredSite;
+
+        let conn_id = Uuid::new_v4();
+        let site = |name: &str| DiscoveredSite {
+            server_name: name.to_string(),
+            config_path: format!("/etc/nginx/sites-enabled/{}", name),
+            root: "/var/www".to_string(),
+            listen_port: 443,
+            ssl: true,
+        };
+        let mut store = ConnectionStore::default();
+        let mut shop = ManagedSite::new(conn_id, "prod", ManagedSiteType::NginxSite(site("shop")));
+        shop.monitor.enabled = true;
+        shop.monitor.keyword = Some("Add to cart".to_string());
+        let shop_id = shop.id;
+        store.managed_sites.push(shop);
+        store.managed_sites.push(ManagedSite::new(
+            conn_id,
+            "prod",
+            ManagedSiteType::NginxSite(site("old")),
+        ));
+
+        store.replace_managed_sites(vec![
+            ManagedSite::new(conn_id, "prod", ManagedSiteType::NginxSite(site("shop"))),
+            ManagedSite::new(conn_id, "prod", ManagedSiteType::NginxSite(site("blog"))),
+        ]);
+
+        assert_eq!(store.managed_sites.len(), 2, "stale site dropped");
+        let shop = store
+            .managed_sites
+            .iter()
+            .find(|s| s.name() == "shop")
+            .unwrap();
+        assert_eq!(shop.id, shop_id);
+        assert!(shop.monitor.enabled);
+        assert_eq!(shop.monitor.keyword.as_deref(), Some("Add to cart"));
+        let blog = store
+            .managed_sites
+            .iter()
+            .find(|s| s.name() == "blog")
+            .unwrap();
+        assert!(!blog.monitor.enabled);
+    }
+
     #[test]
     fn load_from_missing_creates_empty() {
         let path = temp_path("connections.json");
@@ -427,6 +663,37 @@ mod tests {
         std::fs::remove_dir_all(path.parent().unwrap()).ok();
     }
 
+    #[test]
+    fn ssh_config_forwards_follow_the_import() {
+        let connection = Uuid::new_v4();
+        let mut store = ConnectionStore::default();
+        let own = PortForward::new_local(connection, 3000, "localhost", 3000);
+        let mut kept = PortForward::new_local(connection, 8080, "localhost", 80);
+        kept.from_ssh_config = true;
+        kept.auto_start = true;
+        let mut gone = PortForward::new_local(connection, 9000, "localhost", 9000);
+        gone.from_ssh_config = true;
+        store.port_forwards = vec![own.clone(), kept.clone(), gone.clone()];
+
+        let mut moved = kept.clone();
+        moved.auto_start = false;
+        moved.remote_host = "web.internal".into();
+        let mut added = PortForward::new_remote(connection, 9222, "127.0.0.1", 9222);
+        added.from_ssh_config = true;
+
+        let (changed, removed) = store.merge_ssh_config_forwards(&[moved.clone(), added.clone()]);
+        assert!(changed);
+        assert_eq!(removed, vec![gone.id]);
+        let ids: Vec<Uuid> = store.port_forwards.iter().map(|f| f.id).collect();
+        assert_eq!(ids, vec![own.id, kept.id, added.id]);
+        assert_eq!(store.port_forwards[1].remote_host, "web.internal");
+        assert!(store.port_forwards[1].auto_start, "ShellDeck settings kept");
+
+        let (changed, removed) = store.merge_ssh_config_forwards(&[moved, added]);
+        assert!(!changed);
+        assert!(removed.is_empty());
+    }
+
     // SDTEST-084 — Manual, SshConfig, and CloudSync connections must
     // coexist in a single store round-trip without one squashing
     // another. Regression sensor for cloud_sync merge (SDUC-104): a
@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::error::{Result, ShellDeckError};
+use crate::models::tunnel_health::{ForwardHistory, TunnelState};
+
+/// State changes kept per forward.
+const MAX_TRANSITIONS_PER_FORWARD: usize = 500;
+
+/// `[tunnels]` — how the supervisor treats forwards that fail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TunnelsConfig {
+    /// Notify once a supervised forward has been down this many seconds.
+    pub down_alert_secs: u64,
+    /// Longest wait between two reconnect attempts, in seconds.
+    pub max_backoff_secs: u64,
+    /// Failed probes in a row before the forward is torn down and rebuilt.
+    pub probe_failures: u32,
+}
+
+impl Default for TunnelsConfig {
+    fn default() -> Self {
+        Self {
+            down_alert_secs: 120,
+            max_backoff_secs: 60,
+            probe_failures: 3,
+        }
+    }
+}
+
+/// Up/down history of supervised forwards.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TunnelHistory {
+    #[serde(default)]
+    pub forwards: HashMap<Uuid, ForwardHistory>,
+}
+
+impl TunnelHistory {
+    fn history_path() -> PathBuf {
+        super::app_config::AppConfig::config_dir().join("tunnel-history.json")
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::history_path())
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::history_path())
+    }
+
+    /// Record a state change of a forward, dropping the oldest beyond
+    /// [`MAX_TRANSITIONS_PER_FORWARD`]. Returns whether the state changed.
+    pub fn record(&mut self, forward_id: Uuid, state: TunnelState, reason: Option<String>) -> bool {
+        let history = self.forwards.entry(forward_id).or_default();
+        let changed = history.record(chrono::Utc::now(), state, reason);
+        history.truncate_front(MAX_TRANSITIONS_PER_FORWARD);
+        changed
+    }
+
+    pub fn get(&self, forward_id: Uuid) -> Option<&ForwardHistory> {
+        self.forwards.get(&forward_id)
+    }
+
+    /// Forget forwards that were deleted.
+    pub fn retain_forwards(&mut self, live: &[Uuid]) {
+        self.forwards.retain(|id, _| live.contains(id));
+    }
+
+    pub(crate) fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            if !dir.as_os_str().is_empty() && !dir.exists() {
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+        let content = serde_json::to_string(self).map_err(|e| {
+            ShellDeckError::Serialization(format!("Failed to serialize tunnel history: {}", e))
+        })?;
+        crate::util::atomic_write(path, content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Load from a specific path, returning an empty history if missing.
+    pub(crate) fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            ShellDeckError::Serialization(format!("Failed to parse tunnel history: {}", e))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-tunnel-history-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir.join(name)
+    }
+
+    #[test]
+    fn history_is_capped_and_round_trips() {
+        let path = temp_path("tunnel-history.json");
+        assert_eq!(
+            TunnelHistory::load_from(&path).unwrap(),
+            TunnelHistory::default()
+        );
+
+        let kept = Uuid::new_v4();
+        let dropped = Uuid::new_v4();
+        let mut history = TunnelHistory::default();
+        for i in 0..(MAX_TRANSITIONS_PER_FORWARD + 10) {
+            let state = if i % 2 == 0 {
+                TunnelState::Up
+            } else {
+                TunnelState::Down
+            };
+            assert!(history.record(kept, state, None));
+        }
+        assert!(!history.record(kept, TunnelState::Down, None), "no change");
+        history.record(dropped, TunnelState::Up, None);
+        history.retain_forwards(&[kept]);
+        history.save_to(&path).unwrap();
+
+        let loaded = TunnelHistory::load_from(&path).unwrap();
+        assert_eq!(loaded.forwards.len(), 1);
+        let forward = loaded.get(kept).unwrap();
+        assert_eq!(forward.transitions.len(), MAX_TRANSITIONS_PER_FORWARD);
+        assert_eq!(forward.state(), Some(TunnelState::Down));
+        assert!(forward.down_since().is_some());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn tunnels_section_defaults_when_partial() {
+        let config: TunnelsConfig = toml::from_str("down_alert_secs = 30").unwrap();
+        assert_eq!(config.down_alert_secs, 30);
+        assert_eq!(config.max_backoff_secs, 60);
+        assert_eq!(config.probe_failures, 3);
+    }
+}
@@ -1,11 +1,13 @@
+use crate::config::ssh_config;
 use crate::error::{Result, ShellDeckError};
 use notify::{Config, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::path::PathBuf;
 use std::sync::mpsc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 use tracing::{debug, info};
 
-/// Watches ~/.ssh/config for changes and invokes a callback.
+/// Watches ~/.ssh/config and the files it includes for changes and invokes
+/// a callback.
 pub struct ConfigWatcher {
     ssh_config_path: PathBuf,
     _watcher: Option<RecommendedWatcher>,
@@ -45,8 +47,15 @@ impl ConfigWatcher {
         }
     }
 
-    /// Start watching for file changes. Calls `callback` when the config file is modified.
-    /// Events are debounced at 500ms to avoid rapid-fire triggers.
+    /// Start watching for file changes. Calls `callback` when the config file,
+    /// or a file it includes, is created, modified or removed.
+    /// Events are debounced: the callback runs once the files have been quiet
+    /// for 500ms.
+    ///
+    /// The directories holding the files are watched rather than the files,
+    /// so saves that replace the file (editors, `ssh_config_write`) are
+    /// seen. Files matched later by an `Include` wildcard count as well, as
+    /// long as they land in a directory watched from the start.
     ///
     /// This spawns a background thread for debouncing. The watcher is kept alive
     /// as long as the returned `ConfigWatcher` is not dropped.
@@ -54,26 +63,33 @@ impl ConfigWatcher {
     where
         F: Fn() + Send + 'static,
     {
-        let watch_path = if self.ssh_config_path.exists() {
-            self.ssh_config_path.clone()
-        } else {
-            // Watch the .ssh directory instead, in case config doesn't exist yet
-            self.ssh_config_path
+        let root = self.ssh_config_path.clone();
+        let known_files = ssh_config::config_files(&root);
+        let mut watch_dirs: Vec<PathBuf> = Vec::new();
+        for file in std::iter::once(&root).chain(&known_files) {
+            // Watch the .ssh directory even when config doesn't exist yet
+            let dir = file
                 .parent()
                 .map(|p| p.to_path_buf())
-                .unwrap_or_else(|| self.ssh_config_path.clone())
-        };
+                .unwrap_or_else(|| file.clone());
+            if !watch_dirs.contains(&dir) {
+                watch_dirs.push(dir);
+            }
+        }
 
-        let target_path = self.ssh_config_path.clone();
         let (tx, rx) = mpsc::channel();
 
         let mut watcher = RecommendedWatcher::new(
             move |event: std::result::Result<notify::Event, notify::Error>| {
                 if let Ok(event) = event {
                     match event.kind {
-                        EventKind::Modify(_) | EventKind::Create(_) => {
-                            // Check if any of the affected paths match our target
-                            let relevant = event.paths.iter().any(|p| p == &target_path);
+                        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
+                            // Check if any of the affected paths is one of the config files
+                            let relevant = event.paths.iter().any(|p| {
+                                p == &root
+                                    || known_files.contains(p)
+                                    || ssh_config::config_files(&root).contains(p)
+                            });
                             if relevant {
                                 let _ = tx.send(());
                             }
@@ -86,40 +102,40 @@ impl ConfigWatcher {
         )
         .map_err(|e| ShellDeckError::Config(format!("Failed to create file watcher: {}", e)))?;
 
-        watcher
-            .watch(&watch_path, RecursiveMode::NonRecursive)
-            .map_err(|e| {
-                ShellDeckError::Config(format!("Failed to watch {}: {}", watch_path.display(), e))
-            })?;
-
-        info!("Watching {} for changes", watch_path.display());
+        for watch_path in &watch_dirs {
+            watcher
+                .watch(watch_path, RecursiveMode::NonRecursive)
+                .map_err(|e| {
+                    ShellDeckError::Config(format!(
+                        "Failed to watch {}: {}",
+                        watch_path.display(),
+                        e
+                    ))
+                })?;
+            info!("Watching {} for changes", watch_path.display());
+        }
 
         // Debounce thread
         std::thread::Builder::new()
             .name("ssh-config-watcher".to_string())
             .spawn(move || {
                 let debounce_duration = Duration::from_millis(500);
-                let mut last_trigger = Instant::now() - debounce_duration;
 
-                loop {
-                    match rx.recv() {
-                        Ok(()) => {
-                            let now = Instant::now();
-                            if now.duration_since(last_trigger) >= debounce_duration {
-                                last_trigger = now;
-                                debug!("SSH config changed, triggering callback");
-                                callback();
-                            } else {
-                                debug!("SSH config change debounced");
-                            }
-                        }
-                        Err(_) => {
-                            // Channel closed, watcher was dropped
-                            debug!("Config watcher channel closed, stopping");
-                            break;
+                while rx.recv().is_ok() {
+                    // Wait for the files to be quiet, so a save made in
+                    // several writes is read once and complete.
+                    loop {
+                        match rx.recv_timeout(debounce_duration) {
+                            Ok(()) => debug!("SSH config change debounced"),
+                            Err(mpsc::RecvTimeoutError::Timeout) => break,
+                            Err(mpsc::RecvTimeoutError::Disconnected) => return,
                         }
                     }
+                    debug!("SSH config changed, triggering callback");
+                    callback();
                 }
+                // Channel closed, watcher was dropped
+                debug!("Config watcher channel closed, stopping");
             })
             .map_err(|e| {
                 ShellDeckError::Config(format!("Failed to spawn watcher thread: {}", e))
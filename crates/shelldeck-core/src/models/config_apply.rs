@@ -0,0 +1,333 @@
+//! Guarded apply of a web server config edit: stage the new file on the
+//! server, put it in place behind a backup, let the server's own checker
+//! (`nginx -t`, `apachectl configtest`, `caddy validate`) judge it, reload,
+//! and put the backup back when the check or the health probe after the
+//! reload fails.
+//!
+//! nginx and Apache can only check the installed tree, so the candidate is
+//! swapped in for the check. The running server does not read it before
+//! the reload, and the reload only happens once the check passed.
+//!
+//! No SSH or async dependencies — fully unit-testable.
+
+use chrono::{DateTime, Utc};
+
+use super::managed_site::ManagedSiteType;
+use super::site_health::HealthSample;
+use crate::util::shell_escape;
+
+/// Becomes root through `sudo -n` unless the login already is. Commands
+/// below run privileged steps as `$S <cmd>`.
+const PRIVILEGES: &str = "S=; [ \"$(id -u)\" = 0 ] || S='sudo -n';";
+
+/// Upload the new config (stdin) to a fresh temp file and print its path.
+/// `/tmp`, not the config directory: `sites-enabled/*` style includes would
+/// pick up anything written next to the live file.
+pub const STAGE_COMMAND: &str =
+    "t=$(mktemp /tmp/shelldeck-conf.XXXXXX) && cat > \"$t\" && printf '%s\\n' \"$t\"";
+
+/// The web server a site config belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebServer {
+    Nginx,
+    Apache,
+    Caddy,
+}
+
+impl WebServer {
+    /// The server behind a web site entry; `None` for databases and services.
+    pub fn for_site(site_type: &ManagedSiteType) -> Option<Self> {
+        match site_type {
+            ManagedSiteType::NginxSite(_) => Some(WebServer::Nginx),
+            ManagedSiteType::ApacheSite(_) => Some(WebServer::Apache),
+            ManagedSiteType::CaddySite(_) => Some(WebServer::Caddy),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WebServer::Nginx => "nginx",
+            WebServer::Apache => "Apache",
+            WebServer::Caddy => "Caddy",
+        }
+    }
+
+    /// The server's own syntax check of its installed configuration.
+    pub fn check(&self) -> &'static str {
+        match self {
+            WebServer::Nginx => "nginx -t",
+            WebServer::Apache => "apachectl configtest",
+            WebServer::Caddy => "caddy validate --config /etc/caddy/Caddyfile --adapter caddyfile",
+        }
+    }
+
+    /// Make the running server pick up its configuration without dropping
+    /// connections.
+    pub fn reload(&self) -> &'static str {
+        match self {
+            WebServer::Nginx => "systemctl reload nginx || nginx -s reload",
+            WebServer::Apache => "apachectl graceful",
+            WebServer::Caddy => "systemctl reload caddy",
+        }
+    }
+
+    /// [`Self::check`], privileged, with its diagnostics on stdout.
+    pub fn check_command(&self) -> String {
+        privileged(&format!("$S sh -c {}", shell_escape(self.check())))
+    }
+
+    /// [`Self::reload`], privileged, with its diagnostics on stdout.
+    pub fn reload_command(&self) -> String {
+        privileged(&format!("$S sh -c {}", shell_escape(self.reload())))
+    }
+}
+
+fn privileged(script: &str) -> String {
+    format!("{} {{ {}; }} 2>&1", PRIVILEGES, script)
+}
+
+/// Print the live config, for loading it into the editor. Unlike the
+/// other commands, errors stay on stderr so they cannot end up in the file.
+pub fn read_command(config_path: &str) -> String {
+    format!("{} $S cat -- {}", PRIVILEGES, shell_escape(config_path))
+}
+
+/// Where the live config is kept while a new one is tried, outside every
+/// include directory: `/var/tmp/shelldeck-<file>-<time>.bak`. `/var/tmp`
+/// survives a reboot, so the file is still there for a manual restore.
+pub fn backup_path(config_path: &str, at: DateTime<Utc>) -> String {
+    let name: String = config_path
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!(
+        "/var/tmp/shelldeck-{}-{}.bak",
+        name.trim_start_matches('.'),
+        at.format("%Y%m%d%H%M%S")
+    )
+}
+
+/// Back up the live config and swap `staged` in with a rename, so the
+/// server never sees a half-written file. `config_path` is resolved first:
+/// `sites-enabled` entries are usually symlinks, which must keep pointing
+/// at the file in `sites-available`. Prints the resolved path last.
+pub fn install_command(config_path: &str, staged: &str, backup: &str) -> String {
+    privileged(&install_script(config_path, staged, backup))
+}
+
+fn install_script(config_path: &str, staged: &str, backup: &str) -> String {
+    let staged = shell_escape(staged);
+    format!(
+        "t=$(readlink -f -- {config}) && [ -f \"$t\" ] || {{ echo \"not a file: \"{config}; exit 1; }}; \
+         {{ $S cp -p -- \"$t\" {backup} && $S cp -p -- \"$t\" \"$t.shelldeck-new\" \
+         && cat {staged} | $S tee \"$t.shelldeck-new\" > /dev/null \
+         && $S mv -f -- \"$t.shelldeck-new\" \"$t\"; }} \
+         || {{ $S rm -f -- \"$t.shelldeck-new\"; rm -f -- {staged}; exit 1; }}; \
+         rm -f -- {staged}; printf '%s\\n' \"$t\"",
+        config = shell_escape(config_path),
+        backup = shell_escape(backup),
+        staged = staged,
+    )
+}
+
+/// Put `backup` back over `target` (the resolved path printed by
+/// [`install_command`]), again through a rename.
+pub fn restore_command(target: &str, backup: &str) -> String {
+    privileged(&restore_script(target, backup))
+}
+
+fn restore_script(target: &str, backup: &str) -> String {
+    let next = shell_escape(&format!("{}.shelldeck-new", target));
+    format!(
+        "$S cp -p -- {backup} {next} && $S mv -f -- {next} {target}",
+        backup = shell_escape(backup),
+        next = next,
+        target = shell_escape(target),
+    )
+}
+
+/// The resolved config path [`install_command`] printed.
+pub fn installed_target(output: &str) -> Option<&str> {
+    output
+        .lines()
+        .map(str::trim)
+        .rfind(|line| line.starts_with('/'))
+}
+
+/// The line of checker output worth showing next to a failure: the first
+/// one naming an error (`nginx: [emerg] unknown directive ...`), else the
+/// last non-empty one.
+pub fn check_summary(output: &str) -> &str {
+    let lines = || output.lines().map(str::trim).filter(|l| !l.is_empty());
+    lines()
+        .find(|line| {
+            let lower = line.to_ascii_lowercase();
+            ["emerg", "error", "failed", "invalid"]
+                .iter()
+                .any(|word| lower.contains(word))
+        })
+        .or_else(|| lines().last())
+        .unwrap_or_default()
+}
+
+/// Whether the probe after a reload calls for restoring the old config.
+/// A site that was already failing before the change is left alone: the
+/// edit may well be the fix, and rolling back would not bring it up.
+pub fn should_roll_back(before: Option<&HealthSample>, after: &HealthSample) -> bool {
+    !after.is_up() && before.is_none_or(HealthSample::is_up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::server_sync::DiscoveredSite;
+    use crate::models::site_health::HealthFailure;
+    use chrono::TimeZone;
+
+    fn sample(failure: Option<HealthFailure>) -> HealthSample {
+        HealthSample {
+            checked_at: Utc::now(),
+            addresses: Vec::new(),
+            http_status: Some(200),
+            latency_ms: Some(12),
+            keyword_found: None,
+            cert_expires_at: None,
+            chain_valid: None,
+            failure,
+        }
+    }
+
+    #[test]
+    fn commands_for_each_server() {
+        let site = DiscoveredSite {
+            server_name: "example.com".into(),
+            root: "/var/www".into(),
+            config_path: "/etc/nginx/sites-enabled/example".into(),
+            listen_port: 80,
+            ssl: false,
+        };
+        let server = WebServer::for_site(&ManagedSiteType::NginxSite(site)).unwrap();
+        assert_eq!(
+            server.check_command(),
+            "S=; [ \"$(id -u)\" = 0 ] || S='sudo -n'; { $S sh -c 'nginx -t'; } 2>&1"
+        );
+        assert_eq!(
+            WebServer::Apache.reload_command(),
+            "S=; [ \"$(id -u)\" = 0 ] || S='sudo -n'; { $S sh -c 'apachectl graceful'; } 2>&1"
+        );
+        assert_eq!(
+            read_command("/etc/nginx/sites-enabled/it's"),
+            "S=; [ \"$(id -u)\" = 0 ] || S='sudo -n'; $S cat -- '/etc/nginx/sites-enabled/it'\\''s'"
+        );
+    }
+
+    #[test]
+    fn backups_are_named_after_the_config() {
+        let at = Utc.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap();
+        assert_eq!(
+            backup_path("/etc/nginx/sites-enabled/shop.conf", at),
+            "/var/tmp/shelldeck-shop.conf-20260304050607.bak"
+        );
+        assert_eq!(
+            backup_path("/etc/caddy/my site", at),
+            "/var/tmp/shelldeck-my_site-20260304050607.bak"
+        );
+    }
+
+    #[test]
+    fn summary_prefers_the_error_line() {
+        let nginx =
+            "nginx: [emerg] unknown directive \"lisen\" in /etc/nginx/sites-enabled/shop:3\n\
+                     nginx: configuration file /etc/nginx/nginx.conf test failed\n";
+        assert_eq!(
+            check_summary(nginx),
+            "nginx: [emerg] unknown directive \"lisen\" in /etc/nginx/sites-enabled/shop:3"
+        );
+        assert_eq!(check_summary("Syntax OK\n\n"), "Syntax OK");
+        assert_eq!(check_summary(""), "");
+        assert_eq!(
+            installed_target("cp: warning\n/etc/nginx/sites-available/shop\n"),
+            Some("/etc/nginx/sites-available/shop")
+        );
+    }
+
+    #[test]
+    fn only_a_change_that_broke_the_site_rolls_back() {
+        let up = sample(None);
+        let down = sample(Some(HealthFailure::HttpStatus(502)));
+        assert!(should_roll_back(Some(&up), &down));
+        assert!(should_roll_back(None, &down));
+        assert!(!should_roll_back(Some(&down), &down));
+        assert!(!should_roll_back(Some(&up), &up));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_swaps_through_symlinks_and_restore_undoes_it() {
+        let dir =
+            std::env::temp_dir().join(format!("shelldeck-config-apply-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("sites-available")).unwrap();
+        std::fs::create_dir_all(dir.join("sites-enabled")).unwrap();
+        let real = dir.join("sites-available/shop");
+        let link = dir.join("sites-enabled/shop");
+        let staged = dir.join("staged");
+        let backup = dir.join("shop.bak");
+        std::fs::write(&real, "listen 80;\n").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        std::fs::write(&staged, "listen 8080;\n").unwrap();
+
+        let sh = |script: String| {
+            std::process::Command::new("sh")
+                .arg("-c")
+                .arg(script)
+                .env_remove("S")
+                .output()
+                .expect("run sh")
+        };
+        let out = sh(install_script(
+            &link.to_string_lossy(),
+            &staged.to_string_lossy(),
+            &backup.to_string_lossy(),
+        ));
+        assert!(out.status.success(), "{:?}", out);
+        let target = String::from_utf8_lossy(&out.stdout).into_owned();
+        let target = installed_target(&target).unwrap().to_string();
+        let real_resolved = std::fs::canonicalize(&real).unwrap();
+        assert_eq!(target, real_resolved.to_string_lossy());
+        assert!(std::fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(std::fs::read_to_string(&link).unwrap(), "listen 8080;\n");
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "listen 80;\n");
+        assert!(!staged.exists());
+
+        let out = sh(restore_script(&target, &backup.to_string_lossy()));
+        assert!(out.status.success(), "{:?}", out);
+        assert_eq!(std::fs::read_to_string(&link).unwrap(), "listen 80;\n");
+        assert_eq!(
+            std::fs::read_dir(dir.join("sites-available"))
+                .unwrap()
+                .count(),
+            1
+        );
+
+        let out = sh(install_script(
+            &dir.join("missing").to_string_lossy(),
+            &staged.to_string_lossy(),
+            &backup.to_string_lossy(),
+        ));
+        assert!(!out.status.success());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
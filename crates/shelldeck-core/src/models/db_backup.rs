@@ -0,0 +1,520 @@
+//! Database backups: plans that dump a discovered database on demand or on
+//! a cron schedule, the catalog entries each dump leaves behind, retention
+//! (keep the last N plus daily and weekly snapshots), and the shell
+//! commands that dump, store, checksum and restore an archive.
+//!
+//! No SSH or async dependencies — fully unit-testable.
+
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use super::db_credentials::{DbCredentials, DbLogin};
+use super::server_sync::{DatabaseEngine, DiscoveredDatabase};
+use crate::util::shell_escape;
+
+/// Where archives of a plan are kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BackupDestination {
+    /// A directory on this machine.
+    Local { dir: PathBuf },
+    /// A directory on another (or the same) server.
+    Remote { connection_id: Uuid, dir: String },
+}
+
+impl BackupDestination {
+    /// Full path of the archive `name` at this destination.
+    pub fn archive_path(&self, name: &str) -> String {
+        match self {
+            BackupDestination::Local { dir } => dir.join(name).to_string_lossy().into_owned(),
+            BackupDestination::Remote { dir, .. } => {
+                format!("{}/{}", dir.trim_end_matches('/'), name)
+            }
+        }
+    }
+}
+
+/// Which archives of a plan survive a new backup. An archive is kept when
+/// any rule keeps it, and the newest one is always kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// The most recent archives.
+    pub keep_last: u32,
+    /// The newest archive of each of the most recent days (UTC).
+    pub keep_daily: u32,
+    /// The newest archive of each of the most recent ISO weeks.
+    pub keep_weekly: u32,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_last: 5,
+            keep_daily: 7,
+            keep_weekly: 4,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// The archives among `entries` that no rule keeps.
+    pub fn expired<'a>(&self, entries: &[&'a BackupEntry]) -> Vec<&'a BackupEntry> {
+        let mut newest_first: Vec<&BackupEntry> = entries.to_vec();
+        newest_first.sort_by_key(|e| std::cmp::Reverse(e.created_at));
+
+        let mut kept: HashSet<Uuid> = newest_first
+            .iter()
+            .take(self.keep_last.max(1) as usize)
+            .map(|e| e.id)
+            .collect();
+        let mut days = HashSet::new();
+        let mut weeks = HashSet::new();
+        for entry in &newest_first {
+            let day = entry.created_at.date_naive();
+            if days.len() < self.keep_daily as usize && days.insert(day) {
+                kept.insert(entry.id);
+            }
+            let week = day.iso_week();
+            if weeks.len() < self.keep_weekly as usize && weeks.insert((week.year(), week.week())) {
+                kept.insert(entry.id);
+            }
+        }
+        newest_first
+            .into_iter()
+            .filter(|e| !kept.contains(&e.id))
+            .collect()
+    }
+}
+
+/// How one database is backed up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupPlan {
+    pub id: Uuid,
+    /// The server the database lives on.
+    pub connection_id: Uuid,
+    pub database: String,
+    pub engine: DatabaseEngine,
+    /// `None` logs in with the client's defaults. The password is in the
+    /// keychain under the credentials' id.
+    #[serde(default)]
+    pub credentials: Option<DbCredentials>,
+    pub destination: BackupDestination,
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    /// Cron expression. `None` backs up on demand only.
+    #[serde(default)]
+    pub schedule: Option<String>,
+    /// When the scheduler last looked at this plan.
+    #[serde(default)]
+    pub last_checked: Option<DateTime<Utc>>,
+}
+
+impl BackupPlan {
+    pub fn new(
+        connection_id: Uuid,
+        db: &DiscoveredDatabase,
+        destination: BackupDestination,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            connection_id,
+            database: db.name.clone(),
+            engine: db.engine,
+            credentials: None,
+            destination,
+            retention: RetentionPolicy::default(),
+            schedule: None,
+            last_checked: None,
+        }
+    }
+}
+
+/// One archive in the catalog.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub id: Uuid,
+    pub plan_id: Uuid,
+    /// The server the database was dumped from.
+    pub connection_id: Uuid,
+    /// Name of that server at backup time, for display.
+    pub source_name: String,
+    pub database: String,
+    pub engine: DatabaseEngine,
+    pub created_at: DateTime<Utc>,
+    /// Size of the compressed archive.
+    pub size_bytes: u64,
+    /// Hex SHA-256 of the compressed archive.
+    pub sha256: String,
+    pub destination: BackupDestination,
+    /// Full path of the archive at the destination.
+    pub path: String,
+    /// Taken by the schedule rather than on demand.
+    #[serde(default)]
+    pub scheduled: bool,
+}
+
+impl BackupEntry {
+    /// Human-readable archive size.
+    pub fn size_display(&self) -> String {
+        let size = self.size_bytes as f64;
+        if size < 1024.0 {
+            format!("{} B", self.size_bytes)
+        } else if size < 1024.0 * 1024.0 {
+            format!("{:.1} KB", size / 1024.0)
+        } else if size < 1024.0 * 1024.0 * 1024.0 {
+            format!("{:.1} MB", size / (1024.0 * 1024.0))
+        } else {
+            format!("{:.2} GB", size / (1024.0 * 1024.0 * 1024.0))
+        }
+    }
+}
+
+/// File name for a dump of `database` taken at `at`, e.g.
+/// `shop-20261018-021500.sql.gz`.
+pub fn archive_name(database: &str, at: DateTime<Utc>) -> String {
+    let safe: String = database
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let safe = safe.trim_start_matches('.');
+    format!("{}-{}.sql.gz", safe, at.format("%Y%m%d-%H%M%S"))
+}
+
+/// Dump `database` as gzip on stdout. The exit status is the dump tool's
+/// (or gzip's, when it fails), not only gzip's as in a plain pipeline.
+pub fn dump_command(engine: DatabaseEngine, database: &str, login: &DbLogin) -> String {
+    let dump = match engine {
+        DatabaseEngine::Mysql => format!(
+            "{} --single-transaction --routines --triggers {}",
+            login.client(engine, "mysqldump"),
+            shell_escape(database)
+        ),
+        DatabaseEngine::Postgresql => format!(
+            "{} {}",
+            login.client(engine, "pg_dump"),
+            shell_escape(database)
+        ),
+    };
+    checked_pipe(&dump, "gzip -c")
+}
+
+/// `producer | consumer`, failing when either side fails. POSIX sh has no
+/// pipefail: the statuses come back on fd 4.
+fn checked_pipe(producer: &str, consumer: &str) -> String {
+    format!(
+        "{{ s=$( {{ {{ {}; echo $? >&4; }} | {} >&3 || echo 1 >&4; }} 4>&1 ); }} 3>&1; [ \"$s\" = 0 ]",
+        producer, consumer
+    )
+}
+
+/// Save stdin as `path` on a server. The archive only appears under its
+/// name once it is complete.
+pub fn store_command(path: &str) -> String {
+    let dir = match path.rfind('/') {
+        Some(0) => "/",
+        Some(i) => &path[..i],
+        None => ".",
+    };
+    let part = format!("{}.part", path);
+    format!(
+        "umask 077 && mkdir -p {} && cat > {} && mv -f {} {}",
+        shell_escape(dir),
+        shell_escape(&part),
+        shell_escape(&part),
+        shell_escape(path)
+    )
+}
+
+/// Print an archive on a server.
+pub fn read_command(path: &str) -> String {
+    format!("cat {}", shell_escape(path))
+}
+
+/// Print the SHA-256 of an archive on a server.
+pub fn checksum_command(path: &str) -> String {
+    let path = shell_escape(path);
+    format!("sha256sum {} 2>/dev/null || shasum -a 256 {}", path, path)
+}
+
+/// The hash in the output of [`checksum_command`].
+pub fn parse_checksum_output(output: &str) -> Option<String> {
+    let hash = output.split_whitespace().next()?.to_ascii_lowercase();
+    (hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())).then_some(hash)
+}
+
+pub fn remove_command(path: &str) -> String {
+    format!("rm -f {}", shell_escape(path))
+}
+
+/// Restore a gzip dump read from stdin into `database`, creating the
+/// database first when it does not exist. Stops at the first SQL error,
+/// and fails when the archive does not decompress.
+pub fn restore_command(engine: DatabaseEngine, database: &str, login: &DbLogin) -> String {
+    match engine {
+        DatabaseEngine::Mysql => {
+            let mysql = login.client(engine, "mysql");
+            let create = format!(
+                "CREATE DATABASE IF NOT EXISTS `{}`",
+                database.replace('`', "``")
+            );
+            format!(
+                "{} -e {} && {}",
+                mysql,
+                shell_escape(&create),
+                checked_pipe(
+                    "gunzip -c",
+                    &format!("{} {}", mysql, shell_escape(database))
+                )
+            )
+        }
+        DatabaseEngine::Postgresql => format!(
+            "{{ {} {} 2>/dev/null || true; }} && {}",
+            login.client(engine, "createdb"),
+            shell_escape(database),
+            checked_pipe(
+                "gunzip -c",
+                &format!(
+                    "{} -q -v ON_ERROR_STOP=1 {}",
+                    login.client(engine, "psql"),
+                    shell_escape(database)
+                )
+            )
+        ),
+    }
+}
+
+/// Running SHA-256 and size of an archive as it streams past.
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveDigest {
+    hasher: Sha256,
+    bytes: u64,
+}
+
+impl ArchiveDigest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+        self.bytes += data.len() as u64;
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Hex digest and total size.
+    pub fn finish(self) -> (String, u64) {
+        let hash: String = self
+            .hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        (hash, self.bytes)
+    }
+}
+
+/// SHA-256 of a local archive.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut digest = ArchiveDigest::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        digest.update(&buf[..n]);
+    }
+    Ok(digest.finish().0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn entry(at: DateTime<Utc>) -> BackupEntry {
+        BackupEntry {
+            id: Uuid::new_v4(),
+            plan_id: Uuid::nil(),
+            connection_id: Uuid::nil(),
+            source_name: "web1".into(),
+            database: "shop".into(),
+            engine: DatabaseEngine::Mysql,
+            created_at: at,
+            size_bytes: 1024,
+            sha256: String::new(),
+            destination: BackupDestination::Local {
+                dir: PathBuf::from("/backups"),
+            },
+            path: String::new(),
+            scheduled: true,
+        }
+    }
+
+    #[test]
+    fn retention_keeps_last_daily_and_weekly() {
+        // Four backups a day for 30 days, newest last.
+        let start = Utc.with_ymd_and_hms(2026, 9, 1, 0, 0, 0).unwrap();
+        let entries: Vec<BackupEntry> = (0..120)
+            .map(|i| entry(start + Duration::hours(6 * i)))
+            .collect();
+        let refs: Vec<&BackupEntry> = entries.iter().collect();
+        let policy = RetentionPolicy {
+            keep_last: 3,
+            keep_daily: 7,
+            keep_weekly: 4,
+        };
+        let expired: HashSet<Uuid> = policy.expired(&refs).iter().map(|e| e.id).collect();
+        let kept: Vec<&BackupEntry> = entries
+            .iter()
+            .filter(|e| !expired.contains(&e.id))
+            .collect();
+
+        let newest = entries.last().unwrap();
+        assert!(kept.contains(&newest));
+        // The last three, the newest of six more days, and the newest of
+        // the two weeks before those days.
+        assert_eq!(kept.len(), 3 + 6 + 2);
+        assert!(kept
+            .iter()
+            .all(|e| e.created_at >= start + Duration::days(12)));
+    }
+
+    #[test]
+    fn retention_always_keeps_the_newest() {
+        let at = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+        let entries = [entry(at), entry(at + Duration::minutes(5))];
+        let refs: Vec<&BackupEntry> = entries.iter().collect();
+        let policy = RetentionPolicy {
+            keep_last: 0,
+            keep_daily: 0,
+            keep_weekly: 0,
+        };
+        assert_eq!(policy.expired(&refs), vec![&entries[0]]);
+    }
+
+    #[test]
+    fn archive_names_are_safe_file_names() {
+        let at = Utc.with_ymd_and_hms(2026, 10, 18, 2, 15, 0).unwrap();
+        assert_eq!(archive_name("shop", at), "shop-20261018-021500.sql.gz");
+        assert_eq!(
+            archive_name("../my db", at),
+            "_my_db-20261018-021500.sql.gz"
+        );
+        let remote = BackupDestination::Remote {
+            connection_id: Uuid::nil(),
+            dir: "/srv/backups/".into(),
+        };
+        assert_eq!(remote.archive_path("a.sql.gz"), "/srv/backups/a.sql.gz");
+    }
+
+    #[test]
+    fn dump_exits_with_the_dump_status() {
+        let login = DbLogin::new(None, Some("/tmp/shelldeck.x".into()));
+        let cmd = dump_command(DatabaseEngine::Mysql, "shop", &login);
+        assert_eq!(
+            cmd,
+            "{ s=$( { { mysqldump --defaults-extra-file='/tmp/shelldeck.x' --single-transaction --routines --triggers 'shop'; echo $? >&4; } | gzip -c >&3 || echo 1 >&4; } 4>&1 ); } 3>&1; [ \"$s\" = 0 ]"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dump_wrapper_fails_when_the_dump_fails() {
+        let run = |dump: &str| {
+            let cmd = dump_command(DatabaseEngine::Postgresql, "shop", &DbLogin::default())
+                .replace("pg_dump 'shop'", dump);
+            std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("{} > /dev/null", cmd))
+                .status()
+                .expect("run sh")
+                .success()
+        };
+        if !crate::util::executable_on_path("gzip") {
+            return;
+        }
+        assert!(run("echo dump"));
+        assert!(!run("echo partial; false"));
+    }
+
+    #[test]
+    fn restore_creates_the_database_first() {
+        let login = DbLogin::default();
+        assert_eq!(
+            restore_command(DatabaseEngine::Mysql, "shop", &login),
+            "mysql -e 'CREATE DATABASE IF NOT EXISTS `shop`' && { s=$( { { gunzip -c; echo $? >&4; } | mysql 'shop' >&3 || echo 1 >&4; } 4>&1 ); } 3>&1; [ \"$s\" = 0 ]"
+        );
+        assert_eq!(
+            restore_command(DatabaseEngine::Postgresql, "shop", &login),
+            "{ createdb 'shop' 2>/dev/null || true; } && { s=$( { { gunzip -c; echo $? >&4; } | psql -q -v ON_ERROR_STOP=1 'shop' >&3 || echo 1 >&4; } 4>&1 ); } 3>&1; [ \"$s\" = 0 ]"
+        );
+    }
+
+    // A truncated or corrupt archive must fail the restore even though the
+    // client happily loads the part that decompressed.
+    #[cfg(unix)]
+    #[test]
+    fn restore_fails_when_the_archive_does_not_decompress() {
+        let run = |input: &str| {
+            let cmd = restore_command(DatabaseEngine::Mysql, "shop", &DbLogin::default())
+                .replace("mysql -e 'CREATE DATABASE IF NOT EXISTS `shop`'", "true")
+                .replace("mysql 'shop'", "cat");
+            std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("{} | {{ {}; }} > /dev/null", input, cmd))
+                .status()
+                .expect("run sh")
+                .success()
+        };
+        if !crate::util::executable_on_path("gzip") {
+            return;
+        }
+        assert!(run("echo 'SELECT 1;' | gzip -c"));
+        assert!(!run("echo 'SELECT 1;' | gzip -c | head -c 20"));
+        assert!(!run("echo not-gzip"));
+    }
+
+    #[test]
+    fn store_writes_through_a_part_file() {
+        assert_eq!(
+            store_command("/srv/backups/shop.sql.gz"),
+            "umask 077 && mkdir -p '/srv/backups' && cat > '/srv/backups/shop.sql.gz.part' && mv -f '/srv/backups/shop.sql.gz.part' '/srv/backups/shop.sql.gz'"
+        );
+    }
+
+    #[test]
+    fn digest_matches_checksum_tools() {
+        let mut digest = ArchiveDigest::new();
+        digest.update(b"hello ");
+        digest.update(b"world");
+        let (hash, bytes) = digest.finish();
+        assert_eq!(bytes, 11);
+        assert_eq!(
+            hash,
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert_eq!(
+            parse_checksum_output(&format!("{}  /srv/backups/shop.sql.gz\n", hash)),
+            Some(hash)
+        );
+        assert_eq!(parse_checksum_output("sha256sum: not found"), None);
+    }
+}
@@ -0,0 +1,299 @@
+//! SSH doctor: the staged trace of one diagnostic connection. Each step
+//! says which host it ran against, what it found and, when it did not pass,
+//! which fix to suggest. The UI words the fixes; this module only names them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::connection::Connection;
+
+/// The stages of a connection, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticStage {
+    Dns,
+    Tcp,
+    Jump,
+    Banner,
+    Kex,
+    HostKey,
+    AuthMethods,
+    Key,
+    Password,
+    Shell,
+}
+
+impl DiagnosticStage {
+    /// Stable identifier, used for translation keys.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiagnosticStage::Dns => "dns",
+            DiagnosticStage::Tcp => "tcp",
+            DiagnosticStage::Jump => "jump",
+            DiagnosticStage::Banner => "banner",
+            DiagnosticStage::Kex => "kex",
+            DiagnosticStage::HostKey => "host_key",
+            DiagnosticStage::AuthMethods => "auth_methods",
+            DiagnosticStage::Key => "key",
+            DiagnosticStage::Password => "password",
+            DiagnosticStage::Shell => "shell",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    /// Not run because an earlier step made it moot.
+    Skipped,
+    Ok,
+    Warning,
+    Failed,
+}
+
+/// What to try when a step does not pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticFix {
+    /// The name does not resolve: typo, VPN or DNS server.
+    CheckHostname,
+    /// Nothing listens on the port: wrong port or sshd down.
+    CheckPort,
+    /// No answer at all: host down, firewall or routing.
+    CheckNetwork,
+    /// The jump host cannot be reached or used.
+    CheckJumpHost,
+    /// Something other than sshd answers on the port.
+    NotSsh,
+    /// Client and server share no algorithm in some category.
+    NoCommonAlgorithms,
+    /// The host key differs from known_hosts.
+    HostKeyChanged,
+    /// The host is not in known_hosts yet.
+    UnknownHostKey,
+    /// The server does not take public keys.
+    PublicKeyNotOffered,
+    /// The identity file does not exist.
+    KeyMissing,
+    /// The key is encrypted and its passphrase is not in the keychain.
+    KeyPassphrase,
+    /// The server refused the key.
+    KeyRejected,
+    /// No password is saved for the host.
+    StorePassword,
+    /// The server refused the saved password.
+    PasswordRejected,
+    /// Signed in, but no session could be started.
+    ShellFailed,
+}
+
+impl DiagnosticFix {
+    /// Stable identifier, used for translation keys.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DiagnosticFix::CheckHostname => "check_hostname",
+            DiagnosticFix::CheckPort => "check_port",
+            DiagnosticFix::CheckNetwork => "check_network",
+            DiagnosticFix::CheckJumpHost => "check_jump_host",
+            DiagnosticFix::NotSsh => "not_ssh",
+            DiagnosticFix::NoCommonAlgorithms => "no_common_algorithms",
+            DiagnosticFix::HostKeyChanged => "host_key_changed",
+            DiagnosticFix::UnknownHostKey => "unknown_host_key",
+            DiagnosticFix::PublicKeyNotOffered => "public_key_not_offered",
+            DiagnosticFix::KeyMissing => "key_missing",
+            DiagnosticFix::KeyPassphrase => "key_passphrase",
+            DiagnosticFix::KeyRejected => "key_rejected",
+            DiagnosticFix::StorePassword => "store_password",
+            DiagnosticFix::PasswordRejected => "password_rejected",
+            DiagnosticFix::ShellFailed => "shell_failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticStep {
+    pub stage: DiagnosticStage,
+    /// The hop or target the step ran against (`bastion`, `10.0.0.5:22`, …).
+    pub host: String,
+    pub status: StepStatus,
+    pub detail: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fix: Option<DiagnosticFix>,
+}
+
+impl DiagnosticStep {
+    pub fn new(
+        stage: DiagnosticStage,
+        host: impl Into<String>,
+        status: StepStatus,
+        detail: impl Into<String>,
+    ) -> Self {
+        Self {
+            stage,
+            host: host.into(),
+            status,
+            detail: detail.into(),
+            elapsed_ms: None,
+            fix: None,
+        }
+    }
+
+    pub fn ok(stage: DiagnosticStage, host: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(stage, host, StepStatus::Ok, detail)
+    }
+
+    pub fn failed(
+        stage: DiagnosticStage,
+        host: impl Into<String>,
+        detail: impl Into<String>,
+        fix: DiagnosticFix,
+    ) -> Self {
+        Self::new(stage, host, StepStatus::Failed, detail).with_fix(fix)
+    }
+
+    pub fn with_fix(mut self, fix: DiagnosticFix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    pub fn with_elapsed(mut self, elapsed: std::time::Duration) -> Self {
+        self.elapsed_ms = Some(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX));
+        self
+    }
+}
+
+/// One diagnostic run, filled in step by step while it progresses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiagnosticReport {
+    pub connection_name: String,
+    /// `user@host:port` of the final target.
+    pub target: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_jump: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub steps: Vec<DiagnosticStep>,
+    pub finished: bool,
+}
+
+impl DiagnosticReport {
+    pub fn new(connection: &Connection) -> Self {
+        Self {
+            connection_name: connection.display_name().to_string(),
+            target: connection.connection_string(),
+            proxy_jump: connection
+                .proxy_jump
+                .clone()
+                .filter(|jump| !jump.trim().is_empty()),
+            started_at: Utc::now(),
+            steps: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// The worst status of any step; `Ok` for an empty report.
+    pub fn outcome(&self) -> StepStatus {
+        self.steps
+            .iter()
+            .map(|s| s.status)
+            .max()
+            .unwrap_or(StepStatus::Ok)
+            .max(StepStatus::Ok)
+    }
+
+    /// The step that stopped the run, if any.
+    pub fn first_failure(&self) -> Option<&DiagnosticStep> {
+        self.steps.iter().find(|s| s.status == StepStatus::Failed)
+    }
+
+    /// Whether the run got all the way to a shell.
+    pub fn reached_shell(&self) -> bool {
+        self.steps
+            .iter()
+            .any(|s| s.stage == DiagnosticStage::Shell && s.status == StepStatus::Ok)
+    }
+
+    /// Distinct hosts in the order the run reached them.
+    pub fn hosts(&self) -> Vec<&str> {
+        let mut hosts: Vec<&str> = Vec::new();
+        for step in &self.steps {
+            if !hosts.contains(&step.host.as_str()) {
+                hosts.push(&step.host);
+            }
+        }
+        hosts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> DiagnosticReport {
+        let mut conn = Connection::new_manual(
+            "web".to_string(),
+            "web.internal".to_string(),
+            "deploy".to_string(),
+        );
+        conn.proxy_jump = Some("admin@bastion".to_string());
+        DiagnosticReport::new(&conn)
+    }
+
+    #[test]
+    fn outcome_is_the_worst_step_and_skips_do_not_count() {
+        let mut report = report();
+        assert_eq!(report.outcome(), StepStatus::Ok);
+        report.steps.push(DiagnosticStep::new(
+            DiagnosticStage::Password,
+            "web",
+            StepStatus::Skipped,
+            "",
+        ));
+        assert_eq!(report.outcome(), StepStatus::Ok);
+        report.steps.push(
+            DiagnosticStep::new(DiagnosticStage::HostKey, "web", StepStatus::Warning, "new")
+                .with_fix(DiagnosticFix::UnknownHostKey),
+        );
+        assert_eq!(report.outcome(), StepStatus::Warning);
+        report.steps.push(DiagnosticStep::failed(
+            DiagnosticStage::Key,
+            "web",
+            "rejected",
+            DiagnosticFix::KeyRejected,
+        ));
+        assert_eq!(report.outcome(), StepStatus::Failed);
+        assert_eq!(report.first_failure().unwrap().stage, DiagnosticStage::Key);
+        assert!(!report.reached_shell());
+    }
+
+    #[test]
+    fn hosts_keep_the_order_the_run_reached_them() {
+        let mut report = report();
+        assert_eq!(report.target, "deploy@web.internal:22");
+        assert_eq!(report.proxy_jump.as_deref(), Some("admin@bastion"));
+        for host in ["admin@bastion", "admin@bastion", "web", "admin@bastion"] {
+            report
+                .steps
+                .push(DiagnosticStep::ok(DiagnosticStage::Tcp, host, ""));
+        }
+        assert_eq!(report.hosts(), vec!["admin@bastion", "web"]);
+    }
+
+    #[test]
+    fn steps_serialize_with_snake_case_codes() {
+        let step = DiagnosticStep::failed(
+            DiagnosticStage::HostKey,
+            "web",
+            "changed",
+            DiagnosticFix::HostKeyChanged,
+        )
+        .with_elapsed(std::time::Duration::from_millis(42));
+        let json = serde_json::to_value(&step).unwrap();
+        assert_eq!(json["stage"], "host_key");
+        assert_eq!(json["fix"], "host_key_changed");
+        assert_eq!(json["elapsed_ms"], 42);
+        assert_eq!(step.stage.code(), "host_key");
+        assert_eq!(step.fix.unwrap().code(), "host_key_changed");
+    }
+}
@@ -4,9 +4,11 @@
 
 use std::path::{Path, PathBuf};
 
+use super::db_credentials::DbLogin;
 use super::server_sync::{
-    DatabaseEngine, DiscoveredDatabase, DiscoveredSite, FileEntry, SyncOptions,
+    DatabaseEngine, DiscoveredDatabase, DiscoveredSite, FileEntry, SyncItemKind, SyncOptions,
 };
+use super::sync_plan::{escape_glob, escape_wildcards, has_wildcards};
 use crate::util::shell_escape;
 
 // ---------------------------------------------------------------------------
@@ -463,6 +465,308 @@ pub fn parse_nginx_configs(output: &str) -> Vec<DiscoveredSite> {
     sites
 }
 
+// ---------------------------------------------------------------------------
+// Apache and Caddy discovery
+// ---------------------------------------------------------------------------
+
+/// Where Apache keeps its configuration: Debian's `apache2.conf` and
+/// `sites-enabled`, RHEL's `httpd.conf`. Everything else is reached through
+/// their `Include` directives (see [`apache_includes`]).
+pub const APACHE_CONFIG_PATHS: &[&str] = &[
+    "/etc/apache2/apache2.conf",
+    "/etc/apache2/sites-enabled/*",
+    "/etc/httpd/conf/httpd.conf",
+];
+
+/// The stock Caddyfile. Further files come in through `import`
+/// (see [`caddy_imports`]).
+pub const CADDY_CONFIG_PATHS: &[&str] = &["/etc/caddy/Caddyfile"];
+
+/// Build a command that prints every file matching `patterns` with the
+/// `---FILE:path` markers of [`nginx_discover_command`]. A `*` in a pattern
+/// is a glob; the rest is taken literally.
+pub fn config_files_command(patterns: &[String]) -> String {
+    if patterns.is_empty() {
+        return "true".to_string();
+    }
+    let words: Vec<String> = patterns.iter().map(|p| glob_word(p)).collect();
+    format!(
+        r#"for f in {}; do [ -f "$f" ] && echo "---FILE:$f" && cat "$f"; done 2>/dev/null"#,
+        words.join(" ")
+    )
+}
+
+/// Quote `pattern` for the shell while leaving its `*`s free to expand.
+fn glob_word(pattern: &str) -> String {
+    pattern
+        .split('*')
+        .map(shell_escape)
+        .collect::<Vec<_>>()
+        .join("*")
+}
+
+/// Split `---FILE:path` output into `(path, contents)` pairs.
+fn config_files(output: &str) -> impl Iterator<Item = (&str, &str)> {
+    output.split("---FILE:").filter_map(|block| {
+        let (path, contents) = block.split_once('\n').unwrap_or((block, ""));
+        let path = path.trim();
+        (!path.is_empty()).then_some((path, contents))
+    })
+}
+
+/// Add a discovered site, folding it into an earlier entry for the same
+/// name in the same file (an HTTP redirect next to the HTTPS site).
+fn push_site(sites: &mut Vec<DiscoveredSite>, site: DiscoveredSite) {
+    match sites
+        .iter_mut()
+        .find(|s| s.config_path == site.config_path && s.server_name == site.server_name)
+    {
+        Some(prev) => {
+            if site.ssl {
+                prev.ssl = true;
+                prev.listen_port = site.listen_port;
+            }
+            if prev.root.is_empty() {
+                prev.root = site.root;
+            }
+        }
+        None => sites.push(site),
+    }
+}
+
+/// Names that stand for "any host" rather than a real site.
+fn is_catch_all(name: &str) -> bool {
+    matches!(name, "" | "_" | "localhost" | "\"\"" | "*")
+}
+
+/// Files pulled in by `Include` / `IncludeOptional` in Apache configs,
+/// as absolute patterns. Relative paths resolve against `ServerRoot`, or
+/// against the directory the config lives in when it doesn't set one
+/// (`/etc/httpd` for `/etc/httpd/conf/httpd.conf`).
+pub fn apache_includes(output: &str) -> Vec<String> {
+    let server_root = output.lines().find_map(|line| {
+        let (key, value) = directive(line)?;
+        key.eq_ignore_ascii_case("ServerRoot")
+            .then(|| unquote(value).trim_end_matches('/').to_string())
+    });
+    let mut includes = Vec::new();
+    for (path, contents) in config_files(output) {
+        let root = server_root.clone().unwrap_or_else(|| {
+            let dir = parent_dir(path);
+            dir.strip_suffix("/conf").unwrap_or(dir).to_string()
+        });
+        for line in contents.lines() {
+            let Some((key, value)) = directive(line) else {
+                continue;
+            };
+            if !key.eq_ignore_ascii_case("Include") && !key.eq_ignore_ascii_case("IncludeOptional")
+            {
+                continue;
+            }
+            let pattern = resolve(&root, unquote(value));
+            if !includes.contains(&pattern) {
+                includes.push(pattern);
+            }
+        }
+    }
+    includes
+}
+
+/// Files pulled in by `import` in Caddyfiles, resolved against the
+/// importing file's directory. Imports of snippets (`import common`) are
+/// not files and are left out.
+pub fn caddy_imports(output: &str) -> Vec<String> {
+    let mut imports = Vec::new();
+    for (path, contents) in config_files(output) {
+        for line in contents.lines() {
+            let mut words = line.split_whitespace();
+            if words.next() != Some("import") {
+                continue;
+            }
+            let Some(target) = words.next() else {
+                continue;
+            };
+            if !target.contains('/') && !target.contains('*') {
+                continue;
+            }
+            let pattern = resolve(parent_dir(path), target);
+            if !imports.contains(&pattern) {
+                imports.push(pattern);
+            }
+        }
+    }
+    imports
+}
+
+/// A config line as `(directive, arguments)`, skipping comments and blanks.
+fn directive(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    Some((key, value.trim()))
+}
+
+fn unquote(value: &str) -> &str {
+    value.trim().trim_matches('"')
+}
+
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+fn resolve(dir: &str, path: &str) -> String {
+    if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("{}/{}", dir, path)
+    }
+}
+
+/// Parse concatenated Apache config output into discovered sites, one per
+/// `<VirtualHost>` with a `ServerName`.
+///
+/// The port comes from the vhost address (`*:443`), SSL from `SSLEngine
+/// on`. Directive names are matched case-insensitively, as Apache does.
+pub fn parse_apache_configs(output: &str) -> Vec<DiscoveredSite> {
+    let mut sites = Vec::new();
+    for (config_path, contents) in config_files(output) {
+        let mut vhost: Option<DiscoveredSite> = None;
+        for line in contents.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let lower = trimmed.to_ascii_lowercase();
+            if lower.starts_with("<virtualhost") {
+                let listen_port = trimmed["<VirtualHost".len()..]
+                    .trim_end_matches('>')
+                    .split_whitespace()
+                    .next()
+                    .and_then(|addr| addr.rsplit_once(':'))
+                    .and_then(|(_, port)| port.parse::<u16>().ok())
+                    .unwrap_or(80);
+                vhost = Some(DiscoveredSite {
+                    server_name: String::new(),
+                    root: String::new(),
+                    config_path: config_path.to_string(),
+                    listen_port,
+                    ssl: false,
+                });
+                continue;
+            }
+            if lower.starts_with("</virtualhost") {
+                if let Some(site) = vhost.take() {
+                    if !is_catch_all(&site.server_name) {
+                        push_site(&mut sites, site);
+                    }
+                }
+                continue;
+            }
+            let Some(site) = vhost.as_mut() else {
+                continue;
+            };
+            let Some((key, value)) = directive(trimmed) else {
+                continue;
+            };
+            match key.to_ascii_lowercase().as_str() {
+                "servername" => {
+                    let name = unquote(value);
+                    // `ServerName example.com:443` carries the port too.
+                    let name = name.split_once(':').map(|(n, _)| n).unwrap_or(name);
+                    site.server_name = name.to_string();
+                }
+                "documentroot" => site.root = unquote(value).trim_end_matches('/').to_string(),
+                "sslengine" => site.ssl = value.eq_ignore_ascii_case("on"),
+                _ => {}
+            }
+        }
+    }
+    sites
+}
+
+/// Parse concatenated Caddyfile output into discovered sites, one per site
+/// block. Global options (`{ ... }`) and snippets (`(name) { ... }`) are
+/// skipped, as are blocks that only name a port (`:8080`).
+///
+/// Caddy serves every named host over HTTPS unless told otherwise, so a site
+/// is SSL unless its address says `http://` or port 80.
+pub fn parse_caddyfiles(output: &str) -> Vec<DiscoveredSite> {
+    let mut sites = Vec::new();
+    for (config_path, contents) in config_files(output) {
+        let mut depth: i32 = 0;
+        let mut current: Option<DiscoveredSite> = None;
+        for line in contents.lines() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if depth == 0 && trimmed.ends_with('{') {
+                let header = trimmed.trim_end_matches('{').trim();
+                current = if header.is_empty() || header.starts_with('(') {
+                    None
+                } else {
+                    caddy_site(header, config_path)
+                };
+            } else if depth == 1 {
+                if let Some(site) = current.as_mut() {
+                    let mut words = trimmed.split_whitespace();
+                    if words.next() == Some("root") {
+                        // `root [<matcher>] <path>`
+                        if let Some(path) = words.last() {
+                            site.root = path.trim_end_matches('/').to_string();
+                        }
+                    }
+                }
+            }
+            for ch in trimmed.chars() {
+                match ch {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => {}
+                }
+            }
+            if depth <= 0 {
+                depth = 0;
+                if let Some(site) = current.take() {
+                    push_site(&mut sites, site);
+                }
+            }
+        }
+    }
+    sites
+}
+
+/// The site a Caddy block header names: the first address with a host.
+fn caddy_site(header: &str, config_path: &str) -> Option<DiscoveredSite> {
+    header
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|a| !a.is_empty())
+        .find_map(|address| {
+            let (scheme, rest) = match address.split_once("://") {
+                Some((scheme, rest)) => (Some(scheme), rest),
+                None => (None, address),
+            };
+            let rest = rest.split('/').next().unwrap_or(rest);
+            let (host, port) = match rest.rsplit_once(':') {
+                Some((host, port)) => (host, port.parse::<u16>().ok()),
+                None => (rest, None),
+            };
+            if is_catch_all(host) {
+                return None;
+            }
+            let ssl = scheme != Some("http") && port != Some(80);
+            Some(DiscoveredSite {
+                server_name: host.to_string(),
+                root: String::new(),
+                config_path: config_path.to_string(),
+                listen_port: port.unwrap_or(if ssl { 443 } else { 80 }),
+                ssl,
+            })
+        })
+}
+
 // ---------------------------------------------------------------------------
 // Database discovery
 // ---------------------------------------------------------------------------
@@ -588,11 +892,15 @@ pub fn rsync_command(
     source_path: &str,
     dest_user: &str,
     dest_host: &str,
+    dest_port: u16,
     dest_path: &str,
     options: &SyncOptions,
     excludes: &[String],
 ) -> String {
-    let mut cmd = String::from("rsync -avz --progress --info=progress2");
+    let mut cmd = format!(
+        "rsync -avz --progress --info=progress2 -e {}",
+        shell_escape(&format!("ssh -p {}", dest_port))
+    );
 
     if options.compress {
         // -z already added above
@@ -606,85 +914,443 @@ pub fn rsync_command(
     if options.skip_existing {
         cmd.push_str(" --ignore-existing");
     }
+    if options.checksum {
+        cmd.push_str(" --checksum");
+    }
     if let Some(bw) = options.bandwidth_limit {
         cmd.push_str(&format!(" --bwlimit={}", bw));
     }
     for pattern in excludes {
-        cmd.push_str(&format!(" --exclude={}", shell_escape(pattern)));
+        cmd.push_str(&format!(
+            " --exclude={}",
+            shell_escape(&rsync_exclude(source_path, pattern))
+        ));
     }
 
     cmd.push_str(&format!(
-        " {} {}@{}:{}",
+        " {} {}:{}",
         shell_escape(source_path),
-        dest_user,
-        dest_host,
+        ssh_login(dest_user, dest_host),
         shell_escape(dest_path)
     ));
     cmd
 }
 
-/// Build a MySQL database sync command (pipe via SSH).
-pub fn mysql_sync_command(
+/// `user@host` as one shell word, the way every command run on the source
+/// host names the destination.
+fn ssh_login(user: &str, host: &str) -> String {
+    shell_escape(&format!("{}@{}", user, host))
+}
+
+/// The directory a synced source directory ends up as on the destination.
+/// Like rsync, a source without a trailing slash is copied into `dest_path`
+/// under its own name; with one, its contents land in `dest_path` itself.
+pub fn sync_dest_root(source_path: &str, dest_path: &str) -> String {
+    if source_path.ends_with('/') {
+        return dest_path.to_string();
+    }
+    match Path::new(source_path).file_name() {
+        Some(name) => join_child_path(dest_path, &name.to_string_lossy()),
+        None => dest_path.to_string(),
+    }
+}
+
+/// Exclude patterns starting with `/` are anchored at the synced directory.
+/// rsync anchors them at the transfer root, which is the directory's parent
+/// unless the source path ends with a slash.
+fn rsync_exclude(source_path: &str, pattern: &str) -> String {
+    match pattern.strip_prefix('/') {
+        Some(rel) if !source_path.ends_with('/') => {
+            let name = Path::new(source_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            // The name must stay literal next to wildcards in `rel`.
+            let name = if has_wildcards(rel) {
+                escape_wildcards(&name)
+            } else {
+                escape_glob(&name)
+            };
+            format!("/{}/{}", name, rel)
+        }
+        _ => pattern.to_string(),
+    }
+}
+
+/// Build a database sync command that pipes the dump to the destination
+/// over SSH.
+#[allow(clippy::too_many_arguments)]
+pub fn database_sync_command(
+    engine: DatabaseEngine,
     db: &str,
-    src_creds: &str,
+    source: &DbLogin,
     dest_user: &str,
     dest_host: &str,
-    dest_creds: &str,
+    dest_port: u16,
+    dest: &DbLogin,
     compress: bool,
 ) -> String {
+    let relay = database_relay_commands(engine, db, source, dest, compress);
+    format!(
+        "{} | ssh -p {} {} {}",
+        relay.source,
+        dest_port,
+        ssh_login(dest_user, dest_host),
+        shell_escape(&relay.dest)
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Relay transport
+// ---------------------------------------------------------------------------
+
+/// Printed by [`direct_probe_command`] when the source can log in to the
+/// destination.
+pub const DIRECT_PROBE_OK: &str = "SHELLDECK_DIRECT_OK";
+
+/// Check, from the source host, whether it can open the same non-interactive
+/// SSH connection the direct commands rely on.
+pub fn direct_probe_command(dest_user: &str, dest_host: &str, dest_port: u16) -> String {
+    format!(
+        "ssh -o BatchMode=yes -o ConnectTimeout=5 -p {} {} true >/dev/null 2>&1 && echo {}",
+        dest_port,
+        ssh_login(dest_user, dest_host),
+        DIRECT_PROBE_OK
+    )
+}
+
+/// One relayed transfer: `source` writes a stream to stdout on the source
+/// host and `dest` reads it from stdin on the destination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayCommands {
+    pub source: String,
+    pub dest: String,
+    /// Prints `<files> <bytes>` for the stream `source` produces, when the
+    /// size can be known up front (see [`parse_relay_size`]).
+    pub size_probe: Option<String>,
+}
+
+/// A command for one sync step, in the transport chosen for the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncCommand {
+    /// Runs on the source host, which talks to the destination itself.
+    Direct(String),
+    Relay(RelayCommands),
+}
+
+/// Size of the stream `tar -C <dir> -cf - <members>` writes, from the same
+/// arguments so excludes count the same way. Writing to /dev/null, GNU tar
+/// walks the tree without reading file data and `--totals` reports the
+/// bytes it would have written; every listed non-directory is a file.
+fn tar_size_probe(dir: &str, members: &str) -> String {
+    format!(
+        "LC_ALL=C tar -C {} -cvf /dev/null --totals {} 2>&1 | awk '/^Total bytes written: / {{ b = $4; next }} /^tar: / {{ next }} !/\\/$/ {{ n++ }} END {{ print n + 0, b + 0 }}'",
+        shell_escape(dir),
+        members
+    )
+}
+
+/// Parse the `<files> <bytes>` line printed by a relay size probe.
+pub fn parse_relay_size(output: &str) -> Option<(u32, u64)> {
+    let mut parts = output.split_whitespace();
+    let files = parts.next()?.parse().ok()?;
+    let bytes = parts.next()?.parse().ok()?;
+    Some((files, bytes))
+}
+
+/// Relay a directory as an uncompressed tar stream, so the bytes counted on
+/// the way match the size probe. `delete_extra` has no tar equivalent and
+/// is not applied.
+pub fn tar_relay_commands(
+    source_path: &str,
+    dest_path: &str,
+    options: &SyncOptions,
+    excludes: &[String],
+) -> RelayCommands {
+    let mut members = String::new();
+    for pattern in excludes {
+        // Members are named `./<path>`, so anchored patterns map onto that.
+        let mut pattern = match pattern.strip_prefix('/') {
+            Some(rel) => format!("./{}", rel),
+            None => pattern.clone(),
+        };
+        // tar unescapes backslashes even where rsync reads them literally.
+        if !has_wildcards(&pattern) {
+            pattern = pattern.replace('\\', "\\\\");
+        }
+        members.push_str(&format!("--exclude={} ", shell_escape(&pattern)));
+    }
+    members.push('.');
+    let source = format!("tar -C {} -cf - {}", shell_escape(source_path), members);
+
+    let mut dest = format!(
+        "mkdir -p {} && tar -C {} -xf -",
+        shell_escape(dest_path),
+        shell_escape(dest_path)
+    );
+    if options.skip_existing {
+        dest.push_str(" --skip-old-files");
+    }
+    RelayCommands {
+        source,
+        dest,
+        size_probe: Some(tar_size_probe(source_path, &members)),
+    }
+}
+
+/// Relay a single file to the same path on the destination.
+pub fn tar_file_relay_commands(path: &str, options: &SyncOptions) -> RelayCommands {
+    let file = Path::new(path);
+    let dir = file
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| "/".to_string());
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    let mut dest = format!(
+        "mkdir -p {} && tar -C {} -xf -",
+        shell_escape(&dir),
+        shell_escape(&dir)
+    );
+    if options.skip_existing {
+        dest.push_str(" --skip-old-files");
+    }
+    let members = shell_escape(&name);
+    RelayCommands {
+        source: format!("tar -C {} -cf - {}", shell_escape(&dir), members),
+        dest,
+        size_probe: Some(tar_size_probe(&dir, &members)),
+    }
+}
+
+/// Relay a database dump into `mysql` / `psql` on the destination.
+pub fn database_relay_commands(
+    engine: DatabaseEngine,
+    db: &str,
+    source: &DbLogin,
+    dest: &DbLogin,
+    compress: bool,
+) -> RelayCommands {
+    let (dump, restore) = match engine {
+        DatabaseEngine::Mysql => (
+            format!(
+                "{} {} --single-transaction --routines --triggers",
+                source.client(engine, "mysqldump"),
+                shell_escape(db)
+            ),
+            format!("{} {}", dest.client(engine, "mysql"), shell_escape(db)),
+        ),
+        DatabaseEngine::Postgresql => (
+            format!("{} {}", source.client(engine, "pg_dump"), shell_escape(db)),
+            format!("{} {}", dest.client(engine, "psql"), shell_escape(db)),
+        ),
+    };
+    dump_relay_commands(dump, restore, compress)
+}
+
+fn dump_relay_commands(dump: String, restore: String, compress: bool) -> RelayCommands {
     if compress {
-        format!(
-            "mysqldump {} {} --single-transaction --routines --triggers | gzip | ssh {}@{} 'gunzip | mysql {} {}'",
-            src_creds, shell_escape(db), dest_user, dest_host, dest_creds, shell_escape(db)
-        )
+        RelayCommands {
+            source: format!("{} | gzip", dump),
+            dest: format!("gunzip | {}", restore),
+            size_probe: None,
+        }
     } else {
-        format!(
-            "mysqldump {} {} --single-transaction --routines --triggers | ssh {}@{} 'mysql {} {}'",
-            src_creds,
-            shell_escape(db),
+        RelayCommands {
+            source: dump,
+            dest: restore,
+            size_probe: None,
+        }
+    }
+}
+
+/// The commands that sync one item, in order.
+pub fn sync_item_commands(
+    kind: &SyncItemKind,
+    dest_user: &str,
+    dest_host: &str,
+    dest_port: u16,
+    options: &SyncOptions,
+    relay: bool,
+) -> Vec<SyncCommand> {
+    match kind {
+        SyncItemKind::Directory {
+            source_path,
+            dest_path,
+            exclude_patterns,
+        } => vec![if relay {
+            // Extract where rsync would have copied the directory, which is
+            // also where the sync plan compares it.
+            SyncCommand::Relay(tar_relay_commands(
+                source_path,
+                &sync_dest_root(source_path, dest_path),
+                options,
+                exclude_patterns,
+            ))
+        } else {
+            SyncCommand::Direct(rsync_command(
+                source_path,
+                dest_user,
+                dest_host,
+                dest_port,
+                dest_path,
+                options,
+                exclude_patterns,
+            ))
+        }],
+        // Logs in without a password: items with stored passwords get their
+        // commands from `database_commands` once the secret files exist.
+        SyncItemKind::Database {
+            name,
+            engine,
+            source_credentials,
+            dest_credentials,
+        } => database_commands(
+            *engine,
+            name,
+            &DbLogin::new(source_credentials.as_ref(), None),
+            &DbLogin::new(dest_credentials.as_ref(), None),
             dest_user,
             dest_host,
-            dest_creds,
-            shell_escape(db)
-        )
+            dest_port,
+            options,
+            relay,
+        ),
+        SyncItemKind::NginxSite {
+            site,
+            sync_config,
+            sync_root,
+            exclude_patterns,
+        } => {
+            let mut commands = Vec::new();
+            if *sync_root && !site.root.is_empty() {
+                commands.push(site_root_command(
+                    &site.root,
+                    dest_user,
+                    dest_host,
+                    dest_port,
+                    options,
+                    exclude_patterns,
+                    relay,
+                ));
+            }
+            if *sync_config && !site.config_path.is_empty() {
+                commands.push(if relay {
+                    SyncCommand::Relay(tar_file_relay_commands(&site.config_path, options))
+                } else {
+                    SyncCommand::Direct(rsync_command(
+                        &site.config_path,
+                        dest_user,
+                        dest_host,
+                        dest_port,
+                        &site.config_path,
+                        options,
+                        &[],
+                    ))
+                });
+            }
+            commands
+        }
+        // Only the files: the database, config and cache steps need both
+        // sessions and are run by the caller.
+        SyncItemKind::WordPressSite {
+            site,
+            wp_config_path,
+            exclude_patterns,
+            ..
+        } if !site.root.is_empty() => {
+            // The destination keeps its wp-config.php until the database
+            // copy succeeded; the config step rewrites it afterwards.
+            let mut excludes = exclude_patterns.clone();
+            excludes.extend(wp_config_exclude(&site.root, wp_config_path));
+            vec![site_root_command(
+                &site.root, dest_user, dest_host, dest_port, options, &excludes, relay,
+            )]
+        }
+        SyncItemKind::WordPressSite { .. } => Vec::new(),
     }
 }
 
-/// Build a PostgreSQL database sync command (pipe via SSH).
-pub fn pg_sync_command(
-    db: &str,
-    src_creds: &str,
+/// The command that syncs one database with the given logins.
+#[allow(clippy::too_many_arguments)]
+pub fn database_commands(
+    engine: DatabaseEngine,
+    name: &str,
+    source: &DbLogin,
+    dest: &DbLogin,
     dest_user: &str,
     dest_host: &str,
-    dest_creds: &str,
-    compress: bool,
-) -> String {
-    if compress {
-        format!(
-            "pg_dump {} {} | gzip | ssh {}@{} 'gunzip | psql {} {}'",
-            src_creds,
-            shell_escape(db),
+    dest_port: u16,
+    options: &SyncOptions,
+    relay: bool,
+) -> Vec<SyncCommand> {
+    vec![if relay {
+        SyncCommand::Relay(database_relay_commands(
+            engine,
+            name,
+            source,
+            dest,
+            options.compress,
+        ))
+    } else {
+        SyncCommand::Direct(database_sync_command(
+            engine,
+            name,
+            source,
             dest_user,
             dest_host,
-            dest_creds,
-            shell_escape(db)
-        )
+            dest_port,
+            dest,
+            options.compress,
+        ))
+    }]
+}
+
+/// Mirror a site's document root onto the same path on the destination.
+#[allow(clippy::too_many_arguments)]
+fn site_root_command(
+    root: &str,
+    dest_user: &str,
+    dest_host: &str,
+    dest_port: u16,
+    options: &SyncOptions,
+    exclude_patterns: &[String],
+    relay: bool,
+) -> SyncCommand {
+    if relay {
+        SyncCommand::Relay(tar_relay_commands(root, root, options, exclude_patterns))
     } else {
-        format!(
-            "pg_dump {} {} | ssh {}@{} 'psql {} {}'",
-            src_creds,
-            shell_escape(db),
+        // Trailing slash: mirror the root onto itself rather than nesting
+        // it one level down.
+        SyncCommand::Direct(rsync_command(
+            &format!("{}/", root.trim_end_matches('/')),
             dest_user,
             dest_host,
-            dest_creds,
-            shell_escape(db)
-        )
+            dest_port,
+            root,
+            options,
+            exclude_patterns,
+        ))
     }
 }
 
+/// Anchored exclude for `wp-config.php` when it lives inside the site root.
+fn wp_config_exclude(root: &str, wp_config_path: &str) -> Option<String> {
+    let root = format!("{}/", root.trim_end_matches('/'));
+    wp_config_path
+        .strip_prefix(&root)
+        .filter(|rel| !rel.is_empty())
+        .map(|rel| format!("/{}", escape_glob(rel)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::db_credentials::DbCredentials;
 
     #[test]
     fn test_parse_stat_output() {
@@ -730,6 +1396,128 @@ server {
         assert!(!sites[1].ssl);
     }
 
+    #[test]
+    fn apache_vhosts_become_sites_with_ports_and_ssl() {
+        let output = r#"---FILE:/etc/apache2/sites-enabled/shop.conf
+<VirtualHost *:80>
+    ServerName shop.example.com
+    DocumentRoot /var/www/shop/
+    Redirect permanent / https://shop.example.com/
+</VirtualHost>
+<VirtualHost *:443>
+    ServerName shop.example.com:443
+    DocumentRoot "/var/www/shop"
+    SSLEngine on
+</VirtualHost>
+---FILE:/etc/apache2/sites-enabled/000-default.conf
+<VirtualHost *:80>
+    # ServerName www.example.com
+    DocumentRoot /var/www/html
+</VirtualHost>
+---FILE:/etc/httpd/conf.d/blog.conf
+<virtualhost 10.0.0.5:8080>
+    servername blog.example.com
+    documentroot /srv/blog
+</virtualhost>
+"#;
+        let sites = parse_apache_configs(output);
+        assert_eq!(sites.len(), 2, "the nameless default vhost is skipped");
+        assert_eq!(sites[0].server_name, "shop.example.com");
+        assert!(sites[0].ssl, "the HTTPS vhost folds into the redirect");
+        assert_eq!(sites[0].listen_port, 443);
+        assert_eq!(sites[0].root, "/var/www/shop");
+        assert_eq!(sites[1].server_name, "blog.example.com");
+        assert_eq!(sites[1].listen_port, 8080);
+        assert!(!sites[1].ssl);
+    }
+
+    #[test]
+    fn apache_includes_resolve_against_the_server_root() {
+        let debian = "---FILE:/etc/apache2/apache2.conf\n\
+                      Include ports.conf\n\
+                      # IncludeOptional disabled/*.conf\n\
+                      IncludeOptional sites-enabled/*.conf\n";
+        assert_eq!(
+            apache_includes(debian),
+            vec![
+                "/etc/apache2/ports.conf",
+                "/etc/apache2/sites-enabled/*.conf"
+            ]
+        );
+
+        let rhel = "---FILE:/etc/httpd/conf/httpd.conf\n\
+                    IncludeOptional conf.d/*.conf\n\
+                    Include \"/opt/vhosts/*.conf\"\n";
+        assert_eq!(
+            apache_includes(rhel),
+            vec!["/etc/httpd/conf.d/*.conf", "/opt/vhosts/*.conf"]
+        );
+
+        let rooted = "---FILE:/usr/local/apache2/conf/httpd.conf\n\
+                      ServerRoot \"/usr/local/apache2\"\n\
+                      Include conf/extra/httpd-vhosts.conf\n";
+        assert_eq!(
+            apache_includes(rooted),
+            vec!["/usr/local/apache2/conf/extra/httpd-vhosts.conf"]
+        );
+    }
+
+    #[test]
+    fn caddy_site_blocks_become_sites() {
+        let output = r#"---FILE:/etc/caddy/Caddyfile
+{
+    email ops@example.com
+}
+
+(common) {
+    encode gzip
+}
+
+example.com, www.example.com {
+    import common
+    root * /var/www/example/
+    file_server
+    header {
+        X-Frame-Options DENY
+    }
+}
+
+http://legacy.example.com {
+    reverse_proxy localhost:3000
+}
+
+api.example.com:8443 {
+    reverse_proxy {http.request.host}:9000
+}
+
+:8080 {
+    respond "ok"
+}
+
+import sites/*.caddy
+"#;
+        let sites = parse_caddyfiles(output);
+        assert_eq!(sites.len(), 3);
+        assert_eq!(sites[0].server_name, "example.com");
+        assert_eq!(sites[0].root, "/var/www/example");
+        assert!(sites[0].ssl);
+        assert_eq!(sites[0].listen_port, 443);
+        assert_eq!(sites[1].server_name, "legacy.example.com");
+        assert!(!sites[1].ssl);
+        assert_eq!(sites[1].listen_port, 80);
+        assert_eq!(sites[2].server_name, "api.example.com");
+        assert_eq!(sites[2].listen_port, 8443);
+
+        assert_eq!(caddy_imports(output), vec!["/etc/caddy/sites/*.caddy"]);
+    }
+
+    #[test]
+    fn config_files_command_globs_only_the_stars() {
+        let cmd = config_files_command(&["/etc/my sites/*.conf".to_string()]);
+        assert!(cmd.starts_with("for f in '/etc/my sites/'*'.conf'; do"));
+        assert_eq!(config_files_command(&[]), "true");
+    }
+
     #[test]
     fn test_parse_mysql_discovery() {
         let output = "mydb\t104857600\t42\nother_db\t52428800\t15";
@@ -758,11 +1546,13 @@ server {
             delete_extra: false,
             bandwidth_limit: Some(1000),
             skip_existing: false,
+            ..Default::default()
         };
         let cmd = rsync_command(
             "/var/www",
             "deploy",
             "server.com",
+            22,
             "/var/www",
             &opts,
             &["*.log".into()],
@@ -770,7 +1560,7 @@ server {
         assert!(cmd.contains("--dry-run"));
         assert!(cmd.contains("--bwlimit=1000"));
         assert!(cmd.contains("--exclude="));
-        assert!(cmd.contains("deploy@server.com"));
+        assert!(cmd.contains("'deploy@server.com':"));
     }
 
     // SDTEST-016 — filenames with spaces are re-joined intact
@@ -870,8 +1660,9 @@ server {
             delete_extra: true,
             bandwidth_limit: None,
             skip_existing: true,
+            ..Default::default()
         };
-        let cmd = rsync_command("/src", "u", "h", "/dest", &opts, &[]);
+        let cmd = rsync_command("/src", "u", "h", 22, "/dest", &opts, &[]);
         assert!(cmd.contains("--delete"), "delete_extra ⇒ --delete");
         assert!(
             cmd.contains("--ignore-existing"),
@@ -889,14 +1680,23 @@ server {
             delete_extra: false,
             bandwidth_limit: None,
             skip_existing: false,
+            ..Default::default()
         };
-        let cmd = rsync_command("/var/www/my site", "u", "h", "/var/www/dest v2", &opts, &[]);
+        let cmd = rsync_command(
+            "/var/www/my site",
+            "u",
+            "h",
+            22,
+            "/var/www/dest v2",
+            &opts,
+            &[],
+        );
         // Should NOT contain the raw unquoted path (which would break argv).
         assert!(
             !cmd.contains(" /var/www/my site "),
             "raw path with a space must be shell-quoted, got: {cmd}",
         );
-        assert!(cmd.contains("u@h:"));
+        assert!(cmd.contains("'u@h':"));
     }
 
     // SDTEST-1585 — local browsing joins paths with the runner's native
@@ -1005,11 +1805,13 @@ server {
             delete_extra: false,
             bandwidth_limit: None,
             skip_existing: false,
+            ..Default::default()
         };
         let cmd = rsync_command(
             "/src",
             "u",
             "h",
+            22,
             "/dest",
             &opts,
             &["*.log".into(), "node_modules".into(), ".git".into()],
@@ -1020,4 +1822,338 @@ server {
             "one --exclude= per pattern, got {count} in: {cmd}"
         );
     }
+
+    #[test]
+    fn tar_relay_streams_uncompressed_and_honours_skip_existing() {
+        let opts = SyncOptions {
+            skip_existing: true,
+            ..Default::default()
+        };
+        let relay = tar_relay_commands("/var/www", "/srv/www", &opts, &["*.log".into()]);
+        assert_eq!(relay.source, "tar -C '/var/www' -cf - --exclude='*.log' .");
+        assert_eq!(
+            relay.dest,
+            "mkdir -p '/srv/www' && tar -C '/srv/www' -xf - --skip-old-files"
+        );
+        assert!(relay.size_probe.unwrap().starts_with(
+            "LC_ALL=C tar -C '/var/www' -cvf /dev/null --totals --exclude='*.log' . "
+        ));
+    }
+
+    // The relay progress bar divides streamed bytes by the probe's total, so
+    // the probe must count exactly what the excluded stream carries.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn tar_size_probe_matches_the_excluded_stream() {
+        let dir =
+            std::env::temp_dir().join(format!("shelldeck-tar-probe-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("cache")).unwrap();
+        std::fs::write(dir.join("index.php"), "<?php echo 1;\n").unwrap();
+        std::fs::write(dir.join("debug.log"), vec![b'x'; 40_000]).unwrap();
+        std::fs::write(dir.join("cache/page.html"), vec![b'y'; 20_000]).unwrap();
+        let root = dir.to_string_lossy().into_owned();
+        let relay = tar_relay_commands(
+            &root,
+            "/srv/www",
+            &SyncOptions::default(),
+            &["*.log".into(), "/cache".into()],
+        );
+        let sh = |cmd: &str| {
+            let out = std::process::Command::new("sh")
+                .args(["-c", cmd])
+                .output()
+                .unwrap();
+            String::from_utf8_lossy(&out.stdout).trim().to_string()
+        };
+        let probe = sh(relay.size_probe.as_deref().unwrap());
+        let streamed = sh(&format!("{} | wc -c", relay.source));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (files, bytes) = parse_relay_size(&probe).unwrap();
+        assert_eq!(files, 1, "only index.php survives the excludes: {probe}");
+        assert_eq!(bytes.to_string(), streamed);
+    }
+
+    #[test]
+    fn tar_file_relay_extracts_next_to_the_original_path() {
+        let relay =
+            tar_file_relay_commands("/etc/nginx/sites-enabled/app.conf", &SyncOptions::default());
+        assert_eq!(
+            relay.source,
+            "tar -C '/etc/nginx/sites-enabled' -cf - 'app.conf'"
+        );
+        assert_eq!(
+            relay.dest,
+            "mkdir -p '/etc/nginx/sites-enabled' && tar -C '/etc/nginx/sites-enabled' -xf -"
+        );
+    }
+
+    #[test]
+    fn database_relays_split_the_pipe_at_the_ssh_hop() {
+        let source = DbLogin::new(None, Some("/tmp/shelldeck.src".into()));
+        let dest = DbLogin::new(
+            Some(&DbCredentials {
+                user: "app".into(),
+                ..Default::default()
+            }),
+            None,
+        );
+        let relay = database_relay_commands(DatabaseEngine::Mysql, "shop", &source, &dest, true);
+        assert_eq!(
+            relay.source,
+            "mysqldump --defaults-extra-file='/tmp/shelldeck.src' 'shop' --single-transaction --routines --triggers | gzip"
+        );
+        assert_eq!(relay.dest, "gunzip | mysql -u 'app' 'shop'");
+        assert!(relay.size_probe.is_none());
+
+        let none = DbLogin::default();
+        let relay =
+            database_relay_commands(DatabaseEngine::Postgresql, "shop", &none, &none, false);
+        assert_eq!(relay.source, "pg_dump 'shop'");
+        assert_eq!(relay.dest, "psql 'shop'");
+
+        // The destination half runs through the remote shell as one word.
+        let direct = database_sync_command(
+            DatabaseEngine::Postgresql,
+            "shop",
+            &none,
+            "deploy",
+            "dest",
+            22,
+            &none,
+            false,
+        );
+        assert_eq!(
+            direct,
+            r#"pg_dump 'shop' | ssh -p 22 'deploy@dest' 'psql '\''shop'\'''"#
+        );
+    }
+
+    #[test]
+    fn parse_relay_size_reads_files_and_bytes() {
+        assert_eq!(parse_relay_size("12 40960\n"), Some((12, 40960)));
+        assert_eq!(parse_relay_size(""), None);
+        assert_eq!(parse_relay_size("find: permission denied"), None);
+    }
+
+    #[test]
+    fn sync_item_commands_follow_the_transport() {
+        let site = SyncItemKind::NginxSite {
+            site: DiscoveredSite {
+                server_name: "app.example.com".into(),
+                root: "/var/www/app".into(),
+                config_path: "/etc/nginx/sites-enabled/app".into(),
+                listen_port: 443,
+                ssl: true,
+            },
+            sync_config: true,
+            sync_root: true,
+            exclude_patterns: Vec::new(),
+        };
+        let opts = SyncOptions::default();
+
+        let direct = sync_item_commands(&site, "deploy", "dest", 22, &opts, false);
+        assert_eq!(direct.len(), 2);
+        assert!(direct
+            .iter()
+            .all(|c| matches!(c, SyncCommand::Direct(cmd) if cmd.starts_with("rsync "))));
+
+        let relayed = sync_item_commands(&site, "deploy", "dest", 22, &opts, true);
+        assert_eq!(relayed.len(), 2);
+        assert!(relayed
+            .iter()
+            .all(|c| matches!(c, SyncCommand::Relay(r) if r.source.starts_with("tar "))));
+    }
+
+    #[test]
+    fn wordpress_sites_only_emit_the_file_sync() {
+        let site = SyncItemKind::WordPressSite {
+            site: DiscoveredSite {
+                server_name: "blog.example.com".into(),
+                root: "/var/www/blog/".into(),
+                config_path: "/etc/nginx/sites-enabled/blog".into(),
+                listen_port: 443,
+                ssl: true,
+            },
+            wp_config_path: "/var/www/blog/wp-config.php".into(),
+            source_db: Default::default(),
+            dest_db: Default::default(),
+            old_url: "https://blog.example.com".into(),
+            new_url: "https://blog.example.org".into(),
+            exclude_patterns: vec!["wp-content/cache".into()],
+        };
+        let commands =
+            sync_item_commands(&site, "deploy", "dest", 22, &SyncOptions::default(), false);
+        assert_eq!(commands.len(), 1);
+        let SyncCommand::Direct(cmd) = &commands[0] else {
+            panic!("expected a direct command");
+        };
+        assert!(
+            cmd.contains("'/var/www/blog/' 'deploy@dest':'/var/www/blog/'"),
+            "{cmd}"
+        );
+        assert!(cmd.contains("--exclude='wp-content/cache'"));
+    }
+
+    // A failed database copy must leave the destination on its own
+    // database: the file sync never carries the source's wp-config.php,
+    // only the config step after the database does.
+    #[test]
+    fn wordpress_file_sync_leaves_wp_config_to_the_config_step() {
+        let kind = |wp_config_path: &str| SyncItemKind::WordPressSite {
+            site: DiscoveredSite {
+                server_name: "blog.example.com".into(),
+                root: "/var/www/blog".into(),
+                config_path: String::new(),
+                listen_port: 80,
+                ssl: false,
+            },
+            wp_config_path: wp_config_path.into(),
+            source_db: Default::default(),
+            dest_db: Default::default(),
+            old_url: String::new(),
+            new_url: String::new(),
+            exclude_patterns: Vec::new(),
+        };
+        let opts = SyncOptions::default();
+        let site = kind("/var/www/blog/wp-config.php");
+
+        let direct = sync_item_commands(&site, "deploy", "dest", 22, &opts, false);
+        let SyncCommand::Direct(cmd) = &direct[0] else {
+            panic!("expected a direct command");
+        };
+        assert!(cmd.contains("--exclude='/wp-config.php'"), "{cmd}");
+
+        let relayed = sync_item_commands(&site, "deploy", "dest", 22, &opts, true);
+        let SyncCommand::Relay(relay) = &relayed[0] else {
+            panic!("expected a relay");
+        };
+        assert!(relay.source.contains("--exclude='./wp-config.php'"));
+
+        // A config above the root is outside the file sync anyway.
+        let above = sync_item_commands(&kind("/var/www/wp-config.php"), "u", "h", 22, &opts, false);
+        let SyncCommand::Direct(cmd) = &above[0] else {
+            panic!("expected a direct command");
+        };
+        assert!(!cmd.contains("wp-config"));
+    }
+
+    #[test]
+    fn direct_probe_prints_marker_only_on_success() {
+        let cmd = direct_probe_command("deploy", "10.0.0.5", 2222);
+        assert!(cmd.contains("BatchMode=yes"));
+        assert!(cmd.contains("-p 2222 'deploy@10.0.0.5' true"));
+        assert!(cmd.ends_with(&format!("&& echo {}", DIRECT_PROBE_OK)));
+    }
+
+    // Direct transfers reach the destination on the port the probe checked.
+    #[test]
+    fn direct_commands_use_the_destination_port() {
+        let opts = SyncOptions::default();
+        let dir = SyncItemKind::Directory {
+            source_path: "/var/www/site".into(),
+            dest_path: "/srv".into(),
+            exclude_patterns: Vec::new(),
+        };
+        let commands = sync_item_commands(&dir, "deploy", "10.0.0.5", 2222, &opts, false);
+        assert!(matches!(&commands[..], [SyncCommand::Direct(cmd)]
+            if cmd.contains("-e 'ssh -p 2222'") && cmd.ends_with(" 'deploy@10.0.0.5':'/srv'")));
+
+        let db = SyncItemKind::Database {
+            name: "shop".into(),
+            engine: DatabaseEngine::Mysql,
+            source_credentials: None,
+            dest_credentials: None,
+        };
+        let commands = sync_item_commands(&db, "deploy", "10.0.0.5", 2222, &opts, false);
+        assert!(matches!(&commands[..], [SyncCommand::Direct(cmd)]
+            if cmd.contains("| ssh -p 2222 'deploy@10.0.0.5' ")));
+
+        // A user or host that is not a plain word stays one argument.
+        let cmd = database_sync_command(
+            DatabaseEngine::Postgresql,
+            "shop",
+            &DbLogin::new(None, None),
+            "deploy",
+            "dest;id",
+            2222,
+            &DbLogin::new(None, None),
+            false,
+        );
+        assert!(cmd.contains("ssh -p 2222 'deploy@dest;id' "));
+    }
+
+    #[test]
+    fn sync_dest_root_follows_rsync_trailing_slash_rule() {
+        assert_eq!(sync_dest_root("/var/www/site", "/srv"), "/srv/site");
+        assert_eq!(sync_dest_root("/var/www/site/", "/srv"), "/srv");
+    }
+
+    // Both transports write a tree where the sync plan expects it: a
+    // directory under its own name in the destination path, a site root
+    // onto the same path.
+    #[test]
+    fn synced_trees_land_where_they_are_planned() {
+        let dir = SyncItemKind::Directory {
+            source_path: "/var/www/site".into(),
+            dest_path: "/srv".into(),
+            exclude_patterns: Vec::new(),
+        };
+        let opts = SyncOptions::default();
+        let direct = sync_item_commands(&dir, "deploy", "dest", 22, &opts, false);
+        assert!(matches!(&direct[0], SyncCommand::Direct(cmd)
+            if cmd.ends_with("'/var/www/site' 'deploy@dest':'/srv'")));
+        let relayed = sync_item_commands(&dir, "deploy", "dest", 22, &opts, true);
+        assert!(matches!(&relayed[0], SyncCommand::Relay(r)
+            if r.dest.contains("tar -C '/srv/site' -xf -")));
+
+        let site = SyncItemKind::NginxSite {
+            site: DiscoveredSite {
+                server_name: "app.example.com".into(),
+                root: "/var/www/app".into(),
+                config_path: String::new(),
+                listen_port: 80,
+                ssl: false,
+            },
+            sync_config: false,
+            sync_root: true,
+            exclude_patterns: Vec::new(),
+        };
+        let direct = sync_item_commands(&site, "deploy", "dest", 22, &opts, false);
+        assert!(matches!(&direct[0], SyncCommand::Direct(cmd)
+            if cmd.ends_with("'/var/www/app/' 'deploy@dest':'/var/www/app'")));
+    }
+
+    #[test]
+    fn anchored_excludes_are_rooted_at_the_synced_directory() {
+        let opts = SyncOptions {
+            checksum: true,
+            ..Default::default()
+        };
+        let excludes = ["/uploads/big.zip".to_string(), "*.log".to_string()];
+        let cmd = rsync_command("/var/www/site", "u", "h", 22, "/srv", &opts, &excludes);
+        assert!(cmd.contains("--checksum"));
+        assert!(cmd.contains("--exclude='/site/uploads/big.zip'"));
+        assert!(cmd.contains("--exclude='*.log'"));
+
+        let cmd = rsync_command("/var/www/site/", "u", "h", 22, "/srv", &opts, &excludes);
+        assert!(cmd.contains("--exclude='/uploads/big.zip'"));
+
+        let cmd = rsync_command("/var/www/site[1]", "u", "h", 22, "/srv", &opts, &excludes);
+        assert!(cmd.contains("--exclude='/site\\[1]/uploads/big.zip'"));
+
+        let relay = tar_relay_commands("/var/www/site", "/srv/site", &opts, &excludes);
+        assert!(relay.source.contains("--exclude='./uploads/big.zip'"));
+
+        // rsync reads a backslash literally unless the pattern has wildcards;
+        // tar always unescapes, so the relay doubles it.
+        let backslashed = ["/a\\b.png".to_string(), "/logs/*.gz".to_string()];
+        let cmd = rsync_command("/var/www/x\\y", "u", "h", 22, "/srv", &opts, &backslashed);
+        assert!(cmd.contains("--exclude='/x\\y/a\\b.png'"));
+        assert!(cmd.contains("--exclude='/x\\\\y/logs/*.gz'"));
+        let relay = tar_relay_commands("/var/www/site", "/srv/site", &opts, &backslashed);
+        assert!(relay.source.contains("--exclude='./a\\\\b.png'"));
+        assert!(relay.source.contains("--exclude='./logs/*.gz'"));
+    }
 }
@@ -1,8 +1,16 @@
+use std::collections::{BTreeMap, HashMap};
+
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Placeholder stored instead of a secret variable value.
+pub const REDACTED: &str = "••••••";
+
+/// Serializes with secret values masked in `output_log` (see
+/// [`ExecutionRecord::redacted_output`]), so no persisted or exported form
+/// carries them.
+#[derive(Debug, Clone, Deserialize)]
 pub struct ExecutionRecord {
     pub id: Uuid,
     pub script_id: Uuid,
@@ -11,6 +19,44 @@ pub struct ExecutionRecord {
     pub finished_at: Option<DateTime<Utc>>,
     pub exit_code: Option<i32>,
     pub output_log: String,
+    /// Variable values the run was started with. Secret values are replaced
+    /// by [`REDACTED`] before they ever reach this map.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, String>,
+    /// Raw secret values of this run, kept in memory only so they can be
+    /// scrubbed from `output_log` before the record is persisted.
+    #[serde(skip)]
+    secret_values: Vec<String>,
+}
+
+/// Serialized shape of [`ExecutionRecord`].
+#[derive(Serialize)]
+struct StoredRecord<'a> {
+    id: Uuid,
+    script_id: Uuid,
+    connection_id: Option<Uuid>,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    exit_code: Option<i32>,
+    output_log: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    variables: &'a BTreeMap<String, String>,
+}
+
+impl Serialize for ExecutionRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StoredRecord {
+            id: self.id,
+            script_id: self.script_id,
+            connection_id: self.connection_id,
+            started_at: self.started_at,
+            finished_at: self.finished_at,
+            exit_code: self.exit_code,
+            output_log: self.redacted_output(),
+            variables: &self.variables,
+        }
+        .serialize(serializer)
+    }
 }
 
 impl ExecutionRecord {
@@ -23,7 +69,34 @@ impl ExecutionRecord {
             finished_at: None,
             exit_code: None,
             output_log: String::new(),
+            variables: BTreeMap::new(),
+            secret_values: Vec::new(),
+        }
+    }
+
+    /// Attach the resolved variable values, redacting the secret ones.
+    pub fn with_variables(self, values: &HashMap<String, String>) -> Self {
+        self.with_declared_secrets(values, &[])
+    }
+
+    /// Like [`Self::with_variables`], also redacting the variables the script
+    /// declares as secret whatever their name.
+    pub fn with_declared_secrets(
+        mut self,
+        values: &HashMap<String, String>,
+        secret_names: &[String],
+    ) -> Self {
+        for (name, value) in values {
+            if is_secret_variable_name(name) || secret_names.contains(name) {
+                if !value.is_empty() {
+                    self.secret_values.push(value.clone());
+                }
+                self.variables.insert(name.clone(), REDACTED.to_string());
+            } else {
+                self.variables.insert(name.clone(), value.clone());
+            }
         }
+        self
     }
 
     pub fn finish(&mut self, exit_code: i32) {
@@ -51,11 +124,47 @@ impl ExecutionRecord {
     pub fn failed(&self) -> bool {
         self.exit_code.is_some_and(|code| code != 0)
     }
+
+    /// True when at least one variable value was redacted, i.e. a rerun has
+    /// to ask for it again.
+    pub fn has_redacted_variables(&self) -> bool {
+        self.variables.values().any(|value| value == REDACTED)
+    }
+
+    /// Output log with every secret value of this run masked.
+    pub fn redacted_output(&self) -> String {
+        self.secret_values
+            .iter()
+            .fold(self.output_log.clone(), |log, secret| {
+                log.replace(secret.as_str(), REDACTED)
+            })
+    }
+}
+
+/// Heuristic used to keep credentials out of the execution history: a
+/// variable whose name looks like a password, token or key is treated as
+/// secret.
+pub fn is_secret_variable_name(name: &str) -> bool {
+    const MARKERS: &[&str] = &[
+        "password",
+        "passwd",
+        "passphrase",
+        "secret",
+        "token",
+        "api_key",
+        "apikey",
+        "private_key",
+        "credential",
+    ];
+    let name = name.to_ascii_lowercase();
+    MARKERS.iter().any(|marker| name.contains(marker))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ExecutionRecord;
+    use super::{is_secret_variable_name, ExecutionRecord, REDACTED};
+    use crate::models::script::{Script, ScriptTarget, ScriptVariable, VariableKind};
+    use std::collections::HashMap;
     use uuid::Uuid;
 
     // SDTEST-044 — new → running, no exit code, empty log, no duration.
@@ -119,4 +228,80 @@ mod tests {
         assert!(local.connection_id.is_none());
         assert_eq!(remote.connection_id, Some(conn));
     }
+
+    #[test]
+    fn secret_variables_are_redacted_in_values_and_output() {
+        let values = HashMap::from([
+            ("host".to_string(), "db1".to_string()),
+            ("db_password".to_string(), "hunter2".to_string()),
+        ]);
+        let mut r = ExecutionRecord::new(Uuid::new_v4(), None).with_variables(&values);
+        assert_eq!(r.variables["host"], "db1");
+        assert_eq!(r.variables["db_password"], REDACTED);
+        assert!(r.has_redacted_variables());
+
+        r.append_output("connecting with hunter2\n");
+        assert_eq!(r.redacted_output(), format!("connecting with {REDACTED}\n"));
+
+        // The raw secret never reaches the serialized form.
+        let json = serde_json::to_string(&r).unwrap();
+        assert!(!json.contains("hunter2"));
+        let back: ExecutionRecord = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.output_log, format!("connecting with {REDACTED}\n"));
+        assert_eq!(back.variables["db_password"], REDACTED);
+    }
+
+    #[test]
+    fn declared_secrets_are_redacted_whatever_their_name() {
+        let mut script = Script::new(
+            "deploy".into(),
+            "deploy --key {{deploy_key}}".into(),
+            ScriptTarget::Local,
+        );
+        script.variables.push(ScriptVariable {
+            name: "deploy_key".to_string(),
+            kind: VariableKind::Secret,
+            ..Default::default()
+        });
+        let values = HashMap::from([("deploy_key".to_string(), "s3cr3t".to_string())]);
+        let mut r = ExecutionRecord::new(script.id, None)
+            .with_declared_secrets(&values, &script.secret_variable_names());
+        assert_eq!(r.variables["deploy_key"], REDACTED);
+        assert!(r.has_redacted_variables());
+
+        r.append_output("key s3cr3t loaded\n");
+        assert_eq!(r.redacted_output(), format!("key {REDACTED} loaded\n"));
+        assert!(!serde_json::to_string(&r).unwrap().contains("s3cr3t"));
+    }
+
+    #[test]
+    fn secret_name_heuristic() {
+        for name in [
+            "PASSWORD",
+            "db_passwd",
+            "api_key",
+            "GITHUB_TOKEN",
+            "client_secret",
+        ] {
+            assert!(is_secret_variable_name(name), "{name}");
+        }
+        for name in ["host", "path", "keyword", "user"] {
+            assert!(!is_secret_variable_name(name), "{name}");
+        }
+    }
+
+    #[test]
+    fn records_without_variables_still_deserialize() {
+        let raw = r#"{
+            "id":"00000000-0000-0000-0000-000000000001",
+            "script_id":"00000000-0000-0000-0000-000000000002",
+            "connection_id":null,
+            "started_at":"2026-07-15T10:00:00Z",
+            "finished_at":null,
+            "exit_code":null,
+            "output_log":""
+        }"#;
+        let r: ExecutionRecord = serde_json::from_str(raw).expect("old record");
+        assert!(r.variables.is_empty());
+    }
 }
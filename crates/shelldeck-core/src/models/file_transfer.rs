@@ -0,0 +1,1327 @@
+//! File manager of the server sync panels: the queue of copies and moves
+//! between panels, conflict choices, and the shell commands behind each
+//! transfer and the rename / delete / new folder / chmod actions.
+//!
+//! No SSH or async dependencies — fully unit-testable.
+
+use std::path::{Path, PathBuf};
+
+use uuid::Uuid;
+
+use crate::util::shell_escape;
+
+/// Suffix of the file a transfer writes before it is moved into place. A
+/// paused transfer resumes by appending to it.
+pub const PART_SUFFIX: &str = ".shelldeck-part";
+
+/// Candidates tried for "keep both" before giving up.
+const KEEP_BOTH_ATTEMPTS: usize = 99;
+
+/// A path on a server, or on this computer when `connection_id` is `None`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLocation {
+    pub connection_id: Option<Uuid>,
+    pub path: String,
+}
+
+impl FileLocation {
+    pub fn local(path: impl Into<String>) -> Self {
+        Self {
+            connection_id: None,
+            path: path.into(),
+        }
+    }
+
+    pub fn remote(connection_id: Uuid, path: impl Into<String>) -> Self {
+        Self {
+            connection_id: Some(connection_id),
+            path: path.into(),
+        }
+    }
+
+    pub fn is_local(&self) -> bool {
+        self.connection_id.is_none()
+    }
+
+    /// Whether both paths live on the same machine, so a transfer between
+    /// them is a plain `cp` / `mv` there.
+    pub fn same_host(&self, other: &FileLocation) -> bool {
+        self.connection_id == other.connection_id
+    }
+
+    /// Last component of the path.
+    pub fn file_name(&self) -> String {
+        if self.is_local() {
+            Path::new(&self.path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.path.clone())
+        } else {
+            let trimmed = self.path.trim_end_matches('/');
+            trimmed.rsplit('/').next().unwrap_or(trimmed).to_string()
+        }
+    }
+
+    /// `rel` (with `/` separators) below this path.
+    pub fn join(&self, rel: &str) -> String {
+        if self.is_local() {
+            let mut path = PathBuf::from(&self.path);
+            for part in rel.split('/').filter(|p| !p.is_empty()) {
+                path.push(part);
+            }
+            path.to_string_lossy().into_owned()
+        } else {
+            join_remote(&self.path, rel)
+        }
+    }
+
+    /// The same machine, another path.
+    pub fn with_path(&self, path: impl Into<String>) -> Self {
+        Self {
+            connection_id: self.connection_id,
+            path: path.into(),
+        }
+    }
+}
+
+/// `name` inside the remote directory `dir`.
+pub fn join_remote(dir: &str, name: &str) -> String {
+    format!(
+        "{}/{}",
+        dir.trim_end_matches('/'),
+        name.trim_start_matches('/')
+    )
+}
+
+/// Directory holding the remote path `path`.
+pub fn remote_parent(path: &str) -> String {
+    match path.trim_end_matches('/').rsplit_once('/') {
+        Some(("", _)) | None => "/".to_string(),
+        Some((parent, _)) => parent.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferKind {
+    Copy,
+    Move,
+}
+
+/// What to do when the destination already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictChoice {
+    Overwrite,
+    Skip,
+    /// Write next to it as "name (1).ext".
+    KeepBoth,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferStatus {
+    Queued,
+    /// Being transferred, or for a directory, its files are.
+    Running,
+    Paused,
+    /// The destination exists and the user has not chosen what to do yet.
+    Conflict,
+    Done,
+    Skipped,
+    Failed(String),
+}
+
+impl TransferStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            TransferStatus::Done | TransferStatus::Skipped | TransferStatus::Failed(_)
+        )
+    }
+}
+
+/// One file or directory in the transfer queue. A directory is expanded
+/// into one item per file once it starts; those items point back to it
+/// through `parent`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferItem {
+    pub id: Uuid,
+    pub parent: Option<Uuid>,
+    pub kind: TransferKind,
+    pub source: FileLocation,
+    /// Full path the item is written to, not the directory it goes into.
+    pub dest: FileLocation,
+    pub is_dir: bool,
+    pub size_bytes: u64,
+    pub transferred: u64,
+    pub status: TransferStatus,
+    /// How a conflict at the destination is resolved; `None` asks.
+    pub conflict: Option<ConflictChoice>,
+}
+
+impl TransferItem {
+    pub fn new(
+        kind: TransferKind,
+        source: FileLocation,
+        dest: FileLocation,
+        is_dir: bool,
+        size_bytes: u64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            parent: None,
+            kind,
+            source,
+            dest,
+            is_dir,
+            size_bytes,
+            transferred: 0,
+            status: TransferStatus::Queued,
+            conflict: None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.source.file_name()
+    }
+
+    /// Where the bytes go until the file is complete.
+    pub fn part_path(&self) -> String {
+        format!("{}{}", self.dest.path, PART_SUFFIX)
+    }
+}
+
+/// A file or directory below a directory being transferred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeEntry {
+    /// Path relative to the directory, with `/` separators.
+    pub rel_path: String,
+    pub is_dir: bool,
+    pub size: u64,
+}
+
+/// Copies and moves of the file manager, run one at a time in order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferQueue {
+    pub items: Vec<TransferItem>,
+    /// Choice applied to every conflict after "apply to all".
+    pub conflict_default: Option<ConflictChoice>,
+}
+
+impl TransferQueue {
+    pub fn enqueue(&mut self, item: TransferItem) {
+        self.items.push(item);
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<&TransferItem> {
+        self.items.iter().find(|i| i.id == id)
+    }
+
+    fn get_mut(&mut self, id: Uuid) -> Option<&mut TransferItem> {
+        self.items.iter_mut().find(|i| i.id == id)
+    }
+
+    pub fn children(&self, id: Uuid) -> impl Iterator<Item = &TransferItem> {
+        self.items.iter().filter(move |i| i.parent == Some(id))
+    }
+
+    fn has_children(&self, id: Uuid) -> bool {
+        self.children(id).next().is_some()
+    }
+
+    /// Whether anything is waiting or running.
+    pub fn is_busy(&self) -> bool {
+        self.items
+            .iter()
+            .any(|i| matches!(i.status, TransferStatus::Queued | TransferStatus::Running))
+    }
+
+    /// Bytes done and total of an item, summed over its files for a
+    /// directory.
+    pub fn totals(&self, id: Uuid) -> (u64, u64) {
+        if self.has_children(id) {
+            self.children(id).fold((0, 0), |(done, total), c| {
+                (done + c.transferred, total + c.size_bytes)
+            })
+        } else {
+            self.get(id)
+                .map(|i| (i.transferred, i.size_bytes))
+                .unwrap_or_default()
+        }
+    }
+
+    /// Mark the first queued item running and return it, with the
+    /// "apply to all" choice filled in when it has none of its own.
+    pub fn start_next(&mut self) -> Option<TransferItem> {
+        let default = self.conflict_default;
+        let item = self
+            .items
+            .iter_mut()
+            .find(|i| i.status == TransferStatus::Queued)?;
+        item.status = TransferStatus::Running;
+        if item.conflict.is_none() {
+            item.conflict = default;
+        }
+        Some(item.clone())
+    }
+
+    pub fn set_progress(&mut self, id: Uuid, transferred: u64) {
+        if let Some(item) = self.get_mut(id) {
+            item.transferred = transferred;
+        }
+    }
+
+    /// The destination turned out taken and "keep both" picked `path`.
+    pub fn retarget(&mut self, id: Uuid, path: String) {
+        if let Some(item) = self.get_mut(id) {
+            item.dest.path = path;
+        }
+    }
+
+    /// Mark `id` as ended with `status`. Returns the directory it belongs
+    /// to when that was the directory's last file.
+    pub fn finish(&mut self, id: Uuid, status: TransferStatus) -> Option<TransferItem> {
+        let item = self.get_mut(id)?;
+        if item.status == TransferStatus::Paused {
+            // Paused while finishing; the pause wins for partial files only.
+            if !matches!(status, TransferStatus::Done | TransferStatus::Skipped) {
+                return None;
+            }
+        }
+        if status == TransferStatus::Done {
+            item.transferred = item.transferred.max(item.size_bytes);
+        }
+        item.status = status;
+        let parent = item.parent?;
+        self.settle(parent)
+    }
+
+    /// Finish a directory whose files have all ended.
+    fn settle(&mut self, parent: Uuid) -> Option<TransferItem> {
+        let mut failure = None;
+        for child in self.children(parent) {
+            match &child.status {
+                TransferStatus::Done | TransferStatus::Skipped => {}
+                TransferStatus::Failed(error) => {
+                    failure.get_or_insert_with(|| error.clone());
+                }
+                _ => return None,
+            }
+        }
+        let item = self.get_mut(parent)?;
+        item.status = match failure {
+            Some(error) => TransferStatus::Failed(error),
+            None => TransferStatus::Done,
+        };
+        Some(item.clone())
+    }
+
+    /// The destination of `id` exists; wait for the user to choose.
+    pub fn set_conflict(&mut self, id: Uuid) {
+        if let Some(item) = self.get_mut(id) {
+            item.status = TransferStatus::Conflict;
+        }
+    }
+
+    /// Queue `id` again with `choice`. With `apply_to_all`, every other
+    /// waiting conflict and every later one gets the same choice.
+    pub fn resolve(&mut self, id: Uuid, choice: ConflictChoice, apply_to_all: bool) {
+        if apply_to_all {
+            self.conflict_default = Some(choice);
+        }
+        for item in &mut self.items {
+            if item.status == TransferStatus::Conflict && (item.id == id || apply_to_all) {
+                item.conflict = Some(choice);
+                item.status = TransferStatus::Queued;
+            }
+        }
+    }
+
+    /// Replace a directory item by its files, which are queued right after
+    /// it. Returns the directory when it had no files and is done already.
+    pub fn expand(&mut self, id: Uuid, entries: &[TreeEntry]) -> Option<TransferItem> {
+        let index = self.items.iter().position(|i| i.id == id)?;
+        let dir = self.items[index].clone();
+        let children: Vec<TransferItem> = entries
+            .iter()
+            .filter(|e| !e.is_dir)
+            .map(|e| TransferItem {
+                parent: Some(dir.id),
+                // The directory's conflict is settled; files inside it are
+                // new or merged over.
+                conflict: Some(ConflictChoice::Overwrite),
+                ..TransferItem::new(
+                    dir.kind,
+                    dir.source.with_path(dir.source.join(&e.rel_path)),
+                    dir.dest.with_path(dir.dest.join(&e.rel_path)),
+                    false,
+                    e.size,
+                )
+            })
+            .collect();
+        let item = &mut self.items[index];
+        item.size_bytes = children.iter().map(|c| c.size_bytes).sum();
+        if children.is_empty() {
+            item.status = TransferStatus::Done;
+            return Some(item.clone());
+        }
+        self.items.splice(index + 1..index + 1, children);
+        None
+    }
+
+    /// Pause `id`, or the files of a directory that have not ended.
+    pub fn pause(&mut self, id: Uuid) {
+        self.update_family(id, |item| {
+            if matches!(
+                item.status,
+                TransferStatus::Queued | TransferStatus::Running
+            ) {
+                item.status = TransferStatus::Paused;
+            }
+        });
+    }
+
+    pub fn resume(&mut self, id: Uuid) {
+        self.update_family(id, |item| {
+            if item.status == TransferStatus::Paused {
+                item.status = TransferStatus::Queued;
+            }
+        });
+    }
+
+    /// Queue a failed item again; for a directory, its failed files.
+    pub fn retry(&mut self, id: Uuid) {
+        self.update_family(id, |item| {
+            if matches!(item.status, TransferStatus::Failed(_)) {
+                item.status = TransferStatus::Queued;
+            }
+        });
+    }
+
+    /// Apply `f` to `id`, or when it is an expanded directory, to its
+    /// files. The directory then runs while any file waits or runs, and is
+    /// paused while the rest of them are.
+    fn update_family(&mut self, id: Uuid, f: impl Fn(&mut TransferItem)) {
+        if !self.has_children(id) {
+            if let Some(item) = self.get_mut(id) {
+                f(item);
+            }
+            return;
+        }
+        let (mut active, mut paused) = (false, false);
+        for item in self.items.iter_mut().filter(|i| i.parent == Some(id)) {
+            f(item);
+            match item.status {
+                TransferStatus::Queued | TransferStatus::Running => active = true,
+                TransferStatus::Paused => paused = true,
+                _ => {}
+            }
+        }
+        if let Some(item) = self.get_mut(id) {
+            if active {
+                item.status = TransferStatus::Running;
+            } else if paused {
+                item.status = TransferStatus::Paused;
+            }
+        }
+    }
+
+    /// Drop `id` and the files of it. Returns what was removed.
+    pub fn remove(&mut self, id: Uuid) -> Vec<TransferItem> {
+        let (removed, kept) = std::mem::take(&mut self.items)
+            .into_iter()
+            .partition(|i| i.id == id || i.parent == Some(id));
+        self.items = kept;
+        removed
+    }
+
+    /// Drop everything that has ended.
+    pub fn clear_finished(&mut self) {
+        let finished: Vec<Uuid> = self
+            .items
+            .iter()
+            .filter(|i| i.parent.is_none() && i.status.is_finished())
+            .map(|i| i.id)
+            .collect();
+        self.items.retain(|i| {
+            !finished.contains(&i.id) && !i.parent.is_some_and(|p| finished.contains(&p))
+        });
+    }
+}
+
+/// `path` renamed to "name (n).ext" in the same directory. Directories and
+/// dot files keep the whole name before the counter.
+pub fn keep_both_path(path: &str, is_dir: bool, n: usize) -> String {
+    let split = path.rfind(['/', '\\']).map(|i| i + 1).unwrap_or(0);
+    let (dir, name) = path.split_at(split);
+    let stem_len = if is_dir {
+        name.len()
+    } else {
+        name.char_indices()
+            .skip_while(|(_, c)| *c == '.')
+            .find(|(_, c)| *c == '.')
+            .map(|(i, _)| i)
+            .unwrap_or(name.len())
+    };
+    let (stem, ext) = name.split_at(stem_len);
+    format!("{}{} ({}){}", dir, stem, n, ext)
+}
+
+/// Every "keep both" name of `path`, in order of preference.
+pub fn keep_both_candidates(path: &str, is_dir: bool) -> Vec<String> {
+    (1..=KEEP_BOTH_ATTEMPTS)
+        .map(|n| keep_both_path(path, is_dir, n))
+        .collect()
+}
+
+/// Exit status 0 when `path` exists.
+pub fn exists_command(path: &str) -> String {
+    let path = shell_escape(path);
+    format!("[ -e {0} ] || [ -L {0} ]", path)
+}
+
+/// Print the first of `candidates` that does not exist yet.
+pub fn first_free_command(candidates: &[String]) -> String {
+    let list: Vec<String> = candidates.iter().map(|c| shell_escape(c)).collect();
+    format!(
+        "for p in {}; do [ -e \"$p\" ] || {{ printf '%s\\n' \"$p\"; exit 0; }}; done; exit 1",
+        list.join(" ")
+    )
+}
+
+/// List everything below `dir`, one `type\tsize\trelative path` line each
+/// (`d` for directories, `f` for regular files). Uses GNU find when it can
+/// and a POSIX loop otherwise.
+pub fn tree_command(dir: &str) -> String {
+    format!(
+        "cd {} && {{ find . -mindepth 1 -printf '%y\\t%s\\t%P\\n' 2>/dev/null || \
+         find . -mindepth 1 | while IFS= read -r p; do \
+         if [ -d \"$p\" ]; then printf 'd\\t0\\t%s\\n' \"${{p#./}}\"; \
+         elif [ -f \"$p\" ]; then printf 'f\\t%s\\t%s\\n' \"$(wc -c < \"$p\" | tr -d ' ')\" \"${{p#./}}\"; fi; \
+         done; }}",
+        shell_escape(dir)
+    )
+}
+
+/// Parse [`tree_command`] output. Links and special files are left out.
+pub fn parse_tree_listing(output: &str) -> Vec<TreeEntry> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let kind = parts.next()?;
+            let size = parts.next()?.trim().parse().unwrap_or(0);
+            let rel_path = parts.next()?.to_string();
+            if rel_path.is_empty() {
+                return None;
+            }
+            match kind {
+                "d" => Some(TreeEntry {
+                    rel_path,
+                    is_dir: true,
+                    size: 0,
+                }),
+                "f" => Some(TreeEntry {
+                    rel_path,
+                    is_dir: false,
+                    size,
+                }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Everything below the local directory `dir`, parents before children.
+pub fn local_tree(dir: &Path) -> std::io::Result<Vec<TreeEntry>> {
+    fn walk(base: &Path, rel: &str, out: &mut Vec<TreeEntry>) -> std::io::Result<()> {
+        let mut entries: Vec<_> = std::fs::read_dir(base.join(rel))?.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let rel_path = if rel.is_empty() {
+                name
+            } else {
+                format!("{}/{}", rel, name)
+            };
+            // symlink_metadata: links are left out like on servers.
+            let meta = entry.path().symlink_metadata()?;
+            if meta.is_dir() {
+                out.push(TreeEntry {
+                    rel_path: rel_path.clone(),
+                    is_dir: true,
+                    size: 0,
+                });
+                walk(base, &rel_path, out)?;
+            } else if meta.is_file() {
+                out.push(TreeEntry {
+                    rel_path,
+                    is_dir: false,
+                    size: meta.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+    let mut out = Vec::new();
+    walk(dir, "", &mut out)?;
+    Ok(out)
+}
+
+/// Create `dirs` and their parents.
+pub fn mkdirs_command(dirs: &[String]) -> String {
+    let list: Vec<String> = dirs.iter().map(|d| shell_escape(d)).collect();
+    format!("mkdir -p -- {}", list.join(" "))
+}
+
+/// Print `path` from byte `offset` on.
+pub fn read_command(path: &str, offset: u64) -> String {
+    if offset == 0 {
+        format!("cat -- {}", shell_escape(path))
+    } else {
+        format!("tail -c +{} -- {}", offset + 1, shell_escape(path))
+    }
+}
+
+/// Write stdin to `part`, appending when resuming.
+pub fn write_part_command(part: &str, append: bool) -> String {
+    format!(
+        "mkdir -p -- {} && cat {} {}",
+        shell_escape(&remote_parent(part)),
+        if append { ">>" } else { ">" },
+        shell_escape(part)
+    )
+}
+
+/// Size of `part`, 0 when it does not exist.
+pub fn part_size_command(part: &str) -> String {
+    let part = shell_escape(part);
+    format!("if [ -f {0} ]; then wc -c < {0}; else echo 0; fi", part)
+}
+
+/// Move a complete `part` over `path`.
+pub fn commit_part_command(part: &str, path: &str) -> String {
+    format!("mv -f -- {} {}", shell_escape(part), shell_escape(path))
+}
+
+/// Copy or move `source` to `dest` on one machine. A directory is merged
+/// into an existing one.
+pub fn same_host_command(kind: TransferKind, source: &str, dest: &str, is_dir: bool) -> String {
+    let (s, d) = (shell_escape(source), shell_escape(dest));
+    match (kind, is_dir) {
+        (TransferKind::Copy, false) => format!("cp -p -- {} {}", s, d),
+        (TransferKind::Copy, true) => {
+            format!("mkdir -p -- {1} && cp -pR -- {0}/. {1}/", s, d)
+        }
+        (TransferKind::Move, false) => format!("mv -f -- {} {}", s, d),
+        (TransferKind::Move, true) => format!(
+            "if [ -e {1} ]; then cp -pR -- {0}/. {1}/ && rm -rf -- {0}; else mv -- {0} {1}; fi",
+            s, d
+        ),
+    }
+}
+
+/// Remove the directories left under `dir` once its files were moved
+/// out. Directories that still hold something stay.
+pub fn remove_empty_dirs_command(dir: &str) -> String {
+    format!(
+        "find {} -depth -type d -exec rmdir {{}} + 2>/dev/null; true",
+        shell_escape(dir)
+    )
+}
+
+/// Local counterpart of [`remove_empty_dirs_command`].
+pub fn remove_empty_local_dirs(dir: &Path) {
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                remove_empty_local_dirs(&entry.path());
+            }
+        }
+    }
+    let _ = std::fs::remove_dir(dir);
+}
+
+/// Local counterpart of [`same_host_command`].
+pub fn same_host_local(
+    kind: TransferKind,
+    source: &Path,
+    dest: &Path,
+    is_dir: bool,
+) -> std::io::Result<()> {
+    if kind == TransferKind::Move && !dest.exists() && std::fs::rename(source, dest).is_ok() {
+        return Ok(());
+    }
+    if is_dir {
+        std::fs::create_dir_all(dest)?;
+        for entry in local_tree(source)? {
+            let to = join_local(dest, &entry.rel_path);
+            if entry.is_dir {
+                std::fs::create_dir_all(&to)?;
+            } else {
+                std::fs::copy(join_local(source, &entry.rel_path), &to)?;
+            }
+        }
+        if kind == TransferKind::Move {
+            std::fs::remove_dir_all(source)?;
+        }
+    } else {
+        std::fs::copy(source, dest)?;
+        if kind == TransferKind::Move {
+            std::fs::remove_file(source)?;
+        }
+    }
+    Ok(())
+}
+
+fn join_local(base: &Path, rel: &str) -> PathBuf {
+    rel.split('/')
+        .fold(base.to_path_buf(), |p, part| p.join(part))
+}
+
+/// Whether `mode` is something chmod takes: octal (`644`, `2775`) or
+/// symbolic clauses (`u+x,go-w`).
+pub fn valid_chmod_mode(mode: &str) -> bool {
+    let mode = mode.trim();
+    if mode.is_empty() {
+        return false;
+    }
+    if mode.chars().all(|c| c.is_ascii_digit()) {
+        return mode.len() <= 4 && mode.chars().all(|c| c < '8');
+    }
+    mode.split(',').all(|clause| {
+        let ops = clause.trim_start_matches(['u', 'g', 'o', 'a']);
+        let mut chars = ops.chars().peekable();
+        if chars.peek().is_none() {
+            return false;
+        }
+        while let Some(op) = chars.next() {
+            if !matches!(op, '+' | '-' | '=') {
+                return false;
+            }
+            while let Some(c) = chars.peek() {
+                if matches!(c, '+' | '-' | '=') {
+                    break;
+                }
+                if !matches!(c, 'r' | 'w' | 'x' | 'X' | 's' | 't' | 'u' | 'g' | 'o') {
+                    return false;
+                }
+                chars.next();
+            }
+        }
+        true
+    })
+}
+
+/// Octal form of an `ls`-style permission string (`-rwxr-x--- → 750`),
+/// with the setuid / setgid / sticky digit in front when any is set.
+pub fn permissions_to_octal(permissions: &str) -> Option<String> {
+    let bits: Vec<char> = permissions.chars().skip(1).take(9).collect();
+    if bits.len() != 9 {
+        return None;
+    }
+    let mut special = 0;
+    let mut digits = String::new();
+    for (i, triple) in bits.chunks(3).enumerate() {
+        let mut digit = 0;
+        if triple[0] == 'r' {
+            digit |= 4;
+        }
+        if triple[1] == 'w' {
+            digit |= 2;
+        }
+        match triple[2] {
+            'x' => digit |= 1,
+            's' | 't' => {
+                digit |= 1;
+                special |= 4 >> i;
+            }
+            'S' | 'T' => special |= 4 >> i,
+            _ => {}
+        }
+        digits.push(char::from(b'0' + digit));
+    }
+    if special > 0 {
+        digits.insert(0, char::from(b'0' + special));
+    }
+    Some(digits)
+}
+
+/// Rename, delete, new folder and chmod actions of a file panel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileOperation {
+    Rename {
+        from: String,
+        to: String,
+    },
+    Delete {
+        paths: Vec<String>,
+    },
+    Mkdir {
+        path: String,
+    },
+    Chmod {
+        paths: Vec<String>,
+        mode: String,
+        recursive: bool,
+    },
+}
+
+impl FileOperation {
+    /// The operation as a command for a server.
+    pub fn command(&self) -> String {
+        let list = |paths: &[String]| {
+            paths
+                .iter()
+                .map(|p| shell_escape(p))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        match self {
+            FileOperation::Rename { from, to } => {
+                format!(
+                    "if [ -e {1} ]; then echo 'Target already exists' >&2; exit 1; fi; mv -- {0} {1}",
+                    shell_escape(from),
+                    shell_escape(to)
+                )
+            }
+            FileOperation::Delete { paths } => format!("rm -rf -- {}", list(paths)),
+            FileOperation::Mkdir { path } => format!("mkdir -- {}", shell_escape(path)),
+            FileOperation::Chmod {
+                paths,
+                mode,
+                recursive,
+            } => format!(
+                "chmod {}{} -- {}",
+                if *recursive { "-R " } else { "" },
+                shell_escape(mode.trim()),
+                list(paths)
+            ),
+        }
+    }
+
+    /// Run the operation on this computer.
+    pub fn apply_local(&self) -> std::io::Result<()> {
+        use std::io::{Error, ErrorKind};
+        match self {
+            FileOperation::Rename { from, to } => {
+                if Path::new(to).exists() {
+                    return Err(Error::new(
+                        ErrorKind::AlreadyExists,
+                        "Target already exists",
+                    ));
+                }
+                std::fs::rename(from, to)
+            }
+            FileOperation::Delete { paths } => {
+                for path in paths {
+                    let path = Path::new(path);
+                    if path.symlink_metadata()?.is_dir() {
+                        std::fs::remove_dir_all(path)?;
+                    } else {
+                        std::fs::remove_file(path)?;
+                    }
+                }
+                Ok(())
+            }
+            FileOperation::Mkdir { path } => std::fs::create_dir(path),
+            FileOperation::Chmod {
+                paths,
+                mode,
+                recursive,
+            } => chmod_local(paths, mode, *recursive),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn chmod_local(paths: &[String], mode: &str, recursive: bool) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = u32::from_str_radix(mode.trim(), 8).map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Only octal modes can be set on this computer",
+        )
+    })?;
+    for path in paths {
+        let path = Path::new(path);
+        // Walk the tree before changing it and set parents after their
+        // children, so a mode without search permission such as 644 does
+        // not lock the walk out of a directory it has just changed.
+        if recursive && path.is_dir() {
+            for entry in local_tree(path)?.iter().rev() {
+                std::fs::set_permissions(
+                    join_local(path, &entry.rel_path),
+                    std::fs::Permissions::from_mode(mode),
+                )?;
+            }
+        }
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn chmod_local(_paths: &[String], _mode: &str, _recursive: bool) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "Permissions cannot be changed on this computer",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `script` the way a server would.
+    #[cfg(unix)]
+    fn sh(script: &str) -> std::process::Output {
+        std::process::Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .output()
+            .unwrap()
+    }
+
+    fn temp_dir() -> PathBuf {
+        static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "shelldeck-transfer-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn file(src: &str, dest: &str, size: u64) -> TransferItem {
+        TransferItem::new(
+            TransferKind::Copy,
+            FileLocation::local(src),
+            FileLocation::remote(Uuid::nil(), dest),
+            false,
+            size,
+        )
+    }
+
+    #[test]
+    fn remote_paths_join_and_split() {
+        assert_eq!(join_remote("/", "etc"), "/etc");
+        assert_eq!(join_remote("/var/www/", "a/b"), "/var/www/a/b");
+        assert_eq!(remote_parent("/var/www/index.php"), "/var/www");
+        assert_eq!(remote_parent("/etc"), "/");
+        assert_eq!(remote_parent("name"), "/");
+        let loc = FileLocation::remote(Uuid::nil(), "/srv/app/");
+        assert_eq!(loc.file_name(), "app");
+        assert_eq!(loc.join("logs/a.log"), "/srv/app/logs/a.log");
+    }
+
+    #[test]
+    fn keep_both_names() {
+        assert_eq!(
+            keep_both_path("/a/report.pdf", false, 1),
+            "/a/report (1).pdf"
+        );
+        assert_eq!(
+            keep_both_path("/a/site.tar.gz", false, 2),
+            "/a/site (2).tar.gz"
+        );
+        assert_eq!(keep_both_path("/a/.bashrc", false, 1), "/a/.bashrc (1)");
+        assert_eq!(keep_both_path("/a/v1.2", true, 3), "/a/v1.2 (3)");
+        assert_eq!(keep_both_candidates("x", false).len(), KEEP_BOTH_ATTEMPTS);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn free_name_and_exists_commands() {
+        let dir = temp_dir();
+        let taken = dir.join("a.txt");
+        std::fs::write(&taken, b"x").unwrap();
+        std::fs::write(dir.join("a (1).txt"), b"x").unwrap();
+        let candidates = keep_both_candidates(&taken.to_string_lossy(), false);
+        let out = sh(&first_free_command(&candidates));
+        assert!(out.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&out.stdout).trim(),
+            dir.join("a (2).txt").to_string_lossy()
+        );
+        assert!(sh(&exists_command(&taken.to_string_lossy()))
+            .status
+            .success());
+        assert!(!sh(&exists_command(&dir.join("nope").to_string_lossy()))
+            .status
+            .success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tree_listing_matches_local_walk() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(dir.join("sub dir/empty")).unwrap();
+        std::fs::write(dir.join("top.txt"), b"hello").unwrap();
+        std::fs::write(dir.join("sub dir/it's.log"), b"abc").unwrap();
+
+        let mut remote = parse_tree_listing(&String::from_utf8_lossy(
+            &sh(&tree_command(&dir.to_string_lossy())).stdout,
+        ));
+        remote.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+        let mut local = local_tree(&dir).unwrap();
+        local.sort_by(|a, b| a.rel_path.cmp(&b.rel_path));
+        assert_eq!(remote, local);
+        assert_eq!(
+            local,
+            vec![
+                TreeEntry {
+                    rel_path: "sub dir".into(),
+                    is_dir: true,
+                    size: 0
+                },
+                TreeEntry {
+                    rel_path: "sub dir/empty".into(),
+                    is_dir: true,
+                    size: 0
+                },
+                TreeEntry {
+                    rel_path: "sub dir/it's.log".into(),
+                    is_dir: false,
+                    size: 3
+                },
+                TreeEntry {
+                    rel_path: "top.txt".into(),
+                    is_dir: false,
+                    size: 5
+                },
+            ]
+        );
+        assert!(parse_tree_listing("l\t7\tlink\nf\t1\t\n").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn part_file_resumes_and_commits() {
+        let dir = temp_dir();
+        let source = dir.join("src.bin");
+        std::fs::write(&source, b"0123456789").unwrap();
+        let dest = dir.join("out/dest.bin").to_string_lossy().into_owned();
+        let part = format!("{}{}", dest, PART_SUFFIX);
+        let src = source.to_string_lossy();
+
+        let out = sh(&part_size_command(&part));
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "0");
+        // The first four bytes, then the rest appended from the offset.
+        sh(&format!(
+            "head -c 4 {} | {{ {}; }}",
+            shell_escape(&src),
+            write_part_command(&part, false)
+        ));
+        let out = sh(&part_size_command(&part));
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "4");
+        sh(&format!(
+            "{} | {{ {}; }}",
+            read_command(&src, 4),
+            write_part_command(&part, true)
+        ));
+        assert!(sh(&commit_part_command(&part, &dest)).status.success());
+        assert_eq!(std::fs::read(&dest).unwrap(), b"0123456789");
+        assert!(!Path::new(&part).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn same_host_commands_copy_merge_and_move() {
+        let dir = temp_dir();
+        std::fs::create_dir_all(dir.join("a/inner")).unwrap();
+        std::fs::write(dir.join("a/inner/f"), b"1").unwrap();
+        std::fs::create_dir_all(dir.join("b")).unwrap();
+        std::fs::write(dir.join("b/keep"), b"2").unwrap();
+        let path = |p: &str| dir.join(p).to_string_lossy().into_owned();
+
+        assert!(sh(&same_host_command(
+            TransferKind::Copy,
+            &path("a"),
+            &path("b"),
+            true
+        ))
+        .status
+        .success());
+        assert!(dir.join("b/inner/f").exists() && dir.join("b/keep").exists());
+        assert!(dir.join("a/inner/f").exists());
+
+        assert!(sh(&same_host_command(
+            TransferKind::Move,
+            &path("a"),
+            &path("b"),
+            true
+        ))
+        .status
+        .success());
+        assert!(!dir.join("a").exists());
+
+        assert!(sh(&same_host_command(
+            TransferKind::Move,
+            &path("b"),
+            &path("c"),
+            true
+        ))
+        .status
+        .success());
+        assert!(dir.join("c/keep").exists());
+
+        same_host_local(TransferKind::Copy, &dir.join("c"), &dir.join("d"), true).unwrap();
+        assert_eq!(std::fs::read(dir.join("d/inner/f")).unwrap(), b"1");
+        same_host_local(
+            TransferKind::Move,
+            &dir.join("d/keep"),
+            &dir.join("c/moved"),
+            false,
+        )
+        .unwrap();
+        assert!(!dir.join("d/keep").exists() && dir.join("c/moved").exists());
+
+        std::fs::remove_file(dir.join("d/inner/f")).unwrap();
+        sh(&remove_empty_dirs_command(&path("d")));
+        assert!(!dir.join("d").exists());
+        std::fs::create_dir_all(dir.join("e/x/y")).unwrap();
+        std::fs::write(dir.join("e/x/z"), b"").unwrap();
+        remove_empty_local_dirs(&dir.join("e"));
+        assert!(dir.join("e/x/z").exists() && !dir.join("e/x/y").exists());
+    }
+
+    #[test]
+    fn queue_runs_in_order_and_tracks_progress() {
+        let mut queue = TransferQueue::default();
+        let a = file("/tmp/a", "/srv/a", 100);
+        let b = file("/tmp/b", "/srv/b", 50);
+        queue.enqueue(a.clone());
+        queue.enqueue(b.clone());
+
+        assert_eq!(queue.start_next().map(|i| i.id), Some(a.id));
+        queue.set_progress(a.id, 40);
+        assert_eq!(queue.totals(a.id), (40, 100));
+        queue.pause(a.id);
+        assert_eq!(queue.get(a.id).unwrap().status, TransferStatus::Paused);
+        // A pause that raced with the end of a failed run keeps the item paused.
+        assert_eq!(
+            queue.finish(a.id, TransferStatus::Failed("cancelled".into())),
+            None
+        );
+        assert_eq!(queue.get(a.id).unwrap().status, TransferStatus::Paused);
+
+        assert_eq!(queue.start_next().map(|i| i.id), Some(b.id));
+        queue.finish(b.id, TransferStatus::Failed("denied".into()));
+        queue.resume(a.id);
+        queue.retry(b.id);
+        assert_eq!(queue.start_next().map(|i| i.id), Some(a.id));
+        queue.finish(a.id, TransferStatus::Done);
+        assert_eq!(queue.totals(a.id), (100, 100));
+        assert!(queue.is_busy());
+
+        queue.clear_finished();
+        assert_eq!(queue.items.len(), 1);
+        assert_eq!(queue.remove(b.id).len(), 1);
+        assert!(!queue.is_busy());
+    }
+
+    #[test]
+    fn conflicts_wait_for_a_choice() {
+        let mut queue = TransferQueue::default();
+        let items: Vec<TransferItem> = (0..3)
+            .map(|i| file(&format!("/tmp/{}", i), &format!("/srv/{}", i), 1))
+            .collect();
+        for item in &items {
+            queue.enqueue(item.clone());
+        }
+        for item in &items[..2] {
+            queue.start_next();
+            queue.set_conflict(item.id);
+        }
+        queue.resolve(items[0].id, ConflictChoice::Skip, false);
+        assert_eq!(
+            queue.get(items[1].id).unwrap().status,
+            TransferStatus::Conflict
+        );
+        assert_eq!(
+            queue.start_next().map(|i| (i.id, i.conflict)),
+            Some((items[0].id, Some(ConflictChoice::Skip)))
+        );
+
+        queue.resolve(items[1].id, ConflictChoice::KeepBoth, true);
+        assert_eq!(
+            queue.start_next().map(|i| (i.id, i.conflict)),
+            Some((items[1].id, Some(ConflictChoice::KeepBoth)))
+        );
+        assert_eq!(
+            queue.start_next().map(|i| i.conflict),
+            Some(Some(ConflictChoice::KeepBoth))
+        );
+        queue.retarget(items[2].id, "/srv/2 (1)".into());
+        assert_eq!(queue.get(items[2].id).unwrap().dest.path, "/srv/2 (1)");
+    }
+
+    #[test]
+    fn directories_expand_into_their_files() {
+        let mut queue = TransferQueue::default();
+        let dir = TransferItem::new(
+            TransferKind::Move,
+            FileLocation::remote(Uuid::nil(), "/srv/site"),
+            FileLocation::local("/backup/site"),
+            true,
+            0,
+        );
+        queue.enqueue(dir.clone());
+        queue.start_next();
+        let entries = parse_tree_listing("d\t0\tcss\nf\t10\tcss/app.css\nf\t5\tindex.html\n");
+        assert_eq!(queue.expand(dir.id, &entries), None);
+        let files: Vec<TransferItem> = queue.children(dir.id).cloned().collect();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].source.path, "/srv/site/css/app.css");
+        assert_eq!(
+            PathBuf::from(&files[0].dest.path),
+            Path::new("/backup/site").join("css").join("app.css")
+        );
+        assert_eq!(files[0].kind, TransferKind::Move);
+        assert_eq!(files[0].conflict, Some(ConflictChoice::Overwrite));
+        assert_eq!(queue.totals(dir.id), (0, 15));
+
+        // Pausing the directory pauses its files; resuming brings both back.
+        queue.pause(dir.id);
+        assert_eq!(queue.get(dir.id).unwrap().status, TransferStatus::Paused);
+        assert!(queue.start_next().is_none());
+        queue.resume(dir.id);
+        assert_eq!(queue.get(dir.id).unwrap().status, TransferStatus::Running);
+
+        let first = queue.start_next().unwrap();
+        assert_eq!(first.id, files[0].id);
+        assert_eq!(queue.finish(first.id, TransferStatus::Done), None);
+        let second = queue.start_next().unwrap();
+        let settled = queue
+            .finish(second.id, TransferStatus::Failed("disk full".into()))
+            .unwrap();
+        assert_eq!(settled.status, TransferStatus::Failed("disk full".into()));
+
+        queue.retry(dir.id);
+        assert_eq!(queue.get(dir.id).unwrap().status, TransferStatus::Running);
+        let again = queue.start_next().unwrap();
+        assert_eq!(again.id, second.id);
+        assert_eq!(
+            queue
+                .finish(again.id, TransferStatus::Done)
+                .map(|d| d.status),
+            Some(TransferStatus::Done)
+        );
+
+        let mut empty = TransferQueue::default();
+        let lone = TransferItem {
+            is_dir: true,
+            ..file("/tmp/e", "/srv/e", 0)
+        };
+        empty.enqueue(lone.clone());
+        assert_eq!(
+            empty.expand(lone.id, &[]).map(|d| d.status),
+            Some(TransferStatus::Done)
+        );
+    }
+
+    #[test]
+    fn chmod_modes() {
+        for mode in [
+            "644", "0755", "2775", "u+x", "go-w", "a=rX,u+w", "+x", "u=g",
+        ] {
+            assert!(valid_chmod_mode(mode), "{}", mode);
+        }
+        for mode in ["", "888", "12345", "u", "x+", "u+q", "755;rm"] {
+            assert!(!valid_chmod_mode(mode), "{}", mode);
+        }
+        assert_eq!(permissions_to_octal("-rwxr-x---").as_deref(), Some("750"));
+        assert_eq!(permissions_to_octal("drwxrwsr-x").as_deref(), Some("2775"));
+        assert_eq!(permissions_to_octal("drwxrwxrwt").as_deref(), Some("1777"));
+        assert_eq!(permissions_to_octal("-rwSr--r--").as_deref(), Some("4644"));
+        assert_eq!(permissions_to_octal("drw"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_operations_run_through_sh_and_locally() {
+        let dir = temp_dir();
+        let path = |p: &str| dir.join(p).to_string_lossy().into_owned();
+
+        let mkdir = FileOperation::Mkdir {
+            path: path("remote"),
+        };
+        assert!(sh(&mkdir.command()).status.success());
+        FileOperation::Mkdir {
+            path: path("local"),
+        }
+        .apply_local()
+        .unwrap();
+        std::fs::write(dir.join("remote/a b"), b"x").unwrap();
+        std::fs::write(dir.join("local/c"), b"x").unwrap();
+
+        let rename = FileOperation::Rename {
+            from: path("remote/a b"),
+            to: path("local/c"),
+        };
+        let out = sh(&rename.command());
+        assert!(!out.status.success());
+        assert!(String::from_utf8_lossy(&out.stderr).contains("exists"));
+        assert!(rename.apply_local().is_err());
+        let rename = FileOperation::Rename {
+            from: path("remote/a b"),
+            to: path("remote/it's"),
+        };
+        assert!(sh(&rename.command()).status.success());
+        assert!(dir.join("remote/it's").exists());
+
+        let chmod = FileOperation::Chmod {
+            paths: vec![path("remote")],
+            mode: "750".into(),
+            recursive: true,
+        };
+        assert!(sh(&chmod.command()).status.success());
+        chmod_local(&[path("local")], "750", true).unwrap();
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |p: &str| std::fs::metadata(dir.join(p)).unwrap().permissions().mode();
+        assert_eq!(mode("remote/it's") & 0o777, 0o750);
+        assert_eq!(mode("local/c") & 0o777, 0o750);
+        assert!(FileOperation::Chmod {
+            paths: vec![path("local")],
+            mode: "u+x".into(),
+            recursive: false,
+        }
+        .apply_local()
+        .is_err());
+
+        let delete = FileOperation::Delete {
+            paths: vec![path("remote")],
+        };
+        assert!(sh(&delete.command()).status.success());
+        FileOperation::Delete {
+            paths: vec![path("local")],
+        }
+        .apply_local()
+        .unwrap();
+        assert!(!dir.join("remote").exists() && !dir.join("local").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn recursive_local_chmod_reaches_files_under_unsearchable_modes() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir();
+        std::fs::create_dir_all(dir.join("site/sub")).unwrap();
+        std::fs::write(dir.join("site/sub/index.php"), "<?php").unwrap();
+        let site = dir.join("site").to_string_lossy().into_owned();
+
+        chmod_local(&[site], "644", true).unwrap();
+
+        // Restore search permission top-down to look inside again.
+        let mode = |p: &str| std::fs::metadata(dir.join(p)).unwrap().permissions().mode() & 0o777;
+        let open = |p: &str| {
+            std::fs::set_permissions(dir.join(p), std::fs::Permissions::from_mode(0o755)).unwrap()
+        };
+        assert_eq!(mode("site"), 0o644);
+        open("site");
+        assert_eq!(mode("site/sub"), 0o644);
+        open("site/sub");
+        assert_eq!(mode("site/sub/index.php"), 0o644);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,321 @@
+//! Proxy side of a dynamic forward: who may use it, which destinations it
+//! carries, and the PAC file that tells browsers what to send through it.
+
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::port_forward::ClientCidr;
+
+/// Settings of the proxy listener of a dynamic forward. The same listener
+/// speaks SOCKS5, SOCKS4a and HTTP (CONNECT and plain forward proxying).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxySettings {
+    /// User clients must log in as; no authentication when `None`.
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Kept in the OS keychain under the forward's id (see
+    /// `keychain::store_proxy_password`), never in the config file.
+    #[serde(skip)]
+    pub password: Option<String>,
+    /// Destination rules, one per entry, e.g. `allow *.corp.example` or
+    /// `deny 10.0.0.0/8`.
+    #[serde(default)]
+    pub rules: Vec<String>,
+    /// Answer `GET /proxy.pac` with a PAC file built from the rules.
+    #[serde(default)]
+    pub serve_pac: bool,
+}
+
+impl ProxySettings {
+    pub fn requires_auth(&self) -> bool {
+        self.username.as_deref().is_some_and(|u| !u.is_empty())
+    }
+
+    /// Whether a client's credentials match. Always true without auth.
+    pub fn check_credentials(&self, username: &str, password: &str) -> bool {
+        let Some(expected) = self.username.as_deref().filter(|u| !u.is_empty()) else {
+            return true;
+        };
+        let expected_password = self.password.as_deref().unwrap_or_default();
+        // Compare both fields in full so timing does not tell which was wrong.
+        let user_ok = constant_time_eq(expected.as_bytes(), username.as_bytes());
+        let password_ok = constant_time_eq(expected_password.as_bytes(), password.as_bytes());
+        user_ok & password_ok
+    }
+
+    /// Parsed [`rules`](Self::rules), skipping entries that do not parse.
+    pub fn destination_rules(&self) -> Vec<DestinationRule> {
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.parse().ok())
+            .collect()
+    }
+
+    /// Why the settings cannot be used: a bad rule, or a user without a
+    /// password.
+    pub fn error(&self) -> Option<String> {
+        if self.requires_auth() && self.password.as_deref().unwrap_or_default().is_empty() {
+            return Some("proxy authentication has no password".to_string());
+        }
+        parse_destination_rules(&self.rules.join("\n")).err()
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    let mut diff = a.len() ^ b.len();
+    for (i, byte) in a.iter().enumerate() {
+        diff |= (byte ^ b.get(i).copied().unwrap_or(!byte)) as usize;
+    }
+    diff == 0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    Allow,
+    Deny,
+}
+
+/// What a destination rule applies to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DestinationPattern {
+    /// `*`: every destination.
+    Any,
+    /// `*.corp.example`: subdomains of `corp.example`, not the domain itself.
+    Subdomains(String),
+    /// `corp.example` or `10.1.2.3`: that host only.
+    Host(String),
+    /// `10.0.0.0/8`: destinations given as an address in the block. Names
+    /// are resolved on the far side, so they never match a block.
+    Network(ClientCidr),
+}
+
+impl DestinationPattern {
+    pub fn matches(&self, host: &str) -> bool {
+        let host = normalize_host(host);
+        match self {
+            DestinationPattern::Any => true,
+            DestinationPattern::Subdomains(domain) => host
+                .strip_suffix(domain.as_str())
+                .is_some_and(|rest| rest.len() > 1 && rest.ends_with('.')),
+            DestinationPattern::Host(name) => host == *name,
+            DestinationPattern::Network(cidr) => {
+                host.parse::<IpAddr>().is_ok_and(|ip| cidr.contains(ip))
+            }
+        }
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    let host = host.trim();
+    let host = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DestinationRule {
+    pub action: RuleAction,
+    pub pattern: DestinationPattern,
+}
+
+impl FromStr for DestinationRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let action = match parts.next().map(str::to_ascii_lowercase).as_deref() {
+            Some("allow") => RuleAction::Allow,
+            Some("deny") => RuleAction::Deny,
+            _ => return Err(format!("{} does not start with allow or deny", s.trim())),
+        };
+        let target = parts
+            .next()
+            .ok_or_else(|| format!("{} has no destination", s.trim()))?;
+        if parts.next().is_some() {
+            return Err(format!("{} has more than one destination", s.trim()));
+        }
+        let pattern = if target == "*" {
+            DestinationPattern::Any
+        } else if target.contains('/') {
+            DestinationPattern::Network(target.parse()?)
+        } else if let Some(domain) = target.strip_prefix("*.") {
+            if domain.is_empty() || domain.contains('*') {
+                return Err(format!("{} is not a domain pattern", target));
+            }
+            DestinationPattern::Subdomains(normalize_host(domain))
+        } else if target.contains('*') {
+            return Err(format!("{} is not a domain pattern", target));
+        } else {
+            DestinationPattern::Host(normalize_host(target))
+        };
+        Ok(Self { action, pattern })
+    }
+}
+
+/// Parse rules given one per line; blank lines and `#` comments are skipped.
+pub fn parse_destination_rules(text: &str) -> Result<Vec<DestinationRule>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::parse)
+        .collect()
+}
+
+/// Whether the proxy carries a connection to `host`. The first matching
+/// rule decides; with no match, a list holding any `allow` rule denies
+/// and any other list allows.
+pub fn destination_allowed(rules: &[DestinationRule], host: &str) -> bool {
+    match rules.iter().find(|rule| rule.pattern.matches(host)) {
+        Some(rule) => rule.action == RuleAction::Allow,
+        None => !rules.iter().any(|rule| rule.action == RuleAction::Allow),
+    }
+}
+
+/// Path the PAC file is served on.
+pub const PAC_PATH: &str = "/proxy.pac";
+
+/// A PAC file sending what the rules allow to the proxy at `proxy_addr`
+/// and everything else direct. As in the proxy, blocks only match hosts
+/// written as an IPv4 address, so the browser never resolves a name just
+/// to pick a route.
+pub fn pac_script(rules: &[DestinationRule], proxy_addr: &str) -> String {
+    let proxy = format!("PROXY {}", proxy_addr);
+    let mut script = String::from("function FindProxyForURL(url, host) {\n");
+    script.push_str("  host = host.toLowerCase();\n");
+    script.push_str("  var ipv4 = /^\\d+\\.\\d+\\.\\d+\\.\\d+$/.test(host);\n");
+    for rule in rules {
+        let condition = match &rule.pattern {
+            DestinationPattern::Any => "true".to_string(),
+            DestinationPattern::Subdomains(domain) => {
+                format!("shExpMatch(host, \"*.{}\")", js_escape(domain))
+            }
+            DestinationPattern::Host(name) => format!("host == \"{}\"", js_escape(name)),
+            DestinationPattern::Network(cidr) => match cidr.ipv4_mask() {
+                Some((network, mask)) => {
+                    format!("ipv4 && isInNet(host, \"{}\", \"{}\")", network, mask)
+                }
+                // PAC has no IPv6 block test; such rules only apply in the proxy.
+                None => continue,
+            },
+        };
+        let route = match rule.action {
+            RuleAction::Allow => proxy.as_str(),
+            RuleAction::Deny => "DIRECT",
+        };
+        script.push_str(&format!("  if ({}) return \"{}\";\n", condition, route));
+    }
+    let fallback = if rules.iter().any(|rule| rule.action == RuleAction::Allow) {
+        "DIRECT"
+    } else {
+        proxy.as_str()
+    };
+    script.push_str(&format!("  return \"{}\";\n}}\n", fallback));
+    script
+}
+
+fn js_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(text: &str) -> Vec<DestinationRule> {
+        parse_destination_rules(text).unwrap()
+    }
+
+    #[test]
+    fn rules_parse_and_reject_malformed_entries() {
+        let parsed = rules(
+            "# corp only\nallow *.corp.example\n\nDENY 10.0.0.0/8\nallow Wiki.Example.\nallow *",
+        );
+        assert_eq!(
+            parsed[0].pattern,
+            DestinationPattern::Subdomains("corp.example".into())
+        );
+        assert_eq!(parsed[1].action, RuleAction::Deny);
+        assert_eq!(
+            parsed[2].pattern,
+            DestinationPattern::Host("wiki.example".into())
+        );
+        assert_eq!(parsed[3].pattern, DestinationPattern::Any);
+
+        for bad in [
+            "permit *",
+            "allow",
+            "allow a b",
+            "deny 10.0.0.0/40",
+            "allow corp.*",
+            "allow *.",
+        ] {
+            assert!(bad.parse::<DestinationRule>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins_and_allow_lists_deny_the_rest() {
+        let corp = rules("deny secret.corp.example\nallow *.corp.example\nallow 10.0.0.0/8");
+        assert!(destination_allowed(&corp, "git.corp.example"));
+        assert!(destination_allowed(&corp, "GIT.Corp.Example."));
+        assert!(!destination_allowed(&corp, "secret.corp.example"));
+        assert!(
+            !destination_allowed(&corp, "corp.example"),
+            "subdomains only"
+        );
+        assert!(!destination_allowed(&corp, "evilcorp.example"));
+        assert!(destination_allowed(&corp, "10.20.30.40"));
+        assert!(!destination_allowed(&corp, "example.com"));
+
+        let blocklist = rules("deny 192.168.0.0/16\ndeny [fd00::1]");
+        assert!(destination_allowed(&blocklist, "example.com"));
+        assert!(!destination_allowed(&blocklist, "192.168.1.1"));
+        assert!(!destination_allowed(&blocklist, "fd00::1"));
+        assert!(destination_allowed(&[], "anything"));
+    }
+
+    #[test]
+    fn credentials_and_settings_errors() {
+        let mut settings = ProxySettings::default();
+        assert!(!settings.requires_auth());
+        assert!(settings.check_credentials("", ""));
+        assert_eq!(settings.error(), None);
+
+        settings.username = Some("alice".into());
+        assert!(settings.error().is_some(), "no password");
+        settings.password = Some("s3cret".into());
+        assert_eq!(settings.error(), None);
+        assert!(settings.check_credentials("alice", "s3cret"));
+        assert!(!settings.check_credentials("alice", "s3cre"));
+        assert!(!settings.check_credentials("bob", "s3cret"));
+
+        settings.rules = vec!["allow *.corp.example".into(), "maybe x".into()];
+        assert!(settings.error().is_some());
+        assert_eq!(settings.destination_rules().len(), 1);
+    }
+
+    #[test]
+    fn pac_routes_allowed_destinations_through_the_proxy() {
+        let pac = pac_script(
+            &rules(
+                "deny secret.corp.example\nallow *.corp.example\nallow 10.0.0.0/8\nallow fd00::/8",
+            ),
+            "127.0.0.1:1080",
+        );
+        assert!(pac.starts_with("function FindProxyForURL(url, host) {"));
+        assert!(pac.contains("if (host == \"secret.corp.example\") return \"DIRECT\";"));
+        assert!(pac.contains(
+            "if (shExpMatch(host, \"*.corp.example\")) return \"PROXY 127.0.0.1:1080\";"
+        ));
+        assert!(pac.contains("isInNet(host, \"10.0.0.0\", \"255.0.0.0\")"));
+        assert!(!pac.contains("fd00"));
+        assert!(pac.ends_with("  return \"DIRECT\";\n}\n"));
+
+        let everything = pac_script(&[], "proxy.lan:8080");
+        assert!(everything.ends_with("  return \"PROXY proxy.lan:8080\";\n}\n"));
+    }
+}
@@ -0,0 +1,836 @@
+//! What passed through a forward: one record per client connection and,
+//! for local forwards with inspection on, the HTTP/1.1 exchanges read off
+//! those connections.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+/// Connection records kept per forward.
+pub const MAX_CONNECTION_RECORDS: usize = 200;
+/// HTTP exchanges kept per forward.
+pub const MAX_HTTP_EXCHANGES: usize = 200;
+/// Body bytes kept of each captured request or response.
+pub const MAX_CAPTURED_BODY: usize = 64 * 1024;
+/// Longest message head read before inspection gives up on a connection.
+const MAX_HEAD_BYTES: usize = 64 * 1024;
+/// Longest start or chunk-size line read before inspection gives up.
+const MAX_LINE_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionRecord {
+    pub id: u64,
+    /// Who connected, as `address:port`.
+    pub client: String,
+    /// Where the connection was forwarded to; empty until a SOCKS client
+    /// has named it.
+    pub target: String,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    /// Bytes towards the SSH server, counted like the tunnel's totals.
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+impl ConnectionRecord {
+    pub fn is_open(&self) -> bool {
+        self.closed_at.is_none()
+    }
+}
+
+/// A request or response read off an inspected connection, body decoded
+/// from its transfer encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpMessage {
+    Request {
+        method: String,
+        path: String,
+        version: String,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+        body_truncated: bool,
+    },
+    Response {
+        status: u16,
+        reason: String,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+        body_truncated: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub body_truncated: bool,
+    /// From the request having been sent to the response having arrived.
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpExchange {
+    pub id: u64,
+    pub connection_id: u64,
+    pub method: String,
+    pub path: String,
+    pub version: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_body: Vec<u8>,
+    pub request_body_truncated: bool,
+    pub started_at: DateTime<Utc>,
+    /// `None` while the response is outstanding.
+    pub response: Option<HttpResponse>,
+}
+
+impl HttpExchange {
+    /// The request as it can be sent again, asking the server to close the
+    /// connection after answering. `None` when its body was not captured
+    /// in full.
+    pub fn replay_bytes(&self) -> Option<Vec<u8>> {
+        if self.request_body_truncated {
+            return None;
+        }
+        let mut head = format!("{} {} {}\r\n", self.method, self.path, self.version);
+        let mut had_length = false;
+        for (name, value) in &self.request_headers {
+            if name.eq_ignore_ascii_case("content-length")
+                || name.eq_ignore_ascii_case("transfer-encoding")
+            {
+                had_length = true;
+                continue;
+            }
+            if name.eq_ignore_ascii_case("connection") || name.eq_ignore_ascii_case("keep-alive") {
+                continue;
+            }
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("Connection: close\r\n");
+        // The body was stored decoded, so it goes back with a plain length.
+        if had_length || !self.request_body.is_empty() {
+            head.push_str(&format!("Content-Length: {}\r\n", self.request_body.len()));
+        }
+        head.push_str("\r\n");
+        let mut bytes = head.into_bytes();
+        bytes.extend_from_slice(&self.request_body);
+        Some(bytes)
+    }
+}
+
+/// First value of header `name`, compared case-insensitively.
+pub fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Connections and HTTP exchanges of one forward, oldest first.
+#[derive(Debug, Default)]
+pub struct TrafficLog {
+    connections: VecDeque<ConnectionRecord>,
+    exchanges: VecDeque<HttpExchange>,
+    next_connection: u64,
+    next_exchange: u64,
+    revision: u64,
+}
+
+impl TrafficLog {
+    /// Bumped on every change, so a reader can tell whether to redraw.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn connections(&self) -> &VecDeque<ConnectionRecord> {
+        &self.connections
+    }
+
+    pub fn exchanges(&self) -> &VecDeque<HttpExchange> {
+        &self.exchanges
+    }
+
+    pub fn exchange(&self, id: u64) -> Option<&HttpExchange> {
+        self.exchanges.iter().find(|e| e.id == id)
+    }
+
+    pub fn open_connections(&self) -> usize {
+        self.connections.iter().filter(|c| c.is_open()).count()
+    }
+
+    /// Start a record for a new connection and return its id.
+    pub fn open_connection(&mut self, client: String, target: String, now: DateTime<Utc>) -> u64 {
+        self.next_connection += 1;
+        let id = self.next_connection;
+        self.connections.push_back(ConnectionRecord {
+            id,
+            client,
+            target,
+            opened_at: now,
+            closed_at: None,
+            bytes_sent: 0,
+            bytes_received: 0,
+        });
+        if self.connections.len() > MAX_CONNECTION_RECORDS {
+            self.connections.pop_front();
+        }
+        self.revision += 1;
+        id
+    }
+
+    pub fn set_target(&mut self, id: u64, target: String) {
+        if let Some(record) = self.connection_mut(id) {
+            record.target = target;
+            self.revision += 1;
+        }
+    }
+
+    pub fn add_bytes(&mut self, id: u64, sent: u64, received: u64) {
+        if let Some(record) = self.connection_mut(id) {
+            record.bytes_sent += sent;
+            record.bytes_received += received;
+            self.revision += 1;
+        }
+    }
+
+    pub fn close_connection(&mut self, id: u64, now: DateTime<Utc>) {
+        if let Some(record) = self.connection_mut(id) {
+            record.closed_at = Some(now);
+            self.revision += 1;
+        }
+    }
+
+    /// File a message read off connection `connection_id`. A response
+    /// answers the oldest request of that connection still waiting, as
+    /// HTTP/1.1 pipelining requires.
+    pub fn record_http(&mut self, connection_id: u64, message: HttpMessage, now: DateTime<Utc>) {
+        match message {
+            HttpMessage::Request {
+                method,
+                path,
+                version,
+                headers,
+                body,
+                body_truncated,
+            } => {
+                self.next_exchange += 1;
+                self.exchanges.push_back(HttpExchange {
+                    id: self.next_exchange,
+                    connection_id,
+                    method,
+                    path,
+                    version,
+                    request_headers: headers,
+                    request_body: body,
+                    request_body_truncated: body_truncated,
+                    started_at: now,
+                    response: None,
+                });
+                if self.exchanges.len() > MAX_HTTP_EXCHANGES {
+                    self.exchanges.pop_front();
+                }
+            }
+            HttpMessage::Response {
+                status,
+                reason,
+                headers,
+                body,
+                body_truncated,
+            } => {
+                let Some(exchange) = self
+                    .exchanges
+                    .iter_mut()
+                    .find(|e| e.connection_id == connection_id && e.response.is_none())
+                else {
+                    return;
+                };
+                let elapsed_ms = (now - exchange.started_at).num_milliseconds().max(0) as u64;
+                exchange.response = Some(HttpResponse {
+                    status,
+                    reason,
+                    headers,
+                    body,
+                    body_truncated,
+                    elapsed_ms,
+                });
+            }
+        }
+        self.revision += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.connections.retain(|c| c.is_open());
+        self.exchanges.clear();
+        self.revision += 1;
+    }
+
+    fn connection_mut(&mut self, id: u64) -> Option<&mut ConnectionRecord> {
+        self.connections.iter_mut().rev().find(|c| c.id == id)
+    }
+}
+
+/// Reads HTTP/1.1 off both directions of one connection. Anything that
+/// does not parse as HTTP/1.x, and whatever follows an upgrade or a
+/// `CONNECT`, is passed over without being looked at.
+#[derive(Debug)]
+pub struct HttpInspector {
+    request: MessageParser,
+    response: MessageParser,
+    /// Methods of requests still waiting for their response.
+    methods: VecDeque<String>,
+}
+
+impl Default for HttpInspector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpInspector {
+    pub fn new() -> Self {
+        Self {
+            request: MessageParser::new(Side::Request),
+            response: MessageParser::new(Side::Response),
+            methods: VecDeque::new(),
+        }
+    }
+
+    /// Whether the inspector stopped reading this connection.
+    pub fn is_opaque(&self) -> bool {
+        self.request.state == Framing::Opaque
+    }
+
+    /// Bytes from the client; returns the requests they complete.
+    pub fn client_data(&mut self, data: &[u8]) -> Vec<HttpMessage> {
+        let result = self.request.feed(data, &mut self.methods);
+        self.settle(result)
+    }
+
+    /// Bytes from the server; returns the responses they complete.
+    pub fn server_data(&mut self, data: &[u8]) -> Vec<HttpMessage> {
+        let result = self.response.feed(data, &mut self.methods);
+        self.settle(result)
+    }
+
+    /// The connection closed: a response delimited by the close is
+    /// complete now.
+    pub fn finish(&mut self) -> Vec<HttpMessage> {
+        let mut out = Vec::new();
+        if self.response.state == Framing::UntilClose {
+            self.response.complete(&mut out);
+        }
+        out
+    }
+
+    fn settle(&mut self, result: Result<Vec<HttpMessage>, ()>) -> Vec<HttpMessage> {
+        match result {
+            Ok(messages) => {
+                if self.request.state == Framing::Opaque || self.response.state == Framing::Opaque {
+                    self.go_opaque();
+                }
+                messages
+            }
+            Err(()) => {
+                self.go_opaque();
+                Vec::new()
+            }
+        }
+    }
+
+    fn go_opaque(&mut self) {
+        for parser in [&mut self.request, &mut self.response] {
+            parser.state = Framing::Opaque;
+            parser.buf = Vec::new();
+            parser.body = Vec::new();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Request,
+    Response,
+}
+
+/// Where a parser is in the current message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Framing {
+    Head,
+    Length(u64),
+    ChunkSize,
+    ChunkData(u64),
+    ChunkDataEnd,
+    Trailers,
+    UntilClose,
+    Opaque,
+}
+
+#[derive(Debug)]
+struct Head {
+    start: [String; 3],
+    headers: Vec<(String, String)>,
+}
+
+#[derive(Debug)]
+struct MessageParser {
+    side: Side,
+    state: Framing,
+    /// Bytes received but not consumed yet.
+    buf: Vec<u8>,
+    head: Option<Head>,
+    body: Vec<u8>,
+    body_truncated: bool,
+    /// Interim (1xx) response: read, but not reported.
+    interim: bool,
+    /// What follows this message is not HTTP.
+    opaque_after: bool,
+}
+
+impl MessageParser {
+    fn new(side: Side) -> Self {
+        Self {
+            side,
+            state: Framing::Head,
+            buf: Vec::new(),
+            head: None,
+            body: Vec::new(),
+            body_truncated: false,
+            interim: false,
+            opaque_after: false,
+        }
+    }
+
+    fn feed(
+        &mut self,
+        data: &[u8],
+        methods: &mut VecDeque<String>,
+    ) -> Result<Vec<HttpMessage>, ()> {
+        if self.state == Framing::Opaque {
+            return Ok(Vec::new());
+        }
+        self.buf.extend_from_slice(data);
+        let mut out = Vec::new();
+        let mut pos = 0;
+        loop {
+            let rest = &self.buf[pos..];
+            match self.state {
+                Framing::Head => {
+                    // Clients may send stray line breaks between messages.
+                    if rest.starts_with(b"\r\n") {
+                        pos += 2;
+                        continue;
+                    }
+                    // The start line is checked as soon as it is in, so
+                    // non-HTTP traffic is let go early.
+                    match find(rest, b"\r\n") {
+                        Some(end) => parse_start_line(&rest[..end], self.side).ok_or(())?,
+                        None if rest.len() > MAX_LINE_BYTES => return Err(()),
+                        None => {
+                            if rest.first().is_some_and(|b| !b.is_ascii_alphabetic()) {
+                                return Err(());
+                            }
+                            break;
+                        }
+                    };
+                    let Some(end) = find(rest, b"\r\n\r\n") else {
+                        if rest.len() > MAX_HEAD_BYTES {
+                            return Err(());
+                        }
+                        break;
+                    };
+                    let head = parse_head(&rest[..end], self.side).ok_or(())?;
+                    pos += end + 4;
+                    self.state = self.framing(&head, methods)?;
+                    self.head = Some(head);
+                    self.body.clear();
+                    self.body_truncated = false;
+                    if self.state == Framing::Length(0) {
+                        self.complete(&mut out);
+                    }
+                }
+                Framing::Length(remaining) | Framing::ChunkData(remaining) => {
+                    if rest.is_empty() {
+                        break;
+                    }
+                    let n = rest.len().min(remaining as usize);
+                    self.capture(n, pos);
+                    pos += n;
+                    let left = remaining - n as u64;
+                    self.state = match (self.state, left) {
+                        (Framing::ChunkData(_), 0) => Framing::ChunkDataEnd,
+                        (Framing::ChunkData(_), left) => Framing::ChunkData(left),
+                        (_, 0) => {
+                            self.complete(&mut out);
+                            self.state
+                        }
+                        (_, left) => Framing::Length(left),
+                    };
+                }
+                Framing::ChunkSize => {
+                    let Some(end) = find(rest, b"\r\n") else {
+                        if rest.len() > MAX_LINE_BYTES {
+                            return Err(());
+                        }
+                        break;
+                    };
+                    let line = std::str::from_utf8(&rest[..end]).map_err(|_| ())?;
+                    let size = line.split(';').next().unwrap_or("").trim();
+                    let size = u64::from_str_radix(size, 16).map_err(|_| ())?;
+                    pos += end + 2;
+                    self.state = if size == 0 {
+                        Framing::Trailers
+                    } else {
+                        Framing::ChunkData(size)
+                    };
+                }
+                Framing::ChunkDataEnd => {
+                    if rest.len() < 2 {
+                        break;
+                    }
+                    if &rest[..2] != b"\r\n" {
+                        return Err(());
+                    }
+                    pos += 2;
+                    self.state = Framing::ChunkSize;
+                }
+                Framing::Trailers => {
+                    let Some(end) = find(rest, b"\r\n") else {
+                        if rest.len() > MAX_LINE_BYTES {
+                            return Err(());
+                        }
+                        break;
+                    };
+                    pos += end + 2;
+                    if end == 0 {
+                        self.complete(&mut out);
+                    }
+                }
+                Framing::UntilClose => {
+                    let n = rest.len();
+                    self.capture(n, pos);
+                    pos += n;
+                    break;
+                }
+                Framing::Opaque => {
+                    pos = self.buf.len();
+                    break;
+                }
+            }
+        }
+        self.buf.drain(..pos.min(self.buf.len()));
+        Ok(out)
+    }
+
+    /// How the body of the message with `head` is delimited.
+    fn framing(&mut self, head: &Head, methods: &mut VecDeque<String>) -> Result<Framing, ()> {
+        let chunked = header(&head.headers, "transfer-encoding")
+            .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"));
+        let length = match header(&head.headers, "content-length") {
+            Some(value) => Some(value.trim().parse::<u64>().map_err(|_| ())?),
+            None => None,
+        };
+        self.interim = false;
+        match self.side {
+            Side::Request => {
+                let method = head.start[0].clone();
+                self.opaque_after = method == "CONNECT";
+                methods.push_back(method);
+            }
+            Side::Response => {
+                let status: u16 = head.start[1].parse().map_err(|_| ())?;
+                if status == 101 {
+                    methods.pop_front();
+                    self.opaque_after = true;
+                    return Ok(Framing::Length(0));
+                }
+                if (100..200).contains(&status) {
+                    self.interim = true;
+                    return Ok(Framing::Length(0));
+                }
+                let method = methods.pop_front().unwrap_or_default();
+                if method == "CONNECT" && (200..300).contains(&status) {
+                    self.opaque_after = true;
+                    return Ok(Framing::Length(0));
+                }
+                if method == "HEAD" || status == 204 || status == 304 {
+                    return Ok(Framing::Length(0));
+                }
+            }
+        }
+        Ok(if chunked {
+            Framing::ChunkSize
+        } else if let Some(length) = length {
+            Framing::Length(length)
+        } else if self.side == Side::Response {
+            Framing::UntilClose
+        } else {
+            Framing::Length(0)
+        })
+    }
+
+    /// Keep `n` body bytes from `buf[pos..]`, up to [`MAX_CAPTURED_BODY`].
+    fn capture(&mut self, n: usize, pos: usize) {
+        let room = MAX_CAPTURED_BODY.saturating_sub(self.body.len());
+        let kept = n.min(room);
+        self.body.extend_from_slice(&self.buf[pos..pos + kept]);
+        if kept < n {
+            self.body_truncated = true;
+        }
+    }
+
+    fn complete(&mut self, out: &mut Vec<HttpMessage>) {
+        let head = self.head.take();
+        let body = std::mem::take(&mut self.body);
+        let body_truncated = std::mem::take(&mut self.body_truncated);
+        self.state = if self.opaque_after {
+            Framing::Opaque
+        } else {
+            Framing::Head
+        };
+        let Some(Head { start, headers }) = head else {
+            return;
+        };
+        if std::mem::take(&mut self.interim) {
+            return;
+        }
+        let [a, b, c] = start;
+        out.push(match self.side {
+            Side::Request => HttpMessage::Request {
+                method: a,
+                path: b,
+                version: c,
+                headers,
+                body,
+                body_truncated,
+            },
+            Side::Response => HttpMessage::Response {
+                status: b.parse().unwrap_or_default(),
+                reason: c,
+                headers,
+                body,
+                body_truncated,
+            },
+        });
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// The three parts of an HTTP/1.x start line: method, target and version
+/// of a request, or version, status and reason of a response.
+fn parse_start_line(line: &[u8], side: Side) -> Option<[String; 3]> {
+    let line = std::str::from_utf8(line).ok()?;
+    match side {
+        Side::Request => {
+            let mut parts = line.split(' ');
+            let method = parts.next()?;
+            let target = parts.next()?;
+            let version = parts.next()?;
+            let valid = parts.next().is_none()
+                && !method.is_empty()
+                && method.bytes().all(|b| b.is_ascii_uppercase())
+                && !target.is_empty()
+                && version.starts_with("HTTP/1.");
+            valid.then(|| [method.to_string(), target.to_string(), version.to_string()])
+        }
+        Side::Response => {
+            let mut parts = line.splitn(3, ' ');
+            let version = parts.next()?;
+            let status = parts.next()?;
+            let reason = parts.next().unwrap_or("");
+            let valid = version.starts_with("HTTP/1.")
+                && status.len() == 3
+                && status.bytes().all(|b| b.is_ascii_digit());
+            valid.then(|| [version.to_string(), status.to_string(), reason.to_string()])
+        }
+    }
+}
+
+fn parse_head(head: &[u8], side: Side) -> Option<Head> {
+    let text = String::from_utf8_lossy(head);
+    let mut lines = text.split("\r\n");
+    let start = parse_start_line(lines.next()?.as_bytes(), side)?;
+    let mut headers = Vec::new();
+    for line in lines {
+        let (name, value) = line.split_once(':')?;
+        if name.is_empty() || name.ends_with(' ') {
+            return None;
+        }
+        headers.push((name.to_string(), value.trim().to_string()));
+    }
+    Some(Head { start, headers })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_path(message: &HttpMessage) -> &str {
+        match message {
+            HttpMessage::Request { path, .. } => path,
+            other => panic!("expected a request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn reads_pipelined_requests_split_across_reads() {
+        let mut inspector = HttpInspector::new();
+        let wire =
+            b"GET /a HTTP/1.1\r\nHost: x\r\n\r\nPOST /b HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello";
+        let mut requests = Vec::new();
+        for chunk in wire.chunks(7) {
+            requests.extend(inspector.client_data(chunk));
+        }
+        assert_eq!(requests.len(), 2);
+        assert_eq!(request_path(&requests[0]), "/a");
+        match &requests[1] {
+            HttpMessage::Request { method, body, .. } => {
+                assert_eq!(method, "POST");
+                assert_eq!(body, b"hello");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let responses = inspector.server_data(
+            b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nokHTTP/1.1 201 Created\r\nTransfer-Encoding: chunked\r\n\r\n3;x=y\r\nabc\r\n2\r\nde\r\n0\r\nX-Trailer: 1\r\n\r\n",
+        );
+        assert_eq!(responses.len(), 2);
+        match &responses[1] {
+            HttpMessage::Response { status, body, .. } => {
+                assert_eq!(*status, 201);
+                assert_eq!(body, b"abcde");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn response_framing_follows_the_request_and_status() {
+        let mut inspector = HttpInspector::new();
+        inspector.client_data(b"HEAD / HTTP/1.1\r\n\r\nGET /stream HTTP/1.0\r\n\r\n");
+        // A HEAD answer carries no body despite its length.
+        let head = inspector.server_data(
+            b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n",
+        );
+        assert_eq!(head.len(), 1, "interim response not reported");
+        // No length: the body runs until the connection closes.
+        assert!(inspector
+            .server_data(b"HTTP/1.0 200 OK\r\n\r\nsome")
+            .is_empty());
+        assert!(inspector.server_data(b" data").is_empty());
+        match inspector.finish().as_slice() {
+            [HttpMessage::Response { body, .. }] => assert_eq!(body, b"some data"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn non_http_and_upgraded_connections_are_let_go() {
+        let mut tls = HttpInspector::new();
+        assert!(tls.client_data(&[0x16, 0x03, 0x01, 0x02, 0x00]).is_empty());
+        assert!(tls.is_opaque());
+
+        let mut ws = HttpInspector::new();
+        assert_eq!(
+            ws.client_data(b"GET /ws HTTP/1.1\r\nUpgrade: websocket\r\n\r\n")
+                .len(),
+            1
+        );
+        assert_eq!(
+            ws.server_data(b"HTTP/1.1 101 Switching Protocols\r\n\r\n\x81\x05hello")
+                .len(),
+            1
+        );
+        assert!(ws.is_opaque());
+        assert!(ws.client_data(b"GET /not-http HTTP/1.1\r\n\r\n").is_empty());
+
+        let mut big = HttpInspector::new();
+        let mut message = b"POST /upload HTTP/1.1\r\nContent-Length: 70000\r\n\r\n".to_vec();
+        message.resize(message.len() + 70_000, b'x');
+        match big.client_data(&message).as_slice() {
+            [HttpMessage::Request {
+                body,
+                body_truncated,
+                ..
+            }] => {
+                assert_eq!(body.len(), MAX_CAPTURED_BODY);
+                assert!(body_truncated);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn log_pairs_responses_and_caps_records() {
+        let mut log = TrafficLog::default();
+        let t0 = Utc::now();
+        let first = log.open_connection("127.0.0.1:50000".into(), "api:80".into(), t0);
+        let second = log.open_connection("127.0.0.1:50001".into(), String::new(), t0);
+        log.set_target(second, "example.com:443".into());
+        log.add_bytes(first, 10, 20);
+        let request = |path: &str| HttpMessage::Request {
+            method: "GET".into(),
+            path: path.into(),
+            version: "HTTP/1.1".into(),
+            headers: Vec::new(),
+            body: Vec::new(),
+            body_truncated: false,
+        };
+        log.record_http(first, request("/one"), t0);
+        log.record_http(first, request("/two"), t0);
+        log.record_http(
+            first,
+            HttpMessage::Response {
+                status: 404,
+                reason: "Not Found".into(),
+                headers: Vec::new(),
+                body: Vec::new(),
+                body_truncated: false,
+            },
+            t0 + chrono::Duration::milliseconds(35),
+        );
+        let exchanges: Vec<_> = log.exchanges().iter().collect();
+        assert_eq!(exchanges[0].response.as_ref().unwrap().status, 404);
+        assert_eq!(exchanges[0].response.as_ref().unwrap().elapsed_ms, 35);
+        assert!(exchanges[1].response.is_none());
+
+        log.close_connection(first, t0);
+        assert_eq!(log.open_connections(), 1);
+        assert_eq!(log.connections()[0].bytes_received, 20);
+        assert_eq!(log.connections()[1].target, "example.com:443");
+
+        for _ in 0..MAX_CONNECTION_RECORDS {
+            log.open_connection(String::new(), String::new(), t0);
+        }
+        assert_eq!(log.connections().len(), MAX_CONNECTION_RECORDS);
+        let revision = log.revision();
+        log.clear();
+        assert!(log.exchanges().is_empty());
+        assert!(log.revision() > revision);
+    }
+
+    #[test]
+    fn replay_rewrites_framing_and_asks_for_close() {
+        let mut inspector = HttpInspector::new();
+        let messages = inspector.client_data(
+            b"POST /hook HTTP/1.1\r\nHost: api\r\nConnection: keep-alive\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n{\"a\"\r\n2\r\n:1\r\n1\r\n}\r\n0\r\n\r\n",
+        );
+        let mut log = TrafficLog::default();
+        let connection = log.open_connection(String::new(), String::new(), Utc::now());
+        for message in messages {
+            log.record_http(connection, message, Utc::now());
+        }
+        let replay = log.exchanges()[0].replay_bytes().unwrap();
+        assert_eq!(
+            String::from_utf8(replay).unwrap(),
+            "POST /hook HTTP/1.1\r\nHost: api\r\nConnection: close\r\nContent-Length: 7\r\n\r\n{\"a\":1}"
+        );
+
+        let mut truncated = log.exchanges()[0].clone();
+        truncated.request_body_truncated = true;
+        assert_eq!(truncated.replay_bytes(), None);
+    }
+}
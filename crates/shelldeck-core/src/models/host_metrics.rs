@@ -0,0 +1,586 @@
+//! Host metrics: one read of a host's `/proc` (plus `df` and `ps`), the
+//! rates that come out of two reads in a row, a short in-memory history
+//! and the alerts raised when a threshold is crossed.
+//!
+//! Nothing is installed on the host: [`METRICS_COMMAND`] is a plain shell
+//! pipeline run over an exec channel, and its output is parsed here.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Printed between the sections of [`METRICS_COMMAND`]'s output.
+const SECTION_MARK: &str = "@@shelldeck:";
+
+/// Processes kept per sample, busiest first.
+pub const TOP_PROCESSES: usize = 8;
+
+/// One read of everything a sample needs. Every section is optional on
+/// the parsing side, so a host without `df` or with a minimal `ps` still
+/// reports the rest.
+pub const METRICS_COMMAND: &str = "export LC_ALL=C; \
+echo @@shelldeck:stat; cat /proc/stat; \
+echo @@shelldeck:loadavg; cat /proc/loadavg; \
+echo @@shelldeck:meminfo; cat /proc/meminfo; \
+echo @@shelldeck:net; cat /proc/net/dev; \
+echo @@shelldeck:df; df -P -k 2>/dev/null; \
+echo @@shelldeck:ps; ps -eo pid=,pcpu=,pmem=,comm= --sort=-pcpu 2>/dev/null | head -n 16; \
+true";
+
+/// Aggregate CPU time from the `cpu` line of `/proc/stat`, in ticks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub total: u64,
+    /// Idle plus I/O wait.
+    pub idle: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    pub total_kib: u64,
+    pub available_kib: u64,
+    pub swap_total_kib: u64,
+    pub swap_free_kib: u64,
+}
+
+impl MemoryUsage {
+    pub fn used_kib(&self) -> u64 {
+        self.total_kib.saturating_sub(self.available_kib)
+    }
+
+    pub fn used_percent(&self) -> f32 {
+        percent(self.used_kib(), self.total_kib)
+    }
+
+    pub fn swap_used_kib(&self) -> u64 {
+        self.swap_total_kib.saturating_sub(self.swap_free_kib)
+    }
+
+    /// `None` on a host without swap.
+    pub fn swap_percent(&self) -> Option<f32> {
+        (self.swap_total_kib > 0).then(|| percent(self.swap_used_kib(), self.swap_total_kib))
+    }
+}
+
+/// One mounted filesystem, as `df -P -k` reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiskUsage {
+    pub filesystem: String,
+    pub mount: String,
+    pub total_kib: u64,
+    pub used_kib: u64,
+    pub available_kib: u64,
+}
+
+impl DiskUsage {
+    /// Used share of the space a user can write, the way `df` computes it
+    /// (blocks reserved for root do not count as free).
+    pub fn used_percent(&self) -> f32 {
+        percent(self.used_kib, self.used_kib + self.available_kib)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    /// `ps` %CPU: averaged over the process lifetime, not instantaneous.
+    pub cpu_percent: f32,
+    pub mem_percent: f32,
+    pub command: String,
+}
+
+/// Raw counters of one read. CPU and network are cumulative; turning them
+/// into a load and a throughput takes the read before.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostSnapshot {
+    pub at: DateTime<Utc>,
+    pub cpu: Option<CpuTimes>,
+    /// `cpuN` lines in `/proc/stat`.
+    pub cores: u32,
+    pub load: Option<[f32; 3]>,
+    pub memory: Option<MemoryUsage>,
+    /// Received and sent bytes over every interface but loopback.
+    pub net: Option<(u64, u64)>,
+    pub disks: Vec<DiskUsage>,
+    pub processes: Vec<ProcessUsage>,
+}
+
+impl HostSnapshot {
+    /// Parse [`METRICS_COMMAND`]'s output. `None` when it has none of the
+    /// `/proc` sections, e.g. on a host that is not Linux.
+    pub fn parse(output: &str, at: DateTime<Utc>) -> Option<Self> {
+        let mut snapshot = HostSnapshot {
+            at,
+            cpu: None,
+            cores: 0,
+            load: None,
+            memory: None,
+            net: None,
+            disks: Vec::new(),
+            processes: Vec::new(),
+        };
+        for (section, body) in sections(output) {
+            match section {
+                "stat" => {
+                    let (cpu, cores) = parse_stat(body);
+                    snapshot.cpu = cpu;
+                    snapshot.cores = cores;
+                }
+                "loadavg" => snapshot.load = parse_loadavg(body),
+                "meminfo" => snapshot.memory = parse_meminfo(body),
+                "net" => snapshot.net = parse_net_dev(body),
+                "df" => snapshot.disks = parse_df(body),
+                "ps" => snapshot.processes = parse_ps(body),
+                _ => {}
+            }
+        }
+        let any = snapshot.cpu.is_some() || snapshot.load.is_some() || snapshot.memory.is_some();
+        any.then_some(snapshot)
+    }
+}
+
+fn sections(output: &str) -> Vec<(&str, &str)> {
+    let mut found = Vec::new();
+    let mut rest = output;
+    while let Some(start) = rest.find(SECTION_MARK) {
+        let after = &rest[start + SECTION_MARK.len()..];
+        let (name, body) = after.split_once('\n').unwrap_or((after, ""));
+        let end = body.find(SECTION_MARK).unwrap_or(body.len());
+        found.push((name.trim(), &body[..end]));
+        rest = &body[end..];
+    }
+    found
+}
+
+fn parse_stat(body: &str) -> (Option<CpuTimes>, u32) {
+    let mut cpu = None;
+    let mut cores = 0;
+    for line in body.lines() {
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("cpu") => {
+                let ticks: Vec<u64> = fields.filter_map(|f| f.parse().ok()).collect();
+                if ticks.len() >= 4 {
+                    // user nice system idle iowait irq softirq steal; guest
+                    // time is already counted in user and nice.
+                    let total = ticks.iter().take(8).sum();
+                    let idle = ticks[3] + ticks.get(4).copied().unwrap_or(0);
+                    cpu = Some(CpuTimes { total, idle });
+                }
+            }
+            Some(name) if name.starts_with("cpu") => cores += 1,
+            _ => {}
+        }
+    }
+    (cpu, cores)
+}
+
+fn parse_loadavg(body: &str) -> Option<[f32; 3]> {
+    let mut fields = body.split_whitespace().map(|f| f.parse::<f32>().ok());
+    Some([fields.next()??, fields.next()??, fields.next()??])
+}
+
+fn parse_meminfo(body: &str) -> Option<MemoryUsage> {
+    let field = |name: &str| {
+        body.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            if key != name {
+                return None;
+            }
+            value.split_whitespace().next()?.parse::<u64>().ok()
+        })
+    };
+    let total_kib = field("MemTotal")?;
+    // Kernels before 3.14 have no MemAvailable.
+    let available_kib = field("MemAvailable").unwrap_or_else(|| {
+        field("MemFree").unwrap_or(0) + field("Buffers").unwrap_or(0) + field("Cached").unwrap_or(0)
+    });
+    Some(MemoryUsage {
+        total_kib,
+        available_kib: available_kib.min(total_kib),
+        swap_total_kib: field("SwapTotal").unwrap_or(0),
+        swap_free_kib: field("SwapFree").unwrap_or(0),
+    })
+}
+
+fn parse_net_dev(body: &str) -> Option<(u64, u64)> {
+    let mut totals: Option<(u64, u64)> = None;
+    for line in body.lines() {
+        let Some((name, counters)) = line.split_once(':') else {
+            continue;
+        };
+        if name.trim() == "lo" {
+            continue;
+        }
+        let counters: Vec<u64> = counters
+            .split_whitespace()
+            .filter_map(|f| f.parse().ok())
+            .collect();
+        // Receive bytes is the first column, transmit bytes the ninth.
+        if let (Some(rx), Some(tx)) = (counters.first(), counters.get(8)) {
+            let (total_rx, total_tx) = totals.get_or_insert((0, 0));
+            *total_rx += rx;
+            *total_tx += tx;
+        }
+    }
+    totals
+}
+
+/// Filesystems that live in memory or belong to the system, not to data.
+fn is_pseudo_filesystem(filesystem: &str, mount: &str) -> bool {
+    matches!(
+        filesystem,
+        "tmpfs" | "devtmpfs" | "udev" | "none" | "overlay" | "shm" | "squashfs"
+    ) || ["/dev", "/proc", "/sys", "/run", "/snap/"]
+        .iter()
+        .any(|prefix| mount.starts_with(prefix))
+}
+
+fn parse_df(body: &str) -> Vec<DiskUsage> {
+    let mut disks: Vec<DiskUsage> = Vec::new();
+    for line in body.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        // Filesystem 1024-blocks Used Available Capacity Mounted-on, where
+        // the mount point may contain spaces.
+        if fields.len() < 6 {
+            continue;
+        }
+        let (Ok(total_kib), Ok(used_kib), Ok(available_kib)) = (
+            fields[1].parse::<u64>(),
+            fields[2].parse::<u64>(),
+            fields[3].parse::<u64>(),
+        ) else {
+            continue;
+        };
+        let filesystem = fields[0].to_string();
+        let mount = fields[5..].join(" ");
+        if total_kib == 0 || is_pseudo_filesystem(&filesystem, &mount) {
+            continue;
+        }
+        // Bind mounts show the same filesystem twice; keep the first.
+        if disks.iter().any(|d| d.filesystem == filesystem) {
+            continue;
+        }
+        disks.push(DiskUsage {
+            filesystem,
+            mount,
+            total_kib,
+            used_kib,
+            available_kib,
+        });
+    }
+    disks
+}
+
+fn parse_ps(body: &str) -> Vec<ProcessUsage> {
+    let mut processes: Vec<ProcessUsage> = body
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let cpu_percent = fields.next()?.parse().ok()?;
+            let mem_percent = fields.next()?.parse().ok()?;
+            let command = fields.collect::<Vec<_>>().join(" ");
+            (!command.is_empty()).then_some(ProcessUsage {
+                pid,
+                cpu_percent,
+                mem_percent,
+                command,
+            })
+        })
+        .collect();
+    // `ps` without `--sort` (BusyBox) lists by pid.
+    processes.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent));
+    processes.truncate(TOP_PROCESSES);
+    processes
+}
+
+fn percent(part: u64, whole: u64) -> f32 {
+    if whole == 0 {
+        0.0
+    } else {
+        (part as f64 / whole as f64 * 100.0) as f32
+    }
+}
+
+/// What a host looked like at one point, with rates over the interval
+/// since the read before.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSample {
+    pub at: DateTime<Utc>,
+    /// Busy share of all cores; `None` on the first read.
+    pub cpu_percent: Option<f32>,
+    pub cores: u32,
+    pub load: Option<[f32; 3]>,
+    pub memory: Option<MemoryUsage>,
+    pub rx_bytes_per_sec: Option<f64>,
+    pub tx_bytes_per_sec: Option<f64>,
+    pub disks: Vec<DiskUsage>,
+    pub processes: Vec<ProcessUsage>,
+}
+
+impl MetricsSample {
+    /// Derive a sample from `current` and the read before it. Counters
+    /// that went backwards (a reboot, a removed interface) give no rate.
+    pub fn from_snapshots(previous: Option<&HostSnapshot>, current: &HostSnapshot) -> Self {
+        let cpu_percent =
+            previous
+                .and_then(|p| p.cpu)
+                .zip(current.cpu)
+                .and_then(|(before, now)| {
+                    let total = now.total.checked_sub(before.total)?;
+                    let idle = now.idle.checked_sub(before.idle)?;
+                    (total > 0).then(|| percent(total.saturating_sub(idle), total))
+                });
+        let seconds = previous
+            .map(|p| (current.at - p.at).num_milliseconds() as f64 / 1000.0)
+            .filter(|s| *s > 0.0);
+        let rate = |before: u64, now: u64| Some(now.checked_sub(before)? as f64 / seconds?);
+        let net = previous.and_then(|p| p.net).zip(current.net);
+        Self {
+            at: current.at,
+            cpu_percent,
+            cores: current.cores,
+            load: current.load,
+            memory: current.memory,
+            rx_bytes_per_sec: net.and_then(|(before, now)| rate(before.0, now.0)),
+            tx_bytes_per_sec: net.and_then(|(before, now)| rate(before.1, now.1)),
+            disks: current.disks.clone(),
+            processes: current.processes.clone(),
+        }
+    }
+
+    /// The fullest filesystem.
+    pub fn fullest_disk(&self) -> Option<&DiskUsage> {
+        self.disks
+            .iter()
+            .max_by(|a, b| a.used_percent().total_cmp(&b.used_percent()))
+    }
+}
+
+/// The last samples of one host, oldest first, and why the last read
+/// failed if it did. Kept in memory only.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsHistory {
+    pub samples: Vec<MetricsSample>,
+    pub error: Option<String>,
+    last_snapshot: Option<HostSnapshot>,
+}
+
+impl MetricsHistory {
+    /// Add a read, keeping at most `capacity` samples. Returns the new
+    /// sample.
+    pub fn record(&mut self, snapshot: HostSnapshot, capacity: usize) -> &MetricsSample {
+        let sample = MetricsSample::from_snapshots(self.last_snapshot.as_ref(), &snapshot);
+        self.last_snapshot = Some(snapshot);
+        self.error = None;
+        self.samples.push(sample);
+        let excess = self.samples.len().saturating_sub(capacity.max(1));
+        self.samples.drain(..excess);
+        self.samples.last().expect("a sample was just pushed")
+    }
+
+    /// Note a failed read. Rates restart from the next good read.
+    pub fn record_error(&mut self, error: String) {
+        self.last_snapshot = None;
+        self.error = Some(error);
+    }
+
+    pub fn latest(&self) -> Option<&MetricsSample> {
+        self.samples.last()
+    }
+}
+
+/// When a sample is worth an alert.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricsThresholds {
+    /// Used share of a filesystem, in percent.
+    pub disk_percent: f32,
+    /// One-minute load per core.
+    pub load_per_core: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricsAlert {
+    DiskFull { mount: String, percent: f32 },
+    HighLoad { load: f32, cores: u32 },
+}
+
+/// Alerts raised by `current` given the sample before it. Each condition
+/// alerts once, when it starts, not on every sample while it lasts.
+pub fn metrics_alerts(
+    previous: Option<&MetricsSample>,
+    current: &MetricsSample,
+    thresholds: &MetricsThresholds,
+) -> Vec<MetricsAlert> {
+    let mut alerts = Vec::new();
+    let full = |disk: &DiskUsage| disk.used_percent() > thresholds.disk_percent;
+    for disk in current.disks.iter().filter(|d| full(d)) {
+        let was_full =
+            previous.is_some_and(|p| p.disks.iter().any(|d| d.mount == disk.mount && full(d)));
+        if !was_full {
+            alerts.push(MetricsAlert::DiskFull {
+                mount: disk.mount.clone(),
+                percent: disk.used_percent(),
+            });
+        }
+    }
+    let overloaded = |sample: &MetricsSample| {
+        sample.cores > 0
+            && sample
+                .load
+                .is_some_and(|load| load[0] > sample.cores as f32 * thresholds.load_per_core)
+    };
+    if overloaded(current) && !previous.is_some_and(overloaded) {
+        alerts.push(MetricsAlert::HighLoad {
+            load: current.load.map(|l| l[0]).unwrap_or_default(),
+            cores: current.cores,
+        });
+    }
+    alerts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+@@shelldeck:stat
+cpu  1000 0 500 8000 500 0 0 0 0 0
+cpu0 500 0 250 4000 250 0 0 0 0 0
+cpu1 500 0 250 4000 250 0 0 0 0 0
+intr 12345
+ctxt 999
+@@shelldeck:loadavg
+2.50 1.25 0.75 3/250 12345
+@@shelldeck:meminfo
+MemTotal:        4000000 kB
+MemFree:          500000 kB
+MemAvailable:    1000000 kB
+SwapTotal:       2000000 kB
+SwapFree:        1500000 kB
+@@shelldeck:net
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 9999 10 0 0 0 0 0 0 9999 10 0 0 0 0 0 0
+  eth0: 1000 10 0 0 0 0 0 0 2000 20 0 0 0 0 0 0
+  eth1: 500 5 0 0 0 0 0 0 100 1 0 0 0 0 0 0
+@@shelldeck:df
+Filesystem     1024-blocks     Used Available Capacity Mounted on
+/dev/sda1         10000000  9500000    400000      96% /
+tmpfs               100000        0    100000       0% /dev/shm
+/dev/sdb1          1000000   100000    900000      10% /srv/my data
+/dev/sda1         10000000  9500000    400000      96% /var/lib/docker
+@@shelldeck:ps
+  101  0.5  1.0 sshd
+  202 45.0 12.5 postgres
+  303  3.0  2.0 nginx: worker process
+@@shelldeck:end
+";
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_every_section() {
+        let snapshot = HostSnapshot::parse(OUTPUT, at(0)).unwrap();
+        assert_eq!(
+            snapshot.cpu,
+            Some(CpuTimes {
+                total: 10_000,
+                idle: 8_500
+            })
+        );
+        assert_eq!(snapshot.cores, 2);
+        assert_eq!(snapshot.load, Some([2.5, 1.25, 0.75]));
+        let memory = snapshot.memory.unwrap();
+        assert_eq!(memory.used_kib(), 3_000_000);
+        assert_eq!(memory.used_percent(), 75.0);
+        assert_eq!(memory.swap_percent(), Some(25.0));
+        assert_eq!(snapshot.net, Some((1500, 2100)));
+        let mounts: Vec<&str> = snapshot.disks.iter().map(|d| d.mount.as_str()).collect();
+        assert_eq!(mounts, vec!["/", "/srv/my data"]);
+        assert!((snapshot.disks[0].used_percent() - 95.96).abs() < 0.01);
+        let pids: Vec<u32> = snapshot.processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![202, 303, 101]);
+        assert_eq!(snapshot.processes[1].command, "nginx: worker process");
+
+        assert!(HostSnapshot::parse("sh: cat: not found\n", at(0)).is_none());
+    }
+
+    #[test]
+    fn rates_come_from_the_previous_read() {
+        let first = HostSnapshot::parse(OUTPUT, at(0)).unwrap();
+        let mut second = first.clone();
+        second.at = at(10);
+        second.cpu = Some(CpuTimes {
+            total: 11_000,
+            idle: 8_750,
+        });
+        second.net = Some((11_500, 3_100));
+
+        let initial = MetricsSample::from_snapshots(None, &first);
+        assert_eq!(initial.cpu_percent, None);
+        assert_eq!(initial.rx_bytes_per_sec, None);
+
+        let sample = MetricsSample::from_snapshots(Some(&first), &second);
+        assert_eq!(sample.cpu_percent, Some(75.0));
+        assert_eq!(sample.rx_bytes_per_sec, Some(1000.0));
+        assert_eq!(sample.tx_bytes_per_sec, Some(100.0));
+
+        // A reboot resets the counters.
+        second.net = Some((10, 10));
+        let sample = MetricsSample::from_snapshots(Some(&first), &second);
+        assert_eq!(sample.rx_bytes_per_sec, None);
+    }
+
+    #[test]
+    fn history_is_bounded_and_restarts_rates_after_an_error() {
+        let mut history = MetricsHistory::default();
+        let snapshot = HostSnapshot::parse(OUTPUT, at(0)).unwrap();
+        for i in 0..5 {
+            let mut s = snapshot.clone();
+            s.at = at(i * 10);
+            history.record(s, 3);
+        }
+        assert_eq!(history.samples.len(), 3);
+        assert_eq!(history.samples[0].at, at(20));
+
+        history.record_error("timed out".to_string());
+        assert_eq!(history.error.as_deref(), Some("timed out"));
+        let mut s = snapshot.clone();
+        s.at = at(60);
+        let sample = history.record(s, 3);
+        assert_eq!(sample.cpu_percent, None);
+        assert!(history.error.is_none());
+    }
+
+    #[test]
+    fn alerts_fire_when_a_threshold_is_first_crossed() {
+        let thresholds = MetricsThresholds {
+            disk_percent: 90.0,
+            load_per_core: 1.0,
+        };
+        let snapshot = HostSnapshot::parse(OUTPUT, at(0)).unwrap();
+        let sample = MetricsSample::from_snapshots(None, &snapshot);
+        let alerts = metrics_alerts(None, &sample, &thresholds);
+        assert_eq!(alerts.len(), 2);
+        assert!(matches!(&alerts[0], MetricsAlert::DiskFull { mount, .. } if mount == "/"));
+        assert_eq!(
+            alerts[1],
+            MetricsAlert::HighLoad {
+                load: 2.5,
+                cores: 2
+            }
+        );
+
+        // Still over both thresholds: nothing new.
+        assert!(metrics_alerts(Some(&sample), &sample, &thresholds).is_empty());
+
+        let mut calm = sample.clone();
+        calm.load = Some([1.0, 1.0, 1.0]);
+        calm.disks.retain(|d| d.mount != "/");
+        assert!(metrics_alerts(Some(&sample), &calm, &thresholds).is_empty());
+        assert_eq!(metrics_alerts(Some(&calm), &sample, &thresholds).len(), 2);
+    }
+}
@@ -0,0 +1,542 @@
+//! Log tailing across hosts: what to follow on each host and the command
+//! that follows it, plus how lines from several hosts get a date and a
+//! level, are merged into one timeline, filtered and exported.
+//!
+//! Nothing is installed on the hosts. Each source is a plain `tail -F`,
+//! `journalctl -f` or `docker logs -f` run over an exec channel.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::util::shell_escape;
+
+/// Only the start of a line is searched for a level.
+const LEVEL_SCAN_BYTES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSourceKind {
+    /// A file, followed across rotations.
+    File,
+    /// A systemd unit's journal.
+    Journald,
+    /// A Docker container's output.
+    Docker,
+}
+
+impl LogSourceKind {
+    pub const ALL: [LogSourceKind; 3] = [
+        LogSourceKind::File,
+        LogSourceKind::Journald,
+        LogSourceKind::Docker,
+    ];
+
+    /// Stable identifier, used for translation keys.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LogSourceKind::File => "file",
+            LogSourceKind::Journald => "journald",
+            LogSourceKind::Docker => "docker",
+        }
+    }
+}
+
+/// One stream to follow on one host.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LogSource {
+    pub connection_id: Uuid,
+    pub kind: LogSourceKind,
+    /// The path, systemd unit or container, depending on `kind`.
+    pub target: String,
+}
+
+impl LogSource {
+    pub fn new(connection_id: Uuid, kind: LogSourceKind, target: impl Into<String>) -> Self {
+        Self {
+            connection_id,
+            kind,
+            target: target.into().trim().to_string(),
+        }
+    }
+
+    /// Short name shown next to each line: the path, or the unit or
+    /// container with its kind.
+    pub fn label(&self) -> String {
+        match self.kind {
+            LogSourceKind::File => self.target.clone(),
+            LogSourceKind::Journald => format!("journald:{}", self.target),
+            LogSourceKind::Docker => format!("docker:{}", self.target),
+        }
+    }
+
+    /// Shell command printing the last `backlog` lines, then following.
+    /// Errors go to the same stream so a missing file or unit shows up as
+    /// a line rather than as silence.
+    pub fn follow_command(&self, backlog: usize) -> String {
+        let target = shell_escape(&self.target);
+        match self.kind {
+            LogSourceKind::File => format!("tail -n {} -F {} 2>&1", backlog, target),
+            LogSourceKind::Journald => format!(
+                "journalctl --no-pager -o short-iso -n {} -f -u {} 2>&1",
+                backlog, target
+            ),
+            LogSourceKind::Docker => format!(
+                "docker logs --timestamps --tail {} -f {} 2>&1",
+                backlog, target
+            ),
+        }
+    }
+
+    /// Split a raw line into its date, when it carries one, and the text
+    /// to show. The `--timestamps` prefix of Docker lines is ours, so it
+    /// is dropped from the text; any other date is part of the log line.
+    pub fn split_line<'a>(
+        &self,
+        line: &'a str,
+        now: DateTime<Utc>,
+    ) -> (Option<DateTime<Utc>>, &'a str) {
+        if self.kind == LogSourceKind::Docker {
+            if let Some((stamp, rest)) = line.split_once(' ') {
+                if let Some(at) = parse_iso(stamp) {
+                    return (Some(at), rest);
+                }
+            }
+        }
+        (parse_timestamp(line, now), line)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [
+        LogLevel::Trace,
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Warn,
+        LogLevel::Error,
+    ];
+
+    /// Stable identifier, used for translation keys.
+    pub fn code(&self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    /// The level of a line: the status of an access log line (5xx is an
+    /// error, 4xx a warning), else the first level word near its start.
+    pub fn detect(line: &str) -> Option<Self> {
+        if let Some(status) = access_log_status(line) {
+            return Some(match status {
+                500.. => LogLevel::Error,
+                400..=499 => LogLevel::Warn,
+                _ => LogLevel::Info,
+            });
+        }
+        let mut end = line.len().min(LEVEL_SCAN_BYTES);
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        line[..end]
+            .split(|c: char| !c.is_ascii_alphabetic())
+            .find_map(|word| match word.to_ascii_lowercase().as_str() {
+                "error" | "err" | "fatal" | "crit" | "critical" | "emerg" | "alert" | "panic"
+                | "severe" => Some(LogLevel::Error),
+                "warn" | "warning" => Some(LogLevel::Warn),
+                "info" | "notice" => Some(LogLevel::Info),
+                "debug" => Some(LogLevel::Debug),
+                "trace" => Some(LogLevel::Trace),
+                _ => None,
+            })
+    }
+}
+
+/// The status code of a combined/common access log line.
+fn access_log_status(line: &str) -> Option<u16> {
+    static ACCESS_LOG: OnceLock<Regex> = OnceLock::new();
+    let re = ACCESS_LOG.get_or_init(|| {
+        Regex::new(r#"\] "[A-Z]+ [^"]*" (\d{3}) "#).expect("valid access log regex")
+    });
+    re.captures(line)?.get(1)?.as_str().parse().ok()
+}
+
+/// The date at the start of a line (ISO 8601 with or without a zone,
+/// `2026-10-18 12:34:56,789`, syslog's `Oct 18 12:34:56`), or the
+/// bracketed date of an access log. Dates without a zone are taken as
+/// UTC; a syslog date without a year gets the year of `now`.
+pub fn parse_timestamp(line: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let mut tokens = line.split_whitespace();
+    let first = tokens.next()?;
+    let bare = first.trim_start_matches('[').trim_end_matches(']');
+    if let Some(at) = parse_iso(bare) {
+        return Some(at);
+    }
+    if let Some(second) = tokens.next() {
+        let time = second.trim_end_matches(']');
+        if let Some(at) = parse_iso(&format!("{}T{}", bare, time)) {
+            return Some(at);
+        }
+        if let Some(third) = tokens.next() {
+            if let Some(at) = parse_syslog(first, second, third, now) {
+                return Some(at);
+            }
+        }
+    }
+    parse_access_log_date(line)
+}
+
+fn parse_iso(token: &str) -> Option<DateTime<Utc>> {
+    // Anything shorter cannot hold a date and a time.
+    if token.len() < 19 || !token.as_bytes()[0].is_ascii_digit() {
+        return None;
+    }
+    let token = token.replacen(',', ".", 1);
+    if let Ok(at) = DateTime::parse_from_rfc3339(&token) {
+        return Some(at.with_timezone(&Utc));
+    }
+    if let Ok(at) = DateTime::parse_from_str(&token, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        return Some(at.with_timezone(&Utc));
+    }
+    NaiveDateTime::parse_from_str(&token, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .map(|naive| Utc.from_utc_datetime(&naive))
+}
+
+fn parse_syslog(month: &str, day: &str, time: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if month.len() != 3 || !month.as_bytes()[0].is_ascii_alphabetic() {
+        return None;
+    }
+    let text = format!("{} {} {} {}", now.year(), month, day, time);
+    let at =
+        Utc.from_utc_datetime(&NaiveDateTime::parse_from_str(&text, "%Y %b %d %H:%M:%S%.f").ok()?);
+    // A December line read in January is from last year.
+    if at > now + Duration::days(1) {
+        return at.with_year(now.year() - 1);
+    }
+    Some(at)
+}
+
+fn parse_access_log_date(line: &str) -> Option<DateTime<Utc>> {
+    let start = line.find('[')? + 1;
+    let end = start + line[start..].find(']')?;
+    DateTime::parse_from_str(&line[start..end], "%d/%b/%Y:%H:%M:%S %z")
+        .ok()
+        .map(|at| at.with_timezone(&Utc))
+}
+
+/// One line of the merged timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogLine {
+    /// Arrival order; breaks ties between lines with the same date.
+    pub seq: u64,
+    pub at: DateTime<Utc>,
+    /// Whether `at` was read from the line rather than inferred.
+    pub dated: bool,
+    /// Index of the source in the tail's source list.
+    pub source: usize,
+    pub level: Option<LogLevel>,
+    pub text: String,
+}
+
+/// Lines of every source in date order, capped to the newest `capacity`.
+#[derive(Debug, Clone)]
+pub struct LogTimeline {
+    lines: Vec<LogLine>,
+    capacity: usize,
+    next_seq: u64,
+    /// Date of each source's last dated line. A line without a date (a
+    /// stack trace, a wrapped message) takes it, so it stays next to the
+    /// line it belongs to.
+    last_at: HashMap<usize, DateTime<Utc>>,
+}
+
+impl LogTimeline {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Vec::new(),
+            capacity: capacity.max(1),
+            next_seq: 0,
+            last_at: HashMap::new(),
+        }
+    }
+
+    pub fn lines(&self) -> &[LogLine] {
+        &self.lines
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.last_at.clear();
+    }
+
+    /// Add a raw line of source `index`, received at `received`.
+    pub fn push(&mut self, index: usize, source: &LogSource, raw: &str, received: DateTime<Utc>) {
+        let (parsed, text) = source.split_line(raw, received);
+        let at = match parsed {
+            Some(at) => {
+                self.last_at.insert(index, at);
+                at
+            }
+            None => self.last_at.get(&index).copied().unwrap_or(received),
+        };
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let line = LogLine {
+            seq,
+            at,
+            dated: parsed.is_some(),
+            source: index,
+            level: LogLevel::detect(text),
+            text: text.to_string(),
+        };
+        let position = self.lines.partition_point(|l| l.at <= at);
+        self.lines.insert(position, line);
+        if self.lines.len() > self.capacity {
+            let excess = self.lines.len() - self.capacity;
+            self.lines.drain(..excess);
+        }
+    }
+}
+
+/// Which lines of the timeline are shown.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pattern: Option<Regex>,
+    /// Hide lines below this level, and lines without one.
+    pub min_level: Option<LogLevel>,
+    /// Sources whose lines are hidden.
+    pub hidden_sources: HashSet<usize>,
+}
+
+impl LogFilter {
+    /// Match lines against `pattern`, case-insensitively unless it says
+    /// otherwise with `(?-i)`. An empty pattern matches every line.
+    pub fn set_pattern(&mut self, pattern: &str) -> Result<(), String> {
+        let pattern = pattern.trim();
+        if pattern.is_empty() {
+            self.pattern = None;
+            return Ok(());
+        }
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        self.pattern = Some(regex);
+        Ok(())
+    }
+
+    pub fn has_pattern(&self) -> bool {
+        self.pattern.is_some()
+    }
+
+    pub fn matches(&self, line: &LogLine) -> bool {
+        if self.hidden_sources.contains(&line.source) {
+            return false;
+        }
+        if let Some(min) = self.min_level {
+            if line.level.is_none_or(|level| level < min) {
+                return false;
+            }
+        }
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(&line.text))
+    }
+}
+
+/// The lines dated within `from..=to` that pass `filter`, as text: one
+/// line each, prefixed with its date and the `label` of its source.
+pub fn export_window(
+    lines: &[LogLine],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    filter: &LogFilter,
+    label: impl Fn(usize) -> String,
+) -> String {
+    let mut out = String::new();
+    for line in lines
+        .iter()
+        .filter(|l| l.at >= from && l.at <= to && filter.matches(l))
+    {
+        out.push_str(&line.at.to_rfc3339_opts(SecondsFormat::Millis, true));
+        out.push(' ');
+        out.push_str(&label(line.source));
+        out.push_str(" | ");
+        out.push_str(&line.text);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, 12, 0, 0).unwrap()
+    }
+
+    fn at(h: u32, m: u32, s: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 18, h, m, s).unwrap()
+    }
+
+    fn source(kind: LogSourceKind) -> LogSource {
+        LogSource::new(Uuid::nil(), kind, "web")
+    }
+
+    #[test]
+    fn dates_are_read_from_common_log_formats() {
+        let cases = [
+            "2026-10-18T10:00:01Z GET /",
+            "2026-10-18T12:00:01+02:00 started",
+            "2026-10-18T12:00:01+0200 web01 nginx[42]: started",
+            "2026-10-18 10:00:01,250 INFO app: ready",
+            "[2026-10-18T10:00:01.5Z] worker up",
+            "Oct 18 10:00:01 web01 sshd[1]: Accepted",
+            r#"10.0.0.1 - - [18/Oct/2026:10:00:01 +0000] "GET / HTTP/1.1" 200 12 "-" "curl""#,
+        ];
+        for line in cases {
+            let parsed = parse_timestamp(line, now()).unwrap_or_else(|| panic!("{}", line));
+            assert_eq!(parsed.timestamp(), at(10, 0, 1).timestamp(), "{}", line);
+        }
+        assert_eq!(parse_timestamp("    at com.example.Main", now()), None);
+        assert_eq!(parse_timestamp("", now()), None);
+    }
+
+    #[test]
+    fn syslog_dates_from_late_last_year_keep_their_year() {
+        let january = Utc.with_ymd_and_hms(2027, 1, 2, 0, 0, 0).unwrap();
+        let parsed = parse_timestamp("Dec 31 23:59:00 host cron: done", january).unwrap();
+        assert_eq!(parsed.year(), 2026);
+    }
+
+    #[test]
+    fn docker_timestamps_are_stripped_from_the_text() {
+        let (parsed, text) = source(LogSourceKind::Docker)
+            .split_line("2026-10-18T10:00:01.123456789Z listening on :8080", now());
+        assert_eq!(
+            parsed.map(|p| p.timestamp()),
+            Some(at(10, 0, 1).timestamp())
+        );
+        assert_eq!(text, "listening on :8080");
+
+        let (_, text) = source(LogSourceKind::File).split_line("2026-10-18T10:00:01Z x", now());
+        assert_eq!(text, "2026-10-18T10:00:01Z x");
+    }
+
+    #[test]
+    fn levels_come_from_words_or_http_status() {
+        assert_eq!(
+            LogLevel::detect("2026-10-18 ERROR db: timeout"),
+            Some(LogLevel::Error)
+        );
+        assert_eq!(LogLevel::detect("[warn] slow query"), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::detect("level=debug msg=x"), Some(LogLevel::Debug));
+        assert_eq!(LogLevel::detect("just text"), None);
+        let access = |status: u16| {
+            format!(
+                r#"1.2.3.4 - - [18/Oct/2026:10:00:01 +0000] "GET /api/error HTTP/1.1" {} 0 "-" "-""#,
+                status
+            )
+        };
+        assert_eq!(LogLevel::detect(&access(502)), Some(LogLevel::Error));
+        assert_eq!(LogLevel::detect(&access(404)), Some(LogLevel::Warn));
+        assert_eq!(LogLevel::detect(&access(200)), Some(LogLevel::Info));
+    }
+
+    #[test]
+    fn follow_commands_quote_the_target() {
+        let file = LogSource::new(Uuid::nil(), LogSourceKind::File, " /var/log/app's.log ");
+        assert_eq!(
+            file.follow_command(50),
+            r"tail -n 50 -F '/var/log/app'\''s.log' 2>&1"
+        );
+        assert!(source(LogSourceKind::Journald)
+            .follow_command(10)
+            .contains("-n 10 -f -u 'web'"));
+        assert_eq!(source(LogSourceKind::Docker).label(), "docker:web");
+    }
+
+    #[test]
+    fn timeline_merges_hosts_by_date_and_keeps_undated_lines_in_place() {
+        let file = source(LogSourceKind::File);
+        let mut timeline = LogTimeline::new(4);
+        timeline.push(0, &file, "2026-10-18T10:00:05Z a1", now());
+        timeline.push(1, &file, "2026-10-18T10:00:02Z b1", now());
+        timeline.push(0, &file, "  continued", now());
+        timeline.push(1, &file, "2026-10-18T10:00:09Z b2", now());
+        let texts: Vec<&str> = timeline.lines().iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "2026-10-18T10:00:02Z b1",
+                "2026-10-18T10:00:05Z a1",
+                "  continued",
+                "2026-10-18T10:00:09Z b2"
+            ]
+        );
+        assert!(!timeline.lines()[2].dated);
+
+        timeline.push(1, &file, "2026-10-18T10:00:10Z b3", now());
+        assert_eq!(timeline.lines().len(), 4, "oldest line dropped");
+        assert_eq!(timeline.lines()[0].text, "2026-10-18T10:00:05Z a1");
+    }
+
+    #[test]
+    fn filter_and_export_a_window() {
+        let file = source(LogSourceKind::File);
+        let mut timeline = LogTimeline::new(100);
+        timeline.push(0, &file, "2026-10-18T10:00:00Z INFO boot", now());
+        timeline.push(
+            0,
+            &file,
+            "2026-10-18T10:01:00Z ERROR upstream timeout",
+            now(),
+        );
+        timeline.push(1, &file, "2026-10-18T10:02:00Z WARN upstream slow", now());
+        timeline.push(1, &file, "2026-10-18T10:09:00Z ERROR upstream gone", now());
+
+        let mut filter = LogFilter::default();
+        assert!(filter.set_pattern("(").is_err());
+        filter.set_pattern("UPSTREAM").unwrap();
+        filter.min_level = Some(LogLevel::Warn);
+        let shown: Vec<u64> = timeline
+            .lines()
+            .iter()
+            .filter(|l| filter.matches(l))
+            .map(|l| l.seq)
+            .collect();
+        assert_eq!(shown, vec![1, 2, 3]);
+
+        filter.hidden_sources.insert(1);
+        let text = export_window(
+            timeline.lines(),
+            at(10, 0, 30),
+            at(10, 5, 0),
+            &filter,
+            |i| format!("host{}", i),
+        );
+        assert_eq!(
+            text,
+            "2026-10-18T10:01:00.000Z host0 | 2026-10-18T10:01:00Z ERROR upstream timeout\n"
+        );
+    }
+}
@@ -3,8 +3,10 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::server_sync::{DatabaseEngine, DiscoveredDatabase, DiscoveredSite};
+use super::services::{DiscoveredContainer, DiscoveredService};
+use super::site_health::SiteMonitor;
 
-/// A site or database discovered from a server, persisted globally.
+/// A site, database or service discovered from a server, persisted globally.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManagedSite {
     pub id: Uuid,
@@ -22,6 +24,9 @@ pub struct ManagedSite {
     pub favorite: bool,
     #[serde(default)]
     pub tags: Vec<String>,
+    /// Health monitoring, off until the user turns it on.
+    #[serde(default)]
+    pub monitor: SiteMonitor,
 }
 
 /// What kind of managed site this is.
@@ -29,6 +34,10 @@ pub struct ManagedSite {
 pub enum ManagedSiteType {
     NginxSite(DiscoveredSite),
     Database(DiscoveredDatabase),
+    ApacheSite(DiscoveredSite),
+    CaddySite(DiscoveredSite),
+    Container(DiscoveredContainer),
+    SystemdService(DiscoveredService),
 }
 
 /// Runtime status of a managed site.
@@ -42,86 +51,102 @@ pub enum SiteStatus {
 }
 
 impl ManagedSite {
-    /// Create from a discovered nginx site.
-    pub fn from_nginx(connection_id: Uuid, connection_name: &str, site: DiscoveredSite) -> Self {
+    /// Create from anything discovered on a server.
+    pub fn new(connection_id: Uuid, connection_name: &str, site_type: ManagedSiteType) -> Self {
         Self {
             id: Uuid::new_v4(),
             connection_id,
             connection_name: connection_name.to_string(),
-            site_type: ManagedSiteType::NginxSite(site),
+            site_type,
             discovered_at: Utc::now(),
             last_checked: None,
             status: SiteStatus::Unknown,
             notes: None,
             favorite: false,
             tags: Vec::new(),
+            monitor: SiteMonitor::default(),
         }
     }
 
+    /// Create from a discovered nginx site.
+    pub fn from_nginx(connection_id: Uuid, connection_name: &str, site: DiscoveredSite) -> Self {
+        Self::new(
+            connection_id,
+            connection_name,
+            ManagedSiteType::NginxSite(site),
+        )
+    }
+
     /// Create from a discovered database.
     pub fn from_database(
         connection_id: Uuid,
         connection_name: &str,
         db: DiscoveredDatabase,
     ) -> Self {
-        Self {
-            id: Uuid::new_v4(),
+        Self::new(
             connection_id,
-            connection_name: connection_name.to_string(),
-            site_type: ManagedSiteType::Database(db),
-            discovered_at: Utc::now(),
-            last_checked: None,
-            status: SiteStatus::Unknown,
-            notes: None,
-            favorite: false,
-            tags: Vec::new(),
-        }
+            connection_name,
+            ManagedSiteType::Database(db),
+        )
     }
 
     /// Display name for this site.
     pub fn name(&self) -> &str {
         match &self.site_type {
-            ManagedSiteType::NginxSite(s) => &s.server_name,
+            ManagedSiteType::NginxSite(s)
+            | ManagedSiteType::ApacheSite(s)
+            | ManagedSiteType::CaddySite(s) => &s.server_name,
             ManagedSiteType::Database(d) => &d.name,
+            ManagedSiteType::Container(c) => &c.name,
+            ManagedSiteType::SystemdService(s) => s.name(),
         }
     }
 
-    /// URL for nginx sites, None for databases.
-    pub fn url(&self) -> Option<String> {
+    /// The web server site behind this entry — nginx, Apache or Caddy.
+    pub fn web_site(&self) -> Option<&DiscoveredSite> {
         match &self.site_type {
-            ManagedSiteType::NginxSite(s) => {
-                let scheme = if s.ssl { "https" } else { "http" };
-                let port_suffix = match (s.ssl, s.listen_port) {
-                    (true, 443) | (false, 80) => String::new(),
-                    _ => format!(":{}", s.listen_port),
-                };
-                Some(format!("{}://{}{}", scheme, s.server_name, port_suffix))
-            }
-            ManagedSiteType::Database(_) => None,
+            ManagedSiteType::NginxSite(s)
+            | ManagedSiteType::ApacheSite(s)
+            | ManagedSiteType::CaddySite(s) => Some(s),
+            _ => None,
         }
     }
 
-    /// Port number.
+    /// URL for web sites, None for databases and services.
+    pub fn url(&self) -> Option<String> {
+        let s = self.web_site()?;
+        let scheme = if s.ssl { "https" } else { "http" };
+        let port_suffix = match (s.ssl, s.listen_port) {
+            (true, 443) | (false, 80) => String::new(),
+            _ => format!(":{}", s.listen_port),
+        };
+        Some(format!("{}://{}{}", scheme, s.server_name, port_suffix))
+    }
+
+    /// Port number: the listen port of a web site, or the first port a
+    /// container publishes.
     pub fn port(&self) -> Option<u16> {
         match &self.site_type {
-            ManagedSiteType::NginxSite(s) => Some(s.listen_port),
-            ManagedSiteType::Database(_) => None,
+            ManagedSiteType::Container(c) => c.primary_port(),
+            _ => self.web_site().map(|s| s.listen_port),
         }
     }
 
     /// Whether the site has SSL.
     pub fn has_ssl(&self) -> bool {
-        match &self.site_type {
-            ManagedSiteType::NginxSite(s) => s.ssl,
-            ManagedSiteType::Database(_) => false,
-        }
+        self.web_site().is_some_and(|s| s.ssl)
     }
 
-    /// Root path for nginx sites, or size display for databases.
+    /// Root path for web sites, size for databases, image for containers,
+    /// description for services.
     pub fn root_or_size(&self) -> String {
         match &self.site_type {
-            ManagedSiteType::NginxSite(s) => s.root.clone(),
+            ManagedSiteType::NginxSite(s)
+            | ManagedSiteType::ApacheSite(s)
+            | ManagedSiteType::CaddySite(s) => s.root.clone(),
             ManagedSiteType::Database(d) => d.size_display(),
+            ManagedSiteType::Container(c) => c.image.clone(),
+            ManagedSiteType::SystemdService(s) => s.description.clone(),
         }
     }
 }
@@ -132,6 +157,10 @@ impl ManagedSiteType {
         match self {
             ManagedSiteType::NginxSite(_) => "Nginx",
             ManagedSiteType::Database(d) => d.engine.label(),
+            ManagedSiteType::ApacheSite(_) => "Apache",
+            ManagedSiteType::CaddySite(_) => "Caddy",
+            ManagedSiteType::Container(_) => "Docker",
+            ManagedSiteType::SystemdService(_) => "systemd",
         }
     }
 
@@ -143,8 +172,30 @@ impl ManagedSiteType {
                 DatabaseEngine::Mysql => "M",
                 DatabaseEngine::Postgresql => "P",
             },
+            ManagedSiteType::ApacheSite(_) => "A",
+            ManagedSiteType::CaddySite(_) => "C",
+            ManagedSiteType::Container(_) => "D",
+            ManagedSiteType::SystemdService(_) => "S",
         }
     }
+
+    /// Whether this is a site served by nginx, Apache or Caddy.
+    pub fn is_web_site(&self) -> bool {
+        matches!(
+            self,
+            ManagedSiteType::NginxSite(_)
+                | ManagedSiteType::ApacheSite(_)
+                | ManagedSiteType::CaddySite(_)
+        )
+    }
+
+    /// Whether this is a Docker container or a systemd service.
+    pub fn is_service(&self) -> bool {
+        matches!(
+            self,
+            ManagedSiteType::Container(_) | ManagedSiteType::SystemdService(_)
+        )
+    }
 }
 
 impl SiteStatus {
@@ -231,4 +282,55 @@ mod tests {
             "database SSL is not surfaced through ManagedSite"
         );
     }
+
+    #[test]
+    fn services_report_ports_but_no_url() {
+        use crate::models::services::PublishedPort;
+
+        let conn_id = Uuid::new_v4();
+        let container = ManagedSite::new(
+            conn_id,
+            "prod",
+            ManagedSiteType::Container(DiscoveredContainer {
+                id: "4f1c0a9d2b7e".into(),
+                name: "shop-web-1".into(),
+                image: "nginx:1.27".into(),
+                status: "Up 3 hours".into(),
+                ports: vec![PublishedPort {
+                    host_port: 8080,
+                    container_port: 80,
+                    protocol: "tcp".into(),
+                }],
+                volumes: Vec::new(),
+                compose_project: Some("shop".into()),
+                compose_service: Some("web".into()),
+            }),
+        );
+        assert_eq!(container.name(), "shop-web-1");
+        assert_eq!(container.port(), Some(8080));
+        assert_eq!(container.root_or_size(), "nginx:1.27");
+        assert!(container.url().is_none());
+        assert!(container.site_type.is_service());
+
+        let service = ManagedSite::new(
+            conn_id,
+            "prod",
+            ManagedSiteType::SystemdService(DiscoveredService {
+                unit: "api.service".into(),
+                description: "Shop API".into(),
+                ..Default::default()
+            }),
+        );
+        assert_eq!(service.name(), "api");
+        assert!(service.port().is_none());
+        assert!(!service.site_type.is_web_site());
+
+        let caddy = ManagedSite::new(
+            conn_id,
+            "prod",
+            ManagedSiteType::CaddySite(mk_site("shop.example", 443, true)),
+        );
+        assert_eq!(caddy.url().as_deref(), Some("https://shop.example"));
+        assert!(caddy.site_type.is_web_site());
+    }
 }
@@ -1,17 +1,35 @@
+pub mod config_apply;
 pub mod connection;
+pub mod db_backup;
+pub mod db_credentials;
+pub mod diagnostics;
 pub mod discovery;
 pub mod execution;
+pub mod file_transfer;
+pub mod forward_proxy;
+pub mod forward_traffic;
+pub mod host_metrics;
+pub mod log_tail;
 pub mod managed_site;
+pub mod multi_run;
 pub mod port_forward;
+pub mod schedule;
 pub mod script;
 pub mod script_runner;
 pub mod server_sync;
+pub mod services;
+pub mod site_health;
+pub mod sync_plan;
 pub mod templates;
+pub mod tunnel_health;
+pub mod wordpress;
 
 pub use connection::*;
 pub use execution::*;
 pub use managed_site::*;
+pub use multi_run::*;
 pub use port_forward::*;
+pub use schedule::*;
 pub use script::*;
 pub use script_runner::*;
 pub use server_sync::*;
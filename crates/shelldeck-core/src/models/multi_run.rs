@@ -0,0 +1,424 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::connection::Connection;
+use super::execution::ExecutionRecord;
+
+// ---------------------------------------------------------------------------
+// MultiTarget — run one script on a set of hosts
+// ---------------------------------------------------------------------------
+
+/// Which connections a multi-host script runs on.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HostSelector {
+    Connections(Vec<Uuid>),
+    Group(String),
+    Tag(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MultiTarget {
+    pub hosts: HostSelector,
+    /// Hosts running at the same time.
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    /// Per-host limit covering connect and execution. `None` waits forever.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// Stop starting new hosts as soon as one fails; hosts already running
+    /// are left to finish.
+    #[serde(default)]
+    pub rolling: bool,
+}
+
+fn default_concurrency() -> usize {
+    5
+}
+
+impl MultiTarget {
+    pub fn new(hosts: HostSelector) -> Self {
+        Self {
+            hosts,
+            concurrency: default_concurrency(),
+            timeout_secs: None,
+            rolling: false,
+        }
+    }
+
+    /// Connections matched by the selector, in the order they are listed in
+    /// `connections` (or in the explicit list order for `Connections`, where
+    /// a repeated id only runs once). Group and tag names match
+    /// case-insensitively.
+    pub fn resolve<'a>(&self, connections: &'a [Connection]) -> Vec<&'a Connection> {
+        match &self.hosts {
+            HostSelector::Connections(ids) => ids
+                .iter()
+                .enumerate()
+                .filter(|(i, id)| !ids[..*i].contains(id))
+                .filter_map(|(_, id)| connections.iter().find(|c| c.id == *id))
+                .collect(),
+            HostSelector::Group(group) => connections
+                .iter()
+                .filter(|c| {
+                    c.group
+                        .as_deref()
+                        .is_some_and(|g| g.eq_ignore_ascii_case(group))
+                })
+                .collect(),
+            HostSelector::Tag(tag) => connections
+                .iter()
+                .filter(|c| c.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+                .collect(),
+        }
+    }
+
+    pub fn effective_concurrency(&self) -> usize {
+        self.concurrency.max(1)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// MultiRun — live state of one multi-host execution
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostRunStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    TimedOut,
+    /// Never started: the run was cancelled or a rolling run halted.
+    Skipped,
+    Cancelled,
+}
+
+impl HostRunStatus {
+    pub fn is_finished(self) -> bool {
+        !matches!(self, Self::Pending | Self::Running)
+    }
+
+    pub fn is_failure(self) -> bool {
+        matches!(self, Self::Failed | Self::TimedOut)
+    }
+}
+
+/// Progress reported by the workers executing a multi-host run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MultiRunEvent {
+    Started(Uuid),
+    Output(Uuid, String),
+    /// Exit code, or `None` when the host could not be reached.
+    Finished(Uuid, Option<i32>),
+    TimedOut(Uuid),
+    Skipped(Uuid),
+    Cancelled(Uuid),
+}
+
+#[derive(Debug, Clone)]
+pub struct HostRun {
+    pub host: String,
+    pub status: HostRunStatus,
+    /// Per-host execution record, saved to the script history when done.
+    pub record: ExecutionRecord,
+    /// The record's output with secrets masked, refreshed as output arrives
+    /// so views do not redact it again on every frame.
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MultiRunSummary {
+    pub pending: usize,
+    pub running: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub timed_out: usize,
+    pub skipped: usize,
+}
+
+/// Finished hosts that produced the same output with the same exit code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostOutputGroup {
+    pub output: String,
+    pub exit_code: Option<i32>,
+    pub connection_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MultiRun {
+    pub id: Uuid,
+    pub script_id: Uuid,
+    pub target: MultiTarget,
+    pub started_at: DateTime<Utc>,
+    pub hosts: Vec<HostRun>,
+}
+
+impl MultiRun {
+    pub fn new(script_id: Uuid, target: MultiTarget, connections: &[&Connection]) -> Self {
+        let hosts = connections
+            .iter()
+            .map(|conn| HostRun {
+                host: conn.display_name().to_string(),
+                status: HostRunStatus::Pending,
+                record: ExecutionRecord::new(script_id, Some(conn.id)),
+                output: String::new(),
+            })
+            .collect();
+        Self {
+            id: Uuid::new_v4(),
+            script_id,
+            target,
+            started_at: Utc::now(),
+            hosts,
+        }
+    }
+
+    pub fn host(&self, connection_id: Uuid) -> Option<&HostRun> {
+        self.hosts
+            .iter()
+            .find(|h| h.record.connection_id == Some(connection_id))
+    }
+
+    fn host_mut(&mut self, connection_id: Uuid) -> Option<&mut HostRun> {
+        self.hosts
+            .iter_mut()
+            .find(|h| h.record.connection_id == Some(connection_id))
+    }
+
+    /// Apply a worker event. Returns the host's record when the event
+    /// finished it, so the caller can persist it.
+    pub fn apply(&mut self, event: MultiRunEvent) -> Option<ExecutionRecord> {
+        let (connection_id, status, exit_code) = match event {
+            MultiRunEvent::Started(id) => {
+                let host = self.host_mut(id)?;
+                host.status = HostRunStatus::Running;
+                host.record.started_at = Utc::now();
+                return None;
+            }
+            MultiRunEvent::Output(id, text) => {
+                let host = self.host_mut(id)?;
+                host.record.append_output(&text);
+                host.output = host.record.redacted_output();
+                return None;
+            }
+            MultiRunEvent::Finished(id, Some(0)) => (id, HostRunStatus::Succeeded, 0),
+            MultiRunEvent::Finished(id, code) => (id, HostRunStatus::Failed, code.unwrap_or(-1)),
+            MultiRunEvent::TimedOut(id) => (id, HostRunStatus::TimedOut, -1),
+            MultiRunEvent::Cancelled(id) => (id, HostRunStatus::Cancelled, -1),
+            MultiRunEvent::Skipped(id) => {
+                let host = self.host_mut(id)?;
+                if !host.status.is_finished() {
+                    host.status = HostRunStatus::Skipped;
+                }
+                return None;
+            }
+        };
+
+        let host = self.host_mut(connection_id)?;
+        if host.status.is_finished() {
+            return None;
+        }
+        host.status = status;
+        host.record.finish(exit_code);
+        Some(host.record.clone())
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.hosts.iter().all(|h| h.status.is_finished())
+    }
+
+    pub fn summary(&self) -> MultiRunSummary {
+        let mut summary = MultiRunSummary::default();
+        for host in &self.hosts {
+            match host.status {
+                HostRunStatus::Pending => summary.pending += 1,
+                HostRunStatus::Running => summary.running += 1,
+                HostRunStatus::Succeeded => summary.succeeded += 1,
+                HostRunStatus::Failed => summary.failed += 1,
+                HostRunStatus::TimedOut => summary.timed_out += 1,
+                HostRunStatus::Skipped | HostRunStatus::Cancelled => summary.skipped += 1,
+            }
+        }
+        summary
+    }
+
+    /// Group finished hosts by identical output (ignoring trailing
+    /// whitespace) and exit code, largest group first. Hosts that never ran
+    /// are left out.
+    pub fn output_groups(&self) -> Vec<HostOutputGroup> {
+        let mut groups: Vec<HostOutputGroup> = Vec::new();
+        for host in &self.hosts {
+            if !host.status.is_finished() || host.status == HostRunStatus::Skipped {
+                continue;
+            }
+            let Some(connection_id) = host.record.connection_id else {
+                continue;
+            };
+            let output = host.output.trim_end();
+            match groups
+                .iter_mut()
+                .find(|g| g.exit_code == host.record.exit_code && g.output == output)
+            {
+                Some(group) => group.connection_ids.push(connection_id),
+                None => groups.push(HostOutputGroup {
+                    output: output.to_string(),
+                    exit_code: host.record.exit_code,
+                    connection_ids: vec![connection_id],
+                }),
+            }
+        }
+        // Stable: equal-sized groups keep host order.
+        groups.sort_by_key(|group| std::cmp::Reverse(group.connection_ids.len()));
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conn(alias: &str, group: Option<&str>, tags: &[&str]) -> Connection {
+        let mut c =
+            Connection::new_manual(alias.into(), format!("{}.example", alias), "root".into());
+        c.group = group.map(String::from);
+        c.tags = tags.iter().map(|t| t.to_string()).collect();
+        c
+    }
+
+    #[test]
+    fn selectors_resolve_connections() {
+        let conns = vec![
+            conn("web1", Some("Web"), &["prod"]),
+            conn("web2", Some("web"), &["staging"]),
+            conn("db1", Some("DB"), &["Prod"]),
+        ];
+        let aliases = |target: MultiTarget| {
+            target
+                .resolve(&conns)
+                .iter()
+                .map(|c| c.alias.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            aliases(MultiTarget::new(HostSelector::Group("WEB".into()))),
+            vec!["web1", "web2"]
+        );
+        assert_eq!(
+            aliases(MultiTarget::new(HostSelector::Tag("prod".into()))),
+            vec!["web1", "db1"]
+        );
+        assert_eq!(
+            aliases(MultiTarget::new(HostSelector::Connections(vec![
+                conns[2].id,
+                Uuid::new_v4(),
+                conns[0].id,
+                conns[2].id
+            ]))),
+            vec!["db1", "web1"]
+        );
+    }
+
+    #[test]
+    fn events_drive_host_status_and_records() {
+        let conns = [
+            conn("a", None, &[]),
+            conn("b", None, &[]),
+            conn("c", None, &[]),
+        ];
+        let refs: Vec<&Connection> = conns.iter().collect();
+        let mut run = MultiRun::new(
+            Uuid::new_v4(),
+            MultiTarget::new(HostSelector::Tag("x".into())),
+            &refs,
+        );
+
+        assert!(run.apply(MultiRunEvent::Started(conns[0].id)).is_none());
+        run.apply(MultiRunEvent::Output(conns[0].id, "ok\n".into()));
+        let record = run
+            .apply(MultiRunEvent::Finished(conns[0].id, Some(0)))
+            .expect("finished host yields its record");
+        assert_eq!(record.output_log, "ok\n");
+        assert_eq!(record.connection_id, Some(conns[0].id));
+        // A late event for a finished host is ignored.
+        assert!(run.apply(MultiRunEvent::TimedOut(conns[0].id)).is_none());
+
+        run.apply(MultiRunEvent::Started(conns[1].id));
+        run.apply(MultiRunEvent::TimedOut(conns[1].id));
+        run.apply(MultiRunEvent::Skipped(conns[2].id));
+
+        assert!(run.is_done());
+        assert_eq!(
+            run.summary(),
+            MultiRunSummary {
+                succeeded: 1,
+                timed_out: 1,
+                skipped: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            run.host(conns[1].id).map(|h| h.status),
+            Some(HostRunStatus::TimedOut)
+        );
+    }
+
+    #[test]
+    fn identical_outputs_are_grouped() {
+        let conns = [
+            conn("a", None, &[]),
+            conn("b", None, &[]),
+            conn("c", None, &[]),
+            conn("d", None, &[]),
+        ];
+        let refs: Vec<&Connection> = conns.iter().collect();
+        let mut run = MultiRun::new(
+            Uuid::new_v4(),
+            MultiTarget::new(HostSelector::Tag("x".into())),
+            &refs,
+        );
+        let finish = |run: &mut MultiRun, i: usize, out: &str, code: i32| {
+            run.apply(MultiRunEvent::Output(conns[i].id, out.into()));
+            run.apply(MultiRunEvent::Finished(conns[i].id, Some(code)));
+        };
+        finish(&mut run, 0, "disk 40%\n", 0);
+        finish(&mut run, 1, "disk 91%\n", 1);
+        finish(&mut run, 2, "disk 40%", 0);
+        run.apply(MultiRunEvent::Skipped(conns[3].id));
+
+        let groups = run.output_groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].connection_ids, vec![conns[0].id, conns[2].id]);
+        assert_eq!(groups[0].exit_code, Some(0));
+        assert_eq!(groups[1].connection_ids, vec![conns[1].id]);
+    }
+
+    #[test]
+    fn host_output_masks_secrets_split_across_chunks() {
+        let conns = [conn("a", None, &[])];
+        let refs: Vec<&Connection> = conns.iter().collect();
+        let mut run = MultiRun::new(
+            Uuid::new_v4(),
+            MultiTarget::new(HostSelector::Tag("x".into())),
+            &refs,
+        );
+        let values = [("db_password".to_string(), "hunter2".to_string())].into();
+        run.hosts[0].record = run.hosts[0].record.clone().with_variables(&values);
+
+        run.apply(MultiRunEvent::Output(conns[0].id, "pass hun".into()));
+        run.apply(MultiRunEvent::Output(conns[0].id, "ter2\n".into()));
+        let output = &run.host(conns[0].id).expect("host").output;
+        assert!(!output.contains("hunter2"));
+        assert!(output.starts_with("pass "));
+    }
+
+    #[test]
+    fn multi_target_defaults_survive_old_json() {
+        let target: MultiTarget =
+            serde_json::from_str(r#"{"hosts":{"Group":"web"}}"#).expect("parse");
+        assert_eq!(target.concurrency, 5);
+        assert_eq!(target.timeout_secs, None);
+        assert!(!target.rolling);
+    }
+}
@@ -1,4 +1,7 @@
+use super::forward_proxy::{parse_destination_rules, ProxySettings};
 use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use std::str::FromStr;
 use uuid::Uuid;
 
 /// Validate that a port number is non-zero (valid for binding/connecting).
@@ -10,6 +13,122 @@ pub fn validate_port(port: u16) -> Result<(), &'static str> {
     }
 }
 
+/// Host to bind for a listen address as typed: brackets around IPv6
+/// literals are dropped, and an empty host or `localhost` means IPv4
+/// loopback, as with OpenSSH's `-L port`.
+pub fn bind_host(host: &str) -> String {
+    let host = host.trim();
+    let host = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    if host.is_empty() || host.eq_ignore_ascii_case("localhost") {
+        "127.0.0.1".to_string()
+    } else {
+        host.to_string()
+    }
+}
+
+/// Whether a listen address only accepts connections from this machine.
+/// Host names other than `localhost` count as reachable from outside.
+pub fn is_loopback_host(host: &str) -> bool {
+    bind_host(host)
+        .parse::<IpAddr>()
+        .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// An address block allowed to connect to a forward that listens beyond
+/// loopback, e.g. `192.168.1.0/24`, `fd00::/8` or a single address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientCidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl ClientCidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // An IPv4 client reaching a dual-stack `::` listener shows up as
+        // an IPv4-mapped IPv6 address.
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            v4 => v4,
+        };
+        match (self.network, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_matches(&net.octets(), &ip.octets(), self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_matches(&net.octets(), &ip.octets(), self.prefix)
+            }
+            _ => false,
+        }
+    }
+
+    /// Network and dotted mask of an IPv4 block, as PAC's `isInNet` takes
+    /// them; `None` for IPv6.
+    pub fn ipv4_mask(&self) -> Option<(Ipv4Addr, Ipv4Addr)> {
+        let IpAddr::V4(network) = self.network else {
+            return None;
+        };
+        let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+        Some((
+            Ipv4Addr::from(u32::from(network) & mask),
+            Ipv4Addr::from(mask),
+        ))
+    }
+}
+
+fn prefix_matches(network: &[u8], ip: &[u8], prefix: u8) -> bool {
+    let full = (prefix / 8) as usize;
+    if network[..full] != ip[..full] {
+        return false;
+    }
+    let rest = prefix % 8;
+    rest == 0 || {
+        let mask = 0xffu8 << (8 - rest);
+        network[full] & mask == ip[full] & mask
+    }
+}
+
+impl FromStr for ClientCidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("{} is not an IP address", addr))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("{} is not a prefix length of 0-{}", p, max))?,
+            None => max,
+        };
+        Ok(Self { network, prefix })
+    }
+}
+
+impl std::fmt::Display for ClientCidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// Parse a comma- or whitespace-separated list of address blocks.
+pub fn parse_client_allowlist(text: &str) -> Result<Vec<ClientCidr>, String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ForwardDirection {
     LocalToRemote,
@@ -24,6 +143,26 @@ pub enum ForwardStatus {
     Active,
     Error,
     Stopping,
+    /// The supervisor lost the forward and is bringing it back.
+    Reconnecting,
+}
+
+/// A check that a running forward still reaches its far end, on top of the
+/// supervisor watching the SSH transport. Dynamic forwards have no single
+/// far end and are not probed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardProbe {
+    pub kind: ProbeKind,
+    /// Seconds between two probes.
+    pub interval_secs: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProbeKind {
+    /// Open a TCP connection to the far end.
+    Tcp,
+    /// `GET path` on the far end. Any answer below 500 counts as up.
+    Http { path: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,8 +174,33 @@ pub struct PortForward {
     pub local_port: u16,
     pub remote_host: String,
     pub remote_port: u16,
+    /// Start with ShellDeck and keep the forward up under the supervisor.
     pub auto_start: bool,
     pub label: Option<String>,
+    #[serde(default)]
+    pub probe: Option<ForwardProbe>,
+    /// Confirms that a local or dynamic forward may listen on a
+    /// non-loopback address, where other machines can reach it.
+    #[serde(default)]
+    pub allow_lan: bool,
+    /// Clients allowed besides this machine when listening beyond
+    /// loopback; empty lets any client in. Entries are CIDR blocks.
+    #[serde(default)]
+    pub allowed_clients: Vec<String>,
+    /// Listen on the next free port when `local_port` is taken.
+    #[serde(default)]
+    pub auto_port: bool,
+    /// Record HTTP/1.1 requests and responses passing through a local forward.
+    #[serde(default)]
+    pub inspect_http: bool,
+    /// Authentication, destination rules and PAC file of a dynamic forward.
+    #[serde(default)]
+    pub proxy: ProxySettings,
+    /// Read from a `LocalForward` or `RemoteForward` line of the
+    /// connection's `Host` block; it follows that line when the SSH config
+    /// changes.
+    #[serde(default)]
+    pub from_ssh_config: bool,
     #[serde(skip)]
     pub status: ForwardStatus,
     #[serde(skip)]
@@ -62,6 +226,13 @@ impl PortForward {
             remote_port,
             auto_start: false,
             label: None,
+            probe: None,
+            allow_lan: false,
+            allowed_clients: Vec::new(),
+            auto_port: false,
+            inspect_http: false,
+            proxy: ProxySettings::default(),
+            from_ssh_config: false,
             status: ForwardStatus::Inactive,
             bytes_sent: 0,
             bytes_received: 0,
@@ -84,6 +255,13 @@ impl PortForward {
             remote_port,
             auto_start: false,
             label: None,
+            probe: None,
+            allow_lan: false,
+            allowed_clients: Vec::new(),
+            auto_port: false,
+            inspect_http: false,
+            proxy: ProxySettings::default(),
+            from_ssh_config: false,
             status: ForwardStatus::Inactive,
             bytes_sent: 0,
             bytes_received: 0,
@@ -118,6 +296,54 @@ impl PortForward {
         fwd
     }
 
+    /// Whether this forward listens on this machine, as opposed to the
+    /// remote end listening for a reverse forward.
+    pub fn listens_locally(&self) -> bool {
+        self.direction != ForwardDirection::RemoteToLocal
+    }
+
+    /// Whether other machines could reach the local listener.
+    pub fn exposes_to_network(&self) -> bool {
+        self.listens_locally() && !is_loopback_host(&self.local_host)
+    }
+
+    /// Why the local listener must not be started as configured: a
+    /// non-loopback address that was not confirmed, a proxy open to the
+    /// network without authentication, or a bad allowlist or rule.
+    pub fn bind_error(&self) -> Option<String> {
+        let dynamic = self.direction == ForwardDirection::Dynamic;
+        if dynamic {
+            if let Err(error) = parse_destination_rules(&self.proxy.rules.join("\n")) {
+                return Some(error);
+            }
+        }
+        if !self.exposes_to_network() {
+            return None;
+        }
+        if !self.allow_lan {
+            return Some(format!(
+                "{} is reachable from other machines and was not confirmed",
+                bind_host(&self.local_host)
+            ));
+        }
+        if dynamic && !self.proxy.requires_auth() {
+            return Some(format!(
+                "the proxy on {} is reachable from other machines and needs a username and password",
+                bind_host(&self.local_host)
+            ));
+        }
+        parse_client_allowlist(&self.allowed_clients.join(",")).err()
+    }
+
+    /// Parsed [`allowed_clients`](Self::allowed_clients), skipping entries
+    /// that do not parse.
+    pub fn client_allowlist(&self) -> Vec<ClientCidr> {
+        self.allowed_clients
+            .iter()
+            .filter_map(|entry| entry.parse().ok())
+            .collect()
+    }
+
     pub fn description(&self) -> String {
         match self.direction {
             ForwardDirection::LocalToRemote => {
@@ -133,7 +359,7 @@ impl PortForward {
                 )
             }
             ForwardDirection::Dynamic => {
-                format!("D SOCKS5 on {}:{}", self.local_host, self.local_port)
+                format!("D proxy on {}:{}", self.local_host, self.local_port)
             }
         }
     }
@@ -141,7 +367,7 @@ impl PortForward {
 
 #[cfg(test)]
 mod tests {
-    use super::validate_port;
+    use super::*;
 
     // SDTEST-030 — port 0 is the wildcard bind and OS-assign sentinel;
     // ShellDeck forwards must not silently accept it as a valid target.
@@ -159,4 +385,92 @@ mod tests {
             assert!(validate_port(p).is_ok(), "port {p} should be valid");
         }
     }
+
+    #[test]
+    fn bind_hosts_and_loopback_detection() {
+        assert_eq!(bind_host(""), "127.0.0.1");
+        assert_eq!(bind_host("localhost"), "127.0.0.1");
+        assert_eq!(bind_host("[::]"), "::");
+        assert_eq!(bind_host(" 0.0.0.0 "), "0.0.0.0");
+        for host in ["", "localhost", "127.0.0.1", "127.0.1.1", "::1", "[::1]"] {
+            assert!(is_loopback_host(host), "{host} is loopback");
+        }
+        for host in ["0.0.0.0", "::", "192.168.1.20", "devbox.lan"] {
+            assert!(!is_loopback_host(host), "{host} is reachable from outside");
+        }
+    }
+
+    #[test]
+    fn client_cidrs_match_v4_v6_and_mapped_addresses() {
+        let lan: ClientCidr = "192.168.1.0/24".parse().unwrap();
+        assert!(lan.contains("192.168.1.42".parse().unwrap()));
+        assert!(!lan.contains("192.168.2.1".parse().unwrap()));
+        assert!(lan.contains("::ffff:192.168.1.7".parse().unwrap()));
+
+        let odd: ClientCidr = "10.0.0.0/9".parse().unwrap();
+        assert!(odd.contains("10.127.255.255".parse().unwrap()));
+        assert!(!odd.contains("10.128.0.0".parse().unwrap()));
+
+        let ula: ClientCidr = "fd00::/8".parse().unwrap();
+        assert!(ula.contains("fd12:3456::1".parse().unwrap()));
+        assert!(!ula.contains("192.168.1.1".parse().unwrap()));
+
+        assert_eq!(
+            odd.ipv4_mask(),
+            Some(("10.0.0.0".parse().unwrap(), "255.128.0.0".parse().unwrap()))
+        );
+        assert_eq!(ula.ipv4_mask(), None);
+
+        let single: ClientCidr = "10.1.2.3".parse().unwrap();
+        assert_eq!(single.to_string(), "10.1.2.3/32");
+        assert!("0.0.0.0/0"
+            .parse::<ClientCidr>()
+            .unwrap()
+            .contains("8.8.8.8".parse().unwrap()));
+
+        assert!("192.168.1.0/33".parse::<ClientCidr>().is_err());
+        assert!("lan".parse::<ClientCidr>().is_err());
+        assert_eq!(
+            parse_client_allowlist("192.168.1.0/24, fd00::/8\n10.0.0.1")
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn exposing_a_listener_needs_confirmation_and_a_valid_allowlist() {
+        let mut forward = PortForward::new_local(Uuid::nil(), 3000, "localhost", 3000);
+        assert!(!forward.exposes_to_network());
+        assert_eq!(forward.bind_error(), None);
+
+        forward.local_host = "0.0.0.0".to_string();
+        assert!(forward.bind_error().is_some());
+        forward.allow_lan = true;
+        assert_eq!(forward.bind_error(), None);
+        forward.allowed_clients = vec!["192.168.1.0/24".into(), "nope".into()];
+        assert!(forward.bind_error().is_some());
+        assert_eq!(forward.client_allowlist().len(), 1);
+
+        // A reverse forward's local host is where traffic goes, not a listener.
+        let reverse = PortForward::new_remote(Uuid::nil(), 9222, "0.0.0.0", 9222);
+        assert!(!reverse.exposes_to_network());
+    }
+
+    #[test]
+    fn a_proxy_on_the_network_needs_authentication_and_valid_rules() {
+        let mut proxy = PortForward::new_local(Uuid::nil(), 1080, "", 0);
+        proxy.direction = ForwardDirection::Dynamic;
+        proxy.proxy.rules = vec!["allow *.corp.example".into()];
+        assert_eq!(proxy.bind_error(), None);
+        proxy.proxy.rules.push("route everything".into());
+        assert!(proxy.bind_error().is_some(), "bad rule even on loopback");
+        proxy.proxy.rules.pop();
+
+        proxy.local_host = "0.0.0.0".to_string();
+        proxy.allow_lan = true;
+        assert!(proxy.bind_error().unwrap().contains("username"));
+        proxy.proxy.username = Some("team".into());
+        assert_eq!(proxy.bind_error(), None);
+    }
 }
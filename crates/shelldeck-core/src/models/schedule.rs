@@ -0,0 +1,568 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{Result, ShellDeckError};
+
+/// A run whose slot passed less than this long ago is on time; anything older
+/// was missed (machine asleep, app closed).
+pub const ON_TIME_GRACE_MINUTES: i64 = 2;
+
+/// Upper bound on missed slots counted after a long sleep.
+const MAX_COUNTED_MISSES: usize = 1000;
+
+// ---------------------------------------------------------------------------
+// CronSchedule — five-field cron expressions, evaluated in local time
+// ---------------------------------------------------------------------------
+
+/// A parsed `minute hour day-of-month month day-of-week` expression. Fields
+/// accept `*`, numbers, ranges, lists and `/step`; months and weekdays also
+/// take three-letter names. `@hourly`, `@daily`, `@weekly`, `@monthly` and
+/// `@yearly` are accepted as shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Cron semantics: when both day fields are restricted, a day matching
+    /// either of them fires.
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+fn parse_value(raw: &str, min: u32, names: &[&str]) -> Option<u32> {
+    raw.parse::<u32>().ok().or_else(|| {
+        let lower = raw.to_ascii_lowercase();
+        names
+            .iter()
+            .position(|n| *n == lower)
+            .map(|idx| idx as u32 + min)
+    })
+}
+
+/// Parse one cron field into a bitmask of the allowed values.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Option<(u64, bool)> {
+    let mut mask = 0u64;
+    let mut restricted = false;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+            None => (item, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else {
+            restricted = true;
+            match range.split_once('-') {
+                Some((a, b)) => (parse_value(a, min, names)?, parse_value(b, min, names)?),
+                None => {
+                    let v = parse_value(range, min, names)?;
+                    // `5/15` means "from 5, every 15".
+                    (v, if item.contains('/') { max } else { v })
+                }
+            }
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        if range == "*" && step > 1 {
+            restricted = true;
+        }
+        let mut v = start;
+        while v <= end {
+            mask |= 1 << v;
+            v += step;
+        }
+    }
+    Some((mask, restricted))
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        let expanded = match expression.to_ascii_lowercase().as_str() {
+            "@hourly" => "0 * * * *".to_string(),
+            "@daily" | "@midnight" => "0 0 * * *".to_string(),
+            "@weekly" => "0 0 * * 0".to_string(),
+            "@monthly" => "0 0 1 * *".to_string(),
+            "@yearly" | "@annually" => "0 0 1 1 *".to_string(),
+            _ => expression.to_string(),
+        };
+        let invalid = || ShellDeckError::Config(format!("Invalid cron expression: {}", expression));
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        let (minutes, _) = parse_field(minute, 0, 59, &[]).ok_or_else(invalid)?;
+        let (hours, _) = parse_field(hour, 0, 23, &[]).ok_or_else(invalid)?;
+        let (days_of_month, dom_restricted) = parse_field(dom, 1, 31, &[]).ok_or_else(invalid)?;
+        let (months, _) = parse_field(month, 1, 12, &MONTH_NAMES).ok_or_else(invalid)?;
+        let (mut days_of_week, dow_restricted) =
+            parse_field(dow, 0, 7, &DAY_NAMES).ok_or_else(invalid)?;
+        // 7 is Sunday too.
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+
+        Ok(Self {
+            minutes,
+            hours,
+            days_of_month,
+            months,
+            days_of_week,
+            dom_restricted,
+            dow_restricted,
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = self.days_of_month & (1 << date.day()) != 0;
+        let dow = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.dom_restricted && self.dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+
+    /// First slot strictly after `after`, or `None` if the expression never
+    /// fires (e.g. `0 0 31 2 *`).
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let give_up = after.year() + 5;
+        while t.year() <= give_up {
+            if self.months & (1 << t.month()) == 0 {
+                let (y, m) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = NaiveDate::from_ymd_opt(y, m, 1)?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if !self.day_matches(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+                continue;
+            }
+            if self.hours & (1 << t.hour()) == 0 {
+                t = t.with_minute(0)? + Duration::hours(1);
+                continue;
+            }
+            if self.minutes & (1 << t.minute()) == 0 {
+                t += Duration::minutes(1);
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+
+    /// Slots that came up in `(last_checked, now]`.
+    pub fn due(&self, last_checked: NaiveDateTime, now: NaiveDateTime) -> DueRuns {
+        let mut due = DueRuns::default();
+        let mut latest = None;
+        let mut cursor = last_checked;
+        while let Some(slot) = self.next_after(cursor).filter(|slot| *slot <= now) {
+            // Every slot but the latest one was missed.
+            if latest.replace(slot).is_some() {
+                due.missed += 1;
+            }
+            if due.missed >= MAX_COUNTED_MISSES {
+                break;
+            }
+            cursor = slot;
+        }
+        if let Some(latest) = latest {
+            if now - latest <= Duration::minutes(ON_TIME_GRACE_MINUTES) {
+                due.on_time = true;
+            } else {
+                due.missed += 1;
+            }
+        }
+        due
+    }
+}
+
+/// What a scheduler tick found for one schedule.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DueRuns {
+    /// The most recent slot is within the grace period.
+    pub on_time: bool,
+    /// Slots that passed while nothing was checking.
+    pub missed: usize,
+}
+
+// ---------------------------------------------------------------------------
+// Triggers — what starts an unattended run
+// ---------------------------------------------------------------------------
+
+/// What to do with slots missed while the machine slept or ShellDeck was closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUpPolicy {
+    /// Run once for all the missed slots.
+    #[default]
+    RunOnce,
+    /// Only log them.
+    Skip,
+}
+
+impl CatchUpPolicy {
+    pub fn should_run(self, due: DueRuns) -> bool {
+        due.on_time || (due.missed > 0 && self == CatchUpPolicy::RunOnce)
+    }
+}
+
+/// Which site status changes fire an `OnSiteStatus` trigger.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SiteTransition {
+    #[default]
+    Any,
+    WentDown,
+    CameUp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerKind {
+    Schedule {
+        cron: String,
+        #[serde(default)]
+        catch_up: CatchUpPolicy,
+    },
+    /// `None` filters match any connection / tunnel / site.
+    OnConnect {
+        #[serde(default)]
+        connection_id: Option<Uuid>,
+    },
+    OnDisconnect {
+        #[serde(default)]
+        connection_id: Option<Uuid>,
+        /// Only when the connection was lost, not closed by the user.
+        #[serde(default)]
+        unexpected_only: bool,
+    },
+    OnTunnelStart {
+        #[serde(default)]
+        forward_id: Option<Uuid>,
+    },
+    OnSiteStatus {
+        #[serde(default)]
+        site_id: Option<Uuid>,
+        #[serde(default)]
+        change: SiteTransition,
+    },
+}
+
+/// Something that happened in the workspace and may start scripts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerEvent {
+    Connected {
+        connection_id: Uuid,
+    },
+    Disconnected {
+        connection_id: Uuid,
+        unexpected: bool,
+    },
+    TunnelStarted {
+        forward_id: Uuid,
+        connection_id: Uuid,
+    },
+    SiteStatusChanged {
+        site_id: Uuid,
+        connection_id: Uuid,
+        online: bool,
+    },
+}
+
+impl TriggerEvent {
+    /// The connection the event happened on.
+    pub fn connection_id(&self) -> Uuid {
+        match self {
+            Self::Connected { connection_id }
+            | Self::Disconnected { connection_id, .. }
+            | Self::TunnelStarted { connection_id, .. }
+            | Self::SiteStatusChanged { connection_id, .. } => *connection_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptTrigger {
+    pub id: Uuid,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub kind: TriggerKind,
+    /// Run on the connection the event happened on instead of the script's
+    /// own target. Ignored for schedules.
+    #[serde(default)]
+    pub run_on_event_host: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ScriptTrigger {
+    pub fn new(kind: TriggerKind) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            enabled: true,
+            kind,
+            run_on_event_host: false,
+        }
+    }
+
+    pub fn is_schedule(&self) -> bool {
+        matches!(self.kind, TriggerKind::Schedule { .. })
+    }
+
+    /// Whether `event` fires this trigger.
+    pub fn matches(&self, event: &TriggerEvent) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let accepts = |filter: &Option<Uuid>, id: &Uuid| filter.is_none_or(|f| f == *id);
+        match (&self.kind, event) {
+            (
+                TriggerKind::OnConnect { connection_id },
+                TriggerEvent::Connected { connection_id: id },
+            ) => accepts(connection_id, id),
+            (
+                TriggerKind::OnDisconnect {
+                    connection_id,
+                    unexpected_only,
+                },
+                TriggerEvent::Disconnected {
+                    connection_id: id,
+                    unexpected,
+                },
+            ) => accepts(connection_id, id) && (*unexpected || !unexpected_only),
+            (
+                TriggerKind::OnTunnelStart { forward_id },
+                TriggerEvent::TunnelStarted { forward_id: id, .. },
+            ) => accepts(forward_id, id),
+            (
+                TriggerKind::OnSiteStatus { site_id, change },
+                TriggerEvent::SiteStatusChanged {
+                    site_id: id,
+                    online,
+                    ..
+                },
+            ) => {
+                accepts(site_id, id)
+                    && match change {
+                        SiteTransition::Any => true,
+                        SiteTransition::WentDown => !online,
+                        SiteTransition::CameUp => *online,
+                    }
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+    }
+
+    fn next(expr: &str, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        CronSchedule::parse(expr).unwrap().next_after(after)
+    }
+
+    #[test]
+    fn every_fifteen_minutes() {
+        let t = at(2026, 3, 10, 9, 7);
+        assert_eq!(next("*/15 * * * *", t), Some(at(2026, 3, 10, 9, 15)));
+        assert_eq!(
+            next("*/15 * * * *", at(2026, 3, 10, 9, 45)),
+            Some(at(2026, 3, 10, 10, 0)),
+            "a slot exactly at `after` is not returned again"
+        );
+    }
+
+    #[test]
+    fn nightly_rolls_over_months_and_years() {
+        assert_eq!(
+            next("30 2 * * *", at(2026, 12, 31, 3, 0)),
+            Some(at(2027, 1, 1, 2, 30))
+        );
+        assert_eq!(
+            next("@monthly", at(2026, 1, 15, 0, 0)),
+            Some(at(2026, 2, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn names_ranges_lists_and_weekdays() {
+        // 2026-03-10 is a Tuesday.
+        let t = at(2026, 3, 10, 12, 0);
+        assert_eq!(next("0 9 * * mon-fri", t), Some(at(2026, 3, 11, 9, 0)));
+        assert_eq!(next("0 9 * * SAT,sun", t), Some(at(2026, 3, 14, 9, 0)));
+        assert_eq!(next("0 9 * * 7", t), Some(at(2026, 3, 15, 9, 0)));
+        assert_eq!(next("0 0 1 jun *", t), Some(at(2026, 6, 1, 0, 0)));
+        assert_eq!(next("5/20 * * * *", t), Some(at(2026, 3, 10, 12, 5)));
+    }
+
+    // Both day fields restricted: either one matching is enough.
+    #[test]
+    fn restricted_day_fields_are_ored() {
+        // The 13th, or any Friday: from Tue 2026-03-10, Friday the 13th
+        // comes first anyway, so check from the 14th.
+        let t = at(2026, 3, 14, 0, 0);
+        assert_eq!(next("0 0 13 * fri", t), Some(at(2026, 3, 20, 0, 0)));
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for expr in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "* * * * funday",
+        ] {
+            assert!(CronSchedule::parse(expr).is_err(), "{expr:?}");
+        }
+        assert_eq!(next("0 0 31 2 *", at(2026, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn due_distinguishes_on_time_from_missed() {
+        let cron = CronSchedule::parse("*/15 * * * *").unwrap();
+        let last = at(2026, 3, 10, 9, 0);
+
+        assert_eq!(cron.due(last, at(2026, 3, 10, 9, 10)), DueRuns::default());
+        assert_eq!(
+            cron.due(last, at(2026, 3, 10, 9, 16)),
+            DueRuns {
+                on_time: true,
+                missed: 0
+            }
+        );
+        // Slept from 9:00 to 10:20: 9:15, 9:30, 9:45, 10:00 and 10:15 passed.
+        assert_eq!(
+            cron.due(last, at(2026, 3, 10, 10, 20)),
+            DueRuns {
+                on_time: false,
+                missed: 5
+            }
+        );
+        // Woke up right at a slot: it runs on time, the rest were missed.
+        assert_eq!(
+            cron.due(last, at(2026, 3, 10, 10, 16)),
+            DueRuns {
+                on_time: true,
+                missed: 4
+            }
+        );
+    }
+
+    #[test]
+    fn catch_up_policy() {
+        let missed = DueRuns {
+            on_time: false,
+            missed: 3,
+        };
+        assert!(CatchUpPolicy::RunOnce.should_run(missed));
+        assert!(!CatchUpPolicy::Skip.should_run(missed));
+        let on_time = DueRuns {
+            on_time: true,
+            missed: 0,
+        };
+        assert!(CatchUpPolicy::Skip.should_run(on_time));
+        assert!(!CatchUpPolicy::RunOnce.should_run(DueRuns::default()));
+    }
+
+    #[test]
+    fn event_triggers_match_their_filters() {
+        let web = Uuid::new_v4();
+        let db = Uuid::new_v4();
+
+        let any_connect = ScriptTrigger::new(TriggerKind::OnConnect {
+            connection_id: None,
+        });
+        assert!(any_connect.matches(&TriggerEvent::Connected { connection_id: web }));
+        assert!(!any_connect.matches(&TriggerEvent::Disconnected {
+            connection_id: web,
+            unexpected: true
+        }));
+
+        let host_down = ScriptTrigger::new(TriggerKind::OnDisconnect {
+            connection_id: Some(db),
+            unexpected_only: true,
+        });
+        assert!(host_down.matches(&TriggerEvent::Disconnected {
+            connection_id: db,
+            unexpected: true
+        }));
+        assert!(!host_down.matches(&TriggerEvent::Disconnected {
+            connection_id: db,
+            unexpected: false
+        }));
+        assert!(!host_down.matches(&TriggerEvent::Disconnected {
+            connection_id: web,
+            unexpected: true
+        }));
+
+        let site = Uuid::new_v4();
+        let went_down = ScriptTrigger::new(TriggerKind::OnSiteStatus {
+            site_id: None,
+            change: SiteTransition::WentDown,
+        });
+        let change = |online| TriggerEvent::SiteStatusChanged {
+            site_id: site,
+            connection_id: web,
+            online,
+        };
+        assert!(went_down.matches(&change(false)));
+        assert!(!went_down.matches(&change(true)));
+
+        let mut disabled = any_connect.clone();
+        disabled.enabled = false;
+        assert!(!disabled.matches(&TriggerEvent::Connected { connection_id: web }));
+    }
+
+    #[test]
+    fn triggers_round_trip_through_json() {
+        let trigger = ScriptTrigger::new(TriggerKind::Schedule {
+            cron: "*/15 * * * *".into(),
+            catch_up: CatchUpPolicy::Skip,
+        });
+        let json = serde_json::to_string(&trigger).unwrap();
+        assert!(json.contains(r#""type":"schedule""#));
+        let back: ScriptTrigger = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, trigger);
+
+        let minimal: ScriptTrigger = serde_json::from_str(&format!(
+            r#"{{"id":"{}","kind":{{"type":"on_tunnel_start"}}}}"#,
+            Uuid::nil()
+        ))
+        .unwrap();
+        assert!(minimal.enabled);
+        assert_eq!(
+            minimal.kind,
+            TriggerKind::OnTunnelStart { forward_id: None }
+        );
+    }
+}
@@ -3,6 +3,9 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use super::multi_run::MultiTarget;
+use super::schedule::ScriptTrigger;
+
 // ---------------------------------------------------------------------------
 // ScriptLanguage — determines how the script body gets executed
 // ---------------------------------------------------------------------------
@@ -311,7 +314,7 @@ impl PackageManager {
 }
 
 // ---------------------------------------------------------------------------
-// ScriptTarget (unchanged)
+// ScriptTarget
 // ---------------------------------------------------------------------------
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -319,18 +322,140 @@ pub enum ScriptTarget {
     Local,
     Remote(Uuid),
     AskOnRun,
+    /// Several connections at once (explicit list, group or tag).
+    Multi(MultiTarget),
 }
 
 // ---------------------------------------------------------------------------
 // ScriptVariable — template variable metadata
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What kind of value a variable takes; drives the prompt widget and the
+/// validation applied before a run.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VariableKind {
+    #[default]
+    String,
+    Int,
+    Bool,
+    /// One of a fixed list of values.
+    Choice(Vec<String>),
+    Path,
+    /// Host name of one of the saved connections.
+    Connection,
+    /// Never stored in the script or the execution history; the last value
+    /// lives in the OS keychain.
+    Secret,
+}
+
+/// Show a variable only when another one is set (or set to `equals`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VariableCondition {
+    pub variable: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<String>,
+}
+
+impl VariableCondition {
+    /// Without `equals`, any non-empty value other than `false` satisfies
+    /// the condition, so it works for both text and bool variables.
+    pub fn is_met(&self, values: &HashMap<String, String>) -> bool {
+        let value = values.get(&self.variable).map(|v| v.trim()).unwrap_or("");
+        match &self.equals {
+            Some(expected) => value == expected,
+            None => !value.is_empty() && value != "false",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableError {
+    Required,
+    NotAnInteger,
+    NotABoolean,
+    NotAChoice,
+    PatternMismatch,
+    /// The variable's own `pattern` does not compile.
+    InvalidPattern,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ScriptVariable {
     pub name: String,
     pub label: Option<String>,
     pub description: Option<String>,
     pub default_value: Option<String>,
+    // Typed parameters — all #[serde(default)] so untyped scripts still load
+    #[serde(default)]
+    pub kind: VariableKind,
+    #[serde(default)]
+    pub required: bool,
+    /// Regex the whole value must match (non-empty values only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depends_on: Option<VariableCondition>,
+}
+
+impl ScriptVariable {
+    pub fn is_secret(&self) -> bool {
+        self.kind == VariableKind::Secret
+    }
+
+    /// Whether the variable applies given the other values of the run.
+    pub fn is_active(&self, values: &HashMap<String, String>) -> bool {
+        self.depends_on
+            .as_ref()
+            .is_none_or(|condition| condition.is_met(values))
+    }
+
+    pub fn validate(&self, value: &str) -> Result<(), VariableError> {
+        let value = value.trim();
+        if value.is_empty() {
+            return if self.required {
+                Err(VariableError::Required)
+            } else {
+                Ok(())
+            };
+        }
+        match &self.kind {
+            VariableKind::Int if value.parse::<i64>().is_err() => {
+                return Err(VariableError::NotAnInteger);
+            }
+            VariableKind::Bool if !matches!(value, "true" | "false") => {
+                return Err(VariableError::NotABoolean);
+            }
+            VariableKind::Choice(options) if !options.iter().any(|o| o == value) => {
+                return Err(VariableError::NotAChoice);
+            }
+            _ => {}
+        }
+        if let Some(pattern) = self.pattern.as_deref().filter(|p| !p.is_empty()) {
+            let re = regex::Regex::new(&format!("^(?:{})$", pattern))
+                .map_err(|_| VariableError::InvalidPattern)?;
+            if !re.is_match(value) {
+                return Err(VariableError::PatternMismatch);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Validate every active variable against `values`, in declaration order.
+/// Variables whose dependency is not met are skipped.
+pub fn validate_variables(
+    variables: &[ScriptVariable],
+    values: &HashMap<String, String>,
+) -> Vec<(String, VariableError)> {
+    variables
+        .iter()
+        .filter(|var| var.is_active(values))
+        .filter_map(|var| {
+            let value = values.get(&var.name).map(String::as_str).unwrap_or("");
+            var.validate(value).err().map(|e| (var.name.clone(), e))
+        })
+        .collect()
 }
 
 /// Extract `{{name}}` and `{{name:default}}` variable references from a script body.
@@ -420,6 +545,9 @@ pub struct Script {
     pub template_id: Option<String>,
     #[serde(default)]
     pub variables: Vec<ScriptVariable>,
+    /// Schedules and events that run the script unattended.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub triggers: Vec<ScriptTrigger>,
 }
 
 impl Script {
@@ -444,6 +572,7 @@ impl Script {
             is_template: false,
             template_id: None,
             variables: Vec::new(),
+            triggers: Vec::new(),
         }
     }
 
@@ -471,25 +600,35 @@ impl Script {
             .into_iter()
             .map(|(name, inline_default)| {
                 if let Some(meta) = self.variables.iter().find(|v| v.name == name) {
-                    // Prefer metadata, but fall back to inline default if metadata has none
-                    ScriptVariable {
-                        name: meta.name.clone(),
-                        label: meta.label.clone(),
-                        description: meta.description.clone(),
-                        default_value: meta.default_value.clone().or(inline_default),
-                    }
+                    let mut var = meta.clone();
+                    // Prefer metadata, but fall back to inline default if metadata has
+                    // none. Secrets never take a default from the script.
+                    var.default_value = if var.is_secret() {
+                        None
+                    } else {
+                        meta.default_value.clone().or(inline_default)
+                    };
+                    var
                 } else {
                     ScriptVariable {
                         name,
-                        label: None,
-                        description: None,
                         default_value: inline_default,
+                        ..Default::default()
                     }
                 }
             })
             .collect()
     }
 
+    /// Names of the variables declared as [`VariableKind::Secret`].
+    pub fn secret_variable_names(&self) -> Vec<String> {
+        self.variables
+            .iter()
+            .filter(|v| v.is_secret())
+            .map(|v| v.name.clone())
+            .collect()
+    }
+
     /// Built-in: Check disk usage
     pub fn builtin_disk_usage() -> Self {
         let mut s = Self::new(
@@ -526,7 +665,11 @@ impl Script {
 
 #[cfg(test)]
 mod tests {
-    use super::{extract_variables, ScriptLanguage};
+    use super::{
+        extract_variables, validate_variables, Script, ScriptLanguage, ScriptTarget,
+        ScriptVariable, VariableCondition, VariableError, VariableKind,
+    };
+    use std::collections::HashMap;
 
     // SDTEST-037 — every built-in ScriptLanguage produces a runnable
     // spec: non-empty binary and non-empty args. Adding a new variant
@@ -680,4 +823,116 @@ mod tests {
         assert!(names.contains(&"handlebars_var"));
         assert_eq!(vars.len(), 3, "current parser doesn't fence-skip");
     }
+
+    fn var(name: &str, kind: VariableKind) -> ScriptVariable {
+        ScriptVariable {
+            name: name.to_string(),
+            kind,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn typed_variables_validate_their_values() {
+        let port = ScriptVariable {
+            required: true,
+            ..var("port", VariableKind::Int)
+        };
+        assert_eq!(port.validate(""), Err(VariableError::Required));
+        assert_eq!(port.validate("80a"), Err(VariableError::NotAnInteger));
+        assert_eq!(port.validate(" 8080 "), Ok(()));
+
+        let verbose = var("verbose", VariableKind::Bool);
+        assert_eq!(verbose.validate(""), Ok(()), "optional and empty");
+        assert_eq!(verbose.validate("yes"), Err(VariableError::NotABoolean));
+        assert_eq!(verbose.validate("true"), Ok(()));
+
+        let env = var(
+            "env",
+            VariableKind::Choice(vec!["staging".into(), "production".into()]),
+        );
+        assert_eq!(env.validate("prod"), Err(VariableError::NotAChoice));
+        assert_eq!(env.validate("production"), Ok(()));
+    }
+
+    // The pattern is anchored: it must match the whole value.
+    #[test]
+    fn pattern_matches_whole_value() {
+        let branch = ScriptVariable {
+            pattern: Some("[a-z0-9/-]+".into()),
+            ..var("branch", VariableKind::String)
+        };
+        assert_eq!(branch.validate("feature/x-1"), Ok(()));
+        assert_eq!(
+            branch.validate("main; rm -rf /"),
+            Err(VariableError::PatternMismatch)
+        );
+
+        let broken = ScriptVariable {
+            pattern: Some("([".into()),
+            ..var("broken", VariableKind::String)
+        };
+        assert_eq!(broken.validate("x"), Err(VariableError::InvalidPattern));
+    }
+
+    #[test]
+    fn dependent_variables_are_only_checked_when_active() {
+        let tls = var("tls", VariableKind::Bool);
+        let cert = ScriptVariable {
+            required: true,
+            depends_on: Some(VariableCondition {
+                variable: "tls".into(),
+                equals: None,
+            }),
+            ..var("cert", VariableKind::Path)
+        };
+        let vars = vec![tls, cert];
+        let values = |tls: &str| -> HashMap<String, String> {
+            HashMap::from([("tls".to_string(), tls.to_string())])
+        };
+
+        assert!(validate_variables(&vars, &values("false")).is_empty());
+        assert!(validate_variables(&vars, &HashMap::new()).is_empty());
+        assert_eq!(
+            validate_variables(&vars, &values("true")),
+            vec![("cert".to_string(), VariableError::Required)]
+        );
+
+        let exact = VariableCondition {
+            variable: "env".into(),
+            equals: Some("production".into()),
+        };
+        let env = |v: &str| HashMap::from([("env".to_string(), v.to_string())]);
+        assert!(exact.is_met(&env("production")));
+        assert!(!exact.is_met(&env("staging")));
+    }
+
+    // Scripts saved before typed variables existed load as plain strings.
+    #[test]
+    fn untyped_variable_metadata_still_deserializes() {
+        let json = r#"{"name":"host","label":"Host","description":null,"default_value":"a"}"#;
+        let var: ScriptVariable = serde_json::from_str(json).unwrap();
+        assert_eq!(var.kind, VariableKind::String);
+        assert!(!var.required);
+        assert!(var.pattern.is_none() && var.depends_on.is_none());
+
+        let kind: VariableKind = serde_json::from_str(r#"{"choice":["a","b"]}"#).unwrap();
+        assert_eq!(kind, VariableKind::Choice(vec!["a".into(), "b".into()]));
+    }
+
+    // A secret's value must never come from the script itself.
+    #[test]
+    fn secret_variables_drop_script_defaults() {
+        let mut script = Script::new(
+            "deploy".into(),
+            "deploy --token {{token:abc}} --to {{host:web1}}".into(),
+            ScriptTarget::Local,
+        );
+        script.variables.push(var("token", VariableKind::Secret));
+        let resolved = script.resolved_variables();
+        assert_eq!(resolved[0].name, "token");
+        assert!(resolved[0].default_value.is_none());
+        assert_eq!(resolved[1].default_value.as_deref(), Some("web1"));
+        assert_eq!(script.secret_variable_names(), vec!["token".to_string()]);
+    }
 }
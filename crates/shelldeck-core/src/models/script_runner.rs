@@ -17,13 +17,91 @@ pub struct ScriptCommand {
     pub env_vars: Vec<(String, String)>,
 }
 
-/// Replace `{{name}}` and `{{name:default}}` placeholders in `body` with values from `values`.
+/// Quote a variable value for the language it is substituted into, so a
+/// value can never break out of its placeholder.
+///
+/// Shell-like bodies get plain words through unchanged and anything else
+/// single-quoted. In code and SQL bodies the value is escaped for use inside
+/// a string literal: write `"{{name}}"` / `'{{name}}'` in the script.
+pub fn quote_variable_value(language: &ScriptLanguage, value: &str) -> String {
+    match language {
+        ScriptLanguage::Shell
+        | ScriptLanguage::Docker
+        | ScriptLanguage::DockerCompose
+        | ScriptLanguage::Systemd
+        | ScriptLanguage::Nginx
+        | ScriptLanguage::Custom(_) => {
+            if is_plain_word(value) {
+                value.to_string()
+            } else {
+                shell_escape(value)
+            }
+        }
+        ScriptLanguage::Python
+        | ScriptLanguage::Node
+        | ScriptLanguage::Bun
+        | ScriptLanguage::Php => {
+            let mut out = String::with_capacity(value.len());
+            for c in value.chars() {
+                match c {
+                    '\\' => out.push_str("\\\\"),
+                    '\'' => out.push_str("\\'"),
+                    '"' => out.push_str("\\\""),
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    // Template literals (JS) and double-quoted strings (PHP)
+                    '`' | '$' if !matches!(language, ScriptLanguage::Python) => {
+                        out.push('\\');
+                        out.push(c);
+                    }
+                    _ => out.push(c),
+                }
+            }
+            out
+        }
+        ScriptLanguage::Mysql => value.replace('\\', "\\\\").replace('\'', "''"),
+        ScriptLanguage::Postgresql => value.replace('\'', "''"),
+    }
+}
+
+fn is_plain_word(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_./:@%+=,".contains(c))
+}
+
+fn is_shell_like(language: &ScriptLanguage) -> bool {
+    matches!(
+        language,
+        ScriptLanguage::Shell
+            | ScriptLanguage::Docker
+            | ScriptLanguage::DockerCompose
+            | ScriptLanguage::Systemd
+            | ScriptLanguage::Nginx
+            | ScriptLanguage::Custom(_)
+    )
+}
+
+/// Replace `{{name}}` and `{{name:default}}` placeholders in `body` with values from `values`,
+/// quoted for `language` (see [`quote_variable_value`]).
+///
+/// In shell-like bodies every value is a self-contained single-quoted word:
+/// a placeholder inside `"…"` or `'…'` closes that string around the value
+/// and reopens it after (`echo "Deploying {{branch}}"` becomes
+/// `echo "Deploying "'a b'""`). Comments, command substitutions, backticks
+/// and here-documents are followed so the enclosing quote is known.
 /// Falls back to the inline default if present, or leaves the placeholder unchanged.
-pub fn substitute_variables(body: &str, values: &HashMap<String, String>) -> String {
+pub fn substitute_variables(
+    body: &str,
+    values: &HashMap<String, String>,
+    language: &ScriptLanguage,
+) -> String {
     let mut result = String::with_capacity(body.len());
     let bytes = body.as_bytes();
     let len = bytes.len();
     let mut i = 0;
+    let mut lexer = is_shell_like(language).then(ShellLexer::new);
 
     while i < len {
         if i + 1 < len && bytes[i] == b'{' && bytes[i + 1] == b'{' {
@@ -47,31 +125,357 @@ pub fn substitute_variables(body: &str, values: &HashMap<String, String>) -> Str
                     (inner.trim(), None)
                 };
                 if let Some(val) = values.get(name) {
-                    result.push_str(val);
+                    let quoted = match &lexer {
+                        Some(lexer) => lexer.quote(val),
+                        None => quote_variable_value(language, val),
+                    };
+                    result.push_str(&quoted);
                 } else if let Some(d) = default {
                     result.push_str(d);
                 } else {
                     // Leave placeholder unchanged
                     result.push_str(&body[i..j + 2]);
                 }
+                if let Some(lexer) = &mut lexer {
+                    lexer.skip_placeholder();
+                }
                 i = j + 2;
-            } else {
-                result.push('{');
-                i += 1;
+                continue;
             }
-        } else {
-            let ch = body[i..]
-                .chars()
-                .next()
-                .expect("i < body.len() guarantees a char");
-            result.push(ch);
-            i += ch.len_utf8();
         }
+        let next = match &mut lexer {
+            Some(lexer) => lexer.step(body, i),
+            None => i + char_len(body, i),
+        };
+        result.push_str(&body[i..next]);
+        i = next;
     }
 
     result
 }
 
+fn char_len(text: &str, at: usize) -> usize {
+    text[at..].chars().next().map_or(0, char::len_utf8)
+}
+
+/// A here-document whose body is still to come or being read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HereDoc {
+    delimiter: String,
+    /// `<<-`: leading tabs are ignored when matching the delimiter.
+    strip_tabs: bool,
+    /// False for a quoted delimiter (`<<'EOF'`), whose body is literal.
+    expands: bool,
+}
+
+/// What the shell makes of the text at a point of a script body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ShellFrame {
+    /// Unquoted code: the top level, `$(…)` (closed by `)`) or backticks
+    /// (closed by `` ` ``). `depth` counts open plain parentheses.
+    Code {
+        closer: Option<char>,
+        depth: usize,
+    },
+    /// `((…))` and `$((…))`.
+    Arithmetic {
+        depth: usize,
+    },
+    Single,
+    /// `$'…'`
+    AnsiC,
+    Double,
+    Comment,
+    HereDoc(HereDoc),
+}
+
+/// Follows shell quoting through a script body, one token at a time, so a
+/// substituted value can be quoted for the string it lands in.
+#[derive(Debug)]
+struct ShellLexer {
+    /// Innermost frame last; the top-level code frame is never popped.
+    frames: Vec<ShellFrame>,
+    /// Here-documents opened on the current line, read after its newline.
+    pending: Vec<HereDoc>,
+    word_start: bool,
+    line_start: bool,
+}
+
+impl ShellLexer {
+    fn new() -> Self {
+        Self {
+            frames: vec![ShellFrame::Code {
+                closer: None,
+                depth: 0,
+            }],
+            pending: Vec::new(),
+            word_start: true,
+            line_start: true,
+        }
+    }
+
+    fn top(&self) -> &ShellFrame {
+        self.frames
+            .last()
+            .expect("the top-level frame is never popped")
+    }
+
+    fn pop(&mut self) {
+        if self.frames.len() > 1 {
+            self.frames.pop();
+        }
+    }
+
+    /// A placeholder was replaced: it reads as part of a word.
+    fn skip_placeholder(&mut self) {
+        self.word_start = false;
+        self.line_start = false;
+    }
+
+    /// `value` as a self-contained word for the current position.
+    fn quote(&self, value: &str) -> String {
+        let word = || shell_escape(value);
+        let mut quoted = if is_plain_word(value) {
+            value.to_string()
+        } else {
+            match self.top() {
+                ShellFrame::Code { .. } | ShellFrame::Arithmetic { .. } => word(),
+                // Close the string, add the value, reopen it.
+                ShellFrame::Single => format!("'{}'", word()),
+                ShellFrame::AnsiC => format!("'{}$'", word()),
+                ShellFrame::Double => format!("\"{}\"", word()),
+                // A line break would end the comment and run the rest.
+                ShellFrame::Comment => shell_escape(&value.replace(['\n', '\r'], " ")),
+                ShellFrame::HereDoc(doc) => {
+                    let mut text = String::with_capacity(value.len());
+                    for c in value.chars() {
+                        if doc.expands && matches!(c, '\\' | '$' | '`') {
+                            text.push('\\');
+                        }
+                        text.push(c);
+                        // No line of the value may end the document early.
+                        if c == '\n' {
+                            text.push(' ');
+                        }
+                    }
+                    text
+                }
+            }
+        };
+        // Backticks strip one level of backslashes before parsing.
+        for _ in self.frames.iter().filter(|frame| {
+            matches!(
+                frame,
+                ShellFrame::Code {
+                    closer: Some('`'),
+                    ..
+                }
+            )
+        }) {
+            let mut escaped = String::with_capacity(quoted.len());
+            for c in quoted.chars() {
+                if matches!(c, '\\' | '$' | '`') {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+            }
+            quoted = escaped;
+        }
+        quoted
+    }
+
+    /// Consume the token starting at `i` and return where the next one
+    /// starts.
+    fn step(&mut self, body: &str, i: usize) -> usize {
+        let rest = &body[i..];
+        let c = rest.chars().next().expect("step is called before the end");
+        let after = rest[c.len_utf8()..].chars().next();
+        // A backslash escapes the next character, unless that starts a
+        // placeholder, which is still substituted.
+        let escaped_len = match after {
+            Some(next) if next != '{' => 1 + next.len_utf8(),
+            _ => 1,
+        };
+        let line_start = std::mem::replace(&mut self.line_start, false);
+
+        match self.top().clone() {
+            ShellFrame::Code { closer, depth } => {
+                let word_start = std::mem::replace(&mut self.word_start, false);
+                match c {
+                    '\\' => return i + escaped_len,
+                    '\'' => self.frames.push(ShellFrame::Single),
+                    '"' => self.frames.push(ShellFrame::Double),
+                    '$' if rest.starts_with("$'") => {
+                        self.frames.push(ShellFrame::AnsiC);
+                        return i + 2;
+                    }
+                    '$' if rest.starts_with("$((") => {
+                        self.frames.push(ShellFrame::Arithmetic { depth: 0 });
+                        return i + 3;
+                    }
+                    '$' if rest.starts_with("$(") => {
+                        self.open_code(Some(')'));
+                        return i + 2;
+                    }
+                    '(' if word_start && rest.starts_with("((") => {
+                        self.frames.push(ShellFrame::Arithmetic { depth: 0 });
+                        return i + 2;
+                    }
+                    '`' if closer == Some('`') => {
+                        self.pop();
+                    }
+                    '`' => self.open_code(Some('`')),
+                    '(' => {
+                        self.set_depth(depth + 1);
+                        self.word_start = true;
+                    }
+                    ')' if depth > 0 => {
+                        self.set_depth(depth - 1);
+                        self.word_start = true;
+                    }
+                    ')' if closer == Some(')') => self.pop(),
+                    '#' if word_start => self.frames.push(ShellFrame::Comment),
+                    '<' if rest.starts_with("<<") && !rest.starts_with("<<<") => {
+                        return self.here_doc_operator(body, i + 2);
+                    }
+                    '\n' => self.end_line(),
+                    ' ' | '\t' | ';' | '&' | '|' | '<' | '>' => self.word_start = true,
+                    _ => {}
+                }
+            }
+            ShellFrame::Arithmetic { depth } => match c {
+                '(' => self.set_depth(depth + 1),
+                ')' if depth > 0 => self.set_depth(depth - 1),
+                ')' if rest.starts_with("))") => {
+                    self.pop();
+                    return i + 2;
+                }
+                _ => {}
+            },
+            ShellFrame::Single => {
+                if c == '\'' {
+                    self.pop();
+                }
+            }
+            ShellFrame::AnsiC => match c {
+                '\\' => return i + escaped_len,
+                '\'' => self.pop(),
+                _ => {}
+            },
+            ShellFrame::Double => match c {
+                '\\' => return i + escaped_len,
+                '"' => self.pop(),
+                '$' if rest.starts_with("$((") => {
+                    self.frames.push(ShellFrame::Arithmetic { depth: 0 });
+                    return i + 3;
+                }
+                '$' if rest.starts_with("$(") => {
+                    self.open_code(Some(')'));
+                    return i + 2;
+                }
+                '`' => self.open_code(Some('`')),
+                _ => {}
+            },
+            ShellFrame::Comment => {
+                if c == '\n' {
+                    // The newline itself is read by the enclosing code.
+                    self.pop();
+                    return i;
+                }
+            }
+            ShellFrame::HereDoc(doc) => {
+                if line_start {
+                    let line_end = rest.find('\n').map_or(body.len(), |end| i + end);
+                    let line = &body[i..line_end];
+                    let line = if doc.strip_tabs {
+                        line.trim_start_matches('\t')
+                    } else {
+                        line
+                    };
+                    if line == doc.delimiter {
+                        self.pop();
+                        self.word_start = true;
+                        self.end_line();
+                        return (line_end + 1).min(body.len());
+                    }
+                }
+                if c == '\n' {
+                    self.line_start = true;
+                }
+            }
+        }
+        i + c.len_utf8()
+    }
+
+    fn open_code(&mut self, closer: Option<char>) {
+        self.frames.push(ShellFrame::Code { closer, depth: 0 });
+        self.word_start = true;
+    }
+
+    fn set_depth(&mut self, new_depth: usize) {
+        if let Some(ShellFrame::Code { depth, .. } | ShellFrame::Arithmetic { depth }) =
+            self.frames.last_mut()
+        {
+            *depth = new_depth;
+        }
+    }
+
+    /// A command line ended: the first pending here-document starts.
+    fn end_line(&mut self) {
+        self.word_start = true;
+        if !self.pending.is_empty() {
+            let doc = self.pending.remove(0);
+            self.frames.push(ShellFrame::HereDoc(doc));
+            self.line_start = true;
+        }
+    }
+
+    /// Read the `-` and delimiter word following `<<` at `i`.
+    fn here_doc_operator(&mut self, body: &str, mut i: usize) -> usize {
+        let bytes = body.as_bytes();
+        let strip_tabs = bytes.get(i) == Some(&b'-');
+        if strip_tabs {
+            i += 1;
+        }
+        while matches!(bytes.get(i), Some(b' ' | b'\t')) {
+            i += 1;
+        }
+        let mut delimiter = String::new();
+        let mut expands = true;
+        let mut quote = None;
+        while i < body.len() {
+            let c = body[i..].chars().next().expect("i < len");
+            match (quote, c) {
+                (None, '\'' | '"') => {
+                    quote = Some(c);
+                    expands = false;
+                }
+                (Some(q), _) if c == q => quote = None,
+                (None, '\\') => {
+                    expands = false;
+                    i += 1;
+                    if let Some(next) = body[i..].chars().next() {
+                        delimiter.push(next);
+                        i += next.len_utf8();
+                    }
+                    continue;
+                }
+                (None, c) if c.is_whitespace() || ";&|()<>".contains(c) => break,
+                _ => delimiter.push(c),
+            }
+            i += c.len_utf8();
+        }
+        if !delimiter.is_empty() {
+            self.pending.push(HereDoc {
+                delimiter,
+                strip_tabs,
+                expands,
+            });
+        }
+        i
+    }
+}
+
 /// Build an executable command from a script, respecting its language.
 /// When `var_values` is `Some`, template variables in the body are substituted first.
 pub fn build_command(
@@ -80,7 +484,9 @@ pub fn build_command(
 ) -> ScriptCommand {
     let spec = script.language.runner_spec();
     let body: String = match var_values {
-        Some(values) if !values.is_empty() => substitute_variables(&script.body, values),
+        Some(values) if !values.is_empty() => {
+            substitute_variables(&script.body, values, &script.language)
+        }
         _ => script.body.clone(),
     };
 
@@ -279,9 +685,12 @@ pub fn get_install_command(pm_name: &str, dep: &ToolDependency) -> Option<String
 
 #[cfg(test)]
 mod tests {
-    use super::substitute_variables;
+    use super::{quote_variable_value, substitute_variables};
+    use crate::models::script::ScriptLanguage;
     use std::collections::HashMap;
 
+    const SH: &ScriptLanguage = &ScriptLanguage::Shell;
+
     fn m(pairs: &[(&str, &str)]) -> HashMap<String, String> {
         pairs
             .iter()
@@ -293,7 +702,7 @@ mod tests {
     #[test]
     fn provided_value_replaces_placeholder() {
         let body = "ssh {{user}}@{{host}}";
-        let out = substitute_variables(body, &m(&[("user", "alice"), ("host", "server1")]));
+        let out = substitute_variables(body, &m(&[("user", "alice"), ("host", "server1")]), SH);
         assert_eq!(out, "ssh alice@server1");
     }
 
@@ -302,7 +711,7 @@ mod tests {
     #[test]
     fn missing_value_falls_back_to_inline_default() {
         let body = "curl {{url:https://example.com}} -X {{method:GET}}";
-        let out = substitute_variables(body, &m(&[])); // no values provided
+        let out = substitute_variables(body, &m(&[]), SH); // no values provided
         assert_eq!(out, "curl https://example.com -X GET");
     }
 
@@ -312,7 +721,7 @@ mod tests {
     #[test]
     fn missing_value_without_default_leaves_placeholder() {
         let body = "echo {{who}}";
-        let out = substitute_variables(body, &m(&[]));
+        let out = substitute_variables(body, &m(&[]), SH);
         assert_eq!(out, "echo {{who}}");
     }
 
@@ -321,17 +730,143 @@ mod tests {
     #[test]
     fn extra_values_in_map_are_ignored() {
         let body = "echo {{host}}";
-        let out = substitute_variables(body, &m(&[("host", "srv"), ("unused", "junk")]));
+        let out = substitute_variables(body, &m(&[("host", "srv"), ("unused", "junk")]), SH);
         assert_eq!(out, "echo srv");
     }
 
-    // Utf-8: substitution copies the value verbatim; the surrounding
-    // text (including accented chars) also survives byte-for-byte.
+    // Utf-8: substitution keeps the value's characters intact (quoted,
+    // since it contains a space); the surrounding text (including
+    // accented chars) also survives byte-for-byte.
     #[test]
     fn substitution_is_utf8_safe() {
         let body = "commentaire : {{msg}} — fin";
-        let out = substitute_variables(body, &m(&[("msg", "à bientôt")]));
-        assert_eq!(out, "commentaire : à bientôt — fin");
+        let out = substitute_variables(body, &m(&[("msg", "à bientôt")]), SH);
+        assert_eq!(out, "commentaire : 'à bientôt' — fin");
+    }
+
+    // A value can't inject shell syntax: anything beyond a plain word is
+    // single-quoted, embedded quotes included. Inline defaults belong to
+    // the script author and stay verbatim.
+    #[test]
+    fn shell_values_are_quoted_against_injection() {
+        let body = "rm -rf /tmp/{{dir}} && echo {{msg:done here}}";
+        let out = substitute_variables(body, &m(&[("dir", "x; reboot")]), SH);
+        assert_eq!(out, "rm -rf /tmp/'x; reboot' && echo done here");
+
+        assert_eq!(quote_variable_value(SH, "it's"), "'it'\\''s'");
+        assert_eq!(quote_variable_value(SH, "$(id)"), "'$(id)'");
+        assert_eq!(quote_variable_value(SH, ""), "''");
+        assert_eq!(quote_variable_value(SH, "v1.2-rc:3"), "v1.2-rc:3");
+    }
+
+    // A placeholder the script already quoted closes those quotes around a
+    // single-quoted value and reopens them after it.
+    #[test]
+    fn quoted_shell_placeholders_close_and_reopen_their_quotes() {
+        let body = "echo \"Deploying {{branch}}\" && git tag '{{tag}}' {{note}}";
+        let out = substitute_variables(
+            body,
+            &m(&[("branch", "feat/x"), ("tag", "v1"), ("note", "a b")]),
+            SH,
+        );
+        assert_eq!(out, "echo \"Deploying feat/x\" && git tag 'v1' 'a b'");
+
+        let out = substitute_variables(
+            body,
+            &m(&[("branch", "\"$(id)`"), ("tag", "it's"), ("note", "x")]),
+            SH,
+        );
+        assert_eq!(
+            out,
+            r#"echo "Deploying "'"$(id)`'"" && git tag '''it'\''s''' x"#
+        );
+
+        // Escaped quotes do not open a string.
+        let out = substitute_variables("echo \\\" {{v}}", &m(&[("v", "a b")]), SH);
+        assert_eq!(out, "echo \\\" 'a b'");
+    }
+
+    /// Run a substituted shell body and return what it printed.
+    #[cfg(unix)]
+    fn run_sh(body: &str, value: &str) -> String {
+        let script = substitute_variables(body, &m(&[("dir", value)]), SH);
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&script)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    // Apostrophes in comments, command substitutions and here-documents do
+    // not make a later placeholder look quoted.
+    #[cfg(unix)]
+    #[test]
+    fn shell_placeholders_stay_words_after_comments_substitutions_and_heredocs() {
+        for value in ["x; echo pwned", "it's; echo pwned", "$(echo pwned)\"'"] {
+            let expected = format!("{value}\n");
+            assert_eq!(run_sh("# don't\necho {{dir}}", value), expected);
+            assert_eq!(
+                run_sh("echo \"$(echo 'a')\" >/dev/null\necho {{dir}}", value),
+                expected
+            );
+            assert_eq!(run_sh("echo \"$( printf '%s' {{dir}} )\"", value), expected);
+            assert_eq!(
+                run_sh("cat <<EOF >/dev/null\nit's\nEOF\necho {{dir}}", value),
+                expected
+            );
+            assert_eq!(
+                run_sh(
+                    "cat <<'EOF' >/dev/null\n\"\nEOF\necho \"in {{dir}}\"",
+                    value
+                ),
+                format!("in {value}\n")
+            );
+            assert_eq!(run_sh("echo `echo {{dir}}`", value), expected);
+        }
+    }
+
+    // A value inside a here-document stays text, even when it spans lines
+    // that would otherwise end the document.
+    #[cfg(unix)]
+    #[test]
+    fn heredoc_placeholders_cannot_end_the_document() {
+        let value = "$(echo pwned)\nEOF\necho pwned";
+        assert_eq!(
+            run_sh("cat <<EOF\nv: {{dir}}\nEOF", value),
+            "v: $(echo pwned)\n EOF\n echo pwned\n"
+        );
+        assert_eq!(
+            run_sh("cat <<-'EOF'\n\tv: {{dir}}\n\tEOF", value),
+            "v: $(echo pwned)\n EOF\n echo pwned\n"
+        );
+        assert_eq!(run_sh("# {{dir}}\necho ok", value), "ok\n");
+    }
+
+    // Code and SQL bodies escape for a string literal instead of adding
+    // quotes of their own.
+    #[test]
+    fn code_and_sql_values_are_escaped_for_string_literals() {
+        let py = ScriptLanguage::Python;
+        let out = substitute_variables(
+            "print('{{name}}')",
+            &m(&[("name", "a'); import os #")]),
+            &py,
+        );
+        assert_eq!(out, "print('a\\'); import os #')");
+        assert_eq!(quote_variable_value(&py, "line\nnext"), "line\\nnext");
+
+        let node = ScriptLanguage::Node;
+        assert_eq!(quote_variable_value(&node, "${x}`"), "\\${x}\\`");
+
+        assert_eq!(
+            quote_variable_value(&ScriptLanguage::Mysql, "o'neil\\"),
+            "o''neil\\\\"
+        );
+        assert_eq!(
+            quote_variable_value(&ScriptLanguage::Postgresql, "o'neil\\"),
+            "o''neil\\"
+        );
     }
 
     // Unclosed placeholder: the impl emits the stray `{` and moves on
@@ -340,7 +875,7 @@ mod tests {
     #[test]
     fn unclosed_placeholder_does_not_panic() {
         let body = "echo {{oops never closes";
-        let out = substitute_variables(body, &m(&[]));
+        let out = substitute_variables(body, &m(&[]), SH);
         // The output contains the raw bytes; we don't over-specify the
         // exact shape (implementation detail), but it must not panic
         // and it must not be empty.
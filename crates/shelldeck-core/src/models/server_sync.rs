@@ -2,6 +2,9 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::db_credentials::{de_stored_credentials, ser_stored_credentials, DbCredentials};
+use super::wordpress::WpConfig;
+
 /// A remote file or directory entry parsed from ls/stat output.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileEntry {
@@ -92,6 +95,7 @@ impl DiscoveredDatabase {
 
 /// What kind of item to sync.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum SyncItemKind {
     Directory {
         source_path: String,
@@ -101,13 +105,44 @@ pub enum SyncItemKind {
     Database {
         name: String,
         engine: DatabaseEngine,
-        source_credentials: String,
-        dest_credentials: String,
+        /// `None` logs in with the client's defaults (socket or peer
+        /// authentication). Older profiles stored client arguments here.
+        #[serde(
+            default,
+            deserialize_with = "de_stored_credentials",
+            serialize_with = "ser_stored_credentials"
+        )]
+        source_credentials: Option<DbCredentials>,
+        #[serde(
+            default,
+            deserialize_with = "de_stored_credentials",
+            serialize_with = "ser_stored_credentials"
+        )]
+        dest_credentials: Option<DbCredentials>,
     },
     NginxSite {
         site: DiscoveredSite,
         sync_config: bool,
         sync_root: bool,
+        /// Excludes applied to the document root sync.
+        #[serde(default)]
+        exclude_patterns: Vec<String>,
+    },
+    /// A WordPress install: document root and database move together, then
+    /// the site URL is replaced in the database, `wp-config.php` is pointed
+    /// at `dest_db` and caches are flushed.
+    WordPressSite {
+        site: DiscoveredSite,
+        /// `wp-config.php`, in the root or the directory above it.
+        wp_config_path: String,
+        source_db: WpConfig,
+        dest_db: WpConfig,
+        /// Replaced by `new_url` throughout the destination database.
+        /// Equal URLs skip the search-replace.
+        old_url: String,
+        new_url: String,
+        #[serde(default)]
+        exclude_patterns: Vec<String>,
     },
 }
 
@@ -124,6 +159,9 @@ impl SyncItemKind {
             SyncItemKind::NginxSite { site, .. } => {
                 format!("Nginx: {}", site.server_name)
             }
+            SyncItemKind::WordPressSite { site, .. } => {
+                format!("WordPress: {}", site.server_name)
+            }
         }
     }
 }
@@ -136,6 +174,23 @@ pub struct SyncItem {
     pub enabled: bool,
 }
 
+/// How data gets from the source server to the destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncTransport {
+    /// Direct when the source can log in to the destination, relay otherwise.
+    #[default]
+    Auto,
+    /// The source runs rsync / ssh against the destination itself.
+    Direct,
+    /// Data streams through ShellDeck's own sessions to both servers.
+    Relay,
+}
+
+impl SyncTransport {
+    pub const ALL: [SyncTransport; 3] = [Self::Auto, Self::Direct, Self::Relay];
+}
+
 /// Configurable sync behavior.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncOptions {
@@ -144,6 +199,11 @@ pub struct SyncOptions {
     pub delete_extra: bool,
     pub bandwidth_limit: Option<u32>,
     pub skip_existing: bool,
+    #[serde(default)]
+    pub transport: SyncTransport,
+    /// Compare file contents instead of size and modification time.
+    #[serde(default)]
+    pub checksum: bool,
 }
 
 impl Default for SyncOptions {
@@ -154,6 +214,8 @@ impl Default for SyncOptions {
             delete_extra: false,
             bandwidth_limit: None,
             skip_existing: false,
+            transport: SyncTransport::Auto,
+            checksum: false,
         }
     }
 }
@@ -172,6 +234,37 @@ pub struct SyncProfile {
     pub last_synced: Option<DateTime<Utc>>,
 }
 
+impl SyncProfile {
+    /// Database credentials referenced by the profile's items.
+    pub fn db_credentials(&self) -> impl Iterator<Item = &DbCredentials> {
+        self.items
+            .iter()
+            .flat_map(|item| match &item.kind {
+                SyncItemKind::Database {
+                    source_credentials,
+                    dest_credentials,
+                    ..
+                } => [source_credentials.as_ref(), dest_credentials.as_ref()],
+                _ => [None, None],
+            })
+            .flatten()
+    }
+
+    pub fn db_credentials_mut(&mut self) -> impl Iterator<Item = &mut DbCredentials> {
+        self.items
+            .iter_mut()
+            .flat_map(|item| match &mut item.kind {
+                SyncItemKind::Database {
+                    source_credentials,
+                    dest_credentials,
+                    ..
+                } => [source_credentials.as_mut(), dest_credentials.as_mut()],
+                _ => [None, None],
+            })
+            .flatten()
+    }
+}
+
 /// Runtime status of a sync operation.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SyncOperationStatus {
@@ -184,11 +277,34 @@ pub enum SyncOperationStatus {
     Cancelled,
 }
 
+/// Phase of a multi-step item (currently WordPress sites).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyncStep {
+    Files,
+    /// Dump, URL search-replace and restore run as one stream.
+    Database,
+    Config,
+    Caches,
+}
+
+impl SyncStep {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncStep::Files => "Files",
+            SyncStep::Database => "Database",
+            SyncStep::Config => "wp-config.php",
+            SyncStep::Caches => "Caches",
+        }
+    }
+}
+
 /// Per-item progress within a sync operation.
 #[derive(Debug, Clone)]
 pub struct SyncProgress {
     pub item_id: Uuid,
     pub status: SyncOperationStatus,
+    /// The step a multi-step item is on; `None` for single-step items.
+    pub step: Option<SyncStep>,
     pub bytes_transferred: u64,
     pub total_bytes: Option<u64>,
     pub files_transferred: u32,
@@ -257,6 +373,7 @@ mod tests {
         SyncProgress {
             item_id: Uuid::new_v4(),
             status: SyncOperationStatus::Running,
+            step: None,
             bytes_transferred,
             total_bytes,
             files_transferred: 0,
@@ -340,4 +457,11 @@ mod tests {
         let items = vec![progress(500, None), progress(750, None)];
         assert!(op(items).overall_percent().is_none());
     }
+
+    #[test]
+    fn options_without_transport_default_to_auto() {
+        let json = r#"{"compress":true,"dry_run":false,"delete_extra":false,"bandwidth_limit":null,"skip_existing":false}"#;
+        let options: SyncOptions = serde_json::from_str(json).unwrap();
+        assert_eq!(options.transport, SyncTransport::Auto);
+    }
 }
@@ -0,0 +1,288 @@
+//! Docker containers and systemd services running on a server: discovery
+//! commands and the parsers for their output.
+//!
+//! Like `discovery`, everything here is pure — the commands run over SSH
+//! elsewhere and only their stdout comes back through these parsers.
+
+use serde::{Deserialize, Serialize};
+
+/// A running Docker container, standalone or part of a Compose project.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscoveredContainer {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// Docker's human status, e.g. `Up 3 hours (healthy)`.
+    pub status: String,
+    #[serde(default)]
+    pub ports: Vec<PublishedPort>,
+    /// Named volumes and bind-mount sources.
+    #[serde(default)]
+    pub volumes: Vec<String>,
+    #[serde(default)]
+    pub compose_project: Option<String>,
+    #[serde(default)]
+    pub compose_service: Option<String>,
+}
+
+/// A container port published on the host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublishedPort {
+    pub host_port: u16,
+    pub container_port: u16,
+    /// `tcp` or `udp`.
+    pub protocol: String,
+}
+
+impl PublishedPort {
+    /// `8080 → 80/tcp`.
+    pub fn label(&self) -> String {
+        format!(
+            "{} → {}/{}",
+            self.host_port, self.container_port, self.protocol
+        )
+    }
+}
+
+impl DiscoveredContainer {
+    /// First published TCP port on the host, the one worth probing.
+    pub fn primary_port(&self) -> Option<u16> {
+        self.ports
+            .iter()
+            .find(|p| p.protocol == "tcp")
+            .map(|p| p.host_port)
+    }
+}
+
+/// A systemd service installed by an administrator.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscoveredService {
+    /// Unit name, e.g. `api.service`.
+    pub unit: String,
+    pub description: String,
+    /// `active`, `inactive`, `failed`, ...
+    pub active_state: String,
+    /// `running`, `exited`, `dead`, ...
+    pub sub_state: String,
+    /// `enabled`, `disabled`, ...
+    #[serde(default)]
+    pub unit_file_state: String,
+    pub fragment_path: String,
+    #[serde(default)]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub working_directory: Option<String>,
+    /// Command line of the first `ExecStart=`.
+    #[serde(default)]
+    pub exec_start: Option<String>,
+}
+
+impl DiscoveredService {
+    /// Unit name without the `.service` suffix.
+    pub fn name(&self) -> &str {
+        self.unit.strip_suffix(".service").unwrap_or(&self.unit)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Docker
+// ---------------------------------------------------------------------------
+
+/// Build a command listing running containers, one per line:
+/// `id\tname\timage\tstatus\tports\tproject\tservice\tmounts`.
+pub fn docker_discover_command() -> &'static str {
+    r#"timeout 10 docker ps --no-trunc --format '{{.ID}}{{"\t"}}{{.Names}}{{"\t"}}{{.Image}}{{"\t"}}{{.Status}}{{"\t"}}{{.Ports}}{{"\t"}}{{.Label "com.docker.compose.project"}}{{"\t"}}{{.Label "com.docker.compose.service"}}{{"\t"}}{{.Mounts}}' 2>/dev/null"#
+}
+
+/// Parse [`docker_discover_command`] output. Lines that don't carry the
+/// expected fields are skipped.
+pub fn parse_docker_ps(output: &str) -> Vec<DiscoveredContainer> {
+    let mut containers = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+        if fields.len() < 4 || fields[0].is_empty() || fields[1].is_empty() {
+            continue;
+        }
+        let field = |i: usize| fields.get(i).copied().unwrap_or("");
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        containers.push(DiscoveredContainer {
+            id: field(0).chars().take(12).collect(),
+            name: field(1).to_string(),
+            image: field(2).to_string(),
+            status: field(3).to_string(),
+            ports: parse_docker_ports(field(4)),
+            compose_project: non_empty(field(5)),
+            compose_service: non_empty(field(6)),
+            volumes: field(7)
+                .split(',')
+                .map(str::trim)
+                .filter(|m| !m.is_empty())
+                .map(str::to_string)
+                .collect(),
+        });
+    }
+    containers
+}
+
+/// Parse Docker's `Ports` column, e.g.
+/// `0.0.0.0:8080->80/tcp, :::8080->80/tcp, 443/tcp`.
+///
+/// Only published ports are kept, and the IPv4/IPv6 twins of one binding
+/// collapse into a single entry.
+fn parse_docker_ports(column: &str) -> Vec<PublishedPort> {
+    let mut ports: Vec<PublishedPort> = Vec::new();
+    for entry in column.split(',').map(str::trim) {
+        let Some((host, container)) = entry.split_once("->") else {
+            continue;
+        };
+        let Some(host_port) = host.rsplit(':').next().and_then(|p| p.parse::<u16>().ok()) else {
+            continue;
+        };
+        let (container_port, protocol) = container.split_once('/').unwrap_or((container, "tcp"));
+        let Ok(container_port) = container_port.parse::<u16>() else {
+            continue;
+        };
+        let port = PublishedPort {
+            host_port,
+            container_port,
+            protocol: protocol.to_string(),
+        };
+        if !ports.contains(&port) {
+            ports.push(port);
+        }
+    }
+    ports
+}
+
+// ---------------------------------------------------------------------------
+// systemd
+// ---------------------------------------------------------------------------
+
+/// Build a command describing every service unit under
+/// `/etc/systemd/system` — the ones an administrator installed, as opposed
+/// to the distribution's own. Masked units (symlinks to `/dev/null`) and
+/// templates are skipped.
+pub fn systemd_discover_command() -> &'static str {
+    r#"timeout 10 sh -c 'units=""; for f in /etc/systemd/system/*.service; do [ -f "$f" ] || continue; u=${f##*/}; case "$u" in *@*) continue;; esac; units="$units $u"; done; [ -n "$units" ] && systemctl show --no-pager -p Id -p Description -p ActiveState -p SubState -p UnitFileState -p FragmentPath -p User -p WorkingDirectory -p ExecStart $units' 2>/dev/null"#
+}
+
+/// Parse `systemctl show` output: one `Key=value` block per unit, blocks
+/// separated by blank lines.
+pub fn parse_systemd_show(output: &str) -> Vec<DiscoveredService> {
+    let mut services = Vec::new();
+    for block in output.split("\n\n") {
+        let mut service = DiscoveredService::default();
+        for line in block.lines() {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            let optional = || (!value.is_empty()).then(|| value.to_string());
+            match key.trim() {
+                "Id" => service.unit = value.to_string(),
+                "Description" => service.description = value.to_string(),
+                "ActiveState" => service.active_state = value.to_string(),
+                "SubState" => service.sub_state = value.to_string(),
+                "UnitFileState" => service.unit_file_state = value.to_string(),
+                "FragmentPath" => service.fragment_path = value.to_string(),
+                "User" => service.user = optional(),
+                "WorkingDirectory" => service.working_directory = optional(),
+                "ExecStart" => service.exec_start = exec_start_argv(value),
+                _ => {}
+            }
+        }
+        if !service.unit.is_empty() {
+            services.push(service);
+        }
+    }
+    services
+}
+
+/// Pull the command line out of systemd's `ExecStart` rendering:
+/// `{ path=/usr/bin/node ; argv[]=/usr/bin/node server.js ; ignore_errors=no ; ... }`.
+fn exec_start_argv(value: &str) -> Option<String> {
+    let start = value.find("argv[]=")? + "argv[]=".len();
+    let rest = &value[start..];
+    let end = rest.find(" ;").unwrap_or(rest.len());
+    let argv = rest[..end].trim();
+    (!argv.is_empty()).then(|| argv.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docker_ps_lines_become_containers_with_compose_labels() {
+        let output = "4f1c0a9d2b7e8f001122334455667788\tshop-web-1\tnginx:1.27\tUp 3 hours\t0.0.0.0:8080->80/tcp, :::8080->80/tcp, 443/tcp\tshop\tweb\tshop_static,/srv/shop/conf\n\
+                      9a8b7c6d5e4f\tredis\tredis:7\tUp 2 days (healthy)\t6379/tcp\t\t\tredis-data\n\
+                      garbage line\n";
+        let containers = parse_docker_ps(output);
+        assert_eq!(containers.len(), 2);
+
+        let web = &containers[0];
+        assert_eq!(web.id, "4f1c0a9d2b7e");
+        assert_eq!(web.name, "shop-web-1");
+        assert_eq!(web.compose_project.as_deref(), Some("shop"));
+        assert_eq!(web.compose_service.as_deref(), Some("web"));
+        assert_eq!(
+            web.ports,
+            vec![PublishedPort {
+                host_port: 8080,
+                container_port: 80,
+                protocol: "tcp".into(),
+            }],
+            "the IPv6 twin collapses and unpublished ports are dropped"
+        );
+        assert_eq!(web.primary_port(), Some(8080));
+        assert_eq!(web.volumes, vec!["shop_static", "/srv/shop/conf"]);
+
+        let redis = &containers[1];
+        assert!(redis.compose_project.is_none());
+        assert!(redis.ports.is_empty());
+        assert_eq!(redis.primary_port(), None);
+        assert_eq!(redis.status, "Up 2 days (healthy)");
+    }
+
+    #[test]
+    fn systemctl_show_blocks_become_services() {
+        let output = "Id=api.service\n\
+                      Description=Shop API\n\
+                      ActiveState=active\n\
+                      SubState=running\n\
+                      UnitFileState=enabled\n\
+                      FragmentPath=/etc/systemd/system/api.service\n\
+                      User=deploy\n\
+                      WorkingDirectory=/srv/api\n\
+                      ExecStart={ path=/usr/bin/node ; argv[]=/usr/bin/node server.js --port 3000 ; ignore_errors=no ; start_time=[n/a] ; pid=0 ; code=(null) ; status=0/0 }\n\
+                      \n\
+                      Id=worker.service\n\
+                      Description=Queue worker\n\
+                      ActiveState=failed\n\
+                      SubState=failed\n\
+                      UnitFileState=disabled\n\
+                      FragmentPath=/etc/systemd/system/worker.service\n\
+                      User=\n\
+                      WorkingDirectory=\n\
+                      ExecStart=\n";
+        let services = parse_systemd_show(output);
+        assert_eq!(services.len(), 2);
+
+        let api = &services[0];
+        assert_eq!(api.name(), "api");
+        assert_eq!(api.active_state, "active");
+        assert_eq!(api.sub_state, "running");
+        assert_eq!(api.user.as_deref(), Some("deploy"));
+        assert_eq!(api.working_directory.as_deref(), Some("/srv/api"));
+        assert_eq!(
+            api.exec_start.as_deref(),
+            Some("/usr/bin/node server.js --port 3000")
+        );
+
+        let worker = &services[1];
+        assert_eq!(worker.active_state, "failed");
+        assert_eq!(worker.unit_file_state, "disabled");
+        assert!(worker.user.is_none());
+        assert!(worker.exec_start.is_none());
+    }
+}
@@ -0,0 +1,477 @@
+//! Site health monitoring: HTTP(S) probes with DNS and TLS certificate
+//! checks, and the alerts that come out of comparing two probes.
+//!
+//! Probes are blocking (`reqwest::blocking`) — run them off the UI thread.
+
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::managed_site::{ManagedSite, SiteStatus};
+
+/// Redirects followed before a probe gives up and reports the last answer.
+const MAX_REDIRECTS: usize = 5;
+
+/// Per-site monitoring settings, stored on the [`ManagedSite`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteMonitor {
+    pub enabled: bool,
+    /// URL to probe instead of the one derived from the site.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Text the response body must contain for the site to count as up.
+    #[serde(default)]
+    pub keyword: Option<String>,
+}
+
+impl SiteMonitor {
+    /// The URL to probe for `site`, if it has one.
+    pub fn target_url(&self, site: &ManagedSite) -> Option<String> {
+        self.url.clone().or_else(|| site.url())
+    }
+}
+
+/// Why a probe did not count as healthy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthFailure {
+    /// The host name did not resolve.
+    Dns(String),
+    /// No HTTP answer: connection refused, reset or timed out.
+    Unreachable(String),
+    /// The server answered with a 4xx or 5xx status.
+    HttpStatus(u16),
+    /// The body did not contain the expected keyword.
+    KeywordMissing,
+    /// The certificate chain did not verify.
+    InvalidCertificate(String),
+}
+
+impl HealthFailure {
+    pub fn message(&self) -> String {
+        match self {
+            HealthFailure::Dns(e) => format!("DNS lookup failed: {}", e),
+            HealthFailure::Unreachable(e) => format!("Unreachable: {}", e),
+            HealthFailure::HttpStatus(code) => format!("HTTP {}", code),
+            HealthFailure::KeywordMissing => "Keyword not found in the response".to_string(),
+            HealthFailure::InvalidCertificate(e) => format!("Invalid certificate: {}", e),
+        }
+    }
+}
+
+/// One probe of a site.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthSample {
+    pub checked_at: DateTime<Utc>,
+    /// Addresses the host resolved to; empty when DNS failed.
+    #[serde(default)]
+    pub addresses: Vec<IpAddr>,
+    pub http_status: Option<u16>,
+    /// Time to the response headers.
+    pub latency_ms: Option<u32>,
+    /// Whether the keyword was found; `None` when none is configured.
+    pub keyword_found: Option<bool>,
+    pub cert_expires_at: Option<DateTime<Utc>>,
+    /// Whether the certificate chain verified; `None` over plain HTTP.
+    pub chain_valid: Option<bool>,
+    pub failure: Option<HealthFailure>,
+}
+
+impl HealthSample {
+    fn new(checked_at: DateTime<Utc>) -> Self {
+        Self {
+            checked_at,
+            addresses: Vec::new(),
+            http_status: None,
+            latency_ms: None,
+            keyword_found: None,
+            cert_expires_at: None,
+            chain_valid: None,
+            failure: None,
+        }
+    }
+
+    pub fn is_up(&self) -> bool {
+        self.failure.is_none()
+    }
+
+    /// Whole days left on the certificate at probe time.
+    pub fn cert_days_left(&self) -> Option<i64> {
+        self.cert_expires_at
+            .map(|expires| (expires - self.checked_at).num_days())
+    }
+
+    /// The status the site shows for this probe: offline when nothing
+    /// answered, an error when something answered wrongly.
+    pub fn site_status(&self) -> SiteStatus {
+        match &self.failure {
+            None => SiteStatus::Online,
+            Some(HealthFailure::Dns(_) | HealthFailure::Unreachable(_)) => SiteStatus::Offline,
+            Some(failure) => SiteStatus::Error(failure.message()),
+        }
+    }
+}
+
+/// Something worth a notification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthAlert {
+    /// The site stopped answering correctly.
+    Down(HealthFailure),
+    /// The certificate expires in fewer than the configured number of days.
+    CertificateExpiring { days: i64 },
+}
+
+/// Alerts raised by `current` given the probe before it. Each condition
+/// alerts once, when it starts, not on every probe while it lasts.
+pub fn health_alerts(
+    previous: Option<&HealthSample>,
+    current: &HealthSample,
+    warning_days: u32,
+) -> Vec<HealthAlert> {
+    let mut alerts = Vec::new();
+    if let Some(failure) = &current.failure {
+        if previous.is_none_or(HealthSample::is_up) {
+            alerts.push(HealthAlert::Down(failure.clone()));
+        }
+    }
+    let expiring = |sample: &HealthSample| {
+        sample
+            .cert_days_left()
+            .is_some_and(|days| days < i64::from(warning_days))
+    };
+    if expiring(current) && !previous.is_some_and(expiring) {
+        alerts.push(HealthAlert::CertificateExpiring {
+            days: current.cert_days_left().unwrap_or_default(),
+        });
+    }
+    alerts
+}
+
+/// Probe `url`: resolve its host, fetch it, check the status, the keyword
+/// and — over HTTPS — the certificate chain and expiry.
+///
+/// A certificate that fails verification is reported, and the probe is
+/// repeated without verification so the status, latency and expiry are
+/// still known.
+pub fn probe_site(url: &str, keyword: Option<&str>, timeout: Duration) -> HealthSample {
+    let mut sample = HealthSample::new(Utc::now());
+    let parsed = match reqwest::Url::parse(url) {
+        Ok(u) => u,
+        Err(e) => {
+            sample.failure = Some(HealthFailure::Unreachable(e.to_string()));
+            return sample;
+        }
+    };
+    match parsed.socket_addrs(|| None) {
+        Ok(addrs) => {
+            for addr in addrs {
+                if !sample.addresses.contains(&addr.ip()) {
+                    sample.addresses.push(addr.ip());
+                }
+            }
+        }
+        Err(e) => {
+            sample.failure = Some(HealthFailure::Dns(e.to_string()));
+            return sample;
+        }
+    }
+
+    let https = parsed.scheme() == "https";
+    let mut invalid_certificate = None;
+    let started = Instant::now();
+    let response = match fetch(&parsed, timeout, false) {
+        Ok(r) => Ok(r),
+        Err(e) if https && is_certificate_error(&e) => {
+            invalid_certificate = Some(error_chain(&e));
+            fetch(&parsed, timeout, true)
+        }
+        Err(e) => Err(e),
+    };
+    let response = match response {
+        Ok(r) => r,
+        Err(e) => {
+            sample.failure = Some(match invalid_certificate {
+                Some(reason) => HealthFailure::InvalidCertificate(reason),
+                None => HealthFailure::Unreachable(error_chain(&e)),
+            });
+            return sample;
+        }
+    };
+    sample.latency_ms = Some(started.elapsed().as_millis().min(u32::MAX as u128) as u32);
+    let status = response.status().as_u16();
+    sample.http_status = Some(status);
+    if https {
+        sample.chain_valid = Some(invalid_certificate.is_none());
+        sample.cert_expires_at = response
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(|info| info.peer_certificate())
+            .and_then(certificate_not_after);
+    }
+    if let Some(keyword) = keyword.filter(|k| !k.is_empty()) {
+        let body = response.text().unwrap_or_default();
+        sample.keyword_found = Some(body.contains(keyword));
+    }
+
+    sample.failure = if let Some(reason) = invalid_certificate {
+        Some(HealthFailure::InvalidCertificate(reason))
+    } else if status >= 400 {
+        Some(HealthFailure::HttpStatus(status))
+    } else if sample.keyword_found == Some(false) {
+        Some(HealthFailure::KeywordMissing)
+    } else {
+        None
+    };
+    sample
+}
+
+fn fetch(
+    url: &reqwest::Url,
+    timeout: Duration,
+    accept_invalid_certs: bool,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .tls_info(true)
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        .build()?
+        .get(url.clone())
+        .send()
+}
+
+fn is_certificate_error(error: &reqwest::Error) -> bool {
+    error_chain(error).to_lowercase().contains("certificate")
+}
+
+/// The error with its causes, which is where reqwest keeps the detail.
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        let cause_message = cause.to_string();
+        if !message.contains(&cause_message) {
+            message.push_str(": ");
+            message.push_str(&cause_message);
+        }
+        source = cause.source();
+    }
+    message
+}
+
+// ---------------------------------------------------------------------------
+// Certificate expiry
+// ---------------------------------------------------------------------------
+
+/// `notAfter` of an X.509 certificate in DER form. Only walks as far as
+/// the validity period; nothing else in the certificate is interpreted.
+pub fn certificate_not_after(der: &[u8]) -> Option<DateTime<Utc>> {
+    let (certificate, _) = der_element(der, 0x30)?;
+    let (tbs, _) = der_element(certificate, 0x30)?;
+    let mut rest = tbs;
+    // version [0] EXPLICIT, absent for v1 certificates
+    if rest.first() == Some(&0xa0) {
+        rest = der_element(rest, 0xa0)?.1;
+    }
+    // serialNumber, signature, issuer
+    for tag in [0x02, 0x30, 0x30] {
+        rest = der_element(rest, tag)?.1;
+    }
+    let (validity, _) = der_element(rest, 0x30)?;
+    let not_before_tag = *validity.first()?;
+    let (_, after) = der_element(validity, not_before_tag)?;
+    let tag = *after.first()?;
+    let (time, _) = der_element(after, tag)?;
+    parse_asn1_time(tag, std::str::from_utf8(time).ok()?)
+}
+
+/// Split a DER element with the given tag into its contents and whatever
+/// follows it.
+fn der_element(data: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    if *data.first()? != tag {
+        return None;
+    }
+    let first = *data.get(1)?;
+    let (len, header) = if first < 0x80 {
+        (first as usize, 2)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 {
+            return None;
+        }
+        let bytes = data.get(2..2 + count)?;
+        let len = bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize);
+        (len, 2 + count)
+    };
+    let end = header.checked_add(len)?;
+    Some((data.get(header..end)?, data.get(end..)?))
+}
+
+/// UTCTime (`YYMMDDHHMMSSZ`, tag 0x17) or GeneralizedTime
+/// (`YYYYMMDDHHMMSSZ`, tag 0x18).
+fn parse_asn1_time(tag: u8, text: &str) -> Option<DateTime<Utc>> {
+    let full = match tag {
+        0x17 => {
+            let year: u32 = text.get(..2)?.parse().ok()?;
+            let century = if year >= 50 { "19" } else { "20" };
+            format!("{}{}", century, text)
+        }
+        0x18 => text.to_string(),
+        _ => return None,
+    };
+    NaiveDateTime::parse_from_str(&full, "%Y%m%d%H%M%SZ")
+        .ok()
+        .map(|t| t.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// A local HTTP stand-in: answers one request with `status` and `body`.
+    fn serve(status: &'static str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(&stream);
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                    break;
+                }
+            }
+            let mut stream = &stream;
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {status}\r\ncontent-type: text/html\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            let _ = stream.flush();
+        });
+        url
+    }
+
+    #[test]
+    fn a_healthy_site_reports_status_latency_and_keyword() {
+        let url = serve("200 OK", "<h1>Shop is open</h1>");
+        let sample = probe_site(&url, Some("Shop is open"), Duration::from_secs(5));
+        assert!(sample.is_up(), "{:?}", sample.failure);
+        assert_eq!(sample.http_status, Some(200));
+        assert!(sample.latency_ms.is_some());
+        assert_eq!(sample.keyword_found, Some(true));
+        assert_eq!(
+            sample.addresses,
+            vec!["127.0.0.1".parse::<IpAddr>().unwrap()]
+        );
+        assert!(sample.chain_valid.is_none(), "plain HTTP has no chain");
+        assert!(matches!(sample.site_status(), SiteStatus::Online));
+    }
+
+    #[test]
+    fn a_missing_keyword_or_an_error_status_is_a_failure() {
+        let url = serve("200 OK", "<h1>Maintenance</h1>");
+        let sample = probe_site(&url, Some("Shop is open"), Duration::from_secs(5));
+        assert_eq!(sample.failure, Some(HealthFailure::KeywordMissing));
+        assert!(matches!(sample.site_status(), SiteStatus::Error(_)));
+
+        let url = serve("503 Service Unavailable", "");
+        let sample = probe_site(&url, None, Duration::from_secs(5));
+        assert_eq!(sample.failure, Some(HealthFailure::HttpStatus(503)));
+        assert_eq!(sample.keyword_found, None);
+    }
+
+    #[test]
+    fn a_closed_port_is_offline() {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        drop(listener);
+        let sample = probe_site(&url, None, Duration::from_secs(5));
+        assert!(matches!(
+            sample.failure,
+            Some(HealthFailure::Unreachable(_))
+        ));
+        assert!(matches!(sample.site_status(), SiteStatus::Offline));
+    }
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if content.len() < 0x80 {
+            out.push(content.len() as u8);
+        } else {
+            out.push(0x82);
+            out.extend_from_slice(&(content.len() as u16).to_be_bytes());
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn certificate(not_after: &[u8]) -> Vec<u8> {
+        let validity = [tlv(0x17, b"250101000000Z"), not_after.to_vec()].concat();
+        let tbs = [
+            tlv(0xa0, &tlv(0x02, &[2])),
+            tlv(0x02, &[0x01, 0x23]),
+            tlv(0x30, &tlv(0x06, &[0x2a, 0x86, 0x48])),
+            // a long issuer to exercise long-form lengths
+            tlv(0x30, &tlv(0x31, &[0x55; 200])),
+            tlv(0x30, &validity),
+            tlv(0x30, &[]),
+        ]
+        .concat();
+        tlv(
+            0x30,
+            &[tlv(0x30, &tbs), tlv(0x30, &[]), tlv(0x03, &[0])].concat(),
+        )
+    }
+
+    #[test]
+    fn certificate_expiry_is_read_from_both_time_encodings() {
+        let generalized = certificate(&tlv(0x18, b"20270315120000Z"));
+        assert_eq!(
+            certificate_not_after(&generalized),
+            Some(Utc.with_ymd_and_hms(2027, 3, 15, 12, 0, 0).unwrap())
+        );
+        let utc = certificate(&tlv(0x17, b"261231235959Z"));
+        assert_eq!(
+            certificate_not_after(&utc),
+            Some(Utc.with_ymd_and_hms(2026, 12, 31, 23, 59, 59).unwrap())
+        );
+        assert_eq!(certificate_not_after(&utc[..40]), None, "truncated");
+        assert_eq!(certificate_not_after(b"not a certificate"), None);
+    }
+
+    #[test]
+    fn alerts_fire_once_when_a_condition_starts() {
+        let at = Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap();
+        let up = |days: i64| HealthSample {
+            cert_expires_at: Some(at + chrono::Duration::days(days)),
+            ..HealthSample::new(at)
+        };
+        let down = HealthSample {
+            failure: Some(HealthFailure::HttpStatus(502)),
+            ..HealthSample::new(at)
+        };
+
+        assert_eq!(
+            health_alerts(Some(&up(60)), &down, 14),
+            vec![HealthAlert::Down(HealthFailure::HttpStatus(502))]
+        );
+        assert!(
+            health_alerts(Some(&down), &down, 14).is_empty(),
+            "still down"
+        );
+        assert_eq!(
+            health_alerts(Some(&up(20)), &up(10), 14),
+            vec![HealthAlert::CertificateExpiring { days: 10 }]
+        );
+        assert!(
+            health_alerts(Some(&up(10)), &up(9), 14).is_empty(),
+            "already warned"
+        );
+        assert!(health_alerts(None, &up(60), 14).is_empty());
+    }
+}
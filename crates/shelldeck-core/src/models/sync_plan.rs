@@ -0,0 +1,928 @@
+//! Dry-run planning for Server Sync.
+//!
+//! Both sides list their files with [`manifest_command`]; [`plan_entries`]
+//! compares the two listings the way rsync's quick check does (size and
+//! mtime, or content hashes when `SyncOptions::checksum` is set) and
+//! produces the creates, updates and deletes a sync would perform. Entries
+//! the user unticks in the review step are turned back into exclude
+//! patterns by [`apply_exclusions`] before the sync starts.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use uuid::Uuid;
+
+use super::discovery::sync_dest_root;
+use super::server_sync::{SyncItem, SyncItemKind, SyncOptions};
+use crate::ai::AiDiffLine;
+use crate::util::shell_escape;
+
+/// Config files larger than this are listed without a text diff.
+pub const DIFF_MAX_BYTES: u64 = 256 * 1024;
+
+/// One file in a listing, keyed by its path relative to the listed root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub size: u64,
+    /// Modification time in whole seconds.
+    pub mtime: i64,
+    pub checksum: Option<String>,
+}
+
+pub type Manifest = BTreeMap<String, ManifestEntry>;
+
+/// List the regular files under `root` as `F<TAB>size<TAB>mtime<TAB>path`
+/// lines, followed by `H<TAB>sha256  ./path` lines when `checksum` is set.
+/// A missing root lists nothing.
+pub fn manifest_command(root: &str, checksum: bool) -> String {
+    let mut cmd = format!(
+        "cd {} 2>/dev/null && {{ find . -type f -printf 'F\\t%s\\t%T@\\t%P\\n'",
+        shell_escape(root)
+    );
+    if checksum {
+        cmd.push_str("; find . -type f -exec sha256sum {} + | sed 's/^/H\\t/'");
+    }
+    cmd.push_str("; }");
+    cmd
+}
+
+/// Like [`manifest_command`] for a single file; the entry is keyed by the
+/// file name.
+pub fn file_manifest_command(path: &str, checksum: bool) -> String {
+    let (dir, name) = split_file_path(path);
+    let mut cmd = format!(
+        "cd {} 2>/dev/null && {{ find {} -maxdepth 0 -type f -printf 'F\\t%s\\t%T@\\t%p\\n'",
+        shell_escape(&dir),
+        shell_escape(&name)
+    );
+    if checksum {
+        cmd.push_str(&format!(
+            "; sha256sum {} 2>/dev/null | sed 's/^/H\\t/'",
+            shell_escape(&name)
+        ));
+    }
+    cmd.push_str("; }");
+    cmd
+}
+
+/// Parse the output of [`manifest_command`] or [`file_manifest_command`].
+pub fn parse_manifest(output: &str) -> Manifest {
+    let mut manifest = Manifest::new();
+    let mut hashes = Vec::new();
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("F\t") {
+            let mut parts = rest.splitn(3, '\t');
+            let (Some(size), Some(mtime), Some(path)) = (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let Ok(size) = size.parse() else {
+                continue;
+            };
+            // %T@ prints fractional seconds; rsync compares whole seconds.
+            let mtime = mtime
+                .split('.')
+                .next()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0);
+            if !path.is_empty() {
+                manifest.insert(
+                    path.to_string(),
+                    ManifestEntry {
+                        size,
+                        mtime,
+                        checksum: None,
+                    },
+                );
+            }
+        } else if let Some(rest) = line.strip_prefix("H\t") {
+            if let Some((hash, path)) = rest.split_once("  ") {
+                let path = path.strip_prefix("./").unwrap_or(path);
+                hashes.push((path.to_string(), hash.to_string()));
+            }
+        }
+    }
+    for (path, hash) in hashes {
+        if let Some(entry) = manifest.get_mut(&path) {
+            entry.checksum = Some(hash);
+        }
+    }
+    manifest
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// A file the sync would create, update or delete.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanEntry {
+    /// Path relative to the section root.
+    pub path: String,
+    pub action: PlanAction,
+    /// Bytes sent for creates and updates, bytes removed for deletes.
+    pub size: u64,
+    /// Destination → source line diff, for changed config files.
+    pub diff: Option<Vec<AiDiffLine>>,
+    pub excluded: bool,
+}
+
+/// What a plan section covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanTarget {
+    /// A directory tree; entries are relative to both roots.
+    Tree {
+        source_root: String,
+        dest_root: String,
+    },
+    /// A single file synced to the same path (an nginx site config).
+    File { path: String },
+}
+
+impl PlanTarget {
+    /// Full paths on the source and the destination of an entry path.
+    pub fn entry_paths(&self, path: &str) -> (String, String) {
+        match self {
+            PlanTarget::Tree {
+                source_root,
+                dest_root,
+            } => (
+                format!("{}/{}", source_root.trim_end_matches('/'), path),
+                format!("{}/{}", dest_root.trim_end_matches('/'), path),
+            ),
+            PlanTarget::File { path } => (path.clone(), path.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanSection {
+    pub target: PlanTarget,
+    pub entries: Vec<PlanEntry>,
+}
+
+/// The planned changes for one sync item.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemPlan {
+    pub item_id: Uuid,
+    pub sections: Vec<PlanSection>,
+    /// Set when a listing failed; the item syncs unplanned.
+    pub error: Option<String>,
+}
+
+/// Counts and bytes of the entries that will run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlanTotals {
+    pub create: usize,
+    pub update: usize,
+    pub delete: usize,
+    pub bytes: u64,
+}
+
+impl PlanTotals {
+    pub fn is_empty(&self) -> bool {
+        self.create + self.update + self.delete == 0
+    }
+}
+
+impl ItemPlan {
+    pub fn totals(&self) -> PlanTotals {
+        let mut totals = PlanTotals::default();
+        for entry in self.sections.iter().flat_map(|s| &s.entries) {
+            if entry.excluded {
+                continue;
+            }
+            match entry.action {
+                PlanAction::Create => totals.create += 1,
+                PlanAction::Update => totals.update += 1,
+                PlanAction::Delete => totals.delete += 1,
+            }
+            if entry.action != PlanAction::Delete {
+                totals.bytes += entry.size;
+            }
+        }
+        totals
+    }
+}
+
+/// The sections to plan for an item, or none for databases, which are
+/// replaced wholesale.
+pub fn plan_targets(kind: &SyncItemKind) -> Vec<PlanTarget> {
+    match kind {
+        SyncItemKind::Directory {
+            source_path,
+            dest_path,
+            ..
+        } => vec![PlanTarget::Tree {
+            source_root: source_path.trim_end_matches('/').to_string(),
+            dest_root: sync_dest_root(source_path, dest_path),
+        }],
+        SyncItemKind::Database { .. } => Vec::new(),
+        SyncItemKind::NginxSite {
+            site,
+            sync_config,
+            sync_root,
+            ..
+        } => {
+            let mut targets = Vec::new();
+            if *sync_root && !site.root.is_empty() {
+                targets.push(PlanTarget::Tree {
+                    source_root: site.root.clone(),
+                    dest_root: site.root.clone(),
+                });
+            }
+            if *sync_config && !site.config_path.is_empty() {
+                targets.push(PlanTarget::File {
+                    path: site.config_path.clone(),
+                });
+            }
+            targets
+        }
+        SyncItemKind::WordPressSite { site, .. } if !site.root.is_empty() => {
+            vec![PlanTarget::Tree {
+                source_root: site.root.clone(),
+                dest_root: site.root.clone(),
+            }]
+        }
+        SyncItemKind::WordPressSite { .. } => Vec::new(),
+    }
+}
+
+/// Exclude patterns already configured for the tree sections of an item.
+pub fn item_excludes(kind: &SyncItemKind) -> &[String] {
+    match kind {
+        SyncItemKind::Directory {
+            exclude_patterns, ..
+        }
+        | SyncItemKind::NginxSite {
+            exclude_patterns, ..
+        }
+        | SyncItemKind::WordPressSite {
+            exclude_patterns, ..
+        } => exclude_patterns,
+        SyncItemKind::Database { .. } => &[],
+    }
+}
+
+/// Compare two listings and return the planned changes, sorted by path.
+pub fn plan_entries(
+    source: &Manifest,
+    dest: &Manifest,
+    options: &SyncOptions,
+    excludes: &[String],
+) -> Vec<PlanEntry> {
+    let mut entries = Vec::new();
+    for (path, src) in source {
+        if is_excluded(path, excludes) {
+            continue;
+        }
+        let action = match dest.get(path) {
+            None => PlanAction::Create,
+            Some(_) if options.skip_existing => continue,
+            Some(dst) if !differs(src, dst, options.checksum) => continue,
+            Some(_) => PlanAction::Update,
+        };
+        entries.push(PlanEntry {
+            path: path.clone(),
+            action,
+            size: src.size,
+            diff: None,
+            excluded: false,
+        });
+    }
+    if options.delete_extra {
+        for (path, dst) in dest {
+            if source.contains_key(path) || is_excluded(path, excludes) {
+                continue;
+            }
+            entries.push(PlanEntry {
+                path: path.clone(),
+                action: PlanAction::Delete,
+                size: dst.size,
+                diff: None,
+                excluded: false,
+            });
+        }
+    }
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    entries
+}
+
+fn differs(src: &ManifestEntry, dst: &ManifestEntry, checksum: bool) -> bool {
+    match (&src.checksum, &dst.checksum) {
+        (Some(a), Some(b)) if checksum => a != b,
+        _ => src.size != dst.size || src.mtime != dst.mtime,
+    }
+}
+
+/// Whether an update to `path` gets a text diff in the review. Files that
+/// hold credentials never do: the review lists them as changed only.
+pub fn wants_diff(target: &PlanTarget, entry: &PlanEntry) -> bool {
+    if entry.action != PlanAction::Update || entry.size > DIFF_MAX_BYTES {
+        return false;
+    }
+    let name = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+    let ext = Path::new(name)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    if holds_credentials(name, ext.as_deref()) {
+        return false;
+    }
+    if matches!(target, PlanTarget::File { .. }) {
+        return true;
+    }
+    if matches!(name, ".htaccess" | "Caddyfile") {
+        return true;
+    }
+    matches!(
+        ext.as_deref(),
+        Some("conf" | "cnf" | "ini" | "yml" | "yaml" | "toml" | "json" | "xml")
+    )
+}
+
+/// `.env` files, `wp-config.php` and client credential files.
+fn holds_credentials(name: &str, ext: Option<&str>) -> bool {
+    matches!(
+        name,
+        ".env" | "wp-config.php" | ".my.cnf" | ".pgpass" | ".netrc"
+    ) || name.starts_with(".env.")
+        || ext == Some("env")
+}
+
+/// rsync-style exclude check: `/a/b` is anchored at the root, `a/b` matches
+/// at any depth, a bare name matches any path component. Matching a
+/// directory excludes everything under it.
+pub fn is_excluded(path: &str, excludes: &[String]) -> bool {
+    let components: Vec<&str> = path.split('/').collect();
+    excludes.iter().any(|pattern| {
+        let pattern = pattern.trim_end_matches('/');
+        if pattern.is_empty() {
+            return false;
+        }
+        // Like rsync, backslashes only escape in patterns with wildcards.
+        let literal = !has_wildcards(pattern);
+        let (anchored, pattern) = match pattern.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (false, pattern),
+        };
+        let parts: Vec<&str> = pattern.split('/').collect();
+        let starts: Vec<usize> = if anchored {
+            vec![0]
+        } else {
+            (0..components.len()).collect()
+        };
+        starts.into_iter().any(|start| {
+            components.len() >= start + parts.len()
+                && parts.iter().zip(&components[start..]).all(|(p, c)| {
+                    if literal {
+                        p == c
+                    } else {
+                        glob_match(p, c)
+                    }
+                })
+        })
+    })
+}
+
+/// `*` and `?` wildcards within one path component; a backslash makes the
+/// next character literal.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi + 1 < p.len() && p[pi] == '\\' && p[pi + 1] == t[ti] {
+            pi += 2;
+            ti += 1;
+        } else if pi < p.len() && p[pi] != '\\' && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Fold unticked plan entries back into the items before the sync runs:
+/// tree entries become anchored excludes, an unticked site config stops
+/// that config from syncing.
+pub fn apply_exclusions(items: &mut [SyncItem], plans: &[ItemPlan]) {
+    for plan in plans {
+        let Some(item) = items.iter_mut().find(|i| i.id == plan.item_id) else {
+            continue;
+        };
+        for section in &plan.sections {
+            let mut excluded = section.entries.iter().filter(|e| e.excluded).peekable();
+            match (&section.target, &mut item.kind) {
+                (
+                    PlanTarget::Tree { .. },
+                    SyncItemKind::Directory {
+                        exclude_patterns, ..
+                    }
+                    | SyncItemKind::NginxSite {
+                        exclude_patterns, ..
+                    }
+                    | SyncItemKind::WordPressSite {
+                        exclude_patterns, ..
+                    },
+                ) => {
+                    exclude_patterns.extend(excluded.map(|e| format!("/{}", escape_glob(&e.path))));
+                }
+                (PlanTarget::File { .. }, SyncItemKind::NginxSite { sync_config, .. })
+                    if excluded.peek().is_some() =>
+                {
+                    *sync_config = false;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Whether rsync treats `pattern` as a wildcard pattern.
+pub(crate) fn has_wildcards(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Escape the wildcard characters rsync and tar see in an exclude pattern,
+/// so a listed path only ever matches itself. rsync reads backslashes
+/// literally in a pattern without wildcards, so such paths stay as they are.
+pub(crate) fn escape_glob(path: &str) -> String {
+    if has_wildcards(path) {
+        escape_wildcards(path)
+    } else {
+        path.to_string()
+    }
+}
+
+/// Escape `path` for a pattern that has wildcards elsewhere.
+pub(crate) fn escape_wildcards(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '[' | '*' | '?' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// A row of the review tree: a directory heading or a planned file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanRow {
+    Dir {
+        depth: usize,
+        /// Directory path relative to the section root.
+        path: String,
+        name: String,
+    },
+    Entry {
+        depth: usize,
+        name: String,
+        /// Index into the section's entries.
+        index: usize,
+    },
+}
+
+/// Lay out entries (sorted by path) as a tree, emitting each directory once
+/// before its contents.
+pub fn plan_rows(entries: &[PlanEntry]) -> Vec<PlanRow> {
+    let mut rows = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let mut parts: Vec<&str> = entry.path.split('/').collect();
+        let name = parts.pop().unwrap_or_default();
+        let common = open.iter().zip(&parts).take_while(|(a, b)| a == b).count();
+        open.truncate(common);
+        for (depth, dir) in parts.iter().enumerate().skip(common) {
+            open.push(*dir);
+            rows.push(PlanRow::Dir {
+                depth,
+                path: parts[..=depth].join("/"),
+                name: (*dir).to_string(),
+            });
+        }
+        rows.push(PlanRow::Entry {
+            depth: parts.len(),
+            name: name.to_string(),
+            index,
+        });
+    }
+    rows
+}
+
+fn split_file_path(path: &str) -> (String, String) {
+    let file = Path::new(path);
+    let dir = file
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| "/".to_string());
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (dir, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::server_sync::DiscoveredSite;
+
+    fn entry(size: u64, mtime: i64) -> ManifestEntry {
+        ManifestEntry {
+            size,
+            mtime,
+            checksum: None,
+        }
+    }
+
+    fn manifest(files: &[(&str, u64, i64)]) -> Manifest {
+        files
+            .iter()
+            .map(|(path, size, mtime)| (path.to_string(), entry(*size, *mtime)))
+            .collect()
+    }
+
+    #[test]
+    fn parse_manifest_merges_hashes_and_truncates_mtimes() {
+        let output = concat!(
+            "F\t120\t1700000000.4821\tindex.php\n",
+            "F\t5\t1700000100.0000000000\tconf/app.ini\n",
+            "H\tabc123  ./index.php\n",
+            "garbage line\n",
+        );
+        let manifest = parse_manifest(output);
+        assert_eq!(manifest.len(), 2);
+        assert_eq!(manifest["index.php"].size, 120);
+        assert_eq!(manifest["index.php"].mtime, 1_700_000_000);
+        assert_eq!(manifest["index.php"].checksum.as_deref(), Some("abc123"));
+        assert_eq!(manifest["conf/app.ini"].checksum, None);
+    }
+
+    #[test]
+    fn manifest_commands_quote_paths() {
+        let cmd = manifest_command("/var/www/my site", true);
+        assert!(cmd.starts_with("cd '/var/www/my site' 2>/dev/null && {"));
+        assert!(cmd.contains("sha256sum"));
+        assert!(!manifest_command("/srv", false).contains("sha256sum"));
+
+        let cmd = file_manifest_command("/etc/nginx/sites-enabled/app", false);
+        assert!(cmd.starts_with("cd '/etc/nginx/sites-enabled' 2>/dev/null"));
+        assert!(cmd.contains("find 'app' -maxdepth 0"));
+    }
+
+    #[test]
+    fn plan_entries_classifies_by_size_and_mtime() {
+        let source = manifest(&[("a.txt", 10, 100), ("b.txt", 20, 100), ("c.txt", 5, 100)]);
+        let dest = manifest(&[("b.txt", 20, 100), ("c.txt", 5, 90), ("old.txt", 7, 1)]);
+
+        let entries = plan_entries(&source, &dest, &SyncOptions::default(), &[]);
+        let actions: Vec<_> = entries
+            .iter()
+            .map(|e| (e.path.as_str(), e.action))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![("a.txt", PlanAction::Create), ("c.txt", PlanAction::Update)]
+        );
+
+        let options = SyncOptions {
+            delete_extra: true,
+            ..Default::default()
+        };
+        let entries = plan_entries(&source, &dest, &options, &[]);
+        assert_eq!(entries.last().unwrap().path, "old.txt");
+        assert_eq!(entries.last().unwrap().action, PlanAction::Delete);
+        assert_eq!(entries.last().unwrap().size, 7);
+    }
+
+    #[test]
+    fn checksum_mode_ignores_touched_but_identical_files() {
+        let mut source = manifest(&[("a.txt", 10, 200)]);
+        let mut dest = manifest(&[("a.txt", 10, 100)]);
+        source.get_mut("a.txt").unwrap().checksum = Some("same".into());
+        dest.get_mut("a.txt").unwrap().checksum = Some("same".into());
+
+        let options = SyncOptions {
+            checksum: true,
+            ..Default::default()
+        };
+        assert!(plan_entries(&source, &dest, &options, &[]).is_empty());
+        assert_eq!(
+            plan_entries(&source, &dest, &SyncOptions::default(), &[]).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn skip_existing_drops_updates_but_keeps_creates() {
+        let source = manifest(&[("a.txt", 10, 100), ("b.txt", 20, 100)]);
+        let dest = manifest(&[("b.txt", 1, 1)]);
+        let options = SyncOptions {
+            skip_existing: true,
+            ..Default::default()
+        };
+        let entries = plan_entries(&source, &dest, &options, &[]);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, PlanAction::Create);
+    }
+
+    #[test]
+    fn excludes_follow_rsync_anchoring() {
+        let excludes = vec!["*.log".to_string(), "/cache".to_string(), "a/b".to_string()];
+        assert!(is_excluded("error.log", &excludes));
+        assert!(is_excluded("logs/deep/error.log", &excludes));
+        assert!(is_excluded("cache/x.bin", &excludes));
+        assert!(!is_excluded("wp/cache/x.bin", &excludes));
+        assert!(is_excluded("x/a/b/c", &excludes));
+        assert!(!is_excluded("index.php", &excludes));
+        assert!(!is_excluded("ab", &["a?c".to_string()]));
+    }
+
+    #[test]
+    fn totals_skip_excluded_entries_and_deleted_bytes() {
+        let mk = |path: &str, action, size, excluded| PlanEntry {
+            path: path.into(),
+            action,
+            size,
+            diff: None,
+            excluded,
+        };
+        let plan = ItemPlan {
+            item_id: Uuid::new_v4(),
+            sections: vec![PlanSection {
+                target: PlanTarget::Tree {
+                    source_root: "/a".into(),
+                    dest_root: "/b".into(),
+                },
+                entries: vec![
+                    mk("new", PlanAction::Create, 100, false),
+                    mk("changed", PlanAction::Update, 50, false),
+                    mk("gone", PlanAction::Delete, 999, false),
+                    mk("skipped", PlanAction::Create, 7, true),
+                ],
+            }],
+            error: None,
+        };
+        let totals = plan.totals();
+        assert_eq!((totals.create, totals.update, totals.delete), (1, 1, 1));
+        assert_eq!(totals.bytes, 150);
+    }
+
+    #[test]
+    fn apply_exclusions_anchors_tree_entries_and_drops_site_config() {
+        let dir_id = Uuid::new_v4();
+        let site_id = Uuid::new_v4();
+        let mut items = vec![
+            SyncItem {
+                id: dir_id,
+                kind: SyncItemKind::Directory {
+                    source_path: "/var/www".into(),
+                    dest_path: "/srv".into(),
+                    exclude_patterns: vec!["*.log".into()],
+                },
+                enabled: true,
+            },
+            SyncItem {
+                id: site_id,
+                kind: SyncItemKind::NginxSite {
+                    site: DiscoveredSite {
+                        server_name: "app".into(),
+                        root: "/var/www/app".into(),
+                        config_path: "/etc/nginx/sites-enabled/app".into(),
+                        listen_port: 80,
+                        ssl: false,
+                    },
+                    sync_config: true,
+                    sync_root: true,
+                    exclude_patterns: Vec::new(),
+                },
+                enabled: true,
+            },
+        ];
+        let excluded = |path: &str| PlanEntry {
+            path: path.into(),
+            action: PlanAction::Update,
+            size: 1,
+            diff: None,
+            excluded: true,
+        };
+        let plans = vec![
+            ItemPlan {
+                item_id: dir_id,
+                sections: vec![PlanSection {
+                    target: plan_targets(&items[0].kind).remove(0),
+                    entries: vec![excluded("uploads/big.zip")],
+                }],
+                error: None,
+            },
+            ItemPlan {
+                item_id: site_id,
+                sections: plan_targets(&items[1].kind)
+                    .into_iter()
+                    .map(|target| PlanSection {
+                        entries: vec![excluded("app")],
+                        target,
+                    })
+                    .collect(),
+                error: None,
+            },
+        ];
+
+        apply_exclusions(&mut items, &plans);
+
+        assert_eq!(
+            item_excludes(&items[0].kind),
+            ["*.log".to_string(), "/uploads/big.zip".to_string()]
+        );
+        assert_eq!(item_excludes(&items[1].kind), ["/app".to_string()]);
+        assert!(matches!(
+            items[1].kind,
+            SyncItemKind::NginxSite {
+                sync_config: false,
+                sync_root: true,
+                ..
+            }
+        ));
+    }
+
+    // File names are taken literally: `[`, `*`, `?` and `\` in an unticked
+    // path must not widen its exclude to other files.
+    #[test]
+    fn excluded_paths_escape_glob_characters() {
+        let id = Uuid::new_v4();
+        let mut items = vec![SyncItem {
+            id,
+            kind: SyncItemKind::Directory {
+                source_path: "/var/www".into(),
+                dest_path: "/srv".into(),
+                exclude_patterns: Vec::new(),
+            },
+            enabled: true,
+        }];
+        let plans = vec![ItemPlan {
+            item_id: id,
+            sections: vec![PlanSection {
+                target: PlanTarget::Tree {
+                    source_root: "/var/www".into(),
+                    dest_root: "/srv/www".into(),
+                },
+                entries: vec![PlanEntry {
+                    path: "img/[v1]*?\\.png".into(),
+                    action: PlanAction::Create,
+                    size: 1,
+                    diff: None,
+                    excluded: true,
+                }],
+            }],
+            error: None,
+        }];
+
+        apply_exclusions(&mut items, &plans);
+
+        let excludes = item_excludes(&items[0].kind);
+        assert_eq!(excludes, ["/img/\\[v1]\\*\\?\\\\.png".to_string()]);
+        assert!(is_excluded("img/[v1]*?\\.png", excludes));
+        assert!(!is_excluded("img/v.png", excludes));
+        assert!(!is_excluded("img/[v1]ab\\.png", excludes));
+    }
+
+    // Without wildcards rsync takes a backslash literally, so escaping it
+    // would stop the exclude matching the file it names.
+    #[test]
+    fn excluded_paths_without_wildcards_keep_backslashes() {
+        assert_eq!(escape_glob("img/a\\b.png"), "img/a\\b.png");
+        let excludes = ["/img/a\\b.png".to_string()];
+        assert!(is_excluded("img/a\\b.png", &excludes));
+        assert!(!is_excluded("img/ab.png", &excludes));
+    }
+
+    #[test]
+    fn plan_targets_follow_rsync_destination_semantics() {
+        let kind = SyncItemKind::Directory {
+            source_path: "/var/www/site".into(),
+            dest_path: "/srv".into(),
+            exclude_patterns: Vec::new(),
+        };
+        assert_eq!(
+            plan_targets(&kind),
+            vec![PlanTarget::Tree {
+                source_root: "/var/www/site".into(),
+                dest_root: "/srv/site".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn wants_diff_only_for_small_config_updates() {
+        let tree = PlanTarget::Tree {
+            source_root: "/a".into(),
+            dest_root: "/b".into(),
+        };
+        let mk = |path: &str, action, size| PlanEntry {
+            path: path.into(),
+            action,
+            size,
+            diff: None,
+            excluded: false,
+        };
+        assert!(wants_diff(
+            &tree,
+            &mk("conf/php.ini", PlanAction::Update, 10)
+        ));
+        assert!(wants_diff(&tree, &mk(".htaccess", PlanAction::Update, 10)));
+        assert!(!wants_diff(
+            &tree,
+            &mk("conf/php.ini", PlanAction::Create, 10)
+        ));
+        assert!(!wants_diff(&tree, &mk("index.php", PlanAction::Update, 10)));
+        assert!(!wants_diff(
+            &tree,
+            &mk("big.json", PlanAction::Update, DIFF_MAX_BYTES + 1)
+        ));
+        let file = PlanTarget::File {
+            path: "/etc/nginx/sites-enabled/app".into(),
+        };
+        assert!(wants_diff(&file, &mk("app", PlanAction::Update, 10)));
+    }
+
+    // Credential files are listed as changed but never diffed, even when
+    // synced on their own.
+    #[test]
+    fn wants_diff_never_for_credential_files() {
+        let tree = PlanTarget::Tree {
+            source_root: "/a".into(),
+            dest_root: "/b".into(),
+        };
+        let mk = |path: &str| PlanEntry {
+            path: path.into(),
+            action: PlanAction::Update,
+            size: 10,
+            diff: None,
+            excluded: false,
+        };
+        for path in [
+            ".env",
+            "app/.env.production",
+            "config/prod.env",
+            "wp-config.php",
+            "home/.my.cnf",
+        ] {
+            assert!(!wants_diff(&tree, &mk(path)), "{path}");
+        }
+        let file = PlanTarget::File {
+            path: "/var/www/wp-config.php".into(),
+        };
+        assert!(!wants_diff(&file, &mk("wp-config.php")));
+    }
+
+    #[test]
+    fn plan_rows_emit_each_directory_once() {
+        let mk = |path: &str| PlanEntry {
+            path: path.into(),
+            action: PlanAction::Create,
+            size: 0,
+            diff: None,
+            excluded: false,
+        };
+        let entries = vec![
+            mk("a/b/one.txt"),
+            mk("a/b/two.txt"),
+            mk("a/c.txt"),
+            mk("top.txt"),
+        ];
+        let rows = plan_rows(&entries);
+        let shape: Vec<String> = rows
+            .iter()
+            .map(|row| match row {
+                PlanRow::Dir { depth, path, .. } => format!("{depth} dir {path}"),
+                PlanRow::Entry { depth, name, .. } => format!("{depth} {name}"),
+            })
+            .collect();
+        assert_eq!(
+            shape,
+            vec![
+                "0 dir a",
+                "1 dir a/b",
+                "2 one.txt",
+                "2 two.txt",
+                "1 c.txt",
+                "0 top.txt",
+            ]
+        );
+    }
+}
@@ -50,6 +50,7 @@ impl ScriptTemplate {
             } else {
                 Some(default.to_string())
             },
+            ..Default::default()
         });
         self
     }
@@ -0,0 +1,301 @@
+//! Tunnel supervision: retry backoff, up/down history of a forward and the
+//! pieces of its far-end probe that do not need a network.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::port_forward::{ForwardDirection, PortForward};
+
+/// Retry delay after a failure: doubles from `initial` up to `max`, and
+/// starts over once the forward has been up again.
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    failures: u32,
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            failures: 0,
+        }
+    }
+
+    /// Delay before the next attempt, counting this call as a failure.
+    pub fn next_delay(&mut self) -> Duration {
+        let factor = 2u32.saturating_pow(self.failures.min(16));
+        self.failures = self.failures.saturating_add(1);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+
+    /// Failures since the forward was last up.
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    pub fn reset(&mut self) {
+        self.failures = 0;
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+/// Where a forward's far end is reached from for a probe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeTarget {
+    /// Through the SSH session, as the remote side of a local forward.
+    Remote { host: String, port: u16 },
+    /// From this machine, as the local side of a remote forward.
+    Local { host: String, port: u16 },
+}
+
+impl ProbeTarget {
+    /// The far end of `forward`; `None` for dynamic forwards, which have
+    /// no single one.
+    pub fn for_forward(forward: &PortForward) -> Option<Self> {
+        match forward.direction {
+            ForwardDirection::LocalToRemote => Some(ProbeTarget::Remote {
+                host: forward.remote_host.clone(),
+                port: forward.remote_port,
+            }),
+            ForwardDirection::RemoteToLocal => Some(ProbeTarget::Local {
+                host: forward.local_host.clone(),
+                port: forward.local_port,
+            }),
+            ForwardDirection::Dynamic => None,
+        }
+    }
+}
+
+/// A minimal `GET` for an HTTP probe. The connection is closed after the
+/// answer so the probe never waits on keep-alive.
+pub fn http_probe_request(host: &str, port: u16, path: &str) -> String {
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+    let host = if port == 80 {
+        host.to_string()
+    } else {
+        format!("{}:{}", host, port)
+    };
+    format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: ShellDeck tunnel probe\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        path, host
+    )
+}
+
+/// Status code from the first line of an HTTP response, once it is in.
+pub fn parse_http_status(response: &[u8]) -> Option<u16> {
+    let end = response.iter().position(|&b| b == b'\n')?;
+    let line = std::str::from_utf8(&response[..end]).ok()?;
+    let mut parts = line.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    let code = parts.next()?;
+    if code.len() != 3 {
+        return None;
+    }
+    code.parse().ok()
+}
+
+/// Whether an HTTP probe answer means the far end is up. Client errors
+/// still prove something is listening; server errors do not.
+pub fn http_status_is_up(status: u16) -> bool {
+    (100..500).contains(&status)
+}
+
+/// What a supervised forward was doing from a point in time on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TunnelState {
+    Up,
+    Down,
+    /// Stopped on purpose; not counted against uptime.
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TunnelTransition {
+    pub at: DateTime<Utc>,
+    pub state: TunnelState,
+    /// Why the forward went down.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// State changes of one forward, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardHistory {
+    #[serde(default)]
+    pub transitions: Vec<TunnelTransition>,
+}
+
+impl ForwardHistory {
+    /// Record a state change. Repeats of the current state are dropped;
+    /// returns whether anything was recorded.
+    pub fn record(
+        &mut self,
+        at: DateTime<Utc>,
+        state: TunnelState,
+        reason: Option<String>,
+    ) -> bool {
+        if self.state() == Some(state) {
+            return false;
+        }
+        self.transitions
+            .push(TunnelTransition { at, state, reason });
+        true
+    }
+
+    pub fn state(&self) -> Option<TunnelState> {
+        self.transitions.last().map(|t| t.state)
+    }
+
+    /// When the current outage started, if the forward is down.
+    pub fn down_since(&self) -> Option<DateTime<Utc>> {
+        self.transitions
+            .last()
+            .filter(|t| t.state == TunnelState::Down)
+            .map(|t| t.at)
+    }
+
+    /// Times the forward dropped after having been up, since `since`.
+    pub fn flaps_since(&self, since: DateTime<Utc>) -> usize {
+        self.transitions
+            .windows(2)
+            .filter(|pair| {
+                pair[0].state == TunnelState::Up
+                    && pair[1].state == TunnelState::Down
+                    && pair[1].at >= since
+            })
+            .count()
+    }
+
+    /// Share of the supervised time in `[since, now]` the forward was up.
+    /// Time stopped on purpose is left out; `None` when there is none.
+    pub fn uptime_percent(&self, since: DateTime<Utc>, now: DateTime<Utc>) -> Option<f32> {
+        let mut up = 0i64;
+        let mut down = 0i64;
+        for (i, transition) in self.transitions.iter().enumerate() {
+            let end = self.transitions.get(i + 1).map_or(now, |next| next.at);
+            let start = transition.at.max(since);
+            let span = (end.min(now) - start).num_milliseconds();
+            if span <= 0 {
+                continue;
+            }
+            match transition.state {
+                TunnelState::Up => up += span,
+                TunnelState::Down => down += span,
+                TunnelState::Stopped => {}
+            }
+        }
+        if up + down == 0 {
+            return None;
+        }
+        Some(up as f32 * 100.0 / (up + down) as f32)
+    }
+
+    /// Drop transitions beyond the newest `max`.
+    pub fn truncate_front(&mut self, max: usize) {
+        if self.transitions.len() > max {
+            let excess = self.transitions.len() - max;
+            self.transitions.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap() + chrono::Duration::minutes(minutes)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_and_resets() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(10));
+        let delays: Vec<u64> = (0..6).map(|_| backoff.next_delay().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 10, 10]);
+        assert_eq!(backoff.failures(), 6);
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn probe_target_is_the_far_end_of_the_forward() {
+        let local = PortForward::new_local(Default::default(), 8080, "db.internal", 5432);
+        assert_eq!(
+            ProbeTarget::for_forward(&local),
+            Some(ProbeTarget::Remote {
+                host: "db.internal".into(),
+                port: 5432
+            })
+        );
+        let mut dynamic = local.clone();
+        dynamic.direction = ForwardDirection::Dynamic;
+        assert_eq!(ProbeTarget::for_forward(&dynamic), None);
+    }
+
+    #[test]
+    fn http_probe_request_and_status_line() {
+        let request = http_probe_request("localhost", 3000, "health");
+        assert!(request.starts_with("GET /health HTTP/1.1\r\nHost: localhost:3000\r\n"));
+        assert!(request.ends_with("\r\n\r\n"));
+        assert!(http_probe_request("web", 80, "/").contains("Host: web\r\n"));
+
+        assert_eq!(parse_http_status(b"HTTP/1.1 204 No Content\r\n"), Some(204));
+        assert_eq!(
+            parse_http_status(b"HTTP/1.0 502 Bad Gateway\r\nx: y"),
+            Some(502)
+        );
+        assert_eq!(
+            parse_http_status(b"HTTP/1.1 200 OK"),
+            None,
+            "line not complete"
+        );
+        assert_eq!(parse_http_status(b"SSH-2.0-OpenSSH_9.6\r\n"), None);
+        assert!(http_status_is_up(404));
+        assert!(!http_status_is_up(503));
+    }
+
+    #[test]
+    fn history_tracks_outages_flaps_and_uptime() {
+        let mut history = ForwardHistory::default();
+        assert!(history.record(at(0), TunnelState::Up, None));
+        assert!(
+            !history.record(at(1), TunnelState::Up, None),
+            "repeat dropped"
+        );
+        history.record(at(30), TunnelState::Down, Some("reset".into()));
+        assert_eq!(history.down_since(), Some(at(30)));
+        history.record(at(40), TunnelState::Up, None);
+        assert_eq!(history.down_since(), None);
+        history.record(at(60), TunnelState::Stopped, None);
+
+        assert_eq!(history.flaps_since(at(0)), 1);
+        assert_eq!(history.flaps_since(at(31)), 0);
+        // 50 minutes up, 10 down; the stopped hour does not count.
+        let uptime = history.uptime_percent(at(0), at(120)).unwrap();
+        assert!((uptime - 83.33).abs() < 0.01, "{}", uptime);
+        // Only the window counts: 35..60 has 5 down and 20 up.
+        assert_eq!(history.uptime_percent(at(35), at(120)), Some(80.0));
+        assert_eq!(history.uptime_percent(at(60), at(120)), None);
+
+        history.truncate_front(2);
+        assert_eq!(history.transitions.len(), 2);
+        assert_eq!(history.state(), Some(TunnelState::Stopped));
+    }
+}
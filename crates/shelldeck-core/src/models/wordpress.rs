@@ -0,0 +1,851 @@
+//! WordPress-aware site migration: reading and rewriting `wp-config.php`,
+//! the database commands, and a streaming search-replace for mysqldump
+//! output that keeps PHP-serialized values valid.
+//!
+//! No SSH or async dependencies — fully unit-testable.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::db_credentials::{DbCredentials, DbLogin};
+use super::server_sync::DatabaseEngine;
+use crate::util::shell_escape;
+
+/// Database settings of a WordPress install, as found in `wp-config.php`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WpConfig {
+    pub db_name: String,
+    pub db_user: String,
+    /// Read from `wp-config.php` when needed; never written to a profile.
+    #[serde(default, skip_serializing)]
+    pub db_password: String,
+    pub db_host: String,
+    pub table_prefix: String,
+}
+
+/// A WordPress install found under a discovered site's document root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredWordPress {
+    /// The document root the install was looked up for.
+    pub root: String,
+    /// Where `wp-config.php` lives: in the root or, as WordPress allows,
+    /// one directory above it.
+    pub config_path: String,
+    pub config: WpConfig,
+    /// The `home` option from the database, when it could be read.
+    pub home_url: Option<String>,
+}
+
+impl WpConfig {
+    /// The MySQL login for this config, password included. `DB_HOST` may
+    /// carry a port (`db:3307`) or a socket
+    /// (`localhost:/run/mysqld/mysqld.sock`).
+    pub fn credentials(&self) -> DbCredentials {
+        let (host, port, socket) = match self.db_host.split_once(':') {
+            Some((host, rest)) if rest.starts_with('/') => (host, None, Some(rest.to_string())),
+            Some((host, rest)) if !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_digit()) => {
+                (host, rest.parse().ok(), None)
+            }
+            _ => (self.db_host.as_str(), None, None),
+        };
+        DbCredentials {
+            id: Uuid::new_v4(),
+            user: self.db_user.clone(),
+            host: host.to_string(),
+            port,
+            socket,
+            origin: None,
+            password: Some(self.db_password.clone()).filter(|p| !p.is_empty()),
+            legacy_args: None,
+        }
+    }
+
+    /// The option file to pass as the secret file of a [`DbLogin`].
+    pub fn option_file(&self) -> String {
+        let creds = self.credentials();
+        creds.mysql_option_file(creds.password.as_deref())
+    }
+}
+
+// ---------------------------------------------------------------------------
+// wp-config.php
+// ---------------------------------------------------------------------------
+
+const DB_CONSTANTS: [&str; 4] = ["DB_NAME", "DB_USER", "DB_PASSWORD", "DB_HOST"];
+
+fn define_regex() -> regex::Regex {
+    regex::Regex::new(
+        r#"define\(\s*['"](DB_NAME|DB_USER|DB_PASSWORD|DB_HOST)['"]\s*,\s*(?:'((?:[^'\\]|\\.)*)'|"((?:[^"\\]|\\.)*)")\s*\)"#,
+    )
+    .expect("valid define regex")
+}
+
+/// Whether the match at `offset` sits on a `//`, `#` or block-comment line.
+fn in_comment(content: &str, offset: usize) -> bool {
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = content[line_start..offset].trim_start();
+    line.starts_with("//") || line.starts_with('#') || line.starts_with('*')
+}
+
+/// Undo PHP string-literal escaping (`\'`, `\"`, `\\`).
+fn php_unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(next @ ('\'' | '"' | '\\' | '$')) => out.push(next),
+                Some(next) => {
+                    out.push('\\');
+                    out.push(next);
+                }
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Read the database settings from a `wp-config.php`. Returns `None` when
+/// `DB_NAME` is not defined with a literal value.
+pub fn parse_wp_config(content: &str) -> Option<WpConfig> {
+    let mut config = WpConfig {
+        db_host: "localhost".to_string(),
+        table_prefix: "wp_".to_string(),
+        ..Default::default()
+    };
+    let mut seen_name = false;
+    let mut seen = [false; 4];
+    for caps in define_regex().captures_iter(content) {
+        let whole = caps.get(0).expect("match");
+        if in_comment(content, whole.start()) {
+            continue;
+        }
+        let constant = &caps[1];
+        let idx = DB_CONSTANTS.iter().position(|c| *c == constant)?;
+        // PHP keeps the first definition of a constant.
+        if seen[idx] {
+            continue;
+        }
+        seen[idx] = true;
+        let value = php_unescape(caps.get(2).or(caps.get(3)).map_or("", |m| m.as_str()));
+        match constant {
+            "DB_NAME" => {
+                seen_name = true;
+                config.db_name = value;
+            }
+            "DB_USER" => config.db_user = value,
+            "DB_PASSWORD" => config.db_password = value,
+            _ => config.db_host = value,
+        }
+    }
+
+    let prefix_re =
+        regex::Regex::new(r#"\$table_prefix\s*=\s*['"]([A-Za-z0-9_]*)['"]"#).expect("valid regex");
+    if let Some(caps) = prefix_re
+        .captures_iter(content)
+        .find(|c| !in_comment(content, c.get(0).expect("match").start()))
+    {
+        config.table_prefix = caps[1].to_string();
+    }
+
+    (seen_name && !config.db_name.is_empty()).then_some(config)
+}
+
+fn php_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Point a `wp-config.php` at another database, leaving everything else
+/// (salts, table prefix, custom constants) untouched.
+pub fn rewrite_wp_config(content: &str, dest: &WpConfig) -> String {
+    define_regex()
+        .replace_all(content, |caps: &regex::Captures| {
+            let value = match &caps[1] {
+                "DB_NAME" => &dest.db_name,
+                "DB_USER" => &dest.db_user,
+                "DB_PASSWORD" => &dest.db_password,
+                _ => &dest.db_host,
+            };
+            format!("define( '{}', {} )", &caps[1], php_quote(value))
+        })
+        .into_owned()
+}
+
+/// Build a command that prints the `wp-config.php` of every root that has
+/// one, each preceded by a `===WP:<root>\t<path>` marker line.
+pub fn wp_discover_command(roots: &[String]) -> String {
+    let roots: Vec<String> = roots
+        .iter()
+        .map(|r| shell_escape(r.trim_end_matches('/')))
+        .collect();
+    if roots.is_empty() {
+        return "true".to_string();
+    }
+    format!(
+        r#"for r in {}; do for f in "$r/wp-config.php" "$(dirname "$r")/wp-config.php"; do if [ -f "$f" ]; then printf '===WP:%s\t%s\n' "$r" "$f"; cat "$f" 2>/dev/null; echo; break; fi; done; done"#,
+        roots.join(" ")
+    )
+}
+
+/// Parse output from [`wp_discover_command`]. Configs without a usable
+/// `DB_NAME` are skipped.
+pub fn parse_wp_discovery(output: &str) -> Vec<DiscoveredWordPress> {
+    let mut found = Vec::new();
+    let mut current: Option<(String, String, String)> = None;
+    let mut finish = |block: Option<(String, String, String)>| {
+        if let Some((root, config_path, content)) = block {
+            if let Some(config) = parse_wp_config(&content) {
+                found.push(DiscoveredWordPress {
+                    root,
+                    config_path,
+                    config,
+                    home_url: None,
+                });
+            }
+        }
+    };
+    for line in output.lines() {
+        if let Some(marker) = line.strip_prefix("===WP:") {
+            finish(current.take());
+            if let Some((root, path)) = marker.split_once('\t') {
+                current = Some((root.to_string(), path.to_string(), String::new()));
+            }
+        } else if let Some((_, _, content)) = current.as_mut() {
+            content.push_str(line);
+            content.push('\n');
+        }
+    }
+    finish(current.take());
+    found
+}
+
+// ---------------------------------------------------------------------------
+// Database commands
+// ---------------------------------------------------------------------------
+
+/// The table prefix if it is safe to splice into SQL.
+fn safe_prefix(config: &WpConfig) -> Option<&str> {
+    let prefix = config.table_prefix.as_str();
+    prefix
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+        .then_some(prefix)
+}
+
+/// Print the site's `home` option.
+pub fn home_url_command(config: &WpConfig, login: &DbLogin) -> Option<String> {
+    let sql = format!(
+        "SELECT option_value FROM `{}options` WHERE option_name = 'home'",
+        safe_prefix(config)?
+    );
+    Some(format!(
+        "{} --batch --skip-column-names -e {} {} < /dev/null 2>/dev/null",
+        login.client(DatabaseEngine::Mysql, "mysql"),
+        shell_escape(&sql),
+        shell_escape(&config.db_name)
+    ))
+}
+
+/// Dump the source database uncompressed; the stream has to pass through
+/// [`SearchReplace`] on its way to the destination.
+pub fn wp_dump_command(source: &WpConfig, login: &DbLogin) -> String {
+    format!(
+        "{} {} --single-transaction --routines --triggers",
+        login.client(DatabaseEngine::Mysql, "mysqldump"),
+        shell_escape(&source.db_name)
+    )
+}
+
+/// Create the destination database when missing, then load the dump.
+pub fn wp_restore_command(dest: &WpConfig, login: &DbLogin) -> String {
+    let mysql = login.client(DatabaseEngine::Mysql, "mysql");
+    let create = format!(
+        "CREATE DATABASE IF NOT EXISTS `{}`",
+        dest.db_name.replace('`', "``")
+    );
+    format!(
+        "{} -e {} && {} {}",
+        mysql,
+        shell_escape(&create),
+        mysql,
+        shell_escape(&dest.db_name)
+    )
+}
+
+/// Replace a file's content in place (keeping its owner and mode) with
+/// what arrives on stdin, without truncating it when the upload fails. Run
+/// with `SshSession::exec_with_stdin`: the content carries the database
+/// password.
+pub fn write_stdin_command(path: &str) -> String {
+    let tmp = shell_escape(&format!("{}.shelldeck-tmp", path));
+    format!(
+        "(umask 077 && cat > {tmp}) && cat {tmp} > {} && rm -f {tmp}",
+        shell_escape(path)
+    )
+}
+
+/// Flush the object cache (through WP-CLI when installed), empty
+/// `wp-content/cache` and drop transients that may still hold old URLs.
+pub fn flush_caches_command(root: &str, config: &WpConfig, login: &DbLogin) -> String {
+    let root = shell_escape(root.trim_end_matches('/'));
+    let mut steps = vec![
+        format!(
+            "if command -v wp >/dev/null 2>&1; then wp cache flush --path={} --allow-root >/dev/null 2>&1 || true; fi",
+            root
+        ),
+        format!("rm -rf {}/wp-content/cache/*", root),
+    ];
+    if let Some(prefix) = safe_prefix(config) {
+        let sql = format!(
+            "DELETE FROM `{}options` WHERE option_name LIKE '\\_transient\\_%' OR option_name LIKE '\\_site\\_transient\\_%'",
+            prefix
+        );
+        steps.push(format!(
+            "{} -e {} {}",
+            login.client(DatabaseEngine::Mysql, "mysql"),
+            shell_escape(&sql),
+            shell_escape(&config.db_name)
+        ));
+    }
+    steps.join("; ")
+}
+
+// ---------------------------------------------------------------------------
+// Serialization-safe search-replace
+// ---------------------------------------------------------------------------
+
+/// Rewrites one URL to another in a mysqldump stream.
+///
+/// Works statement by statement (mysqldump escapes newlines inside values,
+/// so every literal ends on the line it starts). Each string literal is
+/// unescaped, and PHP-serialized values are rebuilt with corrected `s:N:`
+/// lengths instead of being patched textually, which would corrupt them.
+/// The JSON-escaped form (`https:\/\/old`) is replaced as well.
+#[derive(Debug, Default)]
+pub struct SearchReplace {
+    pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    /// The `old` side of `pairs` as it appears in the dump.
+    needles: Vec<Vec<u8>>,
+    pending: Vec<u8>,
+    replacements: u64,
+}
+
+impl SearchReplace {
+    /// A replacer for `old` → `new`. Trailing slashes are ignored; equal or
+    /// empty URLs give a pass-through.
+    pub fn new(old: &str, new: &str) -> Self {
+        let old = old.trim().trim_end_matches('/');
+        let new = new.trim().trim_end_matches('/');
+        let mut pairs = Vec::new();
+        if !old.is_empty() && old != new {
+            pairs.push((old.as_bytes().to_vec(), new.as_bytes().to_vec()));
+            if old.contains('/') {
+                pairs.push((
+                    old.replace('/', "\\/").into_bytes(),
+                    new.replace('/', "\\/").into_bytes(),
+                ));
+            }
+        }
+        let needles = pairs.iter().map(|(old, _)| sql_escape(old)).collect();
+        Self {
+            pairs,
+            needles,
+            pending: Vec::new(),
+            replacements: 0,
+        }
+    }
+
+    /// Number of occurrences replaced so far.
+    pub fn replacements(&self) -> u64 {
+        self.replacements
+    }
+
+    /// Feed the next chunk of the dump (`None` at EOF) and get back what
+    /// can be written out; the unfinished last line is held back.
+    pub fn feed(&mut self, chunk: Option<&[u8]>) -> Vec<u8> {
+        match chunk {
+            Some(data) => self.push(data),
+            None => self.finish(),
+        }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        if self.pairs.is_empty() {
+            return chunk.to_vec();
+        }
+        self.pending.extend_from_slice(chunk);
+        let Some(last_newline) = self.pending.iter().rposition(|b| *b == b'\n') else {
+            return Vec::new();
+        };
+        let rest = self.pending.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        let mut out = Vec::with_capacity(complete.len());
+        for line in complete.split_inclusive(|b| *b == b'\n') {
+            out.extend(self.replace_line(line));
+        }
+        out
+    }
+
+    pub fn finish(&mut self) -> Vec<u8> {
+        let rest = std::mem::take(&mut self.pending);
+        self.replace_line(&rest)
+    }
+
+    fn replace_line(&mut self, line: &[u8]) -> Vec<u8> {
+        if !self.needles.iter().any(|n| contains(line, n)) {
+            return line.to_vec();
+        }
+        let mut out = Vec::with_capacity(line.len());
+        let mut i = 0;
+        while i < line.len() {
+            match line[i] {
+                b'`' => {
+                    let end = line[i + 1..]
+                        .iter()
+                        .position(|b| *b == b'`')
+                        .map_or(line.len(), |p| i + 1 + p + 1);
+                    out.extend_from_slice(&line[i..end]);
+                    i = end;
+                }
+                b'\'' => {
+                    let Some((value, end)) = sql_literal(line, i + 1) else {
+                        // Unterminated literal: leave the rest as dumped.
+                        out.extend_from_slice(&line[i..]);
+                        break;
+                    };
+                    let replaced = replace_value(&value, &self.pairs, &mut self.replacements);
+                    out.push(b'\'');
+                    out.extend(sql_escape(&replaced));
+                    out.push(b'\'');
+                    i = end;
+                }
+                b => {
+                    out.push(b);
+                    i += 1;
+                }
+            }
+        }
+        out
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    !needle.is_empty() && haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+/// Read a single-quoted SQL literal whose content starts at `start`.
+/// Returns the unescaped value and the index after the closing quote.
+fn sql_literal(line: &[u8], start: usize) -> Option<(Vec<u8>, usize)> {
+    let mut value = Vec::new();
+    let mut i = start;
+    while i < line.len() {
+        match line[i] {
+            b'\\' => {
+                let next = *line.get(i + 1)?;
+                value.push(match next {
+                    b'0' => 0,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'b' => 0x08,
+                    b'Z' => 0x1a,
+                    other => other,
+                });
+                i += 2;
+            }
+            b'\'' if line.get(i + 1) == Some(&b'\'') => {
+                value.push(b'\'');
+                i += 2;
+            }
+            b'\'' => return Some((value, i + 1)),
+            b => {
+                value.push(b);
+                i += 1;
+            }
+        }
+    }
+    None
+}
+
+/// Escape a value the way mysqldump does.
+fn sql_escape(value: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(value.len());
+    for &b in value {
+        match b {
+            0 => out.extend_from_slice(b"\\0"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            0x1a => out.extend_from_slice(b"\\Z"),
+            b'\'' => out.extend_from_slice(b"\\'"),
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b => out.push(b),
+        }
+    }
+    out
+}
+
+/// Replace inside one column value, rebuilding it when it is PHP-serialized.
+fn replace_value(value: &[u8], pairs: &[(Vec<u8>, Vec<u8>)], count: &mut u64) -> Vec<u8> {
+    if !pairs.iter().any(|(old, _)| contains(value, old)) {
+        return value.to_vec();
+    }
+    let mut parser = Unserializer {
+        input: value,
+        pos: 0,
+        out: Vec::with_capacity(value.len()),
+        pairs,
+        count: 0,
+    };
+    if parser.value().is_some() && parser.pos == value.len() {
+        *count += parser.count;
+        return parser.out;
+    }
+    let mut out = value.to_vec();
+    for (old, new) in pairs {
+        out = replace_bytes(&out, old, new, count);
+    }
+    out
+}
+
+fn replace_bytes(haystack: &[u8], old: &[u8], new: &[u8], count: &mut u64) -> Vec<u8> {
+    let mut out = Vec::with_capacity(haystack.len());
+    let mut i = 0;
+    while i < haystack.len() {
+        if haystack[i..].starts_with(old) {
+            out.extend_from_slice(new);
+            i += old.len();
+            *count += 1;
+        } else {
+            out.push(haystack[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Re-emits PHP `serialize()` output with replaced string contents.
+struct Unserializer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    out: Vec<u8>,
+    pairs: &'a [(Vec<u8>, Vec<u8>)],
+    count: u64,
+}
+
+impl Unserializer<'_> {
+    fn expect(&mut self, bytes: &[u8]) -> Option<()> {
+        self.input[self.pos..].starts_with(bytes).then_some(())?;
+        self.pos += bytes.len();
+        Some(())
+    }
+
+    /// Read digits up to `terminator` (consumed).
+    fn number(&mut self, terminator: u8) -> Option<usize> {
+        let len = self.input[self.pos..]
+            .iter()
+            .position(|b| *b == terminator)?;
+        let digits = std::str::from_utf8(&self.input[self.pos..self.pos + len]).ok()?;
+        let n = digits.parse().ok()?;
+        self.pos += len + 1;
+        Some(n)
+    }
+
+    /// Read `len` raw bytes followed by `"`.
+    fn quoted(&mut self, len: usize) -> Option<&[u8]> {
+        self.expect(b"\"")?;
+        let start = self.pos;
+        let end = start.checked_add(len)?;
+        if end > self.input.len() {
+            return None;
+        }
+        self.pos = end;
+        self.expect(b"\"")?;
+        Some(&self.input[start..end])
+    }
+
+    fn copy_from(&mut self, start: usize) {
+        self.out.extend_from_slice(&self.input[start..self.pos]);
+    }
+
+    fn value(&mut self) -> Option<()> {
+        let start = self.pos;
+        let tag = *self.input.get(self.pos)?;
+        if tag == b'N' {
+            self.expect(b"N;")?;
+            self.copy_from(start);
+            return Some(());
+        }
+        self.pos += 1;
+        self.expect(b":")?;
+        match tag {
+            b'b' | b'i' | b'd' | b'r' | b'R' => {
+                let len = self.input[self.pos..].iter().position(|b| *b == b';')?;
+                self.pos += len + 1;
+                self.copy_from(start);
+            }
+            b's' => {
+                let len = self.number(b':')?;
+                let raw = self.quoted(len)?.to_vec();
+                self.expect(b";")?;
+                let replaced = replace_value(&raw, self.pairs, &mut self.count);
+                self.out
+                    .extend_from_slice(format!("s:{}:\"", replaced.len()).as_bytes());
+                self.out.extend(replaced);
+                self.out.extend_from_slice(b"\";");
+            }
+            b'E' => {
+                let len = self.number(b':')?;
+                self.quoted(len)?;
+                self.expect(b";")?;
+                self.copy_from(start);
+            }
+            b'a' => {
+                let n = self.number(b':')?;
+                self.expect(b"{")?;
+                self.copy_from(start);
+                self.entries(n)?;
+            }
+            b'O' => {
+                let len = self.number(b':')?;
+                self.quoted(len)?;
+                self.expect(b":")?;
+                let n = self.number(b':')?;
+                self.expect(b"{")?;
+                self.copy_from(start);
+                self.entries(n)?;
+            }
+            b'C' => {
+                // Custom serialization: opaque payload, copied verbatim.
+                let len = self.number(b':')?;
+                self.quoted(len)?;
+                self.expect(b":")?;
+                let n = self.number(b':')?;
+                self.expect(b"{")?;
+                self.pos = self.pos.checked_add(n)?;
+                if self.pos > self.input.len() {
+                    return None;
+                }
+                self.expect(b"}")?;
+                self.copy_from(start);
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    /// `n` key/value pairs followed by `}`.
+    fn entries(&mut self, n: usize) -> Option<()> {
+        for _ in 0..n {
+            self.value()?;
+            self.value()?;
+        }
+        let start = self.pos;
+        self.expect(b"}")?;
+        self.copy_from(start);
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WP_CONFIG: &str = r#"<?php
+// ** Database settings ** //
+// define( 'DB_NAME', 'commented_out' );
+define( 'DB_NAME', 'blog_db' );
+define( 'DB_USER', "blog_user" );
+define( 'DB_PASSWORD', 'p@ss\'word' );
+define( 'DB_HOST', 'localhost:/run/mysqld/mysqld.sock' );
+define( 'DB_CHARSET', 'utf8mb4' );
+define( 'AUTH_KEY', 'salt' );
+$table_prefix = 'blog_';
+require_once ABSPATH . 'wp-settings.php';
+"#;
+
+    fn run(replacer: &mut SearchReplace, dump: &[u8], chunk: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        for part in dump.chunks(chunk) {
+            out.extend(replacer.feed(Some(part)));
+        }
+        out.extend(replacer.feed(None));
+        out
+    }
+
+    #[test]
+    fn parses_wp_config_skipping_comments() {
+        let config = parse_wp_config(WP_CONFIG).unwrap();
+        assert_eq!(
+            config,
+            WpConfig {
+                db_name: "blog_db".into(),
+                db_user: "blog_user".into(),
+                db_password: "p@ss'word".into(),
+                db_host: "localhost:/run/mysqld/mysqld.sock".into(),
+                table_prefix: "blog_".into(),
+            }
+        );
+        assert_eq!(parse_wp_config("<?php echo 1;"), None);
+    }
+
+    #[test]
+    fn rewrites_only_database_constants() {
+        let dest = WpConfig {
+            db_name: "new_db".into(),
+            db_user: "new_user".into(),
+            db_password: "it's".into(),
+            db_host: "db.internal:3307".into(),
+            table_prefix: "ignored_".into(),
+        };
+        let rewritten = rewrite_wp_config(WP_CONFIG, &dest);
+        assert!(rewritten.contains("define( 'DB_NAME', 'new_db' )"));
+        assert!(rewritten.contains("define( 'DB_PASSWORD', 'it\\'s' )"));
+        assert!(rewritten.contains("define( 'AUTH_KEY', 'salt' );"));
+        assert!(rewritten.contains("$table_prefix = 'blog_';"));
+
+        let mut reparsed = parse_wp_config(&rewritten).unwrap();
+        reparsed.table_prefix = dest.table_prefix.clone();
+        assert_eq!(reparsed, dest);
+    }
+
+    #[test]
+    fn parses_discovery_output() {
+        let output = format!(
+            "===WP:/var/www/blog\t/var/www/wp-config.php\n{}===WP:/var/www/static\t/var/www/static/wp-config.php\n<?php\n",
+            WP_CONFIG
+        );
+        let found = parse_wp_discovery(&output);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].root, "/var/www/blog");
+        assert_eq!(found[0].config_path, "/var/www/wp-config.php");
+        assert_eq!(found[0].config.db_name, "blog_db");
+
+        let cmd = wp_discover_command(&["/var/www/blog/".to_string()]);
+        assert!(cmd.starts_with("for r in '/var/www/blog'; do"));
+        assert_eq!(wp_discover_command(&[]), "true");
+    }
+
+    #[test]
+    fn option_files_carry_ports_sockets_and_the_password() {
+        let mut config = parse_wp_config(WP_CONFIG).unwrap();
+        assert_eq!(
+            config.option_file(),
+            "[client]\nuser=blog_user\npassword=\"p@ss'word\"\nhost=localhost\nsocket=/run/mysqld/mysqld.sock\n"
+        );
+        config.db_host = "db:3307".into();
+        config.db_password.clear();
+        assert_eq!(
+            config.option_file(),
+            "[client]\nuser=blog_user\nhost=db\nport=3307\n"
+        );
+        assert!(!serde_json::to_string(&config)
+            .unwrap()
+            .contains("db_password"));
+    }
+
+    #[test]
+    fn restore_creates_missing_database() {
+        let config = parse_wp_config(WP_CONFIG).unwrap();
+        let login = DbLogin::new(None, Some("/tmp/shelldeck.x".into()));
+        let cmd = wp_restore_command(&config, &login);
+        assert!(cmd.starts_with("mysql --defaults-extra-file='/tmp/shelldeck.x' -e 'CREATE DATABASE IF NOT EXISTS `blog_db`' && mysql"));
+        assert!(cmd.ends_with(" 'blog_db'"));
+        assert!(!cmd.contains("p@ss"));
+    }
+
+    #[test]
+    fn flush_drops_transients_only_with_safe_prefix() {
+        let mut config = parse_wp_config(WP_CONFIG).unwrap();
+        let login = DbLogin::default();
+        let cmd = flush_caches_command("/var/www/blog/", &config, &login);
+        assert!(cmd.contains("rm -rf '/var/www/blog'/wp-content/cache/*"));
+        assert!(cmd.contains("DELETE FROM `blog_options`"));
+        config.table_prefix = "x`; DROP".into();
+        assert!(!flush_caches_command("/var/www/blog", &config, &login).contains("DELETE"));
+        assert_eq!(home_url_command(&config, &login), None);
+    }
+
+    #[test]
+    fn write_stdin_command_replaces_in_place() {
+        assert_eq!(
+            write_stdin_command("/var/www/wp-config.php"),
+            "(umask 077 && cat > '/var/www/wp-config.php.shelldeck-tmp') && cat '/var/www/wp-config.php.shelldeck-tmp' > '/var/www/wp-config.php' && rm -f '/var/www/wp-config.php.shelldeck-tmp'"
+        );
+    }
+
+    #[test]
+    fn replaces_plain_values_and_json_escapes() {
+        let mut replacer = SearchReplace::new("https://old.example/", "https://new.example.org");
+        let dump = b"INSERT INTO `wp_posts` VALUES (1,'<a href=\\\"https://old.example/about\\\">','{\\\"url\\\":\\\"https:\\\\/\\\\/old.example\\\\/x\\\"}');\n";
+        let out = run(&mut replacer, dump, 7);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "INSERT INTO `wp_posts` VALUES (1,'<a href=\\\"https://new.example.org/about\\\">','{\\\"url\\\":\\\"https:\\\\/\\\\/new.example.org\\\\/x\\\"}');\n"
+        );
+        assert_eq!(replacer.replacements(), 2);
+    }
+
+    #[test]
+    fn fixes_serialized_string_lengths() {
+        let mut replacer = SearchReplace::new("http://old.test", "https://www.new.test");
+        let dump = "INSERT INTO `wp_options` VALUES (1,'widget','a:2:{s:3:\\\"url\\\";s:19:\\\"http://old.test/img\\\";i:0;O:8:\\\"stdClass\\\":1:{s:4:\\\"home\\\";s:15:\\\"http://old.test\\\";}}','yes');\n";
+        let out = String::from_utf8(run(&mut replacer, dump.as_bytes(), 4096)).unwrap();
+        assert_eq!(
+            out,
+            "INSERT INTO `wp_options` VALUES (1,'widget','a:2:{s:3:\\\"url\\\";s:24:\\\"https://www.new.test/img\\\";i:0;O:8:\\\"stdClass\\\":1:{s:4:\\\"home\\\";s:20:\\\"https://www.new.test\\\";}}','yes');\n"
+        );
+        assert_eq!(replacer.replacements(), 2);
+    }
+
+    #[test]
+    fn fixes_nested_serialized_payloads() {
+        let inner = "a:1:{i:0;s:14:\"http://old.dev\";}";
+        let value = format!("s:{}:\"{}\";", inner.len(), inner);
+        let pairs = vec![(b"http://old.dev".to_vec(), b"https://new.dev".to_vec())];
+        let mut count = 0;
+        let out = replace_value(value.as_bytes(), &pairs, &mut count);
+        let new_inner = "a:1:{i:0;s:15:\"https://new.dev\";}";
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!("s:{}:\"{}\";", new_inner.len(), new_inner)
+        );
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn serialized_lengths_count_bytes_not_chars() {
+        let pairs = vec![(b"old.fr".to_vec(), b"nouveau.fr".to_vec())];
+        let mut count = 0;
+        let value = "s:15:\"café on old.fr\";";
+        let out = replace_value(value.as_bytes(), &pairs, &mut count);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "s:19:\"café on nouveau.fr\";"
+        );
+    }
+
+    #[test]
+    fn broken_serialization_falls_back_to_plain_replace() {
+        let pairs = vec![(b"old.io".to_vec(), b"new.io".to_vec())];
+        let mut count = 0;
+        let out = replace_value(b"s:99:\"old.io\";", &pairs, &mut count);
+        assert_eq!(out, b"s:99:\"new.io\";");
+    }
+
+    #[test]
+    fn lines_without_the_url_pass_through_untouched() {
+        let mut replacer = SearchReplace::new("http://old.test", "http://new.test");
+        let dump =
+            b"-- MySQL dump\nINSERT INTO t VALUES ('a\\'b','\\0');\nunterminated 'http://old.test";
+        let out = run(&mut replacer, dump, 3);
+        assert_eq!(out, dump);
+        assert_eq!(replacer.replacements(), 0);
+
+        let mut same = SearchReplace::new("http://a.test", "http://a.test/");
+        assert_eq!(same.feed(Some(b"'http://a.test'")), b"'http://a.test'");
+    }
+}
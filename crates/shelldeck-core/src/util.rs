@@ -13,6 +13,18 @@ use std::path::{Path, PathBuf};
 /// target file name only — no randomness or clock is used, so it works even
 /// when those facilities are unavailable.
 pub fn atomic_write(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    write_and_rename(path, contents, None)
+}
+
+/// [`atomic_write`] for a file that gets Unix permission bits `mode`. The
+/// temp file has them before any content is written, so a private file is
+/// never readable by others, even for a moment. `mode` is ignored on other
+/// platforms.
+pub fn atomic_write_with_mode(path: &Path, contents: &[u8], mode: u32) -> std::io::Result<()> {
+    write_and_rename(path, contents, Some(mode))
+}
+
+fn write_and_rename(path: &Path, contents: &[u8], mode: Option<u32>) -> std::io::Result<()> {
     let dir = path.parent().unwrap_or_else(|| Path::new("."));
     let file_name = path
         .file_name()
@@ -23,7 +35,23 @@ pub fn atomic_write(path: &Path, contents: &[u8]) -> std::io::Result<()> {
 
     // Write + flush the temp file, cleaning it up on any error.
     let write_result = (|| {
-        let mut file = std::fs::File::create(&tmp_path)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(mode);
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        let mut file = options.open(&tmp_path)?;
+        // A leftover temp file keeps its old mode, and umask narrows a new
+        // one.
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(std::fs::Permissions::from_mode(mode))?;
+        }
         file.write_all(contents)?;
         file.flush()?;
         file.sync_all()?;
@@ -28,6 +28,10 @@ impl SshClient {
         }
     }
 
+    pub(crate) fn config(&self) -> Arc<client::Config> {
+        self.config.clone()
+    }
+
     /// Connect to a remote host and authenticate.
     ///
     /// If the connection has a `proxy_jump` set, the client will first connect
@@ -197,7 +201,7 @@ impl SshClient {
     /// - `user@host`             -> user @ host : 22
     /// - `user@host:port`        -> user @ host : port
     /// - `ssh://user@host:port`  -> user @ host : port
-    fn parse_jump_spec(spec: &str) -> crate::Result<Connection> {
+    pub(crate) fn parse_jump_spec(spec: &str) -> crate::Result<Connection> {
         let spec = spec.trim();
 
         // Strip optional ssh:// prefix
@@ -304,9 +308,9 @@ impl SshClient {
         ))
     }
 
-    async fn auth_with_key(
+    pub(crate) async fn auth_with_key<H: client::Handler>(
         &self,
-        handle: &mut client::Handle<ClientHandler>,
+        handle: &mut client::Handle<H>,
         user: &str,
         key_path: &Path,
     ) -> crate::Result<()> {
@@ -385,9 +389,9 @@ impl SshClient {
         Ok(())
     }
 
-    async fn auth_with_password(
+    pub(crate) async fn auth_with_password<H: client::Handler>(
         &self,
-        handle: &mut client::Handle<ClientHandler>,
+        handle: &mut client::Handle<H>,
         user: &str,
         hostname: &str,
     ) -> crate::Result<()> {
@@ -433,7 +437,7 @@ impl SshClient {
 /// `ProxyJump none`, and when the field is present but blank. Only the first
 /// hop of a comma-separated chain is honored; chained jumps are not supported
 /// yet and silently using the last hop would connect through the wrong host.
-fn first_jump_hop(proxy_jump: &str) -> Option<&str> {
+pub(crate) fn first_jump_hop(proxy_jump: &str) -> Option<&str> {
     let first_hop = proxy_jump.split(',').next().unwrap_or(proxy_jump).trim();
     if first_hop.is_empty() || first_hop.eq_ignore_ascii_case("none") {
         return None;
@@ -449,12 +453,12 @@ fn first_jump_hop(proxy_jump: &str) -> Option<&str> {
 /// probing fabricated root-level paths like `/.ssh/id_ed25519`. No home →
 /// no default keys to try; explicit `identity_file` and the keychain
 /// password fallback still apply.
-fn default_key_candidates(home: Option<PathBuf>) -> Vec<PathBuf> {
+pub(crate) fn default_key_candidates(home: Option<PathBuf>) -> Vec<PathBuf> {
     let Some(home) = home else {
         return Vec::new();
     };
     let ssh_dir = home.join(".ssh");
-    ["id_ed25519", "id_rsa", "id_ecdsa"]
+    shelldeck_core::config::ssh_keys::DEFAULT_KEY_NAMES
         .into_iter()
         .map(|name| ssh_dir.join(name))
         .collect()
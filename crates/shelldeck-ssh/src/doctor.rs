@@ -0,0 +1,1115 @@
+//! SSH doctor: connect to a host one stage at a time and report every stage
+//! on its own, so a connection that fails says where and why.
+//!
+//! The run changes nothing: an unknown host key is reported, not added to
+//! known_hosts, and no saved password is sent to a host whose key is not
+//! already known.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use russh::client;
+use russh::keys::PublicKeyBase64;
+use russh::ChannelMsg;
+use shelldeck_core::models::diagnostics::{
+    DiagnosticFix, DiagnosticStage, DiagnosticStep, StepStatus,
+};
+use shelldeck_core::models::Connection;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use crate::client::{default_key_candidates, first_jump_hop, SshClient};
+use crate::known_hosts::{self, KnownHostResult};
+use crate::SshError;
+
+/// Upper bound for one network step.
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+/// Upper bound for one TCP connect; a resolver can return several addresses.
+const TCP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Addresses tried per host name.
+const MAX_ADDRESSES: usize = 8;
+/// Identification the banner probe sends before reading the server's offer.
+const PROBE_IDENT: &str = "SSH-2.0-ShellDeck_doctor";
+/// Lines a server may send before its identification (RFC 4253 §4.2).
+const MAX_PRE_IDENT_LINES: usize = 32;
+const MAX_IDENT_LINE: u64 = 1024;
+const MAX_PACKET: usize = 256 * 1024;
+const SSH_MSG_KEXINIT: u8 = 20;
+
+/// Diagnose `connection`, sending each step to `steps` as soon as it is
+/// known. Returns when the run is over; the sender is dropped with it.
+pub async fn diagnose(connection: &Connection, steps: mpsc::UnboundedSender<DiagnosticStep>) {
+    let trace = Trace {
+        steps,
+        started: Instant::now(),
+    };
+    let client = SshClient::new();
+    let name = connection.display_name().to_string();
+
+    let Some(jump_spec) = connection.proxy_jump.as_deref().and_then(first_jump_hop) else {
+        let Some(addr) = resolve(&connection.hostname, connection.port, &name, &trace).await else {
+            return;
+        };
+        // The banner probe speaks raw SSH, so it gets a connection of its own.
+        let Some(stream) = reconnect(addr, &name, &trace).await else {
+            return;
+        };
+        if !probe_transport(stream, &name, &trace).await {
+            return;
+        }
+        let Some(stream) = reconnect(addr, &name, &trace).await else {
+            return;
+        };
+        if let Some(handle) = handshake(&client, stream, connection, &name, &trace).await {
+            open_session(&handle, &name, &trace).await;
+            hang_up(&handle).await;
+        }
+        return;
+    };
+
+    let hops = connection
+        .proxy_jump
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|hop| !hop.trim().is_empty())
+        .count();
+    if hops > 1 {
+        trace.push(
+            DiagnosticStep::new(
+                DiagnosticStage::Jump,
+                jump_spec,
+                StepStatus::Warning,
+                format!("ProxyJump lists {} hops; only the first one is used", hops),
+            )
+            .with_fix(DiagnosticFix::CheckJumpHost),
+        );
+    }
+    let hop = match SshClient::parse_jump_spec(jump_spec) {
+        Ok(hop) => hop,
+        Err(e) => {
+            trace.push(DiagnosticStep::failed(
+                DiagnosticStage::Jump,
+                jump_spec,
+                e.to_string(),
+                DiagnosticFix::CheckJumpHost,
+            ));
+            return;
+        }
+    };
+
+    let Some(jump) = reach_jump_host(&client, &hop, jump_spec, &trace).await else {
+        trace.push(DiagnosticStep::failed(
+            DiagnosticStage::Jump,
+            jump_spec,
+            format!("{} could not be used, so {} was not tried", jump_spec, name),
+            DiagnosticFix::CheckJumpHost,
+        ));
+        return;
+    };
+    trace.push(DiagnosticStep::ok(
+        DiagnosticStage::Jump,
+        jump_spec,
+        format!("Signed in to {}", hop.connection_string()),
+    ));
+
+    let started = Instant::now();
+    let Some(channel) = open_through_jump(&jump, connection, jump_spec, &name, &trace).await else {
+        return;
+    };
+    trace.push(
+        DiagnosticStep::ok(
+            DiagnosticStage::Tcp,
+            &name,
+            format!(
+                "{} opened {}:{} (the name is resolved by the jump host)",
+                jump_spec, connection.hostname, connection.port
+            ),
+        )
+        .with_elapsed(started.elapsed()),
+    );
+    if !probe_transport(channel.into_stream(), &name, &trace).await {
+        return;
+    }
+    let Some(channel) = open_through_jump(&jump, connection, jump_spec, &name, &trace).await else {
+        return;
+    };
+    if let Some(handle) = handshake(&client, channel.into_stream(), connection, &name, &trace).await
+    {
+        open_session(&handle, &name, &trace).await;
+        hang_up(&handle).await;
+    }
+    hang_up(&jump).await;
+}
+
+struct Trace {
+    steps: mpsc::UnboundedSender<DiagnosticStep>,
+    started: Instant,
+}
+
+impl Trace {
+    fn push(&self, step: DiagnosticStep) {
+        let _ = self.steps.send(step);
+    }
+}
+
+/// DNS, TCP, banner and sign-in for the jump host.
+async fn reach_jump_host(
+    client: &SshClient,
+    hop: &Connection,
+    name: &str,
+    trace: &Trace,
+) -> Option<client::Handle<DoctorHandler>> {
+    let addr = resolve(&hop.hostname, hop.port, name, trace).await?;
+    let stream = reconnect(addr, name, trace).await?;
+    if !probe_transport(stream, name, trace).await {
+        return None;
+    }
+    let stream = reconnect(addr, name, trace).await?;
+    handshake(client, stream, hop, name, trace).await
+}
+
+async fn open_through_jump(
+    jump: &client::Handle<DoctorHandler>,
+    target: &Connection,
+    jump_spec: &str,
+    name: &str,
+    trace: &Trace,
+) -> Option<russh::Channel<client::Msg>> {
+    let opened = timeout(
+        STEP_TIMEOUT,
+        jump.channel_open_direct_tcpip(&target.hostname, target.port as u32, "127.0.0.1", 0),
+    )
+    .await;
+    let error = match opened {
+        Ok(Ok(channel)) => return Some(channel),
+        Ok(Err(e)) => e.to_string(),
+        Err(_) => format!("no answer within {} s", STEP_TIMEOUT.as_secs()),
+    };
+    trace.push(DiagnosticStep::failed(
+        DiagnosticStage::Tcp,
+        name,
+        format!(
+            "{} could not open {}:{}: {}",
+            jump_spec, target.hostname, target.port, error
+        ),
+        DiagnosticFix::CheckJumpHost,
+    ));
+    None
+}
+
+/// Resolve `host` and try a TCP connect to each address. Returns the first
+/// address that accepted.
+async fn resolve(host: &str, port: u16, name: &str, trace: &Trace) -> Option<SocketAddr> {
+    let started = Instant::now();
+    let addrs: Vec<SocketAddr> =
+        match timeout(STEP_TIMEOUT, tokio::net::lookup_host((host, port))).await {
+            Ok(Ok(addrs)) => addrs.take(MAX_ADDRESSES).collect(),
+            Ok(Err(e)) => {
+                trace.push(
+                    DiagnosticStep::failed(
+                        DiagnosticStage::Dns,
+                        name,
+                        format!("{} does not resolve: {}", host, e),
+                        DiagnosticFix::CheckHostname,
+                    )
+                    .with_elapsed(started.elapsed()),
+                );
+                return None;
+            }
+            Err(_) => {
+                trace.push(DiagnosticStep::failed(
+                    DiagnosticStage::Dns,
+                    name,
+                    format!("No answer for {} within {} s", host, STEP_TIMEOUT.as_secs()),
+                    DiagnosticFix::CheckHostname,
+                ));
+                return None;
+            }
+        };
+    if addrs.is_empty() {
+        trace.push(DiagnosticStep::failed(
+            DiagnosticStage::Dns,
+            name,
+            format!("{} resolves to no address", host),
+            DiagnosticFix::CheckHostname,
+        ));
+        return None;
+    }
+    let listed: Vec<String> = addrs.iter().map(|a| a.ip().to_string()).collect();
+    trace.push(
+        DiagnosticStep::ok(
+            DiagnosticStage::Dns,
+            name,
+            format!("{} → {}", host, listed.join(", ")),
+        )
+        .with_elapsed(started.elapsed()),
+    );
+
+    let mut results = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        let started = Instant::now();
+        let result = match timeout(TCP_TIMEOUT, TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => Err((
+                format!("{} refused the connection", addr),
+                DiagnosticFix::CheckPort,
+            )),
+            Ok(Err(e)) => Err((format!("{}: {}", addr, e), DiagnosticFix::CheckNetwork)),
+            Err(_) => Err((
+                format!("{} did not answer within {} s", addr, TCP_TIMEOUT.as_secs()),
+                DiagnosticFix::CheckNetwork,
+            )),
+        };
+        results.push((addr, started.elapsed(), result));
+    }
+    // An address that fails next to one that works is only worth a warning.
+    let reachable = results
+        .iter()
+        .find(|(_, _, r)| r.is_ok())
+        .map(|(a, _, _)| *a);
+    for (addr, elapsed, result) in results {
+        let step = match result {
+            Ok(()) => {
+                DiagnosticStep::ok(DiagnosticStage::Tcp, name, format!("Connected to {}", addr))
+            }
+            Err((detail, fix)) => DiagnosticStep::new(
+                DiagnosticStage::Tcp,
+                name,
+                if reachable.is_some() {
+                    StepStatus::Warning
+                } else {
+                    StepStatus::Failed
+                },
+                detail,
+            )
+            .with_fix(fix),
+        };
+        trace.push(step.with_elapsed(elapsed));
+    }
+    reachable
+}
+
+async fn reconnect(addr: SocketAddr, name: &str, trace: &Trace) -> Option<TcpStream> {
+    match timeout(TCP_TIMEOUT, TcpStream::connect(addr)).await {
+        Ok(Ok(stream)) => Some(stream),
+        Ok(Err(e)) => {
+            trace.push(DiagnosticStep::failed(
+                DiagnosticStage::Tcp,
+                name,
+                format!("{} stopped accepting connections: {}", addr, e),
+                DiagnosticFix::CheckNetwork,
+            ));
+            None
+        }
+        Err(_) => {
+            trace.push(DiagnosticStep::failed(
+                DiagnosticStage::Tcp,
+                name,
+                format!("{} stopped answering", addr),
+                DiagnosticFix::CheckNetwork,
+            ));
+            None
+        }
+    }
+}
+
+/// Read the server's identification and key exchange offer, and check that
+/// we share an algorithm in every category. Returns whether to go on.
+async fn probe_transport<S>(stream: S, name: &str, trace: &Trace) -> bool
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let started = Instant::now();
+    let probe = match timeout(STEP_TIMEOUT, read_transport(stream)).await {
+        Ok(Ok(probe)) => probe,
+        Ok(Err(e)) => {
+            trace.push(
+                DiagnosticStep::failed(DiagnosticStage::Banner, name, e, DiagnosticFix::NotSsh)
+                    .with_elapsed(started.elapsed()),
+            );
+            return false;
+        }
+        Err(_) => {
+            trace.push(DiagnosticStep::failed(
+                DiagnosticStage::Banner,
+                name,
+                format!("No SSH identification within {} s", STEP_TIMEOUT.as_secs()),
+                DiagnosticFix::NotSsh,
+            ));
+            return false;
+        }
+    };
+    trace.push(
+        DiagnosticStep::ok(DiagnosticStage::Banner, name, probe.ident)
+            .with_elapsed(started.elapsed()),
+    );
+    let step = kex_step(name, &ClientAlgorithms::russh_defaults(), &probe.offer);
+    let go_on = step.status != StepStatus::Failed;
+    trace.push(step);
+    go_on
+}
+
+struct TransportProbe {
+    ident: String,
+    offer: KexInit,
+}
+
+async fn read_transport<S>(stream: S) -> Result<TransportProbe, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut stream = BufReader::new(stream);
+    let hello = format!("{}\r\n", PROBE_IDENT);
+    let io = |e: std::io::Error| e.to_string();
+    stream
+        .get_mut()
+        .write_all(hello.as_bytes())
+        .await
+        .map_err(io)?;
+    stream.get_mut().flush().await.map_err(io)?;
+    let ident = read_ident(&mut stream).await?;
+    let payload = read_packet(&mut stream).await?;
+    let offer = KexInit::parse(&payload)
+        .ok_or_else(|| format!("{} sent no readable key exchange offer", ident))?;
+    Ok(TransportProbe { ident, offer })
+}
+
+/// The server's `SSH-2.0-…` line, skipping the lines a server may print
+/// before it.
+async fn read_ident<R>(reader: &mut R) -> Result<String, String>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut first_other: Option<String> = None;
+    for _ in 0..MAX_PRE_IDENT_LINES {
+        let mut line = Vec::new();
+        let read = (&mut *reader)
+            .take(MAX_IDENT_LINE)
+            .read_until(b'\n', &mut line)
+            .await
+            .map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        let text = String::from_utf8_lossy(&line).trim_end().to_string();
+        if text.starts_with("SSH-2.0-") || text.starts_with("SSH-1.99-") {
+            return Ok(text);
+        }
+        if text.starts_with("SSH-") {
+            return Err(format!("Unsupported SSH protocol version: {}", text));
+        }
+        if first_other.is_none() && !text.is_empty() {
+            first_other = Some(text);
+        }
+    }
+    Err(match first_other {
+        Some(text) => format!("Not an SSH server; it answered: {}", text),
+        None => "The server closed the connection without identifying itself".to_string(),
+    })
+}
+
+/// The payload of one unencrypted binary packet (RFC 4253 §6).
+async fn read_packet<R>(reader: &mut R) -> Result<Vec<u8>, String>
+where
+    R: AsyncRead + Unpin,
+{
+    let malformed = || "The server sent a malformed packet".to_string();
+    let len = reader.read_u32().await.map_err(|e| e.to_string())? as usize;
+    if !(5..=MAX_PACKET).contains(&len) {
+        return Err(malformed());
+    }
+    let mut packet = vec![0; len];
+    reader
+        .read_exact(&mut packet)
+        .await
+        .map_err(|e| e.to_string())?;
+    let padding = packet[0] as usize;
+    if padding + 1 > len {
+        return Err(malformed());
+    }
+    packet.truncate(len - padding);
+    packet.remove(0);
+    Ok(packet)
+}
+
+/// The algorithm lists of a server's `SSH_MSG_KEXINIT`, client-to-server
+/// direction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct KexInit {
+    kex: Vec<String>,
+    host_key: Vec<String>,
+    cipher: Vec<String>,
+    mac: Vec<String>,
+}
+
+impl KexInit {
+    fn parse(payload: &[u8]) -> Option<Self> {
+        let (&msg, rest) = payload.split_first()?;
+        if msg != SSH_MSG_KEXINIT {
+            return None;
+        }
+        // 16-byte cookie, then kex, host key, cipher c→s, cipher s→c,
+        // mac c→s and mac s→c name-lists.
+        let mut rest = rest.get(16..)?;
+        let mut lists = Vec::with_capacity(6);
+        for _ in 0..6 {
+            let (list, tail) = name_list(rest)?;
+            lists.push(list);
+            rest = tail;
+        }
+        let mut lists = lists.into_iter();
+        let kex = lists.next()?;
+        let host_key = lists.next()?;
+        let cipher = lists.next()?;
+        let _cipher_s2c = lists.next()?;
+        let mac = lists.next()?;
+        Some(Self {
+            kex,
+            host_key,
+            cipher,
+            mac,
+        })
+    }
+}
+
+fn name_list(buf: &[u8]) -> Option<(Vec<String>, &[u8])> {
+    let len = u32::from_be_bytes(buf.get(..4)?.try_into().ok()?) as usize;
+    let names = buf.get(4..4 + len)?;
+    let list = std::str::from_utf8(names)
+        .ok()?
+        .split(',')
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect();
+    Some((list, &buf[4 + len..]))
+}
+
+/// What this client offers, in preference order.
+struct ClientAlgorithms {
+    kex: Vec<String>,
+    host_key: Vec<String>,
+    cipher: Vec<String>,
+    mac: Vec<String>,
+}
+
+impl ClientAlgorithms {
+    fn russh_defaults() -> Self {
+        let preferred = russh::Preferred::default();
+        Self {
+            kex: preferred
+                .kex
+                .iter()
+                .map(|n| n.as_ref().to_string())
+                .collect(),
+            host_key: preferred
+                .key
+                .iter()
+                .map(|a| a.as_str().to_string())
+                .collect(),
+            cipher: preferred
+                .cipher
+                .iter()
+                .map(|n| n.as_ref().to_string())
+                .collect(),
+            mac: preferred
+                .mac
+                .iter()
+                .map(|n| n.as_ref().to_string())
+                .collect(),
+        }
+    }
+}
+
+/// The first client preference the server also offers (RFC 4253 §7.1).
+fn negotiate<'a>(client: &'a [String], server: &[String]) -> Option<&'a str> {
+    client
+        .iter()
+        .find(|name| server.contains(name))
+        .map(String::as_str)
+}
+
+/// Authenticated ciphers carry their own integrity check; no MAC is agreed.
+fn is_aead(cipher: &str) -> bool {
+    cipher.starts_with("chacha20-poly1305") || cipher.contains("-gcm")
+}
+
+fn kex_step(name: &str, client: &ClientAlgorithms, offer: &KexInit) -> DiagnosticStep {
+    let cipher = negotiate(&client.cipher, &offer.cipher);
+    let mac = match cipher {
+        Some(cipher) if is_aead(cipher) => Some("implicit"),
+        _ => negotiate(&client.mac, &offer.mac),
+    };
+    let categories = [
+        ("kex", negotiate(&client.kex, &offer.kex), &offer.kex),
+        (
+            "host key",
+            negotiate(&client.host_key, &offer.host_key),
+            &offer.host_key,
+        ),
+        ("cipher", cipher, &offer.cipher),
+        ("mac", mac, &offer.mac),
+    ];
+    let missing: Vec<String> = categories
+        .iter()
+        .filter(|(_, agreed, _)| agreed.is_none())
+        .map(|(category, _, offered)| {
+            format!(
+                "no common {} algorithm; the server offers {}",
+                category,
+                if offered.is_empty() {
+                    "none".to_string()
+                } else {
+                    offered.join(", ")
+                }
+            )
+        })
+        .collect();
+    if !missing.is_empty() {
+        return DiagnosticStep::failed(
+            DiagnosticStage::Kex,
+            name,
+            missing.join("; "),
+            DiagnosticFix::NoCommonAlgorithms,
+        );
+    }
+    let agreed: Vec<String> = categories
+        .iter()
+        .map(|(category, agreed, _)| format!("{} {}", category, agreed.unwrap_or_default()))
+        .collect();
+    DiagnosticStep::ok(DiagnosticStage::Kex, name, agreed.join(" · "))
+}
+
+struct HostKeyCheck {
+    key_type: String,
+    key_base64: String,
+    result: KnownHostResult,
+}
+
+/// Checks the host key against known_hosts without ever writing to it, and
+/// keeps what it saw for the report.
+struct DoctorHandler {
+    hostname: String,
+    port: u16,
+    host_key: Arc<Mutex<Option<HostKeyCheck>>>,
+}
+
+impl client::Handler for DoctorHandler {
+    type Error = anyhow::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &russh::keys::PublicKey,
+    ) -> Result<bool, Self::Error> {
+        let key_type = server_public_key.algorithm().as_str().to_owned();
+        let key_base64 = server_public_key.public_key_base64();
+        let result =
+            known_hosts::check_known_host(&self.hostname, self.port, &key_type, &key_base64);
+        *self.host_key.lock() = Some(HostKeyCheck {
+            key_type,
+            key_base64,
+            result,
+        });
+        Ok(result != KnownHostResult::Mismatch)
+    }
+}
+
+fn host_key_step(name: &str, check: &HostKeyCheck) -> DiagnosticStep {
+    let fingerprint = shelldeck_core::config::ssh_keys::PublicKey::parse(&format!(
+        "{} {}",
+        check.key_type, check.key_base64
+    ))
+    .map(|key| key.fingerprint())
+    .unwrap_or_default();
+    let key = format!("{} {}", check.key_type, fingerprint);
+    match check.result {
+        KnownHostResult::Match => DiagnosticStep::ok(
+            DiagnosticStage::HostKey,
+            name,
+            format!("{} matches known_hosts", key),
+        ),
+        KnownHostResult::NotFound => DiagnosticStep::new(
+            DiagnosticStage::HostKey,
+            name,
+            StepStatus::Warning,
+            format!(
+                "{} is not in known_hosts; it will be trusted on first connect",
+                key
+            ),
+        )
+        .with_fix(DiagnosticFix::UnknownHostKey),
+        KnownHostResult::Mismatch => DiagnosticStep::failed(
+            DiagnosticStage::HostKey,
+            name,
+            format!("{} differs from the key saved in known_hosts", key),
+            DiagnosticFix::HostKeyChanged,
+        ),
+    }
+}
+
+/// Key exchange, host key check and authentication, one method at a time.
+/// Returns the handle once signed in.
+async fn handshake<S>(
+    client: &SshClient,
+    stream: S,
+    conn: &Connection,
+    name: &str,
+    trace: &Trace,
+) -> Option<client::Handle<DoctorHandler>>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let host_key = Arc::new(Mutex::new(None));
+    let handler = DoctorHandler {
+        hostname: conn.hostname.clone(),
+        port: conn.port,
+        host_key: host_key.clone(),
+    };
+    let started = Instant::now();
+    let connected = timeout(
+        STEP_TIMEOUT,
+        client::connect_stream(client.config(), stream, handler),
+    )
+    .await;
+    let check = host_key.lock().take();
+    if let Some(check) = &check {
+        trace.push(host_key_step(name, check).with_elapsed(started.elapsed()));
+    }
+    let mut handle = match connected {
+        Ok(Ok(handle)) => handle,
+        // The host key step already says why.
+        Ok(Err(_))
+            if check
+                .as_ref()
+                .is_some_and(|c| c.result == KnownHostResult::Mismatch) =>
+        {
+            return None
+        }
+        Ok(Err(e)) => {
+            trace.push(DiagnosticStep::failed(
+                DiagnosticStage::Kex,
+                name,
+                format!("Key exchange failed: {}", e),
+                DiagnosticFix::NoCommonAlgorithms,
+            ));
+            return None;
+        }
+        Err(_) => {
+            trace.push(DiagnosticStep::failed(
+                DiagnosticStage::Kex,
+                name,
+                format!(
+                    "Key exchange did not finish within {} s",
+                    STEP_TIMEOUT.as_secs()
+                ),
+                DiagnosticFix::CheckNetwork,
+            ));
+            return None;
+        }
+    };
+    let key_known = check.is_some_and(|c| c.result == KnownHostResult::Match);
+
+    let methods: Vec<String> = match handle.authenticate_none(&conn.user).await {
+        Ok(client::AuthResult::Success) => {
+            trace.push(DiagnosticStep::new(
+                DiagnosticStage::AuthMethods,
+                name,
+                StepStatus::Warning,
+                format!("{} is let in without authentication", conn.user),
+            ));
+            return Some(handle);
+        }
+        Ok(client::AuthResult::Failure {
+            remaining_methods, ..
+        }) => remaining_methods
+            .iter()
+            .map(|method| <&str>::from(method).to_string())
+            .collect(),
+        Err(e) => {
+            trace.push(DiagnosticStep::failed(
+                DiagnosticStage::AuthMethods,
+                name,
+                e.to_string(),
+                DiagnosticFix::CheckNetwork,
+            ));
+            return None;
+        }
+    };
+    let offers = |method: &str| methods.iter().any(|m| m == method);
+    let methods_step = DiagnosticStep::ok(
+        DiagnosticStage::AuthMethods,
+        name,
+        format!("Offered to {}: {}", conn.user, methods.join(", ")),
+    );
+    trace.push(if offers("publickey") {
+        methods_step
+    } else {
+        DiagnosticStep {
+            status: StepStatus::Warning,
+            ..methods_step
+        }
+        .with_fix(DiagnosticFix::PublicKeyNotOffered)
+    });
+
+    // Each method that does not sign in is a warning; only running out of
+    // methods fails the run.
+    let mut fixes: Vec<DiagnosticFix> = Vec::new();
+    let mut signed_in = false;
+
+    let explicit = conn.identity_file.is_some();
+    let keys: Vec<PathBuf> = match &conn.identity_file {
+        Some(path) => vec![path.clone()],
+        None => default_key_candidates(shelldeck_core::util::home_dir())
+            .into_iter()
+            .filter(|path| path.exists())
+            .collect(),
+    };
+    if !offers("publickey") {
+        if !keys.is_empty() {
+            trace.push(DiagnosticStep::new(
+                DiagnosticStage::Key,
+                name,
+                StepStatus::Skipped,
+                "The server does not accept public keys",
+            ));
+        }
+    } else if keys.is_empty() {
+        trace.push(
+            DiagnosticStep::new(
+                DiagnosticStage::Key,
+                name,
+                StepStatus::Warning,
+                "No identity file is set and ~/.ssh has no default key",
+            )
+            .with_fix(DiagnosticFix::KeyMissing),
+        );
+        fixes.push(DiagnosticFix::KeyMissing);
+    } else {
+        for path in &keys {
+            let started = Instant::now();
+            if explicit && !path.exists() {
+                trace.push(
+                    DiagnosticStep::new(
+                        DiagnosticStage::Key,
+                        name,
+                        StepStatus::Warning,
+                        format!("{} does not exist", path.display()),
+                    )
+                    .with_fix(DiagnosticFix::KeyMissing),
+                );
+                fixes.push(DiagnosticFix::KeyMissing);
+                continue;
+            }
+            let (detail, fix) = match client.auth_with_key(&mut handle, &conn.user, path).await {
+                Ok(()) => {
+                    trace.push(
+                        DiagnosticStep::ok(
+                            DiagnosticStage::Key,
+                            name,
+                            format!("{} accepted", path.display()),
+                        )
+                        .with_elapsed(started.elapsed()),
+                    );
+                    signed_in = true;
+                    break;
+                }
+                Err(SshError::Key(e)) => (e, DiagnosticFix::KeyPassphrase),
+                Err(e) => (
+                    format!("{}: {}", path.display(), e),
+                    DiagnosticFix::KeyRejected,
+                ),
+            };
+            trace.push(
+                DiagnosticStep::new(DiagnosticStage::Key, name, StepStatus::Warning, detail)
+                    .with_fix(fix)
+                    .with_elapsed(started.elapsed()),
+            );
+            fixes.push(fix);
+        }
+    }
+
+    if !signed_in {
+        let skipped = |detail: &str| {
+            DiagnosticStep::new(DiagnosticStage::Password, name, StepStatus::Skipped, detail)
+        };
+        if !offers("password") {
+            trace.push(skipped("The server does not accept passwords"));
+        } else if !key_known {
+            trace.push(skipped(
+                "Not sent to a host whose key is not already in known_hosts",
+            ));
+        } else {
+            match shelldeck_core::config::keychain::get_password(&conn.hostname, &conn.user) {
+                Ok(Some(_)) => {
+                    let started = Instant::now();
+                    match client
+                        .auth_with_password(&mut handle, &conn.user, &conn.hostname)
+                        .await
+                    {
+                        Ok(()) => {
+                            trace.push(
+                                DiagnosticStep::ok(
+                                    DiagnosticStage::Password,
+                                    name,
+                                    "Saved password accepted",
+                                )
+                                .with_elapsed(started.elapsed()),
+                            );
+                            signed_in = true;
+                        }
+                        Err(e) => {
+                            trace.push(
+                                DiagnosticStep::new(
+                                    DiagnosticStage::Password,
+                                    name,
+                                    StepStatus::Warning,
+                                    e.to_string(),
+                                )
+                                .with_fix(DiagnosticFix::PasswordRejected)
+                                .with_elapsed(started.elapsed()),
+                            );
+                            fixes.push(DiagnosticFix::PasswordRejected);
+                        }
+                    }
+                }
+                Ok(None) => {
+                    trace.push(
+                        DiagnosticStep::new(
+                            DiagnosticStage::Password,
+                            name,
+                            StepStatus::Warning,
+                            format!("No password saved for {}@{}", conn.user, conn.hostname),
+                        )
+                        .with_fix(DiagnosticFix::StorePassword),
+                    );
+                    fixes.push(DiagnosticFix::StorePassword);
+                }
+                Err(e) => {
+                    trace.push(
+                        DiagnosticStep::new(
+                            DiagnosticStage::Password,
+                            name,
+                            StepStatus::Warning,
+                            format!("Keychain unavailable: {}", e),
+                        )
+                        .with_fix(DiagnosticFix::StorePassword),
+                    );
+                    fixes.push(DiagnosticFix::StorePassword);
+                }
+            }
+        }
+    }
+
+    if !signed_in {
+        trace.push(DiagnosticStep::failed(
+            DiagnosticStage::AuthMethods,
+            name,
+            format!("Nothing signed in as {}", conn.user),
+            fixes
+                .first()
+                .copied()
+                .unwrap_or(DiagnosticFix::PublicKeyNotOffered),
+        ));
+        return None;
+    }
+    Some(handle)
+}
+
+/// Open a session and run `true`: what a terminal needs before its shell.
+async fn open_session(handle: &client::Handle<DoctorHandler>, name: &str, trace: &Trace) {
+    let started = Instant::now();
+    let ran = timeout(STEP_TIMEOUT, async {
+        let mut channel = handle
+            .channel_open_session()
+            .await
+            .map_err(|e| e.to_string())?;
+        channel
+            .exec(true, "true")
+            .await
+            .map_err(|e| e.to_string())?;
+        while let Some(msg) = channel.wait().await {
+            if let ChannelMsg::ExitStatus { exit_status } = msg {
+                return Ok(exit_status);
+            }
+        }
+        Err("The session closed before the command finished".to_string())
+    })
+    .await;
+    let step = match ran {
+        Ok(Ok(0)) => DiagnosticStep::ok(
+            DiagnosticStage::Shell,
+            name,
+            format!(
+                "Session ready {} ms after the run started",
+                trace.started.elapsed().as_millis()
+            ),
+        ),
+        Ok(Ok(code)) => DiagnosticStep::new(
+            DiagnosticStage::Shell,
+            name,
+            StepStatus::Warning,
+            format!("A test command exited with {}", code),
+        )
+        .with_fix(DiagnosticFix::ShellFailed),
+        Ok(Err(e)) => {
+            DiagnosticStep::failed(DiagnosticStage::Shell, name, e, DiagnosticFix::ShellFailed)
+        }
+        Err(_) => DiagnosticStep::failed(
+            DiagnosticStage::Shell,
+            name,
+            format!("No session within {} s", STEP_TIMEOUT.as_secs()),
+            DiagnosticFix::ShellFailed,
+        ),
+    };
+    trace.push(step.with_elapsed(started.elapsed()));
+}
+
+async fn hang_up(handle: &client::Handle<DoctorHandler>) {
+    let _ = handle
+        .disconnect(russh::Disconnect::ByApplication, "ShellDeck doctor", "en")
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn kexinit_payload(lists: [&str; 10]) -> Vec<u8> {
+        let mut payload = vec![SSH_MSG_KEXINIT];
+        payload.extend_from_slice(&[7; 16]);
+        for list in lists {
+            payload.extend_from_slice(&(list.len() as u32).to_be_bytes());
+            payload.extend_from_slice(list.as_bytes());
+        }
+        payload.push(0);
+        payload.extend_from_slice(&[0; 4]);
+        payload
+    }
+
+    fn client() -> ClientAlgorithms {
+        ClientAlgorithms {
+            kex: names(&["curve25519-sha256", "diffie-hellman-group14-sha256"]),
+            host_key: names(&["ssh-ed25519", "rsa-sha2-512"]),
+            cipher: names(&["chacha20-poly1305@openssh.com", "aes256-ctr"]),
+            mac: names(&["hmac-sha2-256"]),
+        }
+    }
+
+    #[test]
+    fn kexinit_lists_are_read_in_order() {
+        let payload = kexinit_payload([
+            "curve25519-sha256,ext-info-s",
+            "rsa-sha2-512,ssh-ed25519",
+            "aes256-ctr",
+            "aes128-ctr",
+            "hmac-sha2-256",
+            "hmac-sha1",
+            "none",
+            "none",
+            "",
+            "",
+        ]);
+        let offer = KexInit::parse(&payload).unwrap();
+        assert_eq!(offer.kex, names(&["curve25519-sha256", "ext-info-s"]));
+        assert_eq!(offer.host_key, names(&["rsa-sha2-512", "ssh-ed25519"]));
+        assert_eq!(offer.cipher, names(&["aes256-ctr"]));
+        assert_eq!(offer.mac, names(&["hmac-sha2-256"]));
+
+        assert!(KexInit::parse(&payload[..30]).is_none());
+        let mut other = payload.clone();
+        other[0] = 21;
+        assert!(KexInit::parse(&other).is_none());
+    }
+
+    #[test]
+    fn negotiation_follows_client_preference_and_reports_gaps() {
+        let offer = KexInit {
+            kex: names(&["diffie-hellman-group14-sha256", "curve25519-sha256"]),
+            host_key: names(&["rsa-sha2-512", "ssh-ed25519"]),
+            cipher: names(&["aes256-ctr"]),
+            mac: names(&["hmac-sha2-256"]),
+        };
+        let step = kex_step("web", &client(), &offer);
+        assert_eq!(step.status, StepStatus::Ok);
+        assert_eq!(
+            step.detail,
+            "kex curve25519-sha256 · host key ssh-ed25519 · cipher aes256-ctr · mac hmac-sha2-256"
+        );
+
+        // AEAD ciphers need no MAC.
+        let offer = KexInit {
+            cipher: names(&["chacha20-poly1305@openssh.com"]),
+            mac: names(&["umac-64@openssh.com"]),
+            ..offer
+        };
+        assert!(kex_step("web", &client(), &offer)
+            .detail
+            .ends_with("mac implicit"));
+
+        let legacy = KexInit {
+            host_key: names(&["ssh-dss"]),
+            ..offer
+        };
+        let step = kex_step("web", &client(), &legacy);
+        assert_eq!(step.status, StepStatus::Failed);
+        assert_eq!(step.fix, Some(DiagnosticFix::NoCommonAlgorithms));
+        assert_eq!(
+            step.detail,
+            "no common host key algorithm; the server offers ssh-dss"
+        );
+    }
+
+    #[tokio::test]
+    async fn ident_skips_pre_banner_lines_and_rejects_other_services() {
+        let mut reader: &[u8] = b"Welcome\r\n\r\nSSH-2.0-OpenSSH_9.6 Ubuntu\r\nrest";
+        assert_eq!(
+            read_ident(&mut reader).await.unwrap(),
+            "SSH-2.0-OpenSSH_9.6 Ubuntu"
+        );
+
+        let mut reader: &[u8] = b"HTTP/1.1 400 Bad Request\r\n\r\n";
+        assert_eq!(
+            read_ident(&mut reader).await.unwrap_err(),
+            "Not an SSH server; it answered: HTTP/1.1 400 Bad Request"
+        );
+
+        let mut reader: &[u8] = b"SSH-1.5-Legacy\r\n";
+        assert!(read_ident(&mut reader)
+            .await
+            .unwrap_err()
+            .starts_with("Unsupported SSH protocol version"));
+
+        let mut reader: &[u8] = b"";
+        assert!(read_ident(&mut reader)
+            .await
+            .unwrap_err()
+            .contains("without identifying itself"));
+    }
+
+    #[tokio::test]
+    async fn packet_payload_drops_length_and_padding() {
+        let payload = kexinit_payload(["a", "b", "c", "d", "e", "f", "g", "h", "", ""]);
+        let padding = 6u8;
+        let mut wire = Vec::new();
+        wire.extend_from_slice(&((payload.len() + padding as usize + 1) as u32).to_be_bytes());
+        wire.push(padding);
+        wire.extend_from_slice(&payload);
+        wire.extend_from_slice(&[0; 6]);
+        let mut reader: &[u8] = &wire;
+        assert_eq!(read_packet(&mut reader).await.unwrap(), payload);
+
+        let mut reader: &[u8] = &[0xff, 0xff, 0xff, 0xff];
+        assert!(read_packet(&mut reader).await.is_err());
+    }
+}
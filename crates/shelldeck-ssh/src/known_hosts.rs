@@ -3,7 +3,7 @@ use std::io::Write;
 use std::path::PathBuf;
 
 /// Result of checking a server key against known_hosts.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum KnownHostResult {
     /// Key matches an existing entry.
     Match,
@@ -1,9 +1,14 @@
 pub mod client;
+pub mod doctor;
 pub mod error;
 pub mod handler;
 pub mod known_hosts;
+pub mod log_tail;
+pub mod metrics;
 pub mod pool;
+mod proxy;
 pub mod session;
+pub mod supervisor;
 pub mod tunnel;
 
 pub use error::{Result, SshError};
@@ -0,0 +1,272 @@
+//! Follows several log sources of one host over a single SSH session, one
+//! exec channel per source, reconnecting with backoff when it drops.
+
+use std::time::Duration;
+
+use russh::ChannelMsg;
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::log_tail::LogSource;
+use shelldeck_core::models::tunnel_health::Backoff;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+use crate::client::SshClient;
+use crate::handler::SshEvent;
+use crate::session::SharedHandle;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Longer lines (minified JSON, base64 dumps) are cut so one of them
+/// cannot hold a whole buffer.
+const MAX_LINE_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TailEvent {
+    /// The session is up and every source is being followed.
+    Connected,
+    /// One line of a source, by its index in the tailer's source list.
+    Line { source: usize, text: String },
+    /// A source's command exited, e.g. a container that is not running.
+    SourceEnded {
+        source: usize,
+        exit_code: Option<u32>,
+    },
+    /// A source's channel could not be opened.
+    SourceFailed { source: usize, error: String },
+    /// The session went down; it is rebuilt after `retry_in`.
+    Down { reason: String, retry_in: Duration },
+}
+
+/// Tails the sources of one host until shut down.
+pub struct LogTailer {
+    connection: Connection,
+    /// Sources of this host, with their index in the viewer's list.
+    sources: Vec<(usize, LogSource)>,
+    backlog: usize,
+    events: mpsc::UnboundedSender<TailEvent>,
+}
+
+impl LogTailer {
+    pub fn new(
+        connection: Connection,
+        sources: Vec<(usize, LogSource)>,
+        backlog: usize,
+        events: mpsc::UnboundedSender<TailEvent>,
+    ) -> Self {
+        Self {
+            connection,
+            sources,
+            backlog,
+            events,
+        }
+    }
+
+    /// Follow until `shutdown_rx` fires or its sender is dropped.
+    pub async fn run(self, mut shutdown_rx: mpsc::Receiver<()>) {
+        let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+        // Only the first session reads history back; a reconnect would
+        // otherwise repeat lines already shown.
+        let mut backlog = self.backlog;
+        loop {
+            let Some(reason) = self.run_once(backlog, &mut backoff, &mut shutdown_rx).await else {
+                break;
+            };
+            backlog = 0;
+            let retry_in = backoff.next_delay();
+            tracing::warn!(
+                "Log tail on {} down ({}), retrying in {:?}",
+                self.connection.display_name(),
+                reason,
+                retry_in
+            );
+            let _ = self.events.send(TailEvent::Down { reason, retry_in });
+            tokio::select! {
+                _ = tokio::time::sleep(retry_in) => {}
+                _ = shutdown_rx.recv() => break,
+            }
+        }
+        tracing::info!("Log tail on {} stopped", self.connection.display_name());
+    }
+
+    /// One session's worth of tailing. Returns why the session went down,
+    /// or `None` when shutdown was requested.
+    async fn run_once(
+        &self,
+        backlog: usize,
+        backoff: &mut Backoff,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+    ) -> Option<String> {
+        let client = SshClient::new();
+        let connect = tokio::time::timeout(CONNECT_TIMEOUT, client.connect(&self.connection));
+        let mut session = tokio::select! {
+            result = connect => match result {
+                Ok(Ok(session)) => session,
+                Ok(Err(e)) => return Some(format!("SSH connection failed: {}", e)),
+                Err(_) => return Some("SSH connection timed out".to_string()),
+            },
+            _ = shutdown_rx.recv() => return None,
+        };
+        backoff.reset();
+        let _ = self.events.send(TailEvent::Connected);
+
+        let handle = session.shared_handle();
+        let mut tails = JoinSet::new();
+        for (index, source) in &self.sources {
+            tails.spawn(follow(
+                handle.clone(),
+                *index,
+                source.follow_command(backlog),
+                self.events.clone(),
+            ));
+        }
+
+        let outcome = loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break None,
+                event = session.event_rx().recv() => match event {
+                    Some(SshEvent::Disconnected(reason)) => {
+                        break Some(format!("SSH session lost: {}", reason));
+                    }
+                    None => break Some("SSH session closed".to_string()),
+                    Some(_) => {}
+                },
+                // Sources that end stay ended until tailing restarts; the
+                // session is kept for the others.
+                Some(joined) = tails.join_next() => {
+                    if let Err(e) = joined {
+                        tracing::warn!("Log tail task failed: {}", e);
+                    }
+                }
+            }
+        };
+        tails.abort_all();
+        let _ = tokio::time::timeout(DISCONNECT_TIMEOUT, session.disconnect()).await;
+        outcome
+    }
+}
+
+/// Run one source's command and forward its lines until it exits.
+async fn follow(
+    handle: SharedHandle,
+    source: usize,
+    command: String,
+    events: mpsc::UnboundedSender<TailEvent>,
+) {
+    let channel = {
+        let handle = handle.lock().await;
+        handle.channel_open_session().await
+    };
+    let mut channel = match channel {
+        Ok(channel) => channel,
+        Err(e) => {
+            let _ = events.send(TailEvent::SourceFailed {
+                source,
+                error: e.to_string(),
+            });
+            return;
+        }
+    };
+    if let Err(e) = channel.exec(true, command.as_str()).await {
+        let _ = events.send(TailEvent::SourceFailed {
+            source,
+            error: e.to_string(),
+        });
+        return;
+    }
+
+    let mut buffer = LineBuffer::default();
+    let mut exit_code = None;
+    loop {
+        let lines = match channel.wait().await {
+            Some(ChannelMsg::Data { data }) => buffer.push(&data),
+            Some(ChannelMsg::ExtendedData { data, .. }) => buffer.push(&data),
+            Some(ChannelMsg::ExitStatus { exit_status }) => {
+                exit_code = Some(exit_status);
+                continue;
+            }
+            // The exit status can follow EOF, so read until the close.
+            None => break,
+            _ => continue,
+        };
+        for text in lines {
+            let _ = events.send(TailEvent::Line { source, text });
+        }
+    }
+    if let Some(text) = buffer.finish() {
+        let _ = events.send(TailEvent::Line { source, text });
+    }
+    let _ = events.send(TailEvent::SourceEnded { source, exit_code });
+}
+
+/// Splits a byte stream into lines, holding back a trailing partial line
+/// until the rest of it arrives.
+#[derive(Debug, Default)]
+struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    /// Append `data` and return the lines it completed.
+    fn push(&mut self, data: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(data);
+        let mut lines = Vec::new();
+        let mut start = 0;
+        while let Some(offset) = self.pending[start..].iter().position(|b| *b == b'\n') {
+            let end = start + offset;
+            lines.push(line_text(&self.pending[start..end]));
+            start = end + 1;
+        }
+        self.pending.drain(..start);
+        if self.pending.len() > MAX_LINE_BYTES {
+            lines.push(line_text(&self.pending));
+            self.pending.clear();
+        }
+        lines
+    }
+
+    /// The partial line left when the stream ends, if any.
+    fn finish(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let text = line_text(&self.pending);
+        self.pending.clear();
+        Some(text)
+    }
+}
+
+fn line_text(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_suffix(b"\r").unwrap_or(bytes);
+    let bytes = &bytes[..bytes.len().min(MAX_LINE_BYTES)];
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_split_across_chunks_are_joined() {
+        let mut buffer = LineBuffer::default();
+        assert_eq!(buffer.push(b"first\r\nsec"), vec!["first"]);
+        assert!(buffer.push(b"ond").is_empty());
+        assert_eq!(buffer.push(b"\n\nthird"), vec!["second", ""]);
+        assert_eq!(buffer.finish().as_deref(), Some("third"));
+        assert_eq!(buffer.finish(), None);
+    }
+
+    #[test]
+    fn overlong_lines_are_cut() {
+        let mut buffer = LineBuffer::default();
+        let lines = buffer.push(&vec![b'x'; MAX_LINE_BYTES + 10]);
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].len(), MAX_LINE_BYTES);
+        assert_eq!(buffer.finish(), None);
+
+        let mut long = vec![b'y'; MAX_LINE_BYTES * 2];
+        long.push(b'\n');
+        assert_eq!(buffer.push(&long)[0].len(), MAX_LINE_BYTES);
+    }
+}
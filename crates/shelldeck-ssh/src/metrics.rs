@@ -0,0 +1,183 @@
+//! Samples one host's load, memory, disks and processes over a single SSH
+//! session kept open between reads, reconnecting with backoff when it drops.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::host_metrics::{HostSnapshot, METRICS_COMMAND};
+use shelldeck_core::models::tunnel_health::Backoff;
+use tokio::sync::mpsc;
+use tokio::time::MissedTickBehavior;
+
+use crate::client::SshClient;
+use crate::handler::SshEvent;
+use crate::session::{ExecResult, SshSession};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(120);
+/// Cap on one read, however long the interval is.
+const MAX_EXEC_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetricsEvent {
+    /// Raw counters of one read; the caller turns them into rates.
+    Sample(HostSnapshot),
+    /// A read failed, or the session went down and is being rebuilt.
+    Error(String),
+}
+
+/// Reads one host every `interval` until shut down.
+pub struct MetricsCollector {
+    connection: Connection,
+    interval: Duration,
+    events: mpsc::UnboundedSender<MetricsEvent>,
+}
+
+impl MetricsCollector {
+    pub fn new(
+        connection: Connection,
+        interval: Duration,
+        events: mpsc::UnboundedSender<MetricsEvent>,
+    ) -> Self {
+        Self {
+            connection,
+            interval: interval.max(Duration::from_secs(1)),
+            events,
+        }
+    }
+
+    /// Sample until `shutdown_rx` fires or its sender is dropped.
+    pub async fn run(self, mut shutdown_rx: mpsc::Receiver<()>) {
+        let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+        loop {
+            let Some(reason) = self.run_once(&mut backoff, &mut shutdown_rx).await else {
+                break;
+            };
+            let retry_in = backoff.next_delay();
+            tracing::warn!(
+                "Metrics for {} unavailable ({}), retrying in {:?}",
+                self.connection.display_name(),
+                reason,
+                retry_in
+            );
+            let _ = self.events.send(MetricsEvent::Error(reason));
+            tokio::select! {
+                _ = tokio::time::sleep(retry_in) => {}
+                _ = shutdown_rx.recv() => break,
+            }
+        }
+        tracing::info!(
+            "Metrics collector for {} stopped",
+            self.connection.display_name()
+        );
+    }
+
+    /// One session's worth of reads. Returns why the session went down,
+    /// or `None` when shutdown was requested.
+    async fn run_once(
+        &self,
+        backoff: &mut Backoff,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+    ) -> Option<String> {
+        let client = SshClient::new();
+        let connect = tokio::time::timeout(CONNECT_TIMEOUT, client.connect(&self.connection));
+        let mut session = tokio::select! {
+            result = connect => match result {
+                Ok(Ok(session)) => session,
+                Ok(Err(e)) => return Some(format!("SSH connection failed: {}", e)),
+                Err(_) => return Some("SSH connection timed out".to_string()),
+            },
+            _ = shutdown_rx.recv() => return None,
+        };
+        let outcome = self.sample(&mut session, backoff, shutdown_rx).await;
+        let _ = tokio::time::timeout(DISCONNECT_TIMEOUT, session.disconnect()).await;
+        outcome
+    }
+
+    async fn sample(
+        &self,
+        session: &mut SshSession,
+        backoff: &mut Backoff,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+    ) -> Option<String> {
+        let exec_timeout = self.interval.min(MAX_EXEC_TIMEOUT);
+        let mut tick = tokio::time::interval(self.interval);
+        tick.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => return None,
+                event = session.event_rx().recv() => match event {
+                    Some(SshEvent::Disconnected(reason)) => {
+                        return Some(format!("SSH session lost: {}", reason));
+                    }
+                    None => return Some("SSH session closed".to_string()),
+                    Some(_) => {}
+                },
+                _ = tick.tick() => {
+                    let read = tokio::time::timeout(exec_timeout, session.exec(METRICS_COMMAND));
+                    let result = tokio::select! {
+                        result = read => result,
+                        _ = shutdown_rx.recv() => return None,
+                    };
+                    let event = match result {
+                        Ok(Ok(result)) => match snapshot_from(&result) {
+                            Ok(snapshot) => {
+                                backoff.reset();
+                                MetricsEvent::Sample(snapshot)
+                            }
+                            Err(e) => MetricsEvent::Error(e),
+                        },
+                        Ok(Err(e)) => return Some(format!("Metrics read failed: {}", e)),
+                        Err(_) => MetricsEvent::Error("Metrics read timed out".to_string()),
+                    };
+                    let _ = self.events.send(event);
+                }
+            }
+        }
+    }
+}
+
+/// Parse one read. A non-zero exit alone is not an error: a missing `ps`
+/// or `df` still leaves the other sections worth showing.
+fn snapshot_from(result: &ExecResult) -> Result<HostSnapshot, String> {
+    HostSnapshot::parse(&result.stdout_string(), Utc::now()).ok_or_else(|| {
+        let stderr = result.stderr_string();
+        let stderr = stderr.trim();
+        if stderr.is_empty() {
+            "No metrics in the host's output (is /proc available?)".to_string()
+        } else {
+            format!("No metrics in the host's output: {}", stderr)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(stdout: &str, stderr: &str, exit_code: u32) -> ExecResult {
+        ExecResult {
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: stderr.as_bytes().to_vec(),
+            exit_code: Some(exit_code),
+        }
+    }
+
+    #[test]
+    fn partial_output_still_yields_a_snapshot() {
+        let stdout = "@@shelldeck:loadavg\n0.50 0.40 0.30 1/200 1234\n@@shelldeck:ps\n";
+        let snapshot = snapshot_from(&result(stdout, "ps: not found", 127)).unwrap();
+        assert_eq!(snapshot.load, Some([0.5, 0.4, 0.3]));
+        assert!(snapshot.processes.is_empty());
+    }
+
+    #[test]
+    fn empty_output_reports_stderr() {
+        let error = snapshot_from(&result("", "sh: /proc/stat: No such file", 1)).unwrap_err();
+        assert!(error.ends_with("sh: /proc/stat: No such file"));
+        assert!(snapshot_from(&result("", "", 0)).is_err());
+    }
+}
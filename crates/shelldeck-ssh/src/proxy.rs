@@ -0,0 +1,561 @@
+//! Client side of the proxy a dynamic forward listens with. One listener
+//! speaks SOCKS5 (RFC 1928, with RFC 1929 username/password auth), SOCKS4
+//! and 4a, and HTTP: `CONNECT`, plain requests in absolute form and the PAC
+//! file. Clients are told apart by the first byte they send.
+
+use base64::Engine as _;
+use shelldeck_core::models::forward_proxy::{
+    destination_allowed, pac_script, DestinationRule, ProxySettings, PAC_PATH,
+};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+// SOCKS5 protocol constants (RFC 1928, RFC 1929).
+const SOCKS5_VERSION: u8 = 0x05;
+const SOCKS5_AUTH_NONE: u8 = 0x00;
+const SOCKS5_AUTH_PASSWORD: u8 = 0x02;
+const SOCKS5_AUTH_NO_ACCEPTABLE: u8 = 0xFF;
+const SOCKS5_PASSWORD_VERSION: u8 = 0x01;
+const SOCKS5_CMD_CONNECT: u8 = 0x01;
+const SOCKS5_ATYP_IPV4: u8 = 0x01;
+const SOCKS5_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS5_ATYP_IPV6: u8 = 0x04;
+// Reply codes
+const SOCKS5_REP_SUCCESS: u8 = 0x00;
+const SOCKS5_REP_GENERAL_FAILURE: u8 = 0x01;
+const SOCKS5_REP_NOT_ALLOWED: u8 = 0x02;
+const SOCKS5_REP_HOST_UNREACHABLE: u8 = 0x04;
+const SOCKS5_REP_CMD_NOT_SUPPORTED: u8 = 0x07;
+const SOCKS5_REP_ATYP_NOT_SUPPORTED: u8 = 0x08;
+
+// SOCKS4 protocol constants.
+const SOCKS4_VERSION: u8 = 0x04;
+const SOCKS4_CMD_CONNECT: u8 = 0x01;
+const SOCKS4_REP_GRANTED: u8 = 0x5A;
+const SOCKS4_REP_REJECTED: u8 = 0x5B;
+/// Longest SOCKS4 user id or 4a domain name accepted.
+const SOCKS4_MAX_FIELD: usize = 255;
+
+/// Longest HTTP request head accepted.
+const HTTP_MAX_HEAD: usize = 64 * 1024;
+/// Headers about the client-to-proxy hop, dropped from forwarded requests.
+const HTTP_HOP_HEADERS: &[&str] = &[
+    "proxy-authorization",
+    "proxy-connection",
+    "connection",
+    "keep-alive",
+];
+
+/// Proxy settings of a running dynamic forward, with the rules parsed once.
+pub(crate) struct ProxyConfig {
+    settings: ProxySettings,
+    rules: Vec<DestinationRule>,
+}
+
+impl ProxyConfig {
+    pub(crate) fn new(settings: ProxySettings) -> Self {
+        let rules = settings.destination_rules();
+        Self { settings, rules }
+    }
+
+    fn allows(&self, host: &str) -> bool {
+        destination_allowed(&self.rules, host)
+    }
+}
+
+/// A client's request, authenticated and allowed by the rules.
+pub(crate) struct ProxyRequest {
+    pub host: String,
+    pub port: u16,
+    pub kind: RequestKind,
+    /// Bytes for the target once the channel is open: the rewritten head
+    /// of a plain HTTP request, and whatever the client sent past the head.
+    pub early_data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+    Socks5,
+    Socks4,
+    HttpConnect,
+    /// A plain HTTP request, forwarded as the only one of its connection.
+    HttpForward,
+}
+
+impl RequestKind {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            RequestKind::Socks5 => "SOCKS5",
+            RequestKind::Socks4 => "SOCKS4",
+            RequestKind::HttpConnect => "HTTP CONNECT",
+            RequestKind::HttpForward => "HTTP",
+        }
+    }
+
+    /// Tell the client whether the target could be reached.
+    pub(crate) async fn reply<S: AsyncWrite + Unpin>(
+        self,
+        stream: &mut S,
+        connected: bool,
+    ) -> std::io::Result<()> {
+        match (self, connected) {
+            (RequestKind::Socks5, true) => send_socks5_reply(stream, SOCKS5_REP_SUCCESS).await,
+            (RequestKind::Socks5, false) => {
+                send_socks5_reply(stream, SOCKS5_REP_HOST_UNREACHABLE).await
+            }
+            (RequestKind::Socks4, true) => send_socks4_reply(stream, SOCKS4_REP_GRANTED).await,
+            (RequestKind::Socks4, false) => send_socks4_reply(stream, SOCKS4_REP_REJECTED).await,
+            (RequestKind::HttpConnect, true) => {
+                stream
+                    .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                    .await?;
+                stream.flush().await
+            }
+            // The target answers a forwarded request itself.
+            (RequestKind::HttpForward, true) => Ok(()),
+            (RequestKind::HttpConnect | RequestKind::HttpForward, false) => {
+                send_http_error(stream, "502 Bad Gateway", "").await
+            }
+        }
+    }
+}
+
+/// Read what a client wants from the proxy. `None` means the client has
+/// been answered already: served the PAC file, asked to authenticate, or
+/// refused by the destination rules.
+pub(crate) async fn read_request<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    config: &ProxyConfig,
+    local_addr: SocketAddr,
+) -> anyhow::Result<Option<ProxyRequest>> {
+    let mut first = [0u8; 1];
+    stream.read_exact(&mut first).await?;
+    match first[0] {
+        SOCKS5_VERSION => read_socks5_request(stream, config).await,
+        SOCKS4_VERSION => read_socks4_request(stream, config).await,
+        b if b.is_ascii_alphabetic() => read_http_request(stream, config, local_addr, b).await,
+        other => anyhow::bail!("Unknown proxy protocol (first byte {:#04x})", other),
+    }
+}
+
+/// Write a SOCKS5 reply with the given reply code and a bound address of
+/// `0.0.0.0:0` (BND.ADDR/BND.PORT are not meaningful for our CONNECT tunnel).
+async fn send_socks5_reply<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    reply: u8,
+) -> std::io::Result<()> {
+    // VER, REP, RSV, ATYP(IPv4), BND.ADDR(0.0.0.0), BND.PORT(0)
+    let resp = [
+        SOCKS5_VERSION,
+        reply,
+        0x00,
+        SOCKS5_ATYP_IPV4,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+    ];
+    writer.write_all(&resp).await?;
+    writer.flush().await
+}
+
+/// SOCKS5 method negotiation, password sub-negotiation when the proxy
+/// requires it, then the CONNECT request. The version byte is already read.
+async fn read_socks5_request<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    config: &ProxyConfig,
+) -> anyhow::Result<Option<ProxyRequest>> {
+    // --- Method negotiation ---
+    // Client greeting: VER, NMETHODS, METHODS...
+    let mut nmethods = [0u8; 1];
+    stream.read_exact(&mut nmethods).await?;
+    let mut methods = vec![0u8; nmethods[0] as usize];
+    stream.read_exact(&mut methods).await?;
+
+    let method = if config.settings.requires_auth() {
+        SOCKS5_AUTH_PASSWORD
+    } else {
+        SOCKS5_AUTH_NONE
+    };
+    if !methods.contains(&method) {
+        // No acceptable methods.
+        stream
+            .write_all(&[SOCKS5_VERSION, SOCKS5_AUTH_NO_ACCEPTABLE])
+            .await?;
+        anyhow::bail!("Client offered no supported SOCKS5 auth method");
+    }
+    stream.write_all(&[SOCKS5_VERSION, method]).await?;
+
+    if method == SOCKS5_AUTH_PASSWORD {
+        // VER, ULEN, UNAME, PLEN, PASSWD
+        let mut head = [0u8; 2];
+        stream.read_exact(&mut head).await?;
+        if head[0] != SOCKS5_PASSWORD_VERSION {
+            anyhow::bail!("Unsupported SOCKS5 password auth version: {}", head[0]);
+        }
+        let mut username = vec![0u8; head[1] as usize];
+        stream.read_exact(&mut username).await?;
+        let mut password_len = [0u8; 1];
+        stream.read_exact(&mut password_len).await?;
+        let mut password = vec![0u8; password_len[0] as usize];
+        stream.read_exact(&mut password).await?;
+
+        let accepted = config.settings.check_credentials(
+            &String::from_utf8_lossy(&username),
+            &String::from_utf8_lossy(&password),
+        );
+        // STATUS 0 is success; anything else closes the connection.
+        stream
+            .write_all(&[SOCKS5_PASSWORD_VERSION, if accepted { 0x00 } else { 0x01 }])
+            .await?;
+        if !accepted {
+            anyhow::bail!("SOCKS5 client gave wrong credentials");
+        }
+    }
+
+    // --- Request ---
+    // VER, CMD, RSV, ATYP
+    let mut req = [0u8; 4];
+    stream.read_exact(&mut req).await?;
+    if req[0] != SOCKS5_VERSION {
+        send_socks5_reply(stream, SOCKS5_REP_GENERAL_FAILURE).await?;
+        anyhow::bail!("Unsupported SOCKS version in request: {}", req[0]);
+    }
+    let cmd = req[1];
+    let atyp = req[3];
+
+    if cmd != SOCKS5_CMD_CONNECT {
+        // BIND (0x02) and UDP ASSOCIATE (0x03) are not supported.
+        send_socks5_reply(stream, SOCKS5_REP_CMD_NOT_SUPPORTED).await?;
+        anyhow::bail!("Unsupported SOCKS5 command: {}", cmd);
+    }
+
+    // Parse the target address.
+    let host = match atyp {
+        SOCKS5_ATYP_IPV4 => {
+            let mut addr = [0u8; 4];
+            stream.read_exact(&mut addr).await?;
+            Ipv4Addr::from(addr).to_string()
+        }
+        SOCKS5_ATYP_IPV6 => {
+            let mut addr = [0u8; 16];
+            stream.read_exact(&mut addr).await?;
+            Ipv6Addr::from(addr).to_string()
+        }
+        SOCKS5_ATYP_DOMAIN => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).await?;
+            let mut domain = vec![0u8; len[0] as usize];
+            stream.read_exact(&mut domain).await?;
+            String::from_utf8(domain)
+                .map_err(|e| anyhow::anyhow!("Invalid SOCKS5 domain name: {}", e))?
+        }
+        other => {
+            send_socks5_reply(stream, SOCKS5_REP_ATYP_NOT_SUPPORTED).await?;
+            anyhow::bail!("Unsupported SOCKS5 address type: {}", other);
+        }
+    };
+
+    let mut port_buf = [0u8; 2];
+    stream.read_exact(&mut port_buf).await?;
+    let port = u16::from_be_bytes(port_buf);
+
+    if !config.allows(&host) {
+        tracing::info!("SOCKS5: {}:{} refused by the destination rules", host, port);
+        send_socks5_reply(stream, SOCKS5_REP_NOT_ALLOWED).await?;
+        return Ok(None);
+    }
+    Ok(Some(ProxyRequest {
+        host,
+        port,
+        kind: RequestKind::Socks5,
+        early_data: Vec::new(),
+    }))
+}
+
+/// VN (0), CD, DSTPORT, DSTIP (the rest is ignored by clients).
+async fn send_socks4_reply<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    reply: u8,
+) -> std::io::Result<()> {
+    writer.write_all(&[0x00, reply, 0, 0, 0, 0, 0, 0]).await?;
+    writer.flush().await
+}
+
+/// A SOCKS4 or 4a CONNECT request. The version byte is already read.
+/// SOCKS4 has no password, so it is refused when the proxy requires one.
+async fn read_socks4_request<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    config: &ProxyConfig,
+) -> anyhow::Result<Option<ProxyRequest>> {
+    // CD, DSTPORT, DSTIP, USERID, NUL
+    let mut req = [0u8; 7];
+    stream.read_exact(&mut req).await?;
+    let cmd = req[0];
+    let port = u16::from_be_bytes([req[1], req[2]]);
+    let ip = Ipv4Addr::new(req[3], req[4], req[5], req[6]);
+    let _user_id = read_nul_terminated(stream).await?;
+    // SOCKS4a: an address of 0.0.0.x (x != 0) means a domain name follows.
+    let octets = ip.octets();
+    let host = if octets[..3] == [0, 0, 0] && octets[3] != 0 {
+        String::from_utf8(read_nul_terminated(stream).await?)
+            .map_err(|e| anyhow::anyhow!("Invalid SOCKS4a domain name: {}", e))?
+    } else {
+        ip.to_string()
+    };
+
+    if config.settings.requires_auth() {
+        send_socks4_reply(stream, SOCKS4_REP_REJECTED).await?;
+        anyhow::bail!("SOCKS4 client refused: the proxy requires a password");
+    }
+    if cmd != SOCKS4_CMD_CONNECT {
+        send_socks4_reply(stream, SOCKS4_REP_REJECTED).await?;
+        anyhow::bail!("Unsupported SOCKS4 command: {}", cmd);
+    }
+    if !config.allows(&host) {
+        tracing::info!("SOCKS4: {}:{} refused by the destination rules", host, port);
+        send_socks4_reply(stream, SOCKS4_REP_REJECTED).await?;
+        return Ok(None);
+    }
+    Ok(Some(ProxyRequest {
+        host,
+        port,
+        kind: RequestKind::Socks4,
+        early_data: Vec::new(),
+    }))
+}
+
+async fn read_nul_terminated<S: AsyncRead + Unpin>(stream: &mut S) -> anyhow::Result<Vec<u8>> {
+    let mut field = Vec::new();
+    loop {
+        let mut byte = [0u8; 1];
+        stream.read_exact(&mut byte).await?;
+        if byte[0] == 0 {
+            return Ok(field);
+        }
+        if field.len() == SOCKS4_MAX_FIELD {
+            anyhow::bail!("SOCKS4 field longer than {} bytes", SOCKS4_MAX_FIELD);
+        }
+        field.push(byte[0]);
+    }
+}
+
+/// An answer without a body; the connection is closed after it.
+async fn send_http_error<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: &str,
+    extra_headers: &str,
+) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\n{}Content-Length: 0\r\nConnection: close\r\n\r\n",
+        status, extra_headers
+    );
+    writer.write_all(response.as_bytes()).await?;
+    writer.flush().await
+}
+
+/// An HTTP request to the proxy, starting with `first`, the byte already
+/// read. Requests for a path on the proxy itself can only fetch the PAC file.
+async fn read_http_request<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    config: &ProxyConfig,
+    local_addr: SocketAddr,
+    first: u8,
+) -> anyhow::Result<Option<ProxyRequest>> {
+    let mut buf = vec![first];
+    let head_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > HTTP_MAX_HEAD {
+            send_http_error(stream, "431 Request Header Fields Too Large", "").await?;
+            anyhow::bail!("HTTP request head longer than {} bytes", HTTP_MAX_HEAD);
+        }
+        let mut chunk = [0u8; 4096];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            anyhow::bail!("Client closed the connection inside the HTTP request head");
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let rest = buf.split_off(head_end);
+    let head = String::from_utf8_lossy(&buf);
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let headers: Vec<(&str, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        send_http_error(stream, "400 Bad Request", "").await?;
+        anyhow::bail!("Malformed HTTP request line: {}", request_line);
+    };
+
+    if target.starts_with('/') {
+        let path = target.split('?').next().unwrap_or(target);
+        if config.settings.serve_pac && method == "GET" && path == PAC_PATH {
+            // Point browsers at the address they reached the proxy on.
+            let proxy_addr = header(&headers, "host")
+                .map(str::to_string)
+                .unwrap_or_else(|| local_addr.to_string());
+            let body = pac_script(&config.rules, &proxy_addr);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/x-ns-proxy-autoconfig\r\nContent-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await?;
+            stream.flush().await?;
+            return Ok(None);
+        }
+        send_http_error(stream, "400 Bad Request", "").await?;
+        anyhow::bail!("HTTP request for {} is not a proxy request", target);
+    }
+
+    if config.settings.requires_auth() {
+        let accepted = header(&headers, "proxy-authorization")
+            .and_then(parse_basic_auth)
+            .is_some_and(|(user, password)| config.settings.check_credentials(&user, &password));
+        if !accepted {
+            send_http_error(
+                stream,
+                "407 Proxy Authentication Required",
+                "Proxy-Authenticate: Basic realm=\"ShellDeck\"\r\n",
+            )
+            .await?;
+            return Ok(None);
+        }
+    }
+
+    let (kind, authority, early_data) = if method == "CONNECT" {
+        (RequestKind::HttpConnect, target, rest)
+    } else {
+        let Some(uri) = strip_prefix_ignore_case(target, "http://") else {
+            send_http_error(stream, "400 Bad Request", "").await?;
+            anyhow::bail!("Only http:// requests are forwarded, got {}", target);
+        };
+        let (authority, path) = match uri.find(['/', '?']) {
+            Some(i) => (&uri[..i], &uri[i..]),
+            None => (uri, ""),
+        };
+        let path = if path.starts_with('/') {
+            path.to_string()
+        } else {
+            format!("/{}", path)
+        };
+        // Origin form, without the hop-by-hop headers; the target closes
+        // the connection after its answer.
+        let mut rewritten = format!("{} {} {}\r\n", method, path, version);
+        if header(&headers, "host").is_none() {
+            rewritten.push_str(&format!("Host: {}\r\n", authority));
+        }
+        for (name, value) in &headers {
+            if !HTTP_HOP_HEADERS
+                .iter()
+                .any(|hop| name.eq_ignore_ascii_case(hop))
+            {
+                rewritten.push_str(&format!("{}: {}\r\n", name, value));
+            }
+        }
+        rewritten.push_str("Connection: close\r\n\r\n");
+        let mut early_data = rewritten.into_bytes();
+        early_data.extend_from_slice(&rest);
+        (RequestKind::HttpForward, authority, early_data)
+    };
+
+    let default_port = (kind == RequestKind::HttpForward).then_some(80);
+    let Some((host, port)) = split_authority(authority, default_port) else {
+        send_http_error(stream, "400 Bad Request", "").await?;
+        anyhow::bail!("No host and port in HTTP request for {}", target);
+    };
+    if !config.allows(&host) {
+        tracing::info!("HTTP: {}:{} refused by the destination rules", host, port);
+        send_http_error(stream, "403 Forbidden", "").await?;
+        return Ok(None);
+    }
+    Ok(Some(ProxyRequest {
+        host,
+        port,
+        kind,
+        early_data,
+    }))
+}
+
+fn header<'a>(headers: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, value)| *value)
+}
+
+fn strip_prefix_ignore_case<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = s.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &s[prefix.len()..])
+}
+
+/// User and password of a `Basic` authorization header value.
+fn parse_basic_auth(value: &str) -> Option<(String, String)> {
+    let (scheme, encoded) = value.trim().split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+}
+
+/// `host:port`, `[v6]:port`, or a bare host when there is a default port.
+fn split_authority(authority: &str, default_port: Option<u16>) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, after) = rest.split_once(']')?;
+        match after.strip_prefix(':') {
+            Some(port) => (host, port.parse().ok()?),
+            None if after.is_empty() => (host, default_port?),
+            None => return None,
+        }
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().ok()?),
+            None => (authority, default_port?),
+        }
+    };
+    (!host.is_empty()).then(|| (host.to_string(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authorities_and_basic_credentials_parse() {
+        assert_eq!(
+            split_authority("git.corp.example:443", None),
+            Some(("git.corp.example".into(), 443))
+        );
+        assert_eq!(
+            split_authority("[fd00::1]:22", None),
+            Some(("fd00::1".into(), 22))
+        );
+        assert_eq!(split_authority("wiki", Some(80)), Some(("wiki".into(), 80)));
+        assert_eq!(split_authority("wiki", None), None);
+        assert_eq!(split_authority(":80", None), None);
+        assert_eq!(split_authority("[fd00::1]x", Some(80)), None);
+
+        // "alice:pa:ss"
+        assert_eq!(
+            parse_basic_auth("Basic YWxpY2U6cGE6c3M="),
+            Some(("alice".into(), "pa:ss".into()))
+        );
+        assert_eq!(parse_basic_auth("Bearer YWxpY2U6cGE6c3M="), None);
+        assert_eq!(parse_basic_auth("Basic !!"), None);
+        assert_eq!(strip_prefix_ignore_case("HTTP://x", "http://"), Some("x"));
+    }
+}
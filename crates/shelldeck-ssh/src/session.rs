@@ -114,45 +114,26 @@ impl SshSession {
 
     /// Execute a command and collect the full result.
     pub async fn exec(&self, command: &str) -> crate::Result<ExecResult> {
-        let handle = self.handle.lock().await;
-        let mut channel = handle
-            .channel_open_session()
-            .await
-            .map_err(|e| SshError::Channel(e.to_string()))?;
+        let channel = self.open_exec(command).await?;
+        Ok(collect_output(channel).await)
+    }
 
+    /// Execute a command with `input` written to its stdin, which is then
+    /// closed. Used for secrets: unlike the command line, stdin does not
+    /// show up in the remote process list.
+    pub async fn exec_with_stdin(&self, command: &str, input: &[u8]) -> crate::Result<ExecResult> {
+        let channel = self.open_exec(command).await?;
+        if !input.is_empty() {
+            channel
+                .data(input)
+                .await
+                .map_err(|e| SshError::Channel(e.to_string()))?;
+        }
         channel
-            .exec(true, command)
+            .eof()
             .await
             .map_err(|e| SshError::Channel(e.to_string()))?;
-
-        // Drop the handle lock before reading - we don't need it anymore
-        drop(handle);
-
-        let mut stdout = Vec::new();
-        let mut stderr = Vec::new();
-        let mut exit_code = None;
-
-        loop {
-            match channel.wait().await {
-                Some(ChannelMsg::Data { data }) => {
-                    stdout.extend_from_slice(&data);
-                }
-                Some(ChannelMsg::ExtendedData { data, ext: 1 }) => {
-                    stderr.extend_from_slice(&data);
-                }
-                Some(ChannelMsg::ExitStatus { exit_status }) => {
-                    exit_code = Some(exit_status);
-                }
-                Some(ChannelMsg::Eof) | None => break,
-                _ => {}
-            }
-        }
-
-        Ok(ExecResult {
-            stdout,
-            stderr,
-            exit_code,
-        })
+        Ok(collect_output(channel).await)
     }
 
     /// Execute a command with streaming output.
@@ -252,6 +233,308 @@ impl SshSession {
         Ok(exit_code)
     }
 
+    /// Run `command` here and `dest_command` on `dest`, piping this side's
+    /// stdout into the destination's stdin through ShellDeck. Used when the
+    /// two hosts cannot reach each other directly.
+    ///
+    /// Progress and the error output of both commands are reported through
+    /// `events`. `rate_limit_kbps` caps the throughput like rsync's
+    /// `--bwlimit`. A message on `shutdown_rx` closes both channels; it is
+    /// borrowed so one receiver can cover several relays in a row.
+    ///
+    /// The destination's stdin reaches EOF only once the source exited 0.
+    /// When the source fails its channel is closed instead, and both
+    /// channels are closed when writing to the destination fails.
+    pub async fn relay_to(
+        &self,
+        command: &str,
+        dest: &SshSession,
+        dest_command: &str,
+        events: mpsc::UnboundedSender<RelayEvent>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        rate_limit_kbps: Option<u32>,
+    ) -> crate::Result<RelayOutcome> {
+        self.relay_through(
+            command,
+            dest,
+            dest_command,
+            events,
+            shutdown_rx,
+            rate_limit_kbps,
+            &mut |chunk| chunk.map(<[u8]>::to_vec).unwrap_or_default(),
+        )
+        .await
+    }
+
+    /// Like [`relay_to`](Self::relay_to), but every chunk of source output
+    /// passes through `transform` before it is written to the destination.
+    /// `transform` is called with `None` once the source reaches EOF so it
+    /// can flush whatever it still buffers. `RelayOutcome::bytes` counts
+    /// the bytes read from the source.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn relay_through(
+        &self,
+        command: &str,
+        dest: &SshSession,
+        dest_command: &str,
+        events: mpsc::UnboundedSender<RelayEvent>,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+        rate_limit_kbps: Option<u32>,
+        transform: &mut (dyn FnMut(Option<&[u8]>) -> Vec<u8> + Send),
+    ) -> crate::Result<RelayOutcome> {
+        // Start the reader last so nothing is produced before the writer
+        // is ready to take it.
+        let mut sink = dest.open_exec(dest_command).await?;
+        let mut source = self.open_exec(command).await?;
+
+        let started = tokio::time::Instant::now();
+        let mut outcome = RelayOutcome::default();
+        let mut source_eof = false;
+        let mut source_done = false;
+        let mut sink_ended = false;
+        let mut sink_eof = false;
+        let mut sink_done = false;
+
+        while !(source_done && sink_done) {
+            let step: crate::Result<()> = tokio::select! {
+                msg = source.wait(), if !source_done => match msg {
+                    // Once the destination command is gone, keep draining so
+                    // the source can exit, but stop counting.
+                    Some(ChannelMsg::Data { data }) if !sink_done => {
+                        let out = transform(Some(&data[..]));
+                        let written = if out.is_empty() {
+                            Ok(())
+                        } else {
+                            sink.data(&out[..])
+                                .await
+                                .map_err(|e| SshError::Channel(e.to_string()))
+                        };
+                        if written.is_ok() {
+                            outcome.bytes += data.len() as u64;
+                            let _ = events.send(RelayEvent::Transferred(outcome.bytes));
+                            if let Some(kbps) = rate_limit_kbps.filter(|k| *k > 0) {
+                                let due = std::time::Duration::from_secs_f64(
+                                    outcome.bytes as f64 / (kbps as f64 * 1024.0),
+                                );
+                                let elapsed = started.elapsed();
+                                if due > elapsed {
+                                    tokio::time::sleep(due - elapsed).await;
+                                }
+                            }
+                        }
+                        written
+                    }
+                    Some(ChannelMsg::ExtendedData { data, .. }) => {
+                        let _ = events.send(RelayEvent::Output { dest: false, data: data.to_vec() });
+                        Ok(())
+                    }
+                    Some(ChannelMsg::ExitStatus { exit_status }) => {
+                        outcome.source_exit = Some(exit_status);
+                        source_done = source_eof;
+                        Ok(())
+                    }
+                    Some(ChannelMsg::Eof) => {
+                        source_eof = true;
+                        source_done = outcome.source_exit.is_some();
+                        Ok(())
+                    }
+                    Some(ChannelMsg::Close) | None => {
+                        source_done = true;
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                },
+                msg = sink.wait(), if !sink_done => {
+                    match msg {
+                        Some(ChannelMsg::Data { data }) | Some(ChannelMsg::ExtendedData { data, .. }) => {
+                            let _ = events.send(RelayEvent::Output { dest: true, data: data.to_vec() });
+                        }
+                        Some(ChannelMsg::ExitStatus { exit_status }) => {
+                            outcome.dest_exit = Some(exit_status);
+                            sink_done = sink_eof;
+                        }
+                        Some(ChannelMsg::Eof) => {
+                            sink_eof = true;
+                            sink_done = outcome.dest_exit.is_some();
+                        }
+                        Some(ChannelMsg::Close) | None => sink_done = true,
+                        _ => {}
+                    }
+                    Ok(())
+                },
+                _ = shutdown_rx.recv() => {
+                    let _ = source.eof().await;
+                    let _ = source.close().await;
+                    let _ = sink.eof().await;
+                    let _ = sink.close().await;
+                    outcome.cancelled = true;
+                    break;
+                }
+            };
+
+            // The destination only sees EOF once the source exited 0; a
+            // failed source's partial stream is cut off instead, so the
+            // destination command never takes it as complete.
+            let step = match step {
+                Ok(()) if source_done && !sink_ended && !sink_done => {
+                    sink_ended = true;
+                    if outcome.source_exit == Some(0) {
+                        let flushed = flush_transform(&sink, transform).await;
+                        if flushed.is_ok() {
+                            let _ = sink.eof().await;
+                        }
+                        flushed
+                    } else {
+                        let _ = sink.close().await;
+                        Ok(())
+                    }
+                }
+                step => step,
+            };
+            if let Err(e) = step {
+                let _ = source.close().await;
+                let _ = sink.close().await;
+                return Err(e);
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Run `command`, handing its stdout to `sink` as it arrives, for output
+    /// too large to collect in memory such as a database dump. Stderr is
+    /// kept in the outcome. The channel is closed when `sink` fails or a
+    /// message arrives on `shutdown_rx`.
+    pub async fn exec_to_sink(
+        &self,
+        command: &str,
+        sink: &mut (dyn FnMut(&[u8]) -> std::io::Result<()> + Send),
+        shutdown_rx: &mut mpsc::Receiver<()>,
+    ) -> crate::Result<StreamOutcome> {
+        let mut channel = self.open_exec(command).await?;
+        let mut outcome = StreamOutcome::default();
+        let mut eof = false;
+        loop {
+            tokio::select! {
+                msg = channel.wait() => match msg {
+                    Some(ChannelMsg::Data { data }) => {
+                        if let Err(e) = sink(&data[..]) {
+                            let _ = channel.close().await;
+                            return Err(SshError::Io(e));
+                        }
+                        outcome.bytes += data.len() as u64;
+                    }
+                    Some(ChannelMsg::ExtendedData { data, .. }) => {
+                        outcome.output.extend_from_slice(&data);
+                    }
+                    Some(ChannelMsg::ExitStatus { exit_status }) => {
+                        outcome.exit_code = Some(exit_status);
+                        if eof {
+                            break;
+                        }
+                    }
+                    Some(ChannelMsg::Eof) => {
+                        eof = true;
+                        if outcome.exit_code.is_some() {
+                            break;
+                        }
+                    }
+                    Some(ChannelMsg::Close) | None => break,
+                    _ => {}
+                },
+                Some(()) = shutdown_rx.recv() => {
+                    let _ = channel.eof().await;
+                    let _ = channel.close().await;
+                    outcome.cancelled = true;
+                    break;
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Run `command` with `source` streamed to its stdin, for input too
+    /// large to hold in memory such as a dump being restored. What the
+    /// command prints, on either stream, is kept in the outcome.
+    pub async fn exec_from_reader(
+        &self,
+        command: &str,
+        source: &mut (dyn tokio::io::AsyncRead + Unpin + Send),
+        shutdown_rx: &mut mpsc::Receiver<()>,
+    ) -> crate::Result<StreamOutcome> {
+        use tokio::io::AsyncReadExt;
+
+        let mut channel = self.open_exec(command).await?;
+        let mut outcome = StreamOutcome::default();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            if shutdown_rx.try_recv().is_ok() {
+                let _ = channel.eof().await;
+                let _ = channel.close().await;
+                outcome.cancelled = true;
+                return Ok(outcome);
+            }
+            let n = source.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            channel
+                .data(&buf[..n])
+                .await
+                .map_err(|e| SshError::Channel(e.to_string()))?;
+            outcome.bytes += n as u64;
+        }
+        channel
+            .eof()
+            .await
+            .map_err(|e| SshError::Channel(e.to_string()))?;
+
+        let mut eof = false;
+        loop {
+            tokio::select! {
+                msg = channel.wait() => match msg {
+                    Some(ChannelMsg::Data { data })
+                    | Some(ChannelMsg::ExtendedData { data, .. }) => {
+                        outcome.output.extend_from_slice(&data);
+                    }
+                    Some(ChannelMsg::ExitStatus { exit_status }) => {
+                        outcome.exit_code = Some(exit_status);
+                        if eof {
+                            break;
+                        }
+                    }
+                    Some(ChannelMsg::Eof) => {
+                        eof = true;
+                        if outcome.exit_code.is_some() {
+                            break;
+                        }
+                    }
+                    Some(ChannelMsg::Close) | None => break,
+                    _ => {}
+                },
+                Some(()) = shutdown_rx.recv() => {
+                    let _ = channel.close().await;
+                    outcome.cancelled = true;
+                    break;
+                }
+            }
+        }
+        Ok(outcome)
+    }
+
+    async fn open_exec(&self, command: &str) -> crate::Result<Channel<client::Msg>> {
+        let handle = self.handle.lock().await;
+        let channel = handle
+            .channel_open_session()
+            .await
+            .map_err(|e| SshError::Channel(e.to_string()))?;
+        channel
+            .exec(true, command)
+            .await
+            .map_err(|e| SshError::Channel(e.to_string()))?;
+        Ok(channel)
+    }
+
     /// Disconnect the session gracefully.
     pub async fn disconnect(&self) -> crate::Result<()> {
         let handle = self.handle.lock().await;
@@ -287,6 +570,94 @@ impl SshSession {
     }
 }
 
+/// Read a command's stdout, stderr and exit status until it closes.
+async fn collect_output(mut channel: Channel<client::Msg>) -> ExecResult {
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+    let mut exit_code = None;
+
+    loop {
+        match channel.wait().await {
+            Some(ChannelMsg::Data { data }) => {
+                stdout.extend_from_slice(&data);
+            }
+            Some(ChannelMsg::ExtendedData { data, ext: 1 }) => {
+                stderr.extend_from_slice(&data);
+            }
+            Some(ChannelMsg::ExitStatus { exit_status }) => {
+                exit_code = Some(exit_status);
+            }
+            Some(ChannelMsg::Eof) | None => break,
+            _ => {}
+        }
+    }
+
+    ExecResult {
+        stdout,
+        stderr,
+        exit_code,
+    }
+}
+
+/// Write whatever `transform` still holds once the source is done.
+async fn flush_transform(
+    sink: &Channel<client::Msg>,
+    transform: &mut (dyn FnMut(Option<&[u8]>) -> Vec<u8> + Send),
+) -> crate::Result<()> {
+    let tail = transform(None);
+    if !tail.is_empty() {
+        sink.data(&tail[..])
+            .await
+            .map_err(|e| SshError::Channel(e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Reported while [`SshSession::relay_to`] runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RelayEvent {
+    /// Total bytes delivered to the destination so far.
+    Transferred(u64),
+    /// Diagnostic output of the source (`dest == false`) or destination
+    /// command.
+    Output { dest: bool, data: Vec<u8> },
+}
+
+/// How a relay ended.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelayOutcome {
+    pub bytes: u64,
+    pub source_exit: Option<u32>,
+    pub dest_exit: Option<u32>,
+    pub cancelled: bool,
+}
+
+impl RelayOutcome {
+    /// Both commands ran to completion and exited 0.
+    pub fn success(&self) -> bool {
+        !self.cancelled && self.source_exit == Some(0) && self.dest_exit == Some(0)
+    }
+}
+
+/// How [`SshSession::exec_to_sink`] or [`SshSession::exec_from_reader`]
+/// ended.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamOutcome {
+    /// Bytes streamed out of (or into) the command.
+    pub bytes: u64,
+    pub exit_code: Option<u32>,
+    /// Diagnostic output of the command.
+    pub output: Vec<u8>,
+    pub cancelled: bool,
+}
+
+impl StreamOutcome {
+    /// The command ran to completion and exited 0.
+    pub fn success(&self) -> bool {
+        !self.cancelled && self.exit_code == Some(0)
+    }
+}
+
 pub struct SshChannel {
     channel: Channel<client::Msg>,
 }
@@ -470,7 +841,7 @@ mod channel_end_tests {
 
 #[cfg(test)]
 mod in_memory_ssh_tests {
-    use super::SshSession;
+    use super::{RelayEvent, RelayOutcome, SshSession, StreamOutcome};
     use crate::handler::ClientHandler;
     use russh::keys::{ssh_key::Algorithm, PrivateKey};
     use russh::server::{self, Auth, Msg, Session};
@@ -490,6 +861,10 @@ mod in_memory_ssh_tests {
             exit_code: u32,
         },
         WaitForCancellation,
+        /// Swallow stdin and exit with `exit_code` once it is closed.
+        Sink {
+            exit_code: u32,
+        },
     }
 
     #[derive(Debug, PartialEq, Eq)]
@@ -498,6 +873,7 @@ mod in_memory_ssh_tests {
         Shell,
         Exec(Vec<u8>),
         Resize { cols: u32, rows: u32 },
+        Data(Vec<u8>),
         ChannelEof,
     }
 
@@ -589,12 +965,27 @@ mod in_memory_ssh_tests {
             Ok(())
         }
 
-        async fn channel_eof(
+        async fn data(
             &mut self,
             _channel: ChannelId,
+            data: &[u8],
             _session: &mut Session,
+        ) -> Result<(), Self::Error> {
+            let _ = self.events.send(ServerEvent::Data(data.to_vec()));
+            Ok(())
+        }
+
+        async fn channel_eof(
+            &mut self,
+            channel: ChannelId,
+            session: &mut Session,
         ) -> Result<(), Self::Error> {
             let _ = self.events.send(ServerEvent::ChannelEof);
+            if let ExecBehavior::Sink { exit_code } = self.exec_behavior {
+                session.exit_status_request(channel, exit_code)?;
+                session.eof(channel)?;
+                session.close(channel)?;
+            }
             Ok(())
         }
     }
@@ -752,4 +1143,231 @@ mod in_memory_ssh_tests {
 
         server_task.abort();
     }
+
+    #[tokio::test]
+    async fn exec_with_stdin_writes_input_then_closes_it() {
+        let (session, mut events, server_task) =
+            start_session(ExecBehavior::Sink { exit_code: 0 }).await;
+
+        let result = timeout(
+            Duration::from_secs(2),
+            session.exec_with_stdin("cat > secret", b"password=hunter2\n"),
+        )
+        .await
+        .expect("exec timed out")
+        .expect("exec failed");
+
+        assert_eq!(
+            next_event(&mut events).await,
+            ServerEvent::Exec(b"cat > secret".to_vec())
+        );
+        assert_eq!(
+            next_event(&mut events).await,
+            ServerEvent::Data(b"password=hunter2\n".to_vec())
+        );
+        assert_eq!(next_event(&mut events).await, ServerEvent::ChannelEof);
+        assert_eq!(result.exit_code, Some(0));
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn exec_to_sink_streams_stdout_and_keeps_stderr() {
+        let (session, _events, server_task) = start_session(ExecBehavior::Complete {
+            stdout: b"dump bytes".to_vec(),
+            stderr: b"mysqldump: warning\n".to_vec(),
+            exit_code: 0,
+        })
+        .await;
+        let (_shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+
+        let mut received = Vec::new();
+        let mut sink = |data: &[u8]| {
+            received.extend_from_slice(data);
+            Ok(())
+        };
+        let outcome = timeout(
+            Duration::from_secs(2),
+            session.exec_to_sink("mysqldump db | gzip", &mut sink, &mut shutdown_rx),
+        )
+        .await
+        .expect("exec timed out")
+        .expect("exec failed");
+
+        assert_eq!(received, b"dump bytes");
+        assert_eq!(
+            outcome,
+            StreamOutcome {
+                bytes: 10,
+                exit_code: Some(0),
+                output: b"mysqldump: warning\n".to_vec(),
+                cancelled: false,
+            }
+        );
+        assert!(outcome.success());
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn exec_from_reader_streams_the_reader_into_stdin() {
+        let (session, mut events, server_task) =
+            start_session(ExecBehavior::Sink { exit_code: 0 }).await;
+        let (_shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+
+        let mut source: &[u8] = b"compressed dump";
+        let outcome = timeout(
+            Duration::from_secs(2),
+            session.exec_from_reader("gunzip | mysql db", &mut source, &mut shutdown_rx),
+        )
+        .await
+        .expect("exec timed out")
+        .expect("exec failed");
+
+        assert_eq!(
+            next_event(&mut events).await,
+            ServerEvent::Exec(b"gunzip | mysql db".to_vec())
+        );
+        let mut received = Vec::new();
+        loop {
+            match next_event(&mut events).await {
+                ServerEvent::Data(data) => received.extend(data),
+                ServerEvent::ChannelEof => break,
+                other => panic!("unexpected server event {other:?}"),
+            }
+        }
+        assert_eq!(received, b"compressed dump");
+        assert_eq!(outcome.bytes, 15);
+        assert!(outcome.success());
+
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn relay_pipes_source_stdout_into_destination_stdin() {
+        let (source, _source_events, source_task) = start_session(ExecBehavior::Complete {
+            stdout: b"tar stream bytes".to_vec(),
+            stderr: b"source warning\n".to_vec(),
+            exit_code: 0,
+        })
+        .await;
+        let (dest, mut dest_events, dest_task) =
+            start_session(ExecBehavior::Sink { exit_code: 0 }).await;
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        let (_shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+
+        let outcome = timeout(
+            Duration::from_secs(2),
+            source.relay_to(
+                "tar -cf - .",
+                &dest,
+                "tar -xf -",
+                events_tx,
+                &mut shutdown_rx,
+                None,
+            ),
+        )
+        .await
+        .expect("relay timed out")
+        .expect("relay failed");
+
+        assert_eq!(
+            next_event(&mut dest_events).await,
+            ServerEvent::Exec(b"tar -xf -".to_vec())
+        );
+        let mut received = Vec::new();
+        loop {
+            match next_event(&mut dest_events).await {
+                ServerEvent::Data(data) => received.extend(data),
+                ServerEvent::ChannelEof => break,
+                other => panic!("unexpected server event {other:?}"),
+            }
+        }
+        assert_eq!(received, b"tar stream bytes");
+        assert_eq!(
+            outcome,
+            RelayOutcome {
+                bytes: 16,
+                source_exit: Some(0),
+                dest_exit: Some(0),
+                cancelled: false,
+            }
+        );
+        assert!(outcome.success());
+
+        let mut transferred = 0;
+        let mut diagnostics = Vec::new();
+        while let Ok(event) = events_rx.try_recv() {
+            match event {
+                RelayEvent::Transferred(bytes) => transferred = bytes,
+                RelayEvent::Output { dest: false, data } => diagnostics.extend(data),
+                RelayEvent::Output { dest: true, .. } => {}
+            }
+        }
+        assert_eq!(transferred, 16);
+        assert_eq!(diagnostics, b"source warning\n");
+
+        source_task.abort();
+        dest_task.abort();
+    }
+
+    #[tokio::test]
+    async fn relay_through_transforms_and_flushes_source_output() {
+        let (source, _source_events, source_task) = start_session(ExecBehavior::Complete {
+            stdout: b"old.example old.example".to_vec(),
+            stderr: Vec::new(),
+            exit_code: 0,
+        })
+        .await;
+        let (dest, mut dest_events, dest_task) =
+            start_session(ExecBehavior::Sink { exit_code: 0 }).await;
+        let (events_tx, _events_rx) = mpsc::unbounded_channel();
+        let (_shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
+
+        // Hold everything back until EOF to exercise the flush.
+        let mut held = Vec::new();
+        let mut transform = |chunk: Option<&[u8]>| match chunk {
+            Some(data) => {
+                held.extend_from_slice(data);
+                Vec::new()
+            }
+            None => String::from_utf8_lossy(&held)
+                .replace("old", "new")
+                .into_bytes(),
+        };
+        let outcome = timeout(
+            Duration::from_secs(2),
+            source.relay_through(
+                "mysqldump db",
+                &dest,
+                "mysql db",
+                events_tx,
+                &mut shutdown_rx,
+                None,
+                &mut transform,
+            ),
+        )
+        .await
+        .expect("relay timed out")
+        .expect("relay failed");
+
+        assert_eq!(
+            next_event(&mut dest_events).await,
+            ServerEvent::Exec(b"mysql db".to_vec())
+        );
+        let mut received = Vec::new();
+        loop {
+            match next_event(&mut dest_events).await {
+                ServerEvent::Data(data) => received.extend(data),
+                ServerEvent::ChannelEof => break,
+                other => panic!("unexpected server event {other:?}"),
+            }
+        }
+        assert_eq!(received, b"new.example new.example");
+        assert_eq!(outcome.bytes, 23);
+        assert!(outcome.success());
+
+        source_task.abort();
+        dest_task.abort();
+    }
 }
@@ -0,0 +1,425 @@
+//! Keeps one port forward up: rebuilds the SSH session and the listener
+//! with backoff when either dies, and optionally probes the far end.
+
+use std::net::SocketAddr;
+use std::sync::atomic::AtomicU64;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex as ParkingMutex;
+use shelldeck_core::config::keychain;
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::port_forward::{ForwardDirection, PortForward, ProbeKind};
+use shelldeck_core::models::tunnel_health::{
+    http_probe_request, http_status_is_up, parse_http_status, Backoff, ProbeTarget,
+};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+use crate::client::SshClient;
+use crate::handler::SshEvent;
+use crate::session::{SharedHandle, SshSession};
+use crate::tunnel::{LocalBind, SharedTraffic, TunnelHandle, TunnelManager, TunnelStatus};
+use crate::SshError;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(20);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// How often the listener is checked for having stopped on its own.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+/// Bytes read from an HTTP probe answer before giving up on a status line.
+const MAX_PROBE_RESPONSE: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupervisorEvent {
+    /// The forward is listening and its session is up. `local_addr` is
+    /// where a local or dynamic forward listens.
+    Up { local_addr: Option<SocketAddr> },
+    /// The forward was lost or could not be brought up; the next attempt
+    /// is in `retry_in`.
+    Down { reason: String, retry_in: Duration },
+    /// A probe of the far end: its latency, or why it failed.
+    Probe(Result<Duration, String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct SupervisorOptions {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Failed probes in a row before the forward is rebuilt.
+    pub probe_failures: u32,
+}
+
+impl Default for SupervisorOptions {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            probe_failures: 3,
+        }
+    }
+}
+
+/// Owns one forward for as long as it should be up. Status, byte counters
+/// and the traffic log outlive each rebuilt session, so a handle taken
+/// once stays valid across reconnects.
+pub struct TunnelSupervisor {
+    connection: Connection,
+    forward: PortForward,
+    options: SupervisorOptions,
+    status: Arc<ParkingMutex<TunnelStatus>>,
+    bytes_sent: Arc<AtomicU64>,
+    bytes_received: Arc<AtomicU64>,
+    traffic: SharedTraffic,
+    events: mpsc::UnboundedSender<SupervisorEvent>,
+}
+
+impl TunnelSupervisor {
+    pub fn new(
+        connection: Connection,
+        forward: PortForward,
+        options: SupervisorOptions,
+        events: mpsc::UnboundedSender<SupervisorEvent>,
+    ) -> Self {
+        Self {
+            connection,
+            forward,
+            options,
+            status: Arc::new(ParkingMutex::new(TunnelStatus::Reconnecting)),
+            bytes_sent: Arc::new(AtomicU64::new(0)),
+            bytes_received: Arc::new(AtomicU64::new(0)),
+            traffic: SharedTraffic::default(),
+            events,
+        }
+    }
+
+    /// A handle sharing this supervisor's status, byte counters and
+    /// traffic log.
+    /// Signalling `shutdown_tx` (or [`TunnelHandle::stop`]) ends [`run`](Self::run).
+    pub fn handle(&self, shutdown_tx: mpsc::Sender<()>) -> TunnelHandle {
+        TunnelHandle::new_proxy(
+            self.forward.id,
+            self.status.clone(),
+            self.bytes_sent.clone(),
+            self.bytes_received.clone(),
+            self.traffic.clone(),
+            shutdown_tx,
+        )
+    }
+
+    /// Keep the forward up until `shutdown_rx` fires or its sender is dropped.
+    pub async fn run(self, mut shutdown_rx: mpsc::Receiver<()>) {
+        let mut backoff = Backoff::new(self.options.initial_backoff, self.options.max_backoff);
+        loop {
+            *self.status.lock() = TunnelStatus::Reconnecting;
+            let Some(reason) = self.run_once(&mut backoff, &mut shutdown_rx).await else {
+                break;
+            };
+            let retry_in = backoff.next_delay();
+            tracing::warn!(
+                "Forward {} down ({}), retrying in {:?}",
+                self.forward.id,
+                reason,
+                retry_in
+            );
+            let _ = self.events.send(SupervisorEvent::Down { reason, retry_in });
+            tokio::select! {
+                _ = tokio::time::sleep(retry_in) => {}
+                _ = shutdown_rx.recv() => break,
+            }
+        }
+        *self.status.lock() = TunnelStatus::Stopped;
+        tracing::info!("Supervisor for forward {} stopped", self.forward.id);
+    }
+
+    /// One session's worth of forwarding. Returns why the forward went
+    /// down, or `None` when shutdown was requested.
+    async fn run_once(
+        &self,
+        backoff: &mut Backoff,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+    ) -> Option<String> {
+        let client = SshClient::new();
+        let connect = tokio::time::timeout(CONNECT_TIMEOUT, client.connect(&self.connection));
+        let mut session = tokio::select! {
+            result = connect => match result {
+                Ok(Ok(session)) => session,
+                Ok(Err(e)) => return Some(format!("SSH connection failed: {}", e)),
+                Err(_) => return Some("SSH connection timed out".to_string()),
+            },
+            _ = shutdown_rx.recv() => return None,
+        };
+
+        let mut manager =
+            TunnelManager::with_counters(self.bytes_sent.clone(), self.bytes_received.clone())
+                .with_traffic(self.traffic.clone());
+        let outcome = match self.start_forward(&mut manager, &mut session).await {
+            Ok(()) => {
+                *self.status.lock() = TunnelStatus::Active;
+                backoff.reset();
+                let local_addr = manager.tunnels().first().and_then(|t| t.local_addr);
+                let _ = self.events.send(SupervisorEvent::Up { local_addr });
+                self.watch(&mut session, &manager, shutdown_rx).await
+            }
+            Err(e) => Some(format!("Tunnel start failed: {}", e)),
+        };
+
+        manager.stop_all();
+        // Let the tunnel tasks drop their listeners before a rebuild binds again.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let _ = tokio::time::timeout(DISCONNECT_TIMEOUT, session.disconnect()).await;
+        outcome
+    }
+
+    async fn start_forward(
+        &self,
+        manager: &mut TunnelManager,
+        session: &mut SshSession,
+    ) -> crate::Result<()> {
+        let forward = &self.forward;
+        if let Some(error) = forward.bind_error() {
+            return Err(SshError::Tunnel(error));
+        }
+        let handle = session.shared_handle();
+        match forward.direction {
+            ForwardDirection::LocalToRemote => {
+                manager
+                    .start_local_forward(
+                        handle,
+                        LocalBind::for_forward(forward),
+                        forward.remote_host.clone(),
+                        forward.remote_port,
+                        forward.inspect_http,
+                    )
+                    .await?;
+            }
+            ForwardDirection::RemoteToLocal => {
+                let forwarded_rx = session.take_forwarded_tcpip_rx().ok_or_else(|| {
+                    SshError::Tunnel("remote forwarding channel already taken".to_string())
+                })?;
+                manager
+                    .start_remote_forward(
+                        handle,
+                        forward.remote_port,
+                        forward.local_host.clone(),
+                        forward.local_port,
+                        forwarded_rx,
+                    )
+                    .await?;
+            }
+            ForwardDirection::Dynamic => {
+                let mut proxy = forward.proxy.clone();
+                if proxy.requires_auth() && proxy.password.is_none() {
+                    proxy.password = keychain::get_proxy_password(forward.id)
+                        .map_err(|e| SshError::Tunnel(e.to_string()))?;
+                }
+                manager
+                    .start_socks_forward(handle, LocalBind::for_forward(forward), proxy)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait until the session or the listener dies, or the far end fails
+    /// enough probes in a row.
+    async fn watch(
+        &self,
+        session: &mut SshSession,
+        manager: &TunnelManager,
+        shutdown_rx: &mut mpsc::Receiver<()>,
+    ) -> Option<String> {
+        let handle = session.shared_handle();
+        let probe = self
+            .forward
+            .probe
+            .clone()
+            .zip(ProbeTarget::for_forward(&self.forward));
+        let probe_every = probe
+            .as_ref()
+            .map(|(probe, _)| Duration::from_secs(probe.interval_secs.max(1)));
+        let mut next_probe = probe_every.map(|every| Instant::now() + every);
+        let mut failed_probes = 0;
+        let mut tick = tokio::time::interval(WATCH_INTERVAL);
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => return None,
+                event = session.event_rx().recv() => match event {
+                    Some(SshEvent::Disconnected(reason)) => {
+                        return Some(format!("SSH session lost: {}", reason));
+                    }
+                    None => return Some("SSH session closed".to_string()),
+                    Some(_) => {}
+                },
+                _ = tick.tick() => {
+                    if manager
+                        .tunnels()
+                        .iter()
+                        .any(|tunnel| *tunnel.status.lock() != TunnelStatus::Active)
+                    {
+                        return Some("Tunnel listener stopped".to_string());
+                    }
+                    let (Some((probe, target)), Some(at)) = (&probe, next_probe) else {
+                        continue;
+                    };
+                    if Instant::now() < at {
+                        continue;
+                    }
+                    next_probe = probe_every.map(|every| Instant::now() + every);
+                    let result = probe_far_end(&handle, target, &probe.kind).await;
+                    let _ = self.events.send(SupervisorEvent::Probe(result.clone()));
+                    match result {
+                        Ok(_) => failed_probes = 0,
+                        Err(e) => {
+                            failed_probes += 1;
+                            if failed_probes >= self.options.probe_failures.max(1) {
+                                return Some(format!(
+                                    "{} probes failed in a row: {}",
+                                    failed_probes, e
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Reach the far end of a forward the way its traffic does and check it
+/// answers.
+async fn probe_far_end(
+    handle: &SharedHandle,
+    target: &ProbeTarget,
+    kind: &ProbeKind,
+) -> Result<Duration, String> {
+    let started = Instant::now();
+    let attempt = async {
+        match target {
+            ProbeTarget::Remote { host, port } => {
+                let channel = {
+                    let h = handle.lock().await;
+                    h.channel_open_direct_tcpip(host.as_str(), *port as u32, "127.0.0.1", 0)
+                        .await
+                        .map_err(|e| format!("{}:{} unreachable: {}", host, port, e))?
+                };
+                check_stream(channel.into_stream(), host, *port, kind).await
+            }
+            ProbeTarget::Local { host, port } => {
+                let stream = TcpStream::connect((host.as_str(), *port))
+                    .await
+                    .map_err(|e| format!("{}:{} unreachable: {}", host, port, e))?;
+                check_stream(stream, host, *port, kind).await
+            }
+        }
+    };
+    match tokio::time::timeout(PROBE_TIMEOUT, attempt).await {
+        Ok(Ok(())) => Ok(started.elapsed()),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Err("Probe timed out".to_string()),
+    }
+}
+
+/// A TCP probe passes once connected; an HTTP probe needs a status line
+/// that counts as up.
+async fn check_stream<S>(
+    mut stream: S,
+    host: &str,
+    port: u16,
+    kind: &ProbeKind,
+) -> Result<(), String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let ProbeKind::Http { path } = kind else {
+        return Ok(());
+    };
+    stream
+        .write_all(http_probe_request(host, port, path).as_bytes())
+        .await
+        .map_err(|e| format!("Probe request failed: {}", e))?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 256];
+    loop {
+        if let Some(status) = parse_http_status(&response) {
+            return if http_status_is_up(status) {
+                Ok(())
+            } else {
+                Err(format!("HTTP {}", status))
+            };
+        }
+        if response.contains(&b'\n') || response.len() >= MAX_PROBE_RESPONSE {
+            return Err("Not an HTTP response".to_string());
+        }
+        let n = stream
+            .read(&mut buf)
+            .await
+            .map_err(|e| format!("Probe read failed: {}", e))?;
+        if n == 0 {
+            return Err("Connection closed before an HTTP answer".to_string());
+        }
+        response.extend_from_slice(&buf[..n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Play the far end of a probe: read the request, answer with `reply`.
+    async fn probe_against(reply: &'static [u8], kind: ProbeKind) -> (Result<(), String>, String) {
+        let (client, mut server) = tokio::io::duplex(4096);
+        let far_end = tokio::spawn(async move {
+            let mut request = vec![0u8; 1024];
+            let n = server.read(&mut request).await.unwrap_or(0);
+            let _ = server.write_all(reply).await;
+            String::from_utf8_lossy(&request[..n]).into_owned()
+        });
+        let result = check_stream(client, "app.internal", 8080, &kind).await;
+        (result, far_end.await.expect("far end task"))
+    }
+
+    fn http(path: &str) -> ProbeKind {
+        ProbeKind::Http {
+            path: path.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn http_probe_sends_a_get_and_accepts_answers_below_500() {
+        let (result, request) = probe_against(
+            b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n",
+            http("/health"),
+        )
+        .await;
+        assert_eq!(result, Ok(()));
+        assert!(request.starts_with("GET /health HTTP/1.1\r\nHost: app.internal:8080\r\n"));
+
+        let (result, _) = probe_against(b"HTTP/1.1 401 Unauthorized\r\n\r\n", http("/")).await;
+        assert_eq!(result, Ok(()), "something is listening");
+    }
+
+    #[tokio::test]
+    async fn http_probe_fails_on_server_errors_and_non_http_answers() {
+        let (result, _) = probe_against(b"HTTP/1.1 502 Bad Gateway\r\n\r\n", http("/")).await;
+        assert_eq!(result, Err("HTTP 502".to_string()));
+
+        let (result, _) = probe_against(b"SSH-2.0-OpenSSH_9.6\r\n", http("/")).await;
+        assert_eq!(result, Err("Not an HTTP response".to_string()));
+
+        let (result, _) = probe_against(b"", http("/")).await;
+        assert!(result.unwrap_err().contains("closed"));
+    }
+
+    #[tokio::test]
+    async fn tcp_probe_passes_once_connected() {
+        let (client, _server) = tokio::io::duplex(64);
+        assert_eq!(
+            check_stream(client, "db", 5432, &ProbeKind::Tcp).await,
+            Ok(())
+        );
+    }
+}
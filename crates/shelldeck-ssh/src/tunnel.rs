@@ -1,7 +1,12 @@
 use crate::handler::ForwardedTcpIpEvent;
+use crate::proxy::{self, ProxyConfig, RequestKind};
 use crate::session::SharedHandle;
 use crate::SshError;
 use parking_lot::Mutex as ParkingMutex;
+use shelldeck_core::models::forward_proxy::ProxySettings;
+use shelldeck_core::models::forward_traffic::{HttpInspector, HttpMessage, TrafficLog};
+use shelldeck_core::models::port_forward::{bind_host, ClientCidr, PortForward};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -14,13 +19,111 @@ pub enum TunnelStatus {
     Active,
     Error,
     Stopped,
+    /// Set by the supervisor while it rebuilds a lost tunnel.
+    Reconnecting,
+}
+
+/// Connection records and captured HTTP of a tunnel, shared with the UI.
+pub type SharedTraffic = Arc<ParkingMutex<TrafficLog>>;
+
+/// Ports tried above a taken one before asking the OS for any free port.
+const FREE_PORT_ATTEMPTS: u16 = 10;
+
+/// Where a local or dynamic forward listens, and who may connect to it.
+#[derive(Debug, Clone)]
+pub struct LocalBind {
+    /// Address as typed; see [`bind_host`] for how it is read.
+    pub host: String,
+    pub port: u16,
+    /// Clients allowed besides this machine; empty lets any client in.
+    pub allowed_clients: Vec<ClientCidr>,
+    /// Listen on a free port when `port` is taken instead of failing.
+    pub pick_free_port: bool,
+}
+
+impl LocalBind {
+    /// `127.0.0.1:port`, open to local clients only.
+    pub fn loopback(port: u16) -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port,
+            allowed_clients: Vec::new(),
+            pick_free_port: false,
+        }
+    }
+
+    /// The listener of a local or dynamic forward as configured.
+    pub fn for_forward(forward: &PortForward) -> Self {
+        Self {
+            host: forward.local_host.clone(),
+            port: forward.local_port,
+            allowed_clients: forward.client_allowlist(),
+            pick_free_port: forward.auto_port,
+        }
+    }
+
+    /// Whether a client connecting from `peer` may use the forward.
+    /// This machine always may.
+    pub fn allows(&self, peer: IpAddr) -> bool {
+        let peer = match peer {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(peer),
+            v4 => v4,
+        };
+        peer.is_loopback()
+            || self.allowed_clients.is_empty()
+            || self.allowed_clients.iter().any(|cidr| cidr.contains(peer))
+    }
+
+    /// Bind the listener, moving to a free port when the requested one is
+    /// taken and [`pick_free_port`](Self::pick_free_port) is set: first the
+    /// next few ports up, then whatever the OS hands out.
+    async fn listen(&self) -> crate::Result<TcpListener> {
+        let host = bind_host(&self.host);
+        let cannot_listen = |port: u16, e: std::io::Error| {
+            SshError::Tunnel(format!(
+                "Cannot listen on {}: {}",
+                format_addr(&host, port),
+                e
+            ))
+        };
+        match TcpListener::bind((host.as_str(), self.port)).await {
+            Ok(listener) => return Ok(listener),
+            Err(e) if e.kind() != std::io::ErrorKind::AddrInUse => {
+                return Err(cannot_listen(self.port, e));
+            }
+            Err(_) if !self.pick_free_port => return Err(SshError::PortInUse(self.port)),
+            Err(_) => {}
+        }
+        let next = self.port.saturating_add(1);
+        for port in next..next.saturating_add(FREE_PORT_ATTEMPTS) {
+            if let Ok(listener) = TcpListener::bind((host.as_str(), port)).await {
+                return Ok(listener);
+            }
+        }
+        TcpListener::bind((host.as_str(), 0))
+            .await
+            .map_err(|e| cannot_listen(0, e))
+    }
+}
+
+/// `host:port`, with IPv6 literals in brackets.
+fn format_addr(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
 }
 
 pub struct TunnelHandle {
     pub id: Uuid,
     pub status: Arc<ParkingMutex<TunnelStatus>>,
+    /// Address a local or dynamic forward actually listens on, which may
+    /// differ from the requested port when a free one was picked.
+    pub local_addr: Option<SocketAddr>,
     pub bytes_sent: Arc<AtomicU64>,
     pub bytes_received: Arc<AtomicU64>,
+    pub traffic: SharedTraffic,
     shutdown_tx: mpsc::Sender<()>,
 }
 
@@ -36,13 +139,16 @@ impl TunnelHandle {
         status: Arc<ParkingMutex<TunnelStatus>>,
         bytes_sent: Arc<AtomicU64>,
         bytes_received: Arc<AtomicU64>,
+        traffic: SharedTraffic,
         shutdown_tx: mpsc::Sender<()>,
     ) -> Self {
         Self {
             id,
             status,
+            local_addr: None,
             bytes_sent,
             bytes_received,
+            traffic,
             shutdown_tx,
         }
     }
@@ -65,58 +171,85 @@ impl TunnelHandle {
 
 pub struct TunnelManager {
     tunnels: Vec<TunnelHandle>,
+    /// Byte counters every tunnel started here adds to, instead of fresh
+    /// ones per tunnel.
+    counters: Option<(Arc<AtomicU64>, Arc<AtomicU64>)>,
+    /// Traffic log every tunnel started here records into.
+    traffic: Option<SharedTraffic>,
 }
 
 impl TunnelManager {
     pub fn new() -> Self {
         Self {
             tunnels: Vec::new(),
+            counters: None,
+            traffic: None,
         }
     }
 
-    /// Check if a local port is available for binding.
-    pub async fn check_port_available(port: u16) -> bool {
-        TcpListener::bind(format!("127.0.0.1:{}", port))
+    /// A manager whose tunnels count into `bytes_sent`/`bytes_received`, so
+    /// totals carry over when a supervisor rebuilds a tunnel.
+    pub fn with_counters(bytes_sent: Arc<AtomicU64>, bytes_received: Arc<AtomicU64>) -> Self {
+        Self {
+            tunnels: Vec::new(),
+            counters: Some((bytes_sent, bytes_received)),
+            traffic: None,
+        }
+    }
+
+    /// Record connections of the tunnels started here into `traffic`, so
+    /// the log carries over when a supervisor rebuilds a tunnel.
+    pub fn with_traffic(mut self, traffic: SharedTraffic) -> Self {
+        self.traffic = Some(traffic);
+        self
+    }
+
+    fn new_counters(&self) -> (Arc<AtomicU64>, Arc<AtomicU64>) {
+        self.counters
+            .clone()
+            .unwrap_or_else(|| (Arc::new(AtomicU64::new(0)), Arc::new(AtomicU64::new(0))))
+    }
+
+    fn new_traffic(&self) -> SharedTraffic {
+        self.traffic.clone().unwrap_or_default()
+    }
+
+    /// Check if a port is free to listen on at `host`.
+    pub async fn check_port_available(host: &str, port: u16) -> bool {
+        TcpListener::bind((bind_host(host).as_str(), port))
             .await
             .is_ok()
     }
 
     /// Start a local port forward (SSH -L equivalent).
-    /// Binds to `local_port` and forwards connections to `remote_host:remote_port` through SSH.
+    /// Listens on `bind` and forwards connections to `remote_host:remote_port` through SSH.
+    /// With `inspect_http`, HTTP/1.1 passing through is recorded in the traffic log.
     pub async fn start_local_forward(
         &mut self,
         handle: SharedHandle,
-        local_port: u16,
+        bind: LocalBind,
         remote_host: String,
         remote_port: u16,
+        inspect_http: bool,
     ) -> crate::Result<Uuid> {
-        if !Self::check_port_available(local_port).await {
-            return Err(SshError::PortInUse(local_port));
-        }
+        let listener = bind.listen().await?;
+        let local_addr = listener.local_addr()?;
 
         let id = Uuid::new_v4();
         let status = Arc::new(ParkingMutex::new(TunnelStatus::Active));
-        let bytes_sent = Arc::new(AtomicU64::new(0));
-        let bytes_received = Arc::new(AtomicU64::new(0));
+        let (bytes_sent, bytes_received) = self.new_counters();
+        let traffic = self.new_traffic();
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
 
         let status_clone = status.clone();
         let bytes_sent_clone = bytes_sent.clone();
         let bytes_received_clone = bytes_received.clone();
+        let traffic_clone = traffic.clone();
 
         tokio::spawn(async move {
-            let listener = match TcpListener::bind(format!("127.0.0.1:{}", local_port)).await {
-                Ok(l) => l,
-                Err(e) => {
-                    tracing::error!("Failed to bind local port {}: {}", local_port, e);
-                    *status_clone.lock() = TunnelStatus::Error;
-                    return;
-                }
-            };
-
             tracing::info!(
-                "Local forward: 127.0.0.1:{} -> {}:{}",
-                local_port,
+                "Local forward: {} -> {}:{}",
+                local_addr,
                 remote_host,
                 remote_port
             );
@@ -126,16 +259,25 @@ impl TunnelManager {
                 tokio::select! {
                     accept = listener.accept() => {
                         match accept {
+                            Ok((_, addr)) if !bind.allows(addr.ip()) => {
+                                tracing::warn!("Refused tunnel connection from {}: not in the allowlist", addr);
+                            }
                             Ok((stream, addr)) => {
                                 tracing::debug!("Accepted tunnel connection from {}", addr);
                                 let handle = handle.clone();
                                 let rhost = remote_host.clone();
                                 let bs = bytes_sent_clone.clone();
                                 let br = bytes_received_clone.clone();
+                                let tap = ConnectionTap::open(
+                                    traffic_clone.clone(),
+                                    addr.to_string(),
+                                    format_addr(&rhost, remote_port),
+                                    inspect_http,
+                                );
 
                                 connections.spawn(async move {
                                     if let Err(e) = handle_local_forward_connection(
-                                        handle, stream, &rhost, remote_port, bs, br,
+                                        handle, stream, &rhost, remote_port, bs, br, tap,
                                     )
                                     .await
                                     {
@@ -154,7 +296,7 @@ impl TunnelManager {
                         }
                     }
                     _ = shutdown_rx.recv() => {
-                        tracing::info!("Stopping local forward on port {}", local_port);
+                        tracing::info!("Stopping local forward on {}", local_addr);
                         break;
                     }
                 }
@@ -170,7 +312,9 @@ impl TunnelManager {
             status,
             bytes_sent,
             bytes_received,
+            traffic,
             shutdown_tx,
+            local_addr: Some(local_addr),
         });
 
         Ok(id)
@@ -201,13 +345,14 @@ impl TunnelManager {
 
         let id = Uuid::new_v4();
         let status = Arc::new(ParkingMutex::new(TunnelStatus::Active));
-        let bytes_sent = Arc::new(AtomicU64::new(0));
-        let bytes_received = Arc::new(AtomicU64::new(0));
+        let (bytes_sent, bytes_received) = self.new_counters();
+        let traffic = self.new_traffic();
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
 
         let status_clone = status.clone();
         let bytes_sent_clone = bytes_sent.clone();
         let bytes_received_clone = bytes_received.clone();
+        let traffic_clone = traffic.clone();
 
         tracing::info!(
             "Remote forward: remote:{} -> {}:{}",
@@ -238,10 +383,16 @@ impl TunnelManager {
                                 let lport = local_port;
                                 let bs = bytes_sent_clone.clone();
                                 let br = bytes_received_clone.clone();
+                                let tap = ConnectionTap::open(
+                                    traffic_clone.clone(),
+                                    format_addr(&fwd.originator_address, fwd.originator_port as u16),
+                                    format_addr(&lhost, lport),
+                                    false,
+                                );
 
                                 connections.spawn(async move {
                                     if let Err(e) = handle_remote_forward_connection(
-                                        fwd.channel, &lhost, lport, bs, br,
+                                        fwd.channel, &lhost, lport, bs, br, tap,
                                     )
                                     .await
                                     {
@@ -288,80 +439,89 @@ impl TunnelManager {
             status,
             bytes_sent,
             bytes_received,
+            traffic,
             shutdown_tx,
+            local_addr: None,
         });
 
         Ok(id)
     }
 
-    /// Start a dynamic SOCKS5 port forward (SSH -D equivalent).
-    /// Binds a local SOCKS5 proxy on `local_host:local_port`; each accepted
-    /// connection performs a SOCKS5 handshake and CONNECT request, then is
-    /// tunneled to the requested target through an SSH `direct-tcpip` channel.
+    /// Start a dynamic port forward (SSH -D equivalent). Listens on `bind`
+    /// for SOCKS5, SOCKS4/4a and HTTP proxy clients; each request that passes
+    /// `proxy`'s authentication and destination rules is tunneled to its
+    /// target through an SSH `direct-tcpip` channel.
     pub async fn start_socks_forward(
         &mut self,
         handle: SharedHandle,
-        local_host: String,
-        local_port: u16,
+        bind: LocalBind,
+        proxy: ProxySettings,
     ) -> crate::Result<Uuid> {
-        if !Self::check_port_available(local_port).await {
-            return Err(SshError::PortInUse(local_port));
+        if let Some(error) = proxy.error() {
+            return Err(SshError::Tunnel(error));
         }
+        let proxy = Arc::new(ProxyConfig::new(proxy));
+        let listener = bind.listen().await?;
+        let local_addr = listener.local_addr()?;
 
         let id = Uuid::new_v4();
         let status = Arc::new(ParkingMutex::new(TunnelStatus::Active));
-        let bytes_sent = Arc::new(AtomicU64::new(0));
-        let bytes_received = Arc::new(AtomicU64::new(0));
+        let (bytes_sent, bytes_received) = self.new_counters();
+        let traffic = self.new_traffic();
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
 
         let status_clone = status.clone();
         let bytes_sent_clone = bytes_sent.clone();
         let bytes_received_clone = bytes_received.clone();
+        let traffic_clone = traffic.clone();
 
         tokio::spawn(async move {
-            let bind_addr = format!("{}:{}", local_host, local_port);
-            let listener = match TcpListener::bind(&bind_addr).await {
-                Ok(l) => l,
-                Err(e) => {
-                    tracing::error!("Failed to bind SOCKS5 listener on {}: {}", bind_addr, e);
-                    *status_clone.lock() = TunnelStatus::Error;
-                    return;
-                }
-            };
-
-            tracing::info!("Dynamic forward: SOCKS5 proxy on {}", bind_addr);
+            tracing::info!("Dynamic forward: proxy on {}", local_addr);
 
             let mut connections = tokio::task::JoinSet::new();
             loop {
                 tokio::select! {
                     accept = listener.accept() => {
                         match accept {
+                            Ok((_, addr)) if !bind.allows(addr.ip()) => {
+                                tracing::warn!("Refused proxy connection from {}: not in the allowlist", addr);
+                            }
                             Ok((stream, addr)) => {
-                                tracing::debug!("Accepted SOCKS5 connection from {}", addr);
+                                tracing::debug!("Accepted proxy connection from {}", addr);
                                 let handle = handle.clone();
+                                let proxy = proxy.clone();
                                 let bs = bytes_sent_clone.clone();
                                 let br = bytes_received_clone.clone();
+                                // The target is only known once the client asked for it.
+                                let tap = ConnectionTap::open(
+                                    traffic_clone.clone(),
+                                    addr.to_string(),
+                                    String::new(),
+                                    false,
+                                );
 
                                 connections.spawn(async move {
-                                    if let Err(e) =
-                                        handle_socks_connection(handle, stream, bs, br).await
+                                    if let Err(e) = handle_proxy_connection(
+                                        handle, stream, proxy, local_addr, bs, br, tap,
+                                    )
+                                    .await
                                     {
-                                        tracing::error!("SOCKS5 connection error: {}", e);
+                                        tracing::error!("Proxy connection error: {}", e);
                                     }
                                 });
                             }
                             Err(e) => {
-                                tracing::error!("SOCKS5 accept error: {}", e);
+                                tracing::error!("Proxy accept error: {}", e);
                             }
                         }
                     }
                     result = connections.join_next(), if !connections.is_empty() => {
                         if let Some(Err(e)) = result {
-                            tracing::debug!("SOCKS5 connection task ended: {}", e);
+                            tracing::debug!("Proxy connection task ended: {}", e);
                         }
                     }
                     _ = shutdown_rx.recv() => {
-                        tracing::info!("Stopping SOCKS5 forward on {}", bind_addr);
+                        tracing::info!("Stopping proxy forward on {}", local_addr);
                         break;
                     }
                 }
@@ -377,7 +537,9 @@ impl TunnelManager {
             status,
             bytes_sent,
             bytes_received,
+            traffic,
             shutdown_tx,
+            local_addr: Some(local_addr),
         });
 
         Ok(id)
@@ -417,6 +579,81 @@ impl Default for TunnelManager {
     }
 }
 
+/// One connection's entry in its tunnel's traffic log: counts its bytes,
+/// reads HTTP off it when inspection is on, and closes the record when
+/// the connection is dropped.
+struct ConnectionTap {
+    traffic: SharedTraffic,
+    id: u64,
+    http: Option<ParkingMutex<HttpInspector>>,
+}
+
+impl ConnectionTap {
+    fn open(
+        traffic: SharedTraffic,
+        client: String,
+        target: String,
+        inspect_http: bool,
+    ) -> Arc<Self> {
+        let id = traffic
+            .lock()
+            .open_connection(client, target, chrono::Utc::now());
+        Arc::new(Self {
+            traffic,
+            id,
+            http: inspect_http.then(|| ParkingMutex::new(HttpInspector::new())),
+        })
+    }
+
+    fn set_target(&self, target: String) {
+        self.traffic.lock().set_target(self.id, target);
+    }
+
+    /// `data` went towards the SSH server.
+    fn sent(&self, data: &[u8]) {
+        let messages = self
+            .http
+            .as_ref()
+            .map(|http| http.lock().client_data(data))
+            .unwrap_or_default();
+        self.record(data.len() as u64, 0, messages);
+    }
+
+    /// `data` came back from the SSH server.
+    fn received(&self, data: &[u8]) {
+        let messages = self
+            .http
+            .as_ref()
+            .map(|http| http.lock().server_data(data))
+            .unwrap_or_default();
+        self.record(0, data.len() as u64, messages);
+    }
+
+    fn record(&self, sent: u64, received: u64, messages: Vec<HttpMessage>) {
+        let mut traffic = self.traffic.lock();
+        traffic.add_bytes(self.id, sent, received);
+        for message in messages {
+            traffic.record_http(self.id, message, chrono::Utc::now());
+        }
+    }
+}
+
+impl Drop for ConnectionTap {
+    fn drop(&mut self) {
+        let messages = self
+            .http
+            .as_ref()
+            .map(|http| http.lock().finish())
+            .unwrap_or_default();
+        let now = chrono::Utc::now();
+        let mut traffic = self.traffic.lock();
+        for message in messages {
+            traffic.record_http(self.id, message, now);
+        }
+        traffic.close_connection(self.id, now);
+    }
+}
+
 /// Handle a single forwarded TCP connection for local port forwarding.
 /// Opens a direct-tcpip channel through SSH and performs bidirectional data copy
 /// using the channel's into_stream() for clean AsyncRead/AsyncWrite integration.
@@ -427,6 +664,7 @@ async fn handle_local_forward_connection(
     remote_port: u16,
     bytes_sent: Arc<AtomicU64>,
     bytes_received: Arc<AtomicU64>,
+    tap: Arc<ConnectionTap>,
 ) -> anyhow::Result<()> {
     // Open a direct-tcpip channel to the remote target
     let channel = {
@@ -447,6 +685,7 @@ async fn handle_local_forward_connection(
 
     // TCP -> SSH copy
     let bs = bytes_sent;
+    let tap_sent = tap.clone();
     let tcp_to_ssh = async move {
         let mut buf = vec![0u8; 32768];
         loop {
@@ -454,6 +693,7 @@ async fn handle_local_forward_connection(
                 Ok(0) => break,
                 Ok(n) => {
                     bs.fetch_add(n as u64, Ordering::Relaxed);
+                    tap_sent.sent(&buf[..n]);
                     if ssh_write.write_all(&buf[..n]).await.is_err() {
                         break;
                     }
@@ -465,6 +705,7 @@ async fn handle_local_forward_connection(
 
     // SSH -> TCP copy
     let br = bytes_received;
+    let tap_received = tap;
     let ssh_to_tcp = async move {
         let mut buf = vec![0u8; 32768];
         loop {
@@ -472,6 +713,7 @@ async fn handle_local_forward_connection(
                 Ok(0) => break,
                 Ok(n) => {
                     br.fetch_add(n as u64, Ordering::Relaxed);
+                    tap_received.received(&buf[..n]);
                     if tcp_write.write_all(&buf[..n]).await.is_err() {
                         break;
                     }
@@ -497,6 +739,7 @@ async fn handle_remote_forward_connection(
     local_port: u16,
     bytes_sent: Arc<AtomicU64>,
     bytes_received: Arc<AtomicU64>,
+    tap: Arc<ConnectionTap>,
 ) -> anyhow::Result<()> {
     // Connect to the local target
     let tcp_stream = TcpStream::connect(format!("{}:{}", local_host, local_port))
@@ -523,6 +766,7 @@ async fn handle_remote_forward_connection(
 
     // SSH -> TCP copy (data from remote client to local target)
     let br = bytes_received;
+    let tap_received = tap.clone();
     let ssh_to_tcp = async move {
         let mut buf = vec![0u8; 32768];
         loop {
@@ -530,6 +774,7 @@ async fn handle_remote_forward_connection(
                 Ok(0) => break,
                 Ok(n) => {
                     br.fetch_add(n as u64, Ordering::Relaxed);
+                    tap_received.received(&buf[..n]);
                     if tcp_write.write_all(&buf[..n]).await.is_err() {
                         break;
                     }
@@ -541,6 +786,7 @@ async fn handle_remote_forward_connection(
 
     // TCP -> SSH copy (data from local target back to remote client)
     let bs = bytes_sent;
+    let tap_sent = tap;
     let tcp_to_ssh = async move {
         let mut buf = vec![0u8; 32768];
         loop {
@@ -548,6 +794,7 @@ async fn handle_remote_forward_connection(
                 Ok(0) => break,
                 Ok(n) => {
                     bs.fetch_add(n as u64, Ordering::Relaxed);
+                    tap_sent.sent(&buf[..n]);
                     if ssh_write.write_all(&buf[..n]).await.is_err() {
                         break;
                     }
@@ -561,131 +808,31 @@ async fn handle_remote_forward_connection(
     Ok(())
 }
 
-// SOCKS5 protocol constants (RFC 1928).
-const SOCKS5_VERSION: u8 = 0x05;
-const SOCKS5_AUTH_NONE: u8 = 0x00;
-const SOCKS5_AUTH_NO_ACCEPTABLE: u8 = 0xFF;
-const SOCKS5_CMD_CONNECT: u8 = 0x01;
-const SOCKS5_ATYP_IPV4: u8 = 0x01;
-const SOCKS5_ATYP_DOMAIN: u8 = 0x03;
-const SOCKS5_ATYP_IPV6: u8 = 0x04;
-// Reply codes
-const SOCKS5_REP_SUCCESS: u8 = 0x00;
-const SOCKS5_REP_GENERAL_FAILURE: u8 = 0x01;
-const SOCKS5_REP_HOST_UNREACHABLE: u8 = 0x04;
-const SOCKS5_REP_CMD_NOT_SUPPORTED: u8 = 0x07;
-const SOCKS5_REP_ATYP_NOT_SUPPORTED: u8 = 0x08;
-
-/// Write a SOCKS5 reply with the given reply code and a bound address of
-/// `0.0.0.0:0` (BND.ADDR/BND.PORT are not meaningful for our CONNECT tunnel).
-async fn send_socks_reply<W: AsyncWriteExt + Unpin>(
-    writer: &mut W,
-    reply: u8,
-) -> std::io::Result<()> {
-    // VER, REP, RSV, ATYP(IPv4), BND.ADDR(0.0.0.0), BND.PORT(0)
-    let resp = [
-        SOCKS5_VERSION,
-        reply,
-        0x00,
-        SOCKS5_ATYP_IPV4,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
-    ];
-    writer.write_all(&resp).await?;
-    writer.flush().await
-}
-
-/// Handle a single SOCKS5 client connection: perform method negotiation and the
-/// CONNECT request, open an SSH `direct-tcpip` channel to the requested target,
-/// and pump bytes bidirectionally (mirroring `handle_local_forward_connection`).
-async fn handle_socks_connection(
+/// Handle a single client of a dynamic forward: read its proxy request,
+/// open an SSH `direct-tcpip` channel to the requested target, and pump
+/// bytes bidirectionally (mirroring `handle_local_forward_connection`).
+async fn handle_proxy_connection(
     handle: SharedHandle,
     mut tcp_stream: TcpStream,
+    proxy: Arc<ProxyConfig>,
+    local_addr: SocketAddr,
     bytes_sent: Arc<AtomicU64>,
     bytes_received: Arc<AtomicU64>,
+    tap: Arc<ConnectionTap>,
 ) -> anyhow::Result<()> {
-    // --- Method negotiation ---
-    // Client greeting: VER, NMETHODS, METHODS...
-    let mut head = [0u8; 2];
-    tcp_stream.read_exact(&mut head).await?;
-    if head[0] != SOCKS5_VERSION {
-        anyhow::bail!("Unsupported SOCKS version: {}", head[0]);
-    }
-    let nmethods = head[1] as usize;
-    let mut methods = vec![0u8; nmethods];
-    tcp_stream.read_exact(&mut methods).await?;
-
-    if !methods.contains(&SOCKS5_AUTH_NONE) {
-        // No acceptable methods.
-        tcp_stream
-            .write_all(&[SOCKS5_VERSION, SOCKS5_AUTH_NO_ACCEPTABLE])
-            .await?;
-        anyhow::bail!("Client offered no supported SOCKS5 auth method");
-    }
-    // Select "no authentication required".
-    tcp_stream
-        .write_all(&[SOCKS5_VERSION, SOCKS5_AUTH_NONE])
-        .await?;
-
-    // --- Request ---
-    // VER, CMD, RSV, ATYP
-    let mut req = [0u8; 4];
-    tcp_stream.read_exact(&mut req).await?;
-    if req[0] != SOCKS5_VERSION {
-        send_socks_reply(&mut tcp_stream, SOCKS5_REP_GENERAL_FAILURE).await?;
-        anyhow::bail!("Unsupported SOCKS version in request: {}", req[0]);
-    }
-    let cmd = req[1];
-    let atyp = req[3];
-
-    if cmd != SOCKS5_CMD_CONNECT {
-        // BIND (0x02) and UDP ASSOCIATE (0x03) are not supported.
-        send_socks_reply(&mut tcp_stream, SOCKS5_REP_CMD_NOT_SUPPORTED).await?;
-        anyhow::bail!("Unsupported SOCKS5 command: {}", cmd);
-    }
-
-    // Parse the target address.
-    let target_host = match atyp {
-        SOCKS5_ATYP_IPV4 => {
-            let mut addr = [0u8; 4];
-            tcp_stream.read_exact(&mut addr).await?;
-            std::net::Ipv4Addr::from(addr).to_string()
-        }
-        SOCKS5_ATYP_IPV6 => {
-            let mut addr = [0u8; 16];
-            tcp_stream.read_exact(&mut addr).await?;
-            std::net::Ipv6Addr::from(addr).to_string()
-        }
-        SOCKS5_ATYP_DOMAIN => {
-            let mut len = [0u8; 1];
-            tcp_stream.read_exact(&mut len).await?;
-            let mut domain = vec![0u8; len[0] as usize];
-            tcp_stream.read_exact(&mut domain).await?;
-            String::from_utf8(domain)
-                .map_err(|e| anyhow::anyhow!("Invalid SOCKS5 domain name: {}", e))?
-        }
-        other => {
-            send_socks_reply(&mut tcp_stream, SOCKS5_REP_ATYP_NOT_SUPPORTED).await?;
-            anyhow::bail!("Unsupported SOCKS5 address type: {}", other);
-        }
+    let Some(request) = proxy::read_request(&mut tcp_stream, &proxy, local_addr).await? else {
+        return Ok(());
     };
-
-    let mut port_buf = [0u8; 2];
-    tcp_stream.read_exact(&mut port_buf).await?;
-    let target_port = u16::from_be_bytes(port_buf);
-
-    tracing::debug!("SOCKS5 CONNECT -> {}:{}", target_host, target_port);
+    let kind = request.kind;
+    tracing::debug!("{} -> {}:{}", kind.name(), request.host, request.port);
+    tap.set_target(format_addr(&request.host, request.port));
 
     // Open a direct-tcpip channel to the requested target through SSH.
     let channel = {
         let h = handle.lock().await;
         h.channel_open_direct_tcpip(
-            target_host.clone(),
-            target_port as u32,
+            request.host.clone(),
+            request.port as u32,
             "127.0.0.1", // originator address
             0,           // originator port
         )
@@ -696,33 +843,44 @@ async fn handle_socks_connection(
         Ok(c) => c,
         Err(e) => {
             tracing::error!(
-                "SOCKS5: failed to open channel to {}:{}: {}",
-                target_host,
-                target_port,
+                "{}: failed to open channel to {}:{}: {}",
+                kind.name(),
+                request.host,
+                request.port,
                 e
             );
-            send_socks_reply(&mut tcp_stream, SOCKS5_REP_HOST_UNREACHABLE).await?;
+            kind.reply(&mut tcp_stream, false).await?;
             return Ok(());
         }
     };
 
     // Tell the client the connection succeeded.
-    send_socks_reply(&mut tcp_stream, SOCKS5_REP_SUCCESS).await?;
+    kind.reply(&mut tcp_stream, true).await?;
 
     // Convert SSH channel into an AsyncRead + AsyncWrite stream and pump bytes.
     let ssh_stream = channel.into_stream();
     let (mut ssh_read, mut ssh_write) = tokio::io::split(ssh_stream);
     let (mut tcp_read, mut tcp_write) = tokio::io::split(tcp_stream);
 
-    // TCP -> SSH copy
+    // TCP -> SSH copy, starting with what the client sent along with its request
     let bs = bytes_sent;
+    let tap_sent = tap.clone();
+    let early_data = request.early_data;
     let tcp_to_ssh = async move {
+        if !early_data.is_empty() {
+            bs.fetch_add(early_data.len() as u64, Ordering::Relaxed);
+            tap_sent.sent(&early_data);
+            if ssh_write.write_all(&early_data).await.is_err() {
+                return;
+            }
+        }
         let mut buf = vec![0u8; 32768];
         loop {
             match tcp_read.read(&mut buf).await {
                 Ok(0) => break,
                 Ok(n) => {
                     bs.fetch_add(n as u64, Ordering::Relaxed);
+                    tap_sent.sent(&buf[..n]);
                     if ssh_write.write_all(&buf[..n]).await.is_err() {
                         break;
                     }
@@ -734,6 +892,7 @@ async fn handle_socks_connection(
 
     // SSH -> TCP copy
     let br = bytes_received;
+    let tap_received = tap;
     let ssh_to_tcp = async move {
         let mut buf = vec![0u8; 32768];
         loop {
@@ -741,6 +900,7 @@ async fn handle_socks_connection(
                 Ok(0) => break,
                 Ok(n) => {
                     br.fetch_add(n as u64, Ordering::Relaxed);
+                    tap_received.received(&buf[..n]);
                     if tcp_write.write_all(&buf[..n]).await.is_err() {
                         break;
                     }
@@ -748,6 +908,11 @@ async fn handle_socks_connection(
                 Err(_) => break,
             }
         }
+        // A forwarded HTTP request is the only one on its connection: let
+        // the client see the end of the answer.
+        if kind == RequestKind::HttpForward {
+            let _ = tcp_write.shutdown().await;
+        }
     };
 
     let _ = tokio::join!(tcp_to_ssh, ssh_to_tcp);
@@ -756,12 +921,13 @@ async fn handle_socks_connection(
 
 #[cfg(test)]
 mod tests {
-    use super::{TunnelManager, TunnelStatus};
+    use super::{LocalBind, TunnelManager, TunnelStatus};
     use crate::handler::{ClientHandler, ForwardedTcpIpEvent};
     use crate::session::SharedHandle;
     use russh::keys::{ssh_key::Algorithm, PrivateKey};
     use russh::server::{self, Auth, Msg, Session};
     use russh::Channel;
+    use shelldeck_core::models::forward_proxy::ProxySettings;
     use std::sync::Arc;
     use std::time::Duration;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
@@ -1012,6 +1178,53 @@ mod tests {
         assert_eq!(response, [0x05, 0x00]);
     }
 
+    fn proxy_login() -> ProxySettings {
+        ProxySettings {
+            username: Some("alice".to_owned()),
+            password: Some("s3cret".to_owned()),
+            ..Default::default()
+        }
+    }
+
+    fn socks5_connect_request(host: &str, port: u16) -> Vec<u8> {
+        let mut connect = vec![0x05, 0x01, 0x00, 0x03, host.len() as u8];
+        connect.extend_from_slice(host.as_bytes());
+        connect.extend_from_slice(&port.to_be_bytes());
+        connect
+    }
+
+    fn socks4a_connect_request(host: &str, port: u16) -> Vec<u8> {
+        let mut connect = vec![0x04, 0x01];
+        connect.extend_from_slice(&port.to_be_bytes());
+        connect.extend_from_slice(&[0, 0, 0, 1]);
+        connect.extend_from_slice(b"user\0");
+        connect.extend_from_slice(host.as_bytes());
+        connect.push(0);
+        connect
+    }
+
+    async fn read_http_head(stream: &mut TcpStream) -> String {
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut byte = [0_u8; 1];
+            timeout(Duration::from_secs(2), stream.read_exact(&mut byte))
+                .await
+                .expect("HTTP head timed out")
+                .expect("read HTTP head");
+            head.push(byte[0]);
+        }
+        String::from_utf8(head).expect("HTTP head is UTF-8")
+    }
+
+    async fn read_until_closed(stream: &mut TcpStream) -> String {
+        let mut data = Vec::new();
+        timeout(Duration::from_secs(2), stream.read_to_end(&mut data))
+            .await
+            .expect("proxy did not close the connection")
+            .expect("read until closed");
+        String::from_utf8(data).expect("response is UTF-8")
+    }
+
     // SDTEST-562, SDTEST-564, SDTEST-568, SDTEST-569
     #[tokio::test]
     async fn local_forward_echoes_tracks_bytes_and_drains_on_stop() {
@@ -1019,7 +1232,13 @@ mod tests {
         let local_port = unused_local_port().await;
         let mut manager = TunnelManager::new();
         let id = manager
-            .start_local_forward(handle, local_port, "echo.internal".to_owned(), 4242)
+            .start_local_forward(
+                handle,
+                LocalBind::loopback(local_port),
+                "echo.internal".to_owned(),
+                4242,
+                false,
+            )
             .await
             .expect("start local forward");
 
@@ -1161,7 +1380,7 @@ mod tests {
         let local_port = unused_local_port().await;
         let mut manager = TunnelManager::new();
         let id = manager
-            .start_socks_forward(handle, "127.0.0.1".to_owned(), local_port)
+            .start_socks_forward(handle, LocalBind::loopback(local_port), Default::default())
             .await
             .expect("start SOCKS5 forward");
 
@@ -1237,6 +1456,282 @@ mod tests {
         server_task.abort();
     }
 
+    #[tokio::test]
+    async fn socks5_password_auth_and_socks4a_connect() {
+        let (handle, mut requests, server_task) = start_echo_server().await;
+        let mut manager = TunnelManager::new();
+        let no_password = ProxySettings {
+            password: None,
+            ..proxy_login()
+        };
+        assert!(manager
+            .start_socks_forward(handle.clone(), LocalBind::loopback(0), no_password)
+            .await
+            .is_err());
+
+        let auth_port = unused_local_port().await;
+        manager
+            .start_socks_forward(
+                handle.clone(),
+                LocalBind::loopback(auth_port),
+                proxy_login(),
+            )
+            .await
+            .expect("start authenticated proxy");
+
+        let mut anonymous = connect_when_ready(auth_port).await;
+        anonymous
+            .write_all(&[0x05, 0x01, 0x00])
+            .await
+            .expect("write SOCKS5 greeting");
+        let mut response = [0_u8; 2];
+        anonymous
+            .read_exact(&mut response)
+            .await
+            .expect("read SOCKS5 greeting response");
+        assert_eq!(response, [0x05, 0xFF]);
+
+        for (password, status) in [("wrong", 0x01_u8), ("s3cret", 0x00)] {
+            let mut client = connect_when_ready(auth_port).await;
+            client
+                .write_all(&[0x05, 0x02, 0x00, 0x02])
+                .await
+                .expect("write SOCKS5 greeting");
+            client
+                .read_exact(&mut response)
+                .await
+                .expect("read SOCKS5 method");
+            assert_eq!(response, [0x05, 0x02]);
+            let mut login = vec![0x01, 5];
+            login.extend_from_slice(b"alice");
+            login.push(password.len() as u8);
+            login.extend_from_slice(password.as_bytes());
+            client.write_all(&login).await.expect("write SOCKS5 login");
+            client
+                .read_exact(&mut response)
+                .await
+                .expect("read SOCKS5 login status");
+            assert_eq!(response, [0x01, status], "password {password}");
+            if status != 0x00 {
+                continue;
+            }
+
+            client
+                .write_all(&socks5_connect_request("echo.internal", 4242))
+                .await
+                .expect("write SOCKS5 CONNECT");
+            let mut reply = [0_u8; 10];
+            client
+                .read_exact(&mut reply)
+                .await
+                .expect("read SOCKS5 CONNECT reply");
+            assert_eq!(reply[1], 0x00);
+            client.write_all(b"authed").await.expect("write echo");
+            let mut echoed = [0_u8; 6];
+            client.read_exact(&mut echoed).await.expect("read echo");
+            assert_eq!(&echoed, b"authed");
+        }
+
+        // SOCKS4 cannot carry a password.
+        let mut socks4 = connect_when_ready(auth_port).await;
+        socks4
+            .write_all(&socks4a_connect_request("echo.internal", 4242))
+            .await
+            .expect("write SOCKS4a CONNECT");
+        let mut reply = [0_u8; 8];
+        socks4
+            .read_exact(&mut reply)
+            .await
+            .expect("read SOCKS4 reply");
+        assert_eq!(reply[1], 0x5B);
+
+        let open_port = unused_local_port().await;
+        manager
+            .start_socks_forward(handle, LocalBind::loopback(open_port), Default::default())
+            .await
+            .expect("start open proxy");
+        let mut socks4 = connect_when_ready(open_port).await;
+        socks4
+            .write_all(&socks4a_connect_request("echo.internal", 4242))
+            .await
+            .expect("write SOCKS4a CONNECT");
+        socks4
+            .read_exact(&mut reply)
+            .await
+            .expect("read SOCKS4 reply");
+        assert_eq!(reply[..2], [0x00, 0x5A]);
+        socks4.write_all(b"4a").await.expect("write echo");
+        let mut echoed = [0_u8; 2];
+        socks4.read_exact(&mut echoed).await.expect("read echo");
+        assert_eq!(&echoed, b"4a");
+
+        for _ in 0..2 {
+            assert_eq!(
+                timeout(Duration::from_secs(2), requests.recv())
+                    .await
+                    .expect("direct-tcpip request timed out")
+                    .expect("direct-tcpip request channel closed"),
+                DirectTcpIpRequest {
+                    host: "echo.internal".to_owned(),
+                    port: 4242,
+                }
+            );
+        }
+        assert!(requests.try_recv().is_err());
+        manager.stop_all();
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn http_proxy_requires_basic_auth_for_connect_and_forwarded_requests() {
+        let (handle, mut requests, server_task) = start_echo_server().await;
+        let local_port = unused_local_port().await;
+        let mut manager = TunnelManager::new();
+        manager
+            .start_socks_forward(handle, LocalBind::loopback(local_port), proxy_login())
+            .await
+            .expect("start HTTP proxy");
+        // "alice:s3cret"
+        let credentials = "Proxy-Authorization: Basic YWxpY2U6czNjcmV0\r\n";
+
+        let mut anonymous = connect_when_ready(local_port).await;
+        anonymous
+            .write_all(b"CONNECT echo.internal:443 HTTP/1.1\r\nHost: echo.internal:443\r\n\r\n")
+            .await
+            .expect("write CONNECT");
+        let refused = read_until_closed(&mut anonymous).await;
+        assert!(refused.starts_with("HTTP/1.1 407 "), "{refused}");
+        assert!(refused.contains("Proxy-Authenticate: Basic"));
+
+        let mut tunnel = connect_when_ready(local_port).await;
+        tunnel
+            .write_all(
+                format!("CONNECT echo.internal:443 HTTP/1.1\r\n{credentials}\r\n").as_bytes(),
+            )
+            .await
+            .expect("write CONNECT");
+        assert_eq!(
+            read_http_head(&mut tunnel).await,
+            "HTTP/1.1 200 Connection established\r\n\r\n"
+        );
+        tunnel.write_all(b"hello").await.expect("write echo");
+        let mut echoed = [0_u8; 5];
+        tunnel.read_exact(&mut echoed).await.expect("read echo");
+        assert_eq!(&echoed, b"hello");
+
+        let mut forwarded = connect_when_ready(local_port).await;
+        forwarded
+            .write_all(
+                format!(
+                    "GET http://echo.internal:4242/status?full=1 HTTP/1.1\r\nHost: echo.internal:4242\r\n{credentials}Proxy-Connection: keep-alive\r\nAccept: */*\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await
+            .expect("write forwarded GET");
+        // The echo target sends the request back as it received it.
+        assert_eq!(
+            read_http_head(&mut forwarded).await,
+            "GET /status?full=1 HTTP/1.1\r\nHost: echo.internal:4242\r\nAccept: */*\r\nConnection: close\r\n\r\n"
+        );
+
+        let mut targets = Vec::new();
+        for _ in 0..2 {
+            let request = timeout(Duration::from_secs(2), requests.recv())
+                .await
+                .expect("direct-tcpip request timed out")
+                .expect("direct-tcpip request channel closed");
+            targets.push((request.host, request.port));
+        }
+        assert_eq!(
+            targets,
+            vec![
+                ("echo.internal".to_owned(), 443),
+                ("echo.internal".to_owned(), 4242)
+            ]
+        );
+        manager.stop_all();
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn destination_rules_refuse_targets_and_pac_lists_them() {
+        let (handle, mut requests, server_task) = start_echo_server().await;
+        let local_port = unused_local_port().await;
+        let mut manager = TunnelManager::new();
+        let settings = ProxySettings {
+            rules: vec![
+                "deny secret.corp.example".to_owned(),
+                "allow *.corp.example".to_owned(),
+            ],
+            serve_pac: true,
+            ..Default::default()
+        };
+        manager
+            .start_socks_forward(handle, LocalBind::loopback(local_port), settings)
+            .await
+            .expect("start proxy with rules");
+
+        let mut socks = connect_when_ready(local_port).await;
+        negotiate_no_auth(&mut socks).await;
+        socks
+            .write_all(&socks5_connect_request("echo.internal", 4242))
+            .await
+            .expect("write SOCKS5 CONNECT");
+        let mut reply = [0_u8; 10];
+        socks
+            .read_exact(&mut reply)
+            .await
+            .expect("read SOCKS5 CONNECT reply");
+        assert_eq!(reply[1], 0x02, "not allowed by ruleset");
+
+        let mut denied = connect_when_ready(local_port).await;
+        denied
+            .write_all(b"CONNECT secret.corp.example:443 HTTP/1.1\r\n\r\n")
+            .await
+            .expect("write CONNECT");
+        assert!(read_until_closed(&mut denied)
+            .await
+            .starts_with("HTTP/1.1 403 "));
+
+        let mut allowed = connect_when_ready(local_port).await;
+        allowed
+            .write_all(b"CONNECT git.corp.example:443 HTTP/1.1\r\n\r\n")
+            .await
+            .expect("write CONNECT");
+        assert!(read_http_head(&mut allowed)
+            .await
+            .starts_with("HTTP/1.1 200 "));
+
+        let mut pac = connect_when_ready(local_port).await;
+        pac.write_all(
+            format!("GET /proxy.pac HTTP/1.1\r\nHost: 127.0.0.1:{local_port}\r\n\r\n").as_bytes(),
+        )
+        .await
+        .expect("write PAC request");
+        let pac = read_until_closed(&mut pac).await;
+        assert!(pac.starts_with("HTTP/1.1 200 OK\r\n"), "{pac}");
+        assert!(pac.contains("Content-Type: application/x-ns-proxy-autoconfig"));
+        assert!(pac.contains(&format!(
+            "if (shExpMatch(host, \"*.corp.example\")) return \"PROXY 127.0.0.1:{local_port}\";"
+        )));
+        assert!(pac.ends_with("  return \"DIRECT\";\n}\n"));
+
+        assert_eq!(
+            timeout(Duration::from_secs(2), requests.recv())
+                .await
+                .expect("direct-tcpip request timed out")
+                .expect("direct-tcpip request channel closed"),
+            DirectTcpIpRequest {
+                host: "git.corp.example".to_owned(),
+                port: 443,
+            }
+        );
+        assert!(requests.try_recv().is_err());
+        manager.stop_all();
+        server_task.abort();
+    }
+
     // SDTEST-561, SDTEST-563
     #[tokio::test]
     async fn port_availability_and_prebound_start_failure_are_reported() {
@@ -1244,15 +1739,21 @@ mod tests {
             .await
             .expect("bind occupied test port");
         let occupied_port = listener.local_addr().expect("occupied address").port();
-        assert!(!TunnelManager::check_port_available(occupied_port).await);
+        assert!(!TunnelManager::check_port_available("127.0.0.1", occupied_port).await);
 
         let free_port = unused_local_port().await;
-        assert!(TunnelManager::check_port_available(free_port).await);
+        assert!(TunnelManager::check_port_available("127.0.0.1", free_port).await);
 
         let (handle, _requests, server_task) = start_echo_server().await;
         let mut manager = TunnelManager::new();
         let error = manager
-            .start_local_forward(handle, occupied_port, "echo.internal".to_owned(), 4242)
+            .start_local_forward(
+                handle,
+                LocalBind::loopback(occupied_port),
+                "echo.internal".to_owned(),
+                4242,
+                false,
+            )
             .await
             .expect_err("prebound local port must fail");
         assert!(matches!(error, crate::SshError::PortInUse(port) if port == occupied_port));
@@ -1261,6 +1762,96 @@ mod tests {
         server_task.abort();
     }
 
+    #[tokio::test]
+    async fn local_forward_records_connections_and_inspected_requests() {
+        let (handle, _requests, server_task) = start_echo_server().await;
+        let local_port = unused_local_port().await;
+        let mut manager = TunnelManager::new();
+        let id = manager
+            .start_local_forward(
+                handle,
+                LocalBind::loopback(local_port),
+                "echo.internal".to_owned(),
+                4242,
+                true,
+            )
+            .await
+            .expect("start inspected forward");
+        let traffic = manager
+            .get_tunnel(&id)
+            .expect("tunnel handle")
+            .traffic
+            .clone();
+
+        let request = b"GET /hook?x=1 HTTP/1.1\r\nHost: echo\r\n\r\n";
+        let mut client = connect_when_ready(local_port).await;
+        client.write_all(request).await.expect("write tunnel");
+        let mut echoed = vec![0_u8; request.len()];
+        client.read_exact(&mut echoed).await.expect("read tunnel");
+
+        {
+            let log = traffic.lock();
+            let record = &log.connections()[0];
+            assert!(record.is_open());
+            assert_eq!(record.target, "echo.internal:4242");
+            assert_eq!(record.bytes_sent, request.len() as u64);
+            assert_eq!(record.bytes_received, request.len() as u64);
+            let exchange = &log.exchanges()[0];
+            assert_eq!(
+                (exchange.method.as_str(), exchange.path.as_str()),
+                ("GET", "/hook?x=1")
+            );
+            // The echo is not an HTTP response, so it stays unanswered.
+            assert!(exchange.response.is_none());
+        }
+
+        manager.stop_all();
+        wait_until_stopped(&manager, id).await;
+        assert_eq!(traffic.lock().open_connections(), 0);
+        server_task.abort();
+    }
+
+    #[tokio::test]
+    async fn taken_port_falls_back_to_a_free_one_when_allowed() {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind occupied test port");
+        let occupied_port = listener.local_addr().expect("occupied address").port();
+
+        let (handle, _requests, server_task) = start_echo_server().await;
+        let mut manager = TunnelManager::new();
+        let mut bind = LocalBind::loopback(occupied_port);
+        bind.pick_free_port = true;
+        manager
+            .start_local_forward(handle, bind, "echo.internal".to_owned(), 4242, false)
+            .await
+            .expect("start on a free port");
+        let bound = manager.tunnels()[0].local_addr.expect("bound address");
+        assert_ne!(bound.port(), occupied_port);
+
+        let mut client = connect_when_ready(bound.port()).await;
+        client.write_all(b"ok").await.expect("write tunnel");
+        let mut echoed = [0_u8; 2];
+        client.read_exact(&mut echoed).await.expect("read tunnel");
+        assert_eq!(&echoed, b"ok");
+
+        manager.stop_all();
+        server_task.abort();
+    }
+
+    #[test]
+    fn allowlist_admits_listed_networks_and_this_machine() {
+        let mut bind = LocalBind::loopback(8080);
+        assert!(bind.allows("192.168.1.20".parse().unwrap()), "no allowlist");
+
+        bind.allowed_clients = vec!["10.0.0.0/8".parse().unwrap()];
+        assert!(bind.allows("10.2.3.4".parse().unwrap()));
+        assert!(bind.allows("::ffff:10.2.3.4".parse().unwrap()));
+        assert!(!bind.allows("192.168.1.20".parse().unwrap()));
+        assert!(bind.allows("127.0.0.1".parse().unwrap()));
+        assert!(bind.allows("::1".parse().unwrap()));
+    }
+
     // SDTEST-567
     #[tokio::test]
     async fn stop_all_closes_every_listener_and_active_connection() {
@@ -1271,14 +1862,21 @@ mod tests {
         let first_id = manager
             .start_local_forward(
                 handle.clone(),
-                first_port,
+                LocalBind::loopback(first_port),
                 "first.internal".to_owned(),
                 1001,
+                false,
             )
             .await
             .expect("start first tunnel");
         let second_id = manager
-            .start_local_forward(handle, second_port, "second.internal".to_owned(), 1002)
+            .start_local_forward(
+                handle,
+                LocalBind::loopback(second_port),
+                "second.internal".to_owned(),
+                1002,
+                false,
+            )
             .await
             .expect("start second tunnel");
         let mut first = connect_when_ready(first_port).await;
@@ -1318,4 +1916,42 @@ mod tests {
         assert!(manager.tunnels().is_empty());
         server_task.abort();
     }
+
+    #[tokio::test]
+    async fn shared_counters_carry_over_to_a_rebuilt_tunnel() {
+        let sent = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let received = Arc::new(std::sync::atomic::AtomicU64::new(0));
+
+        for expected in [4, 8] {
+            let (handle, _requests, server_task) = start_echo_server().await;
+            let local_port = unused_local_port().await;
+            let mut manager = TunnelManager::with_counters(sent.clone(), received.clone());
+            let id = manager
+                .start_local_forward(
+                    handle,
+                    LocalBind::loopback(local_port),
+                    "echo.internal".to_owned(),
+                    4242,
+                    false,
+                )
+                .await
+                .expect("start local forward");
+
+            let mut client = connect_when_ready(local_port).await;
+            client.write_all(b"ping").await.expect("write tunnel");
+            let mut echoed = [0_u8; 4];
+            client.read_exact(&mut echoed).await.expect("read echo");
+            assert_eq!(
+                manager
+                    .get_tunnel(&id)
+                    .expect("tunnel handle")
+                    .total_bytes(),
+                (expected, expected)
+            );
+
+            manager.stop_all();
+            wait_until_stopped(&manager, id).await;
+            server_task.abort();
+        }
+    }
 }
@@ -0,0 +1,855 @@
+//! Keyboard copy mode: vi-style navigation and selection over the scrollback.
+//!
+//! Copy mode keeps its own cursor, independent of the shell cursor, in
+//! *history* coordinates (see [`TerminalGrid::history_row`]) so it can walk
+//! the whole scrollback while the view follows it. Visual selections are
+//! mirrored into the grid's regular [`SelectionState`] so the renderer paints
+//! them exactly like a mouse selection; yanking reads the text straight from
+//! history, so selections taller than the screen copy in full.
+//!
+//! The UI layer feeds key names through [`CopyMode::handle_key`] and acts on
+//! the returned [`CopyModeAction`] (clipboard, search prompt, exit).
+
+use crate::grid::{CellWidth, GridPos, SelectionKind, SelectionState, TerminalGrid};
+
+/// Visual selection flavour, mirroring vi's `v`, `V` and `Ctrl-v`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VisualMode {
+    Char,
+    Line,
+    Block,
+}
+
+impl VisualMode {
+    fn selection_kind(self) -> SelectionKind {
+        match self {
+            VisualMode::Char => SelectionKind::Simple,
+            VisualMode::Line => SelectionKind::Line,
+            VisualMode::Block => SelectionKind::Block,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchDirection {
+    Forward,
+    Backward,
+}
+
+impl SearchDirection {
+    fn reversed(self) -> Self {
+        match self {
+            SearchDirection::Forward => SearchDirection::Backward,
+            SearchDirection::Backward => SearchDirection::Forward,
+        }
+    }
+}
+
+/// Cursor motions understood by copy mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `w` — start of the next word.
+    WordForward,
+    /// `b` — start of the previous word.
+    WordBackward,
+    /// `e` — end of the current/next word.
+    WordEnd,
+    /// `0`
+    LineStart,
+    /// `^`
+    FirstNonBlank,
+    /// `$`
+    LineEnd,
+    /// `gg`
+    Top,
+    /// `G`
+    Bottom,
+    /// `H`
+    ScreenTop,
+    /// `M`
+    ScreenMiddle,
+    /// `L`
+    ScreenBottom,
+    /// `Ctrl-u`
+    HalfPageUp,
+    /// `Ctrl-d`
+    HalfPageDown,
+    /// `[` — previous OSC 133 prompt.
+    PrevPrompt,
+    /// `]` — next OSC 133 prompt.
+    NextPrompt,
+}
+
+/// What the view should do after a key was handled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopyModeAction {
+    /// The key was consumed (or ignored); repaint.
+    None,
+    /// Put this text on the clipboard and leave copy mode.
+    Yank(String),
+    /// Leave copy mode.
+    Exit,
+    /// Open the search prompt; submit it with [`CopyMode::search`].
+    Search(SearchDirection),
+}
+
+/// State of an active copy-mode session over one grid.
+#[derive(Debug, Clone)]
+pub struct CopyMode {
+    /// Cursor in history coordinates.
+    cursor: GridPos,
+    /// Active visual selection and its anchor (history coordinates).
+    visual: Option<(VisualMode, GridPos)>,
+    /// Pending numeric prefix (`5j`).
+    count: Option<usize>,
+    /// `g` was pressed and waits for its second key.
+    pending_g: bool,
+    last_search: Option<(String, SearchDirection)>,
+    /// [`TerminalGrid::history_dropped`] when the cursor was last placed.
+    history_dropped: u64,
+}
+
+impl CopyMode {
+    /// Enter copy mode. The cursor starts on the shell cursor when the view
+    /// is live, or on the bottom row of the view when scrolled back.
+    pub fn new(grid: &mut TerminalGrid) -> Self {
+        let cursor = if grid.is_at_bottom() {
+            GridPos::new(
+                grid.cursor.col.min(grid.cols.saturating_sub(1)),
+                grid.scrollback_len() + grid.cursor.row,
+            )
+        } else {
+            GridPos::new(0, grid.display_to_history(grid.rows.saturating_sub(1)))
+        };
+        grid.clear_selection();
+        let mode = Self {
+            cursor,
+            visual: None,
+            count: None,
+            pending_g: false,
+            last_search: None,
+            history_dropped: grid.history_dropped(),
+        };
+        grid.reveal_history_row(mode.cursor.row);
+        mode
+    }
+
+    /// Leave copy mode: drop the selection and return the view to the live screen.
+    pub fn exit(self, grid: &mut TerminalGrid) {
+        grid.clear_selection();
+        grid.scroll_view_to_bottom();
+    }
+
+    /// Cursor position in history coordinates.
+    pub fn cursor(&self) -> GridPos {
+        self.cursor
+    }
+
+    /// Cursor position in display coordinates, if it is in view.
+    pub fn display_cursor(&self, grid: &TerminalGrid) -> Option<GridPos> {
+        let row = self.cursor.row.checked_sub(self.rows_dropped(grid))?;
+        grid.history_to_display(row)
+            .map(|row| GridPos::new(self.cursor.col, row))
+    }
+
+    /// History rows dropped off the top since the cursor was last placed.
+    fn rows_dropped(&self, grid: &TerminalGrid) -> usize {
+        grid.history_dropped().saturating_sub(self.history_dropped) as usize
+    }
+
+    /// Move the cursor and the visual anchor up with their rows when output
+    /// pushed history off the top of the scrollback.
+    fn follow_history(&mut self, grid: &TerminalGrid) {
+        let dropped = self.rows_dropped(grid);
+        self.history_dropped = grid.history_dropped();
+        let last_line = grid.history_len().saturating_sub(1);
+        let shift = |pos: &mut GridPos| pos.row = pos.row.saturating_sub(dropped).min(last_line);
+        shift(&mut self.cursor);
+        if let Some((_, anchor)) = &mut self.visual {
+            shift(anchor);
+        }
+    }
+
+    pub fn visual_mode(&self) -> Option<VisualMode> {
+        self.visual.map(|(mode, _)| mode)
+    }
+
+    /// Handle one key. `key` is the produced character when there is one
+    /// (`"G"`, `"$"`, `"/"`) or the key name otherwise (`"escape"`, `"up"`).
+    pub fn handle_key(&mut self, grid: &mut TerminalGrid, key: &str, ctrl: bool) -> CopyModeAction {
+        self.follow_history(grid);
+        if ctrl {
+            self.pending_g = false;
+            let action = match key {
+                "u" => self.apply_motion(grid, Motion::HalfPageUp),
+                "d" => self.apply_motion(grid, Motion::HalfPageDown),
+                "v" => self.toggle_visual(grid, VisualMode::Block),
+                "c" => CopyModeAction::Exit,
+                _ => CopyModeAction::None,
+            };
+            self.count = None;
+            return action;
+        }
+
+        if self.pending_g {
+            self.pending_g = false;
+            let action = if key == "g" {
+                self.apply_motion(grid, Motion::Top)
+            } else {
+                CopyModeAction::None
+            };
+            self.count = None;
+            return action;
+        }
+
+        // Numeric prefix; a leading `0` is the line-start motion.
+        if let Some(digit) = key.chars().next().and_then(|c| c.to_digit(10)) {
+            if key.len() == 1 && (digit != 0 || self.count.is_some()) {
+                let count = self.count.unwrap_or(0).saturating_mul(10) + digit as usize;
+                self.count = Some(count.min(100_000));
+                return CopyModeAction::None;
+            }
+        }
+
+        let motion = match key {
+            "h" | "left" => Some(Motion::Left),
+            "j" | "down" => Some(Motion::Down),
+            "k" | "up" => Some(Motion::Up),
+            "l" | "right" => Some(Motion::Right),
+            "w" => Some(Motion::WordForward),
+            "b" => Some(Motion::WordBackward),
+            "e" => Some(Motion::WordEnd),
+            "0" | "home" => Some(Motion::LineStart),
+            "^" => Some(Motion::FirstNonBlank),
+            "$" | "end" => Some(Motion::LineEnd),
+            "G" => Some(Motion::Bottom),
+            "H" => Some(Motion::ScreenTop),
+            "M" => Some(Motion::ScreenMiddle),
+            "L" => Some(Motion::ScreenBottom),
+            "[" => Some(Motion::PrevPrompt),
+            "]" => Some(Motion::NextPrompt),
+            "pageup" => Some(Motion::HalfPageUp),
+            "pagedown" => Some(Motion::HalfPageDown),
+            _ => None,
+        };
+        if let Some(motion) = motion {
+            return self.apply_motion(grid, motion);
+        }
+
+        if key == "g" {
+            // Keep the count for `5gg`.
+            self.pending_g = true;
+            return CopyModeAction::None;
+        }
+
+        let count = self.count.take().unwrap_or(1);
+        match key {
+            "v" => self.toggle_visual(grid, VisualMode::Char),
+            "V" => self.toggle_visual(grid, VisualMode::Line),
+            "y" | "enter" => match self.yank_text(grid) {
+                Some(text) => CopyModeAction::Yank(text),
+                None => CopyModeAction::None,
+            },
+            "Y" => {
+                let line = grid.history_text(
+                    GridPos::new(0, self.cursor.row),
+                    GridPos::new(grid.cols.saturating_sub(1), self.cursor.row),
+                    SelectionKind::Line,
+                );
+                CopyModeAction::Yank(line)
+            }
+            "/" => CopyModeAction::Search(SearchDirection::Forward),
+            "?" => CopyModeAction::Search(SearchDirection::Backward),
+            "n" | "N" => {
+                if let Some((query, direction)) = self.last_search.clone() {
+                    let direction = if key == "N" {
+                        direction.reversed()
+                    } else {
+                        direction
+                    };
+                    for _ in 0..count {
+                        if !self.jump_to_match(grid, &query, direction) {
+                            break;
+                        }
+                    }
+                    self.sync(grid);
+                }
+                CopyModeAction::None
+            }
+            "escape" => {
+                if self.visual.take().is_some() {
+                    self.sync(grid);
+                    CopyModeAction::None
+                } else {
+                    CopyModeAction::Exit
+                }
+            }
+            "q" => CopyModeAction::Exit,
+            _ => CopyModeAction::None,
+        }
+    }
+
+    /// Move the cursor by `motion`, honouring any pending count.
+    pub fn apply_motion(&mut self, grid: &mut TerminalGrid, motion: Motion) -> CopyModeAction {
+        self.follow_history(grid);
+        let count = self.count.take();
+        let repeat = count.unwrap_or(1).max(1);
+        let last_line = grid.history_len().saturating_sub(1);
+        let last_col = grid.cols.saturating_sub(1);
+        let half_page = (grid.rows / 2).max(1);
+        let mut pos = self.cursor;
+
+        match motion {
+            Motion::Left => pos.col = pos.col.saturating_sub(repeat),
+            Motion::Right => pos.col = (pos.col + repeat).min(last_col),
+            Motion::Up => pos.row = pos.row.saturating_sub(repeat),
+            Motion::Down => pos.row = (pos.row + repeat).min(last_line),
+            Motion::WordForward => {
+                for _ in 0..repeat {
+                    pos = word_forward(grid, pos);
+                }
+            }
+            Motion::WordBackward => {
+                for _ in 0..repeat {
+                    pos = word_backward(grid, pos);
+                }
+            }
+            Motion::WordEnd => {
+                for _ in 0..repeat {
+                    pos = word_end(grid, pos);
+                }
+            }
+            Motion::LineStart => pos.col = 0,
+            Motion::FirstNonBlank => pos.col = first_non_blank(grid, pos.row),
+            Motion::LineEnd => pos.col = last_non_blank(grid, pos.row),
+            Motion::Top | Motion::Bottom => {
+                // `5G` / `5gg` jump to line 5 of the history, like vi.
+                pos.row = match (motion, count) {
+                    (_, Some(n)) => (n - 1).min(last_line),
+                    (Motion::Top, None) => 0,
+                    _ => last_line,
+                };
+                pos.col = first_non_blank(grid, pos.row);
+            }
+            Motion::ScreenTop | Motion::ScreenMiddle | Motion::ScreenBottom => {
+                let visible = grid.rows.min(grid.history_len()).max(1);
+                let row = match motion {
+                    Motion::ScreenTop => 0,
+                    Motion::ScreenMiddle => (visible - 1) / 2,
+                    _ => visible - 1,
+                };
+                pos.row = grid.display_to_history(row).min(last_line);
+                pos.col = first_non_blank(grid, pos.row);
+            }
+            Motion::HalfPageUp => {
+                let n = half_page * repeat;
+                pos.row = pos.row.saturating_sub(n);
+                grid.scroll_view_up(n);
+            }
+            Motion::HalfPageDown => {
+                let n = half_page * repeat;
+                pos.row = (pos.row + n).min(last_line);
+                grid.scroll_view_down(n);
+            }
+            Motion::PrevPrompt => {
+                for _ in 0..repeat {
+                    match grid
+                        .prompt_lines
+                        .iter()
+                        .copied()
+                        .filter(|&line| line < pos.row)
+                        .max()
+                    {
+                        Some(line) => pos = GridPos::new(0, line),
+                        None => break,
+                    }
+                }
+            }
+            Motion::NextPrompt => {
+                for _ in 0..repeat {
+                    match grid
+                        .prompt_lines
+                        .iter()
+                        .copied()
+                        .filter(|&line| line > pos.row && line <= last_line)
+                        .min()
+                    {
+                        Some(line) => pos = GridPos::new(0, line),
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        self.cursor = pos;
+        self.sync(grid);
+        CopyModeAction::None
+    }
+
+    /// Run a search from the cursor and remember it for `n`/`N`. Smart case:
+    /// the search is case sensitive only when the query has an uppercase
+    /// letter. Returns false when nothing matched.
+    pub fn search(
+        &mut self,
+        grid: &mut TerminalGrid,
+        query: &str,
+        direction: SearchDirection,
+    ) -> bool {
+        if query.is_empty() {
+            return false;
+        }
+        self.follow_history(grid);
+        self.last_search = Some((query.to_string(), direction));
+        let found = self.jump_to_match(grid, query, direction);
+        self.sync(grid);
+        found
+    }
+
+    fn jump_to_match(
+        &mut self,
+        grid: &TerminalGrid,
+        query: &str,
+        direction: SearchDirection,
+    ) -> bool {
+        let case_sensitive = query.chars().any(char::is_uppercase);
+        let matches = grid.search_history(query, case_sensitive, false);
+        let positions: Vec<GridPos> = matches.iter().map(|m| GridPos::new(m.col, m.row)).collect();
+        let target = match direction {
+            SearchDirection::Forward => positions
+                .iter()
+                .find(|p| **p > self.cursor)
+                .or_else(|| positions.first()),
+            SearchDirection::Backward => positions
+                .iter()
+                .rev()
+                .find(|p| **p < self.cursor)
+                .or_else(|| positions.last()),
+        };
+        match target {
+            Some(&pos) => {
+                self.cursor = pos;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn toggle_visual(&mut self, grid: &mut TerminalGrid, mode: VisualMode) -> CopyModeAction {
+        self.visual = match self.visual {
+            Some((current, _)) if current == mode => None,
+            Some((_, anchor)) => Some((mode, anchor)),
+            None => Some((mode, self.cursor)),
+        };
+        self.sync(grid);
+        CopyModeAction::None
+    }
+
+    /// Selection endpoints in history coordinates, normalised for the mode.
+    fn selection_range(&self, grid: &TerminalGrid) -> Option<(GridPos, GridPos, SelectionKind)> {
+        let (mode, anchor) = self.visual?;
+        let (mut start, mut end) = match mode {
+            VisualMode::Block => (anchor, self.cursor),
+            _ if anchor <= self.cursor => (anchor, self.cursor),
+            _ => (self.cursor, anchor),
+        };
+        if mode == VisualMode::Line {
+            start.col = 0;
+            end.col = grid.cols.saturating_sub(1);
+        }
+        Some((start, end, mode.selection_kind()))
+    }
+
+    fn yank_text(&self, grid: &TerminalGrid) -> Option<String> {
+        let (start, end, kind) = self.selection_range(grid)?;
+        Some(grid.history_text(start, end, kind))
+    }
+
+    /// Keep the cursor in view and mirror the visual selection into the grid.
+    fn sync(&self, grid: &mut TerminalGrid) {
+        grid.reveal_history_row(self.cursor.row);
+        grid.selection = self
+            .selection_range(grid)
+            .and_then(|(start, end, kind)| display_selection(grid, start, end, kind));
+        grid.dirty = true;
+    }
+}
+
+/// Clip a history-coordinate selection to the current view.
+fn display_selection(
+    grid: &TerminalGrid,
+    start: GridPos,
+    end: GridPos,
+    kind: SelectionKind,
+) -> Option<SelectionState> {
+    let top = grid.display_to_history(0);
+    let bottom = top + grid.rows.saturating_sub(1);
+    let last_col = grid.cols.saturating_sub(1);
+    let (first_row, last_row) = (start.row.min(end.row), start.row.max(end.row));
+    if last_row < top || first_row > bottom {
+        return None;
+    }
+
+    let (start, end) = if kind == SelectionKind::Block {
+        let clamp = |pos: GridPos| GridPos::new(pos.col, pos.row.clamp(top, bottom) - top);
+        (clamp(start), clamp(end))
+    } else {
+        let start = if start.row < top {
+            GridPos::new(0, 0)
+        } else {
+            GridPos::new(start.col, start.row - top)
+        };
+        let end = if end.row > bottom {
+            GridPos::new(last_col, bottom - top)
+        } else {
+            GridPos::new(end.col, end.row - top)
+        };
+        (start, end)
+    };
+
+    Some(SelectionState {
+        start,
+        end,
+        active: false,
+        kind,
+    })
+}
+
+/// Character class used by word motions: blank, word characters, punctuation.
+fn class_at(grid: &TerminalGrid, pos: GridPos) -> u8 {
+    let Some(row) = grid.history_row(pos.row) else {
+        return 0;
+    };
+    let Some(cell) = row.get(pos.col) else {
+        return 0;
+    };
+    // The second half of a wide char belongs to the glyph before it.
+    if cell.wide == CellWidth::Spacer && pos.col > 0 {
+        return class_at(grid, GridPos::new(pos.col - 1, pos.row));
+    }
+    if cell.c.is_whitespace() || cell.c == '\0' {
+        0
+    } else if cell.c.is_alphanumeric() || cell.c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+fn next_pos(grid: &TerminalGrid, pos: GridPos) -> Option<GridPos> {
+    if pos.col + 1 < grid.cols {
+        Some(GridPos::new(pos.col + 1, pos.row))
+    } else if pos.row + 1 < grid.history_len() {
+        Some(GridPos::new(0, pos.row + 1))
+    } else {
+        None
+    }
+}
+
+fn prev_pos(grid: &TerminalGrid, pos: GridPos) -> Option<GridPos> {
+    if pos.col > 0 {
+        Some(GridPos::new(pos.col - 1, pos.row))
+    } else if pos.row > 0 {
+        Some(GridPos::new(grid.cols.saturating_sub(1), pos.row - 1))
+    } else {
+        None
+    }
+}
+
+fn word_forward(grid: &TerminalGrid, pos: GridPos) -> GridPos {
+    let class = class_at(grid, pos);
+    let mut p = pos;
+    // Leave the current word (a line break always ends it).
+    loop {
+        let Some(n) = next_pos(grid, p) else {
+            return p;
+        };
+        let crossed = n.row != p.row;
+        p = n;
+        if crossed || class_at(grid, p) != class {
+            break;
+        }
+    }
+    // Skip blanks up to the next word.
+    while class_at(grid, p) == 0 {
+        match next_pos(grid, p) {
+            Some(n) => p = n,
+            None => return p,
+        }
+    }
+    p
+}
+
+fn word_end(grid: &TerminalGrid, pos: GridPos) -> GridPos {
+    let Some(mut p) = next_pos(grid, pos) else {
+        return pos;
+    };
+    while class_at(grid, p) == 0 {
+        match next_pos(grid, p) {
+            Some(n) => p = n,
+            None => return p,
+        }
+    }
+    let class = class_at(grid, p);
+    while let Some(n) = next_pos(grid, p) {
+        if n.row != p.row || class_at(grid, n) != class {
+            break;
+        }
+        p = n;
+    }
+    p
+}
+
+fn word_backward(grid: &TerminalGrid, pos: GridPos) -> GridPos {
+    let Some(mut p) = prev_pos(grid, pos) else {
+        return pos;
+    };
+    while class_at(grid, p) == 0 {
+        match prev_pos(grid, p) {
+            Some(n) => p = n,
+            None => return p,
+        }
+    }
+    let class = class_at(grid, p);
+    while let Some(n) = prev_pos(grid, p) {
+        if n.row != p.row || class_at(grid, n) != class {
+            break;
+        }
+        p = n;
+    }
+    p
+}
+
+fn first_non_blank(grid: &TerminalGrid, line: usize) -> usize {
+    grid.history_row(line)
+        .and_then(|row| row.iter().position(|cell| !cell.c.is_whitespace()))
+        .unwrap_or(0)
+}
+
+fn last_non_blank(grid: &TerminalGrid, line: usize) -> usize {
+    grid.history_row(line)
+        .and_then(|row| row.iter().rposition(|cell| !cell.c.is_whitespace()))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_str(grid: &mut TerminalGrid, s: &str) {
+        for c in s.chars() {
+            grid.write_char(c);
+        }
+    }
+
+    /// Build a grid from lines, pushing the overflow into scrollback.
+    fn grid_with_lines(rows: usize, cols: usize, lines: &[&str]) -> TerminalGrid {
+        let mut g = TerminalGrid::new(rows, cols);
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                g.carriage_return();
+                g.newline();
+            }
+            write_str(&mut g, line);
+        }
+        g
+    }
+
+    fn keys(mode: &mut CopyMode, grid: &mut TerminalGrid, keys: &[&str]) -> CopyModeAction {
+        let mut last = CopyModeAction::None;
+        for key in keys {
+            last = mode.handle_key(grid, key, false);
+        }
+        last
+    }
+
+    #[test]
+    fn enters_on_shell_cursor_in_history_coordinates() {
+        let mut g = grid_with_lines(2, 10, &["one", "two", "three"]);
+        assert_eq!(g.scrollback_len(), 1);
+        let mode = CopyMode::new(&mut g);
+        assert_eq!(mode.cursor(), GridPos::new(5, 2));
+        assert_eq!(mode.display_cursor(&g), Some(GridPos::new(5, 1)));
+    }
+
+    #[test]
+    fn moving_up_scrolls_the_view_into_scrollback() {
+        let mut g = grid_with_lines(2, 10, &["a", "b", "c", "d"]);
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["k", "k", "k"]);
+        assert_eq!(mode.cursor().row, 0);
+        assert_eq!(g.scroll_offset(), g.scrollback_len());
+        assert_eq!(mode.display_cursor(&g), Some(GridPos::new(1, 0)));
+        mode.exit(&mut g);
+        assert!(g.is_at_bottom());
+    }
+
+    #[test]
+    fn word_motions_cross_punctuation_and_lines() {
+        let mut g = grid_with_lines(3, 20, &["foo.bar baz", "qux"]);
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["g", "g"]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 0));
+        keys(&mut mode, &mut g, &["w"]);
+        assert_eq!(mode.cursor(), GridPos::new(3, 0));
+        keys(&mut mode, &mut g, &["w"]);
+        assert_eq!(mode.cursor(), GridPos::new(4, 0));
+        keys(&mut mode, &mut g, &["e"]);
+        assert_eq!(mode.cursor(), GridPos::new(6, 0));
+        keys(&mut mode, &mut g, &["w", "w"]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 1));
+        keys(&mut mode, &mut g, &["b"]);
+        assert_eq!(mode.cursor(), GridPos::new(8, 0));
+    }
+
+    #[test]
+    fn line_motions_and_counts() {
+        let mut g = grid_with_lines(4, 12, &["  indented", "x", "y", "z"]);
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["g", "g", "$"]);
+        assert_eq!(mode.cursor(), GridPos::new(9, 0));
+        keys(&mut mode, &mut g, &["0"]);
+        assert_eq!(mode.cursor().col, 0);
+        keys(&mut mode, &mut g, &["^"]);
+        assert_eq!(mode.cursor().col, 2);
+        keys(&mut mode, &mut g, &["2", "j"]);
+        assert_eq!(mode.cursor().row, 2);
+        keys(&mut mode, &mut g, &["G"]);
+        assert_eq!(mode.cursor().row, 3);
+        keys(&mut mode, &mut g, &["2", "G"]);
+        assert_eq!(mode.cursor().row, 1);
+    }
+
+    #[test]
+    fn screen_relative_motions() {
+        let mut g = grid_with_lines(3, 10, &["a", "b", "c", "d", "e"]);
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["H"]);
+        assert_eq!(mode.cursor().row, 2);
+        keys(&mut mode, &mut g, &["L"]);
+        assert_eq!(mode.cursor().row, 4);
+        keys(&mut mode, &mut g, &["M"]);
+        assert_eq!(mode.cursor().row, 3);
+        mode.handle_key(&mut g, "u", true);
+        assert_eq!(mode.cursor().row, 2);
+        mode.handle_key(&mut g, "d", true);
+        assert_eq!(mode.cursor().row, 3);
+    }
+
+    #[test]
+    fn visual_line_yank_spans_scrollback() {
+        let mut g = grid_with_lines(2, 10, &["first", "second", "third"]);
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["V", "k", "k"]);
+        assert_eq!(mode.visual_mode(), Some(VisualMode::Line));
+        let sel = g.selection.clone().expect("selection mirrored into grid");
+        assert_eq!(sel.kind, SelectionKind::Line);
+        let action = keys(&mut mode, &mut g, &["y"]);
+        assert_eq!(
+            action,
+            CopyModeAction::Yank("first\nsecond\nthird".to_string())
+        );
+    }
+
+    #[test]
+    fn visual_char_and_block_selections() {
+        let mut g = grid_with_lines(3, 10, &["abcdef", "ghijkl"]);
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["g", "g", "l", "v", "l", "l"]);
+        assert_eq!(
+            keys(&mut mode, &mut g, &["y"]),
+            CopyModeAction::Yank("bcd".to_string())
+        );
+
+        mode.handle_key(&mut g, "escape", false);
+        keys(&mut mode, &mut g, &["g", "g", "l"]);
+        mode.handle_key(&mut g, "v", true);
+        keys(&mut mode, &mut g, &["j", "l"]);
+        assert_eq!(
+            g.selection.as_ref().map(|s| s.kind),
+            Some(SelectionKind::Block)
+        );
+        assert_eq!(
+            keys(&mut mode, &mut g, &["y"]),
+            CopyModeAction::Yank("bc\nhi".to_string())
+        );
+    }
+
+    #[test]
+    fn escape_clears_visual_before_exiting() {
+        let mut g = grid_with_lines(2, 10, &["x"]);
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["v"]);
+        assert!(g.selection.is_some());
+        assert_eq!(keys(&mut mode, &mut g, &["escape"]), CopyModeAction::None);
+        assert!(g.selection.is_none());
+        assert_eq!(keys(&mut mode, &mut g, &["escape"]), CopyModeAction::Exit);
+    }
+
+    #[test]
+    fn search_moves_cursor_and_repeats_with_wraparound() {
+        let mut g = grid_with_lines(2, 16, &["error one", "ok", "error two", "ok"]);
+        let mut mode = CopyMode::new(&mut g);
+        assert_eq!(
+            keys(&mut mode, &mut g, &["?"]),
+            CopyModeAction::Search(SearchDirection::Backward)
+        );
+        assert!(mode.search(&mut g, "error", SearchDirection::Backward));
+        assert_eq!(mode.cursor(), GridPos::new(0, 2));
+        keys(&mut mode, &mut g, &["n"]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 0));
+        keys(&mut mode, &mut g, &["n"]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 2));
+        keys(&mut mode, &mut g, &["N"]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 0));
+        assert!(!mode.search(&mut g, "missing", SearchDirection::Forward));
+    }
+
+    #[test]
+    fn cursor_follows_rows_evicted_from_scrollback() {
+        let mut g = TerminalGrid::with_scrollback(2, 10, 3);
+        for (i, line) in ["$ ls", "a", "b", "$ pwd"].iter().enumerate() {
+            if i > 0 {
+                g.carriage_return();
+                g.newline();
+            }
+            write_str(&mut g, line);
+        }
+        g.prompt_lines = vec![0, 3];
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["["]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 0));
+        keys(&mut mode, &mut g, &["]"]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 3));
+
+        // Three more lines of output evict "$ ls" and "a".
+        for line in ["c", "d", "e"] {
+            g.carriage_return();
+            g.newline();
+            write_str(&mut g, line);
+        }
+        assert_eq!(g.history_dropped(), 2);
+        assert_eq!(g.prompt_lines, vec![1]);
+        keys(&mut mode, &mut g, &["v"]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 1));
+        assert_eq!(g.history_row(1).map(|row| row[0].c), Some('$'));
+    }
+
+    #[test]
+    fn prompt_jumps_follow_osc_133_marks() {
+        let mut g = grid_with_lines(2, 10, &["$ ls", "a", "$ pwd", "/", "$"]);
+        g.prompt_lines = vec![0, 2, 4];
+        let mut mode = CopyMode::new(&mut g);
+        keys(&mut mode, &mut g, &["["]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 2));
+        keys(&mut mode, &mut g, &["["]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 0));
+        keys(&mut mode, &mut g, &["["]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 0));
+        keys(&mut mode, &mut g, &["]"]);
+        assert_eq!(mode.cursor(), GridPos::new(0, 2));
+    }
+}
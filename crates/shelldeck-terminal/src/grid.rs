@@ -43,6 +43,10 @@ impl<T> RingBuffer<T> {
     pub fn len(&self) -> usize {
         self.len
     }
+    /// Whether the next push evicts the oldest item.
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity
+    }
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
@@ -530,7 +534,11 @@ pub struct TerminalGrid {
     pub prompt_mark_sequence: u64,
     /// Absolute history row where the current command output starts.
     pub command_output_start: Option<usize>,
+    /// Absolute history rows where an OSC 133;A prompt started, oldest first.
     pub prompt_lines: Vec<usize>,
+    /// Total history rows dropped off the top (scrollback eviction, clears),
+    /// so holders of history rows can tell how far they shifted.
+    history_dropped: u64,
     pub clipboard_request: Option<(String, String)>,
     pub palette_overrides: HashMap<u8, (u8, u8, u8)>,
 }
@@ -599,6 +607,7 @@ impl TerminalGrid {
             prompt_mark_sequence: 0,
             command_output_start: None,
             prompt_lines: Vec::new(),
+            history_dropped: 0,
             clipboard_request: None,
             palette_overrides: HashMap::new(),
         }
@@ -994,6 +1003,7 @@ impl TerminalGrid {
                 for f in &mut self.line_flags {
                     *f = LineFlags::default();
                 }
+                self.drop_history_rows(self.scrollback.len());
                 self.scrollback.clear();
                 self.scrollback_line_flags.clear();
                 self.scroll_offset = 0;
@@ -1205,6 +1215,9 @@ impl TerminalGrid {
                 } else {
                     LineFlags::default()
                 };
+                if self.scrollback.is_full() {
+                    self.drop_history_rows(1);
+                }
                 self.scrollback.push(self.cells[i].clone());
                 self.scrollback_line_flags.push(flags);
             }
@@ -1302,12 +1315,18 @@ impl TerminalGrid {
         let old_cols = self.cols;
 
         // Reflow: when the terminal grows wider, merge soft-wrapped lines.
-        // When it shrinks, re-wrap long lines.
+        // When it shrinks, re-wrap long lines. Rows no longer line up with
+        // the recorded prompt marks afterwards, so those are forgotten.
+        if new_cols != old_cols {
+            self.prompt_lines.clear();
+            self.command_output_start = None;
+        }
         if new_cols > old_cols {
             self.reflow_on_grow(new_cols);
         } else if new_cols < old_cols {
             self.reflow_on_shrink(new_cols);
         }
+        self.settle_reflowed_rows();
 
         // Resize each existing row (truncate or extend).
         for row in &mut self.cells {
@@ -1344,6 +1363,9 @@ impl TerminalGrid {
             for _ in 0..remove {
                 if !self.cells.is_empty() {
                     let row = self.cells.remove(0);
+                    if self.scrollback.is_full() {
+                        self.drop_history_rows(1);
+                    }
                     self.scrollback.push(row);
                     let flags = if !self.line_flags.is_empty() {
                         self.line_flags.remove(0)
@@ -1394,6 +1416,41 @@ impl TerminalGrid {
         self.dirty = true;
     }
 
+    /// Bring the screen back to `self.rows` rows after a reflow changed how
+    /// many it holds. Rows split off at the top scroll into the scrollback,
+    /// counting any it evicts as dropped history; rows merged away are
+    /// refilled from the scrollback first, then with blank rows.
+    fn settle_reflowed_rows(&mut self) {
+        while self.cells.len() > self.rows {
+            let row = self.cells.remove(0);
+            if self.scrollback.is_full() {
+                self.drop_history_rows(1);
+            }
+            self.scrollback.push(row);
+            let flags = if self.line_flags.is_empty() {
+                LineFlags::default()
+            } else {
+                self.line_flags.remove(0)
+            };
+            self.scrollback_line_flags.push(flags);
+            self.cursor.row = self.cursor.row.saturating_sub(1);
+        }
+        while self.cells.len() < self.rows {
+            match self.scrollback.pop() {
+                Some(row) => {
+                    self.cells.insert(0, row);
+                    let flags = self.scrollback_line_flags.pop().unwrap_or_default();
+                    self.line_flags.insert(0, flags);
+                    self.cursor.row += 1;
+                }
+                None => {
+                    self.cells.push(vec![Cell::default(); self.cols]);
+                    self.line_flags.push(LineFlags::default());
+                }
+            }
+        }
+    }
+
     /// Reflow soft-wrapped lines when the terminal width increases.
     /// Walks the screen rows and merges consecutive soft-wrapped lines
     /// when their combined content fits within `new_cols`.
@@ -1455,16 +1512,13 @@ impl TerminalGrid {
                 let mut new_row = overflow;
                 new_row.resize(self.cols, Cell::default());
 
-                // Mark current row as soft-wrapped.
-                if i < self.line_flags.len() {
-                    self.line_flags[i].soft_wrapped = true;
-                }
-
-                // Insert overflow as a new row below.
+                // Insert overflow as a new row below, marked as the
+                // soft-wrapped continuation of the current one.
                 let insert_at = i + 1;
                 if insert_at <= self.cells.len() {
                     self.cells.insert(insert_at, new_row);
-                    self.line_flags.insert(insert_at, LineFlags::default());
+                    self.line_flags
+                        .insert(insert_at, LineFlags { soft_wrapped: true });
                 }
 
                 // Adjust cursor position if it was on or below the split point.
@@ -1622,7 +1676,9 @@ impl TerminalGrid {
         let rows = self.rows;
         let cols = self.cols;
         let max_scrollback = self.max_scrollback;
+        let history_dropped = self.history_dropped + self.history_len() as u64;
         *self = Self::with_scrollback(rows, cols, max_scrollback);
+        self.history_dropped = history_dropped;
     }
 
     /// Adjust the maximum number of scrollback lines retained, preserving the
@@ -1634,6 +1690,7 @@ impl TerminalGrid {
             return;
         }
         self.max_scrollback = max_scrollback;
+        self.drop_history_rows(self.scrollback.len().saturating_sub(max_scrollback));
         self.scrollback.set_capacity(max_scrollback);
         self.scrollback_line_flags.set_capacity(max_scrollback);
         // Clamp any active scrollback view to the new buffer length.
@@ -2001,57 +2058,59 @@ impl TerminalGrid {
     /// Extract the selected text as a string.
     pub fn selected_text(&self) -> Option<String> {
         let sel = self.selection.as_ref()?;
-        let visible = self.visible_rows();
+        let text = self.history_text(
+            GridPos::new(sel.start.col, self.display_to_history(sel.start.row)),
+            GridPos::new(sel.end.col, self.display_to_history(sel.end.row)),
+            sel.kind,
+        );
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
 
+    /// Extract the text between two history positions (see
+    /// [`history_row`](Self::history_row)), using the same rules as the
+    /// mouse selection: block selections take the same column range from every
+    /// row, every other kind is a stream from `start` to `end`. Trailing
+    /// spaces are trimmed from each line.
+    pub fn history_text(&self, start: GridPos, end: GridPos, kind: SelectionKind) -> String {
         // Block/rectangular selection: extract the same column range from each row.
-        if sel.kind == SelectionKind::Block {
-            let min_row = sel.start.row.min(sel.end.row);
-            let max_row = sel.start.row.max(sel.end.row);
-            let min_col = sel.start.col.min(sel.end.col);
-            let max_col = sel.start.col.max(sel.end.col);
+        if kind == SelectionKind::Block {
+            let min_row = start.row.min(end.row);
+            let max_row = start.row.max(end.row);
+            let min_col = start.col.min(end.col);
+            let max_col = start.col.max(end.col);
 
             let mut lines = Vec::new();
             for row_idx in min_row..=max_row {
-                if row_idx >= visible.len() {
+                let Some(row_cells) = self.history_row(row_idx) else {
                     break;
+                };
+                if min_col >= row_cells.len() {
+                    lines.push(String::new());
+                    continue;
                 }
-                let row_cells = visible[row_idx];
-                let mut line = String::new();
-                for cell in &row_cells[min_col..=max_col.min(row_cells.len().saturating_sub(1))] {
-                    // Skip spacer cells (second half of wide chars).
-                    if cell.wide == CellWidth::Spacer {
-                        continue;
-                    }
-                    line.push(cell.c);
-                    // Append any combining characters.
-                    for &comb in &cell.combining {
-                        line.push(comb);
-                    }
-                }
+                let line = Self::cells_text(&row_cells[min_col..=max_col.min(row_cells.len() - 1)]);
                 lines.push(line.trim_end().to_string());
             }
-
-            let text = lines.join("\n");
-            if text.is_empty() {
-                return None;
-            }
-            return Some(text);
+            return lines.join("\n");
         }
 
         // Stream selection (Simple, Word, Line).
-        let (start, end) = if sel.start <= sel.end {
-            (sel.start, sel.end)
+        let (start, end) = if start <= end {
+            (start, end)
         } else {
-            (sel.end, sel.start)
+            (end, start)
         };
 
         let mut text = String::new();
 
         for row_idx in start.row..=end.row {
-            if row_idx >= visible.len() {
+            let Some(row_cells) = self.history_row(row_idx) else {
                 break;
-            }
-            let row_cells = visible[row_idx];
+            };
             let col_start = if row_idx == start.row { start.col } else { 0 };
             let col_end = if row_idx == end.row {
                 end.col
@@ -2059,32 +2118,35 @@ impl TerminalGrid {
                 row_cells.len().saturating_sub(1)
             };
 
-            let mut line = String::new();
-            for cell in &row_cells[col_start..=col_end.min(row_cells.len().saturating_sub(1))] {
-                // Skip spacer cells (second half of wide chars).
-                if cell.wide == CellWidth::Spacer {
-                    continue;
-                }
-                line.push(cell.c);
-                // Append any combining characters.
-                for &comb in &cell.combining {
-                    line.push(comb);
-                }
+            if col_start < row_cells.len() {
+                let line =
+                    Self::cells_text(&row_cells[col_start..=col_end.min(row_cells.len() - 1)]);
+                // Trim trailing spaces from each line
+                text.push_str(line.trim_end());
             }
-            // Trim trailing spaces from each line
-            let trimmed = line.trim_end();
-            text.push_str(trimmed);
 
             if row_idx < end.row {
                 text.push('\n');
             }
         }
 
-        if text.is_empty() {
-            None
-        } else {
-            Some(text)
+        text
+    }
+
+    /// Concatenate the glyphs of a run of cells, skipping spacer cells (the
+    /// second half of wide chars) and keeping combining characters.
+    fn cells_text(cells: &[Cell]) -> String {
+        let mut line = String::new();
+        for cell in cells {
+            if cell.wide == CellWidth::Spacer {
+                continue;
+            }
+            line.push(cell.c);
+            for &comb in &cell.combining {
+                line.push(comb);
+            }
         }
+        line
     }
 
     /// Return the normalized selection bounds for rendering.
@@ -2110,68 +2172,158 @@ impl TerminalGrid {
 
     /// Search visible rows for `query`. Returns matches with display-relative coordinates.
     pub fn search(&self, query: &str, case_sensitive: bool, use_regex: bool) -> Vec<SearchMatch> {
-        if query.is_empty() {
+        let Some(re) = Self::search_regex(query, case_sensitive, use_regex) else {
             return Vec::new();
+        };
+        let mut matches = Vec::new();
+        for (ri, row) in self.visible_rows().into_iter().enumerate() {
+            Self::search_row(&re, ri, row, &mut matches);
         }
+        matches
+    }
 
-        let visible = self.visible_rows();
+    /// Search the whole history (scrollback + live screen) for `query`.
+    /// Matches carry history rows (see [`history_row`](Self::history_row))
+    /// instead of display rows, so they stay valid while the view scrolls.
+    pub fn search_history(
+        &self,
+        query: &str,
+        case_sensitive: bool,
+        use_regex: bool,
+    ) -> Vec<SearchMatch> {
+        let Some(re) = Self::search_regex(query, case_sensitive, use_regex) else {
+            return Vec::new();
+        };
         let mut matches = Vec::new();
+        for line in 0..self.history_len() {
+            if let Some(row) = self.history_row(line) {
+                Self::search_row(&re, line, row, &mut matches);
+            }
+        }
+        matches
+    }
 
-        let regex = if use_regex {
-            let pattern = if case_sensitive {
-                query.to_string()
-            } else {
-                format!("(?i){}", query)
-            };
-            Regex::new(&pattern).ok()
+    fn search_regex(query: &str, case_sensitive: bool, use_regex: bool) -> Option<Regex> {
+        if query.is_empty() {
+            return None;
+        }
+        let pattern = if use_regex {
+            query.to_string()
         } else {
             // Escape regex special chars for plain text search
-            let escaped = regex::escape(query);
-            let pattern = if case_sensitive {
-                escaped
-            } else {
-                format!("(?i){}", escaped)
-            };
-            Regex::new(&pattern).ok()
+            regex::escape(query)
         };
-
-        let re = match regex {
-            Some(r) => r,
-            None => return Vec::new(),
+        let pattern = if case_sensitive {
+            pattern
+        } else {
+            format!("(?i){}", pattern)
         };
+        Regex::new(&pattern).ok()
+    }
 
-        for (ri, row) in visible.iter().enumerate() {
-            // Build the line text skipping spacer cells, and track the
-            // mapping from character-index-in-string to column-index.
-            let mut line = String::new();
-            let mut char_to_col: Vec<usize> = Vec::new();
-            for (ci, cell) in row.iter().enumerate() {
-                if cell.wide == CellWidth::Spacer {
-                    continue;
-                }
-                char_to_col.push(ci);
-                line.push(cell.c);
-                for &comb in &cell.combining {
-                    line.push(comb);
-                }
+    fn search_row(re: &Regex, row_idx: usize, row: &[Cell], matches: &mut Vec<SearchMatch>) {
+        // Build the line text skipping spacer cells, and track the
+        // mapping from character-index-in-string to column-index.
+        let mut line = String::new();
+        let mut char_to_col: Vec<usize> = Vec::new();
+        for (ci, cell) in row.iter().enumerate() {
+            if cell.wide == CellWidth::Spacer {
+                continue;
             }
-            for m in re.find_iter(&line) {
-                let start_char = m.start();
-                let end_char = m.end();
-                let col = if start_char < char_to_col.len() {
-                    char_to_col[start_char]
-                } else {
-                    continue;
-                };
-                matches.push(SearchMatch {
-                    row: ri,
-                    col,
-                    len: end_char - start_char,
-                });
+            char_to_col.push(ci);
+            line.push(cell.c);
+            for &comb in &cell.combining {
+                line.push(comb);
             }
         }
+        for m in re.find_iter(&line) {
+            let start_char = m.start();
+            let end_char = m.end();
+            let col = if start_char < char_to_col.len() {
+                char_to_col[start_char]
+            } else {
+                continue;
+            };
+            matches.push(SearchMatch {
+                row: row_idx,
+                col,
+                len: end_char - start_char,
+            });
+        }
+    }
 
-        matches
+    // -- History coordinates --
+    //
+    // A "history row" indexes the scrollback and the live screen as one
+    // sequence: 0 is the oldest scrollback line and `history_len() - 1` the
+    // bottom row of the live screen. Unlike display rows they don't move when
+    // the view scrolls, which is what keyboard copy mode navigates in.
+
+    /// Total number of rows in scrollback + live screen.
+    pub fn history_len(&self) -> usize {
+        self.scrollback.len() + self.cells.len()
+    }
+
+    /// Total history rows dropped off the top since the grid was created.
+    /// A history row recorded earlier moved up by the growth of this count.
+    pub fn history_dropped(&self) -> u64 {
+        self.history_dropped
+    }
+
+    /// Account for `n` rows leaving the top of history: prompt marks move up
+    /// with their rows and marks on dropped rows are forgotten.
+    fn drop_history_rows(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.history_dropped += n as u64;
+        self.prompt_lines.retain(|&line| line >= n);
+        for line in &mut self.prompt_lines {
+            *line -= n;
+        }
+        // Output that started on a dropped row now starts at the oldest one.
+        self.command_output_start = self
+            .command_output_start
+            .map(|start| start.saturating_sub(n));
+    }
+
+    /// Return a row by history index.
+    pub fn history_row(&self, line: usize) -> Option<&Vec<Cell>> {
+        let sb_len = self.scrollback.len();
+        if line < sb_len {
+            self.scrollback.get(line)
+        } else {
+            self.cells.get(line - sb_len)
+        }
+    }
+
+    /// Convert a display row (0 = top of the current view) to a history row.
+    pub fn display_to_history(&self, row: usize) -> usize {
+        let sb_len = self.scrollback.len();
+        sb_len - self.scroll_offset.min(sb_len) + row
+    }
+
+    /// Convert a history row to a display row, if it is currently in view.
+    pub fn history_to_display(&self, line: usize) -> Option<usize> {
+        let top = self.display_to_history(0);
+        if line >= top && line < top + self.rows {
+            Some(line - top)
+        } else {
+            None
+        }
+    }
+
+    /// Scroll the view the minimum amount needed to bring `line` into view.
+    pub fn reveal_history_row(&mut self, line: usize) {
+        let sb_len = self.scrollback.len();
+        let line = line.min(self.history_len().saturating_sub(1));
+        let top = self.display_to_history(0);
+        if line < top {
+            self.set_scroll_offset(sb_len - line);
+        } else if line >= top + self.rows {
+            let new_top = line + 1 - self.rows;
+            self.set_scroll_offset(sb_len.saturating_sub(new_top));
+        }
     }
 
     /// Get the scrollback length (for scrollbar calculations).
@@ -2466,6 +2618,54 @@ mod tests {
         assert_eq!(g.scrollback_len(), 3);
     }
 
+    #[test]
+    fn prompt_lines_follow_scrollback_eviction() {
+        let mut g = TerminalGrid::with_scrollback(2, 5, 3);
+        // Prompts on history rows 0 and 3, output starting on row 1.
+        g.prompt_lines = vec![0, 3];
+        g.command_output_start = Some(1);
+        for _ in 0..4 {
+            g.cursor.row = g.rows - 1;
+            g.newline();
+        }
+        // Four rows scrolled off into a three-row buffer: one was evicted.
+        assert_eq!(g.scrollback_len(), 3);
+        assert_eq!(g.history_dropped(), 1);
+        assert_eq!(g.prompt_lines, vec![2]);
+        assert_eq!(g.command_output_start, Some(0));
+
+        g.cursor.row = g.rows - 1;
+        g.newline();
+        g.cursor.row = g.rows - 1;
+        g.newline();
+        g.cursor.row = g.rows - 1;
+        g.newline();
+        assert_eq!(g.history_dropped(), 4);
+        assert!(g.prompt_lines.is_empty());
+        assert_eq!(g.command_output_start, Some(0));
+    }
+
+    #[test]
+    fn prompt_lines_shift_on_scrollback_clear_and_shrink() {
+        let mut g = TerminalGrid::with_scrollback(2, 5, 10);
+        for _ in 0..6 {
+            g.cursor.row = g.rows - 1;
+            g.newline();
+        }
+        g.prompt_lines = vec![1, 4, 6];
+        g.set_max_scrollback(3);
+        assert_eq!(g.prompt_lines, vec![1, 3]);
+
+        // ED 3 drops all scrollback; the prompt on the screen stays.
+        g.erase_display(3);
+        assert_eq!(g.prompt_lines, vec![0]);
+        assert_eq!(g.history_dropped(), 6);
+
+        // A width change reflows rows, so the marks are dropped.
+        g.resize(2, 8);
+        assert!(g.prompt_lines.is_empty());
+    }
+
     #[test]
     fn set_max_scrollback_shrinks_keeping_newest() {
         let mut g = TerminalGrid::with_scrollback(2, 5, 10);
@@ -2733,6 +2933,38 @@ mod tests {
         assert_eq!(&row_text(&g, 0)[..6], "abcdef");
     }
 
+    #[test]
+    fn reflow_after_overflow_counts_evicted_rows() {
+        let mut g = TerminalGrid::with_scrollback(2, 6, 3);
+        for (i, line) in ["0", "1", "2", "3", "4", "abcdef"].iter().enumerate() {
+            if i > 0 {
+                g.carriage_return();
+                g.newline();
+            }
+            write_str(&mut g, line);
+        }
+        assert_eq!(g.history_dropped(), 1);
+        let first = |g: &TerminalGrid, line: usize| g.history_row(line).map(|row| row[0].c);
+        assert_eq!(first(&g, 0), Some('1'));
+
+        // Splitting "abcdef" pushes "4" into the full scrollback, evicting "1".
+        g.resize(2, 3);
+        assert_eq!(g.cells.len(), 2);
+        assert_eq!(row_text(&g, 0), "abc");
+        assert_eq!(row_text(&g, 1), "def");
+        assert_eq!(g.scrollback_len(), 3);
+        assert_eq!(g.history_dropped(), 2);
+        assert_eq!(first(&g, 0), Some('2'));
+        assert_eq!(first(&g, 2), Some('4'));
+
+        // Merging it back pulls "4" down again without dropping anything.
+        g.resize(2, 6);
+        assert_eq!(g.cells.len(), 2);
+        assert_eq!(&row_text(&g, 1), "abcdef");
+        assert_eq!(first(&g, 2), Some('4'));
+        assert_eq!(g.history_dropped(), 2);
+    }
+
     // ---- index / reverse_index ----
 
     #[test]
@@ -1,4 +1,5 @@
 pub mod colors;
+pub mod copy_mode;
 pub mod error;
 pub mod grid;
 pub mod parser;
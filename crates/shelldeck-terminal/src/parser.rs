@@ -960,8 +960,8 @@ impl vte::Perform for Performer<'_> {
                     match marker.chars().next() {
                         Some('A') => {
                             grid.prompt_mark = Some(PromptMark::PromptStart);
-                            // Record the current cursor row as a prompt line.
-                            let row = grid.cursor.row;
+                            // Record the cursor's history row as a prompt line.
+                            let row = grid.scrollback_len() + grid.cursor.row;
                             grid.prompt_lines.push(row);
                         }
                         Some('B') => {
@@ -0,0 +1,446 @@
+//! Import preview for connections exported by other SSH clients.
+//!
+//! The user picks an export, every host it contains is listed with a
+//! checkbox, and hosts already in the store are flagged and left unchecked.
+//! Only the checked connections are handed back to the workspace.
+
+use crate::scale::px;
+use adabraka_ui::components::checkbox::Checkbox;
+use adabraka_ui::prelude::*;
+use gpui::prelude::*;
+use gpui::*;
+use std::path::PathBuf;
+
+use shelldeck_core::config::connection_import::{self, ImportCandidate, ImportFormat};
+use shelldeck_core::models::connection::Connection;
+
+use crate::t;
+use crate::theme::ShellDeckColors;
+
+#[derive(Debug, Clone)]
+pub enum ConnectionImportEvent {
+    /// The checked connections and the label of the format they came from.
+    Import(Vec<Connection>, &'static str),
+    Cancel,
+}
+
+impl EventEmitter<ConnectionImportEvent> for ConnectionImportView {}
+
+pub struct ConnectionImportView {
+    /// Connections already known, used to flag duplicates.
+    existing: Vec<Connection>,
+    source: Option<(PathBuf, ImportFormat)>,
+    candidates: Vec<ImportCandidate>,
+    /// Parallel to `candidates`.
+    selected: Vec<bool>,
+    loading: bool,
+    error: Option<String>,
+    focus_handle: FocusHandle,
+    needs_focus: bool,
+}
+
+impl ConnectionImportView {
+    pub fn new(existing: Vec<Connection>, cx: &mut Context<Self>) -> Self {
+        Self {
+            existing,
+            source: None,
+            candidates: Vec::new(),
+            selected: Vec::new(),
+            loading: false,
+            error: None,
+            focus_handle: cx.focus_handle(),
+            needs_focus: true,
+        }
+    }
+
+    fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        match event.keystroke.key.as_str() {
+            "escape" => cx.emit(ConnectionImportEvent::Cancel),
+            "enter" => self.import_selected(cx),
+            _ => {}
+        }
+    }
+
+    /// Open the native picker. Directories are accepted for Remmina, which
+    /// keeps one file per profile.
+    fn browse(&mut self, cx: &mut Context<Self>) {
+        let receiver = cx.prompt_for_paths(gpui::PathPromptOptions {
+            files: true,
+            directories: true,
+            multiple: false,
+            prompt: Some(t!("connection_import.browse_prompt").to_string().into()),
+            starting_directory: shelldeck_core::util::home_dir(),
+        });
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let Ok(Ok(Some(paths))) = receiver.await else {
+                return;
+            };
+            let Some(path) = paths.into_iter().next() else {
+                return;
+            };
+            let _ = this.update(cx, |this, cx| this.load(path, cx));
+        })
+        .detach();
+    }
+
+    fn load(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+        self.loading = true;
+        self.error = None;
+        cx.notify();
+        let read_path = path.clone();
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { connection_import::read_import_path(&read_path) })
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                this.loading = false;
+                match result {
+                    Ok((format, connections)) => {
+                        this.candidates = connection_import::preview(connections, &this.existing);
+                        this.selected = this
+                            .candidates
+                            .iter()
+                            .map(ImportCandidate::selected_by_default)
+                            .collect();
+                        this.source = Some((path, format));
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to read {}: {}", path.display(), e);
+                        this.candidates.clear();
+                        this.selected.clear();
+                        this.source = None;
+                        this.error = Some(
+                            t!("connection_import.read_failed", error = e.to_string()).to_string(),
+                        );
+                    }
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn selected_count(&self) -> usize {
+        self.selected.iter().filter(|s| **s).count()
+    }
+
+    fn import_selected(&self, cx: &mut Context<Self>) {
+        let Some((_, format)) = &self.source else {
+            return;
+        };
+        let connections: Vec<Connection> = self
+            .candidates
+            .iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .map(|(candidate, _)| candidate.connection.clone())
+            .collect();
+        if !connections.is_empty() {
+            cx.emit(ConnectionImportEvent::Import(connections, format.label()));
+        }
+    }
+
+    fn render_candidate(
+        &self,
+        index: usize,
+        candidate: &ImportCandidate,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let connection = &candidate.connection;
+        let entity = cx.entity();
+        let checkbox = Checkbox::new(ElementId::from(SharedString::from(format!(
+            "conn-import-{}",
+            index
+        ))))
+        .checked(self.selected[index])
+        .on_click(move |checked, _, cx| {
+            let checked = *checked;
+            entity.update(cx, |this, cx| {
+                if let Some(slot) = this.selected.get_mut(index) {
+                    *slot = checked;
+                }
+                cx.notify();
+            });
+        });
+
+        let mut details = vec![connection.connection_string()];
+        if let Some(group) = &connection.group {
+            details.push(group.clone());
+        }
+        if let Some(jump) = &connection.proxy_jump {
+            details.push(t!("connection_import.via", jump = jump.as_str()).to_string());
+        }
+
+        let mut row = div()
+            .flex()
+            .items_center()
+            .gap(px(10.0))
+            .px(px(16.0))
+            .py(px(8.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .child(checkbox)
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .flex_grow()
+                    .min_w_0()
+                    .gap(px(2.0))
+                    .child(
+                        div()
+                            .text_size(px(13.0))
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(connection.alias.clone()),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .truncate()
+                            .child(details.join(" · ")),
+                    ),
+            );
+        if let Some(existing) = &candidate.duplicate_of {
+            row = row.child(
+                div()
+                    .flex_shrink_0()
+                    .text_size(px(10.0))
+                    .px(px(6.0))
+                    .py(px(1.0))
+                    .rounded(px(3.0))
+                    .bg(ShellDeckColors::warning().opacity(0.15))
+                    .text_color(ShellDeckColors::warning())
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .child(
+                        t!("connection_import.duplicate", alias = existing.as_str()).to_string(),
+                    ),
+            );
+        }
+        row
+    }
+}
+
+impl Render for ConnectionImportView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.needs_focus {
+            self.needs_focus = false;
+            self.focus_handle.focus(window);
+        }
+        let source_label = match &self.source {
+            Some((path, format)) => format!("{} · {}", format.label(), path.display()),
+            None => t!("connection_import.formats").to_string(),
+        };
+
+        let mut list = div()
+            .id("connection-import-list")
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .min_h(px(0.0))
+            .overflow_y_scroll();
+        let placeholder = if self.loading {
+            Some(t!("connection_import.loading").to_string())
+        } else if let Some(error) = &self.error {
+            Some(error.clone())
+        } else if self.source.is_none() {
+            Some(t!("connection_import.empty").to_string())
+        } else if self.candidates.is_empty() {
+            Some(t!("connection_import.no_connections").to_string())
+        } else {
+            None
+        };
+        if let Some(message) = placeholder {
+            let color = if self.error.is_some() && !self.loading {
+                ShellDeckColors::error()
+            } else {
+                ShellDeckColors::text_muted()
+            };
+            list = list.child(
+                div()
+                    .px(px(16.0))
+                    .py(px(24.0))
+                    .text_size(px(12.0))
+                    .text_color(color)
+                    .child(message),
+            );
+        } else {
+            for (index, candidate) in self.candidates.iter().enumerate() {
+                list = list.child(self.render_candidate(index, candidate, cx));
+            }
+        }
+
+        let selected = self.selected_count();
+        let mut import_btn = div()
+            .id("connection-import-confirm")
+            .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                this.import_selected(cx);
+            }))
+            .child(
+                Button::new(
+                    "connection-import-confirm-btn",
+                    t!("connection_import.import", count = selected).to_string(),
+                )
+                .variant(ButtonVariant::Default),
+            );
+        if selected > 0 {
+            import_btn = import_btn.cursor_pointer();
+        } else {
+            import_btn = import_btn.opacity(0.5);
+        }
+
+        div()
+            .id("connection-import-overlay")
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
+                this.handle_key_down(event, cx);
+            }))
+            .occlude()
+            .absolute()
+            .top_0()
+            .left_0()
+            .right_0()
+            .bottom_0()
+            .bg(ShellDeckColors::backdrop())
+            .flex()
+            .justify_center()
+            .items_center()
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .w(px(640.0))
+                    .h(px(520.0))
+                    .bg(ShellDeckColors::bg_surface())
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(ShellDeckColors::border())
+                    .shadow_xl()
+                    .overflow_hidden()
+                    // Header
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_b_1()
+                            .border_color(ShellDeckColors::border())
+                            .child(
+                                div()
+                                    .text_size(px(16.0))
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .child(t!("connection_import.title").to_string()),
+                            )
+                            .child(
+                                div()
+                                    .id("close-connection-import")
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .cursor_pointer()
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                                    .on_click(cx.listener(|_this, _: &ClickEvent, _, cx| {
+                                        cx.emit(ConnectionImportEvent::Cancel);
+                                    }))
+                                    .child(
+                                        svg()
+                                            .path("icons/lucide/x.svg")
+                                            .size(px(14.0))
+                                            .text_color(ShellDeckColors::text_muted()),
+                                    ),
+                            ),
+                    )
+                    // Source picker
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(px(10.0))
+                            .px(px(16.0))
+                            .py(px(10.0))
+                            .border_b_1()
+                            .border_color(ShellDeckColors::border())
+                            .child(
+                                div()
+                                    .id("connection-import-browse")
+                                    .flex_shrink_0()
+                                    .cursor_pointer()
+                                    .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                                        this.browse(cx);
+                                    }))
+                                    .child(
+                                        Button::new(
+                                            "connection-import-browse-btn",
+                                            t!("connection_import.choose_file").to_string(),
+                                        )
+                                        .variant(ButtonVariant::Outline),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .flex_grow()
+                                    .min_w_0()
+                                    .truncate()
+                                    .text_size(px(12.0))
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .child(source_label),
+                            ),
+                    )
+                    .child(list)
+                    // Footer
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .gap(px(8.0))
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(ShellDeckColors::border())
+                            .child(
+                                div()
+                                    .text_size(px(12.0))
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .child(
+                                        t!(
+                                            "connection_import.selected",
+                                            selected = selected,
+                                            total = self.candidates.len()
+                                        )
+                                        .to_string(),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(8.0))
+                                    .child(
+                                        div()
+                                            .id("connection-import-cancel")
+                                            .on_click(cx.listener(
+                                                |_this, _: &ClickEvent, _, cx| {
+                                                    cx.emit(ConnectionImportEvent::Cancel);
+                                                },
+                                            ))
+                                            .child(
+                                                Button::new(
+                                                    "connection-import-cancel-btn",
+                                                    t!("scripts.cancel").to_string(),
+                                                )
+                                                .variant(ButtonVariant::Ghost),
+                                            ),
+                                    )
+                                    .child(import_btn),
+                            ),
+                    ),
+            )
+    }
+}
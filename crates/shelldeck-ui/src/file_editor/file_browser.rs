@@ -1,5 +1,8 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::fs::{local_fs, DirChild, EditorFs, SKIPPED_DIRS};
 
 const MAX_DEPTH: usize = 20;
 
@@ -15,8 +18,17 @@ pub struct FileEntry {
 
 /// File browser panel state. Manages a tree of directories/files.
 pub struct FileBrowserPanel {
+    fs: Arc<dyn EditorFs>,
     root: PathBuf,
     expanded_dirs: HashSet<PathBuf>,
+    /// Listings of remote directories. Local ones are read on every render
+    /// so the tree follows the disk; a server is only asked once per
+    /// directory (and again on refresh).
+    listings: HashMap<PathBuf, Vec<DirChild>>,
+    loading: HashSet<PathBuf>,
+    /// Bumped on every re-root so listings fetched for an old root are
+    /// dropped.
+    generation: u64,
 }
 
 impl Default for FileBrowserPanel {
@@ -31,8 +43,12 @@ impl FileBrowserPanel {
         let mut expanded = HashSet::new();
         expanded.insert(root.clone());
         Self {
+            fs: local_fs(),
             root,
             expanded_dirs: expanded,
+            listings: HashMap::new(),
+            loading: HashSet::new(),
+            generation: 0,
         }
     }
 
@@ -40,12 +56,29 @@ impl FileBrowserPanel {
         &self.root
     }
 
+    pub fn fs(&self) -> &Arc<dyn EditorFs> {
+        &self.fs
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.fs.connection_id().is_some()
+    }
+
     pub fn set_root(&mut self, root: PathBuf) {
         self.expanded_dirs.clear();
         self.expanded_dirs.insert(root.clone());
+        self.listings.clear();
+        self.loading.clear();
+        self.generation += 1;
         self.root = root;
     }
 
+    /// Root the tree at `root` on another filesystem.
+    pub fn set_fs(&mut self, fs: Arc<dyn EditorFs>, root: PathBuf) {
+        self.fs = fs;
+        self.set_root(root);
+    }
+
     pub fn toggle_dir(&mut self, path: &Path) {
         if self.expanded_dirs.contains(path) {
             self.expanded_dirs.remove(path);
@@ -54,6 +87,47 @@ impl FileBrowserPanel {
         }
     }
 
+    /// Forget the remote listings so expanded directories are fetched again.
+    pub fn refresh(&mut self) {
+        self.listings.clear();
+        self.loading.clear();
+        self.generation += 1;
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Expanded remote directories with no listing yet, marked as loading.
+    pub fn take_missing_listings(&mut self) -> Vec<PathBuf> {
+        if !self.is_remote() {
+            return Vec::new();
+        }
+        let missing: Vec<PathBuf> = self
+            .expanded_dirs
+            .iter()
+            .filter(|dir| !self.listings.contains_key(*dir) && !self.loading.contains(*dir))
+            .cloned()
+            .collect();
+        self.loading.extend(missing.iter().cloned());
+        missing
+    }
+
+    /// Store a listing fetched under `generation`; a failed fetch shows the
+    /// directory empty. Returns false when the tree was re-rooted since.
+    pub fn set_listing(&mut self, generation: u64, dir: PathBuf, children: Vec<DirChild>) -> bool {
+        if generation != self.generation {
+            return false;
+        }
+        self.loading.remove(&dir);
+        self.listings.insert(dir, children);
+        true
+    }
+
+    pub fn is_loading(&self) -> bool {
+        !self.loading.is_empty()
+    }
+
     /// Returns a flat list of visible entries for rendering.
     pub fn visible_entries(&self) -> Vec<FileEntry> {
         let mut entries = Vec::new();
@@ -61,6 +135,24 @@ impl FileBrowserPanel {
         entries
     }
 
+    fn children(&self, dir: &Path) -> Vec<DirChild> {
+        if self.is_remote() {
+            self.listings.get(dir).cloned().unwrap_or_default()
+        } else {
+            // Skip hidden files/dirs on this computer; on a server the
+            // dotfiles (`.env`, `.htaccess`) are what people come to edit.
+            self.fs
+                .read_dir(dir)
+                .map(|children| {
+                    children
+                        .into_iter()
+                        .filter(|c| !c.name.starts_with('.'))
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+    }
+
     fn collect_entries(&self, dir: &Path, depth: usize, entries: &mut Vec<FileEntry>) {
         if depth > MAX_DEPTH {
             return;
@@ -86,55 +178,24 @@ impl FileBrowserPanel {
             return;
         }
 
-        // Read directory entries
-        let mut dirs = Vec::new();
-        let mut files = Vec::new();
-
-        if let Ok(read_dir) = std::fs::read_dir(dir) {
-            for entry in read_dir.flatten() {
-                let path = entry.path();
-                let name = entry.file_name().to_str().unwrap_or("?").to_string();
-
-                // Skip hidden files/dirs
-                if name.starts_with('.') {
-                    continue;
-                }
-                // Skip common non-useful directories
-                if path.is_dir()
-                    && matches!(
-                        name.as_str(),
-                        "target" | "node_modules" | "__pycache__" | ".git" | "dist" | "build"
-                    )
-                {
-                    continue;
-                }
-
-                if path.is_dir() {
-                    dirs.push((name, path));
-                } else {
-                    files.push((name, path));
-                }
+        // Directories come first, each group sorted by name
+        let children = self.children(dir);
+        for child in children.iter().filter(|c| c.is_dir) {
+            // Skip common non-useful directories
+            if SKIPPED_DIRS.contains(&child.name.as_str()) {
+                continue;
             }
-        }
-
-        // Sort alphabetically
-        dirs.sort_by_key(|a| a.0.to_lowercase());
-        files.sort_by_key(|a| a.0.to_lowercase());
-
-        // Directories first
-        for (_name, path) in dirs {
-            self.collect_entries(&path, depth + 1, entries);
+            self.collect_entries(&child.path, depth + 1, entries);
         }
 
         // Then files
-        let entry_depth = if depth == 0 { 0 } else { depth };
-        for (name, path) in files {
+        for child in children.into_iter().filter(|c| !c.is_dir) {
             entries.push(FileEntry {
-                path,
-                name,
+                path: child.path,
+                name: child.name,
                 is_dir: false,
                 is_expanded: false,
-                depth: entry_depth,
+                depth,
             });
         }
     }
@@ -0,0 +1,756 @@
+//! Filesystems the editor browses, opens and saves on: this computer, or a
+//! server over SSH. Every method blocks, so callers run them on the
+//! background executor.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::file_transfer::join_remote;
+use shelldeck_core::util::shell_escape;
+use shelldeck_ssh::client::SshClient;
+use shelldeck_ssh::session::{ExecResult, SshSession};
+use uuid::Uuid;
+
+/// Directories the browser and find-in-files never descend into.
+pub const SKIPPED_DIRS: &[&str] = &[
+    "target",
+    "node_modules",
+    "__pycache__",
+    ".git",
+    "dist",
+    "build",
+];
+
+const MAX_RESULTS: usize = 1000;
+const MAX_FILES: usize = 8000;
+const MAX_FILE_SIZE: u64 = 1_048_576; // 1 MB
+const MAX_DEPTH: usize = 32;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Exit status the write scripts use for "not writable by this user".
+const EXIT_NOT_WRITABLE: u32 = 77;
+/// Exit status the write scripts use for "sudo wants a (valid) password".
+const EXIT_SUDO_AUTH: u32 = 78;
+
+/// One entry of a directory listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirChild {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+/// Modification time (seconds since the epoch) and size of a file, taken
+/// when it is opened and checked again before saving over it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub modified: i64,
+    pub size: u64,
+}
+
+/// One find-in-files match: a file, 1-based line, and a trimmed preview of
+/// the line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FifMatch {
+    pub path: PathBuf,
+    pub line: usize,
+    pub preview: String,
+}
+
+/// Why a save did not go through.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveError {
+    /// The file changed since it was opened.
+    Conflict,
+    /// The file (or its directory) is not writable by the login user.
+    PermissionDenied,
+    /// sudo needs a password, or the one given was wrong.
+    SudoPassword,
+    Other(String),
+}
+
+pub trait EditorFs: Send + Sync {
+    /// Connection the files live on; `None` for this computer.
+    fn connection_id(&self) -> Option<Uuid>;
+
+    /// `user@host` for a server, `None` for this computer.
+    fn host(&self) -> Option<String>;
+
+    /// `name` inside `dir`, with the separators of this filesystem.
+    fn join(&self, dir: &Path, name: &str) -> PathBuf;
+
+    /// Entries of `dir`, directories first, each group sorted by name.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<DirChild>>;
+
+    fn read(&self, path: &Path) -> io::Result<(Vec<u8>, FileStamp)>;
+
+    /// Stamp of `path`, `None` when it does not exist.
+    fn stamp(&self, path: &Path) -> io::Result<Option<FileStamp>>;
+
+    /// Replace the content of `path`, through sudo when `sudo` is set (an
+    /// empty password relies on NOPASSWD or a cached sudo login).
+    fn write(&self, path: &Path, data: &[u8], sudo: Option<&str>) -> Result<FileStamp, SaveError>;
+
+    fn find_in_files(&self, root: &Path, needle: &str, case_sensitive: bool) -> Vec<FifMatch>;
+}
+
+/// Save `data` unless the file changed since it was stamped `expected`.
+pub fn save(
+    fs: &dyn EditorFs,
+    path: &Path,
+    data: &[u8],
+    expected: Option<FileStamp>,
+    sudo: Option<&str>,
+) -> Result<FileStamp, SaveError> {
+    if let Some(expected) = expected {
+        let current = fs
+            .stamp(path)
+            .map_err(|e| SaveError::Other(e.to_string()))?;
+        if current != Some(expected) {
+            return Err(SaveError::Conflict);
+        }
+    }
+    fs.write(path, data, sudo)
+}
+
+fn sort_children(children: &mut [DirChild]) {
+    children.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+}
+
+fn preview(line: &str) -> String {
+    line.trim_start().chars().take(160).collect()
+}
+
+// ---------------------------------------------------------------------------
+// This computer
+// ---------------------------------------------------------------------------
+
+pub struct LocalFs;
+
+static LOCAL_FS: Lazy<Arc<dyn EditorFs>> = Lazy::new(|| Arc::new(LocalFs));
+
+/// The shared local filesystem.
+pub fn local_fs() -> Arc<dyn EditorFs> {
+    LOCAL_FS.clone()
+}
+
+fn local_stamp(meta: &std::fs::Metadata) -> FileStamp {
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    FileStamp {
+        modified,
+        size: meta.len(),
+    }
+}
+
+impl LocalFs {
+    fn walk(
+        dir: &Path,
+        needle: &str,
+        case_sensitive: bool,
+        out: &mut Vec<FifMatch>,
+        files_scanned: &mut usize,
+        depth: usize,
+    ) {
+        if out.len() >= MAX_RESULTS || *files_scanned >= MAX_FILES || depth > MAX_DEPTH {
+            return;
+        }
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in read_dir.flatten() {
+            if out.len() >= MAX_RESULTS || *files_scanned >= MAX_FILES {
+                return;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            // Use the dir-entry file type (does NOT follow symlinks) so symlink
+            // cycles can't cause infinite recursion / a stack overflow.
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_symlink() {
+                continue;
+            }
+            let path = entry.path();
+            if file_type.is_dir() {
+                if SKIPPED_DIRS.contains(&name.as_str()) {
+                    continue;
+                }
+                Self::walk(&path, needle, case_sensitive, out, files_scanned, depth + 1);
+            } else if file_type.is_file() {
+                if let Ok(meta) = entry.metadata() {
+                    if meta.len() > MAX_FILE_SIZE {
+                        continue;
+                    }
+                }
+                *files_scanned += 1;
+                let Ok(content) = std::fs::read_to_string(&path) else {
+                    continue; // non-UTF-8 / unreadable → skip (binary)
+                };
+                for (li, line) in content.lines().enumerate() {
+                    let matched = if case_sensitive {
+                        line.contains(needle)
+                    } else {
+                        line.to_lowercase().contains(needle)
+                    };
+                    if matched {
+                        out.push(FifMatch {
+                            path: path.clone(),
+                            line: li + 1,
+                            preview: preview(line),
+                        });
+                        if out.len() >= MAX_RESULTS {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl EditorFs for LocalFs {
+    fn connection_id(&self) -> Option<Uuid> {
+        None
+    }
+
+    fn host(&self) -> Option<String> {
+        None
+    }
+
+    fn join(&self, dir: &Path, name: &str) -> PathBuf {
+        dir.join(name)
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<DirChild>> {
+        let mut children: Vec<DirChild> = std::fs::read_dir(dir)?
+            .flatten()
+            .map(|entry| {
+                let path = entry.path();
+                DirChild {
+                    name: entry.file_name().to_string_lossy().into_owned(),
+                    is_dir: path.is_dir(),
+                    path,
+                }
+            })
+            .collect();
+        sort_children(&mut children);
+        Ok(children)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<(Vec<u8>, FileStamp)> {
+        let data = std::fs::read(path)?;
+        let stamp = local_stamp(&std::fs::metadata(path)?);
+        Ok((data, stamp))
+    }
+
+    fn stamp(&self, path: &Path) -> io::Result<Option<FileStamp>> {
+        match std::fs::metadata(path) {
+            Ok(meta) => Ok(Some(local_stamp(&meta))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&self, path: &Path, data: &[u8], sudo: Option<&str>) -> Result<FileStamp, SaveError> {
+        if sudo.is_some() {
+            return Err(SaveError::Other(
+                "sudo saves are only available on servers".to_string(),
+            ));
+        }
+        std::fs::write(path, data).map_err(|e| SaveError::Other(e.to_string()))?;
+        std::fs::metadata(path)
+            .map(|meta| local_stamp(&meta))
+            .map_err(|e| SaveError::Other(e.to_string()))
+    }
+
+    fn find_in_files(&self, root: &Path, needle: &str, case_sensitive: bool) -> Vec<FifMatch> {
+        let mut results = Vec::new();
+        let mut files_scanned = 0usize;
+        Self::walk(
+            root,
+            needle,
+            case_sensitive,
+            &mut results,
+            &mut files_scanned,
+            0,
+        );
+        results
+    }
+}
+
+// ---------------------------------------------------------------------------
+// A server over SSH
+// ---------------------------------------------------------------------------
+
+/// Files on a server, reached through one SSH session that is opened on
+/// first use and reopened when it drops.
+pub struct SshFs {
+    connection: Connection,
+    runtime: tokio::runtime::Runtime,
+    session: tokio::sync::Mutex<Option<SshSession>>,
+}
+
+impl SshFs {
+    pub fn new(connection: Connection) -> io::Result<Self> {
+        // One worker keeps the session's background tasks running between
+        // the calls that block on it.
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("editor-ssh")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            connection,
+            runtime,
+            session: tokio::sync::Mutex::new(None),
+        })
+    }
+
+    /// Run `command` with `input` on stdin, reconnecting once if the
+    /// session went away.
+    fn exec(&self, command: &str, input: &[u8]) -> io::Result<ExecResult> {
+        self.runtime.block_on(async {
+            let mut session = self.session.lock().await;
+            let mut last_error = String::new();
+            for _ in 0..2 {
+                if session.is_none() {
+                    let connect = SshClient::new().connect(&self.connection);
+                    match tokio::time::timeout(CONNECT_TIMEOUT, connect).await {
+                        Ok(Ok(connected)) => *session = Some(connected),
+                        Ok(Err(e)) => return Err(io::Error::other(e.to_string())),
+                        Err(_) => {
+                            return Err(io::Error::other(format!(
+                                "Connection to {} timed out",
+                                self.connection.display_name()
+                            )))
+                        }
+                    }
+                }
+                let Some(connected) = session.as_ref() else {
+                    break;
+                };
+                match connected.exec_with_stdin(command, input).await {
+                    Ok(result) => return Ok(result),
+                    Err(e) => {
+                        last_error = e.to_string();
+                        *session = None;
+                    }
+                }
+            }
+            Err(io::Error::other(last_error))
+        })
+    }
+
+    /// Run `command`, failing with what it printed when it exits non-zero.
+    fn run(&self, command: &str) -> io::Result<Vec<u8>> {
+        let result = self.exec(command, &[])?;
+        if result.success() {
+            Ok(result.stdout)
+        } else {
+            Err(command_error(&result))
+        }
+    }
+}
+
+fn command_error(result: &ExecResult) -> io::Error {
+    let stderr = result.stderr_string().trim().to_string();
+    if stderr.is_empty() {
+        io::Error::other(format!("exit status {}", result.exit_code.unwrap_or(1)))
+    } else {
+        io::Error::other(stderr)
+    }
+}
+
+fn remote_path(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+impl EditorFs for SshFs {
+    fn connection_id(&self) -> Option<Uuid> {
+        Some(self.connection.id)
+    }
+
+    fn host(&self) -> Option<String> {
+        Some(format!(
+            "{}@{}",
+            self.connection.user, self.connection.hostname
+        ))
+    }
+
+    fn join(&self, dir: &Path, name: &str) -> PathBuf {
+        PathBuf::from(join_remote(&remote_path(dir), name))
+    }
+
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<DirChild>> {
+        let dir = remote_path(dir);
+        let output = self.run(&list_command(&dir))?;
+        let mut children = parse_listing(&dir, &String::from_utf8_lossy(&output));
+        sort_children(&mut children);
+        Ok(children)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<(Vec<u8>, FileStamp)> {
+        let output = self.run(&read_command(&remote_path(path)))?;
+        split_stamped(&output).ok_or_else(|| io::Error::other("unexpected stat output"))
+    }
+
+    fn stamp(&self, path: &Path) -> io::Result<Option<FileStamp>> {
+        let output = self.run(&stamp_command(&remote_path(path)))?;
+        let text = String::from_utf8_lossy(&output);
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        parse_stamp(&text)
+            .map(Some)
+            .ok_or_else(|| io::Error::other("unexpected stat output"))
+    }
+
+    fn write(&self, path: &Path, data: &[u8], sudo: Option<&str>) -> Result<FileStamp, SaveError> {
+        let path = remote_path(path);
+        let other = |e: io::Error| SaveError::Other(e.to_string());
+        let result = match sudo {
+            None => self.exec(&write_command(&path), data).map_err(other)?,
+            Some(password) => {
+                // The content waits in a temporary file so stdin is free
+                // for the sudo password.
+                let temp = self.exec(STAGE_COMMAND, data).map_err(other)?;
+                if !temp.success() {
+                    return Err(other(command_error(&temp)));
+                }
+                let temp = temp.stdout_string().trim().to_string();
+                let input = if password.is_empty() {
+                    Vec::new()
+                } else {
+                    format!("{}\n", password).into_bytes()
+                };
+                self.exec(
+                    &sudo_write_command(&temp, &path, !password.is_empty()),
+                    &input,
+                )
+                .map_err(other)?
+            }
+        };
+        match result.exit_code {
+            Some(0) => parse_stamp(&result.stdout_string())
+                .ok_or_else(|| SaveError::Other("unexpected stat output".to_string())),
+            Some(EXIT_NOT_WRITABLE) => Err(SaveError::PermissionDenied),
+            Some(EXIT_SUDO_AUTH) => Err(SaveError::SudoPassword),
+            _ => Err(other(command_error(&result))),
+        }
+    }
+
+    fn find_in_files(&self, root: &Path, needle: &str, case_sensitive: bool) -> Vec<FifMatch> {
+        // grep exits 1 when nothing matched; only the output matters here.
+        match self.exec(
+            &grep_command(&remote_path(root), needle, case_sensitive),
+            &[],
+        ) {
+            Ok(result) => parse_grep(&result.stdout),
+            Err(e) => {
+                tracing::warn!("Remote find in files failed: {}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Remote commands
+// ---------------------------------------------------------------------------
+
+/// Prints `<mtime> <size>` of `$p`, nothing when it does not exist. GNU
+/// stat first, BSD stat as the fallback.
+const STAT_SNIPPET: &str = "if [ -e \"$p\" ]; then \
+     stat -c '%Y %s' -- \"$p\" 2>/dev/null || stat -f '%m %z' -- \"$p\"; fi";
+
+/// Copy stdin into a new temporary file and print its path.
+const STAGE_COMMAND: &str = "t=$(mktemp) && cat > \"$t\" && printf '%s\\n' \"$t\"";
+
+/// One `d|f<TAB>name` line per entry of `dir`, dotfiles included.
+pub fn list_command(dir: &str) -> String {
+    format!(
+        "cd -- {} && for f in * .[!.]* ..?*; do \
+         [ -e \"$f\" ] || [ -L \"$f\" ] || continue; \
+         if [ -d \"$f\" ]; then printf 'd\\t%s\\n' \"$f\"; else printf 'f\\t%s\\n' \"$f\"; fi; \
+         done",
+        shell_escape(dir)
+    )
+}
+
+pub fn parse_listing(dir: &str, output: &str) -> Vec<DirChild> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (kind, name) = line.split_once('\t')?;
+            if name.is_empty() || name == "." || name == ".." {
+                return None;
+            }
+            Some(DirChild {
+                name: name.to_string(),
+                path: PathBuf::from(join_remote(dir, name)),
+                is_dir: kind == "d",
+            })
+        })
+        .collect()
+}
+
+pub fn stamp_command(path: &str) -> String {
+    format!("p={}; {}", shell_escape(path), STAT_SNIPPET)
+}
+
+/// The stamp line, then the content.
+pub fn read_command(path: &str) -> String {
+    format!(
+        "p={}; [ -f \"$p\" ] || {{ echo \"$p: not a regular file\" >&2; exit 1; }}; {} && cat -- \"$p\"",
+        shell_escape(path),
+        STAT_SNIPPET
+    )
+}
+
+pub fn parse_stamp(text: &str) -> Option<FileStamp> {
+    let mut parts = text.split_whitespace();
+    let modified = parts.next()?.parse().ok()?;
+    let size = parts.next()?.parse().ok()?;
+    Some(FileStamp { modified, size })
+}
+
+/// Split what `read_command` printed into content and stamp.
+pub fn split_stamped(output: &[u8]) -> Option<(Vec<u8>, FileStamp)> {
+    let newline = output.iter().position(|&b| b == b'\n')?;
+    let stamp = parse_stamp(std::str::from_utf8(&output[..newline]).ok()?)?;
+    Some((output[newline + 1..].to_vec(), stamp))
+}
+
+/// Overwrite `path` in place with stdin (keeping its owner and mode), then
+/// print its new stamp. Exits 77 when the login user may not write it.
+/// stdin is staged next to `path` first, so a broken upload or a full disk
+/// never leaves the file truncated.
+pub fn write_command(path: &str) -> String {
+    format!(
+        "p={}; d=$(dirname -- \"$p\"); \
+         if [ -e \"$p\" ]; then [ -w \"$p\" ] || exit {code}; else [ -w \"$d\" ] || exit {code}; fi; \
+         t=$(umask 077 && {{ mktemp \"$d/.shelldeck-edit.XXXXXX\" 2>/dev/null || mktemp; }}) || exit 1; \
+         cat > \"$t\" && cat -- \"$t\" > \"$p\"; rc=$?; rm -f -- \"$t\"; \
+         [ $rc -eq 0 ] || exit $rc; {}",
+        shell_escape(path),
+        STAT_SNIPPET,
+        code = EXIT_NOT_WRITABLE
+    )
+}
+
+/// Copy the staged `temp` file over `path` as root and remove it. With
+/// `password`, sudo reads it from stdin; exits 78 when sudo refuses. Like
+/// [`write_command`], the content is copied next to `path` before `path`
+/// is truncated.
+pub fn sudo_write_command(temp: &str, path: &str, password: bool) -> String {
+    let auth = if password {
+        "sudo -S -p '' true"
+    } else {
+        "sudo -n true"
+    };
+    format!(
+        "t={}; p={}; \
+         if ! {} 2>/dev/null; then rm -f -- \"$t\"; exit {}; fi; \
+         sudo -n sh -c 'n=$(umask 077 && mktemp \"$(dirname -- \"$2\")/.shelldeck-edit.XXXXXX\") && \
+         cat -- \"$1\" > \"$n\" && cat -- \"$n\" > \"$2\"; rc=$?; rm -f -- \"$n\"; exit $rc' \
+         sh \"$t\" \"$p\"; rc=$?; rm -f -- \"$t\"; \
+         [ $rc -eq 0 ] || exit $rc; {}",
+        shell_escape(temp),
+        shell_escape(path),
+        auth,
+        EXIT_SUDO_AUTH,
+        STAT_SNIPPET
+    )
+}
+
+/// Fixed-string grep under `root`, text files only, NUL after each file
+/// name so names with `:` split cleanly.
+pub fn grep_command(root: &str, needle: &str, case_sensitive: bool) -> String {
+    let excludes: String = SKIPPED_DIRS
+        .iter()
+        .map(|dir| format!(" --exclude-dir={}", shell_escape(dir)))
+        .collect();
+    format!(
+        "grep -rnIF --null{}{} -e {} -- {} 2>/dev/null | head -n {}",
+        if case_sensitive { "" } else { " -i" },
+        excludes,
+        shell_escape(needle),
+        shell_escape(root),
+        MAX_RESULTS
+    )
+}
+
+/// Parse `path\0line:text` lines.
+pub fn parse_grep(output: &[u8]) -> Vec<FifMatch> {
+    output
+        .split(|&b| b == b'\n')
+        .filter_map(|line| {
+            let nul = line.iter().position(|&b| b == 0)?;
+            let path = String::from_utf8_lossy(&line[..nul]).into_owned();
+            let rest = String::from_utf8_lossy(&line[nul + 1..]);
+            let (number, text) = rest.split_once(':')?;
+            Some(FifMatch {
+                path: PathBuf::from(path),
+                line: number.parse().ok()?,
+                preview: preview(text),
+            })
+        })
+        .take(MAX_RESULTS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn sh(command: &str, input: &[u8]) -> (u32, Vec<u8>) {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(input).unwrap();
+        let output = child.wait_with_output().unwrap();
+        (output.status.code().unwrap_or(1) as u32, output.stdout)
+    }
+
+    #[test]
+    fn listing_and_grep_output_parse() {
+        let children = parse_listing("/etc/nginx", "d\tsites-enabled\nf\t.env\nf\tnginx.conf\n");
+        assert_eq!(children.len(), 3);
+        assert_eq!(children[1].path, PathBuf::from("/etc/nginx/.env"));
+        assert!(children[0].is_dir);
+
+        let matches = parse_grep(b"/srv/app/a:b.conf\x0012:  listen 80;\n/srv/x\x00bad\n");
+        assert_eq!(
+            matches,
+            vec![FifMatch {
+                path: PathBuf::from("/srv/app/a:b.conf"),
+                line: 12,
+                preview: "listen 80;".to_string(),
+            }]
+        );
+        assert_eq!(
+            split_stamped(b"1700000000 5\nhello"),
+            Some((
+                b"hello".to_vec(),
+                FileStamp {
+                    modified: 1_700_000_000,
+                    size: 5
+                }
+            ))
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn remote_commands_run_through_sh() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().into_owned();
+        std::fs::create_dir(dir.path().join("conf.d")).unwrap();
+        std::fs::write(dir.path().join(".env"), "APP_KEY=secret\n").unwrap();
+
+        let (code, output) = sh(&list_command(&root), &[]);
+        assert_eq!(code, 0);
+        let mut names: Vec<String> = parse_listing(&root, &String::from_utf8_lossy(&output))
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![".env", "conf.d"]);
+
+        let file = format!("{}/site.conf", root);
+        let (code, output) = sh(&write_command(&file), b"server {}\n");
+        assert_eq!(code, 0);
+        let stamp = parse_stamp(&String::from_utf8_lossy(&output)).unwrap();
+        assert_eq!(stamp.size, 10);
+        // Rewritten in place: the mode survives and no staging file is left.
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::Permissions::from_mode(0o640);
+            std::fs::set_permissions(&file, mode).unwrap();
+            let (code, _) = sh(&write_command(&file), b"server {}\n");
+            assert_eq!(code, 0);
+            let meta = std::fs::metadata(&file).unwrap();
+            assert_eq!(meta.permissions().mode() & 0o777, 0o640);
+            assert!(std::fs::read_dir(dir.path()).unwrap().all(|e| !e
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .starts_with(".shelldeck-edit")));
+        }
+
+        let (_, output) = sh(&read_command(&file), &[]);
+        assert_eq!(
+            split_stamped(&output),
+            Some((b"server {}\n".to_vec(), stamp))
+        );
+        let (_, output) = sh(&stamp_command(&format!("{}/missing", root)), &[]);
+        assert!(output.is_empty());
+
+        let (_, output) = sh(&grep_command(&root, "app_key", false), &[]);
+        let matches = parse_grep(&output);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].line, 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_files_ask_for_sudo() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("locked.conf");
+        std::fs::write(&file, "x").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o444)).unwrap();
+        // root writes anything; the check only means something for users.
+        if sh("[ \"$(id -u)\" = 0 ]", &[]).0 == 0 {
+            return;
+        }
+        let (code, _) = sh(&write_command(&file.to_string_lossy()), b"y");
+        assert_eq!(code, EXIT_NOT_WRITABLE);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "x");
+    }
+
+    #[test]
+    fn local_saves_refuse_to_clobber_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "one").unwrap();
+        let fs = LocalFs;
+        let (data, stamp) = fs.read(&file).unwrap();
+        assert_eq!(data, b"one");
+
+        let saved = save(&fs, &file, b"two!", Some(stamp), None).unwrap();
+        assert_eq!(saved.size, 4);
+        // Someone else rewrote it in between.
+        std::fs::write(&file, "three").unwrap();
+        assert_eq!(
+            save(&fs, &file, b"four", Some(saved), None),
+            Err(SaveError::Conflict)
+        );
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "three");
+        assert!(save(&fs, &file, b"four", None, None).is_ok());
+    }
+}
@@ -25,6 +25,11 @@ impl FileEditorView {
             return;
         }
 
+        // ---- Save prompts: sudo password / conflict ----
+        if self.handle_save_prompt_key(event, cx) {
+            return;
+        }
+
         // ---- Non-text tabs: only allow Ctrl+W (close) and Ctrl+B (file browser toggle) ----
         if !self.active_tab().is_some_and(|t| t.is_text()) {
             if ctrl {
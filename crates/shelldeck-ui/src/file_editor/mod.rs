@@ -1,5 +1,6 @@
 pub mod buffer;
 pub mod file_browser;
+pub mod fs;
 pub mod highlighter;
 pub mod input;
 pub mod view;
@@ -7,6 +7,7 @@ use uuid::Uuid;
 
 use super::buffer::RopeBuffer;
 use super::file_browser::FileBrowserPanel;
+use super::fs::{local_fs, EditorFs, FifMatch, FileStamp};
 use super::highlighter::{HighlightSpan, SyntaxHighlighter};
 use super::{EditorLanguage, FileKind};
 use crate::glyph_cache::GlyphCache;
@@ -17,6 +18,7 @@ mod canvas;
 mod chrome;
 mod find_in_files;
 mod previews;
+mod remote;
 mod search;
 mod tab;
 
@@ -212,15 +214,12 @@ pub struct FileEditorView {
     pub(crate) fif_selected: usize,
     /// True while a background find-in-files walk is in progress.
     pub(crate) fif_searching: bool,
-}
-
-/// One find-in-files match: a file, 1-based line, byte column, and a trimmed
-/// preview of the line.
-#[derive(Clone)]
-pub(crate) struct FifMatch {
-    pub path: PathBuf,
-    pub line: usize,
-    pub preview: String,
+    // Saving and opening through the filesystem layer
+    pub(crate) saving: std::collections::HashSet<Uuid>,
+    pub(crate) save_conflict: Option<remote::PendingSave>,
+    pub(crate) sudo_prompt: Option<remote::SudoPrompt>,
+    /// Last failed open/save/listing, shown until dismissed.
+    pub(crate) fs_error: Option<String>,
 }
 
 impl EventEmitter<FileEditorEvent> for FileEditorView {}
@@ -275,6 +274,10 @@ impl FileEditorView {
             fif_results: Vec::new(),
             fif_selected: 0,
             fif_searching: false,
+            saving: std::collections::HashSet::new(),
+            save_conflict: None,
+            sudo_prompt: None,
+            fs_error: None,
             canvas_origin: std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0)),
             canvas_height: std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0)),
         };
@@ -295,15 +298,21 @@ impl FileEditorView {
         self.tabs.get_mut(self.active_tab_index)
     }
 
+    /// Open `path` from the file browser's filesystem.
     pub fn open_file(&mut self, path: PathBuf, cx: &mut Context<Self>) {
+        let fs = self.file_browser.fs().clone();
         // Check if already open
         for (i, tab) in self.tabs.iter().enumerate() {
-            if tab.path.as_ref() == Some(&path) {
+            if tab.path.as_ref() == Some(&path) && tab.fs.connection_id() == fs.connection_id() {
                 self.active_tab_index = i;
                 cx.notify();
                 return;
             }
         }
+        if fs.connection_id().is_some() {
+            self.open_remote_file(fs, path, cx);
+            return;
+        }
 
         let filename = path
             .file_name()
@@ -328,8 +337,9 @@ impl FileEditorView {
                 EditorTab::from_binary(path, BinaryInfo { file_size })
             }
             FileKind::Text => {
+                let stamp = fs.stamp(&path).ok().flatten();
                 match std::fs::read_to_string(&path) {
-                    Ok(content) => EditorTab::from_file(path, &content),
+                    Ok(content) => EditorTab::from_file(path, &content).on_fs(fs, stamp),
                     Err(_) => {
                         // UTF-8 decode failed — treat as binary
                         let file_size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
@@ -339,6 +349,12 @@ impl FileEditorView {
             }
         };
 
+        self.push_tab(tab, cx);
+    }
+
+    /// Show `tab`, in place of the lone empty untitled tab if that is all
+    /// there is.
+    pub(crate) fn push_tab(&mut self, tab: EditorTab, cx: &mut Context<Self>) {
         // Replace empty untitled tab instead of adding alongside it
         let replace_empty = self.tabs.len() == 1
             && self.tabs[0].path.is_none()
@@ -356,20 +372,12 @@ impl FileEditorView {
     }
 
     pub fn save_file(&mut self, cx: &mut Context<Self>) {
-        if let Some(tab) = self.tabs.get_mut(self.active_tab_index) {
-            if let (Some(ref path), TabContent::Text { buffer, .. }) = (&tab.path, &mut tab.content)
-            {
-                let content = buffer.text();
-                match std::fs::write(path, &content) {
-                    Ok(()) => {
-                        buffer.set_dirty(false);
-                        cx.notify();
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to save file: {}", e);
-                    }
-                }
-            }
+        if let Some(tab) = self.tabs.get(self.active_tab_index) {
+            let save = remote::PendingSave {
+                tab_id: tab.id,
+                close_after: false,
+            };
+            self.save_tab(save, true, None, cx);
         }
     }
 
@@ -401,25 +409,21 @@ impl FileEditorView {
     }
 
     pub fn save_and_close_tab(&mut self, index: usize, cx: &mut Context<Self>) {
-        // Save first (only for text tabs)
-        if let Some(tab) = self.tabs.get_mut(index) {
-            if let (Some(ref path), TabContent::Text { buffer, .. }) = (&tab.path, &mut tab.content)
-            {
-                let content = buffer.text();
-                match std::fs::write(path, &content) {
-                    Ok(()) => {
-                        buffer.set_dirty(false);
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to save file: {}", e);
-                        self.pending_close_tab = None;
-                        cx.notify();
-                        return;
-                    }
-                }
-            }
+        self.pending_close_tab = None;
+        let Some(tab) = self.tabs.get(index) else {
+            return;
+        };
+        // Only text tabs with a path have anything to save; the tab closes
+        // once the save went through.
+        if tab.is_text() && tab.path.is_some() {
+            let save = remote::PendingSave {
+                tab_id: tab.id,
+                close_after: true,
+            };
+            self.save_tab(save, true, None, cx);
+        } else {
+            self.force_close_tab(index, cx);
         }
-        self.force_close_tab(index, cx);
     }
 
     // -----------------------------------------------------------------------
@@ -792,6 +796,9 @@ impl Render for FileEditorView {
             container = container.child(warning_bar);
         }
 
+        // Save conflicts, sudo password and filesystem errors
+        container = container.children(self.render_save_prompts(cx));
+
         // Main editor area: file browser + editor canvas
         let h_resize_move = cx.entity().downgrade();
         let h_resize_up = cx.entity().downgrade();
@@ -842,21 +849,7 @@ impl Render for FileEditorView {
                 .border_color(ShellDeckColors::border())
                 .flex_shrink_0();
 
-            // Browser header
-            let browser_header = div()
-                .flex()
-                .items_center()
-                .w_full()
-                .h(s(28.0))
-                .px(s(8.0))
-                .border_b_1()
-                .border_color(ShellDeckColors::border())
-                .text_size(s(11.0))
-                .font_weight(FontWeight::BOLD)
-                .text_color(ShellDeckColors::text_muted())
-                .child(t!("file_editor.browser.files").to_string());
-
-            browser_panel = browser_panel.child(browser_header);
+            browser_panel = browser_panel.child(self.render_browser_header(cx));
 
             // File entries (scrollable)
             let mut file_list = div()
@@ -913,6 +906,7 @@ impl Render for FileEditorView {
                         view.update(cx, |this, cx| {
                             if is_dir {
                                 this.file_browser.toggle_dir(&p);
+                                this.load_listings(cx);
                                 cx.notify();
                             } else {
                                 this.open_file(p, cx);
@@ -994,13 +988,15 @@ impl Render for FileEditorView {
                 }
                 TabContent::Binary { info } => {
                     let filename = tab.filename.clone();
-                    let path = tab.path.clone();
+                    let path = tab.location_label().map(PathBuf::from);
+                    let openable = tab.fs.connection_id().is_none();
                     let file_size = info.file_size;
                     let h = cx.entity().downgrade();
                     editor_area = editor_area.child(Self::render_binary_info(
                         &filename,
                         path.as_deref(),
                         file_size,
+                        openable,
                         h,
                     ));
                 }
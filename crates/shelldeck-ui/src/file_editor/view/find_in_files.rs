@@ -14,10 +14,10 @@ impl FileEditorView {
         }
     }
 
-    /// Run the find-in-files query over the file-browser root. The actual file
-    /// walk runs on a background thread (it does blocking disk I/O) and the
-    /// results are applied back on the UI thread, so a large tree can never
-    /// freeze or crash the editor.
+    /// Run the find-in-files query over the file-browser root. The search
+    /// runs on a background thread (a disk walk here, `grep` over SSH for a
+    /// server tree) and the results are applied back on the UI thread, so a
+    /// large tree can never freeze or crash the editor.
     pub fn run_find_in_files(&mut self, cx: &mut Context<Self>) {
         self.fif_results.clear();
         self.fif_selected = 0;
@@ -32,21 +32,12 @@ impl FileEditorView {
             query.to_lowercase()
         };
         let root = self.file_browser.root().to_path_buf();
+        let fs = self.file_browser.fs().clone();
         self.fif_searching = true;
 
-        let search = cx.background_executor().spawn(async move {
-            let mut results = Vec::new();
-            let mut files_scanned = 0usize;
-            FileEditorView::fif_walk(
-                &root,
-                &needle,
-                case_sensitive,
-                &mut results,
-                &mut files_scanned,
-                0,
-            );
-            results
-        });
+        let search = cx
+            .background_executor()
+            .spawn(async move { fs.find_in_files(&root, &needle, case_sensitive) });
 
         cx.spawn(async move |this, cx| {
             let results = search.await;
@@ -62,80 +53,6 @@ impl FileEditorView {
         .detach();
     }
 
-    fn fif_walk(
-        dir: &std::path::Path,
-        needle: &str,
-        case_sensitive: bool,
-        out: &mut Vec<FifMatch>,
-        files_scanned: &mut usize,
-        depth: usize,
-    ) {
-        const MAX_RESULTS: usize = 1000;
-        const MAX_FILES: usize = 8000;
-        const MAX_FILE_SIZE: u64 = 1_048_576; // 1 MB
-        const MAX_DEPTH: usize = 32;
-        if out.len() >= MAX_RESULTS || *files_scanned >= MAX_FILES || depth > MAX_DEPTH {
-            return;
-        }
-        let Ok(read_dir) = std::fs::read_dir(dir) else {
-            return;
-        };
-        for entry in read_dir.flatten() {
-            if out.len() >= MAX_RESULTS || *files_scanned >= MAX_FILES {
-                return;
-            }
-            let name = entry.file_name().to_string_lossy().to_string();
-            if name.starts_with('.') {
-                continue;
-            }
-            // Use the dir-entry file type (does NOT follow symlinks) so symlink
-            // cycles can't cause infinite recursion / a stack overflow.
-            let Ok(file_type) = entry.file_type() else {
-                continue;
-            };
-            if file_type.is_symlink() {
-                continue;
-            }
-            let path = entry.path();
-            if file_type.is_dir() {
-                if matches!(
-                    name.as_str(),
-                    "target" | "node_modules" | "__pycache__" | ".git" | "dist" | "build"
-                ) {
-                    continue;
-                }
-                Self::fif_walk(&path, needle, case_sensitive, out, files_scanned, depth + 1);
-            } else if file_type.is_file() {
-                if let Ok(meta) = entry.metadata() {
-                    if meta.len() > MAX_FILE_SIZE {
-                        continue;
-                    }
-                }
-                *files_scanned += 1;
-                let Ok(content) = std::fs::read_to_string(&path) else {
-                    continue; // non-UTF-8 / unreadable → skip (binary)
-                };
-                for (li, line) in content.lines().enumerate() {
-                    let matched = if case_sensitive {
-                        line.contains(needle)
-                    } else {
-                        line.to_lowercase().contains(needle)
-                    };
-                    if matched {
-                        out.push(FifMatch {
-                            path: path.clone(),
-                            line: li + 1,
-                            preview: line.trim_start().chars().take(160).collect(),
-                        });
-                        if out.len() >= MAX_RESULTS {
-                            return;
-                        }
-                    }
-                }
-            }
-        }
-    }
-
     /// Open the file for the given result and jump to its line.
     pub fn open_fif_result(&mut self, idx: usize, cx: &mut Context<Self>) {
         let Some(m) = self.fif_results.get(idx).cloned() else {
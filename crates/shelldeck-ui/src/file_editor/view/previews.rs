@@ -123,10 +123,13 @@ impl FileEditorView {
             .child(card)
     }
 
+    /// `openable` is false for server files, which have no local copy to
+    /// hand to the system application.
     pub(super) fn render_binary_info(
         filename: &str,
         path: Option<&std::path::Path>,
         file_size: u64,
+        openable: bool,
         _handle: WeakEntity<Self>,
     ) -> impl IntoElement {
         let ext = std::path::Path::new(filename)
@@ -190,7 +193,7 @@ impl FileEditorView {
         }
 
         // Open externally button
-        if let Some(p) = path {
+        if let (Some(p), true) = (path, openable) {
             let path_owned = p.to_path_buf();
             card = card.child(
                 div()
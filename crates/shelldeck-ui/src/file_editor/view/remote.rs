@@ -0,0 +1,625 @@
+use std::path::Path;
+
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::file_transfer::remote_parent;
+
+use super::*;
+use crate::file_editor::fs::{self, SaveError, SshFs};
+
+/// A save waiting on the user: a conflict to settle or a sudo password.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PendingSave {
+    pub tab_id: Uuid,
+    /// Close the tab once the save went through (save & close).
+    pub close_after: bool,
+}
+
+/// Password entry for a save that needs sudo. Typed through the editor's
+/// own key handling, like the search bars, and only ever shown masked.
+pub(crate) struct SudoPrompt {
+    pub save: PendingSave,
+    pub password: String,
+    /// The last password was refused.
+    pub rejected: bool,
+}
+
+fn bar_button(
+    id: &'static str,
+    label: String,
+    primary: bool,
+    handle: WeakEntity<FileEditorView>,
+    on_click: impl Fn(&mut FileEditorView, &mut Context<FileEditorView>) + 'static,
+) -> Stateful<Div> {
+    let button = div()
+        .id(id)
+        .px(px(8.0))
+        .py(px(2.0))
+        .rounded(px(3.0))
+        .text_size(px(11.0))
+        .cursor_pointer()
+        .child(label)
+        .on_click(move |_event, _window, cx| {
+            if let Some(view) = handle.upgrade() {
+                view.update(cx, |this, cx| on_click(this, cx));
+            }
+        });
+    if primary {
+        button
+            .bg(ShellDeckColors::primary())
+            .text_color(ShellDeckColors::bg_primary())
+    } else {
+        button
+            .text_color(ShellDeckColors::text_muted())
+            .hover(|s| s.bg(ShellDeckColors::hover_bg()))
+    }
+}
+
+fn prompt_bar(tone: Hsla, message: String) -> Div {
+    div()
+        .flex()
+        .items_center()
+        .w_full()
+        .min_h(px(32.0))
+        .bg(tone.opacity(0.2))
+        .border_b_1()
+        .border_color(tone.opacity(0.4))
+        .px(px(10.0))
+        .gap(px(8.0))
+        .text_size(px(12.0))
+        .child(
+            div()
+                .flex_shrink()
+                .min_w(px(0.0))
+                .text_color(ShellDeckColors::text_primary())
+                .child(message),
+        )
+        .child(div().flex_grow())
+}
+
+impl FileEditorView {
+    /// Root the file browser at `root` on `fs`: a directory on a server
+    /// (`user@host:/etc/nginx`), or back on this computer.
+    pub fn set_browser_root(
+        &mut self,
+        fs: Arc<dyn EditorFs>,
+        root: PathBuf,
+        cx: &mut Context<Self>,
+    ) {
+        self.file_browser.set_fs(fs, root);
+        self.file_browser_visible = true;
+        self.fif_results.clear();
+        self.fif_last_query.clear();
+        self.load_listings(cx);
+        cx.notify();
+    }
+
+    /// Browse `path` (a directory) or open it (a file, with the browser
+    /// rooted at its directory), on `connection` over SSH or on this
+    /// computer when `None`.
+    pub fn open_location(
+        &mut self,
+        connection: Option<Connection>,
+        path: PathBuf,
+        is_dir: bool,
+        cx: &mut Context<Self>,
+    ) -> std::io::Result<()> {
+        let fs = match connection {
+            None => local_fs(),
+            Some(connection) => self.fs_for(connection)?,
+        };
+        let root = if is_dir {
+            path.clone()
+        } else if fs.connection_id().is_some() {
+            PathBuf::from(remote_parent(&path.to_string_lossy()))
+        } else {
+            path.parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| path.clone())
+        };
+        self.set_browser_root(fs, root, cx);
+        if !is_dir {
+            self.open_file(path, cx);
+        }
+        Ok(())
+    }
+
+    /// The open SSH filesystem of `connection`, if the browser or a tab
+    /// already uses one, so the session is shared.
+    fn fs_for(&self, connection: Connection) -> std::io::Result<Arc<dyn EditorFs>> {
+        let id = Some(connection.id);
+        let open = std::iter::once(self.file_browser.fs())
+            .chain(self.tabs.iter().map(|t| &t.fs))
+            .find(|fs| fs.connection_id() == id);
+        match open {
+            Some(fs) => Ok(fs.clone()),
+            None => Ok(Arc::new(SshFs::new(connection)?)),
+        }
+    }
+
+    /// Fetch the expanded server directories the browser has no listing for.
+    pub(crate) fn load_listings(&mut self, cx: &mut Context<Self>) {
+        let generation = self.file_browser.generation();
+        for dir in self.file_browser.take_missing_listings() {
+            let fs = self.file_browser.fs().clone();
+            let listing = cx.background_executor().spawn({
+                let dir = dir.clone();
+                async move { fs.read_dir(&dir) }
+            });
+            cx.spawn(async move |this, cx| {
+                let result = listing.await;
+                let _ = this.update(cx, |this, cx| {
+                    let (children, error) = match result {
+                        Ok(children) => (children, None),
+                        Err(e) => (Vec::new(), Some(e.to_string())),
+                    };
+                    let path = dir.to_string_lossy().into_owned();
+                    if this.file_browser.set_listing(generation, dir, children) {
+                        if let Some(error) = error {
+                            this.fs_error = Some(
+                                t!(
+                                    "file_editor.remote.list_failed",
+                                    path = path.as_str(),
+                                    error = error.as_str()
+                                )
+                                .to_string(),
+                            );
+                        }
+                        cx.notify();
+                    }
+                });
+            })
+            .detach();
+        }
+    }
+
+    /// Read a server file in the background and open it in a tab. Images
+    /// and PDFs are not downloaded; they show as binary files.
+    pub(super) fn open_remote_file(
+        &mut self,
+        fs: Arc<dyn EditorFs>,
+        path: PathBuf,
+        cx: &mut Context<Self>,
+    ) {
+        let filename = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let is_text = FileKind::from_filename(&filename) == FileKind::Text;
+        let read = cx.background_executor().spawn({
+            let fs = fs.clone();
+            let path = path.clone();
+            async move {
+                if is_text {
+                    fs.read(&path)
+                        .map(|(data, stamp)| (Some(data), Some(stamp)))
+                } else {
+                    fs.stamp(&path).map(|stamp| (None, stamp))
+                }
+            }
+        });
+        cx.spawn(async move |this, cx| {
+            let result = read.await;
+            let _ = this.update(cx, |this, cx| {
+                let (data, stamp) = match result {
+                    Ok(read) => read,
+                    Err(e) => {
+                        this.fs_error = Some(
+                            t!(
+                                "file_editor.remote.open_failed",
+                                name = filename.as_str(),
+                                error = e.to_string().as_str()
+                            )
+                            .to_string(),
+                        );
+                        cx.notify();
+                        return;
+                    }
+                };
+                // Opened twice while the first read was in flight.
+                if let Some(i) = this.tabs.iter().position(|tab| {
+                    tab.path.as_ref() == Some(&path) && tab.fs.connection_id() == fs.connection_id()
+                }) {
+                    this.active_tab_index = i;
+                    cx.notify();
+                    return;
+                }
+                let file_size = stamp.map(|s| s.size).unwrap_or(0);
+                let tab = match data.map(String::from_utf8) {
+                    Some(Ok(content)) => EditorTab::from_file(path, &content),
+                    // UTF-8 decode failed — treat as binary
+                    Some(Err(_)) | None => EditorTab::from_binary(path, BinaryInfo { file_size }),
+                };
+                this.push_tab(tab.on_fs(fs, stamp), cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Save a tab through its filesystem. `check` refuses to overwrite a
+    /// file that changed since it was opened; `sudo` carries the password
+    /// for a sudo save (tabs already saved through sudo keep using it).
+    pub(crate) fn save_tab(
+        &mut self,
+        save: PendingSave,
+        check: bool,
+        sudo: Option<String>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(tab) = self.tabs.iter().find(|t| t.id == save.tab_id) else {
+            return;
+        };
+        let (Some(path), Some(buffer)) = (tab.path.clone(), tab.buffer()) else {
+            return;
+        };
+        if !self.saving.insert(tab.id) {
+            return;
+        }
+        let text = buffer.text();
+        let fs = tab.fs.clone();
+        let expected = if check { tab.stamp } else { None };
+        let sudo = sudo.or_else(|| tab.sudo.then(String::new));
+        let used_sudo = sudo.is_some();
+        let gave_password = sudo.as_deref().is_some_and(|p| !p.is_empty());
+
+        let task = cx.background_executor().spawn(async move {
+            let result = fs::save(&*fs, &path, text.as_bytes(), expected, sudo.as_deref());
+            (text, result)
+        });
+        cx.spawn(async move |this, cx| {
+            let (text, result) = task.await;
+            let _ = this.update(cx, |this, cx| {
+                this.finish_save(save, &text, used_sudo, gave_password, result, cx);
+            });
+        })
+        .detach();
+    }
+
+    fn finish_save(
+        &mut self,
+        save: PendingSave,
+        text: &str,
+        used_sudo: bool,
+        gave_password: bool,
+        result: Result<FileStamp, SaveError>,
+        cx: &mut Context<Self>,
+    ) {
+        self.saving.remove(&save.tab_id);
+        let Some(index) = self.tabs.iter().position(|t| t.id == save.tab_id) else {
+            return;
+        };
+        match result {
+            Ok(stamp) => {
+                let tab = &mut self.tabs[index];
+                tab.stamp = Some(stamp);
+                tab.sudo = used_sudo;
+                // Edits made while the save was in flight keep the tab dirty.
+                if let Some(buffer) = tab.buffer_mut() {
+                    if buffer.text() == text {
+                        buffer.set_dirty(false);
+                    }
+                }
+                if save.close_after {
+                    self.force_close_tab(index, cx);
+                }
+            }
+            Err(SaveError::Conflict) => self.save_conflict = Some(save),
+            Err(SaveError::PermissionDenied) => {
+                self.sudo_prompt = Some(SudoPrompt {
+                    save,
+                    password: String::new(),
+                    rejected: false,
+                });
+            }
+            Err(SaveError::SudoPassword) => {
+                self.sudo_prompt = Some(SudoPrompt {
+                    save,
+                    password: String::new(),
+                    rejected: gave_password,
+                });
+            }
+            Err(SaveError::Other(error)) => {
+                tracing::error!("Failed to save file: {}", error);
+                let name = self.tabs[index].filename.clone();
+                self.fs_error = Some(
+                    t!(
+                        "file_editor.remote.save_failed",
+                        name = name.as_str(),
+                        error = error.as_str()
+                    )
+                    .to_string(),
+                );
+            }
+        }
+        cx.notify();
+    }
+
+    /// Replace a tab's content with what is on disk now, dropping its edits.
+    fn reload_tab(&mut self, tab_id: Uuid, cx: &mut Context<Self>) {
+        let Some(tab) = self.tabs.iter().find(|t| t.id == tab_id) else {
+            return;
+        };
+        let Some(path) = tab.path.clone() else {
+            return;
+        };
+        let fs = tab.fs.clone();
+        let read = cx.background_executor().spawn({
+            let path = path.clone();
+            async move { fs.read(&path) }
+        });
+        cx.spawn(async move |this, cx| {
+            let result = read.await;
+            let _ = this.update(cx, |this, cx| {
+                let Some(tab) = this.tabs.iter_mut().find(|t| t.id == tab_id) else {
+                    return;
+                };
+                let content = result.and_then(|(data, stamp)| {
+                    String::from_utf8(data)
+                        .map(|content| (content, stamp))
+                        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+                });
+                match content {
+                    Ok((content, stamp)) => {
+                        tab.content = EditorTab::from_file(path, &content).content;
+                        tab.stamp = Some(stamp);
+                    }
+                    Err(e) => {
+                        this.fs_error = Some(
+                            t!(
+                                "file_editor.remote.open_failed",
+                                name = tab.filename.as_str(),
+                                error = e.to_string().as_str()
+                            )
+                            .to_string(),
+                        );
+                    }
+                }
+                this.ensure_cursor_visible();
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn submit_sudo_prompt(&mut self, cx: &mut Context<Self>) {
+        if let Some(prompt) = self.sudo_prompt.take() {
+            self.save_tab(prompt.save, false, Some(prompt.password), cx);
+            cx.notify();
+        }
+    }
+
+    /// Keys while a save prompt is open. The sudo prompt takes every key;
+    /// a conflict only claims Escape.
+    pub(crate) fn handle_save_prompt_key(
+        &mut self,
+        event: &KeyDownEvent,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        let ks = &event.keystroke;
+        if let Some(prompt) = self.sudo_prompt.as_mut() {
+            match ks.key.as_str() {
+                "escape" => self.sudo_prompt = None,
+                "enter" => self.submit_sudo_prompt(cx),
+                "backspace" => {
+                    prompt.password.pop();
+                }
+                _ => {
+                    let ctrl = ks.modifiers.control || ks.modifiers.secondary();
+                    if let (false, Some(key_char)) = (ctrl, ks.key_char.as_ref()) {
+                        prompt
+                            .password
+                            .extend(key_char.chars().filter(|ch| !ch.is_control()));
+                    }
+                }
+            }
+            cx.notify();
+            return true;
+        }
+        if self.save_conflict.is_some() && ks.key.as_str() == "escape" {
+            self.save_conflict = None;
+            cx.notify();
+            return true;
+        }
+        false
+    }
+
+    pub(super) fn render_browser_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let s = crate::scale::px;
+        let header = div()
+            .flex()
+            .items_center()
+            .gap(s(6.0))
+            .w_full()
+            .h(s(28.0))
+            .px(s(8.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .text_size(s(11.0))
+            .font_weight(FontWeight::BOLD)
+            .text_color(ShellDeckColors::text_muted());
+        let Some(host) = self.file_browser.fs().host() else {
+            return header.child(t!("file_editor.browser.files").to_string());
+        };
+
+        let handle = cx.entity().downgrade();
+        let h_local = handle.clone();
+        header
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(0.0))
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .text_color(ShellDeckColors::primary())
+                    .child(format!("{}:{}", host, self.file_browser.root().display())),
+            )
+            .child(
+                div()
+                    .id("fb-remote-refresh")
+                    .flex_shrink_0()
+                    .cursor_pointer()
+                    .hover(|st| st.text_color(ShellDeckColors::text_primary()))
+                    .child(if self.file_browser.is_loading() {
+                        "…"
+                    } else {
+                        "⟳"
+                    })
+                    .on_click(move |_event, _window, cx| {
+                        if let Some(view) = handle.upgrade() {
+                            view.update(cx, |this, cx| {
+                                this.file_browser.refresh();
+                                this.load_listings(cx);
+                                cx.notify();
+                            });
+                        }
+                    }),
+            )
+            .child(
+                div()
+                    .id("fb-remote-close")
+                    .flex_shrink_0()
+                    .cursor_pointer()
+                    .hover(|st| st.text_color(ShellDeckColors::text_primary()))
+                    .child("✕")
+                    .on_click(move |_event, _window, cx| {
+                        if let Some(view) = h_local.upgrade() {
+                            view.update(cx, |this, cx| {
+                                let root =
+                                    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+                                this.set_browser_root(local_fs(), root, cx);
+                            });
+                        }
+                    }),
+            )
+    }
+
+    /// Conflict, sudo password and error bars under the tab bar.
+    pub(super) fn render_save_prompts(&self, cx: &mut Context<Self>) -> Vec<Div> {
+        let mut bars = Vec::new();
+        let tab_name = |id: Uuid| {
+            self.tabs
+                .iter()
+                .find(|t| t.id == id)
+                .map(|t| t.filename.clone())
+                .unwrap_or_default()
+        };
+
+        if let Some(save) = self.save_conflict {
+            let handle = cx.entity().downgrade();
+            bars.push(
+                prompt_bar(
+                    ShellDeckColors::warning(),
+                    t!(
+                        "file_editor.remote.conflict",
+                        name = tab_name(save.tab_id).as_str()
+                    )
+                    .to_string(),
+                )
+                .child(bar_button(
+                    "save-conflict-overwrite",
+                    t!("file_editor.remote.overwrite").to_string(),
+                    true,
+                    handle.clone(),
+                    move |this, cx| {
+                        this.save_conflict = None;
+                        this.save_tab(save, false, None, cx);
+                        cx.notify();
+                    },
+                ))
+                .child(bar_button(
+                    "save-conflict-reload",
+                    t!("file_editor.remote.reload").to_string(),
+                    false,
+                    handle.clone(),
+                    move |this, cx| {
+                        this.save_conflict = None;
+                        this.reload_tab(save.tab_id, cx);
+                        cx.notify();
+                    },
+                ))
+                .child(bar_button(
+                    "save-conflict-cancel",
+                    t!("file_editor.unsaved.cancel").to_string(),
+                    false,
+                    handle,
+                    |this, cx| {
+                        this.save_conflict = None;
+                        cx.notify();
+                    },
+                )),
+            );
+        }
+
+        if let Some(prompt) = &self.sudo_prompt {
+            let handle = cx.entity().downgrade();
+            let message = if prompt.rejected {
+                t!("file_editor.remote.sudo_rejected").to_string()
+            } else {
+                t!(
+                    "file_editor.remote.sudo",
+                    name = tab_name(prompt.save.tab_id).as_str()
+                )
+                .to_string()
+            };
+            let masked = if prompt.password.is_empty() {
+                t!("file_editor.remote.sudo_placeholder").to_string()
+            } else {
+                "•".repeat(prompt.password.chars().count())
+            };
+            bars.push(
+                prompt_bar(ShellDeckColors::warning(), message)
+                    .child(
+                        div()
+                            .w(px(180.0))
+                            .px(px(6.0))
+                            .py(px(2.0))
+                            .rounded(px(3.0))
+                            .border_1()
+                            .border_color(ShellDeckColors::border())
+                            .bg(ShellDeckColors::bg_primary())
+                            .text_size(px(11.0))
+                            .text_color(if prompt.password.is_empty() {
+                                ShellDeckColors::text_muted()
+                            } else {
+                                ShellDeckColors::text_primary()
+                            })
+                            .child(masked),
+                    )
+                    .child(bar_button(
+                        "sudo-save",
+                        t!("file_editor.remote.sudo_save").to_string(),
+                        true,
+                        handle.clone(),
+                        |this, cx| this.submit_sudo_prompt(cx),
+                    ))
+                    .child(bar_button(
+                        "sudo-cancel",
+                        t!("file_editor.unsaved.cancel").to_string(),
+                        false,
+                        handle,
+                        |this, cx| {
+                            this.sudo_prompt = None;
+                            cx.notify();
+                        },
+                    )),
+            );
+        }
+
+        if let Some(error) = &self.fs_error {
+            let handle = cx.entity().downgrade();
+            bars.push(
+                prompt_bar(ShellDeckColors::error(), error.clone()).child(bar_button(
+                    "fs-error-dismiss",
+                    t!("file_editor.remote.dismiss").to_string(),
+                    false,
+                    handle,
+                    |this, cx| {
+                        this.fs_error = None;
+                        cx.notify();
+                    },
+                )),
+            );
+        }
+        bars
+    }
+}
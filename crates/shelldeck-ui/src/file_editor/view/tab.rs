@@ -51,6 +51,13 @@ pub struct EditorTab {
     /// Clamped to `[0.0, max_col - visible_cols + margin]` on each scroll or
     /// cursor movement.
     pub h_scroll_offset: f32,
+    /// Filesystem the file was opened from and is saved back to.
+    pub fs: Arc<dyn EditorFs>,
+    /// Stamp of the file when it was opened or last saved; a save is
+    /// refused when the file no longer matches it.
+    pub stamp: Option<FileStamp>,
+    /// Saves go through sudo (the file is not writable by the login user).
+    pub sudo: bool,
 }
 
 impl EditorTab {
@@ -69,6 +76,9 @@ impl EditorTab {
             },
             scroll_offset: 0.0,
             h_scroll_offset: 0.0,
+            fs: local_fs(),
+            stamp: None,
+            sudo: false,
         }
     }
 
@@ -93,6 +103,9 @@ impl EditorTab {
             },
             scroll_offset: 0.0,
             h_scroll_offset: 0.0,
+            fs: local_fs(),
+            stamp: None,
+            sudo: false,
         }
     }
 
@@ -109,6 +122,9 @@ impl EditorTab {
             content: TabContent::Image { image_path: path },
             scroll_offset: 0.0,
             h_scroll_offset: 0.0,
+            fs: local_fs(),
+            stamp: None,
+            sudo: false,
         }
     }
 
@@ -125,6 +141,9 @@ impl EditorTab {
             content: TabContent::Pdf { info },
             scroll_offset: 0.0,
             h_scroll_offset: 0.0,
+            fs: local_fs(),
+            stamp: None,
+            sudo: false,
         }
     }
 
@@ -141,9 +160,28 @@ impl EditorTab {
             content: TabContent::Binary { info },
             scroll_offset: 0.0,
             h_scroll_offset: 0.0,
+            fs: local_fs(),
+            stamp: None,
+            sudo: false,
         }
     }
 
+    /// Attach the filesystem the file came from and its stamp.
+    pub fn on_fs(mut self, fs: Arc<dyn EditorFs>, stamp: Option<FileStamp>) -> Self {
+        self.fs = fs;
+        self.stamp = stamp;
+        self
+    }
+
+    /// Path as shown to the user: `user@host:/path` for server files.
+    pub fn location_label(&self) -> Option<String> {
+        let path = self.path.as_ref()?.to_string_lossy().into_owned();
+        Some(match self.fs.host() {
+            Some(host) => format!("{}:{}", host, path),
+            None => path,
+        })
+    }
+
     pub fn is_text(&self) -> bool {
         matches!(self.content, TabContent::Text { .. })
     }
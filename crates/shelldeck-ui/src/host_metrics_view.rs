@@ -0,0 +1,772 @@
+//! Host metrics: a live panel of one monitored host (CPU, load, memory,
+//! network, disks and the busiest processes) and the card the sidebar
+//! shows when hovering a monitored connection.
+//!
+//! Reads happen in `shelldeck_ssh::metrics`; the workspace owns the
+//! collectors and pushes each host's history here as samples arrive.
+
+use crate::scale::px;
+use adabraka_ui::prelude::*;
+use gpui::prelude::*;
+use gpui::*;
+use uuid::Uuid;
+
+use shelldeck_core::config::host_metrics::HostMetricsConfig;
+use shelldeck_core::models::host_metrics::{DiskUsage, MetricsHistory, MetricsSample};
+
+use crate::t;
+use crate::theme::ShellDeckColors;
+
+/// Height of the sparklines in the panel's cards.
+const CARD_SPARKLINE_HEIGHT: f32 = 28.0;
+/// Height of the sparklines in the sidebar card.
+const TOOLTIP_SPARKLINE_HEIGHT: f32 = 16.0;
+
+#[derive(Debug, Clone)]
+pub enum HostMetricsEvent {
+    Close,
+    /// Start (`true`) or stop sampling this host.
+    SetMonitored(bool),
+    /// Read every host this many seconds apart.
+    SetInterval(u64),
+}
+
+impl EventEmitter<HostMetricsEvent> for HostMetricsView {}
+
+pub struct HostMetricsView {
+    connection_id: Uuid,
+    name: String,
+    history: MetricsHistory,
+    monitored: bool,
+    interval_secs: u64,
+    disk_alert_percent: f32,
+    focus_handle: FocusHandle,
+    needs_focus: bool,
+}
+
+impl HostMetricsView {
+    pub fn new(
+        connection_id: Uuid,
+        name: String,
+        history: MetricsHistory,
+        monitored: bool,
+        interval_secs: u64,
+        disk_alert_percent: f32,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        Self {
+            connection_id,
+            name,
+            history,
+            monitored,
+            interval_secs,
+            disk_alert_percent,
+            focus_handle: cx.focus_handle(),
+            needs_focus: true,
+        }
+    }
+
+    pub fn connection_id(&self) -> Uuid {
+        self.connection_id
+    }
+
+    pub fn set_history(&mut self, history: MetricsHistory) {
+        self.history = history;
+    }
+
+    pub fn set_monitored(&mut self, monitored: bool) {
+        self.monitored = monitored;
+    }
+
+    pub fn set_interval(&mut self, secs: u64) {
+        self.interval_secs = secs;
+    }
+
+    fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        if event.keystroke.key.as_str() == "escape" {
+            cx.emit(HostMetricsEvent::Close);
+        }
+    }
+
+    fn status_text(&self) -> (String, Hsla) {
+        if !self.monitored {
+            return (
+                t!("host_metrics.not_monitored").to_string(),
+                ShellDeckColors::text_muted(),
+            );
+        }
+        if let Some(error) = &self.history.error {
+            return (
+                t!("host_metrics.error", error = error.as_str()).to_string(),
+                ShellDeckColors::error(),
+            );
+        }
+        match self.history.latest() {
+            Some(sample) => (
+                t!(
+                    "host_metrics.updated",
+                    time = crate::i18n::rel_time(sample.at.timestamp_millis() as f64)
+                )
+                .to_string(),
+                ShellDeckColors::text_muted(),
+            ),
+            None => (
+                t!("host_metrics.waiting").to_string(),
+                ShellDeckColors::text_muted(),
+            ),
+        }
+    }
+
+    fn render_toolbar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let (status, status_color) = self.status_text();
+        let mut intervals = div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .flex_shrink_0()
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .mr(px(2.0))
+                    .child(t!("host_metrics.interval").to_string()),
+            );
+        for secs in HostMetricsConfig::INTERVALS {
+            let id = ElementId::from(SharedString::from(format!("host-metrics-every-{secs}")));
+            intervals = intervals.child(
+                Button::new(id, t!("host_metrics.every", secs = secs).to_string())
+                    .size(ButtonSize::Sm)
+                    .h(gpui::px(26.0))
+                    .px(gpui::px(8.0))
+                    .variant(ButtonVariant::Outline)
+                    .selected(self.interval_secs == secs)
+                    .on_click(cx.listener(move |_this, _event, _window, cx| {
+                        cx.emit(HostMetricsEvent::SetInterval(secs));
+                    })),
+            );
+        }
+
+        let monitored = self.monitored;
+        let toggle = Button::new(
+            "host-metrics-toggle",
+            if monitored {
+                t!("host_metrics.stop").to_string()
+            } else {
+                t!("host_metrics.start").to_string()
+            },
+        )
+        .size(ButtonSize::Sm)
+        .variant(if monitored {
+            ButtonVariant::Outline
+        } else {
+            ButtonVariant::Default
+        })
+        .on_click(cx.listener(move |_this, _event, _window, cx| {
+            cx.emit(HostMetricsEvent::SetMonitored(!monitored));
+        }));
+
+        div()
+            .flex()
+            .items_center()
+            .justify_between()
+            .gap(px(12.0))
+            .px(px(20.0))
+            .py(px(10.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .child(
+                div()
+                    .flex_grow()
+                    .min_w(px(0.0))
+                    .text_size(px(12.0))
+                    .text_color(status_color)
+                    .truncate()
+                    .child(status),
+            )
+            .child(intervals)
+            .child(toggle)
+    }
+
+    fn render_cards(&self, latest: &MetricsSample) -> impl IntoElement {
+        let samples = &self.history.samples;
+        let cpu = card(
+            t!("host_metrics.cpu").to_string(),
+            latest
+                .cpu_percent
+                .map(|p| format!("{:.0}%", p))
+                .unwrap_or_else(|| "—".to_string()),
+            t!("host_metrics.cores", count = latest.cores).to_string(),
+            sparkline(
+                &cpu_series(samples),
+                100.0,
+                CARD_SPARKLINE_HEIGHT,
+                ShellDeckColors::primary(),
+            ),
+        );
+
+        let (load_values, load_max) = load_series(samples);
+        let load = card(
+            t!("host_metrics.load").to_string(),
+            latest
+                .load
+                .map(|l| format!("{:.2}", l[0]))
+                .unwrap_or_else(|| "—".to_string()),
+            latest
+                .load
+                .map(|l| format!("{:.2} · {:.2}", l[1], l[2]))
+                .unwrap_or_default(),
+            sparkline(
+                &load_values,
+                load_max,
+                CARD_SPARKLINE_HEIGHT,
+                ShellDeckColors::warning(),
+            ),
+        );
+
+        let memory_detail = latest
+            .memory
+            .map(|m| {
+                let mut detail =
+                    format!("{} / {}", format_kib(m.used_kib()), format_kib(m.total_kib));
+                if let Some(swap) = m.swap_percent() {
+                    detail.push_str(" · ");
+                    detail.push_str(&t!("host_metrics.swap", percent = format!("{:.0}", swap)));
+                }
+                detail
+            })
+            .unwrap_or_default();
+        let memory = card(
+            t!("host_metrics.memory").to_string(),
+            latest
+                .memory
+                .map(|m| format!("{:.0}%", m.used_percent()))
+                .unwrap_or_else(|| "—".to_string()),
+            memory_detail,
+            sparkline(
+                &memory_series(samples),
+                100.0,
+                CARD_SPARKLINE_HEIGHT,
+                ShellDeckColors::success(),
+            ),
+        );
+
+        let (net_values, net_max) = net_series(samples);
+        let network = card(
+            t!("host_metrics.network").to_string(),
+            latest
+                .rx_bytes_per_sec
+                .map(|rx| format!("↓ {}", format_rate(rx)))
+                .unwrap_or_else(|| "—".to_string()),
+            latest
+                .tx_bytes_per_sec
+                .map(|tx| format!("↑ {}", format_rate(tx)))
+                .unwrap_or_default(),
+            sparkline(
+                &net_values,
+                net_max,
+                CARD_SPARKLINE_HEIGHT,
+                ShellDeckColors::primary_hover(),
+            ),
+        );
+
+        div()
+            .flex()
+            .gap(px(10.0))
+            .px(px(20.0))
+            .pt(px(14.0))
+            .child(cpu)
+            .child(load)
+            .child(memory)
+            .child(network)
+    }
+
+    fn render_disks(&self, disks: &[DiskUsage]) -> impl IntoElement {
+        let mut section = div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .px(px(20.0))
+            .pt(px(16.0))
+            .child(section_title(t!("host_metrics.disks").to_string()));
+        if disks.is_empty() {
+            return section.child(muted_line(t!("host_metrics.no_disks").to_string()));
+        }
+        for disk in disks {
+            let percent = disk.used_percent();
+            let color = if percent >= self.disk_alert_percent {
+                ShellDeckColors::warning()
+            } else {
+                ShellDeckColors::primary()
+            };
+            section = section.child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(10.0))
+                    .py(px(2.0))
+                    .text_size(px(12.0))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .flex_grow()
+                            .min_w(px(0.0))
+                            .child(
+                                div()
+                                    .font_family("JetBrains Mono")
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .truncate()
+                                    .child(disk.mount.clone()),
+                            )
+                            .child(
+                                div()
+                                    .text_size(px(10.0))
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .truncate()
+                                    .child(disk.filesystem.clone()),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(format!(
+                                "{} / {}",
+                                format_kib(disk.used_kib),
+                                format_kib(disk.total_kib)
+                            )),
+                    )
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .w(px(96.0))
+                            .h(px(6.0))
+                            .rounded(px(3.0))
+                            .bg(ShellDeckColors::badge_bg())
+                            .child(
+                                div()
+                                    .h_full()
+                                    .w(px(96.0 * percent.clamp(0.0, 100.0) / 100.0))
+                                    .rounded(px(3.0))
+                                    .bg(color),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .flex_shrink_0()
+                            .w(px(40.0))
+                            .text_right()
+                            .text_color(color)
+                            .child(format!("{:.0}%", percent)),
+                    ),
+            );
+        }
+        section
+    }
+
+    fn render_processes(&self, latest: &MetricsSample) -> impl IntoElement {
+        let mut section = div()
+            .flex()
+            .flex_col()
+            .gap(px(2.0))
+            .px(px(20.0))
+            .pt(px(16.0))
+            .pb(px(14.0))
+            .child(section_title(t!("host_metrics.processes").to_string()));
+        if latest.processes.is_empty() {
+            return section.child(muted_line(t!("host_metrics.no_processes").to_string()));
+        }
+        let row = |pid: String, cpu: String, mem: String, command: String, header: bool| {
+            let color = if header {
+                ShellDeckColors::text_muted()
+            } else {
+                ShellDeckColors::text_primary()
+            };
+            div()
+                .flex()
+                .items_center()
+                .gap(px(10.0))
+                .text_size(px(11.0))
+                .font_family("JetBrains Mono")
+                .text_color(color)
+                .child(div().w(px(64.0)).flex_shrink_0().child(pid))
+                .child(div().w(px(56.0)).flex_shrink_0().text_right().child(cpu))
+                .child(div().w(px(56.0)).flex_shrink_0().text_right().child(mem))
+                .child(div().flex_grow().min_w(px(0.0)).truncate().child(command))
+        };
+        section = section.child(row(
+            t!("host_metrics.column.pid").to_string(),
+            t!("host_metrics.cpu").to_string(),
+            t!("host_metrics.column.mem").to_string(),
+            t!("host_metrics.column.command").to_string(),
+            true,
+        ));
+        for process in &latest.processes {
+            section = section.child(row(
+                process.pid.to_string(),
+                format!("{:.1}%", process.cpu_percent),
+                format!("{:.1}%", process.mem_percent),
+                process.command.clone(),
+                false,
+            ));
+        }
+        section
+    }
+}
+
+impl Render for HostMetricsView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.needs_focus {
+            self.needs_focus = false;
+            self.focus_handle.focus(window);
+        }
+
+        let mut body = div()
+            .id("host-metrics-body")
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .min_h(px(0.0))
+            .overflow_y_scroll();
+        match self.history.latest().cloned() {
+            Some(latest) => {
+                body = body
+                    .child(self.render_cards(&latest))
+                    .child(self.render_disks(&latest.disks))
+                    .child(self.render_processes(&latest));
+            }
+            None => {
+                let text = if self.monitored {
+                    t!("host_metrics.empty_waiting", secs = self.interval_secs).to_string()
+                } else {
+                    t!("host_metrics.empty").to_string()
+                };
+                body = body.child(
+                    div()
+                        .flex()
+                        .flex_grow()
+                        .items_center()
+                        .justify_center()
+                        .px(px(40.0))
+                        .text_size(px(13.0))
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(text),
+                );
+            }
+        }
+
+        div()
+            .id("host-metrics-overlay")
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
+                this.handle_key_down(event, cx);
+            }))
+            .occlude()
+            .absolute()
+            .top_0()
+            .left_0()
+            .right_0()
+            .bottom_0()
+            .bg(ShellDeckColors::backdrop())
+            .flex()
+            .justify_center()
+            .items_center()
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .w(px(760.0))
+                    .h(px(600.0))
+                    .bg(ShellDeckColors::bg_surface())
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(ShellDeckColors::border())
+                    .shadow_xl()
+                    .overflow_hidden()
+                    // Header
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_b_1()
+                            .border_color(ShellDeckColors::border())
+                            .child(
+                                div()
+                                    .text_size(px(16.0))
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .child(
+                                        t!("host_metrics.title", name = self.name.as_str())
+                                            .to_string(),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .id("close-host-metrics")
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .cursor_pointer()
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                                    .on_click(cx.listener(|_this, _: &ClickEvent, _, cx| {
+                                        cx.emit(HostMetricsEvent::Close);
+                                    }))
+                                    .child(
+                                        svg()
+                                            .path("icons/lucide/x.svg")
+                                            .size(px(14.0))
+                                            .text_color(ShellDeckColors::text_muted()),
+                                    ),
+                            ),
+                    )
+                    .child(self.render_toolbar(cx))
+                    .child(body),
+            )
+    }
+}
+
+/// The card shown when hovering a monitored connection in the sidebar.
+pub(crate) struct HostMetricsTooltip {
+    pub name: SharedString,
+    pub history: MetricsHistory,
+}
+
+impl Render for HostMetricsTooltip {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        let font_family = use_theme().tokens.font_family.clone();
+        let samples = &self.history.samples;
+        let latest = self.history.latest();
+        let metric = |label: String, value: String, chart: Div| {
+            div()
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .child(
+                    div()
+                        .w(px(64.0))
+                        .flex_shrink_0()
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(label),
+                )
+                .child(div().w(px(40.0)).flex_shrink_0().child(value))
+                .child(div().flex_grow().child(chart))
+        };
+
+        let mut card = div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .w(px(240.0))
+            .px(px(10.0))
+            .py(px(8.0))
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(ShellDeckColors::border())
+            .bg(ShellDeckColors::bg_surface())
+            .shadow_md()
+            .text_size(px(11.0))
+            .font_family(font_family)
+            .text_color(ShellDeckColors::text_primary())
+            .child(
+                div()
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .truncate()
+                    .child(self.name.clone()),
+            );
+        if let Some(latest) = latest {
+            let (load_values, load_max) = load_series(samples);
+            card = card
+                .child(metric(
+                    t!("host_metrics.cpu").to_string(),
+                    latest
+                        .cpu_percent
+                        .map(|p| format!("{:.0}%", p))
+                        .unwrap_or_else(|| "—".to_string()),
+                    sparkline(
+                        &cpu_series(samples),
+                        100.0,
+                        TOOLTIP_SPARKLINE_HEIGHT,
+                        ShellDeckColors::primary(),
+                    ),
+                ))
+                .child(metric(
+                    t!("host_metrics.memory").to_string(),
+                    latest
+                        .memory
+                        .map(|m| format!("{:.0}%", m.used_percent()))
+                        .unwrap_or_else(|| "—".to_string()),
+                    sparkline(
+                        &memory_series(samples),
+                        100.0,
+                        TOOLTIP_SPARKLINE_HEIGHT,
+                        ShellDeckColors::success(),
+                    ),
+                ))
+                .child(metric(
+                    t!("host_metrics.load").to_string(),
+                    latest
+                        .load
+                        .map(|l| format!("{:.2}", l[0]))
+                        .unwrap_or_else(|| "—".to_string()),
+                    sparkline(
+                        &load_values,
+                        load_max,
+                        TOOLTIP_SPARKLINE_HEIGHT,
+                        ShellDeckColors::warning(),
+                    ),
+                ));
+            if let Some(disk) = latest.fullest_disk() {
+                card = card.child(
+                    div().text_color(ShellDeckColors::text_muted()).child(
+                        t!(
+                            "host_metrics.fullest_disk",
+                            mount = disk.mount.as_str(),
+                            percent = format!("{:.0}", disk.used_percent())
+                        )
+                        .to_string(),
+                    ),
+                );
+            }
+        }
+        if let Some(error) = &self.history.error {
+            card = card.child(
+                div()
+                    .text_color(ShellDeckColors::error())
+                    .child(t!("host_metrics.error", error = error.as_str()).to_string()),
+            );
+        } else if latest.is_none() {
+            card = card.child(
+                div()
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("host_metrics.waiting").to_string()),
+            );
+        }
+        card
+    }
+}
+
+/// Bars for a series, oldest first, scaled so `max` fills `height`. A
+/// sample without the value leaves an empty slot.
+pub(crate) fn sparkline(values: &[Option<f32>], max: f32, height: f32, color: Hsla) -> Div {
+    let max = max.max(f32::EPSILON);
+    let mut bars = div().flex().items_end().gap(px(1.0)).h(px(height)).w_full();
+    for value in values {
+        let bar = match value {
+            Some(value) => div()
+                .h(px((value / max * height).clamp(1.0, height)))
+                .bg(color.opacity(0.8)),
+            None => div().h(px(0.0)),
+        };
+        bars = bars.child(bar.flex_1().rounded(px(1.0)));
+    }
+    bars
+}
+
+fn cpu_series(samples: &[MetricsSample]) -> Vec<Option<f32>> {
+    samples.iter().map(|s| s.cpu_percent).collect()
+}
+
+fn memory_series(samples: &[MetricsSample]) -> Vec<Option<f32>> {
+    samples
+        .iter()
+        .map(|s| s.memory.map(|m| m.used_percent()))
+        .collect()
+}
+
+/// One-minute load, scaled to the core count unless the load went past it.
+fn load_series(samples: &[MetricsSample]) -> (Vec<Option<f32>>, f32) {
+    let values: Vec<Option<f32>> = samples.iter().map(|s| s.load.map(|l| l[0])).collect();
+    let cores = samples.last().map(|s| s.cores).unwrap_or(1).max(1) as f32;
+    let max = values.iter().flatten().fold(cores, |max, v| max.max(*v));
+    (values, max)
+}
+
+/// Received plus sent bytes per second, scaled to the busiest sample.
+fn net_series(samples: &[MetricsSample]) -> (Vec<Option<f32>>, f32) {
+    let values: Vec<Option<f32>> = samples
+        .iter()
+        .map(|s| match (s.rx_bytes_per_sec, s.tx_bytes_per_sec) {
+            (Some(rx), Some(tx)) => Some((rx + tx) as f32),
+            _ => None,
+        })
+        .collect();
+    let max = values.iter().flatten().fold(1.0_f32, |max, v| max.max(*v));
+    (values, max)
+}
+
+fn card(label: String, value: String, detail: String, chart: Div) -> Div {
+    div()
+        .flex()
+        .flex_col()
+        .flex_1()
+        .min_w(px(0.0))
+        .gap(px(4.0))
+        .p(px(10.0))
+        .rounded(px(8.0))
+        .border_1()
+        .border_color(ShellDeckColors::border())
+        .child(
+            div()
+                .text_size(px(11.0))
+                .text_color(ShellDeckColors::text_muted())
+                .child(label),
+        )
+        .child(
+            div()
+                .text_size(px(18.0))
+                .font_weight(FontWeight::SEMIBOLD)
+                .text_color(ShellDeckColors::text_primary())
+                .child(value),
+        )
+        .child(
+            div()
+                .text_size(px(10.0))
+                .text_color(ShellDeckColors::text_muted())
+                .truncate()
+                .child(detail),
+        )
+        .child(chart)
+}
+
+fn section_title(text: String) -> Div {
+    div()
+        .pb(px(4.0))
+        .text_size(px(11.0))
+        .font_weight(FontWeight::SEMIBOLD)
+        .text_color(ShellDeckColors::text_muted())
+        .child(text)
+}
+
+fn muted_line(text: String) -> Div {
+    div()
+        .text_size(px(12.0))
+        .text_color(ShellDeckColors::text_muted())
+        .child(text)
+}
+
+fn format_kib(kib: u64) -> String {
+    let units = ["KB", "MB", "GB", "TB"];
+    let mut value = kib as f64;
+    let mut unit_idx = 0;
+    while value >= 1024.0 && unit_idx < units.len() - 1 {
+        value /= 1024.0;
+        unit_idx += 1;
+    }
+    if unit_idx == 0 {
+        format!("{} KB", kib)
+    } else {
+        format!("{:.1} {}", value, units[unit_idx])
+    }
+}
+
+fn format_rate(bytes_per_sec: f64) -> String {
+    if bytes_per_sec < 1024.0 {
+        return format!("{:.0} B/s", bytes_per_sec);
+    }
+    format!("{}/s", format_kib((bytes_per_sec / 1024.0) as u64))
+}
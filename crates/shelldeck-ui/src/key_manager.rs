@@ -0,0 +1,921 @@
+//! SSH key manager: the key pairs in ~/.ssh with their fingerprints and the
+//! connections that sign in with them, key generation, and deploy / rotate /
+//! revoke runs across hosts with a per-host report.
+//!
+//! Keys are generated here, off the UI thread. Remote runs are handed to the
+//! workspace, which owns the connections and rewrites them when a rotation
+//! succeeds, and reports progress back through [`KeyManagerView::set_host_status`].
+
+use crate::scale::px;
+use adabraka_ui::components::checkbox::Checkbox;
+use adabraka_ui::components::input::{Input, InputSize, InputState};
+use adabraka_ui::prelude::*;
+use gpui::prelude::*;
+use gpui::*;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use shelldeck_core::config::ssh_keys::{self, AuthorizedKeysChange, KeyAlgorithm, SshKey};
+use shelldeck_core::models::connection::Connection;
+
+use crate::t;
+use crate::theme::ShellDeckColors;
+
+/// What a run does on each selected host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOperation {
+    /// Add the public key to `authorized_keys`.
+    Deploy,
+    /// Replace the key with a fresh one and switch the connections to it.
+    Rotate,
+    /// Remove the public key from `authorized_keys`.
+    Revoke,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostKeyStatus {
+    Pending,
+    Running,
+    Done(AuthorizedKeysChange),
+    /// The new key signs in and the old one is gone.
+    Rotated,
+    Failed(String),
+    Skipped,
+}
+
+impl HostKeyStatus {
+    fn is_finished(&self) -> bool {
+        !matches!(self, HostKeyStatus::Pending | HostKeyStatus::Running)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HostKeyResult {
+    pub connection_id: Uuid,
+    pub alias: String,
+    pub status: HostKeyStatus,
+}
+
+/// The hosts of the last run, in selection order.
+#[derive(Debug, Clone)]
+pub struct KeyReport {
+    pub operation: KeyOperation,
+    pub key_name: String,
+    pub hosts: Vec<HostKeyResult>,
+}
+
+impl KeyReport {
+    /// (succeeded, failed) among the finished hosts.
+    pub fn counts(&self) -> (usize, usize) {
+        let failed = self
+            .hosts
+            .iter()
+            .filter(|h| matches!(h.status, HostKeyStatus::Failed(_)))
+            .count();
+        let succeeded = self
+            .hosts
+            .iter()
+            .filter(|h| matches!(h.status, HostKeyStatus::Done(_) | HostKeyStatus::Rotated))
+            .count();
+        (succeeded, failed)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum KeyManagerEvent {
+    Run {
+        operation: KeyOperation,
+        key: SshKey,
+        hosts: Vec<Uuid>,
+    },
+    Close,
+}
+
+impl EventEmitter<KeyManagerEvent> for KeyManagerView {}
+
+pub struct KeyManagerView {
+    keys: Vec<SshKey>,
+    connections: Vec<Connection>,
+    selected_key: Option<usize>,
+    /// Hosts ticked for the next run.
+    hosts: HashSet<Uuid>,
+    generating: bool,
+    show_generate: bool,
+    algorithm: KeyAlgorithm,
+    name_state: Entity<InputState>,
+    passphrase_state: Entity<InputState>,
+    comment_state: Entity<InputState>,
+    report: Option<KeyReport>,
+    error: Option<String>,
+    focus_handle: FocusHandle,
+    needs_focus: bool,
+}
+
+fn new_input_state(cx: &mut Context<KeyManagerView>, initial: &str) -> Entity<InputState> {
+    let initial = initial.to_string();
+    cx.new(|cx| {
+        let mut s = InputState::new(cx);
+        if !initial.is_empty() {
+            s.content = initial.into();
+        }
+        s
+    })
+}
+
+impl KeyManagerView {
+    pub fn new(connections: Vec<Connection>, cx: &mut Context<Self>) -> Self {
+        let comment = format!(
+            "{}@{}",
+            shelldeck_core::util::current_username().unwrap_or_default(),
+            shelldeck_core::util::hostname()
+        );
+        let mut view = Self {
+            keys: Vec::new(),
+            connections,
+            selected_key: None,
+            hosts: HashSet::new(),
+            generating: false,
+            show_generate: false,
+            algorithm: KeyAlgorithm::Ed25519,
+            name_state: new_input_state(cx, ""),
+            passphrase_state: new_input_state(cx, ""),
+            comment_state: new_input_state(cx, &comment),
+            report: None,
+            error: None,
+            focus_handle: cx.focus_handle(),
+            needs_focus: true,
+        };
+        view.reload_keys(None, cx);
+        view
+    }
+
+    pub fn set_connections(&mut self, connections: Vec<Connection>, cx: &mut Context<Self>) {
+        self.connections = connections;
+        self.hosts
+            .retain(|id| self.connections.iter().any(|c| c.id == *id));
+        cx.notify();
+    }
+
+    /// Re-read ~/.ssh, keeping `select` (or the current key) selected.
+    pub fn reload_keys(&mut self, select: Option<std::path::PathBuf>, cx: &mut Context<Self>) {
+        let current = select.or_else(|| self.selected().map(|k| k.private_path.clone()));
+        self.keys = ssh_keys::ssh_dir()
+            .map(|dir| ssh_keys::list_keys(&dir))
+            .unwrap_or_default();
+        let index = current.and_then(|path| self.keys.iter().position(|k| k.private_path == path));
+        self.select_key(index.or((!self.keys.is_empty()).then_some(0)), cx);
+        self.show_generate = self.keys.is_empty();
+    }
+
+    fn selected(&self) -> Option<&SshKey> {
+        self.selected_key.and_then(|i| self.keys.get(i))
+    }
+
+    fn is_running(&self) -> bool {
+        self.report
+            .as_ref()
+            .is_some_and(|r| r.hosts.iter().any(|h| !h.status.is_finished()))
+    }
+
+    fn select_key(&mut self, index: Option<usize>, cx: &mut Context<Self>) {
+        self.selected_key = index;
+        self.show_generate = false;
+        // Pre-tick the hosts that sign in with the key.
+        self.hosts = match index.and_then(|i| self.keys.get(i)) {
+            Some(key) => ssh_keys::connections_using(key, &self.connections)
+                .into_iter()
+                .map(|c| c.id)
+                .collect(),
+            None => HashSet::new(),
+        };
+        cx.notify();
+    }
+
+    /// Start showing a run in the report panel.
+    pub fn start_report(&mut self, report: KeyReport, cx: &mut Context<Self>) {
+        self.report = Some(report);
+        cx.notify();
+    }
+
+    pub fn set_host_status(&mut self, id: Uuid, status: HostKeyStatus, cx: &mut Context<Self>) {
+        if let Some(host) = self
+            .report
+            .as_mut()
+            .and_then(|r| r.hosts.iter_mut().find(|h| h.connection_id == id))
+        {
+            host.status = status;
+            cx.notify();
+        }
+    }
+
+    /// Mark hosts the run never reached as skipped.
+    pub fn finish_report(&mut self, cx: &mut Context<Self>) {
+        let Some(report) = self.report.as_mut() else {
+            return;
+        };
+        for host in &mut report.hosts {
+            if !host.status.is_finished() {
+                host.status = HostKeyStatus::Skipped;
+            }
+        }
+        cx.notify();
+    }
+
+    fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        if event.keystroke.key.as_str() == "escape" {
+            cx.emit(KeyManagerEvent::Close);
+        }
+    }
+
+    fn run(&mut self, operation: KeyOperation, cx: &mut Context<Self>) {
+        if self.is_running() || self.hosts.is_empty() {
+            return;
+        }
+        let Some(key) = self.selected().cloned() else {
+            return;
+        };
+        // Keep the sidebar order rather than the set's.
+        let hosts: Vec<Uuid> = self
+            .connections
+            .iter()
+            .filter(|c| self.hosts.contains(&c.id))
+            .map(|c| c.id)
+            .collect();
+        cx.emit(KeyManagerEvent::Run {
+            operation,
+            key,
+            hosts,
+        });
+    }
+
+    fn generate(&mut self, cx: &mut Context<Self>) {
+        if self.generating {
+            return;
+        }
+        let Some(dir) = ssh_keys::ssh_dir() else {
+            return;
+        };
+        let name = self.name_state.read(cx).content().trim().to_string();
+        let name = if name.is_empty() {
+            self.algorithm.default_file_name().to_string()
+        } else {
+            name
+        };
+        if name.contains(['/', '\\']) || name.ends_with(".pub") {
+            self.error = Some(t!("key_manager.error.name").to_string());
+            cx.notify();
+            return;
+        }
+        let path = dir.join(&name);
+        let algorithm = self.algorithm;
+        let passphrase = self.passphrase_state.read(cx).content().to_string();
+        let comment = self.comment_state.read(cx).content().trim().to_string();
+        self.generating = true;
+        self.error = None;
+        cx.notify();
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let result = cx
+                .background_executor()
+                .spawn(
+                    async move { ssh_keys::generate_key(&path, algorithm, &passphrase, &comment) },
+                )
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                this.generating = false;
+                match result {
+                    Ok(key) => {
+                        tracing::info!("Generated SSH key {}", key.private_path.display());
+                        for state in [&this.name_state, &this.passphrase_state] {
+                            state.update(cx, |s, cx| {
+                                s.content = "".into();
+                                cx.notify();
+                            });
+                        }
+                        this.reload_keys(Some(key.private_path), cx);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Key generation failed: {}", e);
+                        this.error = Some(
+                            t!("key_manager.error.generate", error = e.to_string()).to_string(),
+                        );
+                    }
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn render_key_list(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut list = div()
+            .id("key-manager-list")
+            .flex()
+            .flex_col()
+            .w(px(250.0))
+            .h_full()
+            .border_r_1()
+            .border_color(ShellDeckColors::border())
+            .overflow_y_scroll()
+            .child(
+                div()
+                    .id("key-manager-new")
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .px(px(12.0))
+                    .py(px(8.0))
+                    .cursor_pointer()
+                    .text_size(px(12.0))
+                    .text_color(ShellDeckColors::primary())
+                    .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+                    .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                        this.show_generate = true;
+                        this.error = None;
+                        cx.notify();
+                    }))
+                    .child(
+                        svg()
+                            .path("icons/lucide/plus.svg")
+                            .size(px(12.0))
+                            .text_color(ShellDeckColors::primary()),
+                    )
+                    .child(t!("key_manager.new_key").to_string()),
+            );
+
+        for (i, key) in self.keys.iter().enumerate() {
+            let users = ssh_keys::connections_using(key, &self.connections).len();
+            let selected = !self.show_generate && self.selected_key == Some(i);
+            let mut item = div()
+                .id(ElementId::from(SharedString::from(format!("key-{}", i))))
+                .flex()
+                .flex_col()
+                .gap(px(2.0))
+                .px(px(12.0))
+                .py(px(8.0))
+                .cursor_pointer()
+                .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                    this.select_key(Some(i), cx);
+                }));
+            if selected {
+                item = item
+                    .bg(ShellDeckColors::primary().opacity(0.12))
+                    .border_l_2()
+                    .border_color(ShellDeckColors::primary());
+            } else {
+                item = item.hover(|el| el.bg(ShellDeckColors::hover_bg()));
+            }
+            list = list.child(
+                item.child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap(px(6.0))
+                        .child(
+                            div()
+                                .text_size(px(9.0))
+                                .px(px(4.0))
+                                .py(px(1.0))
+                                .rounded(px(3.0))
+                                .bg(ShellDeckColors::badge_bg())
+                                .text_color(ShellDeckColors::text_muted())
+                                .font_weight(FontWeight::SEMIBOLD)
+                                .child(key.public.algorithm_label().to_string()),
+                        )
+                        .child(
+                            div()
+                                .text_size(px(13.0))
+                                .font_weight(FontWeight::MEDIUM)
+                                .text_color(ShellDeckColors::text_primary())
+                                .truncate()
+                                .child(key.name()),
+                        ),
+                )
+                .child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(t!("key_manager.used_by", count = users).to_string()),
+                ),
+            );
+        }
+        list
+    }
+
+    fn render_generate(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut algorithms = div().flex().gap(px(4.0));
+        for algorithm in [KeyAlgorithm::Ed25519, KeyAlgorithm::Rsa] {
+            let selected = self.algorithm == algorithm;
+            let mut tab = div()
+                .id(ElementId::from(SharedString::from(format!(
+                    "key-alg-{}",
+                    algorithm.default_file_name()
+                ))))
+                .px(px(8.0))
+                .py(px(3.0))
+                .rounded(px(4.0))
+                .text_size(px(11.0))
+                .font_weight(FontWeight::MEDIUM)
+                .cursor_pointer()
+                .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                    this.algorithm = algorithm;
+                    cx.notify();
+                }));
+            if selected {
+                tab = tab
+                    .bg(ShellDeckColors::primary().opacity(0.15))
+                    .text_color(ShellDeckColors::primary());
+            } else {
+                tab = tab
+                    .text_color(ShellDeckColors::text_muted())
+                    .hover(|el| el.bg(ShellDeckColors::hover_bg()));
+            }
+            algorithms = algorithms.child(tab.child(algorithm.label()));
+        }
+
+        let field = |label: String, input: Input| {
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(4.0))
+                .child(
+                    div()
+                        .text_size(px(12.0))
+                        .font_weight(FontWeight::MEDIUM)
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(label),
+                )
+                .child(input)
+        };
+
+        let mut generate_btn = div()
+            .id("key-manager-generate")
+            .on_click(cx.listener(|this, _: &ClickEvent, _, cx| this.generate(cx)))
+            .child(
+                Button::new(
+                    "key-manager-generate-btn",
+                    if self.generating {
+                        t!("key_manager.generating").to_string()
+                    } else {
+                        t!("key_manager.generate").to_string()
+                    },
+                )
+                .variant(ButtonVariant::Default),
+            );
+        if self.generating {
+            generate_btn = generate_btn.opacity(0.5);
+        } else {
+            generate_btn = generate_btn.cursor_pointer();
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(12.0))
+            .p(px(16.0))
+            .child(
+                div()
+                    .text_size(px(15.0))
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(t!("key_manager.new_key").to_string()),
+            )
+            .child(algorithms)
+            .child(field(
+                t!("key_manager.field.name").to_string(),
+                Input::new(&self.name_state)
+                    .size(InputSize::Sm)
+                    .placeholder(self.algorithm.default_file_name()),
+            ))
+            .child(field(
+                t!("key_manager.field.passphrase").to_string(),
+                Input::new(&self.passphrase_state)
+                    .size(InputSize::Sm)
+                    .password(true)
+                    .placeholder(t!("key_manager.field.passphrase_placeholder").to_string()),
+            ))
+            .child(field(
+                t!("key_manager.field.comment").to_string(),
+                Input::new(&self.comment_state).size(InputSize::Sm),
+            ))
+            .child(div().flex().justify_end().child(generate_btn))
+    }
+
+    fn render_key_detail(&self, key: &SshKey, cx: &mut Context<Self>) -> impl IntoElement {
+        let public_line = key.public.to_line();
+        let running = self.is_running();
+
+        let mut hosts = div()
+            .id("key-manager-hosts")
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .min_h(px(0.0))
+            .overflow_y_scroll()
+            .border_1()
+            .border_color(ShellDeckColors::border())
+            .rounded(px(6.0));
+        for conn in &self.connections {
+            let id = conn.id;
+            let entity = cx.entity();
+            let signs_in = key.is_used_by(conn);
+            let mut row = div()
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .px(px(10.0))
+                .py(px(5.0))
+                .child(
+                    Checkbox::new(ElementId::from(SharedString::from(format!(
+                        "key-host-{}",
+                        id
+                    ))))
+                    .checked(self.hosts.contains(&id))
+                    .on_click(move |checked, _, cx| {
+                        let checked = *checked;
+                        entity.update(cx, |this, cx| {
+                            if checked {
+                                this.hosts.insert(id);
+                            } else {
+                                this.hosts.remove(&id);
+                            }
+                            cx.notify();
+                        });
+                    }),
+                )
+                .child(
+                    div()
+                        .flex_grow()
+                        .min_w(px(0.0))
+                        .truncate()
+                        .text_size(px(12.0))
+                        .text_color(ShellDeckColors::text_primary())
+                        .child(conn.display_name().to_string()),
+                )
+                .child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(conn.connection_string()),
+                );
+            if signs_in {
+                row = row.child(
+                    div()
+                        .text_size(px(10.0))
+                        .px(px(5.0))
+                        .rounded(px(3.0))
+                        .bg(ShellDeckColors::primary().opacity(0.15))
+                        .text_color(ShellDeckColors::primary())
+                        .child(t!("key_manager.signs_in").to_string()),
+                );
+            }
+            hosts = hosts.child(row);
+        }
+        if self.connections.is_empty() {
+            hosts = hosts.child(
+                div()
+                    .p(px(12.0))
+                    .text_size(px(12.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("key_manager.no_hosts").to_string()),
+            );
+        }
+
+        let action = |id: &'static str, label: String, operation: KeyOperation| {
+            let enabled = !running && !self.hosts.is_empty();
+            let variant = match operation {
+                KeyOperation::Revoke => ButtonVariant::Destructive,
+                KeyOperation::Rotate => ButtonVariant::Outline,
+                KeyOperation::Deploy => ButtonVariant::Default,
+            };
+            let mut btn = div()
+                .id(id)
+                .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                    this.run(operation, cx);
+                }))
+                .child(
+                    Button::new(SharedString::from(format!("{}-btn", id)), label).variant(variant),
+                );
+            if enabled {
+                btn = btn.cursor_pointer();
+            } else {
+                btn = btn.opacity(0.5);
+            }
+            btn
+        };
+
+        div()
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .min_h(px(0.0))
+            .gap(px(10.0))
+            .p(px(16.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .gap(px(2.0))
+                            .min_w(px(0.0))
+                            .child(
+                                div()
+                                    .text_size(px(15.0))
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .child(key.name()),
+                            )
+                            .child(
+                                div()
+                                    .text_size(px(11.0))
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .truncate()
+                                    .child(key.private_path.display().to_string()),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .id("key-manager-copy")
+                            .flex()
+                            .items_center()
+                            .gap(px(4.0))
+                            .cursor_pointer()
+                            .text_size(px(12.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                            .on_click(move |_: &ClickEvent, _, cx: &mut App| {
+                                cx.write_to_clipboard(ClipboardItem::new_string(
+                                    public_line.clone(),
+                                ));
+                            })
+                            .child(
+                                svg()
+                                    .path("icons/lucide/copy.svg")
+                                    .size(px(12.0))
+                                    .text_color(ShellDeckColors::text_muted()),
+                            )
+                            .child(t!("key_manager.copy_public").to_string()),
+                    ),
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .font_family("JetBrains Mono")
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(key.fingerprint()),
+            )
+            .child(
+                div()
+                    .text_size(px(12.0))
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("key_manager.hosts").to_string()),
+            )
+            .child(hosts)
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_end()
+                    .gap(px(8.0))
+                    .child(action(
+                        "key-manager-revoke",
+                        t!("key_manager.revoke").to_string(),
+                        KeyOperation::Revoke,
+                    ))
+                    .child(action(
+                        "key-manager-rotate",
+                        t!("key_manager.rotate").to_string(),
+                        KeyOperation::Rotate,
+                    ))
+                    .child(action(
+                        "key-manager-deploy",
+                        t!("key_manager.deploy").to_string(),
+                        KeyOperation::Deploy,
+                    )),
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("key_manager.revoke_hint").to_string()),
+            )
+    }
+
+    fn render_report(&self, report: &KeyReport) -> impl IntoElement {
+        let (succeeded, failed) = report.counts();
+        let title = match report.operation {
+            KeyOperation::Deploy => t!("key_manager.report.deploy", key = report.key_name.as_str()),
+            KeyOperation::Rotate => t!("key_manager.report.rotate", key = report.key_name.as_str()),
+            KeyOperation::Revoke => t!("key_manager.report.revoke", key = report.key_name.as_str()),
+        };
+        let mut rows = div()
+            .id("key-manager-report")
+            .flex()
+            .flex_col()
+            .max_h(px(140.0))
+            .overflow_y_scroll();
+        for host in &report.hosts {
+            let (text, color) = match &host.status {
+                HostKeyStatus::Pending => (
+                    t!("key_manager.status.pending").to_string(),
+                    ShellDeckColors::text_muted(),
+                ),
+                HostKeyStatus::Running => (
+                    t!("key_manager.status.running").to_string(),
+                    ShellDeckColors::text_muted(),
+                ),
+                HostKeyStatus::Done(change) => (
+                    match change {
+                        AuthorizedKeysChange::Added => t!("key_manager.status.added"),
+                        AuthorizedKeysChange::AlreadyPresent => t!("key_manager.status.present"),
+                        AuthorizedKeysChange::Removed => t!("key_manager.status.removed"),
+                        AuthorizedKeysChange::NotPresent => t!("key_manager.status.absent"),
+                    }
+                    .to_string(),
+                    ShellDeckColors::success(),
+                ),
+                HostKeyStatus::Rotated => (
+                    t!("key_manager.status.rotated").to_string(),
+                    ShellDeckColors::success(),
+                ),
+                HostKeyStatus::Failed(error) => (error.clone(), ShellDeckColors::error()),
+                HostKeyStatus::Skipped => (
+                    t!("key_manager.status.skipped").to_string(),
+                    ShellDeckColors::warning(),
+                ),
+            };
+            rows = rows.child(
+                div()
+                    .flex()
+                    .gap(px(8.0))
+                    .px(px(16.0))
+                    .py(px(3.0))
+                    .text_size(px(12.0))
+                    .child(
+                        div()
+                            .w(px(160.0))
+                            .flex_shrink_0()
+                            .truncate()
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(host.alias.clone()),
+                    )
+                    .child(
+                        div()
+                            .flex_grow()
+                            .min_w(px(0.0))
+                            .text_color(color)
+                            .child(text),
+                    ),
+            );
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .py(px(8.0))
+            .border_t_1()
+            .border_color(ShellDeckColors::border())
+            .child(
+                div()
+                    .flex()
+                    .justify_between()
+                    .px(px(16.0))
+                    .text_size(px(12.0))
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(title.to_string())
+                    .child(
+                        div().text_color(ShellDeckColors::text_muted()).child(
+                            t!(
+                                "key_manager.report.counts",
+                                succeeded = succeeded,
+                                failed = failed,
+                                total = report.hosts.len()
+                            )
+                            .to_string(),
+                        ),
+                    ),
+            )
+            .child(rows)
+    }
+}
+
+impl Render for KeyManagerView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.needs_focus {
+            self.needs_focus = false;
+            self.focus_handle.focus(window);
+        }
+
+        let mut detail = div()
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .h_full()
+            .min_w(px(0.0))
+            .overflow_hidden();
+        let selected = self.selected().cloned();
+        match selected {
+            Some(key) if !self.show_generate => {
+                detail = detail.child(self.render_key_detail(&key, cx));
+            }
+            _ => {
+                detail = detail.child(self.render_generate(cx));
+            }
+        }
+        if let Some(error) = &self.error {
+            detail = detail.child(
+                div()
+                    .px(px(16.0))
+                    .pb(px(8.0))
+                    .text_size(px(12.0))
+                    .text_color(ShellDeckColors::error())
+                    .child(error.clone()),
+            );
+        }
+        if let Some(report) = &self.report {
+            detail = detail.child(self.render_report(report));
+        }
+
+        div()
+            .id("key-manager-overlay")
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
+                this.handle_key_down(event, cx);
+            }))
+            .occlude()
+            .absolute()
+            .top_0()
+            .left_0()
+            .right_0()
+            .bottom_0()
+            .bg(ShellDeckColors::backdrop())
+            .flex()
+            .justify_center()
+            .items_center()
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .w(px(820.0))
+                    .h(px(580.0))
+                    .bg(ShellDeckColors::bg_surface())
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(ShellDeckColors::border())
+                    .shadow_xl()
+                    .overflow_hidden()
+                    // Header
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_b_1()
+                            .border_color(ShellDeckColors::border())
+                            .child(
+                                div()
+                                    .text_size(px(16.0))
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .child(t!("key_manager.title").to_string()),
+                            )
+                            .child(
+                                div()
+                                    .id("close-key-manager")
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .cursor_pointer()
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                                    .on_click(cx.listener(|_this, _: &ClickEvent, _, cx| {
+                                        cx.emit(KeyManagerEvent::Close);
+                                    }))
+                                    .child(
+                                        svg()
+                                            .path("icons/lucide/x.svg")
+                                            .size(px(14.0))
+                                            .text_color(ShellDeckColors::text_muted()),
+                                    ),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_grow()
+                            .min_h(px(0.0))
+                            .child(self.render_key_list(cx))
+                            .child(detail),
+                    ),
+            )
+    }
+}
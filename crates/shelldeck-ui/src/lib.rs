@@ -10,15 +10,19 @@ pub mod command_palette;
 pub mod command_palette_window;
 pub mod connection_combobox;
 pub mod connection_form;
+pub mod connection_import;
 pub mod dashboard;
 pub mod editor_buffer;
 pub(crate) mod external_content;
 pub mod file_editor;
 pub mod fleet_view;
 pub mod glyph_cache;
+pub mod host_metrics_view;
 pub mod i18n;
 pub mod icons;
 pub mod issue_attachments;
+pub mod key_manager;
+pub mod log_viewer;
 pub mod login_form;
 pub(crate) mod markdown;
 pub mod menu_bar;
@@ -35,6 +39,7 @@ pub mod server_sync_view;
 pub mod settings;
 pub mod sidebar;
 pub mod sites_view;
+pub mod ssh_doctor;
 pub mod status_bar;
 pub mod support_view;
 pub mod syntax;
@@ -0,0 +1,1109 @@
+//! Log viewer: follow files, journald units and container logs on several
+//! hosts at once and read them as one timeline, newest first, with each
+//! host in its own color.
+//!
+//! Tailing lives in `shelldeck_ssh::log_tail`, one tailer per host on its
+//! own thread; dating, merging and filtering in
+//! `shelldeck_core::models::log_tail`. The workspace persists the source
+//! list and uploads exported windows to requests.
+
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::scale::px;
+use adabraka_ui::components::checkbox::Checkbox;
+use adabraka_ui::components::input::{Input, InputSize};
+use adabraka_ui::components::input_state::{InputEvent, InputState};
+use adabraka_ui::components::select::{Select, SelectOption};
+use adabraka_ui::prelude::*;
+use chrono::{DateTime, Local, Utc};
+use gpui::prelude::*;
+use gpui::*;
+use uuid::Uuid;
+
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::log_tail::{
+    export_window, LogFilter, LogLevel, LogLine, LogSource, LogSourceKind, LogTimeline,
+};
+use shelldeck_ssh::log_tail::{LogTailer, TailEvent};
+
+use crate::t;
+use crate::theme::ShellDeckColors;
+
+/// How often the tailers' channels are drained into the timeline.
+const DRAIN_INTERVAL: Duration = Duration::from_millis(200);
+/// Export windows offered, in minutes back from the newest line; `None`
+/// exports every line kept.
+const EXPORT_WINDOWS: [Option<i64>; 5] = [Some(1), Some(5), Some(15), Some(60), None];
+
+#[derive(Debug, Clone)]
+pub enum LogViewerEvent {
+    Close,
+    /// The source list was edited; persist it.
+    SourcesChanged(Vec<LogSource>),
+    /// Attach an exported window to a request as a text file.
+    AttachToIssue {
+        issue_id: String,
+        filename: String,
+        text: String,
+        lines: usize,
+    },
+}
+
+impl EventEmitter<LogViewerEvent> for LogViewerView {}
+
+/// Where a host's tailer is at.
+#[derive(Debug, Clone, PartialEq)]
+enum HostState {
+    Connecting,
+    Live,
+    Down(String),
+}
+
+struct ActiveTail {
+    shutdown_tx: tokio::sync::mpsc::Sender<()>,
+    _thread: std::thread::JoinHandle<()>,
+}
+
+pub struct LogViewerView {
+    connections: Vec<Connection>,
+    sources: Vec<LogSource>,
+    backlog: usize,
+    max_lines: usize,
+    timeline: LogTimeline,
+    filter: LogFilter,
+    filter_error: Option<String>,
+    /// Timeline indices of the lines that pass the filter, oldest first.
+    visible: Vec<usize>,
+    /// Lines received while paused, applied on resume.
+    held: VecDeque<(usize, String, DateTime<Utc>)>,
+    paused: bool,
+    tails: Vec<ActiveTail>,
+    /// Bumped on every start and stop so a stopped run's late events are
+    /// dropped.
+    run: u64,
+    host_states: HashMap<Uuid, HostState>,
+    /// Why a source stopped, when it did.
+    source_notes: HashMap<usize, String>,
+    host_select: Entity<Select<Uuid>>,
+    new_host: Option<Uuid>,
+    new_kind: LogSourceKind,
+    target_state: Entity<InputState>,
+    pattern_state: Entity<InputState>,
+    export_minutes: Option<i64>,
+    /// Open requests offered as attachment targets, as `(id, title)`.
+    issues: Vec<(String, String)>,
+    attach_open: bool,
+    attach_busy: bool,
+    focus_handle: FocusHandle,
+    needs_focus: bool,
+    _pattern_sub: Subscription,
+}
+
+impl LogViewerView {
+    pub fn new(
+        connections: Vec<Connection>,
+        sources: Vec<LogSource>,
+        backlog: usize,
+        max_lines: usize,
+        selected_host: Option<Uuid>,
+        issues: Vec<(String, String)>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let new_host = selected_host.or_else(|| connections.first().map(|c| c.id));
+        let host_select = build_host_select(&connections, new_host, cx);
+        let target_state = cx.new(InputState::new);
+        let pattern_state = cx.new(InputState::new);
+        let pattern_sub = cx.subscribe(&pattern_state, |this, state, event, cx| {
+            if matches!(event, InputEvent::Change) {
+                let pattern = state.read(cx).content().to_string();
+                this.set_pattern(&pattern);
+                cx.notify();
+            }
+        });
+        Self {
+            connections,
+            sources,
+            backlog,
+            max_lines,
+            timeline: LogTimeline::new(max_lines),
+            filter: LogFilter::default(),
+            filter_error: None,
+            visible: Vec::new(),
+            held: VecDeque::new(),
+            paused: false,
+            tails: Vec::new(),
+            run: 0,
+            host_states: HashMap::new(),
+            source_notes: HashMap::new(),
+            host_select,
+            new_host,
+            new_kind: LogSourceKind::File,
+            target_state,
+            pattern_state,
+            export_minutes: Some(5),
+            issues,
+            attach_open: false,
+            attach_busy: false,
+            focus_handle: cx.focus_handle(),
+            needs_focus: true,
+            _pattern_sub: pattern_sub,
+        }
+    }
+
+    /// Pick the host new sources are added to, e.g. when reopened from
+    /// another connection's menu.
+    pub fn select_host(&mut self, connection_id: Uuid, cx: &mut Context<Self>) {
+        self.new_host = Some(connection_id);
+        self.host_select = build_host_select(&self.connections, self.new_host, cx);
+    }
+
+    pub fn set_issues(&mut self, issues: Vec<(String, String)>) {
+        self.issues = issues;
+    }
+
+    /// The upload started by `AttachToIssue` finished.
+    pub fn attach_finished(&mut self, ok: bool) {
+        self.attach_busy = false;
+        if ok {
+            self.attach_open = false;
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        !self.tails.is_empty()
+    }
+
+    fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        if event.keystroke.key.as_str() == "escape" {
+            cx.emit(LogViewerEvent::Close);
+        }
+    }
+
+    fn host_name(&self, connection_id: Uuid) -> String {
+        self.connections
+            .iter()
+            .find(|c| c.id == connection_id)
+            .map(|c| c.display_name().to_string())
+            .unwrap_or_else(|| connection_id.to_string())
+    }
+
+    /// Color of a host: its rank among the hosts of the source list.
+    fn host_color(&self, connection_id: Uuid) -> Hsla {
+        let mut hosts: Vec<Uuid> = Vec::new();
+        for source in &self.sources {
+            if !hosts.contains(&source.connection_id) {
+                hosts.push(source.connection_id);
+            }
+        }
+        let rank = hosts
+            .iter()
+            .position(|id| *id == connection_id)
+            .unwrap_or(0);
+        host_palette()[rank % host_palette().len()]
+    }
+
+    fn add_source(&mut self, cx: &mut Context<Self>) {
+        let Some(connection_id) = self.new_host else {
+            return;
+        };
+        let target = self.target_state.read(cx).content().trim().to_string();
+        if target.is_empty() {
+            return;
+        }
+        let source = LogSource::new(connection_id, self.new_kind, target);
+        if self.sources.contains(&source) {
+            return;
+        }
+        self.sources.push(source);
+        self.target_state.update(cx, |s, cx| s.reset(cx));
+        self.sources_changed(cx);
+    }
+
+    fn remove_source(&mut self, index: usize, cx: &mut Context<Self>) {
+        if index < self.sources.len() {
+            self.sources.remove(index);
+            self.sources_changed(cx);
+        }
+    }
+
+    /// Lines refer to sources by index, so edits start from a clean view.
+    fn sources_changed(&mut self, cx: &mut Context<Self>) {
+        self.timeline.clear();
+        self.held.clear();
+        self.source_notes.clear();
+        self.filter.hidden_sources.clear();
+        self.refresh_visible();
+        cx.emit(LogViewerEvent::SourcesChanged(self.sources.clone()));
+        cx.notify();
+    }
+
+    fn set_pattern(&mut self, pattern: &str) {
+        self.filter_error = self.filter.set_pattern(pattern).err();
+        self.refresh_visible();
+    }
+
+    fn refresh_visible(&mut self) {
+        let filter = &self.filter;
+        self.visible = self
+            .timeline
+            .lines()
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| filter.matches(line))
+            .map(|(index, _)| index)
+            .collect();
+    }
+
+    fn toggle_source(&mut self, index: usize) {
+        if !self.filter.hidden_sources.remove(&index) {
+            self.filter.hidden_sources.insert(index);
+        }
+        self.refresh_visible();
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            for (index, raw, received) in std::mem::take(&mut self.held) {
+                if let Some(source) = self.sources.get(index) {
+                    self.timeline.push(index, source, &raw, received);
+                }
+            }
+            self.refresh_visible();
+        }
+    }
+
+    /// Start one tailer per host of the source list.
+    fn start(&mut self, cx: &mut Context<Self>) {
+        if self.is_running() || self.sources.is_empty() {
+            return;
+        }
+        self.run += 1;
+        let run = self.run;
+        self.timeline.clear();
+        self.held.clear();
+        self.source_notes.clear();
+        self.host_states.clear();
+        self.refresh_visible();
+
+        let mut by_host: Vec<(Uuid, Vec<(usize, LogSource)>)> = Vec::new();
+        for (index, source) in self.sources.iter().enumerate() {
+            match by_host
+                .iter_mut()
+                .find(|(id, _)| *id == source.connection_id)
+            {
+                Some((_, sources)) => sources.push((index, source.clone())),
+                None => by_host.push((source.connection_id, vec![(index, source.clone())])),
+            }
+        }
+
+        for (connection_id, sources) in by_host {
+            let Some(connection) = self
+                .connections
+                .iter()
+                .find(|c| c.id == connection_id)
+                .cloned()
+            else {
+                self.host_states.insert(
+                    connection_id,
+                    HostState::Down(t!("log_viewer.host_missing").to_string()),
+                );
+                continue;
+            };
+            let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+            let (shutdown_tx, shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
+            let tailer = LogTailer::new(connection, sources, self.backlog, events_tx);
+            let thread = std::thread::Builder::new()
+                .name(format!("log-tail-{}", connection_id))
+                .spawn(move || {
+                    match tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()
+                    {
+                        Ok(rt) => rt.block_on(tailer.run(shutdown_rx)),
+                        Err(e) => tracing::error!("Failed to create async runtime: {}", e),
+                    }
+                });
+            let thread = match thread {
+                Ok(thread) => thread,
+                Err(e) => {
+                    tracing::error!("Failed to spawn log tail thread: {}", e);
+                    self.host_states
+                        .insert(connection_id, HostState::Down(e.to_string()));
+                    continue;
+                }
+            };
+            self.host_states
+                .insert(connection_id, HostState::Connecting);
+            self.tails.push(ActiveTail {
+                shutdown_tx,
+                _thread: thread,
+            });
+
+            cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+                cx.background_executor().timer(DRAIN_INTERVAL).await;
+                let mut events = Vec::new();
+                let mut finished = false;
+                loop {
+                    match events_rx.try_recv() {
+                        Ok(event) => events.push(event),
+                        Err(tokio::sync::mpsc::error::TryRecvError::Empty) => break,
+                        Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
+                            finished = true;
+                            break;
+                        }
+                    }
+                }
+                if events.is_empty() && !finished {
+                    continue;
+                }
+                let updated = this.update(cx, |view, cx| {
+                    if view.run == run {
+                        view.handle_events(connection_id, events);
+                        cx.notify();
+                    }
+                });
+                if finished || updated.is_err() {
+                    break;
+                }
+            })
+            .detach();
+        }
+        cx.notify();
+    }
+
+    fn stop(&mut self, cx: &mut Context<Self>) {
+        self.run += 1;
+        for tail in self.tails.drain(..) {
+            let _ = tail.shutdown_tx.try_send(());
+        }
+        self.host_states.clear();
+        cx.notify();
+    }
+
+    fn handle_events(&mut self, connection_id: Uuid, events: Vec<TailEvent>) {
+        let received = Utc::now();
+        let mut lines_added = false;
+        for event in events {
+            match event {
+                TailEvent::Connected => {
+                    self.host_states.insert(connection_id, HostState::Live);
+                    // A reconnect restarts every source of the host.
+                    let sources = &self.sources;
+                    self.source_notes.retain(|index, _| {
+                        sources
+                            .get(*index)
+                            .is_none_or(|s| s.connection_id != connection_id)
+                    });
+                }
+                TailEvent::Line { source, text } => {
+                    if self.paused {
+                        self.held.push_back((source, text, received));
+                        if self.held.len() > self.max_lines {
+                            self.held.pop_front();
+                        }
+                    } else if let Some(log_source) = self.sources.get(source) {
+                        self.timeline.push(source, log_source, &text, received);
+                        lines_added = true;
+                    }
+                }
+                TailEvent::SourceEnded { source, exit_code } => {
+                    let note = match exit_code {
+                        Some(code) => t!("log_viewer.source_exited", code = code).to_string(),
+                        None => t!("log_viewer.source_ended").to_string(),
+                    };
+                    self.source_notes.insert(source, note);
+                }
+                TailEvent::SourceFailed { source, error } => {
+                    self.source_notes.insert(source, error);
+                }
+                TailEvent::Down { reason, retry_in } => {
+                    let text = t!(
+                        "log_viewer.host_down",
+                        error = reason.as_str(),
+                        secs = retry_in.as_secs()
+                    )
+                    .to_string();
+                    self.host_states
+                        .insert(connection_id, HostState::Down(text));
+                }
+            }
+        }
+        if lines_added {
+            self.refresh_visible();
+        }
+    }
+
+    /// The export window: the chosen span back from the newest line.
+    fn export_text(&self) -> Option<(String, usize, DateTime<Utc>)> {
+        let to = self.timeline.lines().last()?.at;
+        let from = match self.export_minutes {
+            Some(minutes) => to - chrono::Duration::minutes(minutes),
+            None => DateTime::<Utc>::MIN_UTC,
+        };
+        let text = export_window(
+            self.timeline.lines(),
+            from,
+            to,
+            &self.filter,
+            |index| match self.sources.get(index) {
+                Some(source) => format!(
+                    "{} {}",
+                    self.host_name(source.connection_id),
+                    source.label()
+                ),
+                None => "?".to_string(),
+            },
+        );
+        let count = text.lines().count();
+        (count > 0).then_some((text, count, to))
+    }
+
+    fn copy_export(&self, cx: &mut Context<Self>) {
+        if let Some((text, _, _)) = self.export_text() {
+            cx.write_to_clipboard(ClipboardItem::new_string(text));
+        }
+    }
+
+    fn attach_export(&mut self, issue_id: String, cx: &mut Context<Self>) {
+        let Some((text, lines, to)) = self.export_text() else {
+            return;
+        };
+        self.attach_busy = true;
+        cx.emit(LogViewerEvent::AttachToIssue {
+            issue_id,
+            filename: format!("logs-{}.txt", to.format("%Y%m%d-%H%M%S")),
+            text,
+            lines,
+        });
+        cx.notify();
+    }
+
+    fn render_sources(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let running = self.is_running();
+        let mut kinds = div().flex().items_center().gap(px(4.0)).flex_shrink_0();
+        for kind in LogSourceKind::ALL {
+            let id = ElementId::from(SharedString::from(format!("log-kind-{}", kind.code())));
+            kinds = kinds.child(
+                Button::new(
+                    id,
+                    t!(&format!("log_viewer.kind.{}", kind.code())).to_string(),
+                )
+                .size(ButtonSize::Sm)
+                .h(gpui::px(26.0))
+                .px(gpui::px(8.0))
+                .variant(ButtonVariant::Outline)
+                .selected(self.new_kind == kind)
+                .on_click(cx.listener(move |this, _event, _window, cx| {
+                    this.new_kind = kind;
+                    cx.notify();
+                })),
+            );
+        }
+        let placeholder = t!(&format!("log_viewer.target.{}", self.new_kind.code())).to_string();
+        let add_row = div()
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .w(px(200.0))
+                    .flex_shrink_0()
+                    .child(self.host_select.clone()),
+            )
+            .child(kinds)
+            .child(
+                div().flex_grow().min_w(px(0.0)).child(
+                    Input::new(&self.target_state)
+                        .size(InputSize::Sm)
+                        .placeholder(placeholder),
+                ),
+            )
+            .child(
+                Button::new("log-add-source", t!("log_viewer.add").to_string())
+                    .size(ButtonSize::Sm)
+                    .variant(ButtonVariant::Outline)
+                    .disabled(running || self.new_host.is_none())
+                    .on_click(cx.listener(|this, _event, _window, cx| {
+                        this.add_source(cx);
+                    })),
+            );
+
+        let mut list = div().flex().flex_col().gap(px(2.0));
+        if self.sources.is_empty() {
+            list = list.child(
+                div()
+                    .text_size(px(12.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("log_viewer.no_sources").to_string()),
+            );
+        }
+        for (index, source) in self.sources.iter().enumerate() {
+            let color = self.host_color(source.connection_id);
+            let hidden = self.filter.hidden_sources.contains(&index);
+            let (state_text, state_color) = match self.host_states.get(&source.connection_id) {
+                _ if self.source_notes.contains_key(&index) => (
+                    self.source_notes[&index].clone(),
+                    ShellDeckColors::warning(),
+                ),
+                Some(HostState::Connecting) => (
+                    t!("log_viewer.connecting").to_string(),
+                    ShellDeckColors::text_muted(),
+                ),
+                Some(HostState::Live) => (
+                    t!("log_viewer.live").to_string(),
+                    ShellDeckColors::success(),
+                ),
+                Some(HostState::Down(reason)) => (reason.clone(), ShellDeckColors::error()),
+                None => (String::new(), ShellDeckColors::text_muted()),
+            };
+            let mut row = div()
+                .id(ElementId::from(SharedString::from(format!(
+                    "log-source-{}",
+                    index
+                ))))
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .py(px(2.0))
+                .text_size(px(12.0))
+                .child(
+                    Checkbox::new(ElementId::from(SharedString::from(format!(
+                        "log-source-shown-{}",
+                        index
+                    ))))
+                    .checked(!hidden)
+                    .on_click({
+                        let entity = cx.entity();
+                        move |_checked, _, cx| {
+                            entity.update(cx, |this, cx| {
+                                this.toggle_source(index);
+                                cx.notify();
+                            });
+                        }
+                    }),
+                )
+                .child(div().size(px(8.0)).rounded_full().bg(color).flex_shrink_0())
+                .child(
+                    div()
+                        .flex_shrink_0()
+                        .text_color(color)
+                        .font_weight(FontWeight::MEDIUM)
+                        .child(self.host_name(source.connection_id)),
+                )
+                .child(
+                    div()
+                        .flex_grow()
+                        .min_w(px(0.0))
+                        .truncate()
+                        .font_family("JetBrains Mono")
+                        .text_color(ShellDeckColors::text_primary())
+                        .child(source.label()),
+                )
+                .child(
+                    div()
+                        .max_w(px(260.0))
+                        .truncate()
+                        .text_size(px(11.0))
+                        .text_color(state_color)
+                        .child(state_text),
+                );
+            if !running {
+                row = row.child(
+                    div()
+                        .id(ElementId::from(SharedString::from(format!(
+                            "log-source-remove-{}",
+                            index
+                        ))))
+                        .cursor_pointer()
+                        .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                            this.remove_source(index, cx);
+                        }))
+                        .child(
+                            svg()
+                                .path("icons/lucide/x.svg")
+                                .size(px(12.0))
+                                .text_color(ShellDeckColors::text_muted()),
+                        ),
+                );
+            }
+            list = list.child(row);
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .px(px(20.0))
+            .py(px(10.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .when(!running, |el| el.child(add_row))
+            .child(list)
+    }
+
+    fn render_toolbar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let running = self.is_running();
+        let start = if running {
+            Button::new("log-stop", t!("log_viewer.stop").to_string())
+                .size(ButtonSize::Sm)
+                .variant(ButtonVariant::Outline)
+                .on_click(cx.listener(|this, _event, _window, cx| this.stop(cx)))
+        } else {
+            Button::new("log-start", t!("log_viewer.start").to_string())
+                .size(ButtonSize::Sm)
+                .variant(ButtonVariant::Default)
+                .disabled(self.sources.is_empty())
+                .on_click(cx.listener(|this, _event, _window, cx| this.start(cx)))
+        };
+        let paused = self.paused;
+        let pause = Button::new(
+            "log-pause",
+            if paused {
+                t!("log_viewer.resume", count = self.held.len()).to_string()
+            } else {
+                t!("log_viewer.pause").to_string()
+            },
+        )
+        .size(ButtonSize::Sm)
+        .variant(ButtonVariant::Outline)
+        .selected(paused)
+        .on_click(cx.listener(move |this, _event, _window, cx| {
+            this.set_paused(!paused);
+            cx.notify();
+        }));
+
+        let mut levels = div().flex().items_center().gap(px(4.0)).flex_shrink_0();
+        let all_levels = std::iter::once(None).chain(LogLevel::ALL.into_iter().skip(1).map(Some));
+        for level in all_levels {
+            let code = level.map_or("all", |l| l.code());
+            let id = ElementId::from(SharedString::from(format!("log-level-{}", code)));
+            levels = levels.child(
+                Button::new(id, t!(&format!("log_viewer.level.{}", code)).to_string())
+                    .size(ButtonSize::Sm)
+                    .h(gpui::px(26.0))
+                    .px(gpui::px(8.0))
+                    .variant(ButtonVariant::Outline)
+                    .selected(self.filter.min_level == level)
+                    .on_click(cx.listener(move |this, _event, _window, cx| {
+                        this.filter.min_level = level;
+                        this.refresh_visible();
+                        cx.notify();
+                    })),
+            );
+        }
+
+        let pattern = div()
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .min_w(px(0.0))
+            .child(
+                Input::new(&self.pattern_state)
+                    .size(InputSize::Sm)
+                    .placeholder(t!("log_viewer.filter_placeholder").to_string()),
+            )
+            .when_some(self.filter_error.clone(), |el, error| {
+                el.child(
+                    div()
+                        .pt(px(2.0))
+                        .text_size(px(11.0))
+                        .text_color(ShellDeckColors::error())
+                        .truncate()
+                        .child(t!("log_viewer.filter_invalid", error = error.as_str()).to_string()),
+                )
+            });
+
+        div()
+            .flex()
+            .items_center()
+            .gap(px(10.0))
+            .px(px(20.0))
+            .py(px(8.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .child(start)
+            .child(pause)
+            .child(pattern)
+            .child(levels)
+    }
+
+    fn render_line(&self, line: &LogLine) -> AnyElement {
+        let Some(source) = self.sources.get(line.source) else {
+            return div().into_any_element();
+        };
+        let color = self.host_color(source.connection_id);
+        let text_color = match line.level {
+            Some(LogLevel::Error) => ShellDeckColors::error(),
+            Some(LogLevel::Warn) => ShellDeckColors::warning(),
+            Some(LogLevel::Trace | LogLevel::Debug) => ShellDeckColors::text_muted(),
+            _ => ShellDeckColors::text_primary(),
+        };
+        let time = line
+            .at
+            .with_timezone(&Local)
+            .format("%H:%M:%S%.3f")
+            .to_string();
+        div()
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .h(px(20.0))
+            .px(px(20.0))
+            .font_family("JetBrains Mono")
+            .text_size(px(11.0))
+            .child(
+                div()
+                    .flex_shrink_0()
+                    .text_color(if line.dated {
+                        ShellDeckColors::text_muted()
+                    } else {
+                        ShellDeckColors::border()
+                    })
+                    .child(time),
+            )
+            .child(div().w(px(3.0)).h(px(14.0)).bg(color).flex_shrink_0())
+            .child(
+                div()
+                    .w(px(120.0))
+                    .flex_shrink_0()
+                    .truncate()
+                    .text_color(color)
+                    .child(self.host_name(source.connection_id)),
+            )
+            .child(
+                div()
+                    .flex_grow()
+                    .min_w(px(0.0))
+                    .truncate()
+                    .text_color(text_color)
+                    .child(line.text.clone()),
+            )
+            .into_any_element()
+    }
+
+    fn render_lines(&self, cx: &mut Context<Self>) -> AnyElement {
+        if self.visible.is_empty() {
+            let text = if self.timeline.lines().is_empty() {
+                if self.is_running() {
+                    t!("log_viewer.waiting").to_string()
+                } else {
+                    t!("log_viewer.empty").to_string()
+                }
+            } else {
+                t!("log_viewer.no_match").to_string()
+            };
+            return div()
+                .flex()
+                .flex_grow()
+                .items_center()
+                .justify_center()
+                .px(px(40.0))
+                .text_size(px(13.0))
+                .text_color(ShellDeckColors::text_muted())
+                .child(text)
+                .into_any_element();
+        }
+        uniform_list(
+            "log-lines",
+            self.visible.len(),
+            cx.processor(|this, range: Range<usize>, _window, _cx| {
+                let count = this.visible.len();
+                range
+                    .filter_map(|row| count.checked_sub(row + 1))
+                    .filter_map(|index| this.visible.get(index))
+                    .filter_map(|index| this.timeline.lines().get(*index))
+                    .map(|line| this.render_line(line))
+                    .collect::<Vec<_>>()
+            }),
+        )
+        .flex_grow()
+        .min_h(px(0.0))
+        .w_full()
+        .py(px(4.0))
+        .into_any_element()
+    }
+
+    fn render_export(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_lines = !self.visible.is_empty();
+        let mut windows = div().flex().items_center().gap(px(4.0)).child(
+            div()
+                .text_size(px(11.0))
+                .text_color(ShellDeckColors::text_muted())
+                .mr(px(2.0))
+                .child(t!("log_viewer.export").to_string()),
+        );
+        for minutes in EXPORT_WINDOWS {
+            let label = match minutes {
+                Some(minutes) if minutes >= 60 => {
+                    t!("log_viewer.window_hours", hours = minutes / 60).to_string()
+                }
+                Some(minutes) => t!("log_viewer.window_minutes", minutes = minutes).to_string(),
+                None => t!("log_viewer.window_all").to_string(),
+            };
+            let id = ElementId::from(SharedString::from(format!(
+                "log-window-{}",
+                minutes.unwrap_or(0)
+            )));
+            windows = windows.child(
+                Button::new(id, label)
+                    .size(ButtonSize::Sm)
+                    .h(gpui::px(26.0))
+                    .px(gpui::px(8.0))
+                    .variant(ButtonVariant::Outline)
+                    .selected(self.export_minutes == minutes)
+                    .on_click(cx.listener(move |this, _event, _window, cx| {
+                        this.export_minutes = minutes;
+                        cx.notify();
+                    })),
+            );
+        }
+
+        let actions = div()
+            .flex()
+            .items_center()
+            .gap(px(6.0))
+            .child(
+                Button::new("log-copy", t!("log_viewer.copy").to_string())
+                    .size(ButtonSize::Sm)
+                    .variant(ButtonVariant::Outline)
+                    .disabled(!has_lines)
+                    .on_click(cx.listener(|this, _event, _window, cx| this.copy_export(cx))),
+            )
+            .child(
+                Button::new(
+                    "log-attach",
+                    if self.attach_busy {
+                        t!("log_viewer.attaching").to_string()
+                    } else {
+                        t!("log_viewer.attach").to_string()
+                    },
+                )
+                .size(ButtonSize::Sm)
+                .variant(ButtonVariant::Outline)
+                .selected(self.attach_open)
+                .disabled(!has_lines || self.attach_busy)
+                .on_click(cx.listener(|this, _event, _window, cx| {
+                    this.attach_open = !this.attach_open;
+                    cx.notify();
+                })),
+            );
+
+        let mut section = div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .px(px(20.0))
+            .py(px(8.0))
+            .border_t_1()
+            .border_color(ShellDeckColors::border())
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(10.0))
+                    .child(windows)
+                    .child(actions),
+            );
+        if self.attach_open {
+            let mut issues = div()
+                .id("log-attach-issues")
+                .flex()
+                .flex_col()
+                .max_h(px(140.0))
+                .overflow_y_scroll();
+            if self.issues.is_empty() {
+                issues = issues.child(
+                    div()
+                        .text_size(px(12.0))
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(t!("log_viewer.no_requests").to_string()),
+                );
+            }
+            for (issue_id, title) in &self.issues {
+                let target = issue_id.clone();
+                issues = issues.child(
+                    div()
+                        .id(ElementId::from(SharedString::from(format!(
+                            "log-attach-{}",
+                            issue_id
+                        ))))
+                        .px(px(8.0))
+                        .py(px(4.0))
+                        .rounded(px(4.0))
+                        .truncate()
+                        .text_size(px(12.0))
+                        .text_color(ShellDeckColors::text_primary())
+                        .cursor_pointer()
+                        .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+                        .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                            if !this.attach_busy {
+                                this.attach_export(target.clone(), cx);
+                            }
+                        }))
+                        .child(title.clone()),
+                );
+            }
+            section = section.child(issues);
+        }
+        section
+    }
+}
+
+impl Render for LogViewerView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.needs_focus {
+            self.needs_focus = false;
+            self.focus_handle.focus(window);
+        }
+
+        let count = if self.paused {
+            t!(
+                "log_viewer.paused_count",
+                shown = self.visible.len(),
+                held = self.held.len()
+            )
+            .to_string()
+        } else {
+            t!(
+                "log_viewer.count",
+                shown = self.visible.len(),
+                total = self.timeline.lines().len()
+            )
+            .to_string()
+        };
+
+        div()
+            .id("log-viewer-overlay")
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
+                this.handle_key_down(event, cx);
+            }))
+            .occlude()
+            .absolute()
+            .top_0()
+            .left_0()
+            .right_0()
+            .bottom_0()
+            .bg(ShellDeckColors::backdrop())
+            .flex()
+            .justify_center()
+            .items_center()
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .w(relative(0.92))
+                    .h(relative(0.9))
+                    .bg(ShellDeckColors::bg_surface())
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(ShellDeckColors::border())
+                    .shadow_xl()
+                    .overflow_hidden()
+                    // Header
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .gap(px(12.0))
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_b_1()
+                            .border_color(ShellDeckColors::border())
+                            .child(
+                                div()
+                                    .text_size(px(16.0))
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .child(t!("log_viewer.title").to_string()),
+                            )
+                            .child(
+                                div()
+                                    .flex()
+                                    .items_center()
+                                    .gap(px(12.0))
+                                    .child(
+                                        div()
+                                            .text_size(px(12.0))
+                                            .text_color(ShellDeckColors::text_muted())
+                                            .child(count),
+                                    )
+                                    .child(
+                                        div()
+                                            .id("close-log-viewer")
+                                            .flex()
+                                            .items_center()
+                                            .justify_center()
+                                            .cursor_pointer()
+                                            .text_color(ShellDeckColors::text_muted())
+                                            .hover(|el| {
+                                                el.text_color(ShellDeckColors::text_primary())
+                                            })
+                                            .on_click(cx.listener(
+                                                |_this, _: &ClickEvent, _, cx| {
+                                                    cx.emit(LogViewerEvent::Close);
+                                                },
+                                            ))
+                                            .child(
+                                                svg()
+                                                    .path("icons/lucide/x.svg")
+                                                    .size(px(14.0))
+                                                    .text_color(ShellDeckColors::text_muted()),
+                                            ),
+                                    ),
+                            ),
+                    )
+                    .child(self.render_sources(cx))
+                    .child(self.render_toolbar(cx))
+                    .child(self.render_lines(cx))
+                    .child(self.render_export(cx)),
+            )
+    }
+}
+
+fn build_host_select(
+    connections: &[Connection],
+    selected: Option<Uuid>,
+    cx: &mut Context<LogViewerView>,
+) -> Entity<Select<Uuid>> {
+    let options: Vec<SelectOption<Uuid>> = connections
+        .iter()
+        .map(|conn| {
+            SelectOption::new(conn.id, conn.display_name().to_string())
+                .with_icon("icons/lucide/server.svg")
+        })
+        .collect();
+    let selected_index = selected.and_then(|id| options.iter().position(|o| o.value == id));
+    let parent = cx.entity();
+    cx.new(|select_cx| {
+        Select::new(select_cx)
+            .options(options)
+            .selected_index(selected_index)
+            .placeholder(t!("log_viewer.host_placeholder").to_string())
+            .searchable(true)
+            .on_change(move |conn_id, _window, cx| {
+                let conn_id = *conn_id;
+                parent.update(cx, |view, cx| {
+                    view.new_host = Some(conn_id);
+                    cx.notify();
+                });
+            })
+    })
+}
+
+/// Theme colors that tell hosts apart, in the order hosts are assigned.
+fn host_palette() -> [Hsla; 6] {
+    [
+        ShellDeckColors::primary(),
+        ShellDeckColors::syntax_string(),
+        ShellDeckColors::syntax_keyword(),
+        ShellDeckColors::syntax_number(),
+        ShellDeckColors::syntax_builtin(),
+        ShellDeckColors::syntax_variable(),
+    ]
+}
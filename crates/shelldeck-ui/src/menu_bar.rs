@@ -35,6 +35,8 @@ pub enum MenuCommand {
     QuickConnect,
     NewTerminal,
     NewScript,
+    ImportConnections,
+    ManageKeys,
     NewRequest,
     SyncNow,
     OpenCompanionSettings,
@@ -223,6 +225,22 @@ pub fn menu_bar_spec(ctx: MenuBarContext) -> Vec<MenuSpec> {
                 )
                 .icon("scroll-text"),
             );
+            file.push(
+                MenuEntry::command(
+                    "file-import-connections",
+                    t!("menu.file.import_connections").to_string(),
+                    MenuCommand::ImportConnections,
+                )
+                .icon("download"),
+            );
+            file.push(
+                MenuEntry::command(
+                    "file-ssh-keys",
+                    t!("menu.file.ssh_keys").to_string(),
+                    MenuCommand::ManageKeys,
+                )
+                .icon("key"),
+            );
             file.push(MenuEntry::Separator);
         }
         file.push(
@@ -723,6 +741,8 @@ mod tests {
             MenuCommand::QuickConnect,
             MenuCommand::NewTerminal,
             MenuCommand::NewScript,
+            MenuCommand::ImportConnections,
+            MenuCommand::ManageKeys,
             MenuCommand::GoTerminal,
             MenuCommand::GoFileEditor,
             MenuCommand::ToggleSidebar,
@@ -1,4 +1,5 @@
 use crate::scale::px;
+use adabraka_ui::components::checkbox::Checkbox;
 use adabraka_ui::components::combobox::Combobox;
 use adabraka_ui::components::icon_source::IconSource;
 use adabraka_ui::components::input::{Input, InputSize, InputState};
@@ -6,7 +7,10 @@ use adabraka_ui::prelude::{Button, ButtonSize, ButtonVariant};
 use gpui::prelude::*;
 use gpui::*;
 
-use shelldeck_core::models::port_forward::{ForwardDirection, PortForward};
+use shelldeck_core::models::forward_proxy::{parse_destination_rules, ProxySettings};
+use shelldeck_core::models::port_forward::{
+    parse_client_allowlist, ForwardDirection, ForwardProbe, PortForward, ProbeKind,
+};
 use uuid::Uuid;
 
 use crate::connection_combobox::{build_connection_combobox, connection_idx_for_id};
@@ -27,8 +31,25 @@ impl EventEmitter<PortForwardFormEvent> for PortForwardForm {}
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum FormField {
     Connection,
+    LocalHost,
     LocalPort,
     RemotePort,
+    ProbeInterval,
+    AllowedClients,
+    ProxyUsername,
+    ProxyPassword,
+    ProxyRules,
+}
+
+/// Shortest probe interval the form accepts, in seconds.
+const MIN_PROBE_INTERVAL_SECS: u64 = 5;
+
+/// Which far-end probe the form has selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ProbeChoice {
+    None,
+    Tcp,
+    Http,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -38,6 +59,12 @@ enum ValidationError {
     LocalPortRange,
     RemotePortInvalid,
     RemotePortRange,
+    ProbeIntervalInvalid,
+    LanNotConfirmed,
+    AllowedClientsInvalid,
+    ProxyLoginRequired,
+    ProxyPasswordMissing,
+    ProxyRulesInvalid,
 }
 
 fn forward_form_error(err: ValidationError) -> String {
@@ -51,6 +78,24 @@ fn forward_form_error(err: ValidationError) -> String {
             t!("forward_form.error.remote_port_invalid").to_string()
         }
         ValidationError::RemotePortRange => t!("forward_form.error.remote_port_range").to_string(),
+        ValidationError::ProbeIntervalInvalid => t!(
+            "forward_form.error.probe_interval_invalid",
+            min = MIN_PROBE_INTERVAL_SECS
+        )
+        .to_string(),
+        ValidationError::LanNotConfirmed => t!("forward_form.error.lan_not_confirmed").to_string(),
+        ValidationError::AllowedClientsInvalid => {
+            t!("forward_form.error.allowed_clients_invalid").to_string()
+        }
+        ValidationError::ProxyLoginRequired => {
+            t!("forward_form.error.proxy_login_required").to_string()
+        }
+        ValidationError::ProxyPasswordMissing => {
+            t!("forward_form.error.proxy_password_missing").to_string()
+        }
+        ValidationError::ProxyRulesInvalid => {
+            t!("forward_form.error.proxy_rules_invalid").to_string()
+        }
     }
 }
 
@@ -61,6 +106,12 @@ fn forward_form_error_field(err: ValidationError) -> FormField {
         ValidationError::RemotePortInvalid | ValidationError::RemotePortRange => {
             FormField::RemotePort
         }
+        ValidationError::ProbeIntervalInvalid => FormField::ProbeInterval,
+        ValidationError::LanNotConfirmed => FormField::LocalHost,
+        ValidationError::AllowedClientsInvalid => FormField::AllowedClients,
+        ValidationError::ProxyLoginRequired => FormField::ProxyUsername,
+        ValidationError::ProxyPasswordMissing => FormField::ProxyPassword,
+        ValidationError::ProxyRulesInvalid => FormField::ProxyRules,
     }
 }
 
@@ -74,6 +125,21 @@ pub struct PortForwardForm {
     local_port_state: Entity<InputState>,
     remote_host_state: Entity<InputState>,
     remote_port_state: Entity<InputState>,
+    auto_start: bool,
+    probe: ProbeChoice,
+    probe_path_state: Entity<InputState>,
+    probe_interval_state: Entity<InputState>,
+    allow_lan: bool,
+    allowed_clients_state: Entity<InputState>,
+    auto_port: bool,
+    inspect_http: bool,
+    proxy_username_state: Entity<InputState>,
+    /// Left empty to keep the password already in the keychain.
+    proxy_password_state: Entity<InputState>,
+    /// Whether the forward being edited has a proxy password stored.
+    proxy_password_stored: bool,
+    proxy_rules_state: Entity<InputState>,
+    serve_pac: bool,
     ai_enabled: bool,
     error: Option<String>,
     error_field: Option<FormField>,
@@ -95,6 +161,17 @@ fn new_input_state(cx: &mut Context<PortForwardForm>, initial: &str) -> Entity<I
     })
 }
 
+fn new_multi_line_state(cx: &mut Context<PortForwardForm>, initial: &str) -> Entity<InputState> {
+    let initial = initial.to_string();
+    cx.new(|cx| {
+        let mut s = InputState::new(cx).multi_line(true);
+        if !initial.is_empty() {
+            s.content = initial.into();
+        }
+        s
+    })
+}
+
 impl PortForwardForm {
     fn init_connection_combobox(
         connections: &[(Uuid, String, String)],
@@ -142,6 +219,19 @@ impl PortForwardForm {
             local_port_state: new_input_state(cx, ""),
             remote_host_state: new_input_state(cx, "127.0.0.1"),
             remote_port_state: new_input_state(cx, ""),
+            auto_start: false,
+            probe: ProbeChoice::None,
+            probe_path_state: new_input_state(cx, "/"),
+            probe_interval_state: new_input_state(cx, "30"),
+            allow_lan: false,
+            allowed_clients_state: new_input_state(cx, ""),
+            auto_port: false,
+            inspect_http: false,
+            proxy_username_state: new_input_state(cx, ""),
+            proxy_password_state: new_input_state(cx, ""),
+            proxy_password_stored: false,
+            proxy_rules_state: new_multi_line_state(cx, ""),
+            serve_pac: false,
             ai_enabled,
             error: None,
             error_field: None,
@@ -162,6 +252,17 @@ impl PortForwardForm {
             .position(|(id, _, _)| *id == forward.connection_id)
             .unwrap_or(0);
         let connection_combobox = Self::init_connection_combobox(&connections, selected_idx, cx);
+        let (probe, probe_path, probe_interval) = match &forward.probe {
+            None => (ProbeChoice::None, "/".to_string(), 30),
+            Some(ForwardProbe {
+                kind: ProbeKind::Tcp,
+                interval_secs,
+            }) => (ProbeChoice::Tcp, "/".to_string(), *interval_secs),
+            Some(ForwardProbe {
+                kind: ProbeKind::Http { path },
+                interval_secs,
+            }) => (ProbeChoice::Http, path.clone(), *interval_secs),
+        };
         Self {
             editing_id: Some(forward.id),
             connections,
@@ -172,6 +273,22 @@ impl PortForwardForm {
             local_port_state: new_input_state(cx, &forward.local_port.to_string()),
             remote_host_state: new_input_state(cx, &forward.remote_host),
             remote_port_state: new_input_state(cx, &forward.remote_port.to_string()),
+            auto_start: forward.auto_start,
+            probe,
+            probe_path_state: new_input_state(cx, &probe_path),
+            probe_interval_state: new_input_state(cx, &probe_interval.to_string()),
+            allow_lan: forward.allow_lan,
+            allowed_clients_state: new_input_state(cx, &forward.allowed_clients.join(", ")),
+            auto_port: forward.auto_port,
+            inspect_http: forward.inspect_http,
+            proxy_username_state: new_input_state(
+                cx,
+                forward.proxy.username.as_deref().unwrap_or(""),
+            ),
+            proxy_password_state: new_input_state(cx, ""),
+            proxy_password_stored: forward.proxy.requires_auth(),
+            proxy_rules_state: new_multi_line_state(cx, &forward.proxy.rules.join("\n")),
+            serve_pac: forward.proxy.serve_pac,
             ai_enabled,
             error: None,
             error_field: None,
@@ -271,6 +388,7 @@ impl PortForwardForm {
         if remote_port == 0 {
             return Err(ValidationError::RemotePortRange);
         }
+        let probe = self.validate_probe(cx)?;
 
         let mut forward = match self.direction {
             ForwardDirection::LocalToRemote => {
@@ -291,12 +409,101 @@ impl PortForwardForm {
         if !label.is_empty() {
             forward.label = Some(label);
         }
+        forward.auto_start = self.auto_start;
+        forward.probe = probe;
+        forward.inspect_http =
+            self.inspect_http && forward.direction == ForwardDirection::LocalToRemote;
+        if forward.listens_locally() {
+            forward.auto_port = self.auto_port;
+            if forward.exposes_to_network() {
+                if !self.allow_lan {
+                    return Err(ValidationError::LanNotConfirmed);
+                }
+                forward.allow_lan = true;
+                forward.allowed_clients =
+                    parse_client_allowlist(&Self::field_value(&self.allowed_clients_state, cx))
+                        .map_err(|_| ValidationError::AllowedClientsInvalid)?
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect();
+            }
+        }
+        if forward.direction == ForwardDirection::Dynamic {
+            forward.proxy = self.validate_proxy(forward.exposes_to_network(), cx)?;
+        }
         if let Some(id) = self.editing_id {
             forward.id = id;
         }
         Ok(forward)
     }
 
+    /// Proxy settings of a dynamic forward. A proxy other machines can
+    /// reach must ask for a login.
+    fn validate_proxy(
+        &self,
+        exposed: bool,
+        cx: &Context<Self>,
+    ) -> Result<ProxySettings, ValidationError> {
+        let username = Self::field_value(&self.proxy_username_state, cx)
+            .trim()
+            .to_string();
+        let password = Self::field_value(&self.proxy_password_state, cx);
+        if username.is_empty() && exposed {
+            return Err(ValidationError::ProxyLoginRequired);
+        }
+        if !username.is_empty() && password.is_empty() && !self.proxy_password_stored {
+            return Err(ValidationError::ProxyPasswordMissing);
+        }
+        let rules_text = Self::field_value(&self.proxy_rules_state, cx);
+        parse_destination_rules(&rules_text).map_err(|_| ValidationError::ProxyRulesInvalid)?;
+        let rules = rules_text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        let login = !username.is_empty();
+        Ok(ProxySettings {
+            username: login.then_some(username),
+            password: (login && !password.is_empty()).then_some(password),
+            rules,
+            serve_pac: self.serve_pac,
+        })
+    }
+
+    /// Dynamic forwards have no single far end, so they carry no probe.
+    fn validate_probe(&self, cx: &Context<Self>) -> Result<Option<ForwardProbe>, ValidationError> {
+        if self.probe == ProbeChoice::None || self.direction == ForwardDirection::Dynamic {
+            return Ok(None);
+        }
+        let interval_secs: u64 = Self::field_value(&self.probe_interval_state, cx)
+            .trim()
+            .parse()
+            .map_err(|_| ValidationError::ProbeIntervalInvalid)?;
+        if interval_secs < MIN_PROBE_INTERVAL_SECS {
+            return Err(ValidationError::ProbeIntervalInvalid);
+        }
+        let kind = match self.probe {
+            ProbeChoice::Http => {
+                let path = Self::field_value(&self.probe_path_state, cx)
+                    .trim()
+                    .to_string();
+                ProbeKind::Http {
+                    path: if path.is_empty() {
+                        "/".to_string()
+                    } else {
+                        path
+                    },
+                }
+            }
+            _ => ProbeKind::Tcp,
+        };
+        Ok(Some(ForwardProbe {
+            kind,
+            interval_secs,
+        }))
+    }
+
     fn render_text_field(
         &self,
         field: Option<FormField>,
@@ -344,6 +551,285 @@ impl PortForwardForm {
             .child(input)
     }
 
+    fn render_chip(id: String, label: String, selected: bool) -> Stateful<Div> {
+        let chip = div()
+            .id(ElementId::from(SharedString::from(id)))
+            .px(px(10.0))
+            .py(px(4.0))
+            .rounded(px(6.0))
+            .text_size(px(12.0))
+            .font_weight(FontWeight::MEDIUM)
+            .cursor_pointer();
+        let chip = if selected {
+            chip.bg(ShellDeckColors::primary().opacity(0.2))
+                .text_color(ShellDeckColors::primary())
+                .border_1()
+                .border_color(ShellDeckColors::primary())
+        } else {
+            chip.bg(ShellDeckColors::bg_primary())
+                .text_color(ShellDeckColors::text_muted())
+                .border_1()
+                .border_color(ShellDeckColors::border())
+                .hover(|el| el.border_color(ShellDeckColors::text_muted()))
+        };
+        chip.child(label)
+    }
+
+    /// Keep-alive settings: start with ShellDeck, and how the supervisor
+    /// checks the far end.
+    fn render_supervision(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let entity = cx.entity();
+        let mut section = div().flex().flex_col().gap(px(8.0)).child(
+            Checkbox::new("pf-auto-start")
+                .checked(self.auto_start)
+                .label(t!("forward_form.field.auto_start").to_string())
+                .on_click(move |checked, _, cx| {
+                    let value = *checked;
+                    entity.update(cx, |this, cx| {
+                        this.auto_start = value;
+                        cx.notify();
+                    });
+                }),
+        );
+        if self.direction == ForwardDirection::Dynamic {
+            return section;
+        }
+
+        let options = [
+            (ProbeChoice::None, t!("forward_form.probe.none").to_string()),
+            (ProbeChoice::Tcp, t!("forward_form.probe.tcp").to_string()),
+            (ProbeChoice::Http, t!("forward_form.probe.http").to_string()),
+        ];
+        let mut chips = div().flex().gap(px(6.0));
+        for (choice, label) in options {
+            chips = chips.child(
+                Self::render_chip(format!("pf-probe-{label}"), label, self.probe == choice)
+                    .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                        this.probe = choice;
+                        this.error = None;
+                        this.error_field = None;
+                        cx.notify();
+                    })),
+            );
+        }
+        section = section.child(
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(4.0))
+                .child(
+                    div()
+                        .text_size(px(12.0))
+                        .font_weight(FontWeight::MEDIUM)
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(t!("forward_form.field.probe").to_string()),
+                )
+                .child(chips),
+        );
+        if self.probe != ProbeChoice::None {
+            let mut row = div().flex().gap(px(12.0));
+            if self.probe == ProbeChoice::Http {
+                row = row.child(div().flex_grow().child(self.render_text_field(
+                    None,
+                    t!("forward_form.field.probe_path").to_string(),
+                    &self.probe_path_state,
+                    "/health",
+                    cx,
+                )));
+            }
+            section = section.child(row.child(div().w(px(120.0)).child(self.render_text_field(
+                Some(FormField::ProbeInterval),
+                t!("forward_form.field.probe_interval").to_string(),
+                &self.probe_interval_state,
+                "30",
+                cx,
+            ))));
+        }
+        section
+    }
+
+    /// Who may reach the local listener, and what happens when its port
+    /// is taken. Only forwards that listen on this machine have one.
+    fn render_listener(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut section = div().flex().flex_col().gap(px(8.0));
+        if self.direction == ForwardDirection::RemoteToLocal {
+            return section;
+        }
+        let entity = cx.entity();
+        section = section.child(
+            Checkbox::new("pf-auto-port")
+                .checked(self.auto_port)
+                .label(t!("forward_form.field.auto_port").to_string())
+                .on_click(move |checked, _, cx| {
+                    let value = *checked;
+                    entity.update(cx, |this, cx| {
+                        this.auto_port = value;
+                        cx.notify();
+                    });
+                }),
+        );
+        let entity = cx.entity();
+        section = section.child(
+            Checkbox::new("pf-allow-lan")
+                .checked(self.allow_lan)
+                .label(t!("forward_form.field.allow_lan").to_string())
+                .on_click(move |checked, _, cx| {
+                    let value = *checked;
+                    entity.update(cx, |this, cx| {
+                        this.allow_lan = value;
+                        this.error = None;
+                        this.error_field = None;
+                        cx.notify();
+                    });
+                }),
+        );
+        if self.direction == ForwardDirection::LocalToRemote {
+            let entity = cx.entity();
+            section = section.child(
+                Checkbox::new("pf-inspect-http")
+                    .checked(self.inspect_http)
+                    .label(t!("forward_form.field.inspect_http").to_string())
+                    .on_click(move |checked, _, cx| {
+                        let value = *checked;
+                        entity.update(cx, |this, cx| {
+                            this.inspect_http = value;
+                            cx.notify();
+                        });
+                    }),
+            );
+        }
+        if self.allow_lan {
+            section = section
+                .child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(ShellDeckColors::warning())
+                        .child(t!("forward_form.lan_warning").to_string()),
+                )
+                .child(self.render_text_field(
+                    Some(FormField::AllowedClients),
+                    t!("forward_form.field.allowed_clients").to_string(),
+                    &self.allowed_clients_state,
+                    "192.168.1.0/24, 10.0.0.5",
+                    cx,
+                ));
+        }
+        section
+    }
+
+    /// Login, destination rules and PAC file of a dynamic forward's proxy.
+    fn render_proxy(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut section = div().flex().flex_col().gap(px(8.0));
+        if self.direction != ForwardDirection::Dynamic {
+            return section;
+        }
+        let password_placeholder = if self.proxy_password_stored {
+            t!("forward_form.field.proxy_password_kept").to_string()
+        } else {
+            String::new()
+        };
+        let password_error = self.error_field == Some(FormField::ProxyPassword);
+        section = section.child(
+            div()
+                .flex()
+                .gap(px(12.0))
+                .child(div().flex_1().child(self.render_text_field(
+                    Some(FormField::ProxyUsername),
+                    t!("forward_form.field.proxy_username").to_string(),
+                    &self.proxy_username_state,
+                    t!("forward_form.field.proxy_username_placeholder").to_string(),
+                    cx,
+                )))
+                .child(
+                    div()
+                        .flex_1()
+                        .flex()
+                        .flex_col()
+                        .gap(px(4.0))
+                        .child(
+                            div()
+                                .text_size(px(12.0))
+                                .font_weight(FontWeight::MEDIUM)
+                                .text_color(ShellDeckColors::text_muted())
+                                .child(t!("forward_form.field.proxy_password").to_string()),
+                        )
+                        .child(
+                            Input::new(&self.proxy_password_state)
+                                .size(InputSize::Sm)
+                                .password(true)
+                                .placeholder(SharedString::from(password_placeholder))
+                                .error(password_error)
+                                .on_change({
+                                    let entity = cx.entity();
+                                    move |_value, cx| {
+                                        entity.update(cx, |this, cx| {
+                                            if this.error.is_some() {
+                                                this.error = None;
+                                                this.error_field = None;
+                                                cx.notify();
+                                            }
+                                        });
+                                    }
+                                }),
+                        ),
+                ),
+        );
+        let rules_error = self.error_field == Some(FormField::ProxyRules);
+        section = section.child(
+            div()
+                .flex()
+                .flex_col()
+                .gap(px(4.0))
+                .child(
+                    div()
+                        .text_size(px(12.0))
+                        .font_weight(FontWeight::MEDIUM)
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(t!("forward_form.field.proxy_rules").to_string()),
+                )
+                .child(
+                    Input::new(&self.proxy_rules_state)
+                        .size(InputSize::Sm)
+                        .multi_line(true)
+                        .min_rows(2)
+                        .max_rows(5)
+                        .placeholder(SharedString::from("allow *.corp.example\ndeny 10.0.0.0/8"))
+                        .error(rules_error)
+                        .on_change({
+                            let entity = cx.entity();
+                            move |_value, cx| {
+                                entity.update(cx, |this, cx| {
+                                    if this.error.is_some() {
+                                        this.error = None;
+                                        this.error_field = None;
+                                        cx.notify();
+                                    }
+                                });
+                            }
+                        }),
+                )
+                .child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(t!("forward_form.proxy_rules_hint").to_string()),
+                ),
+        );
+        let entity = cx.entity();
+        section.child(
+            Checkbox::new("pf-serve-pac")
+                .checked(self.serve_pac)
+                .label(t!("forward_form.field.serve_pac").to_string())
+                .on_click(move |checked, _, cx| {
+                    let value = *checked;
+                    entity.update(cx, |this, cx| {
+                        this.serve_pac = value;
+                        cx.notify();
+                    });
+                }),
+        )
+    }
+
     fn render_direction_chips(&self, cx: &mut Context<Self>) -> impl IntoElement {
         let options = [
             (
@@ -363,39 +849,14 @@ impl PortForwardForm {
         let mut chips = div().flex().gap(px(6.0));
 
         for (dir, label) in options {
-            let selected = self.direction == dir;
-            let mut chip = div()
-                .id(ElementId::from(SharedString::from(format!(
-                    "pf-dir-{label}"
-                ))))
-                .px(px(10.0))
-                .py(px(4.0))
-                .rounded(px(6.0))
-                .text_size(px(12.0))
-                .font_weight(FontWeight::MEDIUM)
-                .cursor_pointer()
-                .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
-                    this.direction = dir;
-                    this.error = None;
-                    cx.notify();
-                }));
-
-            if selected {
-                chip = chip
-                    .bg(ShellDeckColors::primary().opacity(0.2))
-                    .text_color(ShellDeckColors::primary())
-                    .border_1()
-                    .border_color(ShellDeckColors::primary());
-            } else {
-                chip = chip
-                    .bg(ShellDeckColors::bg_primary())
-                    .text_color(ShellDeckColors::text_muted())
-                    .border_1()
-                    .border_color(ShellDeckColors::border())
-                    .hover(|el| el.border_color(ShellDeckColors::text_muted()));
-            }
-
-            chips = chips.child(chip.child(label.clone()));
+            chips = chips.child(
+                Self::render_chip(format!("pf-dir-{label}"), label, self.direction == dir)
+                    .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                        this.direction = dir;
+                        this.error = None;
+                        cx.notify();
+                    })),
+            );
         }
 
         div()
@@ -494,7 +955,7 @@ impl Render for PortForwardForm {
                     .flex()
                     .gap(px(12.0))
                     .child(div().flex_grow().child(self.render_text_field(
-                        None,
+                        Some(FormField::LocalHost),
                         t!("forward_form.field.local_host").to_string(),
                         &self.local_host_state,
                         "127.0.0.1",
@@ -526,7 +987,10 @@ impl Render for PortForwardForm {
                         "80",
                         cx,
                     ))),
-            );
+            )
+            .child(self.render_listener(cx))
+            .child(self.render_proxy(cx))
+            .child(self.render_supervision(cx));
 
         // Error message
         if let Some(ref error) = self.error {
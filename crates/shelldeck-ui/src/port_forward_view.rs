@@ -1,7 +1,15 @@
+mod traffic;
+
 use crate::scale::px;
 use adabraka_ui::prelude::*;
 use gpui::*;
+use shelldeck_core::models::forward_proxy::PAC_PATH;
 use shelldeck_core::models::port_forward::{ForwardDirection, ForwardStatus, PortForward};
+use shelldeck_core::models::tunnel_health::ForwardHistory;
+use shelldeck_ssh::tunnel::SharedTraffic;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
 use uuid::Uuid;
 
 use crate::t;
@@ -16,12 +24,29 @@ pub enum PortForwardEvent {
     AddForward,
     EditForward(Uuid),
     AddPresetForward(PortForwardModel),
+    /// Send a captured HTTP request through the forward again.
+    ReplayRequest {
+        forward_id: Uuid,
+        exchange_id: u64,
+    },
 }
 
 impl EventEmitter<PortForwardEvent> for PortForwardView {}
 
 pub struct PortForwardView {
     pub forwards: Vec<PortForward>,
+    /// Up/down history of supervised forwards.
+    pub history: HashMap<Uuid, ForwardHistory>,
+    /// Last far-end probe of each running forward.
+    pub probes: HashMap<Uuid, Result<Duration, String>>,
+    /// Where each running local or dynamic forward actually listens.
+    pub bound_addrs: HashMap<Uuid, SocketAddr>,
+    /// Connections and captured HTTP of each forward started this session.
+    pub traffic: HashMap<Uuid, SharedTraffic>,
+    /// Forward whose traffic panel is open.
+    inspecting: Option<Uuid>,
+    /// HTTP exchange shown with its headers and bodies.
+    expanded_exchange: Option<u64>,
 }
 
 impl Default for PortForwardView {
@@ -34,7 +59,75 @@ impl PortForwardView {
     pub fn new() -> Self {
         Self {
             forwards: Vec::new(),
+            history: HashMap::new(),
+            probes: HashMap::new(),
+            bound_addrs: HashMap::new(),
+            traffic: HashMap::new(),
+            inspecting: None,
+            expanded_exchange: None,
+        }
+    }
+
+    /// Uptime and flaps over the last day, and the last probe result.
+    fn health_summary(&self, forward: &PortForward) -> Option<(String, bool)> {
+        let now = chrono::Utc::now();
+        let since = now - chrono::Duration::hours(24);
+        let mut parts = Vec::new();
+        let mut failing = false;
+        if let Some(history) = self.history.get(&forward.id) {
+            if let Some(uptime) = history.uptime_percent(since, now) {
+                parts.push(
+                    t!("forwards.health.uptime", percent = format!("{:.1}", uptime)).to_string(),
+                );
+            }
+            let flaps = history.flaps_since(since);
+            if flaps > 0 {
+                parts.push(t!("forwards.health.flaps", count = flaps).to_string());
+            }
         }
+        if forward.status == ForwardStatus::Reconnecting {
+            failing = true;
+            parts.push(t!("forwards.health.reconnecting").to_string());
+        } else if let Some(probe) = self.probes.get(&forward.id) {
+            match probe {
+                Ok(latency) => {
+                    parts.push(t!("forwards.health.probe_ok", ms = latency.as_millis()).to_string())
+                }
+                Err(error) => {
+                    failing = true;
+                    parts.push(t!("forwards.health.probe_failed", error = error).to_string());
+                }
+            }
+        }
+        if parts.is_empty() {
+            return None;
+        }
+        Some((parts.join(" · "), failing))
+    }
+
+    /// The local endpoint, with the port actually bound when a free one
+    /// was picked instead of the configured one.
+    fn local_endpoint(&self, forward: &PortForward) -> String {
+        let port = self
+            .bound_addrs
+            .get(&forward.id)
+            .map_or(forward.local_port, |addr| addr.port());
+        format!("{}:{}", forward.local_host, port)
+    }
+
+    /// Where browsers on this machine fetch a running proxy's PAC file.
+    fn pac_url(&self, forward: &PortForward) -> Option<String> {
+        if forward.direction != ForwardDirection::Dynamic || !forward.proxy.serve_pac {
+            return None;
+        }
+        let mut addr = *self.bound_addrs.get(&forward.id)?;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
+        }
+        Some(format!("http://{}{}", addr, PAC_PATH))
     }
 
     fn render_forward_row(
@@ -46,7 +139,7 @@ impl PortForwardView {
             ForwardStatus::Active => ShellDeckColors::success(),
             ForwardStatus::Inactive => ShellDeckColors::text_muted(),
             ForwardStatus::Error => ShellDeckColors::error(),
-            ForwardStatus::Stopping => ShellDeckColors::warning(),
+            ForwardStatus::Stopping | ForwardStatus::Reconnecting => ShellDeckColors::warning(),
         };
 
         let direction_arrow = match forward.direction {
@@ -58,7 +151,7 @@ impl PortForwardView {
         let direction_label = match forward.direction {
             ForwardDirection::LocalToRemote => "Local -> Remote",
             ForwardDirection::RemoteToLocal => "Remote -> Local",
-            ForwardDirection::Dynamic => "SOCKS Proxy",
+            ForwardDirection::Dynamic => "SOCKS / HTTP Proxy",
         };
 
         div()
@@ -78,20 +171,34 @@ impl PortForwardView {
                     .items_center()
                     .child(div().w(px(8.0)).h(px(8.0)).rounded_full().bg(status_color)),
             )
-            // Label
-            .child(
-                div()
-                    .w(px(160.0))
-                    .text_size(px(13.0))
-                    .text_color(ShellDeckColors::text_primary())
-                    .font_weight(FontWeight::MEDIUM)
-                    .child(
-                        forward
-                            .label
-                            .clone()
-                            .unwrap_or_else(|| "Unnamed".to_string()),
-                    ),
-            )
+            // Label, with the supervisor's view of its health below
+            .child({
+                let mut label = div().w(px(160.0)).flex().flex_col().child(
+                    div()
+                        .text_size(px(13.0))
+                        .text_color(ShellDeckColors::text_primary())
+                        .font_weight(FontWeight::MEDIUM)
+                        .child(
+                            forward
+                                .label
+                                .clone()
+                                .unwrap_or_else(|| "Unnamed".to_string()),
+                        ),
+                );
+                if let Some((summary, failing)) = self.health_summary(forward) {
+                    label = label.child(
+                        div()
+                            .text_size(px(10.0))
+                            .text_color(if failing {
+                                ShellDeckColors::warning()
+                            } else {
+                                ShellDeckColors::text_muted()
+                            })
+                            .child(summary),
+                    );
+                }
+                label
+            })
             // Direction
             .child(
                 div()
@@ -100,15 +207,42 @@ impl PortForwardView {
                     .text_color(ShellDeckColors::text_muted())
                     .child(direction_label.to_string()),
             )
-            // Local endpoint
-            .child(
-                div()
-                    .w(px(140.0))
-                    .text_size(px(12.0))
-                    .text_color(ShellDeckColors::primary())
-                    .font_family("JetBrains Mono")
-                    .child(format!("{}:{}", forward.local_host, forward.local_port)),
-            )
+            // Local endpoint, flagged when other machines can reach it
+            .child({
+                let mut endpoint = div().w(px(140.0)).flex().flex_col().child(
+                    div()
+                        .text_size(px(12.0))
+                        .text_color(ShellDeckColors::primary())
+                        .font_family("JetBrains Mono")
+                        .child(self.local_endpoint(forward)),
+                );
+                if forward.exposes_to_network() {
+                    let hint = if forward.allowed_clients.is_empty() {
+                        t!("forwards.lan.open").to_string()
+                    } else {
+                        t!(
+                            "forwards.lan.allowlist",
+                            clients = forward.allowed_clients.join(", ")
+                        )
+                        .to_string()
+                    };
+                    endpoint = endpoint.child(
+                        div()
+                            .text_size(px(10.0))
+                            .text_color(ShellDeckColors::warning())
+                            .child(hint),
+                    );
+                }
+                if let Some(url) = self.pac_url(forward) {
+                    endpoint = endpoint.child(
+                        div()
+                            .text_size(px(10.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(t!("forwards.pac_url", url = url).to_string()),
+                    );
+                }
+                endpoint
+            })
             // Arrow
             .child(
                 div()
@@ -138,7 +272,11 @@ impl PortForwardView {
             // Actions
             .child({
                 let fwd_id = forward.id;
-                let is_active = forward.status == ForwardStatus::Active;
+                // A reconnecting forward is still wanted up; Stop gives up on it.
+                let is_active = matches!(
+                    forward.status,
+                    ForwardStatus::Active | ForwardStatus::Reconnecting
+                );
                 let (label, event) = if is_active {
                     (
                         t!("forwards.stop").to_string(),
@@ -154,6 +292,34 @@ impl PortForwardView {
                     .flex()
                     .items_center()
                     .gap(px(4.0))
+                    .child({
+                        let open = self.inspecting == Some(fwd_id);
+                        div()
+                            .id(ElementId::from(SharedString::from(format!(
+                                "fwd-traffic-{}",
+                                fwd_id
+                            ))))
+                            .cursor_pointer()
+                            .px(px(6.0))
+                            .py(px(3.0))
+                            .rounded(px(4.0))
+                            .text_size(px(11.0))
+                            .text_color(if open {
+                                ShellDeckColors::primary()
+                            } else {
+                                ShellDeckColors::text_muted()
+                            })
+                            .hover(|el| {
+                                el.bg(ShellDeckColors::primary().opacity(0.15))
+                                    .text_color(ShellDeckColors::primary())
+                            })
+                            .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                                this.inspecting = if open { None } else { Some(fwd_id) };
+                                this.expanded_exchange = None;
+                                cx.notify();
+                            }))
+                            .child(t!("forwards.show_traffic").to_string())
+                    })
                     .child(
                         div()
                             .id(ElementId::from(SharedString::from(format!(
@@ -337,7 +503,12 @@ impl PortForwardView {
                             .text_size(px(12.0))
                             .text_color(ShellDeckColors::primary())
                             .font_family("JetBrains Mono")
-                            .child(format!(":{}", forward.local_port)),
+                            .child(format!(
+                                ":{}",
+                                self.bound_addrs
+                                    .get(&forward.id)
+                                    .map_or(forward.local_port, |addr| addr.port())
+                            )),
                     )
                     .child(
                         div()
@@ -565,6 +736,12 @@ impl Render for PortForwardView {
                     .child(Self::render_header_row())
                     .children(self.forwards.iter().map(|f| self.render_forward_row(f, cx))),
             )
+            // Traffic of the selected forward
+            .children(
+                self.inspecting
+                    .and_then(|id| self.forwards.iter().find(|f| f.id == id))
+                    .map(|forward| self.render_traffic_panel(forward, cx)),
+            )
     }
 }
 
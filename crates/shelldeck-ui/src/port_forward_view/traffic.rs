@@ -0,0 +1,428 @@
+use super::*;
+use chrono::{DateTime, Utc};
+use gpui::prelude::FluentBuilder;
+use shelldeck_core::models::forward_traffic::{ConnectionRecord, HttpExchange};
+
+/// Rows drawn of each traffic table, newest first.
+const TRAFFIC_ROWS: usize = 50;
+/// Body bytes shown of an expanded exchange.
+const BODY_PREVIEW_BYTES: usize = 4096;
+
+impl PortForwardView {
+    /// Connections of a forward and, when inspection is on, the HTTP
+    /// exchanges read off them.
+    pub(super) fn render_traffic_panel(
+        &self,
+        forward: &PortForward,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let forward_id = forward.id;
+        let title = t!(
+            "forwards.traffic.title",
+            label = forward
+                .label
+                .clone()
+                .unwrap_or_else(|| forward.description())
+        )
+        .to_string();
+        let mut panel = div()
+            .flex()
+            .flex_col()
+            .gap(px(12.0))
+            .mx(px(24.0))
+            .my(px(16.0))
+            .p(px(16.0))
+            .bg(ShellDeckColors::bg_surface())
+            .rounded(px(8.0))
+            .border_1()
+            .border_color(ShellDeckColors::border())
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_size(px(13.0))
+                            .font_weight(FontWeight::BOLD)
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(title),
+                    )
+                    .child(
+                        div()
+                            .id("fwd-traffic-clear")
+                            .cursor_pointer()
+                            .px(px(6.0))
+                            .py(px(3.0))
+                            .rounded(px(4.0))
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+                            .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                                if let Some(traffic) = this.traffic.get(&forward_id) {
+                                    traffic.lock().clear();
+                                }
+                                this.expanded_exchange = None;
+                                cx.notify();
+                            }))
+                            .child(t!("forwards.traffic.clear").to_string()),
+                    ),
+            );
+
+        let Some(traffic) = self.traffic.get(&forward_id) else {
+            return panel.child(Self::render_traffic_note(
+                t!("forwards.traffic.not_started").to_string(),
+            ));
+        };
+        let log = traffic.lock();
+        let now = Utc::now();
+
+        panel = panel.child(Self::render_traffic_heading(
+            t!(
+                "forwards.traffic.connections",
+                open = log.open_connections(),
+                total = log.connections().len()
+            )
+            .to_string(),
+        ));
+        if log.connections().is_empty() {
+            panel = panel.child(Self::render_traffic_note(
+                t!("forwards.traffic.no_connections").to_string(),
+            ));
+        }
+        for record in log.connections().iter().rev().take(TRAFFIC_ROWS) {
+            panel = panel.child(Self::render_connection_row(record, now));
+        }
+
+        if forward.direction == ForwardDirection::LocalToRemote {
+            panel = panel.child(Self::render_traffic_heading(
+                t!("forwards.traffic.http", count = log.exchanges().len()).to_string(),
+            ));
+            if !forward.inspect_http {
+                panel = panel.child(Self::render_traffic_note(
+                    t!("forwards.traffic.inspection_off").to_string(),
+                ));
+            } else if log.exchanges().is_empty() {
+                panel = panel.child(Self::render_traffic_note(
+                    t!("forwards.traffic.no_requests").to_string(),
+                ));
+            }
+            for exchange in log.exchanges().iter().rev().take(TRAFFIC_ROWS) {
+                panel = panel.child(self.render_exchange_row(forward_id, exchange, cx));
+                if self.expanded_exchange == Some(exchange.id) {
+                    panel = panel.child(Self::render_exchange_detail(forward_id, exchange, cx));
+                }
+            }
+        }
+        panel
+    }
+
+    fn render_traffic_heading(text: String) -> impl IntoElement {
+        div()
+            .text_size(px(11.0))
+            .font_weight(FontWeight::BOLD)
+            .text_color(ShellDeckColors::text_muted())
+            .child(text)
+    }
+
+    fn render_traffic_note(text: String) -> impl IntoElement {
+        div()
+            .text_size(px(11.0))
+            .text_color(ShellDeckColors::text_muted())
+            .child(text)
+    }
+
+    fn render_connection_row(record: &ConnectionRecord, now: DateTime<Utc>) -> impl IntoElement {
+        let lasted = record.closed_at.unwrap_or(now) - record.opened_at;
+        let state = if record.is_open() {
+            t!(
+                "forwards.traffic.open_for",
+                duration = format_duration(lasted)
+            )
+            .to_string()
+        } else {
+            format_duration(lasted)
+        };
+        let target = if record.target.is_empty() {
+            "…".to_string()
+        } else {
+            record.target.clone()
+        };
+        div()
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .text_size(px(11.0))
+            .font_family("JetBrains Mono")
+            .child(
+                div()
+                    .w(px(70.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(
+                        record
+                            .opened_at
+                            .with_timezone(&chrono::Local)
+                            .format("%H:%M:%S")
+                            .to_string(),
+                    ),
+            )
+            .child(
+                div()
+                    .w(px(260.0))
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(format!("{} → {}", record.client, target)),
+            )
+            .child(
+                div()
+                    .w(px(90.0))
+                    .text_color(if record.is_open() {
+                        ShellDeckColors::success()
+                    } else {
+                        ShellDeckColors::text_muted()
+                    })
+                    .child(state),
+            )
+            .child(
+                div()
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(format!(
+                        "↑ {}  ↓ {}",
+                        format_bytes(record.bytes_sent),
+                        format_bytes(record.bytes_received)
+                    )),
+            )
+    }
+
+    fn render_exchange_row(
+        &self,
+        forward_id: Uuid,
+        exchange: &HttpExchange,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let exchange_id = exchange.id;
+        let expanded = self.expanded_exchange == Some(exchange_id);
+        let (status, color, timing) = match &exchange.response {
+            Some(response) => (
+                response.status.to_string(),
+                status_color(response.status),
+                format!("{} ms", response.elapsed_ms),
+            ),
+            None => (
+                "…".to_string(),
+                ShellDeckColors::text_muted(),
+                String::new(),
+            ),
+        };
+        div()
+            .id(ElementId::from(SharedString::from(format!(
+                "fwd-{}-http-{}",
+                forward_id, exchange_id
+            ))))
+            .flex()
+            .items_center()
+            .gap(px(12.0))
+            .px(px(4.0))
+            .py(px(2.0))
+            .rounded(px(4.0))
+            .text_size(px(11.0))
+            .font_family("JetBrains Mono")
+            .cursor_pointer()
+            .when(expanded, |el| {
+                el.bg(ShellDeckColors::primary().opacity(0.08))
+            })
+            .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+            .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                this.expanded_exchange = if expanded { None } else { Some(exchange_id) };
+                cx.notify();
+            }))
+            .child(
+                div()
+                    .w(px(70.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(
+                        exchange
+                            .started_at
+                            .with_timezone(&chrono::Local)
+                            .format("%H:%M:%S")
+                            .to_string(),
+                    ),
+            )
+            .child(
+                div()
+                    .w(px(60.0))
+                    .font_weight(FontWeight::BOLD)
+                    .text_color(ShellDeckColors::primary())
+                    .child(exchange.method.clone()),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(0.0))
+                    .overflow_hidden()
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(exchange.path.clone()),
+            )
+            .child(div().w(px(40.0)).text_color(color).child(status))
+            .child(
+                div()
+                    .w(px(70.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(timing),
+            )
+    }
+
+    fn render_exchange_detail(
+        forward_id: Uuid,
+        exchange: &HttpExchange,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let exchange_id = exchange.id;
+        let replayable = exchange.replay_bytes().is_some();
+        let mut detail = div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .ml(px(82.0))
+            .p(px(10.0))
+            .rounded(px(6.0))
+            .bg(ShellDeckColors::bg_primary())
+            .border_1()
+            .border_color(ShellDeckColors::border())
+            .child(render_message(
+                t!("forwards.traffic.request").to_string(),
+                format!("{} {} {}", exchange.method, exchange.path, exchange.version),
+                &exchange.request_headers,
+                &exchange.request_body,
+                exchange.request_body_truncated,
+            ));
+        if let Some(response) = &exchange.response {
+            detail = detail.child(render_message(
+                t!("forwards.traffic.response").to_string(),
+                format!("{} {}", response.status, response.reason),
+                &response.headers,
+                &response.body,
+                response.body_truncated,
+            ));
+        }
+        detail.child(
+            div()
+                .id(ElementId::from(SharedString::from(format!(
+                    "fwd-{}-replay-{}",
+                    forward_id, exchange_id
+                ))))
+                .w_auto()
+                .px(px(8.0))
+                .py(px(4.0))
+                .rounded(px(4.0))
+                .text_size(px(11.0))
+                .when(replayable, |el| {
+                    el.cursor_pointer()
+                        .text_color(ShellDeckColors::primary())
+                        .hover(|el| el.bg(ShellDeckColors::primary().opacity(0.15)))
+                        .on_click(cx.listener(move |_this, _: &ClickEvent, _, cx| {
+                            cx.emit(PortForwardEvent::ReplayRequest {
+                                forward_id,
+                                exchange_id,
+                            });
+                        }))
+                })
+                .when(!replayable, |el| {
+                    el.text_color(ShellDeckColors::text_muted())
+                })
+                .child(if replayable {
+                    t!("forwards.traffic.replay").to_string()
+                } else {
+                    t!("forwards.traffic.replay_truncated").to_string()
+                }),
+        )
+    }
+}
+
+/// Start line, headers and the start of the body of one message.
+fn render_message(
+    title: String,
+    start_line: String,
+    headers: &[(String, String)],
+    body: &[u8],
+    truncated: bool,
+) -> impl IntoElement {
+    let mut section = div()
+        .flex()
+        .flex_col()
+        .gap(px(2.0))
+        .text_size(px(11.0))
+        .font_family("JetBrains Mono")
+        .child(
+            div()
+                .font_weight(FontWeight::BOLD)
+                .text_color(ShellDeckColors::text_muted())
+                .child(title),
+        )
+        .child(
+            div()
+                .text_color(ShellDeckColors::text_primary())
+                .child(start_line),
+        );
+    for (name, value) in headers {
+        section = section.child(
+            div()
+                .flex()
+                .gap(px(4.0))
+                .child(
+                    div()
+                        .text_color(ShellDeckColors::primary())
+                        .child(format!("{}:", name)),
+                )
+                .child(
+                    div()
+                        .text_color(ShellDeckColors::text_primary())
+                        .child(value.clone()),
+                ),
+        );
+    }
+    if !body.is_empty() {
+        section = section.child(
+            div()
+                .mt(px(4.0))
+                .text_color(ShellDeckColors::text_primary())
+                .child(body_preview(body, truncated)),
+        );
+    }
+    section
+}
+
+/// The body as text, cut to [`BODY_PREVIEW_BYTES`].
+fn body_preview(body: &[u8], truncated: bool) -> String {
+    let shown = &body[..body.len().min(BODY_PREVIEW_BYTES)];
+    let mut text = String::from_utf8_lossy(shown).into_owned();
+    if truncated || shown.len() < body.len() {
+        text.push_str(&format!(
+            "\n… {}",
+            t!(
+                "forwards.traffic.body_cut",
+                size = format_bytes(body.len() as u64)
+            )
+        ));
+    }
+    text
+}
+
+fn status_color(status: u16) -> Hsla {
+    match status {
+        200..=299 => ShellDeckColors::success(),
+        300..=399 => ShellDeckColors::primary(),
+        400..=499 => ShellDeckColors::warning(),
+        _ => ShellDeckColors::error(),
+    }
+}
+
+fn format_duration(duration: chrono::Duration) -> String {
+    let ms = duration.num_milliseconds().max(0);
+    if ms < 1000 {
+        format!("{} ms", ms)
+    } else if ms < 60_000 {
+        format!("{:.1} s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {}s", ms / 60_000, (ms % 60_000) / 1000)
+    }
+}
@@ -130,6 +130,7 @@ impl RecentView {
     fn action_label(action: ActivityAction) -> String {
         match action {
             ActivityAction::ConnectConnection => t!("recent.action.connect").to_string(),
+            ActivityAction::DiagnoseConnection => t!("recent.action.diagnose").to_string(),
             ActivityAction::OpenTerminal => t!("recent.action.resume").to_string(),
             ActivityAction::None => String::new(),
             _ => t!("recent.action.open").to_string(),
@@ -1,3 +1,6 @@
+mod history;
+mod multi_run;
+
 use std::collections::HashMap;
 
 use crate::scale::px;
@@ -5,6 +8,7 @@ use adabraka_ui::overlays::popover_menu::{PopoverMenu, PopoverMenuItem};
 use adabraka_ui::prelude::*;
 use gpui::*;
 use shelldeck_core::models::execution::ExecutionRecord;
+use shelldeck_core::models::multi_run::{HostSelector, MultiRun, MultiTarget};
 use shelldeck_core::models::script::{Script, ScriptCategory, ScriptLanguage, ScriptTarget};
 use uuid::Uuid;
 
@@ -13,6 +17,7 @@ use crate::icons::{lucide_icon, lucide_path, script_category_chip, script_langua
 use crate::syntax::highlight::render_code_block_with_language;
 use crate::t;
 use crate::theme::ShellDeckColors;
+use history::HistoryDetail;
 
 #[derive(Debug, Clone)]
 pub enum ScriptEvent {
@@ -31,6 +36,8 @@ pub enum ScriptEvent {
     ExplainWithAi(Uuid),
     ReviewWithAi(Uuid),
     FixWithAi(Uuid),
+    /// Run a script again against the run's target with its saved variables.
+    RerunExecution(ExecutionRecord),
 }
 
 impl EventEmitter<ScriptEvent> for ScriptEditorView {}
@@ -41,6 +48,11 @@ pub struct ScriptEditorView {
     pub execution_output: Vec<String>,
     pub running_script_id: Option<Uuid>,
     pub history: Vec<ExecutionRecord>,
+    /// Live results of the current (or last) multi-host run.
+    pub multi_run: Option<MultiRun>,
+    multi_run_grouped: bool,
+    /// Host whose full output is unfolded in the results matrix.
+    multi_run_expanded: Option<Uuid>,
     // Run target picker — adabraka PopoverMenu anchored to the split Run button
     run_target_menu_open: bool,
     run_target_btn_bounds: Option<Bounds<Pixels>>,
@@ -66,6 +78,16 @@ pub struct ScriptEditorView {
     kebab_menu: Option<(Uuid, Point<Pixels>)>,
     /// Compact AI actions menu in the selected script toolbar.
     ai_actions_menu: Option<(Uuid, Point<Pixels>)>,
+    // Saved run history (replaces the output panel content when open)
+    history_open: bool,
+    history_query: String,
+    saved_runs: Vec<ExecutionRecord>,
+    /// Bumped on every history reload so an older search finishing late
+    /// cannot replace the results of a newer one.
+    history_generation: u64,
+    /// Runs picked for an output diff (at most two).
+    history_compare: Vec<Uuid>,
+    history_detail: Option<HistoryDetail>,
 }
 
 impl ScriptEditorView {
@@ -328,6 +350,9 @@ impl ScriptEditorView {
             execution_output: Vec::new(),
             running_script_id: None,
             history: Vec::new(),
+            multi_run: None,
+            multi_run_grouped: false,
+            multi_run_expanded: None,
             run_target_menu_open: false,
             run_target_btn_bounds: None,
             run_target_connections: Vec::new(),
@@ -345,6 +370,12 @@ impl ScriptEditorView {
             last_var_values: HashMap::new(),
             kebab_menu: None,
             ai_actions_menu: None,
+            history_open: false,
+            history_query: String::new(),
+            saved_runs: Vec::new(),
+            history_generation: 0,
+            history_compare: Vec::new(),
+            history_detail: None,
         }
     }
 
@@ -404,13 +435,21 @@ impl ScriptEditorView {
             "script": self.ai_context_data(),
             "failed_execution": failed.map(|record| serde_json::json!({
                 "exit_code": record.exit_code,
-                "output": record.output_log,
+                "output": record.redacted_output(),
                 "connection_id": record.connection_id,
                 "started_at": record.started_at,
             })),
         })
     }
 
+    /// Select a script, refreshing the saved runs shown for it.
+    pub fn select_script(&mut self, script_id: Option<Uuid>, cx: &mut Context<Self>) {
+        if self.selected_script != script_id {
+            self.selected_script = script_id;
+            self.reload_history(cx);
+        }
+    }
+
     pub fn set_ai_generation_enabled(&mut self, enabled: bool, cx: &mut Context<Self>) {
         self.ai_generation_enabled = enabled;
         cx.notify();
@@ -418,7 +457,7 @@ impl ScriptEditorView {
 
     pub fn apply_generated_body(&mut self, script_id: Uuid, body: String, cx: &mut Context<Self>) {
         if self.scripts.iter().any(|script| script.id == script_id) {
-            self.selected_script = Some(script_id);
+            self.select_script(Some(script_id), cx);
             self.inline_script_id = Some(script_id);
             self.inline_buffer = EditorBuffer::from_text(body);
             self.inline_editing = true;
@@ -711,6 +750,7 @@ impl ScriptEditorView {
                 ScriptTarget::Local => t!("scripts.target.local").to_string(),
                 ScriptTarget::Remote(_) => t!("scripts.target.remote").to_string(),
                 ScriptTarget::AskOnRun => t!("scripts.target.ask").to_string(),
+                ScriptTarget::Multi(_) => t!("scripts.target.multi").to_string(),
             };
             let is_favorite = script.is_favorite;
             let is_pinned = script.pinned_to_toolbar;
@@ -734,7 +774,7 @@ impl ScriptEditorView {
                         this.start_inline_edit(script_id);
                         this.focus_handle.focus(window);
                     } else {
-                        this.selected_script = Some(script_id);
+                        this.select_script(Some(script_id), cx);
                     }
                     cx.notify();
                 }));
@@ -1371,6 +1411,32 @@ impl ScriptEditorView {
             }
         }
 
+        if self.run_target_connections.len() > 1 {
+            let script_all = {
+                let mut s = script.clone();
+                s.target = ScriptTarget::Multi(MultiTarget::new(HostSelector::Connections(
+                    self.run_target_connections
+                        .iter()
+                        .map(|(id, _)| *id)
+                        .collect(),
+                )));
+                s
+            };
+            items.push(
+                PopoverMenuItem::new("run-target-all", t!("scripts.target.all_hosts").to_string())
+                    .icon("server")
+                    .on_click({
+                        let entity = entity.clone();
+                        move |_, cx| {
+                            entity.update(cx, |this, cx| {
+                                this.close_run_target_menu(cx);
+                                cx.emit(ScriptEvent::RunScript(script_all.clone()));
+                            });
+                        }
+                    }),
+            );
+        }
+
         items
     }
 
@@ -1518,6 +1584,28 @@ impl ScriptEditorView {
                 }))
             });
 
+        let content = if self.history_open {
+            self.render_history_panel(cx).into_any_element()
+        } else if let Some(results) = self.render_multi_run_panel(cx) {
+            results.into_any_element()
+        } else {
+            div()
+                .flex_grow()
+                .p(px(8.0))
+                .bg(ShellDeckColors::terminal_bg())
+                .id("script-output")
+                .overflow_y_scroll()
+                .font_family("JetBrains Mono")
+                .text_size(px(12.0))
+                .text_color(ShellDeckColors::text_primary())
+                .children(
+                    self.execution_output
+                        .iter()
+                        .map(|line| div().child(line.clone())),
+                )
+                .into_any_element()
+        };
+
         let panel = div()
             .relative()
             .flex()
@@ -1566,6 +1654,22 @@ impl ScriptEditorView {
                             .items_center()
                             .gap(px(10.0))
                             .children(fix_button)
+                            .child(
+                                div()
+                                    .id("history-toggle-btn")
+                                    .cursor_pointer()
+                                    .text_size(px(11.0))
+                                    .text_color(if self.history_open {
+                                        ShellDeckColors::primary()
+                                    } else {
+                                        ShellDeckColors::text_muted()
+                                    })
+                                    .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                                    .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                                        this.toggle_history(cx);
+                                    }))
+                                    .child(t!("scripts.history.title").to_string()),
+                            )
                             .child(
                                 div()
                                     .id("copy-output-btn")
@@ -1588,6 +1692,7 @@ impl ScriptEditorView {
                                     .hover(|el| el.text_color(ShellDeckColors::text_primary()))
                                     .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
                                         this.execution_output.clear();
+                                        this.multi_run = None;
                                         cx.emit(ScriptEvent::ClearOutput);
                                         cx.notify();
                                     }))
@@ -1595,23 +1700,8 @@ impl ScriptEditorView {
                             ),
                     ),
             )
-            // Output content
-            .child(
-                div()
-                    .flex_grow()
-                    .p(px(8.0))
-                    .bg(ShellDeckColors::terminal_bg())
-                    .id("script-output")
-                    .overflow_y_scroll()
-                    .font_family("JetBrains Mono")
-                    .text_size(px(12.0))
-                    .text_color(ShellDeckColors::text_primary())
-                    .children(
-                        self.execution_output
-                            .iter()
-                            .map(|line| div().child(line.clone())),
-                    ),
-            );
+            // Output content: saved runs, multi-host results or plain output
+            .child(content);
 
         panel
     }
@@ -1809,6 +1899,8 @@ impl Render for ScriptEditorView {
             .on_key_down(cx.listener(move |this, event: &KeyDownEvent, _window, cx| {
                 if is_editing {
                     this.handle_inline_key_down(event, cx);
+                } else if this.history_open {
+                    this.handle_history_key_down(event, cx);
                 } else {
                     this.handle_search_key_down(event, cx);
                 }
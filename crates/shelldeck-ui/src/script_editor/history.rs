@@ -0,0 +1,428 @@
+use super::*;
+use shelldeck_core::ai::{ai_line_diff, AiDiffLine};
+use shelldeck_core::config::execution_history::ExecutionHistoryStore;
+
+/// What the right half of the history panel shows.
+#[derive(Debug, Clone)]
+pub(super) enum HistoryDetail {
+    Output(Uuid, String),
+    Diff(Vec<AiDiffLine>),
+}
+
+impl ScriptEditorView {
+    /// Finalize the current run and persist it to the on-disk history.
+    pub fn finish_execution(&mut self, exit_code: i32, cx: &mut Context<Self>) {
+        let Some(record) = self.history.last_mut() else {
+            return;
+        };
+        // A cancelled run is finished by StopScript; the runner reporting
+        // afterwards must not overwrite it.
+        if !record.is_running() {
+            return;
+        }
+        record.finish(exit_code);
+        if let Err(e) = ExecutionHistoryStore::save(record) {
+            tracing::warn!("Failed to save execution history: {}", e);
+        }
+        self.reload_history(cx);
+    }
+
+    /// Persist a finished scheduled or event-triggered run. It never went
+    /// through the output pane, so only the saved history shows it.
+    pub fn record_unattended_run(&mut self, record: ExecutionRecord, cx: &mut Context<Self>) {
+        if let Err(e) = ExecutionHistoryStore::save(&record) {
+            tracing::warn!("Failed to save execution history: {}", e);
+        }
+        self.reload_history(cx);
+    }
+
+    pub(super) fn toggle_history(&mut self, cx: &mut Context<Self>) {
+        self.history_open = !self.history_open;
+        self.history_query.clear();
+        self.reload_history(cx);
+        cx.notify();
+    }
+
+    /// Search the saved runs of the selected script for the current query
+    /// in the background. Called whenever the script, the query or the
+    /// saved history changes, so rendering never touches the disk.
+    pub(super) fn reload_history(&mut self, cx: &mut Context<Self>) {
+        self.history_compare.clear();
+        self.history_detail = None;
+        self.history_generation += 1;
+        let Some(script_id) = self.selected_script.filter(|_| self.history_open) else {
+            self.saved_runs.clear();
+            return;
+        };
+        let generation = self.history_generation;
+        let query = self.history_query.clone();
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let runs = cx
+                .background_executor()
+                .spawn(async move { ExecutionHistoryStore::search(script_id, &query) })
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to read execution history: {}", e);
+                    Vec::new()
+                });
+            let _ = this.update(cx, |this, cx| {
+                // A newer reload was started while this one ran.
+                if this.history_generation == generation {
+                    this.saved_runs = runs;
+                    cx.notify();
+                }
+            });
+        })
+        .detach();
+    }
+
+    pub(super) fn handle_history_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        let key = event.keystroke.key.as_str();
+        let mods = &event.keystroke.modifiers;
+
+        match key {
+            "escape" if self.history_query.is_empty() => {
+                self.history_open = false;
+            }
+            "escape" => self.history_query.clear(),
+            "backspace" => {
+                self.history_query.pop();
+            }
+            _ => {
+                if let Some(ref kc) = event.keystroke.key_char {
+                    if mods.control || mods.alt {
+                        return;
+                    }
+                    self.history_query.push_str(kc);
+                } else {
+                    return;
+                }
+            }
+        }
+        self.reload_history(cx);
+        cx.notify();
+    }
+
+    fn show_run_output(&mut self, record_id: Uuid, cx: &mut Context<Self>) {
+        let Some(script_id) = self.selected_script else {
+            return;
+        };
+        let output = ExecutionHistoryStore::load_output(script_id, record_id).unwrap_or_default();
+        self.history_compare.clear();
+        self.history_detail = Some(HistoryDetail::Output(record_id, output));
+        cx.notify();
+    }
+
+    /// Mark a run for comparison. Once two runs are marked, their outputs are
+    /// diffed oldest to newest.
+    fn toggle_compare(&mut self, record_id: Uuid, cx: &mut Context<Self>) {
+        if let Some(pos) = self.history_compare.iter().position(|id| *id == record_id) {
+            self.history_compare.remove(pos);
+        } else {
+            if self.history_compare.len() == 2 {
+                self.history_compare.remove(0);
+            }
+            self.history_compare.push(record_id);
+        }
+
+        self.history_detail = None;
+        if let (Some(script_id), [a, b]) = (self.selected_script, self.history_compare.as_slice()) {
+            let started = |id: &Uuid| {
+                self.saved_runs
+                    .iter()
+                    .find(|r| r.id == *id)
+                    .map(|r| r.started_at)
+            };
+            let (older, newer) = if started(a) <= started(b) {
+                (*a, *b)
+            } else {
+                (*b, *a)
+            };
+            let load = |id| ExecutionHistoryStore::load_output(script_id, id).unwrap_or_default();
+            self.history_detail = Some(HistoryDetail::Diff(ai_line_diff(
+                &load(older),
+                &load(newer),
+            )));
+        }
+        cx.notify();
+    }
+
+    fn run_target_label(&self, record: &ExecutionRecord) -> String {
+        match record.connection_id {
+            Some(id) => self
+                .run_target_connections
+                .iter()
+                .find(|(cid, _)| *cid == id)
+                .map(|(_, name)| name.clone())
+                .unwrap_or_else(|| t!("scripts.history.unknown_host").to_string()),
+            None => t!("scripts.target.local").to_string(),
+        }
+    }
+
+    pub(super) fn render_history_panel(&self, cx: &mut Context<Self>) -> Div {
+        let search = div()
+            .px(px(8.0))
+            .py(px(6.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .child(
+                div()
+                    .w_full()
+                    .px(px(8.0))
+                    .py(px(4.0))
+                    .rounded(px(4.0))
+                    .bg(ShellDeckColors::bg_primary())
+                    .border_1()
+                    .border_color(ShellDeckColors::border())
+                    .text_size(px(12.0))
+                    .child(if self.history_query.is_empty() {
+                        div()
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(t!("scripts.history.search_placeholder").to_string())
+                    } else {
+                        div()
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(self.history_query.clone())
+                    }),
+            );
+
+        let mut list = div()
+            .id("script-history-list")
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .overflow_y_scroll();
+        if self.saved_runs.is_empty() {
+            list = list.child(
+                div()
+                    .p(px(12.0))
+                    .text_size(px(12.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(if self.history_query.is_empty() {
+                        t!("scripts.history.empty").to_string()
+                    } else {
+                        t!("scripts.history.no_match").to_string()
+                    }),
+            );
+        }
+        for record in &self.saved_runs {
+            list = list.child(self.render_history_row(record, cx));
+        }
+
+        div()
+            .flex()
+            .flex_grow()
+            .min_h_0()
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .w(px(300.0))
+                    .flex_shrink_0()
+                    .border_r_1()
+                    .border_color(ShellDeckColors::border())
+                    .bg(ShellDeckColors::bg_sidebar())
+                    .child(search)
+                    .child(list),
+            )
+            .child(self.render_history_detail())
+    }
+
+    fn render_history_row(
+        &self,
+        record: &ExecutionRecord,
+        cx: &mut Context<Self>,
+    ) -> Stateful<Div> {
+        let record_id = record.id;
+        let is_shown = matches!(
+            &self.history_detail,
+            Some(HistoryDetail::Output(id, _)) if *id == record_id
+        );
+        let is_compared = self.history_compare.contains(&record_id);
+
+        let (status, status_color) = match record.exit_code {
+            Some(0) => ("0".to_string(), ShellDeckColors::success()),
+            Some(code) => (code.to_string(), ShellDeckColors::error()),
+            None => ("?".to_string(), ShellDeckColors::warning()),
+        };
+        let duration = record
+            .duration_secs()
+            .map(|secs| format!("{:.1}s", secs))
+            .unwrap_or_default();
+        let variables = record
+            .variables
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("  ");
+
+        let rerun = record.clone();
+        div()
+            .id(ElementId::Name(format!("history-run-{}", record_id).into()))
+            .flex()
+            .flex_col()
+            .gap(px(2.0))
+            .px(px(10.0))
+            .py(px(6.0))
+            .cursor_pointer()
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .when(is_shown || is_compared, |el| {
+                el.bg(ShellDeckColors::selected_bg())
+            })
+            .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+            .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                this.show_run_output(record_id, cx);
+            }))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .text_size(px(12.0))
+                    .child(
+                        div()
+                            .px(px(5.0))
+                            .rounded(px(3.0))
+                            .font_family("JetBrains Mono")
+                            .text_size(px(10.0))
+                            .text_color(status_color)
+                            .bg(status_color.opacity(0.12))
+                            .child(status),
+                    )
+                    .child(
+                        div()
+                            .flex_grow()
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(
+                                record
+                                    .started_at
+                                    .with_timezone(&chrono::Local)
+                                    .format("%Y-%m-%d %H:%M:%S")
+                                    .to_string(),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(10.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(duration),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(div().flex_grow().child(self.run_target_label(record)))
+                    .child(
+                        div()
+                            .id(ElementId::Name(
+                                format!("history-compare-{}", record_id).into(),
+                            ))
+                            .cursor_pointer()
+                            .when(is_compared, |el| el.text_color(ShellDeckColors::primary()))
+                            .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                            .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                                cx.stop_propagation();
+                                this.toggle_compare(record_id, cx);
+                            }))
+                            .child(t!("scripts.history.compare").to_string()),
+                    )
+                    .child(
+                        div()
+                            .id(ElementId::Name(
+                                format!("history-rerun-{}", record_id).into(),
+                            ))
+                            .cursor_pointer()
+                            .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                            .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                                cx.stop_propagation();
+                                if !this.is_running() {
+                                    cx.emit(ScriptEvent::RerunExecution(rerun.clone()));
+                                }
+                            }))
+                            .child(t!("scripts.history.rerun").to_string()),
+                    ),
+            )
+            .when(!variables.is_empty(), |el| {
+                el.child(
+                    div()
+                        .font_family("JetBrains Mono")
+                        .text_size(px(10.0))
+                        .text_color(ShellDeckColors::text_muted())
+                        .truncate()
+                        .child(variables),
+                )
+            })
+    }
+
+    fn render_history_detail(&self) -> Stateful<Div> {
+        let mut body = div()
+            .id("script-history-detail")
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .min_w_0()
+            .p(px(8.0))
+            .bg(ShellDeckColors::terminal_bg())
+            .overflow_y_scroll()
+            .font_family("JetBrains Mono")
+            .text_size(px(12.0))
+            .text_color(ShellDeckColors::text_primary());
+
+        match &self.history_detail {
+            Some(HistoryDetail::Output(_, output)) => {
+                body = body.children(output.lines().map(|line| div().child(line.to_string())));
+            }
+            Some(HistoryDetail::Diff(lines)) => {
+                for line in lines {
+                    let (prefix, color, bg, text) = match line {
+                        AiDiffLine::Context(text) => (
+                            " ",
+                            ShellDeckColors::text_muted(),
+                            gpui::transparent_black(),
+                            text,
+                        ),
+                        AiDiffLine::Removed(text) => (
+                            "-",
+                            ShellDeckColors::error(),
+                            ShellDeckColors::error().opacity(0.08),
+                            text,
+                        ),
+                        AiDiffLine::Added(text) => (
+                            "+",
+                            ShellDeckColors::success(),
+                            ShellDeckColors::success().opacity(0.08),
+                            text,
+                        ),
+                    };
+                    body = body.child(
+                        div()
+                            .flex()
+                            .gap(px(8.0))
+                            .bg(bg)
+                            .text_color(color)
+                            .child(prefix)
+                            .child(text.clone()),
+                    );
+                }
+            }
+            None => {
+                let hint = if self.history_compare.len() == 1 {
+                    t!("scripts.history.compare_hint")
+                } else {
+                    t!("scripts.history.select_hint")
+                };
+                body = body.child(
+                    div()
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(hint.to_string()),
+                );
+            }
+        }
+        body
+    }
+}
@@ -0,0 +1,344 @@
+use super::*;
+use shelldeck_core::config::execution_history::ExecutionHistoryStore;
+use shelldeck_core::models::multi_run::{HostRun, HostRunStatus, MultiRunEvent, MultiRunSummary};
+
+fn status_label(status: HostRunStatus) -> String {
+    match status {
+        HostRunStatus::Pending => t!("scripts.multi.status.pending"),
+        HostRunStatus::Running => t!("scripts.multi.status.running"),
+        HostRunStatus::Succeeded => t!("scripts.multi.status.succeeded"),
+        HostRunStatus::Failed => t!("scripts.multi.status.failed"),
+        HostRunStatus::TimedOut => t!("scripts.multi.status.timed_out"),
+        HostRunStatus::Skipped => t!("scripts.multi.status.skipped"),
+        HostRunStatus::Cancelled => t!("scripts.multi.status.cancelled"),
+    }
+    .to_string()
+}
+
+fn status_color(status: HostRunStatus) -> Hsla {
+    match status {
+        HostRunStatus::Pending | HostRunStatus::Skipped | HostRunStatus::Cancelled => {
+            ShellDeckColors::text_muted()
+        }
+        HostRunStatus::Running => ShellDeckColors::primary(),
+        HostRunStatus::Succeeded => ShellDeckColors::success(),
+        HostRunStatus::Failed => ShellDeckColors::error(),
+        HostRunStatus::TimedOut => ShellDeckColors::warning(),
+    }
+}
+
+fn output_block(output: &str) -> Div {
+    div()
+        .px(px(10.0))
+        .py(px(6.0))
+        .bg(ShellDeckColors::terminal_bg())
+        .font_family("JetBrains Mono")
+        .text_size(px(12.0))
+        .text_color(ShellDeckColors::text_primary())
+        .children(output.lines().map(|line| div().child(line.to_string())))
+}
+
+impl ScriptEditorView {
+    pub fn start_multi_run(&mut self, run: MultiRun, header: String) {
+        self.running_script_id = Some(run.script_id);
+        self.execution_output.clear();
+        self.execution_output.push(header);
+        self.multi_run_expanded = None;
+        self.multi_run = Some(run);
+    }
+
+    /// Apply worker progress; every host that finished is saved to the
+    /// execution history like a single-host run.
+    pub fn apply_multi_run_events(&mut self, events: Vec<MultiRunEvent>, cx: &mut Context<Self>) {
+        let Some(run) = self.multi_run.as_mut() else {
+            return;
+        };
+        let mut saved = false;
+        for event in events {
+            if let Some(record) = run.apply(event) {
+                if let Err(e) = ExecutionHistoryStore::save(&record) {
+                    tracing::warn!("Failed to save execution history: {}", e);
+                }
+                self.history.push(record);
+                saved = true;
+            }
+        }
+        if saved {
+            self.reload_history(cx);
+        }
+    }
+
+    /// Close the run once its workers are gone. Hosts that never reported
+    /// back are marked cancelled.
+    pub fn finish_multi_run(&mut self, cx: &mut Context<Self>) -> Option<MultiRunSummary> {
+        self.running_script_id = None;
+        let ids: Vec<Uuid> = self
+            .multi_run
+            .as_ref()?
+            .hosts
+            .iter()
+            .filter(|h| !h.status.is_finished())
+            .filter_map(|h| h.record.connection_id)
+            .collect();
+        self.apply_multi_run_events(ids.into_iter().map(MultiRunEvent::Cancelled).collect(), cx);
+        self.multi_run.as_ref().map(|run| run.summary())
+    }
+
+    /// Results matrix of a multi-host run: one row per host, or one block
+    /// per distinct output when grouping is on.
+    pub(super) fn render_multi_run_panel(&self, cx: &mut Context<Self>) -> Option<Stateful<Div>> {
+        let run = self.multi_run.as_ref()?;
+        let summary = run.summary();
+
+        let mut counts = div()
+            .flex()
+            .items_center()
+            .gap(px(10.0))
+            .text_size(px(11.0))
+            .text_color(ShellDeckColors::text_muted());
+        for (count, status) in [
+            (summary.succeeded, HostRunStatus::Succeeded),
+            (summary.failed, HostRunStatus::Failed),
+            (summary.timed_out, HostRunStatus::TimedOut),
+            (summary.running, HostRunStatus::Running),
+            (summary.pending, HostRunStatus::Pending),
+            (summary.skipped, HostRunStatus::Skipped),
+        ] {
+            if count > 0 {
+                counts = counts.child(div().text_color(status_color(status)).child(format!(
+                    "{} {}",
+                    count,
+                    status_label(status)
+                )));
+            }
+        }
+
+        let toolbar = div()
+            .flex()
+            .items_center()
+            .justify_between()
+            .px(px(12.0))
+            .py(px(4.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .child(counts)
+            .child(
+                div()
+                    .id("multi-run-group-toggle")
+                    .cursor_pointer()
+                    .text_size(px(11.0))
+                    .text_color(if self.multi_run_grouped {
+                        ShellDeckColors::primary()
+                    } else {
+                        ShellDeckColors::text_muted()
+                    })
+                    .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                    .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                        this.multi_run_grouped = !this.multi_run_grouped;
+                        cx.notify();
+                    }))
+                    .child(t!("scripts.multi.group_outputs").to_string()),
+            );
+
+        let mut body = div()
+            .id("multi-run-results")
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .overflow_y_scroll();
+
+        if self.multi_run_grouped {
+            for (idx, group) in run.output_groups().into_iter().enumerate() {
+                let hosts = group
+                    .connection_ids
+                    .iter()
+                    .filter_map(|id| run.host(*id))
+                    .map(|h| h.host.clone())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let color = match group.exit_code {
+                    Some(0) => ShellDeckColors::success(),
+                    _ => ShellDeckColors::error(),
+                };
+                body = body.child(
+                    div()
+                        .id(ElementId::Name(format!("multi-run-group-{}", idx).into()))
+                        .flex()
+                        .flex_col()
+                        .border_b_1()
+                        .border_color(ShellDeckColors::border())
+                        .child(
+                            div()
+                                .flex()
+                                .items_center()
+                                .gap(px(8.0))
+                                .px(px(12.0))
+                                .py(px(6.0))
+                                .text_size(px(12.0))
+                                .child(
+                                    div().text_color(color).child(
+                                        t!(
+                                            "scripts.multi.group_header",
+                                            count = group.connection_ids.len(),
+                                            code = group
+                                                .exit_code
+                                                .map(|c| c.to_string())
+                                                .unwrap_or_else(|| "?".to_string())
+                                        )
+                                        .to_string(),
+                                    ),
+                                )
+                                .child(
+                                    div()
+                                        .flex_grow()
+                                        .truncate()
+                                        .text_color(ShellDeckColors::text_muted())
+                                        .child(hosts),
+                                ),
+                        )
+                        .child(output_block(&group.output)),
+                );
+            }
+            let unfinished = run
+                .hosts
+                .iter()
+                .filter(|h| matches!(h.status, HostRunStatus::Pending | HostRunStatus::Running))
+                .map(|h| h.host.clone())
+                .collect::<Vec<_>>();
+            if !unfinished.is_empty() {
+                body = body.child(
+                    div()
+                        .px(px(12.0))
+                        .py(px(6.0))
+                        .text_size(px(11.0))
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(
+                            t!("scripts.multi.still_running", hosts = unfinished.join(", "))
+                                .to_string(),
+                        ),
+                );
+            }
+        } else {
+            for host in &run.hosts {
+                body = body.child(self.render_multi_run_row(host, cx));
+            }
+        }
+
+        Some(
+            div()
+                .id("multi-run-panel")
+                .flex()
+                .flex_col()
+                .flex_grow()
+                .min_h_0()
+                .child(toolbar)
+                .child(body),
+        )
+    }
+
+    fn render_multi_run_row(&self, host: &HostRun, cx: &mut Context<Self>) -> Stateful<Div> {
+        let Some(connection_id) = host.record.connection_id else {
+            return div().id("multi-run-row-local");
+        };
+        let expanded = self.multi_run_expanded == Some(connection_id);
+        let last_line = host
+            .output
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("")
+            .to_string();
+        let exit_code = host
+            .record
+            .exit_code
+            .filter(|_| host.status != HostRunStatus::TimedOut)
+            .map(|code| code.to_string())
+            .unwrap_or_default();
+        let duration = host
+            .record
+            .duration_secs()
+            .map(|secs| format!("{:.1}s", secs))
+            .unwrap_or_default();
+
+        div()
+            .id(ElementId::Name(
+                format!("multi-run-row-{}", connection_id).into(),
+            ))
+            .flex()
+            .flex_col()
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .child(
+                div()
+                    .id(ElementId::Name(
+                        format!("multi-run-head-{}", connection_id).into(),
+                    ))
+                    .flex()
+                    .items_center()
+                    .gap(px(10.0))
+                    .px(px(12.0))
+                    .py(px(5.0))
+                    .cursor_pointer()
+                    .text_size(px(12.0))
+                    .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+                    .when(expanded, |el| el.bg(ShellDeckColors::selected_bg()))
+                    .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                        this.multi_run_expanded = if this.multi_run_expanded == Some(connection_id)
+                        {
+                            None
+                        } else {
+                            Some(connection_id)
+                        };
+                        cx.notify();
+                    }))
+                    .child(
+                        div()
+                            .w(px(7.0))
+                            .h(px(7.0))
+                            .rounded_full()
+                            .flex_shrink_0()
+                            .bg(status_color(host.status)),
+                    )
+                    .child(
+                        div()
+                            .w(px(160.0))
+                            .flex_shrink_0()
+                            .truncate()
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(host.host.clone()),
+                    )
+                    .child(
+                        div()
+                            .w(px(80.0))
+                            .flex_shrink_0()
+                            .text_color(status_color(host.status))
+                            .child(status_label(host.status)),
+                    )
+                    .child(
+                        div()
+                            .w(px(36.0))
+                            .flex_shrink_0()
+                            .font_family("JetBrains Mono")
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(exit_code),
+                    )
+                    .child(
+                        div()
+                            .w(px(56.0))
+                            .flex_shrink_0()
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(duration),
+                    )
+                    .child(
+                        div()
+                            .flex_grow()
+                            .min_w_0()
+                            .truncate()
+                            .font_family("JetBrains Mono")
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(last_line),
+                    ),
+            )
+            .when(expanded, |el| el.child(output_block(&output)))
+    }
+}
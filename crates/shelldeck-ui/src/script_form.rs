@@ -1,3 +1,5 @@
+mod triggers;
+
 use crate::scale::px;
 use adabraka_ui::components::combobox::Combobox;
 use adabraka_ui::components::icon_source::IconSource;
@@ -7,7 +9,10 @@ use gpui::prelude::*;
 use gpui::*;
 
 use shelldeck_core::ai::{AiBackend, AiGeneratedScriptDraft};
+use shelldeck_core::models::multi_run::{HostSelector, MultiTarget};
+use shelldeck_core::models::schedule::ScriptTrigger;
 use shelldeck_core::models::script::{Script, ScriptCategory, ScriptLanguage, ScriptTarget};
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::connection_combobox::{build_connection_combobox, connection_idx_for_id};
@@ -24,12 +29,16 @@ use crate::theme::ShellDeckColors;
 enum ValidationError {
     NameRequired,
     NoConnections,
+    NoHostsSelected,
+    InvalidSchedule,
 }
 
 fn script_form_error(err: ValidationError) -> String {
     match err {
         ValidationError::NameRequired => t!("script_form.error.name_required").to_string(),
         ValidationError::NoConnections => t!("script_form.error.no_connections").to_string(),
+        ValidationError::NoHostsSelected => t!("script_form.error.no_hosts").to_string(),
+        ValidationError::InvalidSchedule => t!("script_form.error.invalid_schedule").to_string(),
     }
 }
 
@@ -90,6 +99,15 @@ pub struct ScriptForm {
     category: ScriptCategory,
     body: EditorBuffer,
     target: ScriptTarget,
+    /// Multi-host settings, kept while the user flips between targets.
+    multi: MultiTarget,
+    host_groups: Vec<String>,
+    host_tags: Vec<String>,
+    triggers: Vec<ScriptTrigger>,
+    /// Cron expression inputs of the schedule triggers, by trigger id.
+    cron_inputs: HashMap<Uuid, Entity<InputState>>,
+    trigger_forwards: Vec<(Uuid, String)>,
+    trigger_sites: Vec<(Uuid, String)>,
     error: Option<String>,
     error_field: Option<FormField>,
     /// Non-text active field (Body / Target / Language / Category / Connection).
@@ -172,6 +190,13 @@ impl ScriptForm {
             category: ScriptCategory::Uncategorized,
             body: EditorBuffer::new(),
             target: ScriptTarget::Local,
+            multi: MultiTarget::new(HostSelector::Connections(Vec::new())),
+            host_groups: Vec::new(),
+            host_tags: Vec::new(),
+            triggers: Vec::new(),
+            cron_inputs: HashMap::new(),
+            trigger_forwards: Vec::new(),
+            trigger_sites: Vec::new(),
             error: None,
             error_field: None,
             active_field: None,
@@ -201,6 +226,7 @@ impl ScriptForm {
             other => (other.clone(), 0),
         };
         let connection_combobox = Self::init_connection_combobox(&connections, selected_idx, cx);
+        let cron_inputs = Self::init_cron_inputs(&script.triggers, cx);
         Self {
             editing_id: Some(script.id),
             connections,
@@ -218,7 +244,17 @@ impl ScriptForm {
             language: script.language.clone(),
             category: script.category,
             body: EditorBuffer::from_text(script.body.clone()),
+            multi: match &target {
+                ScriptTarget::Multi(multi) => multi.clone(),
+                _ => MultiTarget::new(HostSelector::Connections(Vec::new())),
+            },
             target,
+            host_groups: Vec::new(),
+            host_tags: Vec::new(),
+            triggers: script.triggers.clone(),
+            cron_inputs,
+            trigger_forwards: Vec::new(),
+            trigger_sites: Vec::new(),
             error: None,
             error_field: None,
             active_field: None,
@@ -228,6 +264,12 @@ impl ScriptForm {
         }
     }
 
+    /// Connection groups and tags offered as multi-host selectors.
+    pub fn set_host_labels(&mut self, groups: Vec<String>, tags: Vec<String>) {
+        self.host_groups = groups;
+        self.host_tags = tags;
+    }
+
     pub fn focus(&self, window: &mut Window) {
         self.focus_handle.focus(window);
     }
@@ -340,7 +382,10 @@ impl ScriptForm {
                 }
             }
             ScriptTarget::Remote(_) => ScriptTarget::AskOnRun,
-            ScriptTarget::AskOnRun => ScriptTarget::Local,
+            ScriptTarget::AskOnRun if !self.connections.is_empty() => {
+                ScriptTarget::Multi(self.multi.clone())
+            }
+            ScriptTarget::AskOnRun | ScriptTarget::Multi(_) => ScriptTarget::Local,
         };
     }
 
@@ -534,10 +579,13 @@ impl ScriptForm {
                 cx.emit(ScriptFormEvent::Save(script));
             }
             Err(err) => {
-                self.error_field = Some(match err {
-                    ValidationError::NameRequired => FormField::Name,
-                    ValidationError::NoConnections => FormField::Connection,
-                });
+                self.error_field = match err {
+                    ValidationError::NameRequired => Some(FormField::Name),
+                    ValidationError::NoConnections | ValidationError::NoHostsSelected => {
+                        Some(FormField::Connection)
+                    }
+                    ValidationError::InvalidSchedule => None,
+                };
                 self.error = Some(script_form_error(err));
                 cx.notify();
             }
@@ -557,6 +605,16 @@ impl ScriptForm {
                 }
                 ScriptTarget::Remote(self.connections[self.selected_connection_idx].0)
             }
+            ScriptTarget::Multi(_) => {
+                let empty = match &self.multi.hosts {
+                    HostSelector::Connections(ids) => ids.is_empty(),
+                    HostSelector::Group(name) | HostSelector::Tag(name) => name.is_empty(),
+                };
+                if empty {
+                    return Err(ValidationError::NoHostsSelected);
+                }
+                ScriptTarget::Multi(self.multi.clone())
+            }
             other => other.clone(),
         };
 
@@ -570,6 +628,7 @@ impl ScriptForm {
         if !description.is_empty() {
             script.description = Some(description);
         }
+        script.triggers = self.collect_triggers(cx)?;
 
         if let Some(id) = self.editing_id {
             script.id = id;
@@ -725,6 +784,10 @@ impl ScriptForm {
                 ScriptTarget::AskOnRun,
                 t!("script_form.target.ask_on_run").to_string(),
             ),
+            (
+                ScriptTarget::Multi(self.multi.clone()),
+                t!("scripts.target.multi").to_string(),
+            ),
         ];
 
         let mut chips = div().flex().gap(px(6.0));
@@ -735,6 +798,7 @@ impl ScriptForm {
                 (ScriptTarget::Local, ScriptTarget::Local)
                     | (ScriptTarget::Remote(_), ScriptTarget::Remote(_))
                     | (ScriptTarget::AskOnRun, ScriptTarget::AskOnRun)
+                    | (ScriptTarget::Multi(_), ScriptTarget::Multi(_))
             );
 
             let target_for_click = match &target {
@@ -999,6 +1063,238 @@ impl ScriptForm {
                     .child(self.connection_combobox.clone()),
             )
     }
+
+    fn option_chip(id: String, label: String, selected: bool) -> Stateful<Div> {
+        let chip = div()
+            .id(ElementId::from(SharedString::from(id)))
+            .px(px(8.0))
+            .py(px(3.0))
+            .rounded(px(6.0))
+            .text_size(px(11.0))
+            .cursor_pointer()
+            .border_1()
+            .child(label);
+        if selected {
+            chip.bg(ShellDeckColors::primary().opacity(0.2))
+                .text_color(ShellDeckColors::primary())
+                .border_color(ShellDeckColors::primary())
+        } else {
+            chip.bg(ShellDeckColors::bg_primary())
+                .text_color(ShellDeckColors::text_muted())
+                .border_color(ShellDeckColors::border())
+                .hover(|el| el.border_color(ShellDeckColors::text_muted()))
+        }
+    }
+
+    /// Host selection (connections, group or tag) plus concurrency, per-host
+    /// timeout and rolling mode for multi-host targets.
+    fn render_multi_target(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_error = self.error_field == Some(FormField::Connection);
+        let label = |key: &str| {
+            div()
+                .text_size(px(11.0))
+                .text_color(ShellDeckColors::text_muted())
+                .child(t!(key).to_string())
+        };
+
+        let mut modes = div().flex().gap(px(4.0));
+        for (idx, (mode, text)) in [
+            (
+                HostSelector::Connections(Vec::new()),
+                t!("script_form.multi.by_hosts"),
+            ),
+            (
+                HostSelector::Group(self.host_groups.first().cloned().unwrap_or_default()),
+                t!("script_form.multi.by_group"),
+            ),
+            (
+                HostSelector::Tag(self.host_tags.first().cloned().unwrap_or_default()),
+                t!("script_form.multi.by_tag"),
+            ),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let selected =
+                std::mem::discriminant(&mode) == std::mem::discriminant(&self.multi.hosts);
+            modes = modes.child(
+                Self::option_chip(format!("sf-multi-mode-{idx}"), text.to_string(), selected)
+                    .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                        if !selected {
+                            this.multi.hosts = mode.clone();
+                        }
+                        this.error = None;
+                        this.error_field = None;
+                        cx.notify();
+                    })),
+            );
+        }
+
+        let mut choices = div().flex().flex_wrap().gap(px(4.0));
+        match &self.multi.hosts {
+            HostSelector::Connections(ids) => {
+                for (id, name, _) in &self.connections {
+                    let id = *id;
+                    choices = choices.child(
+                        Self::option_chip(
+                            format!("sf-multi-host-{id}"),
+                            name.clone(),
+                            ids.contains(&id),
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &ClickEvent, _, cx| {
+                                if let HostSelector::Connections(ids) = &mut this.multi.hosts {
+                                    match ids.iter().position(|x| *x == id) {
+                                        Some(pos) => {
+                                            ids.remove(pos);
+                                        }
+                                        None => ids.push(id),
+                                    }
+                                }
+                                this.error = None;
+                                this.error_field = None;
+                                cx.notify();
+                            },
+                        )),
+                    );
+                }
+            }
+            HostSelector::Group(current) | HostSelector::Tag(current) => {
+                let is_group = matches!(self.multi.hosts, HostSelector::Group(_));
+                let names = if is_group {
+                    &self.host_groups
+                } else {
+                    &self.host_tags
+                };
+                if names.is_empty() {
+                    choices = choices.child(label(if is_group {
+                        "script_form.multi.no_groups"
+                    } else {
+                        "script_form.multi.no_tags"
+                    }));
+                }
+                for (idx, name) in names.iter().enumerate() {
+                    let value = name.clone();
+                    choices = choices.child(
+                        Self::option_chip(
+                            format!("sf-multi-label-{idx}"),
+                            name.clone(),
+                            name == current,
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &ClickEvent, _, cx| {
+                                this.multi.hosts = if is_group {
+                                    HostSelector::Group(value.clone())
+                                } else {
+                                    HostSelector::Tag(value.clone())
+                                };
+                                this.error = None;
+                                this.error_field = None;
+                                cx.notify();
+                            },
+                        )),
+                    );
+                }
+            }
+        }
+
+        let mut step_button = |id: &'static str, text: &'static str, delta: isize| {
+            div()
+                .id(id)
+                .px(px(7.0))
+                .rounded(px(4.0))
+                .cursor_pointer()
+                .border_1()
+                .border_color(ShellDeckColors::border())
+                .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+                .child(text)
+                .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                    this.multi.concurrency = this
+                        .multi
+                        .effective_concurrency()
+                        .saturating_add_signed(delta)
+                        .clamp(1, 64);
+                    cx.notify();
+                }))
+        };
+        let concurrency = div()
+            .flex()
+            .items_center()
+            .gap(px(6.0))
+            .text_size(px(12.0))
+            .text_color(ShellDeckColors::text_primary())
+            .child(label("script_form.multi.concurrency"))
+            .child(step_button("sf-multi-conc-dec", "-", -1))
+            .child(self.multi.effective_concurrency().to_string())
+            .child(step_button("sf-multi-conc-inc", "+", 1));
+
+        let mut timeouts = div()
+            .flex()
+            .items_center()
+            .gap(px(4.0))
+            .child(label("script_form.multi.timeout"));
+        for secs in [None, Some(30), Some(60), Some(300), Some(900)] {
+            let text = match secs {
+                None => t!("script_form.multi.no_timeout").to_string(),
+                Some(s) if s < 60 => format!("{}s", s),
+                Some(s) => format!("{}m", s / 60),
+            };
+            timeouts = timeouts.child(
+                Self::option_chip(
+                    format!("sf-multi-timeout-{}", secs.unwrap_or(0)),
+                    text,
+                    self.multi.timeout_secs == secs,
+                )
+                .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                    this.multi.timeout_secs = secs;
+                    cx.notify();
+                })),
+            );
+        }
+
+        let rolling = Self::option_chip(
+            "sf-multi-rolling".to_string(),
+            t!("script_form.multi.rolling").to_string(),
+            self.multi.rolling,
+        )
+        .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+            this.multi.rolling = !this.multi.rolling;
+            cx.notify();
+        }));
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .p(px(10.0))
+            .rounded(px(6.0))
+            .bg(ShellDeckColors::bg_primary())
+            .border_1()
+            .border_color(if has_error {
+                ShellDeckColors::error()
+            } else {
+                ShellDeckColors::border()
+            })
+            .child(
+                div()
+                    .text_size(px(12.0))
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("script_form.field.hosts").to_string()),
+            )
+            .child(modes)
+            .child(choices)
+            .child(concurrency)
+            .child(timeouts)
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(rolling)
+                    .child(label("script_form.multi.rolling_hint")),
+            )
+    }
 }
 
 impl Render for ScriptForm {
@@ -1008,6 +1304,7 @@ impl Render for ScriptForm {
             self.focus_handle.focus(window);
         }
         let show_connection = matches!(self.target, ScriptTarget::Remote(_));
+        let show_multi = matches!(self.target, ScriptTarget::Multi(_));
 
         let mut form_fields = div().flex().flex_col().gap(px(12.0));
 
@@ -1193,6 +1490,10 @@ impl Render for ScriptForm {
         if show_connection {
             form_fields = form_fields.child(self.render_connection_picker(cx));
         }
+        if show_multi {
+            form_fields = form_fields.child(self.render_multi_target(cx));
+        }
+        form_fields = form_fields.child(self.render_triggers(cx));
 
         // Error message
         if let Some(ref error) = self.error {
@@ -0,0 +1,454 @@
+use super::*;
+use shelldeck_core::models::schedule::{
+    CatchUpPolicy, CronSchedule, ScriptTrigger, SiteTransition, TriggerKind,
+};
+
+/// Trigger kinds offered by the "add" chips, with their label keys.
+fn trigger_templates() -> [(TriggerKind, &'static str); 5] {
+    [
+        (
+            TriggerKind::Schedule {
+                cron: "0 * * * *".to_string(),
+                catch_up: CatchUpPolicy::RunOnce,
+            },
+            "script_form.trigger.kind.schedule",
+        ),
+        (
+            TriggerKind::OnConnect {
+                connection_id: None,
+            },
+            "script_form.trigger.kind.on_connect",
+        ),
+        (
+            TriggerKind::OnDisconnect {
+                connection_id: None,
+                unexpected_only: false,
+            },
+            "script_form.trigger.kind.on_disconnect",
+        ),
+        (
+            TriggerKind::OnTunnelStart { forward_id: None },
+            "script_form.trigger.kind.on_tunnel_start",
+        ),
+        (
+            TriggerKind::OnSiteStatus {
+                site_id: None,
+                change: SiteTransition::Any,
+            },
+            "script_form.trigger.kind.on_site_status",
+        ),
+    ]
+}
+
+fn kind_label(kind: &TriggerKind) -> String {
+    let key = match kind {
+        TriggerKind::Schedule { .. } => "script_form.trigger.kind.schedule",
+        TriggerKind::OnConnect { .. } => "script_form.trigger.kind.on_connect",
+        TriggerKind::OnDisconnect { .. } => "script_form.trigger.kind.on_disconnect",
+        TriggerKind::OnTunnelStart { .. } => "script_form.trigger.kind.on_tunnel_start",
+        TriggerKind::OnSiteStatus { .. } => "script_form.trigger.kind.on_site_status",
+    };
+    t!(key).to_string()
+}
+
+impl ScriptForm {
+    /// Port forwards and managed sites offered as event trigger filters.
+    pub fn set_trigger_sources(
+        &mut self,
+        forwards: Vec<(Uuid, String)>,
+        sites: Vec<(Uuid, String)>,
+    ) {
+        self.trigger_forwards = forwards;
+        self.trigger_sites = sites;
+    }
+
+    pub(super) fn init_cron_inputs(
+        triggers: &[ScriptTrigger],
+        cx: &mut Context<Self>,
+    ) -> HashMap<Uuid, Entity<InputState>> {
+        triggers
+            .iter()
+            .filter_map(|trigger| match &trigger.kind {
+                TriggerKind::Schedule { cron, .. } => {
+                    Some((trigger.id, new_input_state_sf(cx, cron)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn add_trigger(&mut self, kind: TriggerKind, cx: &mut Context<Self>) {
+        let trigger = ScriptTrigger::new(kind);
+        if let TriggerKind::Schedule { cron, .. } = &trigger.kind {
+            let input = new_input_state_sf(cx, cron);
+            self.cron_inputs.insert(trigger.id, input);
+        }
+        self.triggers.push(trigger);
+        cx.notify();
+    }
+
+    /// Triggers with the cron fields read back from their inputs.
+    pub(super) fn collect_triggers(&self, cx: &App) -> Result<Vec<ScriptTrigger>, ValidationError> {
+        let mut triggers = self.triggers.clone();
+        for trigger in &mut triggers {
+            if let TriggerKind::Schedule { cron, .. } = &mut trigger.kind {
+                if let Some(input) = self.cron_inputs.get(&trigger.id) {
+                    *cron = Self::field_value(input, cx);
+                }
+                if CronSchedule::parse(cron).is_err() {
+                    return Err(ValidationError::InvalidSchedule);
+                }
+            }
+        }
+        Ok(triggers)
+    }
+
+    fn update_trigger(
+        &mut self,
+        id: Uuid,
+        cx: &mut Context<Self>,
+        f: impl FnOnce(&mut ScriptTrigger),
+    ) {
+        if let Some(trigger) = self.triggers.iter_mut().find(|t| t.id == id) {
+            f(trigger);
+        }
+        self.error = None;
+        cx.notify();
+    }
+
+    /// "Any" plus one chip per choice; clicking sets the filter.
+    fn render_filter_chips(
+        &self,
+        trigger_id: Uuid,
+        choices: &[(Uuid, String)],
+        current: Option<Uuid>,
+        cx: &mut Context<Self>,
+        set: fn(&mut TriggerKind, Option<Uuid>),
+    ) -> Div {
+        let mut row = div().flex().flex_wrap().gap(px(4.0));
+        let options = std::iter::once((None, t!("script_form.trigger.any").to_string()))
+            .chain(choices.iter().map(|(id, name)| (Some(*id), name.clone())));
+        for (value, label) in options {
+            let key = value.map(|id| id.to_string()).unwrap_or_default();
+            row = row.child(
+                Self::option_chip(
+                    format!("sf-trigger-{trigger_id}-filter-{key}"),
+                    label,
+                    current == value,
+                )
+                .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                    this.update_trigger(trigger_id, cx, |t| set(&mut t.kind, value));
+                })),
+            );
+        }
+        row
+    }
+
+    fn render_trigger(&self, trigger: &ScriptTrigger, cx: &mut Context<Self>) -> impl IntoElement {
+        let id = trigger.id;
+        let hint = |text: String| {
+            div()
+                .text_size(px(11.0))
+                .text_color(ShellDeckColors::text_muted())
+                .child(text)
+        };
+
+        let header = div()
+            .flex()
+            .items_center()
+            .justify_between()
+            .gap(px(6.0))
+            .child(
+                div()
+                    .text_size(px(12.0))
+                    .font_weight(FontWeight::MEDIUM)
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(kind_label(&trigger.kind)),
+            )
+            .child(
+                div()
+                    .flex()
+                    .gap(px(4.0))
+                    .child(
+                        Self::option_chip(
+                            format!("sf-trigger-{id}-enabled"),
+                            t!("script_form.trigger.enabled").to_string(),
+                            trigger.enabled,
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &ClickEvent, _, cx| {
+                                this.update_trigger(id, cx, |t| t.enabled = !t.enabled);
+                            },
+                        )),
+                    )
+                    .child(
+                        Self::option_chip(
+                            format!("sf-trigger-{id}-remove"),
+                            t!("script_form.trigger.remove").to_string(),
+                            false,
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &ClickEvent, _, cx| {
+                                this.triggers.retain(|t| t.id != id);
+                                this.cron_inputs.remove(&id);
+                                this.error = None;
+                                cx.notify();
+                            },
+                        )),
+                    ),
+            );
+
+        let mut card = div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .p(px(8.0))
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(ShellDeckColors::border())
+            .when(!trigger.enabled, |el| el.opacity(0.6))
+            .child(header);
+
+        match &trigger.kind {
+            TriggerKind::Schedule { catch_up, .. } => {
+                let Some(input) = self.cron_inputs.get(&id) else {
+                    return card;
+                };
+                let cron = Self::field_value(input, cx);
+                let parsed = CronSchedule::parse(&cron);
+                let preview = match &parsed {
+                    Ok(schedule) => match schedule.next_after(chrono::Local::now().naive_local()) {
+                        Some(next) => t!(
+                            "script_form.trigger.next_run",
+                            time = next.format("%Y-%m-%d %H:%M").to_string()
+                        )
+                        .to_string(),
+                        None => t!("script_form.trigger.never").to_string(),
+                    },
+                    Err(_) => t!("script_form.trigger.invalid_cron").to_string(),
+                };
+                let entity = cx.entity();
+                card = card
+                    .child(
+                        Input::new(input)
+                            .size(InputSize::Sm)
+                            .placeholder(t!("script_form.trigger.cron_placeholder").to_string())
+                            .error(parsed.is_err())
+                            .on_change(move |_, cx| {
+                                // Re-render so the next-run preview follows the value.
+                                entity.update(cx, |this, cx| {
+                                    this.error = None;
+                                    cx.notify();
+                                });
+                            }),
+                    )
+                    .child(hint(preview).when(parsed.is_err(), |el| {
+                        el.text_color(ShellDeckColors::error())
+                    }));
+
+                let mut policies = div().flex().items_center().gap(px(4.0));
+                for (policy, key) in [
+                    (
+                        CatchUpPolicy::RunOnce,
+                        "script_form.trigger.catch_up.run_once",
+                    ),
+                    (CatchUpPolicy::Skip, "script_form.trigger.catch_up.skip"),
+                ] {
+                    policies = policies.child(
+                        Self::option_chip(
+                            format!("sf-trigger-{id}-catch-up-{key}"),
+                            t!(key).to_string(),
+                            *catch_up == policy,
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &ClickEvent, _, cx| {
+                                this.update_trigger(id, cx, |t| {
+                                    if let TriggerKind::Schedule { catch_up, .. } = &mut t.kind {
+                                        *catch_up = policy;
+                                    }
+                                });
+                            },
+                        )),
+                    );
+                }
+                card = card.child(policies);
+            }
+            TriggerKind::OnConnect { connection_id } => {
+                let hosts = self.connection_choices();
+                card = card.child(self.render_filter_chips(
+                    id,
+                    &hosts,
+                    *connection_id,
+                    cx,
+                    |kind, value| {
+                        if let TriggerKind::OnConnect { connection_id } = kind {
+                            *connection_id = value;
+                        }
+                    },
+                ));
+            }
+            TriggerKind::OnDisconnect {
+                connection_id,
+                unexpected_only,
+            } => {
+                let hosts = self.connection_choices();
+                card = card
+                    .child(self.render_filter_chips(
+                        id,
+                        &hosts,
+                        *connection_id,
+                        cx,
+                        |kind, value| {
+                            if let TriggerKind::OnDisconnect { connection_id, .. } = kind {
+                                *connection_id = value;
+                            }
+                        },
+                    ))
+                    .child(
+                        Self::option_chip(
+                            format!("sf-trigger-{id}-unexpected"),
+                            t!("script_form.trigger.unexpected_only").to_string(),
+                            *unexpected_only,
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &ClickEvent, _, cx| {
+                                this.update_trigger(id, cx, |t| {
+                                    if let TriggerKind::OnDisconnect {
+                                        unexpected_only, ..
+                                    } = &mut t.kind
+                                    {
+                                        *unexpected_only = !*unexpected_only;
+                                    }
+                                });
+                            },
+                        )),
+                    );
+            }
+            TriggerKind::OnTunnelStart { forward_id } => {
+                card = card.child(self.render_filter_chips(
+                    id,
+                    &self.trigger_forwards,
+                    *forward_id,
+                    cx,
+                    |kind, value| {
+                        if let TriggerKind::OnTunnelStart { forward_id } = kind {
+                            *forward_id = value;
+                        }
+                    },
+                ));
+            }
+            TriggerKind::OnSiteStatus { site_id, change } => {
+                let mut changes = div().flex().gap(px(4.0));
+                for (transition, key) in [
+                    (SiteTransition::Any, "script_form.trigger.site.any_change"),
+                    (
+                        SiteTransition::WentDown,
+                        "script_form.trigger.site.went_down",
+                    ),
+                    (SiteTransition::CameUp, "script_form.trigger.site.came_up"),
+                ] {
+                    changes = changes.child(
+                        Self::option_chip(
+                            format!("sf-trigger-{id}-change-{key}"),
+                            t!(key).to_string(),
+                            *change == transition,
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &ClickEvent, _, cx| {
+                                this.update_trigger(id, cx, |t| {
+                                    if let TriggerKind::OnSiteStatus { change, .. } = &mut t.kind {
+                                        *change = transition;
+                                    }
+                                });
+                            },
+                        )),
+                    );
+                }
+                card = card
+                    .child(self.render_filter_chips(
+                        id,
+                        &self.trigger_sites,
+                        *site_id,
+                        cx,
+                        |kind, value| {
+                            if let TriggerKind::OnSiteStatus { site_id, .. } = kind {
+                                *site_id = value;
+                            }
+                        },
+                    ))
+                    .child(changes);
+            }
+        }
+
+        if !trigger.is_schedule() {
+            card = card.child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(
+                        Self::option_chip(
+                            format!("sf-trigger-{id}-event-host"),
+                            t!("script_form.trigger.run_on_event_host").to_string(),
+                            trigger.run_on_event_host,
+                        )
+                        .on_click(cx.listener(
+                            move |this, _: &ClickEvent, _, cx| {
+                                this.update_trigger(id, cx, |t| {
+                                    t.run_on_event_host = !t.run_on_event_host;
+                                });
+                            },
+                        )),
+                    )
+                    .child(hint(
+                        t!("script_form.trigger.run_on_event_host_hint").to_string(),
+                    )),
+            );
+        }
+        card
+    }
+
+    fn connection_choices(&self) -> Vec<(Uuid, String)> {
+        self.connections
+            .iter()
+            .map(|(id, name, _)| (*id, name.clone()))
+            .collect()
+    }
+
+    /// Schedules and events that run the script unattended.
+    pub(super) fn render_triggers(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let mut add = div().flex().flex_wrap().gap(px(4.0));
+        for (idx, (kind, key)) in trigger_templates().into_iter().enumerate() {
+            add = add.child(
+                Self::option_chip(
+                    format!("sf-trigger-add-{idx}"),
+                    format!("+ {}", t!(key)),
+                    false,
+                )
+                .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                    this.add_trigger(kind.clone(), cx);
+                })),
+            );
+        }
+
+        let mut section = div().flex().flex_col().gap(px(6.0)).child(
+            div()
+                .text_size(px(12.0))
+                .font_weight(FontWeight::MEDIUM)
+                .text_color(ShellDeckColors::text_muted())
+                .child(t!("script_form.field.triggers").to_string()),
+        );
+        if self.triggers.is_empty() {
+            section = section.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("script_form.trigger.none").to_string()),
+            );
+        }
+        for trigger in &self.triggers {
+            section = section.child(self.render_trigger(trigger, cx));
+        }
+        section.child(add)
+    }
+}
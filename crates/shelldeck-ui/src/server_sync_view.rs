@@ -1,9 +1,23 @@
+mod database;
+mod file_manager;
+mod plan;
+mod wordpress;
+
+use std::collections::{HashMap, HashSet};
+
 use crate::scale::px;
+use adabraka_ui::components::input::InputState;
 use adabraka_ui::components::select::{Select, SelectOption};
 use gpui::prelude::*;
 use gpui::*;
 use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::db_credentials::DiscoveredCredentials;
+use shelldeck_core::models::file_transfer::{
+    ConflictChoice, FileOperation, TransferItem, TransferQueue,
+};
 use shelldeck_core::models::server_sync::*;
+use shelldeck_core::models::sync_plan::{self, ItemPlan};
+use shelldeck_core::models::wordpress::DiscoveredWordPress;
 use uuid::Uuid;
 
 use crate::t;
@@ -89,6 +103,11 @@ pub enum ServerSyncEvent {
         path: String,
         panel: PanelSide,
     },
+    /// Build the review-step plan for the wizard's selection.
+    PlanSync {
+        request_id: Uuid,
+        profile: SyncProfile,
+    },
     StartSync(SyncProfile),
     CancelSync(Uuid),
     SaveProfile(SyncProfile),
@@ -99,6 +118,29 @@ pub enum ServerSyncEvent {
         operation_id: Uuid,
         item_id: Uuid,
     },
+    /// Copy or move panel entries to the other panel.
+    QueueTransfers(Vec<TransferItem>),
+    PauseTransfer(Uuid),
+    ResumeTransfer(Uuid),
+    RetryTransfer(Uuid),
+    CancelTransfer(Uuid),
+    ResolveConflict {
+        id: Uuid,
+        choice: ConflictChoice,
+        apply_to_all: bool,
+    },
+    ClearTransfers,
+    /// Rename, delete, create or chmod on one side.
+    FileOperation {
+        connection_id: Uuid,
+        operation: FileOperation,
+    },
+    /// Browse a directory or edit a file in the file editor.
+    OpenInEditor {
+        connection_id: Uuid,
+        path: String,
+        is_dir: bool,
+    },
 }
 
 /// Wizard steps for the sync configuration flow.
@@ -123,10 +165,15 @@ pub struct ServerPanelState {
     pub path_history: Vec<String>,
     pub discovered_sites: Vec<DiscoveredSite>,
     pub discovered_databases: Vec<DiscoveredDatabase>,
+    pub discovered_wordpress: Vec<DiscoveredWordPress>,
+    /// Database logins found in config files on the server.
+    pub discovered_credentials: Vec<DiscoveredCredentials>,
     pub discovery_loading: bool,
     pub files_loading: bool,
     pub discovery_panel_height: f32,
     pub discovery_resizing: bool,
+    /// Paths of the selected file entries.
+    pub selected: HashSet<String>,
 }
 
 impl ServerPanelState {
@@ -140,10 +187,13 @@ impl ServerPanelState {
             path_history: Vec::new(),
             discovered_sites: Vec::new(),
             discovered_databases: Vec::new(),
+            discovered_wordpress: Vec::new(),
+            discovered_credentials: Vec::new(),
             discovery_loading: false,
             files_loading: false,
             discovery_panel_height: 150.0,
             discovery_resizing: false,
+            selected: HashSet::new(),
         }
     }
 }
@@ -158,6 +208,13 @@ pub struct ServerSyncView {
     pub wizard_step: WizardStep,
     pub wizard_items: Vec<SyncItem>,
     pub wizard_options: SyncOptions,
+    /// Plan shown in the review step; unticked entries are excluded.
+    pub sync_plans: Vec<ItemPlan>,
+    plan_request: Option<Uuid>,
+    plan_collapsed: HashSet<String>,
+    plan_diff_open: Option<plan::PlanEntryKey>,
+    /// New-URL fields of the wizard's WordPress items, by item id.
+    wp_url_inputs: HashMap<Uuid, Entity<InputState>>,
     pub active_operation: Option<SyncOperation>,
     pub log_lines: Vec<String>,
     source_select: Entity<Select<Uuid>>,
@@ -167,6 +224,7 @@ pub struct ServerSyncView {
     focus_handle: FocusHandle,
     pub panel_ratio: f32,
     pub panel_dragging: bool,
+    file_manager: file_manager::FileManagerState,
 }
 
 impl EventEmitter<ServerSyncEvent> for ServerSyncView {}
@@ -188,6 +246,11 @@ impl ServerSyncView {
             wizard_step: WizardStep::SelectItems,
             wizard_items: Vec::new(),
             wizard_options: SyncOptions::default(),
+            sync_plans: Vec::new(),
+            plan_request: None,
+            plan_collapsed: HashSet::new(),
+            plan_diff_open: None,
+            wp_url_inputs: HashMap::new(),
             active_operation: None,
             log_lines: Vec::new(),
             source_select,
@@ -197,6 +260,7 @@ impl ServerSyncView {
             focus_handle: cx.focus_handle(),
             panel_ratio: 0.5,
             panel_dragging: false,
+            file_manager: file_manager::FileManagerState::new(cx),
         }
     }
 
@@ -226,6 +290,28 @@ impl ServerSyncView {
         self.profiles = profiles;
     }
 
+    /// The profile the wizard would run, if both sides are chosen.
+    fn wizard_profile(&self) -> Option<SyncProfile> {
+        let (src_id, dest_id) = (
+            self.source_panel.connection_id?,
+            self.dest_panel.connection_id?,
+        );
+        Some(SyncProfile {
+            id: Uuid::new_v4(),
+            name: format!(
+                "{} -> {}",
+                self.source_panel.connection_name, self.dest_panel.connection_name
+            ),
+            description: None,
+            source_connection_id: src_id,
+            dest_connection_id: dest_id,
+            items: self.wizard_items.clone(),
+            options: self.wizard_options.clone(),
+            created_at: chrono::Utc::now(),
+            last_synced: None,
+        })
+    }
+
     pub fn load_profile(&mut self, profile_id: Uuid, cx: &mut Context<Self>) {
         self.selected_profile = Some(profile_id);
         if let Some(profile) = self.profiles.iter().find(|p| p.id == profile_id) {
@@ -269,6 +355,13 @@ impl ServerSyncView {
             PanelSide::Source => &mut self.source_panel,
             PanelSide::Destination => &mut self.dest_panel,
         };
+        if state.current_path == path {
+            state
+                .selected
+                .retain(|selected| entries.iter().any(|e| &e.path == selected));
+        } else {
+            state.selected.clear();
+        }
         state.current_path = path;
         state.file_entries = entries;
         state.files_loading = false;
@@ -482,6 +575,8 @@ impl ServerSyncView {
                             this.wizard_step = WizardStep::SelectItems;
                             this.wizard_items.clear();
                             this.wizard_options = SyncOptions::default();
+                            this.sync_plans.clear();
+                            this.plan_request = None;
                             cx.notify();
                         }))
                 })
@@ -580,6 +675,8 @@ impl ServerSyncView {
         state.file_entries.clear();
         state.discovered_sites.clear();
         state.discovered_databases.clear();
+        state.discovered_wordpress.clear();
+        state.discovered_credentials.clear();
         state.files_loading = true;
 
         cx.emit(ServerSyncEvent::ListFiles {
@@ -711,7 +808,12 @@ impl ServerSyncView {
             .flex_col()
             .flex_grow()
             .overflow_y_scroll()
-            .min_h(px(0.0));
+            .min_h(px(0.0))
+            .when(state.connection_id.is_some(), |el| {
+                el.on_drop(cx.listener(move |this, paths: &ExternalPaths, _, cx| {
+                    this.queue_dropped_paths(side, paths.paths().to_vec(), cx);
+                }))
+            });
 
         if state.files_loading {
             list = list.child(
@@ -867,6 +969,7 @@ impl ServerSyncView {
         for entry in &state.file_entries {
             let entry_path = entry.path.clone();
             let is_dir = entry.is_dir;
+            let selected = state.selected.contains(&entry.path);
             let icon = if is_dir { "📁" } else { "📄" };
             let name = entry.name.clone();
             let size = entry.size_display();
@@ -887,25 +990,13 @@ impl ServerSyncView {
                     .px(px(8.0))
                     .py(px(3.0))
                     .cursor_pointer()
-                    .hover(|el| el.bg(ShellDeckColors::hover_bg()))
-                    .when(is_dir, |el| {
-                        el.on_click(cx.listener(move |this, _, _, cx| {
-                            let state = match side {
-                                PanelSide::Source => &mut this.source_panel,
-                                PanelSide::Destination => &mut this.dest_panel,
-                            };
-                            if let Some(conn_id) = state.connection_id {
-                                state.path_history.push(state.current_path.clone());
-                                state.files_loading = true;
-                                cx.emit(ServerSyncEvent::ListFiles {
-                                    connection_id: conn_id,
-                                    path: entry_path.clone(),
-                                    panel: side,
-                                });
-                                cx.notify();
-                            }
-                        }))
+                    .when(selected, |el| {
+                        el.bg(ShellDeckColors::primary().opacity(0.12))
                     })
+                    .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+                    .on_click(cx.listener(move |this, event: &ClickEvent, _, cx| {
+                        this.click_file_entry(side, entry_path.clone(), is_dir, event, cx);
+                    }))
                     .child(
                         div()
                             .flex()
@@ -1085,6 +1176,10 @@ impl ServerSyncView {
                     String::new()
                 };
                 let port_str = format!(":{}", site.listen_port);
+                let is_wordpress = state
+                    .discovered_wordpress
+                    .iter()
+                    .any(|wp| wp.root == site.root.trim_end_matches('/'));
                 content = content.child(
                     div()
                         .flex()
@@ -1109,6 +1204,18 @@ impl ServerSyncView {
                                 .text_color(ShellDeckColors::text_muted())
                                 .child(port_str),
                         )
+                        .when(is_wordpress, |el| {
+                            el.child(
+                                div()
+                                    .px(px(4.0))
+                                    .py(px(1.0))
+                                    .rounded(px(3.0))
+                                    .bg(ShellDeckColors::primary().opacity(0.15))
+                                    .text_size(px(9.0))
+                                    .text_color(ShellDeckColors::primary())
+                                    .child(t!("sync.wordpress.badge").to_string()),
+                            )
+                        })
                         .child(
                             div()
                                 .text_size(px(10.0))
@@ -1279,7 +1386,10 @@ impl ServerSyncView {
         // Breadcrumbs — only once a connection is picked. Otherwise the
         // panel would render a stray "/" root crumb under an empty dropdown.
         if self.panel_state(side).connection_id.is_some() {
-            panel = panel.child(self.render_breadcrumbs(side, cx));
+            panel = panel
+                .child(self.render_breadcrumbs(side, cx))
+                .child(self.render_file_actions(side, cx))
+                .children(self.render_file_prompt(side, cx));
         }
 
         panel
@@ -1424,7 +1534,12 @@ impl ServerSyncView {
             for prog in &op.item_progress {
                 if prog.status == SyncOperationStatus::Running {
                     let pct = prog.percent().unwrap_or(0.0);
-                    let label = prog.current_file.clone().unwrap_or_default();
+                    let file = prog.current_file.clone().unwrap_or_default();
+                    let label = match prog.step {
+                        Some(step) if file.is_empty() => step_label(step),
+                        Some(step) => format!("{} · {}", step_label(step), file),
+                        None => file,
+                    };
                     panel = panel.child(
                         div()
                             .flex()
@@ -1694,12 +1809,14 @@ impl ServerSyncView {
                             }) {
                                 this.wizard_items.remove(idx);
                             } else {
+                                this.remove_wordpress_item(&site_name);
                                 this.wizard_items.push(SyncItem {
                                     id: Uuid::new_v4(),
                                     kind: SyncItemKind::NginxSite {
                                         site: site_clone.clone(),
                                         sync_config: true,
                                         sync_root: true,
+                                        exclude_patterns: Vec::new(),
                                     },
                                     enabled: true,
                                 });
@@ -1736,6 +1853,11 @@ impl ServerSyncView {
             }
         }
 
+        // WordPress installs under the source sites
+        if let Some(section) = self.render_wizard_wordpress_sites(cx) {
+            content = content.child(section);
+        }
+
         // Discovered databases from source
         if !self.source_panel.discovered_databases.is_empty() {
             content = content.child(
@@ -1778,13 +1900,23 @@ impl ServerSyncView {
                             }) {
                                 this.wizard_items.remove(idx);
                             } else {
+                                let source_credentials = this.default_db_credentials(
+                                    PanelSide::Source,
+                                    &db_name_inner,
+                                    engine,
+                                );
+                                let dest_credentials = this.default_db_credentials(
+                                    PanelSide::Destination,
+                                    &db_name_inner,
+                                    engine,
+                                );
                                 this.wizard_items.push(SyncItem {
                                     id: Uuid::new_v4(),
                                     kind: SyncItemKind::Database {
                                         name: db_name_inner,
                                         engine,
-                                        source_credentials: String::new(),
-                                        dest_credentials: String::new(),
+                                        source_credentials,
+                                        dest_credentials,
                                     },
                                     enabled: true,
                                 });
@@ -1924,6 +2056,14 @@ impl ServerSyncView {
                     this.wizard_options.skip_existing = val;
                 },
             ))
+            .child(self.render_option_toggle(
+                t!("sync.option.checksum").to_string(),
+                opts.checksum,
+                cx,
+                |this, val| {
+                    this.wizard_options.checksum = val;
+                },
+            ))
             // Bandwidth limit
             .child(
                 div()
@@ -1947,6 +2087,71 @@ impl ServerSyncView {
                             ),
                     ),
             )
+            .child(self.render_transport_choice(cx))
+            .children(self.render_database_logins(cx))
+            .children(self.render_wordpress_options(cx))
+    }
+
+    /// Transport selector: direct commands on the source, a relay through
+    /// ShellDeck, or pick automatically at sync time.
+    fn render_transport_choice(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let current = self.wizard_options.transport;
+
+        let mut chips = div().flex().gap(px(4.0));
+        for transport in SyncTransport::ALL {
+            let selected = transport == current;
+            chips = chips.child(
+                div()
+                    .id(ElementId::from(SharedString::from(format!(
+                        "sync-transport-{transport:?}"
+                    ))))
+                    .px(px(8.0))
+                    .py(px(3.0))
+                    .rounded(px(6.0))
+                    .text_size(px(11.0))
+                    .cursor_pointer()
+                    .border_1()
+                    .when(selected, |el| {
+                        el.bg(ShellDeckColors::primary().opacity(0.2))
+                            .text_color(ShellDeckColors::primary())
+                            .border_color(ShellDeckColors::primary())
+                    })
+                    .when(!selected, |el| {
+                        el.bg(ShellDeckColors::bg_primary())
+                            .text_color(ShellDeckColors::text_muted())
+                            .border_color(ShellDeckColors::border())
+                    })
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.wizard_options.transport = transport;
+                        cx.notify();
+                    }))
+                    .child(transport_label(transport)),
+            );
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_size(px(13.0))
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(t!("sync.option.transport").to_string()),
+                    )
+                    .child(chips),
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(transport_hint(current)),
+            )
     }
 
     fn render_option_toggle(
@@ -1996,7 +2201,7 @@ impl ServerSyncView {
             )
     }
 
-    fn render_wizard_review(&self, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render_wizard_review(&self, cx: &mut Context<Self>) -> impl IntoElement {
         let mut content = div().flex().flex_col().gap(px(8.0));
 
         content = content.child(
@@ -2029,6 +2234,18 @@ impl ServerSyncView {
         if self.wizard_options.skip_existing {
             opts_summary.push(t!("sync.review.option.skip_existing").to_string());
         }
+        if self.wizard_options.checksum {
+            opts_summary.push(t!("sync.review.option.checksum").to_string());
+        }
+        if self.wizard_options.transport != SyncTransport::Auto {
+            opts_summary.push(
+                t!(
+                    "sync.review.option.transport",
+                    transport = transport_label(self.wizard_options.transport).as_str()
+                )
+                .to_string(),
+            );
+        }
 
         if !opts_summary.is_empty() {
             content = content.child(
@@ -2045,32 +2262,9 @@ impl ServerSyncView {
             );
         }
 
-        // Item list
+        // Item list with the planned changes of each item
         for item in &self.wizard_items {
-            let label = item.kind.label();
-            content = content.child(
-                div()
-                    .flex()
-                    .items_center()
-                    .gap(px(8.0))
-                    .px(px(8.0))
-                    .py(px(6.0))
-                    .rounded(px(6.0))
-                    .bg(bg_secondary())
-                    .child(
-                        div()
-                            .w(px(6.0))
-                            .h(px(6.0))
-                            .rounded_full()
-                            .bg(ShellDeckColors::primary()),
-                    )
-                    .child(
-                        div()
-                            .text_size(px(12.0))
-                            .text_color(ShellDeckColors::text_primary())
-                            .child(label),
-                    ),
-            );
+            content = content.child(self.render_item_plan(item, cx));
         }
 
         content
@@ -2149,6 +2343,14 @@ impl ServerSyncView {
                                     .to_string(),
                                 ),
                         )
+                        .when_some(prog.step, |el, step| {
+                            el.child(
+                                div()
+                                    .text_size(px(10.0))
+                                    .text_color(ShellDeckColors::primary())
+                                    .child(step_label(step)),
+                            )
+                        })
                         .when_some(prog.current_file.clone(), |el, file| {
                             el.child(
                                 div()
@@ -2275,6 +2477,7 @@ impl ServerSyncView {
                                 .text_color(hsla(0.0, 0.0, 1.0, 1.0))
                                 .hover(|el| el.opacity(0.9))
                                 .on_click(cx.listener(|this, _, _, cx| {
+                                    this.ensure_wordpress_inputs(cx);
                                     this.wizard_step = WizardStep::ConfigureOptions;
                                     cx.notify();
                                 }))
@@ -2301,6 +2504,7 @@ impl ServerSyncView {
                         .hover(|el| el.opacity(0.9))
                         .on_click(cx.listener(|this, _, _, cx| {
                             this.wizard_step = WizardStep::ReviewConfirm;
+                            this.request_sync_plan(cx);
                             cx.notify();
                         }))
                         .child(t!("sync.next").to_string()),
@@ -2321,26 +2525,10 @@ impl ServerSyncView {
                         .hover(|el| el.opacity(0.9))
                         .on_click(cx.listener(|this, _, _, cx| {
                             this.wizard_step = WizardStep::Executing;
-                            // Build and emit the sync profile
-                            if let (Some(src_id), Some(dest_id)) = (
-                                this.source_panel.connection_id,
-                                this.dest_panel.connection_id,
-                            ) {
-                                let profile = SyncProfile {
-                                    id: Uuid::new_v4(),
-                                    name: format!(
-                                        "{} -> {}",
-                                        this.source_panel.connection_name,
-                                        this.dest_panel.connection_name
-                                    ),
-                                    description: None,
-                                    source_connection_id: src_id,
-                                    dest_connection_id: dest_id,
-                                    items: this.wizard_items.clone(),
-                                    options: this.wizard_options.clone(),
-                                    created_at: chrono::Utc::now(),
-                                    last_synced: None,
-                                };
+                            // Build and emit the sync profile, minus what
+                            // was unticked in the plan.
+                            if let Some(mut profile) = this.wizard_profile() {
+                                sync_plan::apply_exclusions(&mut profile.items, &this.sync_plans);
                                 cx.emit(ServerSyncEvent::StartSync(profile));
                             }
                             cx.notify();
@@ -2424,6 +2612,9 @@ impl Render for ServerSyncView {
 
         root = root.child(panels);
 
+        // Transfer queue
+        root = root.children(self.render_transfer_queue(cx));
+
         // Log panel
         root = root.child(self.render_log_panel(cx));
 
@@ -2434,6 +2625,31 @@ impl Render for ServerSyncView {
     }
 }
 
+fn transport_label(transport: SyncTransport) -> String {
+    match transport {
+        SyncTransport::Auto => t!("sync.transport.auto").to_string(),
+        SyncTransport::Direct => t!("sync.transport.direct").to_string(),
+        SyncTransport::Relay => t!("sync.transport.relay").to_string(),
+    }
+}
+
+fn transport_hint(transport: SyncTransport) -> String {
+    match transport {
+        SyncTransport::Auto => t!("sync.transport.hint.auto").to_string(),
+        SyncTransport::Direct => t!("sync.transport.hint.direct").to_string(),
+        SyncTransport::Relay => t!("sync.transport.hint.relay").to_string(),
+    }
+}
+
+fn step_label(step: SyncStep) -> String {
+    match step {
+        SyncStep::Files => t!("sync.step.files").to_string(),
+        SyncStep::Database => t!("sync.step.database").to_string(),
+        SyncStep::Config => t!("sync.step.config").to_string(),
+        SyncStep::Caches => t!("sync.step.caches").to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::breadcrumb_segments;
@@ -0,0 +1,256 @@
+use super::*;
+use shelldeck_core::models::db_credentials::{DbCredentials, DiscoveredCredentials};
+
+impl ServerSyncView {
+    pub fn set_discovered_credentials(
+        &mut self,
+        panel: PanelSide,
+        credentials: Vec<DiscoveredCredentials>,
+    ) {
+        self.panel_state_mut(panel).discovered_credentials = credentials;
+    }
+
+    /// The login a new database item starts with on one side: credentials
+    /// discovered for a database of the same name, if any.
+    pub(super) fn default_db_credentials(
+        &self,
+        panel: PanelSide,
+        name: &str,
+        engine: DatabaseEngine,
+    ) -> Option<DbCredentials> {
+        self.panel_state(panel)
+            .discovered_credentials
+            .iter()
+            .find(|c| c.engine == engine && c.database.as_deref() == Some(name))
+            .map(item_credentials)
+    }
+
+    fn set_db_credentials(
+        &mut self,
+        item_id: Uuid,
+        panel: PanelSide,
+        credentials: Option<DbCredentials>,
+    ) {
+        if let Some(SyncItemKind::Database {
+            source_credentials,
+            dest_credentials,
+            ..
+        }) = self
+            .wizard_items
+            .iter_mut()
+            .find(|i| i.id == item_id)
+            .map(|i| &mut i.kind)
+        {
+            match panel {
+                PanelSide::Source => *source_credentials = credentials,
+                PanelSide::Destination => *dest_credentials = credentials,
+            }
+        }
+    }
+
+    /// Options-step block choosing how each database item logs in on either
+    /// side.
+    pub(super) fn render_database_logins(&self, cx: &mut Context<Self>) -> Option<Div> {
+        let mut block = div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(8.0))
+            .border_t_1()
+            .border_color(ShellDeckColors::border())
+            .child(
+                div()
+                    .text_size(px(13.0))
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(t!("sync.db.logins").to_string()),
+            );
+        let mut any = false;
+        for item in &self.wizard_items {
+            let SyncItemKind::Database {
+                name,
+                engine,
+                source_credentials,
+                dest_credentials,
+            } = &item.kind
+            else {
+                continue;
+            };
+            any = true;
+            block = block.child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(
+                        div()
+                            .text_size(px(12.0))
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(item.kind.label()),
+                    )
+                    .child(self.render_login_choice(
+                        item.id,
+                        name,
+                        *engine,
+                        PanelSide::Source,
+                        source_credentials.as_ref(),
+                        cx,
+                    ))
+                    .child(self.render_login_choice(
+                        item.id,
+                        name,
+                        *engine,
+                        PanelSide::Destination,
+                        dest_credentials.as_ref(),
+                        cx,
+                    )),
+            );
+        }
+        if any {
+            block = block.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("sync.db.keychain_hint").to_string()),
+            );
+        }
+        any.then_some(block)
+    }
+
+    fn render_login_choice(
+        &self,
+        item_id: Uuid,
+        name: &str,
+        engine: DatabaseEngine,
+        panel: PanelSide,
+        current: Option<&DbCredentials>,
+        cx: &mut Context<Self>,
+    ) -> Div {
+        let side = match panel {
+            PanelSide::Source => "src",
+            PanelSide::Destination => "dst",
+        };
+        let mut chips = div().flex().flex_wrap().gap(px(4.0)).child(login_chip(
+            SharedString::from(format!("db-login-{}-{}-default", item_id, side)),
+            t!("sync.db.default_login").to_string(),
+            current.is_none(),
+            cx.listener(move |this, _, _, cx| {
+                this.set_db_credentials(item_id, panel, None);
+                cx.notify();
+            }),
+        ));
+        let discovered = self
+            .panel_state(panel)
+            .discovered_credentials
+            .iter()
+            .filter(|c| c.engine == engine);
+        for (i, found) in discovered.enumerate() {
+            let selected = current.is_some_and(|c| same_login(c, &found.credentials));
+            let label = match &found.credentials.origin {
+                Some(origin) => format!("{} · {}", found.credentials.label(), origin),
+                None => found.credentials.label(),
+            };
+            let label = match &found.database {
+                Some(db) if db != name => format!("{} ({})", label, db),
+                _ => label,
+            };
+            let credentials = item_credentials(found);
+            chips = chips.child(login_chip(
+                SharedString::from(format!("db-login-{}-{}-{}", item_id, side, i)),
+                label,
+                selected,
+                cx.listener(move |this, _, _, cx| {
+                    this.set_db_credentials(item_id, panel, Some(credentials.clone()));
+                    cx.notify();
+                }),
+            ));
+        }
+
+        let label = match panel {
+            PanelSide::Source => t!("sync.db.source_login"),
+            PanelSide::Destination => t!("sync.db.dest_login"),
+        };
+        div()
+            .flex()
+            .items_start()
+            .gap(px(8.0))
+            .child(
+                div()
+                    .w(px(90.0))
+                    .flex_shrink_0()
+                    .pt(px(3.0))
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(label.to_string()),
+            )
+            .child(chips)
+    }
+}
+
+fn login_chip(
+    id: SharedString,
+    label: String,
+    selected: bool,
+    on_click: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+) -> Stateful<Div> {
+    div()
+        .id(ElementId::from(id))
+        .px(px(8.0))
+        .py(px(3.0))
+        .rounded(px(6.0))
+        .text_size(px(11.0))
+        .cursor_pointer()
+        .border_1()
+        .when(selected, |el| {
+            el.bg(ShellDeckColors::primary().opacity(0.2))
+                .text_color(ShellDeckColors::primary())
+                .border_color(ShellDeckColors::primary())
+        })
+        .when(!selected, |el| {
+            el.bg(ShellDeckColors::bg_primary())
+                .text_color(ShellDeckColors::text_muted())
+                .border_color(ShellDeckColors::border())
+        })
+        .on_click(on_click)
+        .child(label)
+}
+
+/// A copy of discovered credentials for one item. Each item gets its own
+/// id, so its keychain entry goes away with the item.
+fn item_credentials(found: &DiscoveredCredentials) -> DbCredentials {
+    DbCredentials {
+        id: Uuid::new_v4(),
+        ..found.credentials.clone()
+    }
+}
+
+fn same_login(a: &DbCredentials, b: &DbCredentials) -> bool {
+    a.user == b.user
+        && a.host == b.host
+        && a.port == b.port
+        && a.socket == b.socket
+        && a.origin == b.origin
+}
+
+/// Review-step line naming the logins of a database item.
+pub(super) fn database_review_line(kind: &SyncItemKind) -> Option<String> {
+    let SyncItemKind::Database {
+        source_credentials,
+        dest_credentials,
+        ..
+    } = kind
+    else {
+        return None;
+    };
+    let login = |creds: &Option<DbCredentials>| match creds {
+        Some(creds) => creds.label(),
+        None => t!("sync.db.default_login").to_string(),
+    };
+    Some(
+        t!(
+            "sync.db.review",
+            source = login(source_credentials).as_str(),
+            dest = login(dest_credentials).as_str()
+        )
+        .to_string(),
+    )
+}
@@ -0,0 +1,985 @@
+use super::*;
+use adabraka_ui::components::input::{Input, InputSize};
+use shelldeck_core::models::file_transfer::{self, FileLocation, TransferKind, TransferStatus};
+
+/// Failed files listed under a directory in the queue before the rest
+/// are summed up.
+const FAILED_FILES_SHOWN: usize = 5;
+
+/// A file panel action waiting for its input or confirmation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum FilePrompt {
+    Rename { side: PanelSide, path: String },
+    Mkdir { side: PanelSide },
+    Chmod { side: PanelSide, paths: Vec<String> },
+    Delete { side: PanelSide, paths: Vec<String> },
+}
+
+impl FilePrompt {
+    fn side(&self) -> PanelSide {
+        match self {
+            FilePrompt::Rename { side, .. }
+            | FilePrompt::Mkdir { side }
+            | FilePrompt::Chmod { side, .. }
+            | FilePrompt::Delete { side, .. } => *side,
+        }
+    }
+}
+
+/// State of the panels' file manager: prompts and the transfer queue the
+/// workspace runs.
+pub(super) struct FileManagerState {
+    pub(super) transfers: TransferQueue,
+    prompt: Option<FilePrompt>,
+    input: Entity<InputState>,
+    chmod_recursive: bool,
+    /// Whether the next conflict choice also covers the later ones.
+    apply_to_all: bool,
+    error: Option<String>,
+}
+
+impl FileManagerState {
+    pub(super) fn new(cx: &mut Context<ServerSyncView>) -> Self {
+        Self {
+            transfers: TransferQueue::default(),
+            prompt: None,
+            input: cx.new(InputState::new),
+            chmod_recursive: false,
+            apply_to_all: false,
+            error: None,
+        }
+    }
+}
+
+fn other_side(side: PanelSide) -> PanelSide {
+    match side {
+        PanelSide::Source => PanelSide::Destination,
+        PanelSide::Destination => PanelSide::Source,
+    }
+}
+
+/// The panel connection id as a transfer location; the local machine
+/// sentinel is this computer.
+fn location(connection_id: Uuid, path: impl Into<String>) -> FileLocation {
+    if connection_id == LOCAL_MACHINE_ID {
+        FileLocation::local(path)
+    } else {
+        FileLocation::remote(connection_id, path)
+    }
+}
+
+fn chip(
+    id: impl Into<SharedString>,
+    label: String,
+    enabled: bool,
+    color: Hsla,
+    on_click: impl Fn(&ClickEvent, &mut Window, &mut App) + 'static,
+) -> Stateful<Div> {
+    div()
+        .id(ElementId::from(id.into()))
+        .px(px(8.0))
+        .py(px(3.0))
+        .rounded(px(4.0))
+        .border_1()
+        .text_size(px(11.0))
+        .flex_shrink_0()
+        .when(enabled, |el| {
+            el.border_color(color.opacity(0.4))
+                .text_color(color)
+                .cursor_pointer()
+                .hover(|el| el.bg(color.opacity(0.1)))
+                .on_click(on_click)
+        })
+        .when(!enabled, |el| {
+            el.border_color(ShellDeckColors::border())
+                .text_color(ShellDeckColors::text_muted().opacity(0.5))
+        })
+        .child(label)
+}
+
+impl ServerSyncView {
+    pub fn set_transfers(&mut self, transfers: TransferQueue) {
+        self.file_manager.transfers = transfers;
+    }
+
+    fn selected_entries(&self, side: PanelSide) -> Vec<&FileEntry> {
+        let state = self.panel_state(side);
+        state
+            .file_entries
+            .iter()
+            .filter(|e| state.selected.contains(&e.path))
+            .collect()
+    }
+
+    /// Select a row; with the secondary modifier or shift it is added to
+    /// or taken out of the selection. A double click opens a directory.
+    pub(super) fn click_file_entry(
+        &mut self,
+        side: PanelSide,
+        path: String,
+        is_dir: bool,
+        event: &ClickEvent,
+        cx: &mut Context<Self>,
+    ) {
+        let state = self.panel_state_mut(side);
+        if event.click_count() >= 2 {
+            if let (true, Some(conn_id)) = (is_dir, state.connection_id) {
+                state.path_history.push(state.current_path.clone());
+                state.files_loading = true;
+                cx.emit(ServerSyncEvent::ListFiles {
+                    connection_id: conn_id,
+                    path,
+                    panel: side,
+                });
+            }
+        } else if event.modifiers().secondary() || event.modifiers().shift {
+            if !state.selected.remove(&path) {
+                state.selected.insert(path);
+            }
+        } else {
+            state.selected.clear();
+            state.selected.insert(path);
+        }
+        cx.notify();
+    }
+
+    /// Copy or move the selection of `side` into the directory the other
+    /// panel shows.
+    fn queue_transfer(&mut self, side: PanelSide, kind: TransferKind, cx: &mut Context<Self>) {
+        let target = self.panel_state(other_side(side));
+        let (Some(source_id), Some(target_id)) =
+            (self.panel_state(side).connection_id, target.connection_id)
+        else {
+            return;
+        };
+        let dir = location(target_id, target.current_path.clone());
+        let items: Vec<TransferItem> = self
+            .selected_entries(side)
+            .into_iter()
+            .map(|entry| {
+                TransferItem::new(
+                    kind,
+                    location(source_id, entry.path.clone()),
+                    dir.with_path(dir.join(&entry.name)),
+                    entry.is_dir,
+                    entry.size,
+                )
+            })
+            // Copying into the directory it is already in would only make
+            // "name (1)" copies through the conflict prompt; skip the no-op.
+            .filter(|item| item.source != item.dest)
+            .collect();
+        self.panel_state_mut(side).selected.clear();
+        if !items.is_empty() {
+            cx.emit(ServerSyncEvent::QueueTransfers(items));
+        }
+        cx.notify();
+    }
+
+    /// Copy files dropped from the OS into the directory `side` shows.
+    pub(super) fn queue_dropped_paths(
+        &mut self,
+        side: PanelSide,
+        paths: Vec<std::path::PathBuf>,
+        cx: &mut Context<Self>,
+    ) {
+        let state = self.panel_state(side);
+        let Some(target_id) = state.connection_id else {
+            return;
+        };
+        let dir = location(target_id, state.current_path.clone());
+        let items: Vec<TransferItem> = paths
+            .into_iter()
+            .filter_map(|path| {
+                let meta = std::fs::metadata(&path).ok()?;
+                let source = FileLocation::local(path.to_string_lossy().into_owned());
+                let dest = dir.with_path(dir.join(&source.file_name()));
+                let size = if meta.is_dir() { 0 } else { meta.len() };
+                Some(TransferItem::new(
+                    TransferKind::Copy,
+                    source,
+                    dest,
+                    meta.is_dir(),
+                    size,
+                ))
+            })
+            .filter(|item| item.source != item.dest)
+            .collect();
+        if !items.is_empty() {
+            cx.emit(ServerSyncEvent::QueueTransfers(items));
+        }
+    }
+
+    fn open_file_prompt(&mut self, prompt: FilePrompt, value: String, cx: &mut Context<Self>) {
+        let fm = &mut self.file_manager;
+        fm.prompt = Some(prompt);
+        fm.error = None;
+        fm.chmod_recursive = false;
+        fm.input
+            .update(cx, |state, cx| state.replace_content(value, cx));
+        cx.notify();
+    }
+
+    /// Turn the open prompt into an operation for the workspace, or say
+    /// what is wrong with the input.
+    fn submit_file_prompt(&mut self, cx: &mut Context<Self>) {
+        let Some(prompt) = self.file_manager.prompt.clone() else {
+            return;
+        };
+        let state = self.panel_state(prompt.side());
+        let Some(connection_id) = state.connection_id else {
+            return;
+        };
+        let dir = location(connection_id, state.current_path.clone());
+        let value = self
+            .file_manager
+            .input
+            .read(cx)
+            .content()
+            .trim()
+            .to_string();
+        let valid_name =
+            !value.is_empty() && value != "." && value != ".." && !value.contains(['/', '\\']);
+
+        let operation = match prompt {
+            FilePrompt::Rename { path, .. } => {
+                if !valid_name {
+                    self.file_manager.error = Some(t!("sync.files.error.name").to_string());
+                    cx.notify();
+                    return;
+                }
+                let to = if dir.is_local() {
+                    std::path::Path::new(&path)
+                        .with_file_name(&value)
+                        .to_string_lossy()
+                        .into_owned()
+                } else {
+                    file_transfer::join_remote(&file_transfer::remote_parent(&path), &value)
+                };
+                FileOperation::Rename { from: path, to }
+            }
+            FilePrompt::Mkdir { .. } => {
+                if !valid_name {
+                    self.file_manager.error = Some(t!("sync.files.error.name").to_string());
+                    cx.notify();
+                    return;
+                }
+                FileOperation::Mkdir {
+                    path: dir.join(&value),
+                }
+            }
+            FilePrompt::Chmod { paths, .. } => {
+                if !file_transfer::valid_chmod_mode(&value) {
+                    self.file_manager.error = Some(t!("sync.files.error.mode").to_string());
+                    cx.notify();
+                    return;
+                }
+                FileOperation::Chmod {
+                    paths,
+                    mode: value,
+                    recursive: self.file_manager.chmod_recursive,
+                }
+            }
+            FilePrompt::Delete { paths, .. } => FileOperation::Delete { paths },
+        };
+        self.panel_state_mut(prompt.side()).selected.clear();
+        self.file_manager.prompt = None;
+        self.file_manager.error = None;
+        cx.emit(ServerSyncEvent::FileOperation {
+            connection_id,
+            operation,
+        });
+        cx.notify();
+    }
+
+    /// Copy / move / rename / delete / new folder / chmod bar of a panel.
+    pub(super) fn render_file_actions(&self, side: PanelSide, cx: &mut Context<Self>) -> Div {
+        let state = self.panel_state(side);
+        let selected = self.selected_entries(side);
+        let count = selected.len();
+        let target_ready = self.panel_state(other_side(side)).connection_id.is_some();
+        let arrow = match side {
+            PanelSide::Source => "→",
+            PanelSide::Destination => "←",
+        };
+        let rename_path = (count == 1).then(|| selected[0].path.clone());
+        let chmod_value = selected
+            .first()
+            .and_then(|e| file_transfer::permissions_to_octal(&e.permissions))
+            .unwrap_or_default();
+        let selected_paths: Vec<String> = selected.iter().map(|e| e.path.clone()).collect();
+        let primary = ShellDeckColors::primary();
+        let muted = ShellDeckColors::text_muted();
+
+        let mut bar = div()
+            .flex()
+            .flex_wrap()
+            .items_center()
+            .gap(px(4.0))
+            .px(px(10.0))
+            .pb(px(6.0))
+            .child(chip(
+                format!("fm-copy-{:?}", side),
+                format!("{} {}", t!("sync.files.copy"), arrow),
+                count > 0 && target_ready,
+                primary,
+                cx.listener(move |this, _, _, cx| {
+                    this.queue_transfer(side, TransferKind::Copy, cx);
+                }),
+            ))
+            .child(chip(
+                format!("fm-move-{:?}", side),
+                format!("{} {}", t!("sync.files.move"), arrow),
+                count > 0 && target_ready,
+                primary,
+                cx.listener(move |this, _, _, cx| {
+                    this.queue_transfer(side, TransferKind::Move, cx);
+                }),
+            ))
+            .child(chip(
+                format!("fm-edit-{:?}", side),
+                t!("sync.files.edit").to_string(),
+                count <= 1,
+                muted,
+                cx.listener(move |this, _, _, cx| {
+                    // The selected file or folder, else the folder shown.
+                    let state = this.panel_state(side);
+                    let Some(connection_id) = state.connection_id else {
+                        return;
+                    };
+                    let (path, is_dir) = match this.selected_entries(side).first() {
+                        Some(entry) => (entry.path.clone(), entry.is_dir),
+                        None => (state.current_path.clone(), true),
+                    };
+                    cx.emit(ServerSyncEvent::OpenInEditor {
+                        connection_id,
+                        path,
+                        is_dir,
+                    });
+                }),
+            ))
+            .child(chip(
+                format!("fm-rename-{:?}", side),
+                t!("sync.files.rename").to_string(),
+                rename_path.is_some(),
+                muted,
+                cx.listener(move |this, _, _, cx| {
+                    let conn_id = this.panel_state(side).connection_id;
+                    if let (Some(path), Some(conn_id)) = (rename_path.clone(), conn_id) {
+                        let name = location(conn_id, path.clone()).file_name();
+                        this.open_file_prompt(FilePrompt::Rename { side, path }, name, cx);
+                    }
+                }),
+            ))
+            .child(chip(
+                format!("fm-chmod-{:?}", side),
+                t!("sync.files.chmod").to_string(),
+                count > 0,
+                muted,
+                cx.listener({
+                    let paths = selected_paths.clone();
+                    move |this, _, _, cx| {
+                        let prompt = FilePrompt::Chmod {
+                            side,
+                            paths: paths.clone(),
+                        };
+                        this.open_file_prompt(prompt, chmod_value.clone(), cx);
+                    }
+                }),
+            ))
+            .child(chip(
+                format!("fm-mkdir-{:?}", side),
+                t!("sync.files.mkdir").to_string(),
+                true,
+                muted,
+                cx.listener(move |this, _, _, cx| {
+                    this.open_file_prompt(FilePrompt::Mkdir { side }, String::new(), cx);
+                }),
+            ))
+            .child(chip(
+                format!("fm-delete-{:?}", side),
+                t!("sync.files.delete").to_string(),
+                count > 0,
+                ShellDeckColors::error(),
+                cx.listener(move |this, _, _, cx| {
+                    let prompt = FilePrompt::Delete {
+                        side,
+                        paths: selected_paths.clone(),
+                    };
+                    this.open_file_prompt(prompt, String::new(), cx);
+                }),
+            ))
+            .child(chip(
+                format!("fm-refresh-{:?}", side),
+                t!("sync.files.refresh").to_string(),
+                true,
+                muted,
+                cx.listener(move |this, _, _, cx| {
+                    let state = this.panel_state_mut(side);
+                    if let Some(conn_id) = state.connection_id {
+                        state.files_loading = true;
+                        cx.emit(ServerSyncEvent::ListFiles {
+                            connection_id: conn_id,
+                            path: state.current_path.clone(),
+                            panel: side,
+                        });
+                        cx.notify();
+                    }
+                }),
+            ));
+        if count > 0 {
+            bar = bar.child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("sync.files.selected", count = count).to_string()),
+            );
+        }
+        if state.is_local {
+            bar = bar.child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(ShellDeckColors::text_muted().opacity(0.7))
+                    .child(t!("sync.files.drop_hint").to_string()),
+            );
+        }
+        bar
+    }
+
+    /// Input or confirmation of the action opened on `side`.
+    pub(super) fn render_file_prompt(
+        &self,
+        side: PanelSide,
+        cx: &mut Context<Self>,
+    ) -> Option<Div> {
+        let fm = &self.file_manager;
+        let prompt = fm.prompt.as_ref().filter(|p| p.side() == side)?;
+        let (title, input) = match prompt {
+            FilePrompt::Rename { path, .. } => (
+                t!("sync.files.rename_title", name = path.as_str()).to_string(),
+                true,
+            ),
+            FilePrompt::Mkdir { .. } => (t!("sync.files.mkdir_title").to_string(), true),
+            FilePrompt::Chmod { paths, .. } => (
+                t!("sync.files.chmod_title", count = paths.len()).to_string(),
+                true,
+            ),
+            FilePrompt::Delete { paths, .. } => (
+                t!("sync.files.delete_title", count = paths.len()).to_string(),
+                false,
+            ),
+        };
+        let is_delete = matches!(prompt, FilePrompt::Delete { .. });
+        let is_chmod = matches!(prompt, FilePrompt::Chmod { .. });
+
+        let mut block = div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .mx(px(10.0))
+            .mb(px(6.0))
+            .p(px(8.0))
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(if is_delete {
+                ShellDeckColors::error().opacity(0.4)
+            } else {
+                ShellDeckColors::border()
+            })
+            .bg(bg_secondary())
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(title),
+            );
+        if input {
+            let placeholder = if is_chmod {
+                t!("sync.files.mode_placeholder")
+            } else {
+                t!("sync.files.name_placeholder")
+            };
+            block = block.child(
+                Input::new(&fm.input)
+                    .size(InputSize::Sm)
+                    .placeholder(placeholder.to_string()),
+            );
+        }
+        if is_chmod {
+            let recursive = fm.chmod_recursive;
+            block = block.child(
+                div()
+                    .id(ElementId::from(SharedString::from(format!(
+                        "fm-chmod-recursive-{:?}",
+                        side
+                    ))))
+                    .text_size(px(11.0))
+                    .cursor_pointer()
+                    .text_color(if recursive {
+                        ShellDeckColors::primary()
+                    } else {
+                        ShellDeckColors::text_muted()
+                    })
+                    .on_click(cx.listener(|this, _, _, cx| {
+                        this.file_manager.chmod_recursive = !this.file_manager.chmod_recursive;
+                        cx.notify();
+                    }))
+                    .child(format!(
+                        "{} {}",
+                        if recursive { "☑" } else { "☐" },
+                        t!("sync.files.recursive")
+                    )),
+            );
+        }
+        if let Some(error) = &fm.error {
+            block = block.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::error())
+                    .child(error.clone()),
+            );
+        }
+        block = block.child(
+            div()
+                .flex()
+                .items_center()
+                .gap(px(6.0))
+                .child(chip(
+                    format!("fm-prompt-ok-{:?}", side),
+                    if is_delete {
+                        t!("sync.files.confirm_delete").to_string()
+                    } else {
+                        t!("sync.files.apply").to_string()
+                    },
+                    true,
+                    if is_delete {
+                        ShellDeckColors::error()
+                    } else {
+                        ShellDeckColors::primary()
+                    },
+                    cx.listener(|this, _, _, cx| this.submit_file_prompt(cx)),
+                ))
+                .child(chip(
+                    format!("fm-prompt-cancel-{:?}", side),
+                    t!("sync.cancel").to_string(),
+                    true,
+                    ShellDeckColors::text_muted(),
+                    cx.listener(|this, _, _, cx| {
+                        this.file_manager.prompt = None;
+                        this.file_manager.error = None;
+                        cx.notify();
+                    }),
+                )),
+        );
+        Some(block)
+    }
+
+    fn location_label(&self, location: &FileLocation) -> String {
+        let host = match location.connection_id {
+            None => local_machine_label(),
+            Some(id) => self
+                .connections
+                .iter()
+                .find(|c| c.id == id)
+                .map(|c| c.display_name().to_string())
+                .unwrap_or_else(|| t!("sync.files.unknown_server").to_string()),
+        };
+        format!("{}:{}", host, location.path)
+    }
+
+    /// Transfer queue under the panels: conflicts waiting for a choice,
+    /// then every item with its progress and controls.
+    pub(super) fn render_transfer_queue(&self, cx: &mut Context<Self>) -> Option<Div> {
+        let queue = &self.file_manager.transfers;
+        if queue.items.is_empty() {
+            return None;
+        }
+        let top: Vec<&TransferItem> = queue.items.iter().filter(|i| i.parent.is_none()).collect();
+
+        let mut panel = div()
+            .id("fm-transfer-queue")
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .w_full()
+            .max_h(px(220.0))
+            .overflow_y_scroll()
+            .px(px(12.0))
+            .py(px(8.0))
+            .border_t_1()
+            .border_color(ShellDeckColors::border())
+            .bg(bg_secondary())
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(t!("sync.files.queue", count = top.len()).to_string()),
+                    )
+                    .child(chip(
+                        "fm-clear-finished",
+                        t!("sync.files.clear_finished").to_string(),
+                        top.iter().any(|i| i.status.is_finished()),
+                        ShellDeckColors::text_muted(),
+                        cx.listener(|_, _, _, cx| cx.emit(ServerSyncEvent::ClearTransfers)),
+                    )),
+            );
+
+        for item in top.iter().filter(|i| i.status == TransferStatus::Conflict) {
+            panel = panel.child(self.render_conflict(item, cx));
+        }
+        for item in top {
+            panel = panel.child(self.render_transfer_item(item, cx));
+        }
+        Some(div().child(panel))
+    }
+
+    fn render_conflict(&self, item: &TransferItem, cx: &mut Context<Self>) -> Div {
+        let id = item.id;
+        let apply_to_all = self.file_manager.apply_to_all;
+        let choose = |key: &str, label: String, choice: ConflictChoice, cx: &mut Context<Self>| {
+            chip(
+                format!("fm-conflict-{}-{}", key, id),
+                label,
+                true,
+                ShellDeckColors::primary(),
+                cx.listener(move |this, _, _, cx| {
+                    let apply_to_all = this.file_manager.apply_to_all;
+                    this.file_manager.apply_to_all = false;
+                    cx.emit(ServerSyncEvent::ResolveConflict {
+                        id,
+                        choice,
+                        apply_to_all,
+                    });
+                    cx.notify();
+                }),
+            )
+        };
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .p(px(8.0))
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(ShellDeckColors::warning().opacity(0.5))
+            .bg(ShellDeckColors::warning().opacity(0.08))
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(
+                        t!(
+                            "sync.files.conflict",
+                            name = item.name().as_str(),
+                            dest = self.location_label(&item.dest).as_str()
+                        )
+                        .to_string(),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .flex_wrap()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(choose(
+                        "overwrite",
+                        t!("sync.files.overwrite").to_string(),
+                        ConflictChoice::Overwrite,
+                        cx,
+                    ))
+                    .child(choose(
+                        "skip",
+                        t!("sync.files.skip").to_string(),
+                        ConflictChoice::Skip,
+                        cx,
+                    ))
+                    .child(choose(
+                        "keep-both",
+                        t!("sync.files.keep_both").to_string(),
+                        ConflictChoice::KeepBoth,
+                        cx,
+                    ))
+                    .child(
+                        div()
+                            .id(ElementId::from(SharedString::from(format!(
+                                "fm-conflict-all-{}",
+                                id
+                            ))))
+                            .text_size(px(11.0))
+                            .cursor_pointer()
+                            .text_color(if apply_to_all {
+                                ShellDeckColors::primary()
+                            } else {
+                                ShellDeckColors::text_muted()
+                            })
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.file_manager.apply_to_all = !this.file_manager.apply_to_all;
+                                cx.notify();
+                            }))
+                            .child(format!(
+                                "{} {}",
+                                if apply_to_all { "☑" } else { "☐" },
+                                t!("sync.files.apply_to_all")
+                            )),
+                    ),
+            )
+    }
+
+    fn render_transfer_item(&self, item: &TransferItem, cx: &mut Context<Self>) -> Div {
+        let queue = &self.file_manager.transfers;
+        let id = item.id;
+        let (done, total) = queue.totals(id);
+        let fraction = if total > 0 {
+            (done as f32 / total as f32).clamp(0.0, 1.0)
+        } else if item.status == TransferStatus::Done {
+            1.0
+        } else {
+            0.0
+        };
+        let (status_text, status_color) = match &item.status {
+            TransferStatus::Queued => (
+                t!("sync.files.status.queued").to_string(),
+                ShellDeckColors::text_muted(),
+            ),
+            TransferStatus::Running => (
+                t!(
+                    "sync.progress.percent",
+                    pct = (fraction * 100.0).round() as u32
+                )
+                .to_string(),
+                ShellDeckColors::primary(),
+            ),
+            TransferStatus::Paused => (
+                t!("sync.files.status.paused").to_string(),
+                ShellDeckColors::warning(),
+            ),
+            TransferStatus::Conflict => (
+                t!("sync.files.status.conflict").to_string(),
+                ShellDeckColors::warning(),
+            ),
+            TransferStatus::Done => (
+                t!("sync.files.status.done").to_string(),
+                ShellDeckColors::success(),
+            ),
+            TransferStatus::Skipped => (
+                t!("sync.files.status.skipped").to_string(),
+                ShellDeckColors::text_muted(),
+            ),
+            TransferStatus::Failed(_) => (
+                t!("sync.files.status.failed").to_string(),
+                ShellDeckColors::error(),
+            ),
+        };
+        let verb = match item.kind {
+            TransferKind::Copy => t!("sync.files.copy"),
+            TransferKind::Move => t!("sync.files.move"),
+        };
+        let sizes = if total > 0 {
+            format!("{} / {}", format_bytes(done), format_bytes(total))
+        } else {
+            format_bytes(done)
+        };
+
+        let mut controls = div().flex().items_center().gap(px(4.0)).flex_shrink_0();
+        let control = |key: &str, label: String, event: ServerSyncEvent, cx: &mut Context<Self>| {
+            chip(
+                format!("fm-{}-{}", key, id),
+                label,
+                true,
+                ShellDeckColors::text_muted(),
+                cx.listener(move |_, _, _, cx| cx.emit(event.clone())),
+            )
+        };
+        match item.status {
+            TransferStatus::Queued | TransferStatus::Running => {
+                controls = controls.child(control(
+                    "pause",
+                    t!("sync.files.pause").to_string(),
+                    ServerSyncEvent::PauseTransfer(id),
+                    cx,
+                ));
+            }
+            TransferStatus::Paused => {
+                controls = controls.child(control(
+                    "resume",
+                    t!("sync.files.resume").to_string(),
+                    ServerSyncEvent::ResumeTransfer(id),
+                    cx,
+                ));
+            }
+            TransferStatus::Failed(_) => {
+                controls = controls.child(control(
+                    "retry",
+                    t!("sync.files.retry").to_string(),
+                    ServerSyncEvent::RetryTransfer(id),
+                    cx,
+                ));
+            }
+            _ => {}
+        }
+        controls = controls.child(control(
+            "remove",
+            "✕".to_string(),
+            ServerSyncEvent::CancelTransfer(id),
+            cx,
+        ));
+
+        let mut row = div()
+            .flex()
+            .flex_col()
+            .gap(px(3.0))
+            .py(px(4.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border().opacity(0.5))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .flex_grow()
+                            .min_w(px(0.0))
+                            .overflow_hidden()
+                            .child(
+                                div()
+                                    .text_size(px(12.0))
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .truncate()
+                                    .child(format!(
+                                        "{} {} {}",
+                                        verb,
+                                        if item.is_dir { "📁" } else { "📄" },
+                                        item.name()
+                                    )),
+                            )
+                            .child(
+                                div()
+                                    .text_size(px(10.0))
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .truncate()
+                                    .child(format!(
+                                        "{} → {}",
+                                        self.location_label(&item.source),
+                                        self.location_label(&item.dest)
+                                    )),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .w(px(120.0))
+                            .flex_shrink_0()
+                            .text_size(px(10.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(sizes),
+                    )
+                    .child(
+                        div()
+                            .w(px(70.0))
+                            .flex_shrink_0()
+                            .text_size(px(10.0))
+                            .text_color(status_color)
+                            .child(status_text),
+                    )
+                    .child(controls),
+            )
+            .child(
+                div()
+                    .w_full()
+                    .h(px(3.0))
+                    .rounded(px(2.0))
+                    .bg(bg_tertiary())
+                    .child(
+                        div()
+                            .h_full()
+                            .rounded(px(2.0))
+                            .w(relative(fraction))
+                            .bg(status_color),
+                    ),
+            );
+
+        if let TransferStatus::Failed(error) = &item.status {
+            row = row.child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(ShellDeckColors::error())
+                    .child(error.clone()),
+            );
+        }
+        // Files of a directory: the one in flight and those that failed.
+        if let Some(current) = queue
+            .children(id)
+            .find(|c| c.status == TransferStatus::Running)
+        {
+            row = row.child(
+                div()
+                    .pl(px(16.0))
+                    .text_size(px(10.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .truncate()
+                    .child(format!(
+                        "{} · {} / {}",
+                        current.source.path,
+                        format_bytes(current.transferred),
+                        format_bytes(current.size_bytes)
+                    )),
+            );
+        }
+        let failed: Vec<&TransferItem> = queue
+            .children(id)
+            .filter(|c| matches!(c.status, TransferStatus::Failed(_)))
+            .collect();
+        for child in failed.iter().take(FAILED_FILES_SHOWN) {
+            if let TransferStatus::Failed(error) = &child.status {
+                row = row.child(
+                    div()
+                        .pl(px(16.0))
+                        .text_size(px(10.0))
+                        .text_color(ShellDeckColors::error())
+                        .truncate()
+                        .child(format!("{}: {}", child.source.path, error)),
+                );
+            }
+        }
+        if failed.len() > FAILED_FILES_SHOWN {
+            row = row.child(
+                div()
+                    .pl(px(16.0))
+                    .text_size(px(10.0))
+                    .text_color(ShellDeckColors::error())
+                    .child(
+                        t!(
+                            "sync.files.more_failed",
+                            count = failed.len() - FAILED_FILES_SHOWN
+                        )
+                        .to_string(),
+                    ),
+            );
+        }
+        row
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit_idx = 0;
+    while value >= 1024.0 && unit_idx < units.len() - 1 {
+        value /= 1024.0;
+        unit_idx += 1;
+    }
+    if unit_idx == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, units[unit_idx])
+    }
+}
@@ -0,0 +1,421 @@
+use super::*;
+use shelldeck_core::ai::AiDiffLine;
+use shelldeck_core::models::sync_plan::{plan_rows, ItemPlan, PlanAction, PlanRow, PlanTarget};
+
+/// Identifies one entry of the review plan: item, section, entry index.
+pub(super) type PlanEntryKey = (Uuid, usize, usize);
+
+impl ServerSyncView {
+    /// Ask the workspace for a fresh plan of the current wizard selection.
+    pub(super) fn request_sync_plan(&mut self, cx: &mut Context<Self>) {
+        let Some(profile) = self.wizard_profile() else {
+            return;
+        };
+        let request_id = Uuid::new_v4();
+        self.sync_plans.clear();
+        self.plan_request = Some(request_id);
+        self.plan_collapsed.clear();
+        self.plan_diff_open = None;
+        cx.emit(ServerSyncEvent::PlanSync {
+            request_id,
+            profile,
+        });
+    }
+
+    /// Store a finished plan unless the review it was made for was left.
+    pub fn set_sync_plans(&mut self, request_id: Uuid, plans: Vec<ItemPlan>) {
+        if self.plan_request == Some(request_id) {
+            self.plan_request = None;
+            self.sync_plans = plans;
+        }
+    }
+
+    fn toggle_plan_entry(&mut self, (item_id, section, index): PlanEntryKey) {
+        if let Some(entry) = self
+            .sync_plans
+            .iter_mut()
+            .find(|p| p.item_id == item_id)
+            .and_then(|p| p.sections.get_mut(section))
+            .and_then(|s| s.entries.get_mut(index))
+        {
+            entry.excluded = !entry.excluded;
+        }
+    }
+
+    /// Untick every entry under `dir`, or tick them all again when any of
+    /// them is already unticked.
+    fn toggle_plan_dir(&mut self, item_id: Uuid, section: usize, dir: &str) {
+        let prefix = format!("{}/", dir);
+        let Some(section) = self
+            .sync_plans
+            .iter_mut()
+            .find(|p| p.item_id == item_id)
+            .and_then(|p| p.sections.get_mut(section))
+        else {
+            return;
+        };
+        let mut under: Vec<_> = section
+            .entries
+            .iter_mut()
+            .filter(|e| e.path.starts_with(&prefix))
+            .collect();
+        let exclude = under.iter().all(|e| !e.excluded);
+        for entry in under.iter_mut() {
+            entry.excluded = exclude;
+        }
+    }
+
+    /// Per-item plan block for the review step.
+    pub(super) fn render_item_plan(&self, item: &SyncItem, cx: &mut Context<Self>) -> Div {
+        let mut block = div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .px(px(8.0))
+            .py(px(6.0))
+            .rounded(px(6.0))
+            .bg(bg_secondary());
+
+        let plan = self.sync_plans.iter().find(|p| p.item_id == item.id);
+        let summary = match plan {
+            _ if matches!(item.kind, SyncItemKind::Database { .. }) => {
+                t!("sync.plan.database").to_string()
+            }
+            None if self.plan_request.is_some() => t!("sync.plan.loading").to_string(),
+            None => String::new(),
+            Some(ItemPlan {
+                error: Some(error), ..
+            }) => t!("sync.plan.error", error = error.as_str()).to_string(),
+            Some(plan) => {
+                let totals = plan.totals();
+                if totals.is_empty() {
+                    t!("sync.plan.no_changes").to_string()
+                } else {
+                    t!(
+                        "sync.plan.summary",
+                        create = totals.create,
+                        update = totals.update,
+                        delete = totals.delete,
+                        size = plan_size(totals.bytes).as_str()
+                    )
+                    .to_string()
+                }
+            }
+        };
+
+        block = block.child(
+            div()
+                .flex()
+                .items_center()
+                .gap(px(8.0))
+                .child(
+                    div()
+                        .w(px(6.0))
+                        .h(px(6.0))
+                        .rounded_full()
+                        .bg(ShellDeckColors::primary()),
+                )
+                .child(
+                    div()
+                        .flex_grow()
+                        .text_size(px(12.0))
+                        .text_color(ShellDeckColors::text_primary())
+                        .child(item.kind.label()),
+                )
+                .child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(ShellDeckColors::text_muted())
+                        .child(summary),
+                ),
+        );
+        if let Some(line) = wordpress::wordpress_review_line(&item.kind)
+            .or_else(|| database::database_review_line(&item.kind))
+        {
+            block = block.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(line),
+            );
+        }
+
+        let Some(plan) = plan else {
+            return block;
+        };
+        for (section_idx, section) in plan.sections.iter().enumerate() {
+            if section.entries.is_empty() {
+                continue;
+            }
+            let heading = match &section.target {
+                PlanTarget::Tree {
+                    source_root,
+                    dest_root,
+                } => format!("{} → {}", source_root, dest_root),
+                PlanTarget::File { path } => path.clone(),
+            };
+            block = block.child(
+                div()
+                    .mt(px(4.0))
+                    .text_size(px(10.0))
+                    .font_family("JetBrains Mono")
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(heading),
+            );
+
+            let mut hidden_under: Option<String> = None;
+            for row in plan_rows(&section.entries) {
+                let (depth, path) = match &row {
+                    PlanRow::Dir { depth, path, .. } => (*depth, path.clone()),
+                    PlanRow::Entry { depth, index, .. } => {
+                        (*depth, section.entries[*index].path.clone())
+                    }
+                };
+                if let Some(ref hidden) = hidden_under {
+                    if path.starts_with(&format!("{}/", hidden)) {
+                        continue;
+                    }
+                    hidden_under = None;
+                }
+                match row {
+                    PlanRow::Dir { name, .. } => {
+                        let collapse_key = format!("{}:{}:{}", plan.item_id, section_idx, path);
+                        let collapsed = self.plan_collapsed.contains(&collapse_key);
+                        if collapsed {
+                            hidden_under = Some(path.clone());
+                        }
+                        let prefix = format!("{}/", path);
+                        let all_excluded = section
+                            .entries
+                            .iter()
+                            .filter(|e| e.path.starts_with(&prefix))
+                            .all(|e| e.excluded);
+                        block = block.child(self.render_plan_dir_row(
+                            (plan.item_id, section_idx),
+                            depth,
+                            name,
+                            path,
+                            collapsed,
+                            all_excluded,
+                            collapse_key,
+                            cx,
+                        ));
+                    }
+                    PlanRow::Entry { name, index, .. } => {
+                        let key = (plan.item_id, section_idx, index);
+                        let entry = &section.entries[index];
+                        let diff_open = self.plan_diff_open == Some(key);
+                        block = block.child(self.render_plan_entry_row(
+                            key,
+                            depth,
+                            name,
+                            entry.action,
+                            entry.size,
+                            entry.excluded,
+                            entry.diff.is_some(),
+                            diff_open,
+                            cx,
+                        ));
+                        if let (true, Some(diff)) = (diff_open, &entry.diff) {
+                            block = block.child(render_plan_diff(diff, depth));
+                        }
+                    }
+                }
+            }
+        }
+        block
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_plan_dir_row(
+        &self,
+        (item_id, section): (Uuid, usize),
+        depth: usize,
+        name: String,
+        path: String,
+        collapsed: bool,
+        excluded: bool,
+        collapse_key: String,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let toggle_path = path.clone();
+        div()
+            .id(ElementId::from(SharedString::from(format!(
+                "plan-dir-{}",
+                collapse_key
+            ))))
+            .flex()
+            .items_center()
+            .gap(px(6.0))
+            .pl(px(depth as f32 * 14.0))
+            .text_size(px(11.0))
+            .child(
+                plan_checkbox(format!("plan-dir-check-{}", collapse_key), !excluded).on_click(
+                    cx.listener(move |this, _, _, cx| {
+                        this.toggle_plan_dir(item_id, section, &toggle_path);
+                        cx.notify();
+                    }),
+                ),
+            )
+            .child(
+                div()
+                    .id(ElementId::from(SharedString::from(format!(
+                        "plan-dir-name-{}",
+                        collapse_key
+                    ))))
+                    .flex()
+                    .items_center()
+                    .gap(px(4.0))
+                    .cursor_pointer()
+                    .text_color(ShellDeckColors::text_primary())
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        if !this.plan_collapsed.remove(&collapse_key) {
+                            this.plan_collapsed.insert(collapse_key.clone());
+                        }
+                        cx.notify();
+                    }))
+                    .child(if collapsed { "▸" } else { "▾" })
+                    .child(format!("{}/", name)),
+            )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_plan_entry_row(
+        &self,
+        key: PlanEntryKey,
+        depth: usize,
+        name: String,
+        action: PlanAction,
+        size: u64,
+        excluded: bool,
+        has_diff: bool,
+        diff_open: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let (marker, color) = match action {
+            PlanAction::Create => ("+", ShellDeckColors::success()),
+            PlanAction::Update => ("~", ShellDeckColors::warning()),
+            PlanAction::Delete => ("−", ShellDeckColors::error()),
+        };
+        let (item_id, section, index) = key;
+        div()
+            .flex()
+            .items_center()
+            .gap(px(6.0))
+            .pl(px(depth as f32 * 14.0))
+            .text_size(px(11.0))
+            .when(excluded, |el| el.opacity(0.5))
+            .child(
+                plan_checkbox(
+                    format!("plan-entry-{}-{}-{}", item_id, section, index),
+                    !excluded,
+                )
+                .on_click(cx.listener(move |this, _, _, cx| {
+                    this.toggle_plan_entry(key);
+                    cx.notify();
+                })),
+            )
+            .child(
+                div()
+                    .w(px(10.0))
+                    .font_family("JetBrains Mono")
+                    .text_color(color)
+                    .child(marker),
+            )
+            .child(
+                div()
+                    .flex_grow()
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(name),
+            )
+            .when(has_diff, |el| {
+                el.child(
+                    div()
+                        .id(ElementId::from(SharedString::from(format!(
+                            "plan-diff-{}-{}-{}",
+                            item_id, section, index
+                        ))))
+                        .cursor_pointer()
+                        .text_color(ShellDeckColors::primary())
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            this.plan_diff_open = if this.plan_diff_open == Some(key) {
+                                None
+                            } else {
+                                Some(key)
+                            };
+                            cx.notify();
+                        }))
+                        .child(if diff_open {
+                            t!("sync.plan.hide_diff").to_string()
+                        } else {
+                            t!("sync.plan.show_diff").to_string()
+                        }),
+                )
+            })
+            .child(
+                div()
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(plan_size(size)),
+            )
+    }
+}
+
+fn plan_checkbox(id: String, checked: bool) -> Stateful<Div> {
+    div()
+        .id(ElementId::from(SharedString::from(id)))
+        .w(px(12.0))
+        .h(px(12.0))
+        .rounded(px(2.0))
+        .border_1()
+        .border_color(ShellDeckColors::border())
+        .cursor_pointer()
+        .flex()
+        .items_center()
+        .justify_center()
+        .when(checked, |el| {
+            el.bg(ShellDeckColors::primary()).child(
+                div()
+                    .text_size(px(8.0))
+                    .text_color(hsla(0.0, 0.0, 1.0, 1.0))
+                    .child("✓"),
+            )
+        })
+}
+
+fn render_plan_diff(diff: &[AiDiffLine], depth: usize) -> impl IntoElement {
+    let mut lines = div()
+        .flex()
+        .flex_col()
+        .ml(px((depth as f32 + 1.0) * 14.0))
+        .p(px(6.0))
+        .rounded(px(4.0))
+        .bg(ShellDeckColors::bg_primary())
+        .font_family("JetBrains Mono")
+        .text_size(px(10.0));
+    for line in diff {
+        let (prefix, text, color) = match line {
+            AiDiffLine::Context(text) => (" ", text, ShellDeckColors::text_muted()),
+            AiDiffLine::Removed(text) => ("-", text, ShellDeckColors::error()),
+            AiDiffLine::Added(text) => ("+", text, ShellDeckColors::success()),
+        };
+        lines = lines.child(
+            div()
+                .text_color(color)
+                .child(format!("{} {}", prefix, text)),
+        );
+    }
+    lines
+}
+
+fn plan_size(bytes: u64) -> String {
+    let size = bytes as f64;
+    if size < 1024.0 {
+        format!("{} B", bytes)
+    } else if size < 1024.0 * 1024.0 {
+        format!("{:.1} KB", size / 1024.0)
+    } else if size < 1024.0 * 1024.0 * 1024.0 {
+        format!("{:.1} MB", size / (1024.0 * 1024.0))
+    } else {
+        format!("{:.2} GB", size / (1024.0 * 1024.0 * 1024.0))
+    }
+}
@@ -0,0 +1,367 @@
+use super::*;
+use adabraka_ui::components::input::{Input, InputSize};
+
+impl ServerSyncView {
+    pub fn set_discovered_wordpress(
+        &mut self,
+        panel: PanelSide,
+        installs: Vec<DiscoveredWordPress>,
+    ) {
+        self.panel_state_mut(panel).discovered_wordpress = installs;
+    }
+
+    /// Drop the WordPress item of a site, if any. The plain nginx item and
+    /// the WordPress item both sync the document root, so only one of the
+    /// two is kept.
+    pub(super) fn remove_wordpress_item(&mut self, server_name: &str) {
+        let wp_url_inputs = &mut self.wp_url_inputs;
+        self.wizard_items.retain(|item| match &item.kind {
+            SyncItemKind::WordPressSite { site, .. } if site.server_name == server_name => {
+                wp_url_inputs.remove(&item.id);
+                false
+            }
+            _ => true,
+        });
+    }
+
+    fn toggle_wordpress_item(
+        &mut self,
+        site: &DiscoveredSite,
+        install: &DiscoveredWordPress,
+        cx: &mut Context<Self>,
+    ) {
+        let selected = self.wizard_items.iter().any(|item| {
+            matches!(&item.kind, SyncItemKind::WordPressSite { site: s, .. } if s.server_name == site.server_name)
+        });
+        if selected {
+            self.remove_wordpress_item(&site.server_name);
+            return;
+        }
+
+        self.wizard_items.retain(|item| {
+            !matches!(&item.kind, SyncItemKind::NginxSite { site: s, .. } if s.server_name == site.server_name)
+        });
+        let kind = self.wordpress_item_kind(site, install);
+        let id = Uuid::new_v4();
+        if let SyncItemKind::WordPressSite { new_url, .. } = &kind {
+            self.wp_url_inputs.insert(id, wp_url_input(new_url, cx));
+        }
+        self.wizard_items.push(SyncItem {
+            id,
+            kind,
+            enabled: true,
+        });
+    }
+
+    /// Defaults for a new WordPress item. The destination database and URL
+    /// come from a WordPress install at the same root on the destination,
+    /// then from a destination site with that root, and otherwise stay as
+    /// on the source.
+    fn wordpress_item_kind(
+        &self,
+        site: &DiscoveredSite,
+        install: &DiscoveredWordPress,
+    ) -> SyncItemKind {
+        let dest_install = self
+            .dest_panel
+            .discovered_wordpress
+            .iter()
+            .find(|wp| wp.root == install.root);
+        let old_url = install.home_url.clone().unwrap_or_else(|| site_url(site));
+        let new_url = dest_install
+            .and_then(|wp| wp.home_url.clone())
+            .or_else(|| {
+                self.dest_panel
+                    .discovered_sites
+                    .iter()
+                    .find(|s| s.root.trim_end_matches('/') == install.root)
+                    .map(site_url)
+            })
+            .unwrap_or_else(|| old_url.clone());
+        SyncItemKind::WordPressSite {
+            site: site.clone(),
+            wp_config_path: install.config_path.clone(),
+            source_db: install.config.clone(),
+            dest_db: dest_install
+                .map(|wp| wp.config.clone())
+                .unwrap_or_else(|| install.config.clone()),
+            old_url,
+            new_url,
+            exclude_patterns: Vec::new(),
+        }
+    }
+
+    /// Give every WordPress item a URL field, including items of a loaded
+    /// profile.
+    pub(super) fn ensure_wordpress_inputs(&mut self, cx: &mut Context<Self>) {
+        for item in &self.wizard_items {
+            if let SyncItemKind::WordPressSite { new_url, .. } = &item.kind {
+                if !self.wp_url_inputs.contains_key(&item.id) {
+                    self.wp_url_inputs
+                        .insert(item.id, wp_url_input(new_url, cx));
+                }
+            }
+        }
+        let ids: HashSet<Uuid> = self.wizard_items.iter().map(|i| i.id).collect();
+        self.wp_url_inputs.retain(|id, _| ids.contains(id));
+    }
+
+    /// Select-items section listing the source sites that run WordPress.
+    pub(super) fn render_wizard_wordpress_sites(&self, cx: &mut Context<Self>) -> Option<Div> {
+        let installs: Vec<(DiscoveredSite, DiscoveredWordPress)> = self
+            .source_panel
+            .discovered_sites
+            .iter()
+            .filter_map(|site| {
+                self.source_panel
+                    .discovered_wordpress
+                    .iter()
+                    .find(|wp| wp.root == site.root.trim_end_matches('/'))
+                    .map(|wp| (site.clone(), wp.clone()))
+            })
+            .collect();
+        if installs.is_empty() {
+            return None;
+        }
+
+        let mut section = div().flex().flex_col().gap(px(8.0)).child(
+            div()
+                .mt(px(8.0))
+                .text_size(px(12.0))
+                .font_weight(FontWeight::SEMIBOLD)
+                .text_color(ShellDeckColors::text_muted())
+                .child(t!("sync.wordpress.source").to_string()),
+        );
+        for (i, (site, install)) in installs.into_iter().enumerate() {
+            let is_selected = self.wizard_items.iter().any(|item| {
+                matches!(&item.kind, SyncItemKind::WordPressSite { site: s, .. } if s.server_name == site.server_name)
+            });
+            let detail = t!(
+                "sync.wordpress.detail",
+                db = install.config.db_name.as_str(),
+                url = install
+                    .home_url
+                    .clone()
+                    .unwrap_or_else(|| site_url(&site))
+                    .as_str()
+            )
+            .to_string();
+            let name = site.server_name.clone();
+            section = section.child(
+                div()
+                    .id(ElementId::from(SharedString::from(format!(
+                        "wizard-wp-{}",
+                        i
+                    ))))
+                    .flex()
+                    .items_center()
+                    .gap(px(8.0))
+                    .px(px(8.0))
+                    .py(px(6.0))
+                    .rounded(px(6.0))
+                    .cursor_pointer()
+                    .border_1()
+                    .when(is_selected, |el| {
+                        el.border_color(ShellDeckColors::primary())
+                            .bg(ShellDeckColors::primary().opacity(0.05))
+                    })
+                    .when(!is_selected, |el| {
+                        el.border_color(ShellDeckColors::border())
+                    })
+                    .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.toggle_wordpress_item(&site, &install, cx);
+                        cx.notify();
+                    }))
+                    .child(
+                        div()
+                            .w(px(16.0))
+                            .h(px(16.0))
+                            .rounded(px(3.0))
+                            .border_1()
+                            .border_color(ShellDeckColors::border())
+                            .flex()
+                            .items_center()
+                            .justify_center()
+                            .when(is_selected, |el| {
+                                el.bg(ShellDeckColors::primary()).child(
+                                    div()
+                                        .text_size(px(10.0))
+                                        .text_color(hsla(0.0, 0.0, 1.0, 1.0))
+                                        .child("✓"),
+                                )
+                            }),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .flex_col()
+                            .child(
+                                div()
+                                    .text_size(px(12.0))
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .child(name),
+                            )
+                            .child(
+                                div()
+                                    .text_size(px(10.0))
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .child(detail),
+                            ),
+                    ),
+            );
+        }
+        Some(section)
+    }
+
+    /// Options-step block with the URL change of each WordPress item.
+    pub(super) fn render_wordpress_options(&self, cx: &mut Context<Self>) -> Option<Div> {
+        let mut block = div()
+            .flex()
+            .flex_col()
+            .gap(px(8.0))
+            .pt(px(8.0))
+            .border_t_1()
+            .border_color(ShellDeckColors::border())
+            .child(
+                div()
+                    .text_size(px(13.0))
+                    .text_color(ShellDeckColors::text_primary())
+                    .child(t!("sync.wordpress.urls").to_string()),
+            );
+        let mut any = false;
+        for item in &self.wizard_items {
+            let SyncItemKind::WordPressSite {
+                site,
+                old_url,
+                source_db,
+                dest_db,
+                ..
+            } = &item.kind
+            else {
+                continue;
+            };
+            let Some(input) = self.wp_url_inputs.get(&item.id) else {
+                continue;
+            };
+            any = true;
+            let item_id = item.id;
+            let entity = cx.entity();
+            block = block.child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(
+                        div()
+                            .text_size(px(12.0))
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(site.server_name.clone()),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(
+                                t!(
+                                    "sync.wordpress.database",
+                                    source = source_db.db_name.as_str(),
+                                    dest = dest_db.db_name.as_str()
+                                )
+                                .to_string(),
+                            ),
+                    )
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .gap(px(6.0))
+                            .child(
+                                div()
+                                    .text_size(px(11.0))
+                                    .font_family("JetBrains Mono")
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .child(format!("{} →", old_url)),
+                            )
+                            .child(
+                                div().flex_grow().child(
+                                    Input::new(input)
+                                        .size(InputSize::Sm)
+                                        .placeholder(t!("sync.wordpress.new_url").to_string())
+                                        .on_change(move |value, cx| {
+                                            entity.update(cx, |this, cx| {
+                                                this.set_wordpress_url(item_id, value.trim());
+                                                cx.notify();
+                                            });
+                                        }),
+                                ),
+                            ),
+                    ),
+            );
+        }
+        any.then_some(block)
+    }
+
+    fn set_wordpress_url(&mut self, item_id: Uuid, url: &str) {
+        if let Some(SyncItemKind::WordPressSite { new_url, .. }) = self
+            .wizard_items
+            .iter_mut()
+            .find(|i| i.id == item_id)
+            .map(|i| &mut i.kind)
+        {
+            *new_url = url.to_string();
+        }
+    }
+}
+
+fn wp_url_input(initial: &str, cx: &mut Context<ServerSyncView>) -> Entity<InputState> {
+    let initial = initial.to_string();
+    cx.new(|cx| {
+        let mut s = InputState::new(cx);
+        s.content = initial.into();
+        s
+    })
+}
+
+/// The URL a site answers on, from its nginx server block.
+fn site_url(site: &DiscoveredSite) -> String {
+    let scheme = if site.ssl { "https" } else { "http" };
+    format!("{}://{}", scheme, site.server_name)
+}
+
+/// Review-step line describing what a WordPress item changes besides files.
+pub(super) fn wordpress_review_line(kind: &SyncItemKind) -> Option<String> {
+    let SyncItemKind::WordPressSite {
+        old_url,
+        new_url,
+        source_db,
+        dest_db,
+        ..
+    } = kind
+    else {
+        return None;
+    };
+    let database = t!(
+        "sync.wordpress.database",
+        source = source_db.db_name.as_str(),
+        dest = dest_db.db_name.as_str()
+    )
+    .to_string();
+    if old_url.trim_end_matches('/') == new_url.trim_end_matches('/') {
+        Some(format!(
+            "{} · {}",
+            database,
+            t!("sync.wordpress.url_unchanged")
+        ))
+    } else {
+        Some(format!(
+            "{} · {}",
+            database,
+            t!(
+                "sync.wordpress.url_change",
+                old = old_url.as_str(),
+                new = new_url.as_str()
+            )
+        ))
+    }
+}
@@ -1072,6 +1072,54 @@ impl SettingsView {
                     },
                 ),
             ))
+            .child(Self::render_setting_row(
+                t!("settings.tray.notify_scheduled_failures.label").as_ref(),
+                t!("settings.tray.notify_scheduled_failures.description").as_ref(),
+                Self::bind_toggle(
+                    "tray-notify-scheduled-failures",
+                    self.config.tray.notify_scheduled_failures,
+                    &entity,
+                    |this, value| {
+                        this.config.tray.notify_scheduled_failures = value;
+                    },
+                ),
+            ))
+            .child(Self::render_setting_row(
+                t!("settings.tray.notify_site_health.label").as_ref(),
+                t!("settings.tray.notify_site_health.description").as_ref(),
+                Self::bind_toggle(
+                    "tray-notify-site-health",
+                    self.config.tray.notify_site_health,
+                    &entity,
+                    |this, value| {
+                        this.config.tray.notify_site_health = value;
+                    },
+                ),
+            ))
+            .child(Self::render_setting_row(
+                t!("settings.tray.notify_tunnel_down.label").as_ref(),
+                t!("settings.tray.notify_tunnel_down.description").as_ref(),
+                Self::bind_toggle(
+                    "tray-notify-tunnel-down",
+                    self.config.tray.notify_tunnel_down,
+                    &entity,
+                    |this, value| {
+                        this.config.tray.notify_tunnel_down = value;
+                    },
+                ),
+            ))
+            .child(Self::render_setting_row(
+                t!("settings.tray.notify_host_metrics.label").as_ref(),
+                t!("settings.tray.notify_host_metrics.description").as_ref(),
+                Self::bind_toggle(
+                    "tray-notify-host-metrics",
+                    self.config.tray.notify_host_metrics,
+                    &entity,
+                    |this, value| {
+                        this.config.tray.notify_host_metrics = value;
+                    },
+                ),
+            ))
             .child(self.render_cloud_sync_settings(cx))
     }
 
@@ -6,10 +6,12 @@ use adabraka_ui::components::input::{Input, InputSize, InputState};
 
 use adabraka_ui::prelude::*;
 use shelldeck_core::models::connection::{Connection, ConnectionStatus};
+use shelldeck_core::models::host_metrics::MetricsHistory;
 use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::command_palette::fuzzy_match;
+use crate::host_metrics_view::HostMetricsTooltip;
 use crate::icons::lucide_icon;
 use crate::t;
 use crate::theme::ShellDeckColors;
@@ -335,6 +337,8 @@ pub struct SidebarView {
     /// section so the panel can render whichever activity is selected without
     /// the workspace having to re-push on every switch.
     panel_items: HashMap<SidebarSection, Vec<PanelItem>>,
+    /// Recent samples of monitored hosts, shown when hovering their row.
+    host_metrics: HashMap<Uuid, MetricsHistory>,
     focus_handle: FocusHandle,
 }
 
@@ -355,6 +359,7 @@ impl SidebarView {
             monique_available: false,
             fleet_available: false,
             panel_items: HashMap::new(),
+            host_metrics: HashMap::new(),
             focus_handle: cx.focus_handle(),
         }
     }
@@ -422,6 +427,19 @@ impl SidebarView {
         self.pinned_connections = pinned_connections;
     }
 
+    /// Replace a host's metrics history; `None` once it is no longer
+    /// monitored.
+    pub fn set_host_metrics(&mut self, connection_id: Uuid, history: Option<MetricsHistory>) {
+        match history {
+            Some(history) => {
+                self.host_metrics.insert(connection_id, history);
+            }
+            None => {
+                self.host_metrics.remove(&connection_id);
+            }
+        }
+    }
+
     /// Highlight a connection in the Connections section without opening an
     /// SSH session. Used by the `shelldeck://open/connection/<uuid>` deep
     /// link so a link can point the user at a connection without connecting.
@@ -967,7 +985,7 @@ impl SidebarView {
             );
         }
 
-        let content = div()
+        let mut content = div()
             .id(ElementId::from(SharedString::from(format!(
                 "conn-{}",
                 conn_id
@@ -1005,6 +1023,17 @@ impl SidebarView {
                     .child(conn_str_el),
             );
 
+        if let Some(history) = self.host_metrics.get(&conn_id).cloned() {
+            let name = SharedString::from(connection.display_name().to_string());
+            content = content.tooltip(move |_, cx| {
+                cx.new(|_| HostMetricsTooltip {
+                    name: name.clone(),
+                    history: history.clone(),
+                })
+                .into()
+            });
+        }
+
         row = row.child(content);
         row = row.child(pin_button);
         row = row.child(action_buttons);
@@ -1,4 +1,7 @@
-use std::collections::HashSet;
+mod backups;
+mod config_edit;
+
+use std::collections::{HashMap, HashSet};
 use std::ops::Range;
 
 use crate::scale::px;
@@ -9,12 +12,17 @@ use adabraka_ui::display::badge::{Badge, BadgeVariant};
 use adabraka_ui::prelude::Markdown;
 use gpui::prelude::*;
 use gpui::*;
+use shelldeck_core::config::backup_catalog::BackupCatalog;
 use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::db_backup::BackupPlan;
 use shelldeck_core::models::managed_site::{ManagedSite, ManagedSiteType, SiteStatus};
 use shelldeck_core::models::server_sync::DatabaseEngine;
+use shelldeck_core::models::site_health::{HealthSample, SiteMonitor};
 use uuid::Uuid;
 
 const PAGE_SIZE: usize = 50;
+/// Probes drawn in the detail panel's latency sparkline.
+const SPARKLINE_SAMPLES: usize = 48;
 
 use crate::icons::lucide_icon;
 use crate::markdown::{markdown_link_popover, MarkdownLinkAction, MarkdownLinkHandler};
@@ -32,12 +40,38 @@ pub enum SitesEvent {
     ScanServer(Uuid),
     ScanAllServers,
     CheckSiteStatus(Uuid),
+    /// Run a health probe now, outside the monitor's schedule.
+    ProbeSite(Uuid),
+    UpdateMonitor(Uuid, SiteMonitor),
     RemoveSite(Uuid),
     ToggleFavorite(Uuid),
     UpdateTags(Uuid, Vec<String>),
     OpenInBrowser(String),
     SshToServer(Uuid),
     AddToSync(Uuid),
+    /// Back up a database site now, with its plan or a default one.
+    BackupDatabase(Uuid),
+    /// Save a backup plan; the password goes to the keychain.
+    SaveBackupPlan {
+        plan: BackupPlan,
+        password: Option<String>,
+    },
+    /// Restore an archive, confirmed by the user, into `database` on
+    /// `connection_id`.
+    RestoreBackup {
+        entry_id: Uuid,
+        connection_id: Uuid,
+        database: String,
+    },
+    DeleteBackup(Uuid),
+    /// Fetch a web site's config into the detail panel's editor.
+    LoadSiteConfig(Uuid),
+    /// Check, install and reload an edited config, rolling it back if the
+    /// site stops answering.
+    ApplySiteConfig {
+        site_id: Uuid,
+        content: String,
+    },
     RefreshSites,
     ClearAllSites,
 }
@@ -56,8 +90,12 @@ pub enum SitesViewMode {
 pub enum SiteTypeFilter {
     All,
     Nginx,
+    Apache,
+    Caddy,
     Mysql,
     Postgresql,
+    Docker,
+    Systemd,
 }
 
 impl SiteTypeFilter {
@@ -65,8 +103,12 @@ impl SiteTypeFilter {
         match self {
             SiteTypeFilter::All => t!("sites.filter.all").to_string(),
             SiteTypeFilter::Nginx => "Nginx".to_string(),
+            SiteTypeFilter::Apache => "Apache".to_string(),
+            SiteTypeFilter::Caddy => "Caddy".to_string(),
             SiteTypeFilter::Mysql => "MySQL".to_string(),
             SiteTypeFilter::Postgresql => "PostgreSQL".to_string(),
+            SiteTypeFilter::Docker => "Docker".to_string(),
+            SiteTypeFilter::Systemd => "systemd".to_string(),
         }
     }
 
@@ -74,14 +116,35 @@ impl SiteTypeFilter {
         match self {
             SiteTypeFilter::All => true,
             SiteTypeFilter::Nginx => matches!(site.site_type, ManagedSiteType::NginxSite(_)),
+            SiteTypeFilter::Apache => matches!(site.site_type, ManagedSiteType::ApacheSite(_)),
+            SiteTypeFilter::Caddy => matches!(site.site_type, ManagedSiteType::CaddySite(_)),
             SiteTypeFilter::Mysql => matches!(&site.site_type,
                 ManagedSiteType::Database(d) if d.engine == DatabaseEngine::Mysql),
             SiteTypeFilter::Postgresql => matches!(&site.site_type,
                 ManagedSiteType::Database(d) if d.engine == DatabaseEngine::Postgresql),
+            SiteTypeFilter::Docker => matches!(site.site_type, ManagedSiteType::Container(_)),
+            SiteTypeFilter::Systemd => {
+                matches!(site.site_type, ManagedSiteType::SystemdService(_))
+            }
         }
     }
 }
 
+/// Accent color of a site type, shared by badges, cards and the detail panel.
+fn type_color(site_type: &ManagedSiteType) -> Hsla {
+    match site_type {
+        ManagedSiteType::NginxSite(_) => ShellDeckColors::success(),
+        ManagedSiteType::Database(d) => match d.engine {
+            DatabaseEngine::Mysql => ShellDeckColors::primary(),
+            DatabaseEngine::Postgresql => ShellDeckColors::status_connected(),
+        },
+        ManagedSiteType::ApacheSite(_) => ShellDeckColors::warning(),
+        ManagedSiteType::CaddySite(_) => ShellDeckColors::syntax_keyword(),
+        ManagedSiteType::Container(_) => ShellDeckColors::syntax_builtin(),
+        ManagedSiteType::SystemdService(_) => ShellDeckColors::syntax_string(),
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SiteSortBy {
     Name,
@@ -144,6 +207,17 @@ pub struct SitesView {
     /// Open kebab (⋮) menu: which site, and the click position (window coords).
     kebab_menu: Option<(Uuid, Point<Pixels>)>,
     markdown_link_action: Option<MarkdownLinkAction>,
+    /// Probe history of monitored sites, oldest first.
+    health: HashMap<Uuid, Vec<HealthSample>>,
+    /// Keyword input of the detail panel's monitoring section.
+    monitor_keyword_state: Entity<InputState>,
+    backup_catalog: BackupCatalog,
+    /// Plans with a backup in flight.
+    backup_jobs: HashSet<Uuid>,
+    /// Archives being restored.
+    restore_jobs: HashSet<Uuid>,
+    backup_form: backups::BackupForm,
+    config_editor: config_edit::ConfigEditor,
 }
 
 impl EventEmitter<SitesEvent> for SitesView {}
@@ -169,6 +243,13 @@ impl SitesView {
             focus_handle: cx.focus_handle(),
             kebab_menu: None,
             markdown_link_action: None,
+            health: HashMap::new(),
+            monitor_keyword_state: cx.new(InputState::new),
+            backup_catalog: BackupCatalog::default(),
+            backup_jobs: HashSet::new(),
+            restore_jobs: HashSet::new(),
+            backup_form: backups::BackupForm::new(cx),
+            config_editor: config_edit::ConfigEditor::new(cx),
         }
     }
 
@@ -192,12 +273,35 @@ impl SitesView {
         self.connections = connections;
     }
 
+    pub fn set_health_samples(&mut self, site_id: Uuid, samples: Vec<HealthSample>) {
+        self.health.insert(site_id, samples);
+    }
+
+    /// Open the detail panel on a site, loading its monitor keyword and
+    /// backup plan into the inputs. An edited config stays in the editor.
+    fn select_site(&mut self, site_id: Uuid, cx: &mut Context<Self>) {
+        self.selected_site = Some(site_id);
+        self.detail_panel_open = true;
+        let keyword = self
+            .sites
+            .iter()
+            .find(|s| s.id == site_id)
+            .and_then(|s| s.monitor.keyword.clone())
+            .unwrap_or_default();
+        self.monitor_keyword_state.update(cx, |state, cx| {
+            state.replace_content(keyword, cx);
+        });
+        self.load_backup_form(cx);
+        self.config_editor.clear_error();
+        cx.notify();
+    }
+
     // -- Stats helpers -------------------------------------------------------
 
     pub fn total_sites(&self) -> usize {
         self.sites
             .iter()
-            .filter(|s| matches!(s.site_type, ManagedSiteType::NginxSite(_)))
+            .filter(|s| s.site_type.is_web_site())
             .count()
     }
 
@@ -208,6 +312,13 @@ impl SitesView {
             .count()
     }
 
+    pub fn total_services(&self) -> usize {
+        self.sites
+            .iter()
+            .filter(|s| s.site_type.is_service())
+            .count()
+    }
+
     pub fn servers_scanned(&self) -> usize {
         self.sites
             .iter()
@@ -516,6 +627,11 @@ impl SitesView {
                 self.total_databases().to_string(),
                 ShellDeckColors::success(),
             ))
+            .child(Self::render_stat_card(
+                &t!("sites.stats.services"),
+                self.total_services().to_string(),
+                ShellDeckColors::syntax_builtin(),
+            ))
             .child(Self::render_stat_card(
                 &t!("sites.stats.servers"),
                 self.servers_scanned().to_string(),
@@ -668,8 +784,12 @@ impl SitesView {
         chips_row = chips_row
             .child(self.render_type_filter_chip(SiteTypeFilter::All, cx))
             .child(self.render_type_filter_chip(SiteTypeFilter::Nginx, cx))
+            .child(self.render_type_filter_chip(SiteTypeFilter::Apache, cx))
+            .child(self.render_type_filter_chip(SiteTypeFilter::Caddy, cx))
             .child(self.render_type_filter_chip(SiteTypeFilter::Mysql, cx))
-            .child(self.render_type_filter_chip(SiteTypeFilter::Postgresql, cx));
+            .child(self.render_type_filter_chip(SiteTypeFilter::Postgresql, cx))
+            .child(self.render_type_filter_chip(SiteTypeFilter::Docker, cx))
+            .child(self.render_type_filter_chip(SiteTypeFilter::Systemd, cx));
 
         // Separator + sort chips.
         chips_row = chips_row
@@ -791,13 +911,7 @@ impl SitesView {
         let is_selected = self.selected_site == Some(site_id);
         let group_name = SharedString::from(format!("site-row-{}", site_id));
 
-        let type_color = match &site.site_type {
-            ManagedSiteType::NginxSite(_) => ShellDeckColors::success(),
-            ManagedSiteType::Database(d) => match d.engine {
-                DatabaseEngine::Mysql => ShellDeckColors::primary(),
-                DatabaseEngine::Postgresql => ShellDeckColors::status_connected(),
-            },
-        };
+        let type_color = type_color(&site.site_type);
 
         let port_str = site
             .port()
@@ -882,9 +996,7 @@ impl SitesView {
             .when(is_selected, |el| el.bg(ShellDeckColors::selected_bg()))
             .hover(|el| el.bg(ShellDeckColors::hover_bg()))
             .on_click(cx.listener(move |this, _, _, cx| {
-                this.selected_site = Some(site_id);
-                this.detail_panel_open = true;
-                cx.notify();
+                this.select_site(site_id, cx);
             }))
             // Type badge
             .child(
@@ -975,13 +1087,7 @@ impl SitesView {
         let site_id = site.id;
         let is_selected = self.selected_site == Some(site_id);
 
-        let type_color = match &site.site_type {
-            ManagedSiteType::NginxSite(_) => ShellDeckColors::success(),
-            ManagedSiteType::Database(d) => match d.engine {
-                DatabaseEngine::Mysql => ShellDeckColors::primary(),
-                DatabaseEngine::Postgresql => ShellDeckColors::status_connected(),
-            },
-        };
+        let type_color = type_color(&site.site_type);
 
         let mut card = div()
             .id(ElementId::from(SharedString::from(format!(
@@ -1005,9 +1111,7 @@ impl SitesView {
                     .hover(|el| el.border_color(ShellDeckColors::primary().opacity(0.5)))
             })
             .on_click(cx.listener(move |this, _, _, cx| {
-                this.selected_site = Some(site_id);
-                this.detail_panel_open = true;
-                cx.notify();
+                this.select_site(site_id, cx);
             }));
 
         // Type badge + favorite star
@@ -1134,13 +1238,7 @@ impl SitesView {
             None => return div(),
         };
 
-        let type_color = match &site.site_type {
-            ManagedSiteType::NginxSite(_) => ShellDeckColors::success(),
-            ManagedSiteType::Database(d) => match d.engine {
-                DatabaseEngine::Mysql => ShellDeckColors::primary(),
-                DatabaseEngine::Postgresql => ShellDeckColors::status_connected(),
-            },
-        };
+        let type_color = type_color(&site.site_type);
 
         let fav_id = site.id;
         let remove_id = site.id;
@@ -1264,7 +1362,9 @@ impl SitesView {
 
         // Type-specific details
         match &site.site_type {
-            ManagedSiteType::NginxSite(s) => {
+            ManagedSiteType::NginxSite(s)
+            | ManagedSiteType::ApacheSite(s)
+            | ManagedSiteType::CaddySite(s) => {
                 content = content.child(
                     div()
                         .flex()
@@ -1351,6 +1451,63 @@ impl SitesView {
                     );
                 }
             }
+            ManagedSiteType::Container(c) => {
+                content = content
+                    .child(Self::detail_field(t!("sites.detail.image"), &c.image))
+                    .child(Self::detail_field(t!("sites.detail.state"), &c.status))
+                    .child(Self::detail_field(t!("sites.detail.container_id"), &c.id));
+                if let Some(project) = &c.compose_project {
+                    let compose = match &c.compose_service {
+                        Some(service) => format!("{} / {}", project, service),
+                        None => project.clone(),
+                    };
+                    content = content.child(Self::detail_field(
+                        t!("sites.detail.compose_project"),
+                        compose,
+                    ));
+                }
+                if !c.ports.is_empty() {
+                    let ports: Vec<String> = c.ports.iter().map(|p| p.label()).collect();
+                    content = content.child(Self::detail_field(
+                        t!("sites.detail.ports"),
+                        ports.join(", "),
+                    ));
+                }
+                if !c.volumes.is_empty() {
+                    content = content.child(Self::detail_field(
+                        t!("sites.detail.volumes"),
+                        c.volumes.join(", "),
+                    ));
+                }
+            }
+            ManagedSiteType::SystemdService(svc) => {
+                content = content
+                    .child(Self::detail_field(
+                        t!("sites.detail.description"),
+                        &svc.description,
+                    ))
+                    .child(Self::detail_field(
+                        t!("sites.detail.state"),
+                        format!("{} ({})", svc.active_state, svc.sub_state),
+                    ))
+                    .child(Self::detail_field(
+                        t!("sites.detail.unit_file_state"),
+                        &svc.unit_file_state,
+                    ))
+                    .child(Self::detail_field(
+                        t!("sites.detail.config_path"),
+                        &svc.fragment_path,
+                    ));
+                if let Some(user) = &svc.user {
+                    content = content.child(Self::detail_field(t!("sites.detail.user"), user));
+                }
+                if let Some(dir) = &svc.working_directory {
+                    content = content.child(Self::detail_field(t!("sites.detail.root"), dir));
+                }
+                if let Some(exec) = &svc.exec_start {
+                    content = content.child(Self::detail_field(t!("sites.detail.command"), exec));
+                }
+            }
         }
 
         // Tags
@@ -1443,6 +1600,18 @@ impl SitesView {
                 ),
         );
 
+        if let Some(health) = self.render_health_section(site, cx) {
+            content = content.child(health);
+        }
+
+        if let Some(backups) = self.render_backup_section(site, cx) {
+            content = content.child(backups);
+        }
+
+        if let Some(config) = self.render_config_section(site, cx) {
+            content = content.child(config);
+        }
+
         // Discovered at
         content = content.child(
             div()
@@ -1535,6 +1704,212 @@ impl SitesView {
         panel
     }
 
+    /// Monitoring controls and probe history of a web site; `None` for
+    /// sites without a URL to probe.
+    fn render_health_section(&self, site: &ManagedSite, cx: &mut Context<Self>) -> Option<Div> {
+        site.monitor.target_url(site)?;
+        let site_id = site.id;
+        let monitor = site.monitor.clone();
+        let samples = self.health.get(&site_id).map(Vec::as_slice).unwrap_or(&[]);
+
+        let mut section = div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .pt(px(8.0))
+            .border_t_1()
+            .border_color(ShellDeckColors::border())
+            .child(Self::detail_label(t!("sites.health.section")));
+
+        if monitor.enabled {
+            match samples.last() {
+                None => {
+                    section = section.child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(t!("sites.health.no_samples").to_string()),
+                    );
+                }
+                Some(latest) => {
+                    section = section.child(Self::render_health_summary(samples, latest));
+                    section = section.child(Self::render_latency_sparkline(samples));
+                }
+            }
+        }
+
+        let keyword_monitor = monitor.clone();
+        let keyword_state = self.monitor_keyword_state.clone();
+        section = section.child(
+            div()
+                .flex()
+                .items_center()
+                .gap(px(6.0))
+                .child(
+                    div().flex_1().min_w(px(0.0)).child(
+                        Input::new(&self.monitor_keyword_state)
+                            .size(InputSize::Sm)
+                            .placeholder(t!("sites.health.keyword_placeholder").to_string()),
+                    ),
+                )
+                .child(
+                    Button::new(
+                        "detail-monitor-keyword",
+                        t!("sites.health.save_keyword").to_string(),
+                    )
+                    .size(ButtonSize::Sm)
+                    .variant(ButtonVariant::Outline)
+                    .on_click(cx.listener(move |_this, _, _, cx| {
+                        let keyword = keyword_state.read(cx).content().trim().to_string();
+                        let mut monitor = keyword_monitor.clone();
+                        monitor.keyword = (!keyword.is_empty()).then_some(keyword);
+                        cx.emit(SitesEvent::UpdateMonitor(site_id, monitor));
+                    })),
+                ),
+        );
+
+        let toggle_monitor = monitor.clone();
+        section = section.child(Self::detail_action_button(
+            "detail-monitor-toggle",
+            if monitor.enabled {
+                t!("sites.health.stop").to_string()
+            } else {
+                t!("sites.health.start").to_string()
+            },
+            ShellDeckColors::status_connected(),
+            cx.listener(move |_this, _, _, cx| {
+                let mut monitor = toggle_monitor.clone();
+                monitor.enabled = !monitor.enabled;
+                cx.emit(SitesEvent::UpdateMonitor(site_id, monitor));
+            }),
+        ));
+        if monitor.enabled {
+            section = section.child(Self::detail_action_button(
+                "detail-monitor-probe",
+                t!("sites.health.probe_now").to_string(),
+                ShellDeckColors::primary(),
+                cx.listener(move |_this, _, _, cx| {
+                    cx.emit(SitesEvent::ProbeSite(site_id));
+                }),
+            ));
+        }
+        Some(section)
+    }
+
+    /// Uptime, latest latency and status, DNS and certificate of a site.
+    fn render_health_summary(samples: &[HealthSample], latest: &HealthSample) -> Div {
+        let uptime = shelldeck_core::config::site_health::uptime_percent(samples)
+            .map(|percent| format!("{:.1}%", percent))
+            .unwrap_or_else(|| "-".to_string());
+        let latency = latest
+            .latency_ms
+            .map(|ms| t!("sites.health.latency_ms", ms = ms).to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let http = latest
+            .http_status
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "-".to_string());
+
+        let mut summary = div().flex().flex_col().gap(px(6.0)).child(
+            div()
+                .flex()
+                .items_center()
+                .gap(px(12.0))
+                .child(Self::detail_field(t!("sites.health.uptime"), uptime))
+                .child(Self::detail_field(t!("sites.health.latency"), latency))
+                .child(Self::detail_field(t!("sites.health.http_status"), http)),
+        );
+
+        let dns = if latest.addresses.is_empty() {
+            "-".to_string()
+        } else {
+            latest
+                .addresses
+                .iter()
+                .map(|ip| ip.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        summary = summary.child(Self::detail_field(t!("sites.health.dns"), dns));
+
+        if let Some(days) = latest.cert_days_left() {
+            let mut cert = t!("sites.health.cert_days", days = days).to_string();
+            if latest.chain_valid == Some(false) {
+                cert = format!("{} · {}", cert, t!("sites.health.cert_chain_invalid"));
+            }
+            summary = summary.child(Self::detail_field(t!("sites.health.certificate"), cert));
+        }
+
+        if let Some(failure) = samples.iter().rev().find_map(|s| s.failure.as_ref()) {
+            summary = summary.child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(Self::detail_label(t!("sites.health.last_failure")))
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::error())
+                            .child(failure.message()),
+                    ),
+            );
+        }
+        summary
+    }
+
+    /// One bar per recent probe, scaled to the slowest; failed probes are
+    /// drawn full height in the error color.
+    fn render_latency_sparkline(samples: &[HealthSample]) -> Div {
+        const HEIGHT: f32 = 28.0;
+        let recent = &samples[samples.len().saturating_sub(SPARKLINE_SAMPLES)..];
+        let slowest = recent
+            .iter()
+            .filter_map(|s| s.latency_ms)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+
+        let mut bars = div().flex().items_end().gap(px(1.0)).h(px(HEIGHT)).w_full();
+        for sample in recent {
+            let (height, color) = match (sample.is_up(), sample.latency_ms) {
+                (true, Some(ms)) => (
+                    (ms as f32 / slowest as f32 * HEIGHT).max(2.0),
+                    ShellDeckColors::success(),
+                ),
+                (true, None) => (2.0, ShellDeckColors::success()),
+                (false, _) => (HEIGHT, ShellDeckColors::error()),
+            };
+            bars = bars.child(
+                div()
+                    .flex_1()
+                    .h(px(height))
+                    .rounded(px(1.0))
+                    .bg(color.opacity(0.8)),
+            );
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(Self::detail_label(t!(
+                "sites.health.latency_history",
+                count = recent.len()
+            )))
+            .child(bars)
+    }
+
+    /// A label over its value, the building block of the detail panel.
+    fn detail_field(label: impl AsRef<str>, value: impl AsRef<str>) -> Div {
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .child(Self::detail_label(label))
+            .child(Self::detail_value(value))
+    }
+
     fn detail_label(text: impl AsRef<str>) -> Div {
         div()
             .text_size(px(10.0))
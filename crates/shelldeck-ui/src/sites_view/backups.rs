@@ -0,0 +1,585 @@
+use super::*;
+use shelldeck_core::config::backup_catalog::BackupCatalog;
+use shelldeck_core::models::db_backup::{BackupDestination, BackupEntry, BackupPlan};
+use shelldeck_core::models::db_credentials::DbCredentials;
+use shelldeck_core::models::schedule::CronSchedule;
+use shelldeck_core::models::server_sync::DiscoveredDatabase;
+
+/// Inputs of the detail panel's backup section.
+pub(super) struct BackupForm {
+    schedule: Entity<InputState>,
+    dir: Entity<InputState>,
+    keep_last: Entity<InputState>,
+    keep_daily: Entity<InputState>,
+    keep_weekly: Entity<InputState>,
+    user: Entity<InputState>,
+    password: Entity<InputState>,
+    /// Server the archives go to; `None` keeps them on this computer.
+    destination: Option<Uuid>,
+    error: Option<String>,
+    /// Archive whose restore is waiting for confirmation.
+    restore_entry: Option<Uuid>,
+    restore_target: Uuid,
+    restore_database: Entity<InputState>,
+}
+
+impl BackupForm {
+    pub(super) fn new(cx: &mut Context<SitesView>) -> Self {
+        Self {
+            schedule: cx.new(InputState::new),
+            dir: cx.new(InputState::new),
+            keep_last: cx.new(InputState::new),
+            keep_daily: cx.new(InputState::new),
+            keep_weekly: cx.new(InputState::new),
+            user: cx.new(InputState::new),
+            password: cx.new(InputState::new),
+            destination: None,
+            error: None,
+            restore_entry: None,
+            restore_target: Uuid::nil(),
+            restore_database: cx.new(InputState::new),
+        }
+    }
+}
+
+fn set_input(state: &Entity<InputState>, value: String, cx: &mut Context<SitesView>) {
+    state.update(cx, |state, cx| state.replace_content(value, cx));
+}
+
+fn read_input(state: &Entity<InputState>, cx: &App) -> String {
+    state.read(cx).content().trim().to_string()
+}
+
+impl SitesView {
+    pub fn set_backups(
+        &mut self,
+        catalog: BackupCatalog,
+        backup_jobs: HashSet<Uuid>,
+        restore_jobs: HashSet<Uuid>,
+    ) {
+        self.backup_catalog = catalog;
+        self.backup_jobs = backup_jobs;
+        self.restore_jobs = restore_jobs;
+    }
+
+    fn selected_database(&self) -> Option<(&ManagedSite, &DiscoveredDatabase)> {
+        let site = self
+            .sites
+            .iter()
+            .find(|s| Some(s.id) == self.selected_site)?;
+        match &site.site_type {
+            ManagedSiteType::Database(db) => Some((site, db)),
+            _ => None,
+        }
+    }
+
+    /// Fill the backup inputs from the selected database's plan, or the
+    /// defaults when it has none yet.
+    pub(super) fn load_backup_form(&mut self, cx: &mut Context<Self>) {
+        let Some((site, db)) = self.selected_database() else {
+            return;
+        };
+        let plan = self
+            .backup_catalog
+            .plan_for(site.connection_id, &db.name)
+            .cloned()
+            .unwrap_or_else(|| {
+                BackupPlan::new(
+                    site.connection_id,
+                    db,
+                    BackupDestination::Local {
+                        dir: BackupCatalog::default_archive_dir(),
+                    },
+                )
+            });
+        let (destination, dir) = match &plan.destination {
+            BackupDestination::Local { dir } => (None, dir.to_string_lossy().into_owned()),
+            BackupDestination::Remote { connection_id, dir } => (Some(*connection_id), dir.clone()),
+        };
+        let form = &mut self.backup_form;
+        form.destination = destination;
+        form.error = None;
+        form.restore_entry = None;
+        let fields = [
+            (
+                form.schedule.clone(),
+                plan.schedule.clone().unwrap_or_default(),
+            ),
+            (form.dir.clone(), dir),
+            (form.keep_last.clone(), plan.retention.keep_last.to_string()),
+            (
+                form.keep_daily.clone(),
+                plan.retention.keep_daily.to_string(),
+            ),
+            (
+                form.keep_weekly.clone(),
+                plan.retention.keep_weekly.to_string(),
+            ),
+            (
+                form.user.clone(),
+                plan.credentials
+                    .as_ref()
+                    .map(|c| c.user.clone())
+                    .unwrap_or_default(),
+            ),
+            (form.password.clone(), String::new()),
+        ];
+        for (state, value) in fields {
+            set_input(&state, value, cx);
+        }
+    }
+
+    /// Build the plan from the inputs, or say what is wrong with them.
+    fn backup_plan_from_form(&self, cx: &App) -> Result<(BackupPlan, Option<String>), String> {
+        let (site, db) = self
+            .selected_database()
+            .ok_or_else(|| t!("sites.backup.error.no_database").to_string())?;
+        let form = &self.backup_form;
+        let mut plan = self
+            .backup_catalog
+            .plan_for(site.connection_id, &db.name)
+            .cloned()
+            .unwrap_or_else(|| {
+                BackupPlan::new(
+                    site.connection_id,
+                    db,
+                    BackupDestination::Local {
+                        dir: BackupCatalog::default_archive_dir(),
+                    },
+                )
+            });
+
+        let schedule = read_input(&form.schedule, cx);
+        if !schedule.is_empty() {
+            CronSchedule::parse(&schedule).map_err(|e| {
+                t!("sites.backup.error.schedule", error = e.to_string()).to_string()
+            })?;
+        }
+        plan.schedule = (!schedule.is_empty()).then_some(schedule);
+
+        let dir = read_input(&form.dir, cx);
+        plan.destination = match form.destination {
+            None if dir.is_empty() => BackupDestination::Local {
+                dir: BackupCatalog::default_archive_dir(),
+            },
+            None => BackupDestination::Local { dir: dir.into() },
+            Some(_) if !dir.starts_with('/') => {
+                return Err(t!("sites.backup.error.remote_dir").to_string());
+            }
+            Some(connection_id) => BackupDestination::Remote { connection_id, dir },
+        };
+
+        let count = |state: &Entity<InputState>| {
+            read_input(state, cx)
+                .parse::<u32>()
+                .map_err(|_| t!("sites.backup.error.retention").to_string())
+        };
+        plan.retention.keep_last = count(&form.keep_last)?;
+        plan.retention.keep_daily = count(&form.keep_daily)?;
+        plan.retention.keep_weekly = count(&form.keep_weekly)?;
+
+        let user = read_input(&form.user, cx);
+        let password = read_input(&form.password, cx);
+        plan.credentials = match plan.credentials.take() {
+            _ if user.is_empty() => None,
+            Some(mut creds) => {
+                creds.user = user;
+                Some(creds)
+            }
+            None => Some(DbCredentials {
+                id: Uuid::new_v4(),
+                user,
+                ..Default::default()
+            }),
+        };
+        let password = (plan.credentials.is_some() && !password.is_empty()).then_some(password);
+        Ok((plan, password))
+    }
+
+    /// Backups of a database: run one now, the plan (schedule, destination,
+    /// retention, login) and the catalog with restore. `None` for anything
+    /// but databases.
+    pub(super) fn render_backup_section(
+        &self,
+        site: &ManagedSite,
+        cx: &mut Context<Self>,
+    ) -> Option<Div> {
+        let ManagedSiteType::Database(db) = &site.site_type else {
+            return None;
+        };
+        let site_id = site.id;
+        let plan = self.backup_catalog.plan_for(site.connection_id, &db.name);
+        let running = plan.is_some_and(|p| self.backup_jobs.contains(&p.id));
+        let form = &self.backup_form;
+
+        let mut section = div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .pt(px(8.0))
+            .border_t_1()
+            .border_color(ShellDeckColors::border())
+            .child(Self::detail_label(t!("sites.backup.section")));
+
+        section = section.child(Self::detail_action_button(
+            "detail-backup-now",
+            if running {
+                t!("sites.backup.running").to_string()
+            } else {
+                t!("sites.backup.now").to_string()
+            },
+            ShellDeckColors::primary(),
+            cx.listener(move |this, _, _, cx| {
+                if !running {
+                    this.backup_form.error = None;
+                    cx.emit(SitesEvent::BackupDatabase(site_id));
+                }
+            }),
+        ));
+
+        // Plan
+        let destination_label = match form.destination {
+            None => t!("sites.backup.this_computer").to_string(),
+            Some(id) => self
+                .connections
+                .iter()
+                .find(|c| c.id == id)
+                .map(|c| c.display_name().to_string())
+                .unwrap_or_else(|| t!("sites.backup.unknown_server").to_string()),
+        };
+        section = section
+            .child(Self::backup_input(
+                &form.schedule,
+                t!("sites.backup.schedule_placeholder"),
+                false,
+            ))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(Self::detail_label(t!("sites.backup.destination")))
+                    .child(
+                        Button::new("detail-backup-destination", destination_label)
+                            .size(ButtonSize::Sm)
+                            .variant(ButtonVariant::Outline)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.cycle_backup_destination();
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .child(Self::backup_input(
+                &form.dir,
+                t!("sites.backup.dir_placeholder"),
+                false,
+            ))
+            .child(Self::detail_label(t!("sites.backup.retention")))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(Self::backup_input(
+                        &form.keep_last,
+                        t!("sites.backup.keep_last"),
+                        false,
+                    ))
+                    .child(Self::backup_input(
+                        &form.keep_daily,
+                        t!("sites.backup.keep_daily"),
+                        false,
+                    ))
+                    .child(Self::backup_input(
+                        &form.keep_weekly,
+                        t!("sites.backup.keep_weekly"),
+                        false,
+                    )),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(Self::backup_input(
+                        &form.user,
+                        t!("sites.backup.user_placeholder"),
+                        false,
+                    ))
+                    .child(Self::backup_input(
+                        &form.password,
+                        t!("sites.backup.password_placeholder"),
+                        true,
+                    )),
+            );
+        if let Some(error) = &form.error {
+            section = section.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::error())
+                    .child(error.clone()),
+            );
+        }
+        section = section.child(Self::detail_action_button(
+            "detail-backup-save-plan",
+            t!("sites.backup.save_plan").to_string(),
+            ShellDeckColors::status_connected(),
+            cx.listener(move |this, _, _, cx| {
+                match this.backup_plan_from_form(cx) {
+                    Ok((plan, password)) => {
+                        this.backup_form.error = None;
+                        this.backup_form.password.update(cx, |state, cx| {
+                            state.replace_content(String::new(), cx);
+                        });
+                        cx.emit(SitesEvent::SaveBackupPlan { plan, password });
+                    }
+                    Err(error) => this.backup_form.error = Some(error),
+                }
+                cx.notify();
+            }),
+        ));
+
+        // Catalog
+        let entries = plan
+            .map(|p| self.backup_catalog.entries_for(p.id))
+            .unwrap_or_default();
+        section = section.child(Self::detail_label(t!(
+            "sites.backup.catalog",
+            count = entries.len()
+        )));
+        if entries.is_empty() {
+            section = section.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("sites.backup.no_backups").to_string()),
+            );
+        }
+        for entry in entries {
+            section = section.child(self.render_backup_entry(entry, cx));
+        }
+        Some(section)
+    }
+
+    fn backup_input(
+        state: &Entity<InputState>,
+        placeholder: impl std::fmt::Display,
+        password: bool,
+    ) -> Div {
+        div().flex_1().min_w(px(0.0)).child(
+            Input::new(state)
+                .size(InputSize::Sm)
+                .password(password)
+                .placeholder(placeholder.to_string()),
+        )
+    }
+
+    /// Next destination: this computer, then each server in turn.
+    fn cycle_backup_destination(&mut self) {
+        let ids: Vec<Uuid> = self.connections.iter().map(|c| c.id).collect();
+        self.backup_form.destination = match self.backup_form.destination {
+            None => ids.first().copied(),
+            Some(current) => ids
+                .iter()
+                .position(|id| *id == current)
+                .and_then(|i| ids.get(i + 1))
+                .copied(),
+        };
+    }
+
+    fn render_backup_entry(&self, entry: &BackupEntry, cx: &mut Context<Self>) -> Div {
+        let entry_id = entry.id;
+        let restoring = self.restore_jobs.contains(&entry_id);
+        let location = match &entry.destination {
+            BackupDestination::Local { .. } => t!("sites.backup.this_computer").to_string(),
+            BackupDestination::Remote { connection_id, .. } => self
+                .connections
+                .iter()
+                .find(|c| c.id == *connection_id)
+                .map(|c| c.display_name().to_string())
+                .unwrap_or_else(|| t!("sites.backup.unknown_server").to_string()),
+        };
+        let mut summary = format!(
+            "{} · {} · {}",
+            entry
+                .created_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M"),
+            entry.size_display(),
+            location
+        );
+        if entry.scheduled {
+            summary = format!("{} · {}", summary, t!("sites.backup.scheduled"));
+        }
+
+        let mut row = div()
+            .flex()
+            .flex_col()
+            .gap(px(4.0))
+            .p(px(6.0))
+            .rounded(px(4.0))
+            .bg(ShellDeckColors::hover_bg())
+            .child(Self::detail_value(summary))
+            .child(
+                div()
+                    .text_size(px(10.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .overflow_hidden()
+                    .whitespace_nowrap()
+                    .child(
+                        t!(
+                            "sites.backup.source",
+                            server = entry.source_name.as_str(),
+                            sha = &entry.sha256[..entry.sha256.len().min(12)]
+                        )
+                        .to_string(),
+                    ),
+            );
+
+        if self.backup_form.restore_entry == Some(entry_id) {
+            return row.child(self.render_restore_confirmation(entry, cx));
+        }
+
+        let (database, source) = (entry.database.clone(), entry.connection_id);
+        row = row.child(
+            div()
+                .flex()
+                .items_center()
+                .gap(px(6.0))
+                .child(
+                    Button::new(
+                        SharedString::from(format!("backup-restore-{}", entry_id)),
+                        if restoring {
+                            t!("sites.backup.restoring").to_string()
+                        } else {
+                            t!("sites.backup.restore").to_string()
+                        },
+                    )
+                    .size(ButtonSize::Sm)
+                    .variant(ButtonVariant::Outline)
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        if restoring {
+                            return;
+                        }
+                        this.backup_form.restore_entry = Some(entry_id);
+                        this.backup_form.restore_target = source;
+                        set_input(&this.backup_form.restore_database, database.clone(), cx);
+                        cx.notify();
+                    })),
+                )
+                .child(
+                    Button::new(
+                        SharedString::from(format!("backup-delete-{}", entry_id)),
+                        t!("sites.backup.delete").to_string(),
+                    )
+                    .size(ButtonSize::Sm)
+                    .variant(ButtonVariant::Ghost)
+                    .on_click(cx.listener(move |_this, _, _, cx| {
+                        cx.emit(SitesEvent::DeleteBackup(entry_id));
+                    })),
+                ),
+        );
+        row
+    }
+
+    /// Target of a restore and the warning the user confirms.
+    fn render_restore_confirmation(&self, entry: &BackupEntry, cx: &mut Context<Self>) -> Div {
+        let entry_id = entry.id;
+        let form = &self.backup_form;
+        let target_name = self
+            .connections
+            .iter()
+            .find(|c| c.id == form.restore_target)
+            .map(|c| c.display_name().to_string())
+            .unwrap_or_else(|| t!("sites.backup.unknown_server").to_string());
+        let database = read_input(&form.restore_database, cx);
+
+        div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .pt(px(4.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(Self::detail_label(t!("sites.backup.restore_into")))
+                    .child(
+                        Button::new("backup-restore-target", target_name.clone())
+                            .size(ButtonSize::Sm)
+                            .variant(ButtonVariant::Outline)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                let ids: Vec<Uuid> =
+                                    this.connections.iter().map(|c| c.id).collect();
+                                let next = ids
+                                    .iter()
+                                    .position(|id| *id == this.backup_form.restore_target)
+                                    .map(|i| (i + 1) % ids.len())
+                                    .unwrap_or(0);
+                                if let Some(id) = ids.get(next) {
+                                    this.backup_form.restore_target = *id;
+                                }
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .child(Self::backup_input(
+                &form.restore_database,
+                t!("sites.backup.database_placeholder"),
+                false,
+            ))
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::warning())
+                    .child(
+                        t!(
+                            "sites.backup.restore_warning",
+                            database = database.as_str(),
+                            server = target_name.as_str()
+                        )
+                        .to_string(),
+                    ),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(
+                        Button::new(
+                            "backup-restore-confirm",
+                            t!("sites.backup.confirm_restore").to_string(),
+                        )
+                        .size(ButtonSize::Sm)
+                        .variant(ButtonVariant::Destructive)
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            let database = read_input(&this.backup_form.restore_database, cx);
+                            if database.is_empty() {
+                                return;
+                            }
+                            this.backup_form.restore_entry = None;
+                            cx.emit(SitesEvent::RestoreBackup {
+                                entry_id,
+                                connection_id: this.backup_form.restore_target,
+                                database,
+                            });
+                            cx.notify();
+                        })),
+                    )
+                    .child(
+                        Button::new(
+                            "backup-restore-cancel",
+                            t!("sites.backup.cancel").to_string(),
+                        )
+                        .size(ButtonSize::Sm)
+                        .variant(ButtonVariant::Ghost)
+                        .on_click(cx.listener(|this, _, _, cx| {
+                            this.backup_form.restore_entry = None;
+                            cx.notify();
+                        })),
+                    ),
+            )
+    }
+}
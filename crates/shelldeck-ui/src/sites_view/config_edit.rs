@@ -0,0 +1,243 @@
+use super::*;
+use shelldeck_core::models::config_apply::WebServer;
+
+/// The detail panel's config editor. One config is open at a time.
+pub(super) struct ConfigEditor {
+    /// Site whose config is in the editor.
+    site_id: Option<Uuid>,
+    /// Site whose config is being fetched.
+    loading: Option<Uuid>,
+    content: Entity<InputState>,
+    /// Sites with an apply in flight.
+    applying: HashSet<Uuid>,
+    /// What the apply did so far, and whether each step failed.
+    steps: Vec<(String, bool)>,
+    /// What the server's checker printed on the last apply.
+    check_output: Option<String>,
+    error: Option<String>,
+}
+
+impl ConfigEditor {
+    pub(super) fn new(cx: &mut Context<SitesView>) -> Self {
+        Self {
+            site_id: None,
+            loading: None,
+            content: cx.new(|cx| InputState::new(cx).multi_line(true)),
+            applying: HashSet::new(),
+            steps: Vec::new(),
+            check_output: None,
+            error: None,
+        }
+    }
+
+    /// Drop a load error shown for the previously selected site.
+    pub(super) fn clear_error(&mut self) {
+        self.error = None;
+    }
+}
+
+impl SitesView {
+    /// Fill the editor with a config fetched from the server, or show why
+    /// it could not be read.
+    pub fn set_site_config(
+        &mut self,
+        site_id: Uuid,
+        result: Result<String, String>,
+        cx: &mut Context<Self>,
+    ) {
+        let editor = &mut self.config_editor;
+        if editor.loading != Some(site_id) {
+            return;
+        }
+        editor.loading = None;
+        editor.steps.clear();
+        editor.check_output = None;
+        match result {
+            Ok(content) => {
+                editor.site_id = Some(site_id);
+                editor.error = None;
+                editor
+                    .content
+                    .update(cx, |state, cx| state.replace_content(content, cx));
+            }
+            Err(error) => editor.error = Some(error),
+        }
+    }
+
+    pub fn set_config_applying(&mut self, site_id: Uuid, applying: bool) {
+        if applying {
+            self.config_editor.applying.insert(site_id);
+        } else {
+            self.config_editor.applying.remove(&site_id);
+        }
+    }
+
+    /// Add a step of the running apply to the editor's log.
+    pub fn push_config_step(&mut self, site_id: Uuid, message: String, failed: bool) {
+        if self.config_editor.site_id == Some(site_id) {
+            self.config_editor.steps.push((message, failed));
+        }
+    }
+
+    pub fn set_config_check_output(&mut self, site_id: Uuid, output: String) {
+        if self.config_editor.site_id == Some(site_id) {
+            self.config_editor.check_output = Some(output);
+        }
+    }
+
+    /// Edit and apply the config of an nginx, Apache or Caddy site. `None`
+    /// for anything else.
+    pub(super) fn render_config_section(
+        &self,
+        site: &ManagedSite,
+        cx: &mut Context<Self>,
+    ) -> Option<Div> {
+        let server = WebServer::for_site(&site.site_type)?;
+        let site_id = site.id;
+        let editor = &self.config_editor;
+        let applying = editor.applying.contains(&site_id);
+
+        let mut section = div()
+            .flex()
+            .flex_col()
+            .gap(px(6.0))
+            .pt(px(8.0))
+            .border_t_1()
+            .border_color(ShellDeckColors::border())
+            .child(Self::detail_label(t!("sites.config.section")));
+
+        if let Some(error) = &editor.error {
+            section = section.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::error())
+                    .child(error.clone()),
+            );
+        }
+
+        if editor.site_id != Some(site_id) {
+            let loading = editor.loading == Some(site_id);
+            section = section.child(Self::detail_action_button(
+                "detail-config-edit",
+                if loading {
+                    t!("sites.config.loading").to_string()
+                } else {
+                    t!("sites.config.edit").to_string()
+                },
+                ShellDeckColors::primary(),
+                cx.listener(move |this, _, _, cx| {
+                    if this.config_editor.loading.is_none() {
+                        this.config_editor.loading = Some(site_id);
+                        this.config_editor.error = None;
+                        cx.emit(SitesEvent::LoadSiteConfig(site_id));
+                        cx.notify();
+                    }
+                }),
+            ));
+            return Some(section);
+        }
+
+        section = section
+            .child(
+                Input::new(&editor.content)
+                    .size(InputSize::Sm)
+                    .multi_line(true)
+                    .min_rows(12)
+                    .max_rows(24)
+                    .disabled(applying),
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("sites.config.hint", check = server.check()).to_string()),
+            )
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .gap(px(6.0))
+                    .child(
+                        Button::new(
+                            "detail-config-apply",
+                            if applying {
+                                t!("sites.config.applying").to_string()
+                            } else {
+                                t!("sites.config.apply").to_string()
+                            },
+                        )
+                        .size(ButtonSize::Sm)
+                        .variant(ButtonVariant::Default)
+                        .disabled(applying)
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            let content = this.config_editor.content.read(cx).content().to_string();
+                            this.config_editor.steps.clear();
+                            this.config_editor.check_output = None;
+                            this.config_editor.error = None;
+                            cx.emit(SitesEvent::ApplySiteConfig { site_id, content });
+                            cx.notify();
+                        })),
+                    )
+                    .child(
+                        Button::new(
+                            "detail-config-discard",
+                            t!("sites.config.discard").to_string(),
+                        )
+                        .size(ButtonSize::Sm)
+                        .variant(ButtonVariant::Outline)
+                        .disabled(applying)
+                        .on_click(cx.listener(|this, _, _, cx| {
+                            this.config_editor.site_id = None;
+                            this.config_editor.steps.clear();
+                            this.config_editor.check_output = None;
+                            cx.notify();
+                        })),
+                    ),
+            );
+
+        if !editor.steps.is_empty() {
+            let mut steps = div()
+                .flex()
+                .flex_col()
+                .gap(px(2.0))
+                .child(Self::detail_label(t!("sites.config.steps")));
+            for (message, failed) in &editor.steps {
+                steps = steps.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(if *failed {
+                            ShellDeckColors::error()
+                        } else {
+                            ShellDeckColors::text_primary()
+                        })
+                        .child(message.clone()),
+                );
+            }
+            section = section.child(steps);
+        }
+
+        if let Some(output) = editor.check_output.as_deref().map(str::trim) {
+            section = section.child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .gap(px(4.0))
+                    .child(Self::detail_label(t!("sites.config.checker_output")))
+                    .child(
+                        div()
+                            .p(px(6.0))
+                            .rounded(px(4.0))
+                            .bg(ShellDeckColors::bg_sidebar())
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(if output.is_empty() {
+                                t!("sites.config.no_output").to_string()
+                            } else {
+                                output.to_string()
+                            }),
+                    ),
+            );
+        }
+        Some(section)
+    }
+}
@@ -0,0 +1,475 @@
+//! SSH doctor: run a staged connection test against one connection and
+//! show every stage (DNS, TCP, jump host, banner, key exchange, host key,
+//! authentication, shell) with its timing and a suggested fix.
+//!
+//! The run itself lives in `shelldeck_ssh::doctor`; this view starts it on
+//! its own thread and fills the report in as the steps arrive.
+
+use crate::scale::px;
+use adabraka_ui::prelude::*;
+use gpui::prelude::*;
+use gpui::*;
+use std::time::Duration;
+
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::diagnostics::{DiagnosticReport, DiagnosticStep, StepStatus};
+
+use crate::t;
+use crate::theme::ShellDeckColors;
+
+#[derive(Debug, Clone)]
+pub enum SshDoctorEvent {
+    Close,
+}
+
+impl EventEmitter<SshDoctorEvent> for SshDoctorView {}
+
+pub struct SshDoctorView {
+    connection: Connection,
+    report: DiagnosticReport,
+    running: bool,
+    focus_handle: FocusHandle,
+    needs_focus: bool,
+}
+
+impl SshDoctorView {
+    pub fn new(connection: Connection, cx: &mut Context<Self>) -> Self {
+        let mut view = Self {
+            report: DiagnosticReport::new(&connection),
+            connection,
+            running: false,
+            focus_handle: cx.focus_handle(),
+            needs_focus: true,
+        };
+        view.run(cx);
+        view
+    }
+
+    /// Start a fresh run. Ignored while one is in progress.
+    fn run(&mut self, cx: &mut Context<Self>) {
+        if self.running {
+            return;
+        }
+        self.running = true;
+        self.report = DiagnosticReport::new(&self.connection);
+        cx.notify();
+
+        let (step_tx, mut step_rx) = tokio::sync::mpsc::unbounded_channel::<DiagnosticStep>();
+        let conn = self.connection.clone();
+        let spawned = std::thread::Builder::new()
+            .name("ssh-doctor".to_string())
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        tracing::error!("Failed to create async runtime: {}", e);
+                        return;
+                    }
+                };
+                rt.block_on(shelldeck_ssh::doctor::diagnose(&conn, step_tx));
+            });
+        if let Err(e) = spawned {
+            tracing::error!("Failed to spawn SSH doctor thread: {}", e);
+            self.running = false;
+            self.report.finished = true;
+            return;
+        }
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor()
+                .timer(Duration::from_millis(50))
+                .await;
+
+            let mut steps = Vec::new();
+            let mut finished = false;
+            loop {
+                match step_rx.try_recv() {
+                    Ok(step) => steps.push(step),
+                    Err(tokio::sync::mpsc::error::TryRecvError::Empty) => break,
+                    Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+            if steps.is_empty() && !finished {
+                continue;
+            }
+            let updated = this.update(cx, |view, cx| {
+                view.report.steps.extend(steps);
+                view.report.finished = finished;
+                view.running = !finished;
+                cx.notify();
+            });
+            if finished || updated.is_err() {
+                break;
+            }
+        })
+        .detach();
+    }
+
+    fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        if event.keystroke.key.as_str() == "escape" {
+            cx.emit(SshDoctorEvent::Close);
+        }
+    }
+
+    fn render_step(&self, step: &DiagnosticStep) -> impl IntoElement {
+        let (icon, color) = status_icon(step.status);
+        let mut text = div()
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .min_w(px(0.0))
+            .gap(px(2.0))
+            .child(
+                div()
+                    .flex()
+                    .items_center()
+                    .justify_between()
+                    .gap(px(8.0))
+                    .child(
+                        div()
+                            .text_size(px(12.0))
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(ShellDeckColors::text_primary())
+                            .child(stage_label(step)),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .child(
+                                step.elapsed_ms
+                                    .map(|ms| t!("ssh_doctor.elapsed", ms = ms).to_string())
+                                    .unwrap_or_default(),
+                            ),
+                    ),
+            )
+            .child(
+                div()
+                    .text_size(px(11.0))
+                    .font_family("JetBrains Mono")
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(step.detail.clone()),
+            );
+        if let Some(fix) = fix_text(step) {
+            text = text.child(div().text_size(px(12.0)).text_color(color).child(fix));
+        }
+
+        div()
+            .flex()
+            .items_start()
+            .gap(px(10.0))
+            .px(px(20.0))
+            .py(px(6.0))
+            .child(
+                svg()
+                    .path(icon)
+                    .size(px(14.0))
+                    .mt(px(1.0))
+                    .flex_shrink_0()
+                    .text_color(color),
+            )
+            .child(text)
+    }
+
+    fn render_summary(&self) -> impl IntoElement {
+        let (icon, color, text) = if !self.report.finished {
+            (
+                "icons/lucide/refresh-cw.svg",
+                ShellDeckColors::text_muted(),
+                t!("ssh_doctor.running").to_string(),
+            )
+        } else {
+            let (icon, color) = status_icon(self.report.outcome());
+            (icon, color, outcome_text(&self.report))
+        };
+        div()
+            .flex()
+            .items_center()
+            .gap(px(8.0))
+            .px(px(20.0))
+            .py(px(10.0))
+            .border_b_1()
+            .border_color(ShellDeckColors::border())
+            .child(svg().path(icon).size(px(16.0)).text_color(color))
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .min_w(px(0.0))
+                    .child(
+                        div()
+                            .text_size(px(13.0))
+                            .font_weight(FontWeight::MEDIUM)
+                            .text_color(color)
+                            .child(text),
+                    )
+                    .child(
+                        div()
+                            .text_size(px(11.0))
+                            .text_color(ShellDeckColors::text_muted())
+                            .truncate()
+                            .child(target_text(&self.report)),
+                    ),
+            )
+    }
+}
+
+impl Render for SshDoctorView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if self.needs_focus {
+            self.needs_focus = false;
+            self.focus_handle.focus(window);
+        }
+
+        let mut steps = div()
+            .id("ssh-doctor-steps")
+            .flex()
+            .flex_col()
+            .flex_grow()
+            .min_h(px(0.0))
+            .py(px(6.0))
+            .overflow_y_scroll();
+        for host in self.report.hosts() {
+            steps = steps.child(
+                div()
+                    .px(px(20.0))
+                    .pt(px(8.0))
+                    .pb(px(2.0))
+                    .text_size(px(11.0))
+                    .font_weight(FontWeight::SEMIBOLD)
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(host.to_string()),
+            );
+            for step in self.report.steps.iter().filter(|s| s.host == host) {
+                steps = steps.child(self.render_step(step));
+            }
+        }
+
+        let export = report_text(&self.report);
+        let mut run_again = div()
+            .id("ssh-doctor-run-again")
+            .on_click(cx.listener(|this, _: &ClickEvent, _, cx| {
+                this.run(cx);
+            }))
+            .child(
+                Button::new(
+                    "ssh-doctor-run-again-btn",
+                    t!("ssh_doctor.run_again").to_string(),
+                )
+                .variant(ButtonVariant::Default),
+            );
+        if !self.running {
+            run_again = run_again.cursor_pointer();
+        } else {
+            run_again = run_again.opacity(0.5);
+        }
+
+        div()
+            .id("ssh-doctor-overlay")
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
+                this.handle_key_down(event, cx);
+            }))
+            .occlude()
+            .absolute()
+            .top_0()
+            .left_0()
+            .right_0()
+            .bottom_0()
+            .bg(ShellDeckColors::backdrop())
+            .flex()
+            .justify_center()
+            .items_center()
+            .child(
+                div()
+                    .flex()
+                    .flex_col()
+                    .w(px(640.0))
+                    .h(px(560.0))
+                    .bg(ShellDeckColors::bg_surface())
+                    .rounded(px(12.0))
+                    .border_1()
+                    .border_color(ShellDeckColors::border())
+                    .shadow_xl()
+                    .overflow_hidden()
+                    // Header
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_between()
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_b_1()
+                            .border_color(ShellDeckColors::border())
+                            .child(
+                                div()
+                                    .text_size(px(16.0))
+                                    .font_weight(FontWeight::SEMIBOLD)
+                                    .text_color(ShellDeckColors::text_primary())
+                                    .child(
+                                        t!(
+                                            "ssh_doctor.title",
+                                            name = self.report.connection_name.as_str()
+                                        )
+                                        .to_string(),
+                                    ),
+                            )
+                            .child(
+                                div()
+                                    .id("close-ssh-doctor")
+                                    .flex()
+                                    .items_center()
+                                    .justify_center()
+                                    .cursor_pointer()
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .hover(|el| el.text_color(ShellDeckColors::text_primary()))
+                                    .on_click(cx.listener(|_this, _: &ClickEvent, _, cx| {
+                                        cx.emit(SshDoctorEvent::Close);
+                                    }))
+                                    .child(
+                                        svg()
+                                            .path("icons/lucide/x.svg")
+                                            .size(px(14.0))
+                                            .text_color(ShellDeckColors::text_muted()),
+                                    ),
+                            ),
+                    )
+                    .child(self.render_summary())
+                    .child(steps)
+                    // Footer
+                    .child(
+                        div()
+                            .flex()
+                            .items_center()
+                            .justify_end()
+                            .gap(px(8.0))
+                            .px(px(20.0))
+                            .py(px(12.0))
+                            .border_t_1()
+                            .border_color(ShellDeckColors::border())
+                            .child(
+                                div()
+                                    .id("ssh-doctor-copy")
+                                    .cursor_pointer()
+                                    .on_click(move |_: &ClickEvent, _, cx: &mut App| {
+                                        cx.write_to_clipboard(ClipboardItem::new_string(
+                                            export.clone(),
+                                        ));
+                                    })
+                                    .child(
+                                        Button::new(
+                                            "ssh-doctor-copy-btn",
+                                            t!("ssh_doctor.copy").to_string(),
+                                        )
+                                        .variant(ButtonVariant::Outline),
+                                    ),
+                            )
+                            .child(run_again),
+                    ),
+            )
+    }
+}
+
+fn status_icon(status: StepStatus) -> (&'static str, Hsla) {
+    match status {
+        StepStatus::Skipped => ("icons/lucide/minus.svg", ShellDeckColors::text_muted()),
+        StepStatus::Ok => ("icons/lucide/circle-check.svg", ShellDeckColors::success()),
+        StepStatus::Warning => (
+            "icons/lucide/triangle-alert.svg",
+            ShellDeckColors::warning(),
+        ),
+        StepStatus::Failed => ("icons/lucide/circle-alert.svg", ShellDeckColors::error()),
+    }
+}
+
+fn stage_label(step: &DiagnosticStep) -> String {
+    t!(&format!("ssh_doctor.stage.{}", step.stage.code())).to_string()
+}
+
+fn fix_text(step: &DiagnosticStep) -> Option<String> {
+    step.fix
+        .map(|fix| t!(&format!("ssh_doctor.fix.{}", fix.code())).to_string())
+}
+
+fn target_text(report: &DiagnosticReport) -> String {
+    match &report.proxy_jump {
+        Some(jump) => t!(
+            "ssh_doctor.target_via",
+            target = report.target.as_str(),
+            jump = jump.as_str()
+        )
+        .to_string(),
+        None => report.target.clone(),
+    }
+}
+
+fn outcome_text(report: &DiagnosticReport) -> String {
+    match report.first_failure() {
+        Some(step) => t!("ssh_doctor.outcome.failed", stage = stage_label(step)).to_string(),
+        None if report.outcome() == StepStatus::Warning => {
+            t!("ssh_doctor.outcome.warning").to_string()
+        }
+        None => t!("ssh_doctor.outcome.ok").to_string(),
+    }
+}
+
+/// Plain-text report for pasting into a ticket or chat.
+fn report_text(report: &DiagnosticReport) -> String {
+    let mut out = String::new();
+    out.push_str(
+        &t!(
+            "ssh_doctor.export.title",
+            name = report.connection_name.as_str()
+        )
+        .to_string(),
+    );
+    out.push('\n');
+    out.push_str(&target_text(report));
+    out.push('\n');
+    out.push_str(
+        &report
+            .started_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+    );
+    out.push('\n');
+    out.push_str(&if report.finished {
+        outcome_text(report)
+    } else {
+        t!("ssh_doctor.running").to_string()
+    });
+    out.push('\n');
+    for host in report.hosts() {
+        out.push('\n');
+        out.push_str(host);
+        out.push('\n');
+        for step in report.steps.iter().filter(|s| s.host == host) {
+            let marker = match step.status {
+                StepStatus::Skipped => "[skip]",
+                StepStatus::Ok => "[ok]",
+                StepStatus::Warning => "[warn]",
+                StepStatus::Failed => "[FAIL]",
+            };
+            out.push_str(&format!("  {:<6} {}", marker, stage_label(step)));
+            if let Some(ms) = step.elapsed_ms {
+                out.push_str(&format!(" ({})", t!("ssh_doctor.elapsed", ms = ms)));
+            }
+            out.push('\n');
+            if !step.detail.is_empty() {
+                out.push_str(&format!("         {}\n", step.detail));
+            }
+            if let Some(fix) = fix_text(step) {
+                out.push_str(&format!("         → {}\n", fix));
+            }
+        }
+    }
+    out
+}
@@ -6,6 +6,7 @@ use gpui::prelude::*;
 use gpui::*;
 use parking_lot::Mutex;
 use shelldeck_terminal::colors::{NamedColor, TermColor};
+use shelldeck_terminal::copy_mode::{CopyMode, CopyModeAction, SearchDirection, VisualMode};
 use shelldeck_terminal::grid::{
     CellWidth, CursorShape, CursorState, MouseEncoding, MouseMode, SearchMatch, TerminalGrid,
     UnderlineStyle,
@@ -70,6 +71,7 @@ actions!(
         ZoomOut,
         ZoomReset,
         ToggleSplitFocus,
+        ToggleCopyMode,
     ]
 );
 
@@ -191,6 +193,11 @@ pub struct TerminalView {
     search_current_idx: Option<usize>,
     search_case_sensitive: bool,
     search_regex: bool,
+    /// Keyboard copy mode over the focused session's scrollback, keyed by the
+    /// session it was entered on.
+    copy_mode: Option<(Uuid, CopyMode)>,
+    /// Set while the search bar is collecting a copy-mode `/` or `?` query.
+    copy_search: Option<SearchDirection>,
     /// Detected URLs in the focused pane's visible grid. Recomputed only when
     /// the focused grid changes (dirty) or the focused session changes, not on
     /// every repaint (e.g. cursor-blink frames keep the cached result).
@@ -342,6 +349,8 @@ impl TerminalView {
             search_current_idx: None,
             search_case_sensitive: false,
             search_regex: false,
+            copy_mode: None,
+            copy_search: None,
             detected_urls: Vec::new(),
             last_url_session: None,
             _hovered_url: None,
@@ -2913,6 +2922,10 @@ impl TerminalView {
                                     this.toggle_search();
                                     cx.notify();
                                 }
+                                "enter" if this.copy_search.is_some() => {
+                                    this.submit_copy_search();
+                                    cx.notify();
+                                }
                                 "enter" => {
                                     if event.keystroke.modifiers.shift {
                                         this.search_prev();
@@ -2949,6 +2962,17 @@ impl TerminalView {
                             return;
                         }
 
+                        if let Some((copy_session, _)) = this.copy_mode {
+                            // Copy mode follows its session; switching tabs or
+                            // panes drops back to normal input.
+                            if this.active_session().map(|s| s.id) == Some(copy_session) {
+                                this.handle_copy_mode_key(event, cx);
+                                cx.notify();
+                                return;
+                            }
+                            this.exit_copy_mode();
+                        }
+
                         // Normal terminal input
                         let app_cursor = this
                             .active_session()
@@ -3021,6 +3045,17 @@ impl TerminalView {
                     }
                 }
             })
+            .on_action({
+                let h = cx.entity().downgrade();
+                move |_: &ToggleCopyMode, _window: &mut Window, cx: &mut App| {
+                    if let Some(view) = h.upgrade() {
+                        view.update(cx, |this, cx| {
+                            this.toggle_copy_mode();
+                            cx.notify();
+                        });
+                    }
+                }
+            })
             .on_action({
                 let h = cx.entity().downgrade();
                 move |_: &ClearTerminal, _window: &mut Window, cx: &mut App| {
@@ -3714,6 +3749,7 @@ impl TerminalView {
     fn toggle_search(&mut self) {
         self.search_visible = !self.search_visible;
         if !self.search_visible {
+            self.copy_search = None;
             self.search_query.clear();
             self.search_matches.clear();
             self.search_current_idx = None;
@@ -3869,6 +3905,106 @@ impl TerminalView {
             )
     }
 
+    /// Enter or leave keyboard copy mode on the active session.
+    fn toggle_copy_mode(&mut self) {
+        if self.copy_mode.is_some() {
+            self.exit_copy_mode();
+            return;
+        }
+        if let Some(session) = self.active_session() {
+            let id = session.id;
+            let mode = CopyMode::new(&mut session.grid.lock());
+            self.copy_mode = Some((id, mode));
+        }
+    }
+
+    fn exit_copy_mode(&mut self) {
+        if let Some((id, mode)) = self.copy_mode.take() {
+            if let Some(session) = self.session_by_id(id) {
+                mode.exit(&mut session.grid.lock());
+            }
+        }
+        if self.copy_search.is_some() && self.search_visible {
+            self.toggle_search();
+        }
+    }
+
+    /// Route a key press to copy mode and act on the result.
+    fn handle_copy_mode_key(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
+        let Some((id, mut mode)) = self.copy_mode.take() else {
+            return;
+        };
+        let Some(grid) = self.session_by_id(id).map(|s| s.grid.clone()) else {
+            return;
+        };
+        let keystroke = &event.keystroke;
+        // Prefer the produced character so `G`, `$` and `?` arrive as typed.
+        let key = match keystroke.key_char.as_deref() {
+            Some(ch) if !keystroke.modifiers.control && ch.chars().count() == 1 => ch,
+            _ => keystroke.key.as_str(),
+        };
+        let action = mode.handle_key(&mut grid.lock(), key, keystroke.modifiers.control);
+        match action {
+            CopyModeAction::None => self.copy_mode = Some((id, mode)),
+            CopyModeAction::Yank(text) => {
+                if !text.is_empty() {
+                    cx.write_to_clipboard(ClipboardItem::new_string(text));
+                }
+                mode.exit(&mut grid.lock());
+            }
+            CopyModeAction::Exit => mode.exit(&mut grid.lock()),
+            CopyModeAction::Search(direction) => {
+                self.copy_mode = Some((id, mode));
+                if !self.search_visible {
+                    self.toggle_search();
+                }
+                self.search_query.clear();
+                self.search_matches.clear();
+                self.search_current_idx = None;
+                self.copy_search = Some(direction);
+            }
+        }
+    }
+
+    /// Submit the search bar query as a copy-mode `/` or `?` search.
+    fn submit_copy_search(&mut self) {
+        let Some(direction) = self.copy_search.take() else {
+            return;
+        };
+        let query = self.search_query.clone();
+        let grid = self
+            .copy_mode
+            .as_ref()
+            .and_then(|(id, _)| self.session_by_id(*id))
+            .map(|session| session.grid.clone());
+        if let (Some((_, mode)), Some(grid)) = (self.copy_mode.as_mut(), grid) {
+            mode.search(&mut grid.lock(), &query, direction);
+        }
+        self.toggle_search();
+    }
+
+    /// Small status badge shown while copy mode is active.
+    fn render_copy_mode_badge(&self, mode: &CopyMode) -> impl IntoElement {
+        let label = match mode.visual_mode() {
+            None => t!("terminal.copy_mode.normal"),
+            Some(VisualMode::Char) => t!("terminal.copy_mode.visual"),
+            Some(VisualMode::Line) => t!("terminal.copy_mode.visual_line"),
+            Some(VisualMode::Block) => t!("terminal.copy_mode.visual_block"),
+        };
+        div()
+            .absolute()
+            .bottom(px(8.0))
+            .right(px(16.0))
+            .px(px(8.0))
+            .py(px(3.0))
+            .bg(ShellDeckColors::primary())
+            .rounded(px(4.0))
+            .text_size(px(11.0))
+            .font_weight(FontWeight::SEMIBOLD)
+            .text_color(ShellDeckColors::bg_primary())
+            .child(label.to_string())
+    }
+
     /// Paste clipboard content into the terminal.
     fn paste_clipboard(&self, cx: &App) {
         if let Some(item) = cx.read_from_clipboard() {
@@ -4729,6 +4865,18 @@ impl TerminalView {
                         format!("{}F", cmd),
                         t!("terminal.shortcut.search").to_string(),
                     ))
+                    .child(shortcut_row(
+                        format!(
+                            "{}{}Space",
+                            if cfg!(target_os = "macos") {
+                                "\u{2303}"
+                            } else {
+                                "Ctrl+"
+                            },
+                            shift
+                        ),
+                        t!("terminal.shortcut.copy_mode").to_string(),
+                    ))
                     .child(shortcut_row(
                         format!("{}B", cmd),
                         t!("terminal.shortcut.toggle_sidebar").to_string(),
@@ -4903,6 +5051,23 @@ impl Render for TerminalView {
                         (g.mouse_mode, g.mouse_encoding, g.cursor.clone())
                     };
                     cursor.shape = self.effective_cursor_shape(cursor.shape);
+                    // In copy mode the painted cursor is the copy-mode cursor,
+                    // hidden while it is scrolled out of view.
+                    let copy_mode = self
+                        .copy_mode
+                        .as_ref()
+                        .filter(|(sid, _)| Some(*sid) == focused_session)
+                        .map(|(_, mode)| mode.clone());
+                    if let Some(mode) = &copy_mode {
+                        let display = mode.display_cursor(&grid_arc.lock());
+                        cursor.visible = display.is_some();
+                        if let Some(pos) = display {
+                            cursor.row = pos.row;
+                            cursor.col = pos.col;
+                        }
+                        cursor.shape = CursorShape::Block;
+                        cursor.blink = false;
+                    }
                     wrapper = wrapper.child(self.render_terminal_grid(
                         mouse_mode,
                         mouse_encoding,
@@ -4911,6 +5076,9 @@ impl Render for TerminalView {
                         grid_arc,
                         cx,
                     ));
+                    if let Some(mode) = &copy_mode {
+                        wrapper = wrapper.child(self.render_copy_mode_badge(mode));
+                    }
                     if self.search_visible {
                         wrapper = wrapper.child(self.render_search_bar(cx));
                     }
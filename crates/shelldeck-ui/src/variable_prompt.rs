@@ -5,7 +5,10 @@ use adabraka_ui::components::input::{Input, InputSize, InputState};
 use gpui::prelude::*;
 use gpui::*;
 
-use shelldeck_core::models::script::{Script, ScriptVariable};
+use shelldeck_core::config::keychain;
+use shelldeck_core::models::script::{
+    validate_variables, Script, ScriptVariable, VariableError, VariableKind,
+};
 
 use crate::t;
 use crate::theme::ShellDeckColors;
@@ -19,21 +22,68 @@ pub enum VariablePromptEvent {
 
 impl EventEmitter<VariablePromptEvent> for VariablePrompt {}
 
+fn variable_error_message(err: VariableError) -> String {
+    match err {
+        VariableError::Required => t!("variable_prompt.error.required"),
+        VariableError::NotAnInteger => t!("variable_prompt.error.not_integer"),
+        VariableError::NotABoolean => t!("variable_prompt.error.not_boolean"),
+        VariableError::NotAChoice => t!("variable_prompt.error.not_choice"),
+        VariableError::PatternMismatch => t!("variable_prompt.error.pattern"),
+        VariableError::InvalidPattern => t!("variable_prompt.error.invalid_pattern"),
+    }
+    .to_string()
+}
+
+/// Whether a variable is picked from a list rather than typed.
+fn is_picked(kind: &VariableKind) -> bool {
+    matches!(
+        kind,
+        VariableKind::Bool | VariableKind::Choice(_) | VariableKind::Connection
+    )
+}
+
 pub struct VariablePrompt {
     script: Script,
     variables: Vec<ScriptVariable>,
     /// One `InputState` per variable, initialised with the variable's default.
     /// Cursor / selection / undo are owned by each widget individually.
     states: Vec<Entity<InputState>>,
+    /// Current pick of each bool / choice / connection variable.
+    picks: Vec<Option<String>>,
+    /// `(label, host name)` of every saved connection, for connection pickers.
+    connections: Vec<(String, String)>,
+    /// Validation errors of the last submit attempt, by variable name.
+    errors: HashMap<String, VariableError>,
     focus_handle: FocusHandle,
 }
 
 impl VariablePrompt {
-    pub fn new(script: Script, variables: Vec<ScriptVariable>, cx: &mut Context<Self>) -> Self {
-        let states: Vec<Entity<InputState>> = variables
+    pub fn new(
+        script: Script,
+        variables: Vec<ScriptVariable>,
+        connections: Vec<(String, String)>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let initial_values: Vec<String> = variables
             .iter()
             .map(|v| {
-                let initial = v.default_value.clone().unwrap_or_default();
+                if v.is_secret() {
+                    // Prefill the last value used for this secret.
+                    keychain::get_script_secret(script.id, &v.name)
+                        .unwrap_or_else(|e| {
+                            tracing::warn!("Failed to read secret {}: {}", v.name, e);
+                            None
+                        })
+                        .unwrap_or_default()
+                } else {
+                    v.default_value.clone().unwrap_or_default()
+                }
+            })
+            .collect();
+        let states: Vec<Entity<InputState>> = initial_values
+            .iter()
+            .map(|initial| {
+                let initial = initial.clone();
                 cx.new(|cx| {
                     let mut s = InputState::new(cx);
                     if !initial.is_empty() {
@@ -43,10 +93,20 @@ impl VariablePrompt {
                 })
             })
             .collect();
+        let picks = variables
+            .iter()
+            .zip(&initial_values)
+            .map(|(v, initial)| {
+                (is_picked(&v.kind) && !initial.is_empty()).then(|| initial.clone())
+            })
+            .collect();
         Self {
             script,
             variables,
             states,
+            picks,
+            connections,
+            errors: HashMap::new(),
             focus_handle: cx.focus_handle(),
         }
     }
@@ -78,48 +138,185 @@ impl VariablePrompt {
         }
     }
 
-    pub fn submit(&mut self, cx: &mut Context<Self>) {
+    /// Non-empty values of every variable, active or not.
+    fn current_values(&self, cx: &App) -> HashMap<String, String> {
         let mut map = HashMap::new();
         for (i, var) in self.variables.iter().enumerate() {
-            let value = self
-                .states
-                .get(i)
-                .map(|s| s.read(cx).content().to_string())
-                .unwrap_or_default();
+            let value = if is_picked(&var.kind) {
+                self.picks.get(i).cloned().flatten().unwrap_or_default()
+            } else {
+                self.states
+                    .get(i)
+                    .map(|s| s.read(cx).content().to_string())
+                    .unwrap_or_default()
+            };
+            let value = value.trim().to_string();
             if !value.is_empty() {
                 map.insert(var.name.clone(), value);
             }
         }
+        map
+    }
+
+    pub fn submit(&mut self, cx: &mut Context<Self>) {
+        let all = self.current_values(cx);
+        let mut map = all.clone();
+        for var in &self.variables {
+            if !var.is_active(&all) {
+                map.remove(&var.name);
+            }
+        }
+
+        self.errors = validate_variables(&self.variables, &map)
+            .into_iter()
+            .collect();
+        if !self.errors.is_empty() {
+            cx.notify();
+            return;
+        }
+
+        for var in self.variables.iter().filter(|v| v.is_secret()) {
+            let result = match map.get(&var.name) {
+                Some(value) => keychain::store_script_secret(self.script.id, &var.name, value),
+                None => keychain::delete_script_secret(self.script.id, &var.name),
+            };
+            if let Err(e) = result {
+                tracing::warn!("Failed to save secret {}: {}", var.name, e);
+            }
+        }
         cx.emit(VariablePromptEvent::Run(self.script.clone(), map));
     }
+
+    fn set_pick(&mut self, idx: usize, value: String, cx: &mut Context<Self>) {
+        if let Some(pick) = self.picks.get_mut(idx) {
+            // Clicking the current pick clears it.
+            *pick = if pick.as_deref() == Some(value.as_str()) {
+                None
+            } else {
+                Some(value)
+            };
+        }
+        if let Some(var) = self.variables.get(idx) {
+            self.errors.remove(&var.name);
+        }
+        cx.notify();
+    }
+
+    /// Chip row for bool / choice / connection variables.
+    fn render_picker(&self, idx: usize, var: &ScriptVariable, cx: &mut Context<Self>) -> Div {
+        let options: Vec<(String, String)> = match &var.kind {
+            VariableKind::Bool => vec![
+                (t!("variable_prompt.yes").to_string(), "true".to_string()),
+                (t!("variable_prompt.no").to_string(), "false".to_string()),
+            ],
+            VariableKind::Choice(options) => {
+                options.iter().map(|o| (o.clone(), o.clone())).collect()
+            }
+            VariableKind::Connection => self.connections.clone(),
+            _ => Vec::new(),
+        };
+        let current = self.picks.get(idx).cloned().flatten();
+
+        let mut row = div().flex().flex_wrap().gap(px(6.0));
+        if options.is_empty() {
+            return row.child(
+                div()
+                    .text_size(px(11.0))
+                    .text_color(ShellDeckColors::text_muted())
+                    .child(t!("variable_prompt.no_connections").to_string()),
+            );
+        }
+        for (opt_idx, (label, value)) in options.into_iter().enumerate() {
+            let selected = current.as_deref() == Some(value.as_str());
+            row = row.child(
+                div()
+                    .id(ElementId::Name(
+                        format!("var-pick-{}-{}", idx, opt_idx).into(),
+                    ))
+                    .px(px(10.0))
+                    .py(px(4.0))
+                    .rounded(px(6.0))
+                    .border_1()
+                    .text_size(px(12.0))
+                    .cursor_pointer()
+                    .when(selected, |el| {
+                        el.bg(ShellDeckColors::primary().opacity(0.15))
+                            .border_color(ShellDeckColors::primary())
+                            .text_color(ShellDeckColors::primary())
+                    })
+                    .when(!selected, |el| {
+                        el.border_color(ShellDeckColors::border())
+                            .text_color(ShellDeckColors::text_primary())
+                            .hover(|el| el.bg(ShellDeckColors::hover_bg()))
+                    })
+                    .on_click(cx.listener(move |this, _: &ClickEvent, _, cx| {
+                        this.set_pick(idx, value.clone(), cx);
+                    }))
+                    .child(label),
+            );
+        }
+        row
+    }
 }
 
 impl Render for VariablePrompt {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         self.focus_handle.focus(window);
 
-        let field_count = self.variables.len();
+        let values = self.current_values(cx);
+        let variables = self.variables.clone();
+        let field_count = variables.iter().filter(|v| v.is_active(&values)).count();
 
-        // Build variable fields — each is a real `Input`. Enter submits the
-        // whole prompt (matches the pre-migration behavior).
+        // Build variable fields — typed values are real `Input`s, where Enter
+        // submits the whole prompt; bool / choice / connection values are
+        // picked from chips. Fields whose dependency is unmet are hidden.
         let mut fields = div().flex().flex_col().gap(px(12.0));
 
-        for (idx, var) in self.variables.iter().enumerate() {
-            let label_text = self.display_label(var);
-            let placeholder = SharedString::from(format!("{{{{{}}}}}", var.name));
-            let Some(state) = self.states.get(idx) else {
+        for (idx, var) in variables.iter().enumerate() {
+            if !var.is_active(&values) {
                 continue;
-            };
+            }
+            let mut label_text = self.display_label(var);
+            if var.required {
+                label_text.push_str(" *");
+            }
+            let error = self.errors.get(&var.name).copied();
 
-            let input = Input::new(state)
-                .size(InputSize::Sm)
-                .placeholder(placeholder)
-                .on_enter({
-                    let entity = cx.entity();
-                    move |_v, cx| {
-                        entity.update(cx, |this, cx| this.submit(cx));
-                    }
-                });
+            let control = if is_picked(&var.kind) {
+                self.render_picker(idx, var, cx).into_any_element()
+            } else {
+                let Some(state) = self.states.get(idx) else {
+                    continue;
+                };
+                let placeholder = match var.kind {
+                    VariableKind::Int => t!("variable_prompt.placeholder.int").to_string(),
+                    VariableKind::Path => t!("variable_prompt.placeholder.path").to_string(),
+                    _ => format!("{{{{{}}}}}", var.name),
+                };
+                Input::new(state)
+                    .size(InputSize::Sm)
+                    .placeholder(SharedString::from(placeholder))
+                    .password(var.is_secret())
+                    .error(error.is_some())
+                    .on_change({
+                        let entity = cx.entity();
+                        let name = var.name.clone();
+                        move |_v, cx| {
+                            entity.update(cx, |this, cx| {
+                                // Re-render so dependent fields follow the value.
+                                this.errors.remove(&name);
+                                cx.notify();
+                            });
+                        }
+                    })
+                    .on_enter({
+                        let entity = cx.entity();
+                        move |_v, cx| {
+                            entity.update(cx, |this, cx| this.submit(cx));
+                        }
+                    })
+                    .into_any_element()
+            };
 
             let mut field = div()
                 .flex()
@@ -127,12 +324,35 @@ impl Render for VariablePrompt {
                 .gap(px(4.0))
                 .child(
                     div()
-                        .text_size(px(12.0))
-                        .font_weight(FontWeight::MEDIUM)
-                        .text_color(ShellDeckColors::text_primary())
-                        .child(label_text),
+                        .flex()
+                        .items_center()
+                        .gap(px(6.0))
+                        .child(
+                            div()
+                                .text_size(px(12.0))
+                                .font_weight(FontWeight::MEDIUM)
+                                .text_color(ShellDeckColors::text_primary())
+                                .child(label_text),
+                        )
+                        .when(var.is_secret(), |el| {
+                            el.child(
+                                div()
+                                    .text_size(px(10.0))
+                                    .text_color(ShellDeckColors::text_muted())
+                                    .child(t!("variable_prompt.secret_hint").to_string()),
+                            )
+                        }),
                 )
-                .child(input);
+                .child(control);
+
+            if let Some(err) = error {
+                field = field.child(
+                    div()
+                        .text_size(px(11.0))
+                        .text_color(ShellDeckColors::error())
+                        .child(variable_error_message(err)),
+                );
+            }
 
             if let Some(ref desc) = var.description {
                 field = field.child(
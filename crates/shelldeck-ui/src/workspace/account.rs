@@ -470,6 +470,16 @@ impl Workspace {
         self._ai_workflow_sub = None;
         self.connection_form = None;
         self._form_sub = None;
+        self.connection_import = None;
+        self._connection_import_sub = None;
+        self.key_manager = None;
+        self._key_manager_sub = None;
+        self.ssh_doctor = None;
+        self._ssh_doctor_sub = None;
+        self.host_metrics_view = None;
+        self._host_metrics_sub = None;
+        self.log_viewer = None;
+        self._log_viewer_sub = None;
         self.port_forward_form = None;
         self._pf_form_sub = None;
         self.script_form = None;
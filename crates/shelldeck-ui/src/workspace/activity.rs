@@ -89,6 +89,24 @@ impl Workspace {
                 self.on_active_view_changed(cx);
                 cx.notify();
             }
+            ActivityAction::DiagnoseConnection => {
+                if let Some(id) = entry
+                    .target_id
+                    .as_deref()
+                    .and_then(|id| Uuid::parse_str(id).ok())
+                {
+                    self.show_ssh_doctor(id, cx);
+                }
+            }
+            ActivityAction::OpenHostMetrics => {
+                if let Some(id) = entry
+                    .target_id
+                    .as_deref()
+                    .and_then(|id| Uuid::parse_str(id).ok())
+                {
+                    self.show_host_metrics(id, cx);
+                }
+            }
             ActivityAction::OpenForward => {
                 self.activate_dev_section(SidebarSection::PortForwards, cx);
             }
@@ -100,7 +118,7 @@ impl Workspace {
                 self.activate_dev_section(SidebarSection::Scripts, cx);
                 if let Some(id) = script_id {
                     self.scripts.update(cx, |editor, cx| {
-                        editor.selected_script = Some(id);
+                        editor.select_script(Some(id), cx);
                         cx.notify();
                     });
                 }
@@ -175,14 +193,37 @@ impl Workspace {
             match event {
                 ConnectionFormEvent::Save(conn) => {
                     tracing::info!("Connection saved: {}", conn.display_name());
+                    let ssh_config_host = this
+                        .connections
+                        .iter()
+                        .find(|c| c.id == conn.id && c.source == ConnectionSource::SshConfig)
+                        .cloned();
+                    // The form only edits the SSH settings; a host read from
+                    // the SSH config keeps its source and linked forwards.
+                    let conn = match &ssh_config_host {
+                        Some(previous) => Connection {
+                            alias: conn.alias.clone(),
+                            hostname: conn.hostname.clone(),
+                            port: conn.port,
+                            user: conn.user.clone(),
+                            identity_file: conn.identity_file.clone(),
+                            proxy_jump: conn.proxy_jump.clone(),
+                            group: conn.group.clone(),
+                            forward_agent: conn.forward_agent,
+                            ..previous.clone()
+                        },
+                        None => conn.clone(),
+                    };
                     // Add to connections list
                     if let Some(idx) = this.connections.iter().position(|c| c.id == conn.id) {
                         this.connections[idx] = conn.clone();
                     } else {
                         this.connections.push(conn.clone());
                     }
-                    // Persist to store
-                    if let Err(e) = this.store.add_connection(conn.clone()) {
+                    // Persist to its Host block or to the store
+                    if let Some(previous) = &ssh_config_host {
+                        this.write_ssh_config_host(&previous.alias, &conn, cx);
+                    } else if let Err(e) = this.store.add_connection(conn.clone()) {
                         tracing::error!("Failed to save connection store: {}", e);
                         this.show_toast(
                             t!("toast.connection.save_failed", error = e.to_string()).to_string(),
@@ -194,6 +235,8 @@ impl Workspace {
                     this.sidebar.update(cx, |sidebar, _| {
                         sidebar.set_connections(this.connections.clone());
                     });
+                    // A monitored host is read with the new settings from now on.
+                    this.restart_metrics_collector(conn.id, cx);
                     let conn_name = conn.display_name().to_string();
                     this.add_activity_entry(
                         ActivityEntry::new(
@@ -231,6 +274,64 @@ impl Workspace {
         cx.notify();
     }
 
+    /// Open the import preview for connections exported by another client.
+    pub fn show_connection_import(&mut self, cx: &mut Context<Self>) {
+        if !self.enter_dev_mode(cx) {
+            return;
+        }
+        let existing = self.connections.clone();
+        let view = cx.new(|view_cx| ConnectionImportView::new(existing, view_cx));
+
+        let sub = cx.subscribe(
+            &view,
+            |this, _view, event: &ConnectionImportEvent, cx| match event {
+                ConnectionImportEvent::Import(connections, source) => {
+                    let count = connections.len();
+                    tracing::info!("Importing {} connections from {}", count, source);
+                    if let Err(e) = this.store.add_connections_bulk(connections.clone()) {
+                        tracing::error!("Failed to save imported connections: {}", e);
+                        this.show_toast(
+                            t!("toast.connection.save_failed", error = e.to_string()).to_string(),
+                            ToastLevel::Error,
+                            cx,
+                        );
+                        return;
+                    }
+                    this.connections.extend(connections.iter().cloned());
+                    this.publish_connections(cx);
+                    this.add_activity(
+                        t!(
+                            "activity.connections_imported",
+                            count = count,
+                            source = *source
+                        )
+                        .to_string(),
+                        ActivityKind::Connection,
+                        cx,
+                    );
+                    this.show_toast(
+                        t!("toast.connection.imported", count = count, source = *source)
+                            .to_string(),
+                        ToastLevel::Success,
+                        cx,
+                    );
+                    this.connection_import = None;
+                    this._connection_import_sub = None;
+                    cx.notify();
+                }
+                ConnectionImportEvent::Cancel => {
+                    this.connection_import = None;
+                    this._connection_import_sub = None;
+                    cx.notify();
+                }
+            },
+        );
+
+        self.connection_import = Some(view);
+        self._connection_import_sub = Some(sub);
+        cx.notify();
+    }
+
     pub(super) fn add_activity(
         &mut self,
         message: String,
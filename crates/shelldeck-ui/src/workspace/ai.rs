@@ -2352,7 +2352,7 @@ impl Workspace {
                 if let Ok(script_id) = Uuid::parse_str(&task.target_id) {
                     self.active_view = ActiveView::Scripts;
                     self.scripts.update(cx, |view, cx| {
-                        view.selected_script = Some(script_id);
+                        view.select_script(Some(script_id), cx);
                         cx.notify();
                     });
                 }
@@ -1259,6 +1259,33 @@ impl Workspace {
                     }
                 }),
             ))
+            .child(item(
+                "doctor",
+                "Diagnose…",
+                ShellDeckColors::primary(),
+                false,
+                Box::new(move |this, cx| {
+                    this.show_ssh_doctor(conn_id, cx);
+                }),
+            ))
+            .child(item(
+                "metrics",
+                "Host metrics…",
+                ShellDeckColors::primary(),
+                false,
+                Box::new(move |this, cx| {
+                    this.show_host_metrics(conn_id, cx);
+                }),
+            ))
+            .child(item(
+                "logs",
+                "Tail logs…",
+                ShellDeckColors::primary(),
+                false,
+                Box::new(move |this, cx| {
+                    this.show_log_viewer(Some(conn_id), cx);
+                }),
+            ))
             .child(item(
                 "bext",
                 "Manage bext…",
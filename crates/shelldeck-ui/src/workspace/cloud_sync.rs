@@ -115,19 +115,6 @@ impl Workspace {
 
         self.store = store;
         self.connections = merged;
-
-        let conns = self.connections.clone();
-        self.sidebar.update(cx, |sidebar, cx| {
-            sidebar.set_connections(conns.clone());
-            cx.notify();
-        });
-        self.server_sync.update(cx, |view, cx| {
-            view.set_connections(conns.clone(), cx);
-        });
-        self.sites.update(cx, |view, _| {
-            view.set_connections(conns);
-        });
-        self.update_dashboard_stats(cx);
-        cx.notify();
+        self.publish_connections(cx);
     }
 }
@@ -0,0 +1,521 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use gpui::*;
+use shelldeck_core::config::activity::{ActivityEntry, ActivityKind};
+use shelldeck_core::models::config_apply::{self, WebServer};
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::managed_site::ManagedSiteType;
+use shelldeck_core::models::site_health::{probe_site, HealthSample};
+use shelldeck_ssh::session::SshSession;
+use uuid::Uuid;
+
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::db_backups::{command_error, connect};
+use super::Workspace;
+
+/// What an apply running in the background reports, in order. It ends with
+/// `Applied`, `RolledBack` or `Failed`.
+enum ApplyProgress {
+    /// The new config is on the server at this temp path.
+    Staged(String),
+    /// The new config replaced the file at `target`; the old one is at
+    /// `backup`.
+    Installed {
+        target: String,
+        backup: String,
+    },
+    /// The server's checker ran on the new config.
+    Checked {
+        passed: bool,
+        output: String,
+    },
+    Reloaded,
+    /// The site was probed after the reload.
+    Probed(HealthSample),
+    Applied,
+    /// The probe failed, so the old config is back in place and loaded.
+    RolledBack,
+    Failed(String),
+}
+
+impl ApplyProgress {
+    fn is_final(&self) -> bool {
+        matches!(
+            self,
+            ApplyProgress::Applied | ApplyProgress::RolledBack | ApplyProgress::Failed(_)
+        )
+    }
+}
+
+/// Everything the apply thread needs, captured on the UI thread.
+struct ApplyJob {
+    connection: Connection,
+    server: WebServer,
+    config_path: String,
+    content: String,
+    /// URL probed before and after the change, if the site has one.
+    url: Option<String>,
+    keyword: Option<String>,
+    timeout: Duration,
+}
+
+impl Workspace {
+    /// Read a web site's config from its server into the editor.
+    pub(super) fn load_site_config(&mut self, site_id: Uuid, cx: &mut Context<Self>) {
+        let Some((connection, _, config_path)) = self.site_config_target(site_id) else {
+            self.sites.update(cx, |view, cx| {
+                view.set_site_config(
+                    site_id,
+                    Err(t!("sites.config.error.no_server").to_string()),
+                    cx,
+                );
+                cx.notify();
+            });
+            return;
+        };
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<Result<String, String>>();
+        let spawn_result = std::thread::Builder::new()
+            .name(format!("site-config-read-{}", site_id))
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        let _ = done_tx.send(Err(format!("async runtime: {}", e)));
+                        return;
+                    }
+                };
+                let result = rt.block_on(async {
+                    let session = connect(&connection).await?;
+                    match session
+                        .exec(&config_apply::read_command(&config_path))
+                        .await
+                    {
+                        Ok(r) if r.success() => Ok(r.stdout_string()),
+                        Ok(r) => Err(command_error(&r.stderr, r.exit_code)),
+                        Err(e) => Err(e.to_string()),
+                    }
+                });
+                let _ = done_tx.send(result);
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn config read thread: {}", e);
+            return;
+        }
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let result = loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(250))
+                    .await;
+                match done_rx.try_recv() {
+                    Ok(result) => break result,
+                    Err(std::sync::mpsc::TryRecvError::Empty) => continue,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        break Err("config read thread ended unexpectedly".to_string())
+                    }
+                }
+            };
+            let _ = this.update(cx, |ws, cx| {
+                ws.sites.update(cx, |view, cx| {
+                    view.set_site_config(site_id, result, cx);
+                    cx.notify();
+                });
+            });
+        })
+        .detach();
+    }
+
+    /// Check, install and reload an edited config in the background,
+    /// recording each step in the activity feed. Only one apply per site
+    /// runs at a time.
+    pub(super) fn apply_site_config(
+        &mut self,
+        site_id: Uuid,
+        content: String,
+        cx: &mut Context<Self>,
+    ) {
+        if self.config_applies.contains(&site_id) {
+            return;
+        }
+        let Some(site) = self.store.managed_sites.iter().find(|s| s.id == site_id) else {
+            return;
+        };
+        let name = site.name().to_string();
+        let url = site.monitor.target_url(site);
+        let keyword = site.monitor.keyword.clone();
+        let Some((connection, server, config_path)) = self.site_config_target(site_id) else {
+            self.show_toast(
+                t!("sites.config.error.no_server").to_string(),
+                ToastLevel::Error,
+                cx,
+            );
+            return;
+        };
+        let job = ApplyJob {
+            connection,
+            server,
+            config_path,
+            content,
+            url,
+            keyword,
+            timeout: Duration::from_secs(self.app_config.site_health.timeout_secs.max(1)),
+        };
+
+        let (progress_tx, progress_rx) = std::sync::mpsc::channel::<ApplyProgress>();
+        let spawn_result = std::thread::Builder::new()
+            .name(format!("site-config-apply-{}", site_id))
+            .spawn(move || run_apply(job, progress_tx));
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn config apply thread: {}", e);
+            return;
+        }
+        self.config_applies.insert(site_id);
+        self.sites.update(cx, |view, cx| {
+            view.set_config_applying(site_id, true);
+            cx.notify();
+        });
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor()
+                .timer(Duration::from_millis(250))
+                .await;
+            let mut batch = Vec::new();
+            let finished = loop {
+                match progress_rx.try_recv() {
+                    Ok(progress) => {
+                        let last = progress.is_final();
+                        batch.push(progress);
+                        if last {
+                            break true;
+                        }
+                    }
+                    Err(std::sync::mpsc::TryRecvError::Empty) => break false,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        batch.push(ApplyProgress::Failed(
+                            "config apply thread ended unexpectedly".to_string(),
+                        ));
+                        break true;
+                    }
+                }
+            };
+            let updated = this.update(cx, |ws, cx| {
+                for progress in batch {
+                    ws.config_apply_progress(site_id, server, &name, progress, cx);
+                }
+                if finished {
+                    ws.config_applies.remove(&site_id);
+                    ws.sites.update(cx, |view, cx| {
+                        view.set_config_applying(site_id, false);
+                        cx.notify();
+                    });
+                }
+            });
+            if finished || updated.is_err() {
+                break;
+            }
+        })
+        .detach();
+    }
+
+    /// The server, web server and config file behind a web site.
+    fn site_config_target(&self, site_id: Uuid) -> Option<(Connection, WebServer, String)> {
+        let site = self.store.managed_sites.iter().find(|s| s.id == site_id)?;
+        let server = WebServer::for_site(&site.site_type)?;
+        let config_path = match &site.site_type {
+            ManagedSiteType::NginxSite(s)
+            | ManagedSiteType::ApacheSite(s)
+            | ManagedSiteType::CaddySite(s) => s.config_path.clone(),
+            _ => return None,
+        };
+        let connection = self
+            .connections
+            .iter()
+            .find(|c| c.id == site.connection_id)?
+            .clone();
+        Some((connection, server, config_path))
+    }
+
+    /// Record one step of an apply in the activity feed and the editor.
+    fn config_apply_progress(
+        &mut self,
+        site_id: Uuid,
+        server: WebServer,
+        name: &str,
+        progress: ApplyProgress,
+        cx: &mut Context<Self>,
+    ) {
+        let (message, failed) = match progress {
+            ApplyProgress::Staged(path) => (
+                t!(
+                    "activity.config_apply.staged",
+                    name = name,
+                    path = path.as_str()
+                )
+                .to_string(),
+                false,
+            ),
+            ApplyProgress::Installed { target, backup } => (
+                t!(
+                    "activity.config_apply.installed",
+                    path = target.as_str(),
+                    backup = backup.as_str()
+                )
+                .to_string(),
+                false,
+            ),
+            ApplyProgress::Checked { passed, output } => {
+                let message = if passed {
+                    t!(
+                        "activity.config_apply.check_passed",
+                        check = server.check(),
+                        name = name
+                    )
+                    .to_string()
+                } else {
+                    t!(
+                        "activity.config_apply.check_failed",
+                        check = server.check(),
+                        name = name,
+                        summary = config_apply::check_summary(&output)
+                    )
+                    .to_string()
+                };
+                self.sites.update(cx, |view, _| {
+                    view.set_config_check_output(site_id, output);
+                });
+                (message, !passed)
+            }
+            ApplyProgress::Reloaded => (
+                t!(
+                    "activity.config_apply.reloaded",
+                    server = server.label(),
+                    name = name
+                )
+                .to_string(),
+                false,
+            ),
+            ApplyProgress::Probed(sample) => {
+                let message = match &sample.failure {
+                    None => t!("activity.config_apply.probe_up", name = name).to_string(),
+                    Some(failure) => t!(
+                        "activity.config_apply.probe_down",
+                        name = name,
+                        reason = failure.message()
+                    )
+                    .to_string(),
+                };
+                let failed = !sample.is_up();
+                self.record_site_probe(site_id, sample, cx);
+                (message, failed)
+            }
+            ApplyProgress::Applied => {
+                let message = t!("activity.config_apply.applied", name = name).to_string();
+                self.show_toast(message.clone(), ToastLevel::Success, cx);
+                (message, false)
+            }
+            ApplyProgress::RolledBack => {
+                let message = t!(
+                    "activity.config_apply.rolled_back",
+                    name = name,
+                    server = server.label()
+                )
+                .to_string();
+                self.show_toast(message.clone(), ToastLevel::Warning, cx);
+                (message, true)
+            }
+            ApplyProgress::Failed(error) => {
+                tracing::warn!("Config change for {} failed: {}", name, error);
+                let message = t!(
+                    "activity.config_apply.failed",
+                    name = name,
+                    error = error.as_str()
+                )
+                .to_string();
+                self.show_toast(message.clone(), ToastLevel::Error, cx);
+                (message, true)
+            }
+        };
+        let kind = if failed {
+            ActivityKind::Error
+        } else {
+            ActivityKind::Site
+        };
+        self.add_activity_entry(
+            ActivityEntry::new(kind, message.clone()).with_target(site_id.to_string(), name),
+            cx,
+        );
+        self.sites.update(cx, |view, cx| {
+            view.push_config_step(site_id, message, failed);
+            cx.notify();
+        });
+    }
+}
+
+/// The whole apply, on its own thread. Probes are blocking, so they run
+/// between the SSH steps rather than inside the runtime.
+fn run_apply(job: ApplyJob, progress: std::sync::mpsc::Sender<ApplyProgress>) {
+    let send = |p: ApplyProgress| {
+        let _ = progress.send(p);
+    };
+    let rt = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(rt) => rt,
+        Err(e) => {
+            send(ApplyProgress::Failed(format!("async runtime: {}", e)));
+            return;
+        }
+    };
+    // Probed now rather than taken from the monitor's history, which may be
+    // stale or missing for an unmonitored site.
+    let before = job
+        .url
+        .as_deref()
+        .map(|url| probe_site(url, job.keyword.as_deref(), job.timeout));
+
+    let session = match rt.block_on(connect(&job.connection)) {
+        Ok(session) => session,
+        Err(e) => {
+            send(ApplyProgress::Failed(e));
+            return;
+        }
+    };
+    let (target, backup) = match rt.block_on(install_and_reload(&session, &job, &send)) {
+        Ok(installed) => installed,
+        Err(e) => {
+            send(ApplyProgress::Failed(e));
+            return;
+        }
+    };
+
+    let Some(url) = job.url.as_deref() else {
+        send(ApplyProgress::Applied);
+        return;
+    };
+    let after = probe_site(url, job.keyword.as_deref(), job.timeout);
+    let roll_back = config_apply::should_roll_back(before.as_ref(), &after);
+    send(ApplyProgress::Probed(after));
+    if !roll_back {
+        send(ApplyProgress::Applied);
+        return;
+    }
+    match rt.block_on(restore_and_reload(&session, job.server, &target, &backup)) {
+        Ok(()) => send(ApplyProgress::RolledBack),
+        Err(e) => send(ApplyProgress::Failed(format!(
+            "restoring the previous config failed, it is still at {}: {}",
+            backup, e
+        ))),
+    }
+}
+
+/// Stage, install, check and reload. A config the checker rejects, or one
+/// the server fails to reload with, is replaced by the backup before
+/// returning the error. Returns the installed file and its backup.
+async fn install_and_reload(
+    session: &SshSession,
+    job: &ApplyJob,
+    send: &impl Fn(ApplyProgress),
+) -> Result<(String, String), String> {
+    let staged = session
+        .exec_with_stdin(config_apply::STAGE_COMMAND, job.content.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    let staged_path = staged.stdout_string().trim().to_string();
+    if !staged.success() || staged_path.is_empty() {
+        return Err(command_error(&staged.stderr, staged.exit_code));
+    }
+    send(ApplyProgress::Staged(staged_path.clone()));
+
+    let backup = config_apply::backup_path(&job.config_path, Utc::now());
+    let installed = session
+        .exec(&config_apply::install_command(
+            &job.config_path,
+            &staged_path,
+            &backup,
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    let target = match config_apply::installed_target(&installed.stdout_string()) {
+        Some(target) if installed.success() => target.to_string(),
+        _ => return Err(command_error(&installed.stdout, installed.exit_code)),
+    };
+    send(ApplyProgress::Installed {
+        target: target.clone(),
+        backup: backup.clone(),
+    });
+
+    let check = session
+        .exec(&job.server.check_command())
+        .await
+        .map_err(|e| e.to_string())?;
+    let passed = check.success();
+    let output = check.stdout_string();
+    send(ApplyProgress::Checked {
+        passed,
+        output: output.clone(),
+    });
+    if !passed {
+        // The running server never read the rejected file: putting the old
+        // one back is enough, no reload.
+        restore(session, &target, &backup).await?;
+        return Err(format!(
+            "{} rejected the config, the previous version is back in place: {}",
+            job.server.check(),
+            config_apply::check_summary(&output)
+        ));
+    }
+
+    let reload = session
+        .exec(&job.server.reload_command())
+        .await
+        .map_err(|e| e.to_string())?;
+    if !reload.success() {
+        let error = command_error(&reload.stdout, reload.exit_code);
+        restore_and_reload(session, job.server, &target, &backup).await?;
+        return Err(format!(
+            "reloading {} failed, the previous config is back in place: {}",
+            job.server.label(),
+            error
+        ));
+    }
+    send(ApplyProgress::Reloaded);
+    Ok((target, backup))
+}
+
+async fn restore(session: &SshSession, target: &str, backup: &str) -> Result<(), String> {
+    let restored = session
+        .exec(&config_apply::restore_command(target, backup))
+        .await
+        .map_err(|e| e.to_string())?;
+    if restored.success() {
+        Ok(())
+    } else {
+        Err(command_error(&restored.stdout, restored.exit_code))
+    }
+}
+
+async fn restore_and_reload(
+    session: &SshSession,
+    server: WebServer,
+    target: &str,
+    backup: &str,
+) -> Result<(), String> {
+    restore(session, target, backup).await?;
+    let reload = session
+        .exec(&server.reload_command())
+        .await
+        .map_err(|e| e.to_string())?;
+    if reload.success() {
+        Ok(())
+    } else {
+        Err(command_error(&reload.stdout, reload.exit_code))
+    }
+}
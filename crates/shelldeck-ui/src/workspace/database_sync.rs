@@ -0,0 +1,136 @@
+use shelldeck_core::config::keychain;
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::db_credentials::{self, DbCredentials, DbLogin};
+use shelldeck_core::models::discovery;
+use shelldeck_core::models::server_sync::{DatabaseEngine, SyncItemKind, SyncOptions};
+use shelldeck_ssh::session::SshSession;
+use uuid::Uuid;
+
+use super::server_sync::{relay_commands, relay_item, run_direct, RelayReporter};
+
+/// Write `content` to a private temporary file on the host behind
+/// `session` and return its path. The content travels on stdin, so it never
+/// shows up in the remote process list.
+pub(super) async fn upload_secret(session: &SshSession, content: &str) -> Result<String, String> {
+    match session
+        .exec_with_stdin(db_credentials::SECRET_FILE_COMMAND, content.as_bytes())
+        .await
+    {
+        Ok(r) if r.success() && !r.stdout_string().trim().is_empty() => {
+            Ok(r.stdout_string().trim().to_string())
+        }
+        Ok(r) => Err(r.stderr_string().trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Delete the secret file of a login, if it has one.
+pub(super) async fn remove_secret(session: &SshSession, login: &DbLogin) {
+    if let Some(path) = &login.secret_file {
+        let _ = session
+            .exec(&db_credentials::remove_secret_file_command(path))
+            .await;
+    }
+}
+
+/// The login for one side of a database item. The password comes from the
+/// credentials until the profile is saved, and from the keychain after.
+pub(super) async fn prepare_login(
+    session: &SshSession,
+    engine: DatabaseEngine,
+    credentials: Option<&DbCredentials>,
+) -> Result<DbLogin, String> {
+    let Some(creds) = credentials else {
+        return Ok(DbLogin::default());
+    };
+    let password = match &creds.password {
+        Some(password) => Some(password.clone()),
+        None => keychain::get_db_password(creds.id).map_err(|e| e.to_string())?,
+    };
+    let secret_file = match creds.secret_file(engine, password.as_deref()) {
+        Some(content) => Some(upload_secret(session, &content).await?),
+        None => None,
+    };
+    Ok(DbLogin::new(Some(creds), secret_file))
+}
+
+/// Sync a database that logs in with stored credentials: write the secret
+/// files on both hosts, run the dump, then remove the files whatever the
+/// outcome.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn sync_database(
+    item_id: Uuid,
+    kind: &SyncItemKind,
+    source: &SshSession,
+    dest: &SshSession,
+    dest_conn: &Connection,
+    relay: bool,
+    options: &SyncOptions,
+    shutdown_rx: &mut tokio::sync::mpsc::Receiver<()>,
+    reporter: &RelayReporter,
+) -> bool {
+    let SyncItemKind::Database {
+        name,
+        engine,
+        source_credentials,
+        dest_credentials,
+    } = kind
+    else {
+        return false;
+    };
+    let log = |line: String| {
+        let _ = reporter.log.send((item_id, line));
+    };
+
+    let source_login = match prepare_login(source, *engine, source_credentials.as_ref()).await {
+        Ok(login) => login,
+        Err(e) => {
+            log(format!(
+                "[sync] Cannot set up the source database login: {}",
+                e
+            ));
+            return false;
+        }
+    };
+    let dest_login = match prepare_login(dest, *engine, dest_credentials.as_ref()).await {
+        Ok(login) => login,
+        Err(e) => {
+            log(format!(
+                "[sync] Cannot set up the destination database login: {}",
+                e
+            ));
+            remove_secret(source, &source_login).await;
+            return false;
+        }
+    };
+
+    let commands = discovery::database_commands(
+        *engine,
+        name,
+        &source_login,
+        &dest_login,
+        &dest_conn.user,
+        &dest_conn.hostname,
+        dest_conn.port,
+        options,
+        relay,
+    );
+    let success = if relay {
+        relay_item(
+            item_id,
+            source,
+            dest,
+            &relay_commands(commands),
+            options,
+            shutdown_rx,
+            reporter,
+        )
+        .await
+    } else {
+        run_direct(item_id, source, commands, reporter).await
+    };
+
+    remove_secret(source, &source_login).await;
+    remove_secret(dest, &dest_login).await;
+    success
+}
@@ -0,0 +1,745 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Local, Utc};
+use gpui::*;
+use shelldeck_core::config::activity::{ActivityEntry, ActivityKind};
+use shelldeck_core::config::backup_catalog::BackupCatalog;
+use shelldeck_core::config::keychain;
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::db_backup::{
+    self, ArchiveDigest, BackupDestination, BackupEntry, BackupPlan,
+};
+use shelldeck_core::models::db_credentials::DbCredentials;
+use shelldeck_core::models::managed_site::ManagedSiteType;
+use shelldeck_core::models::schedule::CronSchedule;
+use shelldeck_ssh::client::SshClient;
+use shelldeck_ssh::session::{RelayEvent, SshSession, StreamOutcome};
+use uuid::Uuid;
+
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::database_sync::{prepare_login, remove_secret};
+use super::{TrayNotification, Workspace};
+
+/// How often backup schedules are checked. Cron resolution is one minute.
+const BACKUP_TICK: Duration = Duration::from_secs(30);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+impl Workspace {
+    /// Load the backup catalog, then run scheduled backups for as long as
+    /// the workspace lives. Slots missed while ShellDeck was closed run
+    /// once on the first check.
+    pub fn start_backup_scheduler(&mut self, cx: &mut Context<Self>) {
+        if self._backup_scheduler_task.is_some() {
+            return;
+        }
+        self.backup_catalog = BackupCatalog::load().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load backup catalog: {}", e);
+            BackupCatalog::default()
+        });
+        self.refresh_backups_view(cx);
+        self.run_due_backups(cx);
+        self._backup_scheduler_task = Some(cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor().timer(BACKUP_TICK).await;
+            if this.update(cx, |ws, cx| ws.run_due_backups(cx)).is_err() {
+                break;
+            }
+        }));
+    }
+
+    fn refresh_backups_view(&mut self, cx: &mut Context<Self>) {
+        let catalog = self.backup_catalog.clone();
+        let backup_jobs = self.backup_jobs.clone();
+        let restore_jobs = self.restore_jobs.clone();
+        self.sites.update(cx, |view, cx| {
+            view.set_backups(catalog, backup_jobs, restore_jobs);
+            cx.notify();
+        });
+    }
+
+    fn save_backup_catalog(&self) {
+        if let Err(e) = self.backup_catalog.save() {
+            tracing::warn!("Failed to save backup catalog: {}", e);
+        }
+    }
+
+    /// Start every plan with a schedule slot between its last check and
+    /// now. Plans seen for the first time start counting from now.
+    fn run_due_backups(&mut self, cx: &mut Context<Self>) {
+        let now = Utc::now();
+        let now_local = now.with_timezone(&Local).naive_local();
+        let mut changed = false;
+        let mut due = Vec::new();
+        for plan in &mut self.backup_catalog.plans {
+            let Some(cron) = plan.schedule.as_deref() else {
+                continue;
+            };
+            let Some(last) = plan.last_checked.replace(now) else {
+                changed = true;
+                continue;
+            };
+            let schedule = match CronSchedule::parse(cron) {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::warn!("Invalid backup schedule for {}: {}", plan.database, e);
+                    continue;
+                }
+            };
+            let runs = schedule.due(last.with_timezone(&Local).naive_local(), now_local);
+            if runs.on_time || runs.missed > 0 {
+                changed = true;
+                due.push(plan.id);
+            }
+        }
+        if changed {
+            self.save_backup_catalog();
+        }
+        for plan_id in due {
+            self.run_backup(plan_id, true, cx);
+        }
+    }
+
+    /// Back up a database site now, creating a plan that keeps archives
+    /// on this computer if it has none.
+    pub(super) fn backup_database(&mut self, site_id: Uuid, cx: &mut Context<Self>) {
+        let Some(site) = self.store.managed_sites.iter().find(|s| s.id == site_id) else {
+            return;
+        };
+        let ManagedSiteType::Database(db) = &site.site_type else {
+            return;
+        };
+        let plan_id = match self.backup_catalog.plan_for(site.connection_id, &db.name) {
+            Some(plan) => plan.id,
+            None => {
+                let plan = BackupPlan::new(
+                    site.connection_id,
+                    db,
+                    BackupDestination::Local {
+                        dir: BackupCatalog::default_archive_dir(),
+                    },
+                );
+                let plan_id = plan.id;
+                self.backup_catalog.upsert_plan(plan);
+                self.save_backup_catalog();
+                plan_id
+            }
+        };
+        self.run_backup(plan_id, false, cx);
+    }
+
+    /// Store an edited plan. A new password goes to the keychain.
+    pub(super) fn save_backup_plan(
+        &mut self,
+        plan: BackupPlan,
+        password: Option<String>,
+        cx: &mut Context<Self>,
+    ) {
+        if let (Some(creds), Some(password)) = (&plan.credentials, password) {
+            if let Err(e) = keychain::store_db_password(creds.id, &password) {
+                self.show_toast(
+                    t!("toast.backup.password_failed", error = e.to_string()).to_string(),
+                    ToastLevel::Error,
+                    cx,
+                );
+                return;
+            }
+        }
+        // A login that was removed takes its password with it.
+        let dropped = self
+            .backup_catalog
+            .plan(plan.id)
+            .and_then(|old| old.credentials.as_ref())
+            .filter(|old| plan.credentials.as_ref().is_none_or(|new| new.id != old.id))
+            .map(|old| old.id);
+        if let Some(id) = dropped {
+            let _ = keychain::delete_db_password(id);
+        }
+        self.backup_catalog.upsert_plan(plan);
+        self.save_backup_catalog();
+        self.refresh_backups_view(cx);
+        self.show_toast(
+            t!("toast.backup.plan_saved").to_string(),
+            ToastLevel::Success,
+            cx,
+        );
+    }
+
+    /// Dump a plan's database to its destination in the background, unless
+    /// a backup of it is already running.
+    fn run_backup(&mut self, plan_id: Uuid, scheduled: bool, cx: &mut Context<Self>) {
+        if self.backup_jobs.contains(&plan_id) {
+            return;
+        }
+        let Some(plan) = self.backup_catalog.plan(plan_id).cloned() else {
+            return;
+        };
+        let find = |id: Uuid| self.connections.iter().find(|c| c.id == id).cloned();
+        let source = find(plan.connection_id);
+        let dest = match &plan.destination {
+            BackupDestination::Local { .. } => Some(None),
+            BackupDestination::Remote { connection_id, .. } => find(*connection_id).map(Some),
+        };
+        let (Some(source), Some(dest)) = (source, dest) else {
+            let error = t!("sites.backup.error.no_server").to_string();
+            self.backup_failed(&plan, error, scheduled, cx);
+            return;
+        };
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<Result<BackupEntry, String>>();
+        let thread_plan = plan.clone();
+        let spawn_result = std::thread::Builder::new()
+            .name(format!("db-backup-{}", plan.database))
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        let _ = done_tx.send(Err(format!("async runtime: {}", e)));
+                        return;
+                    }
+                };
+                let result =
+                    rt.block_on(take_backup(&thread_plan, &source, dest.as_ref(), scheduled));
+                let _ = done_tx.send(result);
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn backup thread: {}", e);
+            return;
+        }
+        self.backup_jobs.insert(plan_id);
+        self.refresh_backups_view(cx);
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let result = loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(250))
+                    .await;
+                match done_rx.try_recv() {
+                    Ok(result) => break result,
+                    Err(std::sync::mpsc::TryRecvError::Empty) => continue,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        break Err("backup thread ended unexpectedly".to_string())
+                    }
+                }
+            };
+            let _ = this.update(cx, |ws, cx| {
+                ws.backup_jobs.remove(&plan_id);
+                match result {
+                    Ok(entry) => ws.backup_finished(entry, cx),
+                    Err(e) => ws.backup_failed(&plan, e, scheduled, cx),
+                }
+                ws.refresh_backups_view(cx);
+            });
+        })
+        .detach();
+    }
+
+    /// Record a new archive, then let the plan's retention drop old ones.
+    fn backup_finished(&mut self, entry: BackupEntry, cx: &mut Context<Self>) {
+        let message = t!(
+            "activity.backup.done",
+            database = entry.database.as_str(),
+            server = entry.source_name.as_str(),
+            size = entry.size_display()
+        )
+        .to_string();
+        let plan_id = entry.plan_id;
+        let scheduled = entry.scheduled;
+        self.backup_catalog.record(entry);
+        let expired = self.backup_catalog.expired(plan_id);
+        for old in &expired {
+            self.backup_catalog.remove_entry(old.id);
+        }
+        self.save_backup_catalog();
+        self.delete_archives(expired);
+
+        self.add_activity_entry(ActivityEntry::new(ActivityKind::Site, message.clone()), cx);
+        if !scheduled {
+            self.show_toast(message, ToastLevel::Success, cx);
+        }
+    }
+
+    fn backup_failed(
+        &mut self,
+        plan: &BackupPlan,
+        error: String,
+        scheduled: bool,
+        cx: &mut Context<Self>,
+    ) {
+        tracing::warn!("Backup of {} failed: {}", plan.database, error);
+        let message = t!(
+            "activity.backup.failed",
+            database = plan.database.as_str(),
+            error = error.as_str()
+        )
+        .to_string();
+        self.add_activity_entry(ActivityEntry::new(ActivityKind::Error, message.clone()), cx);
+        if !scheduled {
+            self.show_toast(message, ToastLevel::Error, cx);
+        } else if self.app_config.tray.notify_scheduled_failures {
+            self.emit_tray_notification(TrayNotification::BackupFailed {
+                database: plan.database.clone(),
+            });
+        }
+    }
+
+    /// Remove an archive from the catalog and from where it is stored.
+    pub(super) fn delete_backup(&mut self, entry_id: Uuid, cx: &mut Context<Self>) {
+        let Some(entry) = self.backup_catalog.remove_entry(entry_id) else {
+            return;
+        };
+        self.save_backup_catalog();
+        self.delete_archives(vec![entry]);
+        self.refresh_backups_view(cx);
+    }
+
+    /// Delete archive files in the background. Failures are only logged:
+    /// the catalog has already forgotten them.
+    fn delete_archives(&self, entries: Vec<BackupEntry>) {
+        let mut remote: Vec<(Connection, String)> = Vec::new();
+        for entry in entries {
+            match &entry.destination {
+                BackupDestination::Local { .. } => {
+                    if let Err(e) = std::fs::remove_file(&entry.path) {
+                        tracing::warn!("Failed to delete backup {}: {}", entry.path, e);
+                    }
+                }
+                BackupDestination::Remote { connection_id, .. } => {
+                    match self.connections.iter().find(|c| c.id == *connection_id) {
+                        Some(conn) => remote.push((conn.clone(), entry.path)),
+                        None => tracing::warn!("Backup {} is on an unknown server", entry.path),
+                    }
+                }
+            }
+        }
+        if remote.is_empty() {
+            return;
+        }
+        let spawn_result = std::thread::Builder::new()
+            .name("db-backup-prune".to_string())
+            .spawn(move || {
+                let Ok(rt) = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                else {
+                    return;
+                };
+                rt.block_on(async move {
+                    for (conn, path) in remote {
+                        let result = match connect(&conn).await {
+                            Ok(session) => session
+                                .exec(&db_backup::remove_command(&path))
+                                .await
+                                .map_err(|e| e.to_string()),
+                            Err(e) => Err(e),
+                        };
+                        if let Err(e) = result {
+                            tracing::warn!("Failed to delete backup {}: {}", path, e);
+                        }
+                    }
+                });
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn backup prune thread: {}", e);
+        }
+    }
+
+    /// Restore an archive into `database` on `connection_id`. The user has
+    /// confirmed; the archive's checksum is verified before anything is
+    /// written.
+    pub(super) fn restore_backup(
+        &mut self,
+        entry_id: Uuid,
+        connection_id: Uuid,
+        database: String,
+        cx: &mut Context<Self>,
+    ) {
+        if self.restore_jobs.contains(&entry_id) {
+            return;
+        }
+        let Some(entry) = self.backup_catalog.entry(entry_id).cloned() else {
+            return;
+        };
+        let find = |id: Uuid| self.connections.iter().find(|c| c.id == id).cloned();
+        let target = find(connection_id);
+        let archive_host = match &entry.destination {
+            BackupDestination::Local { .. } => Ok(None),
+            BackupDestination::Remote { connection_id, .. } => {
+                find(*connection_id).map(Some).ok_or(())
+            }
+        };
+        let (Some(target), Ok(archive_host)) = (target, archive_host) else {
+            self.show_toast(
+                t!("sites.backup.error.no_server").to_string(),
+                ToastLevel::Error,
+                cx,
+            );
+            return;
+        };
+        // The plan's login only applies to the server it was made for.
+        let credentials = self
+            .backup_catalog
+            .plan(entry.plan_id)
+            .filter(|plan| plan.connection_id == connection_id)
+            .and_then(|plan| plan.credentials.clone());
+        let target_name = target.display_name().to_string();
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<Result<(), String>>();
+        let thread_entry = entry.clone();
+        let thread_database = database.clone();
+        let spawn_result = std::thread::Builder::new()
+            .name(format!("db-restore-{}", database))
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        let _ = done_tx.send(Err(format!("async runtime: {}", e)));
+                        return;
+                    }
+                };
+                let result = rt.block_on(restore_archive(
+                    &thread_entry,
+                    archive_host.as_ref(),
+                    &target,
+                    &thread_database,
+                    credentials.as_ref(),
+                ));
+                let _ = done_tx.send(result);
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn restore thread: {}", e);
+            return;
+        }
+        self.restore_jobs.insert(entry_id);
+        self.refresh_backups_view(cx);
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let result = loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(250))
+                    .await;
+                match done_rx.try_recv() {
+                    Ok(result) => break result,
+                    Err(std::sync::mpsc::TryRecvError::Empty) => continue,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                        break Err("restore thread ended unexpectedly".to_string())
+                    }
+                }
+            };
+            let _ = this.update(cx, |ws, cx| {
+                ws.restore_jobs.remove(&entry_id);
+                let (kind, message, level) = match result {
+                    Ok(()) => (
+                        ActivityKind::Site,
+                        t!(
+                            "activity.backup.restored",
+                            database = database.as_str(),
+                            server = target_name.as_str()
+                        )
+                        .to_string(),
+                        ToastLevel::Success,
+                    ),
+                    Err(e) => (
+                        ActivityKind::Error,
+                        t!(
+                            "activity.backup.restore_failed",
+                            database = database.as_str(),
+                            server = target_name.as_str(),
+                            error = e.as_str()
+                        )
+                        .to_string(),
+                        ToastLevel::Error,
+                    ),
+                };
+                ws.add_activity_entry(ActivityEntry::new(kind, message.clone()), cx);
+                ws.show_toast(message, level, cx);
+                ws.refresh_backups_view(cx);
+            });
+        })
+        .detach();
+    }
+}
+
+pub(super) async fn connect(conn: &Connection) -> Result<SshSession, String> {
+    match tokio::time::timeout(CONNECT_TIMEOUT, SshClient::new().connect(conn)).await {
+        Ok(Ok(session)) => Ok(session),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("Connection to {} timed out", conn.display_name())),
+    }
+}
+
+/// What a failed command printed, or its exit status when it printed
+/// nothing.
+pub(super) fn command_error(output: &[u8], exit_code: Option<u32>) -> String {
+    let output = String::from_utf8_lossy(output).trim().to_string();
+    match (output.is_empty(), exit_code) {
+        (false, _) => output,
+        (true, Some(code)) => format!("exit status {}", code),
+        (true, None) => "the command did not finish".to_string(),
+    }
+}
+
+pub(super) fn stream_error(outcome: &StreamOutcome) -> String {
+    if outcome.cancelled {
+        "cancelled".to_string()
+    } else {
+        command_error(&outcome.output, outcome.exit_code)
+    }
+}
+
+/// Diagnostic output a relay collected.
+pub(super) fn relay_output(
+    events: &mut tokio::sync::mpsc::UnboundedReceiver<RelayEvent>,
+) -> Vec<u8> {
+    let mut output = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let RelayEvent::Output { data, .. } = event {
+            output.extend(data);
+        }
+    }
+    output
+}
+
+/// Dump the plan's database into a new archive at its destination.
+async fn take_backup(
+    plan: &BackupPlan,
+    source: &Connection,
+    dest: Option<&Connection>,
+    scheduled: bool,
+) -> Result<BackupEntry, String> {
+    let session = connect(source).await?;
+    let login = prepare_login(&session, plan.engine, plan.credentials.as_ref()).await?;
+    let created_at = Utc::now();
+    let path = plan
+        .destination
+        .archive_path(&db_backup::archive_name(&plan.database, created_at));
+    let dump = db_backup::dump_command(plan.engine, &plan.database, &login);
+    let result = match dest {
+        None => dump_to_file(&session, &dump, Path::new(&path)).await,
+        Some(dest) => dump_to_server(&session, &dump, dest, &path).await,
+    };
+    remove_secret(&session, &login).await;
+    let (sha256, size_bytes) = result?;
+    Ok(BackupEntry {
+        id: Uuid::new_v4(),
+        plan_id: plan.id,
+        connection_id: plan.connection_id,
+        source_name: source.display_name().to_string(),
+        database: plan.database.clone(),
+        engine: plan.engine,
+        created_at,
+        size_bytes,
+        sha256,
+        destination: plan.destination.clone(),
+        path,
+        scheduled,
+    })
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut part = path.as_os_str().to_owned();
+    part.push(".part");
+    PathBuf::from(part)
+}
+
+/// A new file only the current user can read.
+fn create_private_file(path: &Path) -> std::io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Stream the dump into a local archive, hashing it on the way. The
+/// archive only appears under its name once the dump succeeded.
+async fn dump_to_file(
+    session: &SshSession,
+    dump: &str,
+    path: &Path,
+) -> Result<(String, u64), String> {
+    use std::io::Write;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    let part = part_path(path);
+    let file = create_private_file(&part).map_err(|e| format!("{}: {}", part.display(), e))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let mut digest = ArchiveDigest::new();
+    let (_shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel(1);
+    let mut sink = |data: &[u8]| {
+        digest.update(data);
+        writer.write_all(data)
+    };
+    let result = session
+        .exec_to_sink(dump, &mut sink, &mut shutdown_rx)
+        .await;
+    let written = writer.flush().and_then(|()| writer.get_ref().sync_all());
+    drop(writer);
+    let finished = match result {
+        Ok(outcome) if outcome.success() => written.map_err(|e| e.to_string()),
+        Ok(outcome) => Err(stream_error(&outcome)),
+        Err(e) => Err(e.to_string()),
+    }
+    .and_then(|()| std::fs::rename(&part, path).map_err(|e| e.to_string()));
+    if let Err(e) = finished {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    Ok(digest.finish())
+}
+
+/// Relay the dump into an archive on another server, hashing it in
+/// flight, then check the stored copy against the hash.
+async fn dump_to_server(
+    session: &SshSession,
+    dump: &str,
+    dest_conn: &Connection,
+    path: &str,
+) -> Result<(String, u64), String> {
+    let dest = connect(dest_conn).await?;
+    let mut digest = ArchiveDigest::new();
+    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (_shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel(1);
+    let result = session
+        .relay_through(
+            dump,
+            &dest,
+            &db_backup::store_command(path),
+            events_tx,
+            &mut shutdown_rx,
+            None,
+            &mut |chunk| match chunk {
+                Some(data) => {
+                    digest.update(data);
+                    data.to_vec()
+                }
+                None => Vec::new(),
+            },
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    if !result.success() {
+        let _ = dest
+            .exec(&db_backup::remove_command(&format!("{}.part", path)))
+            .await;
+        let exit = result
+            .source_exit
+            .filter(|code| *code != 0)
+            .or(result.dest_exit);
+        return Err(command_error(&relay_output(&mut events_rx), exit));
+    }
+
+    let (sha256, bytes) = digest.finish();
+    let stored = dest
+        .exec(&db_backup::checksum_command(path))
+        .await
+        .ok()
+        .and_then(|r| db_backup::parse_checksum_output(&r.stdout_string()));
+    if stored.as_deref() != Some(sha256.as_str()) {
+        let _ = dest.exec(&db_backup::remove_command(path)).await;
+        return Err(format!(
+            "the copy stored at {} does not match its checksum",
+            path
+        ));
+    }
+    Ok((sha256, bytes))
+}
+
+/// Restore an archive, after checking it still matches its checksum.
+async fn restore_archive(
+    entry: &BackupEntry,
+    archive_host: Option<&Connection>,
+    target: &Connection,
+    database: &str,
+    credentials: Option<&DbCredentials>,
+) -> Result<(), String> {
+    let session = connect(target).await?;
+    let login = prepare_login(&session, entry.engine, credentials).await?;
+    let restore = db_backup::restore_command(entry.engine, database, &login);
+    let result = match archive_host {
+        None => restore_from_file(&session, &restore, entry).await,
+        Some(host) => restore_from_server(&session, &restore, host, entry).await,
+    };
+    remove_secret(&session, &login).await;
+    result
+}
+
+fn checksum_mismatch(entry: &BackupEntry) -> String {
+    format!("{} does not match its checksum", entry.path)
+}
+
+async fn restore_from_file(
+    session: &SshSession,
+    restore: &str,
+    entry: &BackupEntry,
+) -> Result<(), String> {
+    let path = Path::new(&entry.path);
+    let actual = db_backup::sha256_file(path).map_err(|e| format!("{}: {}", entry.path, e))?;
+    if actual != entry.sha256 {
+        return Err(checksum_mismatch(entry));
+    }
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("{}: {}", entry.path, e))?;
+    let (_shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel(1);
+    let outcome = session
+        .exec_from_reader(restore, &mut file, &mut shutdown_rx)
+        .await
+        .map_err(|e| e.to_string())?;
+    if outcome.success() {
+        Ok(())
+    } else {
+        Err(stream_error(&outcome))
+    }
+}
+
+async fn restore_from_server(
+    session: &SshSession,
+    restore: &str,
+    host: &Connection,
+    entry: &BackupEntry,
+) -> Result<(), String> {
+    let archive = connect(host).await?;
+    let stored = archive
+        .exec(&db_backup::checksum_command(&entry.path))
+        .await
+        .map_err(|e| e.to_string())?;
+    if db_backup::parse_checksum_output(&stored.stdout_string()).as_deref()
+        != Some(entry.sha256.as_str())
+    {
+        return Err(checksum_mismatch(entry));
+    }
+    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+    let (_shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel(1);
+    let outcome = archive
+        .relay_through(
+            &db_backup::read_command(&entry.path),
+            session,
+            restore,
+            events_tx,
+            &mut shutdown_rx,
+            None,
+            &mut |chunk| chunk.map(<[u8]>::to_vec).unwrap_or_default(),
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+    if outcome.success() {
+        Ok(())
+    } else {
+        let exit = outcome.dest_exit.or(outcome.source_exit);
+        Err(command_error(&relay_output(&mut events_rx), exit))
+    }
+}
@@ -2,14 +2,16 @@ use gpui::prelude::*;
 use gpui::*;
 use shelldeck_core::config::cloud_account::AppMode;
 use shelldeck_core::models::connection::Connection;
-use shelldeck_core::models::managed_site::ManagedSite;
+use shelldeck_core::models::managed_site::{ManagedSite, ManagedSiteType, SiteStatus};
 use shelldeck_ssh::client::SshClient;
+use shelldeck_ssh::session::SshSession;
 
 use crate::server_sync_view::PanelSide;
 use crate::sites_view::SitesEvent;
 use crate::t;
 use crate::toast::ToastLevel;
 
+use super::database_sync::{remove_secret, upload_secret};
 use super::{ActiveView, Workspace};
 
 impl Workspace {
@@ -67,6 +69,54 @@ impl Workspace {
                 });
                 cx.notify();
             }
+            SitesEvent::UpdateMonitor(id, monitor) => {
+                let id = *id;
+                let enabled = monitor.enabled;
+                if let Some(site) = self.store.managed_sites.iter_mut().find(|s| s.id == id) {
+                    site.monitor = monitor.clone();
+                }
+                let _ = self.store.save();
+                if !enabled && self.site_health.sites.remove(&id).is_some() {
+                    if let Err(e) = self.site_health.save() {
+                        tracing::warn!("Failed to save site health history: {}", e);
+                    }
+                }
+                self.sites.update(cx, |view, _| {
+                    view.set_sites(self.store.managed_sites.clone());
+                    if !enabled {
+                        view.set_health_samples(id, Vec::new());
+                    }
+                });
+                if enabled {
+                    self.probe_site_now(id, cx);
+                }
+                cx.notify();
+            }
+            SitesEvent::ProbeSite(id) => {
+                self.probe_site_now(*id, cx);
+            }
+            SitesEvent::BackupDatabase(site_id) => {
+                self.backup_database(*site_id, cx);
+            }
+            SitesEvent::SaveBackupPlan { plan, password } => {
+                self.save_backup_plan(plan.clone(), password.clone(), cx);
+            }
+            SitesEvent::RestoreBackup {
+                entry_id,
+                connection_id,
+                database,
+            } => {
+                self.restore_backup(*entry_id, *connection_id, database.clone(), cx);
+            }
+            SitesEvent::DeleteBackup(entry_id) => {
+                self.delete_backup(*entry_id, cx);
+            }
+            SitesEvent::LoadSiteConfig(site_id) => {
+                self.load_site_config(*site_id, cx);
+            }
+            SitesEvent::ApplySiteConfig { site_id, content } => {
+                self.apply_site_config(*site_id, content.clone(), cx);
+            }
             SitesEvent::OpenInBrowser(url) => {
                 let _ = open::that(url);
             }
@@ -145,31 +195,39 @@ impl Workspace {
                                 return;
                             }
 
-                            cx.spawn(async move |_ws, cx: &mut AsyncApp| {
-                                loop {
-                                    cx.background_executor()
-                                        .timer(std::time::Duration::from_millis(50))
-                                        .await;
-                                    if let Ok((online, err_msg)) = done_rx.try_recv() {
-                                        let _ = sites_handle.update(cx, |view, cx| {
-                                            if let Some(site) = view.sites.iter_mut().find(|s| s.id == site_id) {
-                                                site.last_checked = Some(chrono::Utc::now());
-                                                if err_msg.is_empty() {
-                                                    site.status = if online {
-                                                        shelldeck_core::models::managed_site::SiteStatus::Online
-                                                    } else {
-                                                        shelldeck_core::models::managed_site::SiteStatus::Offline
-                                                    };
-                                                } else {
-                                                    site.status = shelldeck_core::models::managed_site::SiteStatus::Error(err_msg);
-                                                }
-                                            }
-                                            cx.notify();
+                            cx.spawn(async move |ws, cx: &mut AsyncApp| loop {
+                                cx.background_executor()
+                                    .timer(std::time::Duration::from_millis(50))
+                                    .await;
+                                if let Ok((online, err_msg)) = done_rx.try_recv() {
+                                    let change = sites_handle.update(cx, |view, cx| {
+                                        let site =
+                                            view.sites.iter_mut().find(|s| s.id == site_id)?;
+                                        let previous = site.status.clone();
+                                        site.last_checked = Some(chrono::Utc::now());
+                                        if err_msg.is_empty() {
+                                            site.status = if online {
+                                                SiteStatus::Online
+                                            } else {
+                                                SiteStatus::Offline
+                                            };
+                                        } else {
+                                            site.status = SiteStatus::Error(err_msg);
+                                        }
+                                        cx.notify();
+                                        Some((previous, site.status.clone()))
+                                    });
+                                    if let Ok(Some((previous, current))) = change {
+                                        let _ = ws.update(cx, |ws, cx| {
+                                            ws.site_status_changed(
+                                                site_id, conn_id, &previous, &current, cx,
+                                            );
                                         });
-                                        break;
                                     }
+                                    break;
                                 }
-                            }).detach();
+                            })
+                            .detach();
                         }
                     }
                 }
@@ -278,7 +336,10 @@ impl Workspace {
         panel: PanelSide,
         cx: &mut Context<Self>,
     ) {
+        use shelldeck_core::models::db_credentials::{self, DbLogin, DiscoveredCredentials};
         use shelldeck_core::models::discovery;
+        use shelldeck_core::models::server_sync::DatabaseEngine;
+        use shelldeck_core::models::wordpress::{self, DiscoveredWordPress};
 
         let disc_conn_id = connection.id;
         let disc_conn_name = connection.display_name().to_string();
@@ -288,6 +349,9 @@ impl Workspace {
         let pg_cmd = discovery::pg_discover_command("-U postgres");
 
         let (stream_tx, stream_rx) = std::sync::mpsc::channel::<(String, String)>();
+        let (wp_tx, wp_rx) = std::sync::mpsc::channel::<Vec<DiscoveredWordPress>>();
+        let (cred_tx, cred_rx) = std::sync::mpsc::channel::<Vec<DiscoveredCredentials>>();
+        let (services_tx, services_rx) = std::sync::mpsc::channel::<Vec<ManagedSiteType>>();
         let (done_tx, done_rx) = std::sync::mpsc::channel::<bool>();
 
         let thread_disc_conn_name = disc_conn_name.clone();
@@ -334,9 +398,15 @@ impl Workspace {
                     let exec_timeout = std::time::Duration::from_secs(30);
 
                     // Discover nginx
+                    let mut roots = Vec::new();
                     match tokio::time::timeout(exec_timeout, session.exec(&nginx_cmd)).await {
                         Ok(Ok(result)) => {
                             let output = String::from_utf8_lossy(&result.stdout).to_string();
+                            for site in discovery::parse_nginx_configs(&output) {
+                                if !site.root.is_empty() && !roots.contains(&site.root) {
+                                    roots.push(site.root);
+                                }
+                            }
                             let _ = stream_tx.send(("nginx".to_string(), output));
                         }
                         Ok(Err(e)) => tracing::debug!(
@@ -349,6 +419,73 @@ impl Workspace {
                         }
                     }
 
+                    // Discover WordPress installs under the site roots
+                    let mut credentials = Vec::new();
+                    if !roots.is_empty() {
+                        let wp_cmd = wordpress::wp_discover_command(&roots);
+                        match tokio::time::timeout(exec_timeout, session.exec(&wp_cmd)).await {
+                            Ok(Ok(result)) => {
+                                let mut installs =
+                                    wordpress::parse_wp_discovery(&result.stdout_string());
+                                for install in &mut installs {
+                                    // The password goes through a secret file,
+                                    // not the mysql command line.
+                                    let login = match upload_secret(
+                                        &session,
+                                        &install.config.option_file(),
+                                    )
+                                    .await
+                                    {
+                                        Ok(path) => DbLogin::new(None, Some(path)),
+                                        Err(_) => continue,
+                                    };
+                                    if let Some(cmd) =
+                                        wordpress::home_url_command(&install.config, &login)
+                                    {
+                                        if let Ok(Ok(r)) =
+                                            tokio::time::timeout(exec_timeout, session.exec(&cmd))
+                                                .await
+                                        {
+                                            let url = r.stdout_string().trim().to_string();
+                                            install.home_url = (!url.is_empty()).then_some(url);
+                                        }
+                                    }
+                                    remove_secret(&session, &login).await;
+                                }
+                                credentials.extend(installs.iter().map(|install| {
+                                    let mut creds = install.config.credentials();
+                                    creds.origin = Some(install.config_path.clone());
+                                    DiscoveredCredentials {
+                                        engine: DatabaseEngine::Mysql,
+                                        database: Some(install.config.db_name.clone()),
+                                        credentials: creds,
+                                    }
+                                }));
+                                let _ = wp_tx.send(installs);
+                            }
+                            Ok(Err(e)) => tracing::debug!(
+                                "wordpress discover exec error on {}: {}",
+                                thread_disc_conn_name,
+                                e
+                            ),
+                            Err(_) => tracing::warn!(
+                                "wordpress discover timed out on {}",
+                                thread_disc_conn_name
+                            ),
+                        }
+                    }
+
+                    // Database credentials in .env files and ~/.my.cnf
+                    let cred_cmd = db_credentials::credential_discover_command(&roots);
+                    if let Ok(Ok(result)) =
+                        tokio::time::timeout(exec_timeout, session.exec(&cred_cmd)).await
+                    {
+                        credentials.extend(db_credentials::parse_credential_discovery(
+                            &result.stdout_string(),
+                        ));
+                    }
+                    let _ = cred_tx.send(credentials);
+
                     // Discover MySQL
                     match tokio::time::timeout(exec_timeout, session.exec(&mysql_cmd)).await {
                         Ok(Ok(result)) => {
@@ -381,6 +518,10 @@ impl Workspace {
                         }
                     }
 
+                    // Discover other web servers and services
+                    let _ =
+                        services_tx.send(discover_services(&session, &thread_disc_conn_name).await);
+
                     let _ = done_tx.send(true);
                 });
             });
@@ -467,6 +608,26 @@ impl Workspace {
                     }
                 }
 
+                if let Ok(found) = services_rx.try_recv() {
+                    auto_sites.extend(found.into_iter().map(|site_type| {
+                        ManagedSite::new(disc_conn_id, &disc_conn_name, site_type)
+                    }));
+                }
+
+                if let Ok(credentials) = cred_rx.try_recv() {
+                    let _ = sync_handle.update(cx, |view, cx| {
+                        view.set_discovered_credentials(panel, credentials);
+                        cx.notify();
+                    });
+                }
+
+                if let Ok(installs) = wp_rx.try_recv() {
+                    let _ = sync_handle.update(cx, |view, cx| {
+                        view.set_discovered_wordpress(panel, installs);
+                        cx.notify();
+                    });
+                }
+
                 if done_rx.try_recv().is_ok() {
                     let _ = sync_handle.update(cx, |view, cx| {
                         view.panel_state_mut(panel).discovery_loading = false;
@@ -521,6 +682,7 @@ impl Workspace {
         let pg_cmd = discovery::pg_discover_command("-U postgres");
 
         let (stream_tx, stream_rx) = std::sync::mpsc::channel::<(String, String)>();
+        let (services_tx, services_rx) = std::sync::mpsc::channel::<Vec<ManagedSiteType>>();
         let (done_tx, done_rx) = std::sync::mpsc::channel::<bool>();
 
         let thread_conn_name = conn_name.clone();
@@ -606,6 +768,8 @@ impl Workspace {
                         Err(_) => tracing::warn!("pg discover timed out on {}", thread_conn_name),
                     }
 
+                    let _ = services_tx.send(discover_services(&session, &thread_conn_name).await);
+
                     let _ = done_tx.send(true);
                 });
             });
@@ -653,6 +817,14 @@ impl Workspace {
                     }
                 }
 
+                if let Ok(found) = services_rx.try_recv() {
+                    new_sites.extend(
+                        found
+                            .into_iter()
+                            .map(|site_type| ManagedSite::new(conn_id, &conn_name, site_type)),
+                    );
+                }
+
                 if done_rx.try_recv().is_ok() {
                     let _ = this.update(cx, |ws, cx| {
                         let _ = ws.store.add_managed_sites_bulk(new_sites);
@@ -786,3 +958,119 @@ fn run_local_discovery_tool(label: &str, argv: &[String], timeout: std::time::Du
         }
     }
 }
+
+/// Probe for Apache and Caddy sites, Docker containers and systemd
+/// services. Each probe is best-effort: a host without the tool reports
+/// nothing for it.
+async fn discover_services(session: &SshSession, conn_name: &str) -> Vec<ManagedSiteType> {
+    use shelldeck_core::models::{discovery, services};
+
+    let mut found = Vec::new();
+    let apache = read_config_tree(
+        session,
+        discovery::APACHE_CONFIG_PATHS,
+        discovery::apache_includes,
+        conn_name,
+    )
+    .await;
+    found.extend(
+        discovery::parse_apache_configs(&apache)
+            .into_iter()
+            .map(ManagedSiteType::ApacheSite),
+    );
+    let caddy = read_config_tree(
+        session,
+        discovery::CADDY_CONFIG_PATHS,
+        discovery::caddy_imports,
+        conn_name,
+    )
+    .await;
+    found.extend(
+        discovery::parse_caddyfiles(&caddy)
+            .into_iter()
+            .map(ManagedSiteType::CaddySite),
+    );
+    if let Some(output) = discovery_output(
+        session,
+        services::docker_discover_command(),
+        "docker",
+        conn_name,
+    )
+    .await
+    {
+        found.extend(
+            services::parse_docker_ps(&output)
+                .into_iter()
+                .map(ManagedSiteType::Container),
+        );
+    }
+    if let Some(output) = discovery_output(
+        session,
+        services::systemd_discover_command(),
+        "systemd",
+        conn_name,
+    )
+    .await
+    {
+        found.extend(
+            services::parse_systemd_show(&output)
+                .into_iter()
+                .map(ManagedSiteType::SystemdService),
+        );
+    }
+    found
+}
+
+/// Read a web server's config files, then — up to three levels deep — the
+/// files they include.
+async fn read_config_tree(
+    session: &SshSession,
+    roots: &[&str],
+    includes: fn(&str) -> Vec<String>,
+    conn_name: &str,
+) -> String {
+    use shelldeck_core::models::discovery;
+
+    let mut requested: Vec<String> = roots.iter().map(|r| r.to_string()).collect();
+    let command = discovery::config_files_command(&requested);
+    let Some(mut output) = discovery_output(session, &command, "config", conn_name).await else {
+        return String::new();
+    };
+    let mut latest = output.clone();
+    for _ in 0..3 {
+        let next: Vec<String> = includes(&latest)
+            .into_iter()
+            .filter(|p| !requested.contains(p))
+            .collect();
+        if next.is_empty() {
+            break;
+        }
+        requested.extend(next.iter().cloned());
+        let command = discovery::config_files_command(&next);
+        latest = discovery_output(session, &command, "config", conn_name)
+            .await
+            .unwrap_or_default();
+        output.push_str(&latest);
+    }
+    output
+}
+
+/// Run one discovery command with the usual 30 s budget, logging failures.
+async fn discovery_output(
+    session: &SshSession,
+    command: &str,
+    label: &str,
+    conn_name: &str,
+) -> Option<String> {
+    match tokio::time::timeout(std::time::Duration::from_secs(30), session.exec(command)).await {
+        Ok(Ok(result)) => Some(result.stdout_string()),
+        Ok(Err(e)) => {
+            tracing::debug!("{} discover exec error on {}: {}", label, conn_name, e);
+            None
+        }
+        Err(_) => {
+            tracing::warn!("{} discover timed out on {}", label, conn_name);
+            None
+        }
+    }
+}
@@ -109,6 +109,10 @@ impl Workspace {
                         self.app_config
                             .pinned_connections
                             .retain(|pinned| *pinned != id);
+                        self.app_config.host_metrics.set_monitored(id, false);
+                        self.stop_metrics_collector(id);
+                        self.host_metrics.remove(&id);
+                        self.app_config.log_viewer.forget_connection(id);
                         if let Err(error) = self.app_config.save() {
                             tracing::error!("Failed to persist removed connection pin: {error}");
                         }
@@ -117,6 +121,7 @@ impl Workspace {
                             sidebar.set_connections(self.connections.clone());
                             sidebar
                                 .set_pinned_connections(self.app_config.pinned_connections.clone());
+                            sidebar.set_host_metrics(id, None);
                         });
                         self.port_forwards.update(cx, |pf, _| {
                             pf.forwards.retain(|f| f.connection_id != id);
@@ -0,0 +1,730 @@
+use std::io::{SeekFrom, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::mpsc;
+use std::task::Poll;
+use std::time::Duration;
+
+use gpui::*;
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::file_transfer::{
+    self, ConflictChoice, FileOperation, TransferItem, TransferKind, TransferStatus, TreeEntry,
+};
+use shelldeck_ssh::session::SshSession;
+use tokio::io::{AsyncRead, AsyncSeekExt, ReadBuf};
+use uuid::Uuid;
+
+use crate::server_sync_view::{PanelSide, LOCAL_MACHINE_ID};
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::db_backups::{command_error, connect, relay_output, stream_error};
+use super::Workspace;
+
+/// How often a running transfer's progress is picked up.
+const TRANSFER_POLL: Duration = Duration::from_millis(100);
+
+/// What the thread of a running transfer reports.
+enum TransferUpdate {
+    Progress(u64),
+    /// "Keep both" picked a new destination path.
+    Retarget(String),
+    /// The destination exists and the item has no conflict choice.
+    Conflict,
+    /// A directory was created at the destination; its files follow.
+    Expanded(Vec<TreeEntry>),
+    Finished(TransferStatus),
+    /// Paused or removed while running; the queue knows already.
+    Stopped,
+}
+
+impl TransferUpdate {
+    fn ends_job(&self) -> bool {
+        !matches!(
+            self,
+            TransferUpdate::Progress(_) | TransferUpdate::Retarget(_)
+        )
+    }
+}
+
+impl Workspace {
+    pub(super) fn queue_transfers(&mut self, items: Vec<TransferItem>, cx: &mut Context<Self>) {
+        for item in items {
+            self.file_transfers.enqueue(item);
+        }
+        self.pump_transfers(cx);
+    }
+
+    pub(super) fn pause_transfer(&mut self, id: Uuid, cx: &mut Context<Self>) {
+        self.file_transfers.pause(id);
+        self.stop_transfer_job(id);
+        self.refresh_transfers_view(cx);
+    }
+
+    pub(super) fn resume_transfer(&mut self, id: Uuid, cx: &mut Context<Self>) {
+        self.file_transfers.resume(id);
+        self.pump_transfers(cx);
+    }
+
+    pub(super) fn retry_transfer(&mut self, id: Uuid, cx: &mut Context<Self>) {
+        self.file_transfers.retry(id);
+        self.pump_transfers(cx);
+    }
+
+    /// Drop an item from the queue, stopping it if it runs, and delete what
+    /// it wrote so far.
+    pub(super) fn cancel_transfer(&mut self, id: Uuid, cx: &mut Context<Self>) {
+        self.stop_transfer_job(id);
+        for item in self.file_transfers.remove(id) {
+            if item.is_dir || item.status.is_finished() {
+                continue;
+            }
+            let part = item.part_path();
+            self.run_file_command(
+                item.dest.connection_id,
+                FileOperation::Delete { paths: vec![part] },
+                |_, _, _| {},
+                cx,
+            );
+        }
+        self.refresh_transfers_view(cx);
+    }
+
+    pub(super) fn resolve_transfer_conflict(
+        &mut self,
+        id: Uuid,
+        choice: ConflictChoice,
+        apply_to_all: bool,
+        cx: &mut Context<Self>,
+    ) {
+        self.file_transfers.resolve(id, choice, apply_to_all);
+        self.pump_transfers(cx);
+    }
+
+    pub(super) fn clear_finished_transfers(&mut self, cx: &mut Context<Self>) {
+        self.file_transfers.clear_finished();
+        if !self.file_transfers.is_busy() {
+            self.file_transfers.conflict_default = None;
+        }
+        self.refresh_transfers_view(cx);
+    }
+
+    /// Signal the running transfer to stop when it is `id` or a file of it.
+    fn stop_transfer_job(&self, id: Uuid) {
+        let Some((running, shutdown)) = &self.transfer_job else {
+            return;
+        };
+        let parent = self.file_transfers.get(*running).and_then(|i| i.parent);
+        if *running == id || parent == Some(id) {
+            let _ = shutdown.try_send(());
+        }
+    }
+
+    fn refresh_transfers_view(&mut self, cx: &mut Context<Self>) {
+        let queue = self.file_transfers.clone();
+        self.server_sync.update(cx, |view, cx| {
+            view.set_transfers(queue);
+            cx.notify();
+        });
+    }
+
+    /// Start the next queued item unless one is running.
+    fn pump_transfers(&mut self, cx: &mut Context<Self>) {
+        if self.transfer_job.is_some() {
+            return;
+        }
+        let Some(item) = self.file_transfers.start_next() else {
+            self.refresh_transfers_view(cx);
+            return;
+        };
+        let find = |id: Option<Uuid>| match id {
+            None => Some(None),
+            Some(id) => self
+                .connections
+                .iter()
+                .find(|c| c.id == id)
+                .cloned()
+                .map(Some),
+        };
+        let (Some(source), Some(dest)) = (
+            find(item.source.connection_id),
+            find(item.dest.connection_id),
+        ) else {
+            let error = t!("sync.files.error.unknown_server").to_string();
+            self.transfer_update(
+                item.id,
+                TransferUpdate::Finished(TransferStatus::Failed(error)),
+                cx,
+            );
+            self.pump_transfers(cx);
+            return;
+        };
+
+        let id = item.id;
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
+        let (update_tx, update_rx) = mpsc::channel::<TransferUpdate>();
+        let spawn_result = std::thread::Builder::new()
+            .name("file-transfer".to_string())
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        let error = format!("async runtime: {}", e);
+                        let _ =
+                            update_tx.send(TransferUpdate::Finished(TransferStatus::Failed(error)));
+                        return;
+                    }
+                };
+                let result = rt.block_on(transfer(
+                    item,
+                    source.as_ref(),
+                    dest.as_ref(),
+                    &update_tx,
+                    &mut shutdown_rx,
+                ));
+                let _ = update_tx.send(
+                    result.unwrap_or_else(|e| TransferUpdate::Finished(TransferStatus::Failed(e))),
+                );
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn transfer thread: {}", e);
+            let error = e.to_string();
+            self.transfer_update(
+                id,
+                TransferUpdate::Finished(TransferStatus::Failed(error)),
+                cx,
+            );
+            return;
+        }
+        self.transfer_job = Some((id, shutdown_tx));
+        self.refresh_transfers_view(cx);
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor().timer(TRANSFER_POLL).await;
+            let mut updates = Vec::new();
+            let ended = loop {
+                match update_rx.try_recv() {
+                    Ok(update) => {
+                        let ends = update.ends_job();
+                        updates.push(update);
+                        if ends {
+                            break true;
+                        }
+                    }
+                    Err(mpsc::TryRecvError::Empty) => break false,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        updates.push(TransferUpdate::Finished(TransferStatus::Failed(
+                            "transfer thread ended unexpectedly".to_string(),
+                        )));
+                        break true;
+                    }
+                }
+            };
+            let _ = this.update(cx, |ws, cx| {
+                for update in updates {
+                    ws.transfer_update(id, update, cx);
+                }
+                if ended {
+                    ws.transfer_job = None;
+                    ws.pump_transfers(cx);
+                    if !ws.file_transfers.is_busy() {
+                        ws.refresh_file_panels(&[], cx);
+                    }
+                }
+                ws.refresh_transfers_view(cx);
+            });
+            if ended {
+                break;
+            }
+        })
+        .detach();
+    }
+
+    fn transfer_update(&mut self, id: Uuid, update: TransferUpdate, cx: &mut Context<Self>) {
+        let settled = match update {
+            TransferUpdate::Progress(bytes) => {
+                self.file_transfers.set_progress(id, bytes);
+                None
+            }
+            TransferUpdate::Retarget(path) => {
+                self.file_transfers.retarget(id, path);
+                None
+            }
+            TransferUpdate::Conflict => {
+                self.file_transfers.set_conflict(id);
+                None
+            }
+            TransferUpdate::Expanded(entries) => self.file_transfers.expand(id, &entries),
+            TransferUpdate::Finished(status) => self.file_transfers.finish(id, status),
+            TransferUpdate::Stopped => None,
+        };
+        // Files of a moved directory went one by one; drop the directories
+        // they leave behind.
+        if let Some(dir) = settled.filter(|d| {
+            d.kind == TransferKind::Move
+                && d.status == TransferStatus::Done
+                && !d.source.same_host(&d.dest)
+        }) {
+            let source = dir.source.path.clone();
+            match dir.source.connection_id {
+                None => file_transfer::remove_empty_local_dirs(Path::new(&source)),
+                Some(id) => self.run_remote_command(
+                    id,
+                    file_transfer::remove_empty_dirs_command(&source),
+                    |_, _, _| {},
+                    cx,
+                ),
+            }
+        }
+    }
+
+    /// Rename, delete, create or chmod on one side, then list it again.
+    pub(super) fn run_file_operation(
+        &mut self,
+        connection_id: Uuid,
+        operation: FileOperation,
+        cx: &mut Context<Self>,
+    ) {
+        let location = (connection_id != LOCAL_MACHINE_ID).then_some(connection_id);
+        self.run_file_command(
+            location,
+            operation,
+            move |ws, result, cx| {
+                if let Err(error) = result {
+                    ws.show_toast(
+                        t!("toast.sync.file_operation_failed", error = error).to_string(),
+                        ToastLevel::Error,
+                        cx,
+                    );
+                }
+                ws.refresh_file_panels(&[location], cx);
+            },
+            cx,
+        );
+    }
+
+    /// Run `operation` on this computer (`None`) or a server, then call
+    /// `done` with the outcome.
+    fn run_file_command(
+        &mut self,
+        connection_id: Option<Uuid>,
+        operation: FileOperation,
+        done: impl FnOnce(&mut Workspace, Result<(), String>, &mut Context<Workspace>) + 'static,
+        cx: &mut Context<Self>,
+    ) {
+        match connection_id {
+            Some(id) => self.run_remote_command(id, operation.command(), done, cx),
+            None => {
+                let result = operation.apply_local().map_err(|e| e.to_string());
+                done(self, result, cx);
+            }
+        }
+    }
+
+    fn run_remote_command(
+        &mut self,
+        connection_id: Uuid,
+        command: String,
+        done: impl FnOnce(&mut Workspace, Result<(), String>, &mut Context<Workspace>) + 'static,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(conn) = self
+            .connections
+            .iter()
+            .find(|c| c.id == connection_id)
+            .cloned()
+        else {
+            done(
+                self,
+                Err(t!("sync.files.error.unknown_server").to_string()),
+                cx,
+            );
+            return;
+        };
+        let (done_tx, done_rx) = mpsc::channel::<Result<(), String>>();
+        let spawn_result = std::thread::Builder::new()
+            .name("file-command".to_string())
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        let _ = done_tx.send(Err(format!("async runtime: {}", e)));
+                        return;
+                    }
+                };
+                let result = rt.block_on(async move {
+                    let session = connect(&conn).await?;
+                    let result = session.exec(&command).await.map_err(|e| e.to_string())?;
+                    if result.success() {
+                        Ok(())
+                    } else {
+                        Err(command_error(&result.stderr, result.exit_code))
+                    }
+                });
+                let _ = done_tx.send(result);
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn file command thread: {}", e);
+            done(self, Err(e.to_string()), cx);
+            return;
+        }
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let result = loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(250))
+                    .await;
+                match done_rx.try_recv() {
+                    Ok(result) => break result,
+                    Err(mpsc::TryRecvError::Empty) => continue,
+                    Err(mpsc::TryRecvError::Disconnected) => {
+                        break Err("file command thread ended unexpectedly".to_string())
+                    }
+                }
+            };
+            let _ = this.update(cx, |ws, cx| done(ws, result, cx));
+        })
+        .detach();
+    }
+
+    /// List the panels again, all of them or those showing one of
+    /// `connection_ids` (`None` is this computer).
+    fn refresh_file_panels(&mut self, connection_ids: &[Option<Uuid>], cx: &mut Context<Self>) {
+        let panels: Vec<(PanelSide, Uuid, String)> = {
+            let view = self.server_sync.read(cx);
+            [
+                (PanelSide::Source, &view.source_panel),
+                (PanelSide::Destination, &view.dest_panel),
+            ]
+            .into_iter()
+            .filter_map(|(side, state)| {
+                let id = state.connection_id?;
+                let location = (id != LOCAL_MACHINE_ID).then_some(id);
+                (connection_ids.is_empty() || connection_ids.contains(&location))
+                    .then(|| (side, id, state.current_path.clone()))
+            })
+            .collect()
+        };
+        for (side, id, path) in panels {
+            if id == LOCAL_MACHINE_ID {
+                self.list_local_files(path, side, cx);
+            } else if let Some(conn) = self.connections.iter().find(|c| c.id == id).cloned() {
+                self.list_remote_files(conn, path, side, cx);
+            }
+        }
+    }
+}
+
+/// Counts what a reader hands out and reports it as progress.
+struct ProgressReader<R> {
+    inner: R,
+    done: u64,
+    updates: mpsc::Sender<TransferUpdate>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for ProgressReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let read = buf.filled().len() - before;
+        if read > 0 {
+            self.done += read as u64;
+            let _ = self.updates.send(TransferUpdate::Progress(self.done));
+        }
+        poll
+    }
+}
+
+/// Run one queue item. `source` and `dest` are the servers of either side,
+/// `None` for this computer.
+async fn transfer(
+    mut item: TransferItem,
+    source: Option<&Connection>,
+    dest: Option<&Connection>,
+    updates: &mpsc::Sender<TransferUpdate>,
+    shutdown_rx: &mut tokio::sync::mpsc::Receiver<()>,
+) -> Result<TransferUpdate, String> {
+    let same_host = item.source.same_host(&item.dest);
+    let source_session = match source {
+        Some(conn) => Some(connect(conn).await?),
+        None => None,
+    };
+    let dest_session = match dest {
+        Some(conn) if !same_host => Some(connect(conn).await?),
+        _ => None,
+    };
+    let dest_host = if same_host {
+        source_session.as_ref()
+    } else {
+        dest_session.as_ref()
+    };
+
+    // A paused file resumes into its part file; its conflict was settled
+    // when it started.
+    if item.conflict != Some(ConflictChoice::Overwrite)
+        && item.transferred == 0
+        && exists(dest_host, &item.dest.path).await?
+    {
+        match item.conflict {
+            None => return Ok(TransferUpdate::Conflict),
+            Some(ConflictChoice::Skip) => {
+                return Ok(TransferUpdate::Finished(TransferStatus::Skipped))
+            }
+            Some(ConflictChoice::KeepBoth) => {
+                let path = free_path(dest_host, &item.dest.path, item.is_dir).await?;
+                let _ = updates.send(TransferUpdate::Retarget(path.clone()));
+                item.dest.path = path;
+            }
+            Some(ConflictChoice::Overwrite) => {}
+        }
+    }
+
+    if same_host {
+        match &source_session {
+            None => file_transfer::same_host_local(
+                item.kind,
+                Path::new(&item.source.path),
+                Path::new(&item.dest.path),
+                item.is_dir,
+            )
+            .map_err(|e| e.to_string())?,
+            Some(session) => {
+                let command = file_transfer::same_host_command(
+                    item.kind,
+                    &item.source.path,
+                    &item.dest.path,
+                    item.is_dir,
+                );
+                run(session, &command).await?;
+            }
+        }
+        return Ok(TransferUpdate::Finished(TransferStatus::Done));
+    }
+
+    if item.is_dir {
+        let entries = match &source_session {
+            None => file_transfer::local_tree(Path::new(&item.source.path))
+                .map_err(|e| e.to_string())?,
+            Some(session) => {
+                let output = run(session, &file_transfer::tree_command(&item.source.path)).await?;
+                file_transfer::parse_tree_listing(&output)
+            }
+        };
+        let dirs: Vec<String> = std::iter::once(item.dest.path.clone())
+            .chain(
+                entries
+                    .iter()
+                    .filter(|e| e.is_dir)
+                    .map(|e| item.dest.join(&e.rel_path)),
+            )
+            .collect();
+        match &dest_session {
+            None => {
+                for dir in &dirs {
+                    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+            }
+            Some(session) => {
+                run(session, &file_transfer::mkdirs_command(&dirs)).await?;
+            }
+        }
+        return Ok(TransferUpdate::Expanded(entries));
+    }
+
+    let stopped = transfer_file(
+        &item,
+        source_session.as_ref(),
+        dest_session.as_ref(),
+        updates,
+        shutdown_rx,
+    )
+    .await?;
+    if stopped {
+        return Ok(TransferUpdate::Stopped);
+    }
+    if item.kind == TransferKind::Move {
+        match &source_session {
+            None => std::fs::remove_file(&item.source.path).map_err(|e| e.to_string())?,
+            Some(session) => {
+                let remove = FileOperation::Delete {
+                    paths: vec![item.source.path.clone()],
+                };
+                run(session, &remove.command()).await?;
+            }
+        }
+    }
+    Ok(TransferUpdate::Finished(TransferStatus::Done))
+}
+
+/// Stream one file between two machines into its part file, resuming
+/// where a pause left it, then move it into place. Returns `true` when a
+/// pause stopped it first.
+async fn transfer_file(
+    item: &TransferItem,
+    source: Option<&SshSession>,
+    dest: Option<&SshSession>,
+    updates: &mpsc::Sender<TransferUpdate>,
+    shutdown_rx: &mut tokio::sync::mpsc::Receiver<()>,
+) -> Result<bool, String> {
+    let part = item.part_path();
+    let offset = if item.transferred == 0 {
+        0
+    } else {
+        match dest {
+            None => std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0),
+            Some(session) => run(session, &file_transfer::part_size_command(&part))
+                .await?
+                .trim()
+                .parse()
+                .unwrap_or(0),
+        }
+    };
+    let _ = updates.send(TransferUpdate::Progress(offset));
+    let write = file_transfer::write_part_command(&part, offset > 0);
+    let read = file_transfer::read_command(&item.source.path, offset);
+
+    match (source, dest) {
+        (None, Some(dest)) => {
+            let mut file = tokio::fs::File::open(&item.source.path)
+                .await
+                .map_err(|e| e.to_string())?;
+            file.seek(SeekFrom::Start(offset))
+                .await
+                .map_err(|e| e.to_string())?;
+            let mut reader = ProgressReader {
+                inner: file,
+                done: offset,
+                updates: updates.clone(),
+            };
+            let outcome = dest
+                .exec_from_reader(&write, &mut reader, shutdown_rx)
+                .await
+                .map_err(|e| e.to_string())?;
+            if outcome.cancelled {
+                return Ok(true);
+            }
+            if !outcome.success() {
+                return Err(stream_error(&outcome));
+            }
+            run(
+                dest,
+                &file_transfer::commit_part_command(&part, &item.dest.path),
+            )
+            .await?;
+        }
+        (Some(source), None) => {
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(offset > 0)
+                .truncate(offset == 0)
+                .open(&part)
+                .map_err(|e| e.to_string())?;
+            let mut done = offset;
+            let progress = updates.clone();
+            let mut sink = |chunk: &[u8]| -> std::io::Result<()> {
+                file.write_all(chunk)?;
+                done += chunk.len() as u64;
+                let _ = progress.send(TransferUpdate::Progress(done));
+                Ok(())
+            };
+            let outcome = source
+                .exec_to_sink(&read, &mut sink, shutdown_rx)
+                .await
+                .map_err(|e| e.to_string())?;
+            if outcome.cancelled {
+                return Ok(true);
+            }
+            if !outcome.success() {
+                return Err(stream_error(&outcome));
+            }
+            // Windows does not rename over an existing file.
+            if Path::new(&item.dest.path).is_file() {
+                std::fs::remove_file(&item.dest.path).map_err(|e| e.to_string())?;
+            }
+            std::fs::rename(&part, &item.dest.path).map_err(|e| e.to_string())?;
+        }
+        (Some(source), Some(dest)) => {
+            let (events, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+            let mut done = offset;
+            let progress = updates.clone();
+            let mut count = |chunk: Option<&[u8]>| -> Vec<u8> {
+                match chunk {
+                    Some(chunk) => {
+                        done += chunk.len() as u64;
+                        let _ = progress.send(TransferUpdate::Progress(done));
+                        chunk.to_vec()
+                    }
+                    None => Vec::new(),
+                }
+            };
+            let outcome = source
+                .relay_through(&read, dest, &write, events, shutdown_rx, None, &mut count)
+                .await
+                .map_err(|e| e.to_string())?;
+            if outcome.cancelled {
+                return Ok(true);
+            }
+            if outcome.source_exit != Some(0) || outcome.dest_exit != Some(0) {
+                let exit = outcome
+                    .source_exit
+                    .filter(|c| *c != 0)
+                    .or(outcome.dest_exit);
+                return Err(command_error(&relay_output(&mut events_rx), exit));
+            }
+            run(
+                dest,
+                &file_transfer::commit_part_command(&part, &item.dest.path),
+            )
+            .await?;
+        }
+        (None, None) => unreachable!("transfers on one machine are a plain copy"),
+    }
+    Ok(false)
+}
+
+/// Run `command`, returning its stdout or what went wrong.
+async fn run(session: &SshSession, command: &str) -> Result<String, String> {
+    let result = session.exec(command).await.map_err(|e| e.to_string())?;
+    if result.success() {
+        Ok(result.stdout_string())
+    } else {
+        Err(command_error(&result.stderr, result.exit_code))
+    }
+}
+
+async fn exists(host: Option<&SshSession>, path: &str) -> Result<bool, String> {
+    match host {
+        None => Ok(Path::new(path).symlink_metadata().is_ok()),
+        Some(session) => session
+            .exec(&file_transfer::exists_command(path))
+            .await
+            .map(|r| r.success())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// First "name (n).ext" next to `path` that is not taken.
+async fn free_path(host: Option<&SshSession>, path: &str, is_dir: bool) -> Result<String, String> {
+    let candidates = file_transfer::keep_both_candidates(path, is_dir);
+    let free = match host {
+        None => candidates
+            .into_iter()
+            .find(|c| Path::new(c).symlink_metadata().is_err()),
+        Some(session) => run(session, &file_transfer::first_free_command(&candidates))
+            .await
+            .ok()
+            .map(|out| out.trim_end_matches('\n').to_string()),
+    };
+    free.ok_or_else(|| format!("No free name next to {}", path))
+}
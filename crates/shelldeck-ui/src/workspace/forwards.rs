@@ -1,11 +1,15 @@
+use std::io::Write;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpStream};
+use std::time::Duration;
+
 use gpui::prelude::*;
 use gpui::*;
 use shelldeck_core::ai::AiSurface;
 use shelldeck_core::config::activity::{ActivityAction, ActivityEntry, ActivityKind};
 use shelldeck_core::config::cloud_account::AppMode;
-use shelldeck_core::models::port_forward::{ForwardDirection, ForwardStatus};
-use shelldeck_ssh::client::SshClient;
-use shelldeck_ssh::tunnel::TunnelHandle;
+use shelldeck_core::models::connection::ConnectionSource;
+use shelldeck_core::models::port_forward::ForwardStatus;
+use shelldeck_core::models::tunnel_health::TunnelState;
 use uuid::Uuid;
 
 use crate::ai_workflow::{AiNamingKind, AiWorkflowTarget};
@@ -14,7 +18,19 @@ use crate::port_forward_view::PortForwardEvent;
 use crate::t;
 use crate::toast::ToastLevel;
 
-use super::{ActiveTunnel, Workspace};
+use super::Workspace;
+
+/// How long a replayed request may wait for its answer.
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Write `request` to the forward at `addr` and read the answer to the end.
+fn send_replay(addr: SocketAddr, request: &[u8]) -> std::io::Result<()> {
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(5))?;
+    stream.set_read_timeout(Some(REPLAY_TIMEOUT))?;
+    stream.write_all(request)?;
+    std::io::copy(&mut stream, &mut std::io::sink())?;
+    Ok(())
+}
 
 impl Workspace {
     pub(super) fn handle_forward_event(
@@ -26,398 +42,17 @@ impl Workspace {
             return;
         }
         match event {
-            PortForwardEvent::StartForward(id) => {
-                let forward_id = *id;
-                tracing::info!("Start forward requested: {}", forward_id);
-
-                // Look up the port forward configuration
-                let forward = {
-                    let pf_view = self.port_forwards.read(cx);
-                    pf_view
-                        .forwards
-                        .iter()
-                        .find(|f| f.id == forward_id)
-                        .cloned()
-                };
-                let forward = match forward {
-                    Some(f) => f,
-                    None => {
-                        tracing::error!("Port forward not found: {}", forward_id);
-                        self.add_activity(
-                            t!("activity.forward_not_found", id = forward_id).to_string(),
-                            ActivityKind::Error,
-                            cx,
-                        );
-                        self.show_toast(
-                            t!("toast.port_forward.not_found").to_string(),
-                            ToastLevel::Error,
-                            cx,
-                        );
-                        return;
-                    }
-                };
-
-                // Don't start if already active
-                if self.active_tunnels.contains_key(&forward_id) {
-                    tracing::warn!("Port forward {} is already active", forward_id);
-                    return;
-                }
-
-                // Look up the connection for this forward
-                let connection = self
-                    .connections
-                    .iter()
-                    .find(|c| c.id == forward.connection_id)
-                    .cloned();
-                let connection = match connection {
-                    Some(c) => c,
-                    None => {
-                        tracing::error!(
-                            "Connection {} not found for port forward {}",
-                            forward.connection_id,
-                            forward_id
-                        );
-                        self.port_forwards.update(cx, |pf, _| {
-                            if let Some(f) = pf.forwards.iter_mut().find(|f| f.id == forward_id) {
-                                f.status = ForwardStatus::Error;
-                            }
-                        });
-                        self.add_activity(
-                            t!("activity.forward_connection_not_found").to_string(),
-                            ActivityKind::Error,
-                            cx,
-                        );
-                        self.show_toast(
-                            t!("toast.forward.connection_not_found").to_string(),
-                            ToastLevel::Error,
-                            cx,
-                        );
-                        cx.notify();
-                        return;
-                    }
-                };
-
-                let label = forward
-                    .label
-                    .clone()
-                    .unwrap_or_else(|| forward.description());
-
-                // Update status to show we're starting
-                self.port_forwards.update(cx, |pf, _| {
-                    if let Some(f) = pf.forwards.iter_mut().find(|f| f.id == forward_id) {
-                        f.status = ForwardStatus::Active;
-                    }
-                });
-
-                self.add_activity_entry(
-                    ActivityEntry::new(
-                        ActivityKind::Forward,
-                        t!("activity.forward_starting", label = label.as_str()).to_string(),
-                    )
-                    .with_target(forward_id.to_string(), label.clone())
-                    .with_action(ActivityAction::OpenForward),
-                    cx,
-                );
-                self.show_toast(
-                    t!("toast.forward.starting", label = label.as_str()).to_string(),
-                    ToastLevel::Info,
-                    cx,
-                );
-
-                // Use a channel to send the TunnelHandle back from the background thread
-                let (result_tx, result_rx) =
-                    std::sync::mpsc::channel::<Result<TunnelHandle, String>>();
-
-                let direction = forward.direction;
-                let local_port = forward.local_port;
-                let remote_host = forward.remote_host.clone();
-                let remote_port = forward.remote_port;
-                let local_host = forward.local_host.clone();
-
-                // Spawn a dedicated thread with its own tokio runtime for the SSH tunnel.
-                // The thread stays alive as long as the tunnel is running; the tokio runtime
-                // drives the TcpListener accept loop inside start_local_forward/start_remote_forward.
-                let thread_handle = std::thread::Builder::new()
-                    .name(format!("tunnel-{}", forward_id))
-                    .spawn(move || {
-                        let rt = match tokio::runtime::Builder::new_current_thread()
-                            .enable_all()
-                            .build()
-                        {
-                            Ok(rt) => rt,
-                            Err(e) => {
-                                let msg = format!("Failed to create async runtime: {}", e);
-                                tracing::error!("{}", msg);
-                                let _ = result_tx.send(Err(msg));
-                                return;
-                            }
-                        };
-
-                        rt.block_on(async move {
-                            // Establish SSH connection
-                            let client = SshClient::new();
-                            let mut session = match client.connect(&connection).await {
-                                Ok(s) => s,
-                                Err(e) => {
-                                    let msg = format!("SSH connection failed: {}", e);
-                                    tracing::error!("{}", msg);
-                                    let _ = result_tx.send(Err(msg));
-                                    return;
-                                }
-                            };
-                            tracing::info!(
-                                "SSH connected for tunnel to {}",
-                                connection.display_name()
-                            );
-
-                            let shared_handle = session.shared_handle();
-                            let mut tunnel_manager = shelldeck_ssh::tunnel::TunnelManager::new();
-
-                            let tunnel_result = match direction {
-                                ForwardDirection::LocalToRemote => {
-                                    tunnel_manager
-                                        .start_local_forward(
-                                            shared_handle,
-                                            local_port,
-                                            remote_host,
-                                            remote_port,
-                                        )
-                                        .await
-                                }
-                                ForwardDirection::RemoteToLocal => {
-                                    match session.take_forwarded_tcpip_rx() {
-                                        Some(forwarded_rx) => {
-                                            tunnel_manager
-                                                .start_remote_forward(
-                                                    shared_handle,
-                                                    remote_port,
-                                                    local_host,
-                                                    local_port,
-                                                    forwarded_rx,
-                                                )
-                                                .await
-                                        }
-                                        None => Err(shelldeck_ssh::SshError::Tunnel(
-                                            "remote forwarding channel already taken".to_string(),
-                                        )),
-                                    }
-                                }
-                                ForwardDirection::Dynamic => {
-                                    tunnel_manager
-                                        .start_socks_forward(shared_handle, local_host, local_port)
-                                        .await
-                                }
-                            };
-
-                            match tunnel_result {
-                                Ok(_tunnel_id) => {
-                                    // Create a proxy shutdown channel so the UI thread can
-                                    // signal this background thread to tear down the tunnel.
-                                    let (thread_shutdown_tx, mut thread_shutdown_rx) =
-                                        tokio::sync::mpsc::channel::<()>(1);
-
-                                    // Build a proxy TunnelHandle that shares the real tunnel's
-                                    // Arc-wrapped status and byte counters but uses the
-                                    // thread-level shutdown channel.
-                                    let tunnel_ref = &tunnel_manager.tunnels()
-                                        [tunnel_manager.tunnels().len() - 1];
-                                    let proxy_handle = TunnelHandle::new_proxy(
-                                        tunnel_ref.id,
-                                        tunnel_ref.status.clone(),
-                                        tunnel_ref.bytes_sent.clone(),
-                                        tunnel_ref.bytes_received.clone(),
-                                        thread_shutdown_tx,
-                                    );
-
-                                    let _ = result_tx.send(Ok(proxy_handle));
-
-                                    // Park this thread -- keep the tokio runtime alive so the
-                                    // tunnel's spawned tasks continue to run. Wait for shutdown.
-                                    thread_shutdown_rx.recv().await;
-
-                                    // Shutdown received -- stop all tunnels and exit
-                                    tracing::info!("Stopping tunnels for forward {}", forward_id);
-                                    tunnel_manager.stop_all();
-
-                                    // Give tunnel tasks a moment to clean up
-                                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-                                }
-                                Err(e) => {
-                                    let msg = format!("Tunnel start failed: {}", e);
-                                    tracing::error!("{}", msg);
-                                    let _ = result_tx.send(Err(msg));
-                                }
-                            }
-                        });
-                    });
-                let thread_handle = match thread_handle {
-                    Ok(h) => h,
-                    Err(e) => {
-                        tracing::error!("Failed to spawn tunnel thread: {}", e);
-                        self.port_forwards.update(cx, |pf, _| {
-                            if let Some(f) = pf.forwards.iter_mut().find(|f| f.id == forward_id) {
-                                f.status = ForwardStatus::Error;
-                            }
-                        });
-                        self.add_activity(
-                            t!("activity.forward_start_failed", label = label.as_str()).to_string(),
-                            ActivityKind::Error,
-                            cx,
-                        );
-                        self.show_toast(
-                            t!("toast.forward.start_failed", error = e.to_string()).to_string(),
-                            ToastLevel::Error,
-                            cx,
-                        );
-                        cx.notify();
-                        return;
-                    }
-                };
-
-                // Now wait for the result from the background thread.
-                // We use cx.spawn to avoid blocking the UI thread.
-                let pf_handle = self.port_forwards.downgrade();
-                let weak_self = cx.entity().downgrade();
-                let label_for_activity = label.clone();
-
-                cx.spawn(async move |_this, cx: &mut AsyncApp| {
-                    // Wait for the result on the background executor so we don't block GPUI
-                    let result = cx
-                        .background_executor()
-                        .spawn(async move {
-                            // The SSH connection + tunnel setup happens on the dedicated thread.
-                            // We give it a generous timeout.
-                            result_rx.recv_timeout(std::time::Duration::from_secs(30))
-                        })
-                        .await;
-
-                    match result {
-                        Ok(Ok(tunnel_handle)) => {
-                            tracing::info!(
-                                "Tunnel started successfully for forward {}",
-                                forward_id
-                            );
-
-                            // Store the active tunnel in the workspace
-                            let _ = weak_self.update(cx, |ws, cx| {
-                                ws.active_tunnels.insert(
-                                    forward_id,
-                                    ActiveTunnel {
-                                        tunnel_handle,
-                                        _thread: thread_handle,
-                                    },
-                                );
-
-                                // Update forward status to Active
-                                ws.port_forwards.update(cx, |pf, _| {
-                                    if let Some(f) =
-                                        pf.forwards.iter_mut().find(|f| f.id == forward_id)
-                                    {
-                                        f.status = ForwardStatus::Active;
-                                    }
-                                });
-
-                                ws.add_activity_entry(
-                                    ActivityEntry::new(
-                                        ActivityKind::Forward,
-                                        t!(
-                                            "activity.forward_active",
-                                            label = label_for_activity.as_str()
-                                        )
-                                        .to_string(),
-                                    )
-                                    .with_target(forward_id.to_string(), label_for_activity.clone())
-                                    .with_action(ActivityAction::OpenForward),
-                                    cx,
-                                );
-                                ws.show_toast(
-                                    t!("toast.forward.active", label = label_for_activity.as_str())
-                                        .to_string(),
-                                    ToastLevel::Success,
-                                    cx,
-                                );
-                                ws.update_dashboard_stats(cx);
-                                cx.notify();
-                            });
-                        }
-                        Ok(Err(err_msg)) => {
-                            tracing::error!(
-                                "Tunnel failed for forward {}: {}",
-                                forward_id,
-                                err_msg
-                            );
-
-                            let _ = pf_handle.update(cx, |pf, cx| {
-                                if let Some(f) = pf.forwards.iter_mut().find(|f| f.id == forward_id)
-                                {
-                                    f.status = ForwardStatus::Error;
-                                }
-                                cx.notify();
-                            });
-
-                            let _ = weak_self.update(cx, |ws, cx| {
-                                ws.add_activity(
-                                    t!("activity.forward_failed", error = err_msg.as_str())
-                                        .to_string(),
-                                    ActivityKind::Error,
-                                    cx,
-                                );
-                                ws.show_toast(
-                                    t!("toast.forward.failed", error = err_msg.as_str())
-                                        .to_string(),
-                                    ToastLevel::Error,
-                                    cx,
-                                );
-                            });
-                        }
-                        Err(_timeout) => {
-                            tracing::error!("Tunnel setup timed out for forward {}", forward_id);
-
-                            let _ = pf_handle.update(cx, |pf, cx| {
-                                if let Some(f) = pf.forwards.iter_mut().find(|f| f.id == forward_id)
-                                {
-                                    f.status = ForwardStatus::Error;
-                                }
-                                cx.notify();
-                            });
-
-                            let _ = weak_self.update(cx, |ws, cx| {
-                                ws.add_activity(
-                                    t!(
-                                        "activity.forward_timeout",
-                                        label = label_for_activity.as_str()
-                                    )
-                                    .to_string(),
-                                    ActivityKind::Error,
-                                    cx,
-                                );
-                                ws.show_toast(
-                                    t!(
-                                        "toast.forward.timeout",
-                                        label = label_for_activity.as_str()
-                                    )
-                                    .to_string(),
-                                    ToastLevel::Warning,
-                                    cx,
-                                );
-                            });
-                        }
-                    }
-                })
-                .detach();
-
-                cx.notify();
-            }
+            PortForwardEvent::StartForward(id) => self.start_forward(*id, cx),
             PortForwardEvent::StopForward(id) => {
                 let forward_id = *id;
                 tracing::info!("Stop forward requested: {}", forward_id);
 
                 // Look up and remove the active tunnel
                 if let Some(active_tunnel) = self.active_tunnels.remove(&forward_id) {
-                    // Signal the tunnel to stop. This sends through the shutdown channel
-                    // which causes the background thread's tokio runtime to stop the
-                    // TunnelManager and exit.
+                    // Signal the supervisor to stop. It tears down the tunnel and
+                    // its session, and the background thread exits.
                     active_tunnel.tunnel_handle.stop();
+                    self.record_tunnel_state(forward_id, TunnelState::Stopped, None, cx);
 
                     // Capture final byte counts before we drop the handle
                     let (final_sent, final_recv) = active_tunnel.tunnel_handle.total_bytes();
@@ -429,6 +64,8 @@ impl Workspace {
                             f.bytes_sent = final_sent;
                             f.bytes_received = final_recv;
                         }
+                        pf.probes.remove(&forward_id);
+                        pf.bound_addrs.remove(&forward_id);
                     });
 
                     let label = {
@@ -496,7 +133,69 @@ impl Workspace {
                 // Open the form pre-filled with preset values so the user can pick a connection
                 self.show_port_forward_form_edit(preset, cx);
             }
+            PortForwardEvent::ReplayRequest {
+                forward_id,
+                exchange_id,
+            } => self.replay_forward_request(*forward_id, *exchange_id, cx),
+        }
+    }
+
+    /// Send a captured request through its forward again. The answer is
+    /// read and dropped here; the inspector records it as a new exchange.
+    fn replay_forward_request(
+        &mut self,
+        forward_id: Uuid,
+        exchange_id: u64,
+        cx: &mut Context<Self>,
+    ) {
+        let view = self.port_forwards.read(cx);
+        let request = view.traffic.get(&forward_id).and_then(|traffic| {
+            let log = traffic.lock();
+            let exchange = log.exchange(exchange_id)?;
+            Some((
+                exchange.replay_bytes()?,
+                format!("{} {}", exchange.method, exchange.path),
+            ))
+        });
+        let bound = view.bound_addrs.get(&forward_id).copied();
+        let Some((bytes, summary)) = request else {
+            return;
+        };
+        let Some(mut addr) = bound.filter(|_| self.active_tunnels.contains_key(&forward_id)) else {
+            self.show_toast(
+                t!("toast.forward.replay_not_running").to_string(),
+                ToastLevel::Warning,
+                cx,
+            );
+            return;
+        };
+        // A forward listening on every interface is still reached locally.
+        if addr.ip().is_unspecified() {
+            addr.set_ip(match addr {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            });
         }
+
+        let replay = cx
+            .background_executor()
+            .spawn(async move { send_replay(addr, &bytes) });
+        cx.spawn(async move |this, cx| {
+            let result = replay.await;
+            let _ = this.update(cx, |ws, cx| match result {
+                Ok(()) => ws.show_toast(
+                    t!("toast.forward.replayed", request = summary.as_str()).to_string(),
+                    ToastLevel::Success,
+                    cx,
+                ),
+                Err(e) => ws.show_toast(
+                    t!("toast.forward.replay_failed", error = e.to_string()).to_string(),
+                    ToastLevel::Error,
+                    cx,
+                ),
+            });
+        })
+        .detach();
     }
 
     fn show_port_forward_form(&mut self, cx: &mut Context<Self>) {
@@ -597,6 +296,12 @@ impl Workspace {
             match event {
                 PortForwardFormEvent::Save(forward) => {
                     tracing::info!("Port forward updated: {}", forward.description());
+                    let mut forward = forward.clone();
+                    forward.from_ssh_config = this
+                        .store
+                        .port_forwards
+                        .iter()
+                        .any(|f| f.id == forward.id && f.from_ssh_config);
                     // Update in store
                     match this.store.update_port_forward(forward.clone()) {
                         Ok(true) => {}
@@ -622,6 +327,18 @@ impl Workspace {
                             );
                         }
                     }
+                    // A forward read from the SSH config goes back to its Host block
+                    let linked_host = this
+                        .connections
+                        .iter()
+                        .find(|c| {
+                            c.source == ConnectionSource::SshConfig
+                                && c.auto_forwards.contains(&forward.id)
+                        })
+                        .cloned();
+                    if let Some(host) = linked_host {
+                        this.write_ssh_config_host(&host.alias, &host, cx);
+                    }
                     // Update the view
                     this.port_forwards.update(cx, |pf, _| {
                         if let Some(existing) = pf.forwards.iter_mut().find(|f| f.id == forward.id)
@@ -0,0 +1,365 @@
+use std::time::Duration;
+
+use gpui::*;
+use shelldeck_core::config::activity::{ActivityAction, ActivityEntry, ActivityKind};
+use shelldeck_core::config::cloud_account::AppMode;
+use shelldeck_core::models::host_metrics::{metrics_alerts, MetricsAlert};
+use shelldeck_ssh::metrics::{MetricsCollector, MetricsEvent};
+use uuid::Uuid;
+
+use crate::host_metrics_view::{HostMetricsEvent, HostMetricsView};
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::{ActiveMetrics, TrayNotification, Workspace};
+
+/// How often a collector's channel is drained. Reads are seconds apart,
+/// so this only bounds how late a sample shows up.
+const DRAIN_INTERVAL: Duration = Duration::from_millis(250);
+
+impl Workspace {
+    /// Start sampling every monitored host that still exists.
+    pub fn start_host_metrics(&mut self, cx: &mut Context<Self>) {
+        if !self.can_access_mode(AppMode::Dev) {
+            return;
+        }
+        let monitored: Vec<Uuid> = self
+            .app_config
+            .host_metrics
+            .connections
+            .iter()
+            .copied()
+            .filter(|id| self.connections.iter().any(|c| c.id == *id))
+            .collect();
+        for connection_id in monitored {
+            self.start_metrics_collector(connection_id, cx);
+        }
+    }
+
+    /// Open the metrics panel of one host.
+    pub fn show_host_metrics(&mut self, conn_id: Uuid, cx: &mut Context<Self>) {
+        if !self.enter_dev_mode(cx) {
+            return;
+        }
+        let Some(conn) = self.connections.iter().find(|c| c.id == conn_id) else {
+            self.show_toast(
+                t!("toast.deeplink.connection_not_found").to_string(),
+                ToastLevel::Warning,
+                cx,
+            );
+            return;
+        };
+        let name = conn.display_name().to_string();
+        let config = &self.app_config.host_metrics;
+        let monitored = config.is_monitored(conn_id);
+        let interval_secs = config.interval_secs;
+        let disk_alert_percent = config.disk_alert_percent;
+        let history = self.host_metrics.get(&conn_id).cloned().unwrap_or_default();
+        let view = cx.new(|view_cx| {
+            HostMetricsView::new(
+                conn_id,
+                name,
+                history,
+                monitored,
+                interval_secs,
+                disk_alert_percent,
+                view_cx,
+            )
+        });
+
+        let sub = cx.subscribe(
+            &view,
+            move |this, _view, event: &HostMetricsEvent, cx| match event {
+                HostMetricsEvent::Close => {
+                    this.host_metrics_view = None;
+                    this._host_metrics_sub = None;
+                    cx.notify();
+                }
+                HostMetricsEvent::SetMonitored(monitored) => {
+                    this.set_host_monitored(conn_id, *monitored, cx);
+                }
+                HostMetricsEvent::SetInterval(secs) => {
+                    this.set_host_metrics_interval(*secs, cx);
+                }
+            },
+        );
+
+        self.host_metrics_view = Some(view);
+        self._host_metrics_sub = Some(sub);
+        cx.notify();
+    }
+
+    /// Add a host to the monitored set or drop it, and start or stop its
+    /// collector to match.
+    pub(super) fn set_host_monitored(
+        &mut self,
+        conn_id: Uuid,
+        monitored: bool,
+        cx: &mut Context<Self>,
+    ) {
+        if self
+            .app_config
+            .host_metrics
+            .set_monitored(conn_id, monitored)
+        {
+            if let Err(e) = self.app_config.save() {
+                tracing::error!("Failed to save host metrics config: {}", e);
+            }
+            self.sync_settings_config(cx);
+        }
+        if monitored {
+            self.start_metrics_collector(conn_id, cx);
+        } else {
+            self.stop_metrics_collector(conn_id);
+            self.host_metrics.remove(&conn_id);
+        }
+        if let Some(view) = &self.host_metrics_view {
+            view.update(cx, |view, cx| {
+                if view.connection_id() == conn_id {
+                    view.set_monitored(monitored);
+                    cx.notify();
+                }
+            });
+        }
+        self.push_host_metrics(conn_id, cx);
+    }
+
+    /// Change how often hosts are read. Running collectors restart so the
+    /// new interval applies at once.
+    fn set_host_metrics_interval(&mut self, secs: u64, cx: &mut Context<Self>) {
+        if self.app_config.host_metrics.interval_secs == secs {
+            return;
+        }
+        self.app_config.host_metrics.interval_secs = secs;
+        if let Err(e) = self.app_config.save() {
+            tracing::error!("Failed to save host metrics config: {}", e);
+        }
+        self.sync_settings_config(cx);
+        let running: Vec<Uuid> = self.active_metrics.keys().copied().collect();
+        for conn_id in running {
+            self.restart_metrics_collector(conn_id, cx);
+        }
+        if let Some(view) = &self.host_metrics_view {
+            view.update(cx, |view, cx| {
+                view.set_interval(secs);
+                cx.notify();
+            });
+        }
+    }
+
+    /// Restart a running collector, e.g. after its connection was edited.
+    pub(super) fn restart_metrics_collector(&mut self, conn_id: Uuid, cx: &mut Context<Self>) {
+        if self.stop_metrics_collector(conn_id) {
+            self.start_metrics_collector(conn_id, cx);
+        }
+    }
+
+    /// Stop a host's collector. Returns whether one was running.
+    pub(super) fn stop_metrics_collector(&mut self, conn_id: Uuid) -> bool {
+        let Some(active) = self.active_metrics.remove(&conn_id) else {
+            return false;
+        };
+        tracing::info!("Stopping metrics collector for connection {}", conn_id);
+        let _ = active.shutdown_tx.try_send(());
+        true
+    }
+
+    fn start_metrics_collector(&mut self, conn_id: Uuid, cx: &mut Context<Self>) {
+        if self.active_metrics.contains_key(&conn_id) {
+            return;
+        }
+        let Some(connection) = self.connections.iter().find(|c| c.id == conn_id).cloned() else {
+            tracing::warn!("Connection {} not found for host metrics", conn_id);
+            return;
+        };
+        let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
+        let collector = MetricsCollector::new(
+            connection,
+            self.app_config.host_metrics.interval(),
+            events_tx,
+        );
+
+        // Like supervised forwards, each collector gets a thread with its
+        // own runtime for as long as the host is monitored.
+        let thread = std::thread::Builder::new()
+            .name(format!("metrics-{}", conn_id))
+            .spawn(move || {
+                match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt.block_on(collector.run(shutdown_rx)),
+                    Err(e) => tracing::error!("Failed to create async runtime: {}", e),
+                }
+            });
+        let thread = match thread {
+            Ok(thread) => thread,
+            Err(e) => {
+                tracing::error!("Failed to spawn metrics thread: {}", e);
+                self.host_metrics
+                    .entry(conn_id)
+                    .or_default()
+                    .record_error(e.to_string());
+                self.push_host_metrics(conn_id, cx);
+                return;
+            }
+        };
+
+        let run_id = Uuid::new_v4();
+        self.active_metrics.insert(
+            conn_id,
+            ActiveMetrics {
+                shutdown_tx,
+                run_id,
+                _thread: thread,
+            },
+        );
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor().timer(DRAIN_INTERVAL).await;
+            let mut events = Vec::new();
+            let mut finished = false;
+            loop {
+                match events_rx.try_recv() {
+                    Ok(event) => events.push(event),
+                    Err(tokio::sync::mpsc::error::TryRecvError::Empty) => break,
+                    Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+            if events.is_empty() && !finished {
+                continue;
+            }
+            let updated = this.update(cx, |ws, cx| {
+                for event in events {
+                    ws.handle_metrics_event(conn_id, run_id, event, cx);
+                }
+            });
+            if finished || updated.is_err() {
+                break;
+            }
+        })
+        .detach();
+    }
+
+    fn handle_metrics_event(
+        &mut self,
+        conn_id: Uuid,
+        run_id: Uuid,
+        event: MetricsEvent,
+        cx: &mut Context<Self>,
+    ) {
+        // Events from a collector that was stopped since are stale.
+        if self
+            .active_metrics
+            .get(&conn_id)
+            .is_none_or(|active| active.run_id != run_id)
+        {
+            return;
+        }
+        let capacity = self.app_config.host_metrics.history_samples.max(2);
+        let history = self.host_metrics.entry(conn_id).or_default();
+        let alerts = match event {
+            MetricsEvent::Sample(snapshot) => {
+                let previous = history.latest().cloned();
+                let sample = history.record(snapshot, capacity);
+                metrics_alerts(
+                    previous.as_ref(),
+                    sample,
+                    &self.app_config.host_metrics.thresholds(),
+                )
+            }
+            MetricsEvent::Error(error) => {
+                history.record_error(error);
+                Vec::new()
+            }
+        };
+        if !alerts.is_empty() {
+            self.raise_metrics_alerts(conn_id, alerts, cx);
+        }
+        self.push_host_metrics(conn_id, cx);
+    }
+
+    fn raise_metrics_alerts(
+        &mut self,
+        conn_id: Uuid,
+        alerts: Vec<MetricsAlert>,
+        cx: &mut Context<Self>,
+    ) {
+        let name = self
+            .connections
+            .iter()
+            .find(|c| c.id == conn_id)
+            .map(|c| c.display_name().to_string())
+            .unwrap_or_else(|| conn_id.to_string());
+        for alert in alerts {
+            let (message, notification) = match alert {
+                MetricsAlert::DiskFull { mount, percent } => {
+                    let percent = percent.round() as u32;
+                    (
+                        t!(
+                            "activity.host_metrics.disk_full",
+                            name = name.as_str(),
+                            mount = mount.as_str(),
+                            percent = percent
+                        )
+                        .to_string(),
+                        TrayNotification::HostDiskFull {
+                            name: name.clone(),
+                            mount,
+                            percent,
+                        },
+                    )
+                }
+                MetricsAlert::HighLoad { load, cores } => {
+                    let load = format!("{:.2}", load);
+                    (
+                        t!(
+                            "activity.host_metrics.high_load",
+                            name = name.as_str(),
+                            load = load.as_str(),
+                            cores = cores
+                        )
+                        .to_string(),
+                        TrayNotification::HostHighLoad {
+                            name: name.clone(),
+                            load,
+                            cores,
+                        },
+                    )
+                }
+            };
+            tracing::warn!("{}", message);
+            self.add_activity_entry(
+                ActivityEntry::new(ActivityKind::Connection, message)
+                    .with_target(conn_id.to_string(), name.clone())
+                    .with_action(ActivityAction::OpenHostMetrics),
+                cx,
+            );
+            if self.app_config.tray.notify_host_metrics {
+                self.emit_tray_notification(notification);
+            }
+        }
+    }
+
+    /// Show a host's latest history in the sidebar and, when it is open on
+    /// that host, in the panel.
+    fn push_host_metrics(&mut self, conn_id: Uuid, cx: &mut Context<Self>) {
+        let history = self.host_metrics.get(&conn_id).cloned();
+        self.sidebar.update(cx, |sidebar, cx| {
+            sidebar.set_host_metrics(conn_id, history.clone());
+            cx.notify();
+        });
+        if let Some(view) = &self.host_metrics_view {
+            view.update(cx, |view, cx| {
+                if view.connection_id() == conn_id {
+                    view.set_history(history.unwrap_or_default());
+                    cx.notify();
+                }
+            });
+        }
+    }
+}
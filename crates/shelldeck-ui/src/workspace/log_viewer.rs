@@ -0,0 +1,189 @@
+use gpui::*;
+use shelldeck_core::config::activity::{ActivityAction, ActivityEntry, ActivityKind};
+use shelldeck_core::config::issues::{self, IssueAttachmentUpload};
+use shelldeck_core::models::log_tail::LogSource;
+use uuid::Uuid;
+
+use crate::log_viewer::{LogViewerEvent, LogViewerView};
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::Workspace;
+
+impl Workspace {
+    /// Open the log viewer on the saved sources. `connection` preselects
+    /// the host new sources are added to.
+    pub fn show_log_viewer(&mut self, connection: Option<Uuid>, cx: &mut Context<Self>) {
+        if !self.enter_dev_mode(cx) {
+            return;
+        }
+        let issues = self.open_issue_targets();
+        // Tailing keeps running while the viewer is open, so reopening it
+        // from another host only changes where new sources go.
+        if let Some(view) = &self.log_viewer {
+            view.update(cx, |view, cx| {
+                if let Some(id) = connection {
+                    view.select_host(id, cx);
+                }
+                view.set_issues(issues);
+                cx.notify();
+            });
+            return;
+        }
+
+        let config = &self.app_config.log_viewer;
+        let connections = self.connections.clone();
+        let sources = config.sources.clone();
+        let backlog = config.backlog_lines;
+        let max_lines = config.max_lines;
+        let view = cx.new(|view_cx| {
+            LogViewerView::new(
+                connections,
+                sources,
+                backlog,
+                max_lines,
+                connection,
+                issues,
+                view_cx,
+            )
+        });
+
+        let sub = cx.subscribe(
+            &view,
+            |this, _view, event: &LogViewerEvent, cx| match event {
+                LogViewerEvent::Close => {
+                    this.log_viewer = None;
+                    this._log_viewer_sub = None;
+                    cx.notify();
+                }
+                LogViewerEvent::SourcesChanged(sources) => {
+                    this.save_log_sources(sources.clone(), cx);
+                }
+                LogViewerEvent::AttachToIssue {
+                    issue_id,
+                    filename,
+                    text,
+                    lines,
+                } => {
+                    this.attach_log_to_issue(
+                        issue_id.clone(),
+                        filename.clone(),
+                        text.clone(),
+                        *lines,
+                        cx,
+                    );
+                }
+            },
+        );
+
+        self.log_viewer = Some(view);
+        self._log_viewer_sub = Some(sub);
+        cx.notify();
+    }
+
+    /// Open requests a log export can be attached to.
+    fn open_issue_targets(&self) -> Vec<(String, String)> {
+        self.issues_list
+            .iter()
+            .filter(|issue| !issue.is_closed())
+            .map(|issue| (issue.id.clone(), issue.title.clone()))
+            .collect()
+    }
+
+    fn save_log_sources(&mut self, sources: Vec<LogSource>, cx: &mut Context<Self>) {
+        if self.app_config.log_viewer.sources == sources {
+            return;
+        }
+        self.app_config.log_viewer.sources = sources;
+        if let Err(e) = self.app_config.save() {
+            tracing::error!("Failed to save log viewer config: {}", e);
+        }
+        self.sync_settings_config(cx);
+    }
+
+    /// Upload an exported log window to a request and comment with it.
+    fn attach_log_to_issue(
+        &mut self,
+        issue_id: String,
+        filename: String,
+        text: String,
+        lines: usize,
+        cx: &mut Context<Self>,
+    ) {
+        let Some((base, token)) = self.fleet_base_token() else {
+            self.finish_log_attach(false, cx);
+            self.show_toast(
+                t!("log_viewer.attach_signed_out").to_string(),
+                ToastLevel::Warning,
+                cx,
+            );
+            return;
+        };
+        let body = t!("log_viewer.attach_comment", count = lines).to_string();
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    let upload = IssueAttachmentUpload::log_text(filename, &text);
+                    let receipts =
+                        issues::upload_issue_attachments(&base, &token, &issue_id, &[upload])?;
+                    issues::comment_issue_with_attachments(
+                        &base, &token, &issue_id, &body, &receipts,
+                    )
+                })
+                .await;
+            let _ = this.update(cx, |ws, cx| match result {
+                Ok(issue) => {
+                    ws.finish_log_attach(true, cx);
+                    let (id, title) = (issue.id.clone(), issue.title.clone());
+                    if ws.issue_detail.as_ref().is_some_and(|d| d.id == id) {
+                        ws.issue_detail = Some(issue.clone());
+                    }
+                    ws.upsert_issue_in_list(issue);
+                    ws.push_issues_to_support(cx);
+                    ws.add_activity_entry(
+                        ActivityEntry::new(
+                            ActivityKind::Issue,
+                            t!(
+                                "activity.log_viewer.attached",
+                                count = lines,
+                                title = title.as_str()
+                            )
+                            .to_string(),
+                        )
+                        .with_target(id, title.clone())
+                        .with_action(ActivityAction::OpenIssue),
+                        cx,
+                    );
+                    ws.show_toast(
+                        t!("log_viewer.attached", title = title.as_str()).to_string(),
+                        ToastLevel::Success,
+                        cx,
+                    );
+                }
+                Err(e) => {
+                    ws.finish_log_attach(false, cx);
+                    ws.show_toast(
+                        t!(
+                            "log_viewer.attach_failed",
+                            error = crate::i18n::api_error_message(&e)
+                        )
+                        .to_string(),
+                        ToastLevel::Error,
+                        cx,
+                    );
+                }
+            });
+        })
+        .detach();
+    }
+
+    fn finish_log_attach(&mut self, ok: bool, cx: &mut Context<Self>) {
+        if let Some(view) = &self.log_viewer {
+            view.update(cx, |view, cx| {
+                view.attach_finished(ok);
+                cx.notify();
+            });
+        }
+    }
+}
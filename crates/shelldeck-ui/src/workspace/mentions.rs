@@ -248,6 +248,7 @@ impl Workspace {
                 let target = match script.target {
                     ScriptTarget::Local => "local".to_string(),
                     ScriptTarget::AskOnRun => "ask_on_run".to_string(),
+                    ScriptTarget::Multi(_) => "multi_host".to_string(),
                     ScriptTarget::Remote(id) => self
                         .connections
                         .iter()
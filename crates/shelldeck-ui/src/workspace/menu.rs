@@ -117,6 +117,8 @@ impl Workspace {
             Cmd::QuickConnect => self.execute_palette_action(&OpenQuickConnect, cx),
             Cmd::NewTerminal => self.execute_palette_action(&NewTerminal, cx),
             Cmd::NewScript => self.execute_palette_action(&NewScript, cx),
+            Cmd::ImportConnections => self.show_connection_import(cx),
+            Cmd::ManageKeys => self.show_key_manager(cx),
             Cmd::NewRequest => self.execute_palette_action(&NewRequest, cx),
             Cmd::SyncNow => self.execute_palette_action(&CloudSyncNow, cx),
             Cmd::OpenCompanionSettings => self.open_companion_settings(cx),
@@ -30,6 +30,7 @@ use shelldeck_core::config::activity::{
     ActivityAction, ActivityEntry, ActivityKind, ActivityStore,
 };
 use shelldeck_core::config::app_config::{AppConfig, CompanionConfig, ThemePreference};
+use shelldeck_core::config::backup_catalog::BackupCatalog;
 use shelldeck_core::config::bext_cloud;
 use shelldeck_core::config::cloud_account::{self, AccountInfo, AppMode};
 use shelldeck_core::config::deep_link::DeepLink;
@@ -42,12 +43,18 @@ use shelldeck_core::config::monique::{
 use shelldeck_core::config::monique_fleet::{
     self, FleetSnapshot, MoniqueInstance, MoniqueJob, MoniqueRuntimeConfig, RegisterInstance,
 };
+use shelldeck_core::config::scheduler_state::SchedulerState;
+use shelldeck_core::config::site_health::SiteHealthHistory;
 use shelldeck_core::config::store::ConnectionStore;
 use shelldeck_core::config::themes::TerminalTheme;
+use shelldeck_core::config::tunnel_history::TunnelHistory;
+use shelldeck_core::config::ConfigWatcher;
 use shelldeck_core::models::connection::{Connection, ConnectionSource, ConnectionStatus};
+use shelldeck_core::models::file_transfer::TransferQueue;
+use shelldeck_core::models::host_metrics::MetricsHistory;
 use shelldeck_ssh::tunnel::TunnelHandle;
 use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::{DerefMut, Range};
 use std::rc::Rc;
 use uuid::Uuid;
@@ -65,13 +72,17 @@ use crate::command_palette::{
     PaletteAction, SetAppMode, ToggleCommandPalette,
 };
 use crate::connection_form::{ConnectionForm, ConnectionFormEvent};
+use crate::connection_import::{ConnectionImportEvent, ConnectionImportView};
 use crate::dashboard::{DashboardEvent, DashboardView};
 use crate::file_editor::view::{FileEditorEvent, FileEditorView};
 use crate::fleet_view::{FleetView, FleetViewEvent};
+use crate::host_metrics_view::HostMetricsView;
 use crate::issue_attachments::{
     capture_region, draft_from_clipboard_image, render_attachment_draft_gallery,
     render_stored_attachment_gallery, AttachmentDraft, AttachmentLightbox, LightboxItem,
 };
+use crate::key_manager::KeyManagerView;
+use crate::log_viewer::LogViewerView;
 use crate::login_form::{LoginForm, LoginFormEvent};
 use crate::monique_view::{MoniqueView, MoniqueViewEvent};
 use crate::monolith::{animated_loading_text, animated_monolith, MonolithMotion};
@@ -87,6 +98,7 @@ use crate::settings::{
 };
 use crate::sidebar::{SidebarEvent, SidebarSection, SidebarView};
 use crate::sites_view::{SitesEvent, SitesView};
+use crate::ssh_doctor::SshDoctorView;
 use crate::status_bar::{StatusBar, StatusBarEvent};
 use crate::support_view::thread::{
     human_message, note as thread_note, HumanMessageMeta, ThreadMessageExtras, ThreadNoteKind,
@@ -116,14 +128,21 @@ mod ai_routing;
 mod bext;
 mod chrome;
 mod cloud_sync;
+mod config_apply;
+mod database_sync;
+mod db_backups;
 mod discovery;
 mod events;
+mod file_transfers;
 mod fleet;
 mod forwards;
+mod host_metrics;
+mod log_viewer;
 mod mentions;
 mod menu;
 mod modes;
 mod monique;
+mod multi_run;
 mod navigation;
 mod overlays;
 mod palette;
@@ -132,13 +151,20 @@ mod polling;
 mod render;
 mod request_views;
 mod requests;
+mod scheduler;
 mod scripts;
 mod server_sync;
+mod site_health;
 mod sites;
 mod ssh;
+mod ssh_config_sync;
+mod ssh_keys;
 mod support;
+mod sync_plan;
 mod tray;
+mod tunnel_supervisor;
 mod user_home;
+mod wordpress_sync;
 
 /// Health of the signed-in cloud account, surfaced as the titlebar status dot.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -366,16 +392,29 @@ actions!(
     ]
 );
 
-/// Tracks a running tunnel: the handle to stop it, plus the join handle for the
-/// background thread that owns the tokio runtime driving the tunnel.
+/// Tracks a supervised tunnel: the handle to stop it, plus the join handle for
+/// the background thread that owns the tokio runtime driving the supervisor.
 struct ActiveTunnel {
     tunnel_handle: TunnelHandle,
+    /// Tells this supervisor's events from those of an earlier, stopped one.
+    run_id: Uuid,
+    /// Whether the supervisor has brought the forward up since it started.
+    was_up: bool,
     /// Dropping the JoinHandle does NOT abort the thread -- we use the
     /// TunnelHandle's shutdown channel for that. We keep this so we can
     /// optionally join on cleanup.
     _thread: std::thread::JoinHandle<()>,
 }
 
+/// Tracks a host metrics collector: the sender that stops it, plus the
+/// background thread whose runtime drives it.
+struct ActiveMetrics {
+    shutdown_tx: tokio::sync::mpsc::Sender<()>,
+    /// Tells this collector's events from those of an earlier, stopped one.
+    run_id: Uuid,
+    _thread: std::thread::JoinHandle<()>,
+}
+
 /// Tracks a running script execution with a cancellation channel.
 struct ActiveScript {
     shutdown_tx: tokio::sync::mpsc::Sender<()>,
@@ -438,6 +477,11 @@ pub struct Workspace {
     companion_command_palette: Entity<CommandPalette>,
     toasts: Entity<ToastContainer>,
     connection_form: Option<Entity<ConnectionForm>>,
+    connection_import: Option<Entity<ConnectionImportView>>,
+    key_manager: Option<Entity<KeyManagerView>>,
+    ssh_doctor: Option<Entity<SshDoctorView>>,
+    host_metrics_view: Option<Entity<HostMetricsView>>,
+    log_viewer: Option<Entity<LogViewerView>>,
     login_form: Option<Entity<LoginForm>>,
     post_login_splash: Option<PostLoginSplash>,
     mode_transition: Option<ModeTransition>,
@@ -496,12 +540,52 @@ pub struct Workspace {
     _recent_sub: Subscription,
     _file_editor_sub: Subscription,
     _form_sub: Option<Subscription>,
+    _connection_import_sub: Option<Subscription>,
+    _key_manager_sub: Option<Subscription>,
+    _ssh_doctor_sub: Option<Subscription>,
+    _host_metrics_sub: Option<Subscription>,
+    _log_viewer_sub: Option<Subscription>,
     _pf_form_sub: Option<Subscription>,
     _dashboard_sub: Subscription,
     _script_form_sub: Option<Subscription>,
     _template_browser_sub: Option<Subscription>,
     _variable_prompt_sub: Option<Subscription>,
     _git_poll_task: Option<gpui::Task<()>>,
+    /// Last check of each schedule trigger, persisted across restarts.
+    scheduler_state: SchedulerState,
+    /// Scripts with an unattended run in flight.
+    scheduled_runs: HashSet<Uuid>,
+    _scheduler_task: Option<gpui::Task<()>>,
+    /// Probe history of monitored sites, persisted across restarts.
+    site_health: SiteHealthHistory,
+    /// Sites with a health probe in flight.
+    site_probes: HashSet<Uuid>,
+    _site_monitor_task: Option<gpui::Task<()>>,
+    /// Database backup plans and archives, persisted across restarts.
+    backup_catalog: BackupCatalog,
+    /// Plans with a backup in flight.
+    backup_jobs: HashSet<Uuid>,
+    /// Archives being restored.
+    restore_jobs: HashSet<Uuid>,
+    _backup_scheduler_task: Option<gpui::Task<()>>,
+    /// Sites with a config apply in flight.
+    config_applies: HashSet<Uuid>,
+    /// Up/down history of supervised forwards, persisted across restarts.
+    tunnel_history: TunnelHistory,
+    /// Forwards whose current outage was already notified.
+    tunnel_alerts: HashSet<Uuid>,
+    _tunnel_watch_task: Option<gpui::Task<()>>,
+    /// Recent samples of monitored hosts, in memory only.
+    host_metrics: HashMap<Uuid, MetricsHistory>,
+    /// Running metrics collectors keyed by connection ID.
+    active_metrics: HashMap<Uuid, ActiveMetrics>,
+    /// Follows ~/.ssh/config for live reconciliation of its hosts.
+    _ssh_config_watcher: Option<ConfigWatcher>,
+    _ssh_config_task: Option<gpui::Task<()>>,
+    /// Copies and moves of the server sync file manager.
+    file_transfers: TransferQueue,
+    /// The transfer running now and the sender that stops it.
+    transfer_job: Option<(Uuid, tokio::sync::mpsc::Sender<()>)>,
     auto_updater: Entity<AutoUpdater>,
     _update_sub: Subscription,
     _status_bar_sub: Subscription,
@@ -752,6 +836,29 @@ pub enum TrayNotification {
     /// An AI generation or executable action finished while the main window
     /// was not active.
     AiTaskDone { success: bool },
+    /// A scheduled or event-triggered script run failed.
+    ScheduledRunFailed { name: String },
+    /// A monitored site stopped answering correctly.
+    SiteDown { name: String, reason: String },
+    /// A monitored site's certificate expires within the warning window.
+    CertificateExpiring { name: String, days: i64 },
+    /// A scheduled database backup failed.
+    BackupFailed { database: String },
+    /// A supervised port forward has been down for `minutes`.
+    TunnelDown { name: String, minutes: i64 },
+    /// A filesystem of a monitored host crossed the disk alert threshold.
+    HostDiskFull {
+        name: String,
+        mount: String,
+        percent: u32,
+    },
+    /// A monitored host's one-minute load (formatted) crossed the load
+    /// alert threshold.
+    HostHighLoad {
+        name: String,
+        load: String,
+        cores: u32,
+    },
 }
 
 impl TrayNotification {
@@ -793,6 +900,60 @@ impl TrayNotification {
                     t!("notification.ai.failed").to_string()
                 },
             ),
+            Self::ScheduledRunFailed { name } => (
+                t!("notification.schedule.summary").to_string(),
+                t!("notification.schedule.failed", name = name).to_string(),
+            ),
+            Self::SiteDown { name, reason } => (
+                t!("notification.site_health.summary").to_string(),
+                t!(
+                    "notification.site_health.down",
+                    name = name,
+                    reason = reason
+                )
+                .to_string(),
+            ),
+            Self::CertificateExpiring { name, days } => (
+                t!("notification.site_health.summary").to_string(),
+                t!(
+                    "notification.site_health.cert_expiring",
+                    name = name,
+                    days = days
+                )
+                .to_string(),
+            ),
+            Self::BackupFailed { database } => (
+                t!("notification.backup.summary").to_string(),
+                t!("notification.backup.failed", database = database).to_string(),
+            ),
+            Self::TunnelDown { name, minutes } => (
+                t!("notification.tunnel.summary").to_string(),
+                t!("notification.tunnel.down", name = name, minutes = minutes).to_string(),
+            ),
+            Self::HostDiskFull {
+                name,
+                mount,
+                percent,
+            } => (
+                t!("notification.host_metrics.summary").to_string(),
+                t!(
+                    "notification.host_metrics.disk_full",
+                    name = name,
+                    mount = mount,
+                    percent = percent
+                )
+                .to_string(),
+            ),
+            Self::HostHighLoad { name, load, cores } => (
+                t!("notification.host_metrics.summary").to_string(),
+                t!(
+                    "notification.host_metrics.high_load",
+                    name = name,
+                    load = load,
+                    cores = cores
+                )
+                .to_string(),
+            ),
         }
     }
 }
@@ -1246,6 +1407,11 @@ impl Workspace {
             companion_command_palette,
             toasts,
             connection_form: None,
+            connection_import: None,
+            key_manager: None,
+            ssh_doctor: None,
+            host_metrics_view: None,
+            log_viewer: None,
             login_form: None,
             post_login_splash: None,
             mode_transition: None,
@@ -1283,11 +1449,36 @@ impl Workspace {
             _file_editor_sub: file_editor_sub,
             _dashboard_sub: dashboard_sub,
             _form_sub: None,
+            _connection_import_sub: None,
+            _key_manager_sub: None,
+            _ssh_doctor_sub: None,
+            _host_metrics_sub: None,
+            _log_viewer_sub: None,
             _pf_form_sub: None,
             _script_form_sub: None,
             _template_browser_sub: None,
             _variable_prompt_sub: None,
             _git_poll_task: None,
+            scheduler_state: SchedulerState::default(),
+            scheduled_runs: HashSet::new(),
+            _scheduler_task: None,
+            site_health: SiteHealthHistory::default(),
+            site_probes: HashSet::new(),
+            _site_monitor_task: None,
+            backup_catalog: BackupCatalog::default(),
+            backup_jobs: HashSet::new(),
+            restore_jobs: HashSet::new(),
+            _backup_scheduler_task: None,
+            config_applies: HashSet::new(),
+            tunnel_history: TunnelHistory::default(),
+            tunnel_alerts: HashSet::new(),
+            _tunnel_watch_task: None,
+            host_metrics: HashMap::new(),
+            active_metrics: HashMap::new(),
+            _ssh_config_watcher: None,
+            _ssh_config_task: None,
+            file_transfers: TransferQueue::default(),
+            transfer_job: None,
             auto_updater,
             _update_sub: update_sub,
             _status_bar_sub: status_bar_sub,
@@ -0,0 +1,451 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::time::Duration;
+
+use gpui::*;
+use shelldeck_core::config::activity::{ActivityAction, ActivityEntry, ActivityKind};
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::multi_run::{MultiRun, MultiRunEvent, MultiTarget};
+use shelldeck_core::models::script::Script;
+use shelldeck_core::models::script_runner::build_command;
+use shelldeck_ssh::client::SshClient;
+use tokio::sync::{mpsc, watch, Semaphore};
+use uuid::Uuid;
+
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::{ActiveScript, Workspace};
+
+impl Workspace {
+    /// Distinct connection groups and tags, sorted, for multi-host selectors.
+    pub(super) fn connection_labels(&self) -> (Vec<String>, Vec<String>) {
+        let mut groups: Vec<String> = self
+            .connections
+            .iter()
+            .filter_map(|c| c.group.clone())
+            .collect();
+        let mut tags: Vec<String> = self
+            .connections
+            .iter()
+            .flat_map(|c| c.tags.iter().cloned())
+            .collect();
+        for labels in [&mut groups, &mut tags] {
+            labels.sort_by_key(|label| label.to_lowercase());
+            labels.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        }
+        (groups, tags)
+    }
+
+    /// Run `script` on every connection matched by `target`, bounded by the
+    /// target's concurrency. Results land in the script editor's matrix.
+    pub(super) fn run_script_multi(
+        &mut self,
+        script: Script,
+        target: MultiTarget,
+        values: HashMap<String, String>,
+        cx: &mut Context<Self>,
+    ) {
+        let hosts: Vec<Connection> = target
+            .resolve(&self.connections)
+            .into_iter()
+            .cloned()
+            .collect();
+        if hosts.is_empty() {
+            self.show_toast(
+                t!("toast.script.multi_no_hosts").to_string(),
+                ToastLevel::Warning,
+                cx,
+            );
+            return;
+        }
+
+        tracing::info!("Running script {} on {} hosts", script.name, hosts.len());
+        let cmd = build_command(&script, (!values.is_empty()).then_some(&values));
+        let script_id = script.id;
+        let script_name = script.name.clone();
+        let host_count = hosts.len();
+
+        let mut run = MultiRun::new(script_id, target.clone(), &hosts.iter().collect::<Vec<_>>());
+        let secret_names = script.secret_variable_names();
+        for host in &mut run.hosts {
+            host.record = host
+                .record
+                .clone()
+                .with_declared_secrets(&values, &secret_names);
+        }
+        let header = format!("$ [{} hosts] {}", host_count, cmd.ssh_command);
+
+        self.scripts.update(cx, |editor, _| {
+            editor.start_multi_run(run, header);
+            if !values.is_empty() {
+                editor.last_var_values.insert(script_id, values.clone());
+            }
+            if let Some(s) = editor.scripts.iter_mut().find(|s| s.id == script_id) {
+                s.last_run = Some(chrono::Utc::now());
+                s.run_count += 1;
+            }
+        });
+        if let Some(s) = self
+            .scripts
+            .read(cx)
+            .scripts
+            .iter()
+            .find(|s| s.id == script_id)
+            .cloned()
+        {
+            let _ = self.store.update_script(s);
+        }
+
+        self.add_activity_entry(
+            ActivityEntry::new(
+                ActivityKind::Script,
+                t!(
+                    "activity.script.running_multi",
+                    name = script_name.as_str(),
+                    count = host_count
+                )
+                .to_string(),
+            )
+            .with_target(script_id.to_string(), script_name.clone())
+            .with_action(ActivityAction::OpenScript),
+            cx,
+        );
+        self.show_toast(
+            t!(
+                "toast.script.running_multi",
+                name = script_name.as_str(),
+                count = host_count
+            )
+            .to_string(),
+            ToastLevel::Info,
+            cx,
+        );
+        self.update_dashboard_stats(cx);
+
+        let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>(1);
+        let (event_tx, event_rx) = std::sync::mpsc::channel::<MultiRunEvent>();
+        let command = cmd.ssh_command;
+
+        let thread_handle = std::thread::Builder::new()
+            .name(format!("script-multi-{}", script_id))
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        tracing::error!("Failed to create async runtime: {}", e);
+                        return;
+                    }
+                };
+                rt.block_on(run_hosts(hosts, command, target, event_tx, shutdown_rx));
+            });
+        let thread_handle = match thread_handle {
+            Ok(h) => h,
+            Err(e) => {
+                tracing::error!("Failed to spawn multi-host script thread: {}", e);
+                self.scripts.update(cx, |editor, cx| {
+                    editor.finish_multi_run(cx);
+                    cx.notify();
+                });
+                self.show_toast(
+                    t!("toast.script.start_remote_failed", error = e.to_string()).to_string(),
+                    ToastLevel::Error,
+                    cx,
+                );
+                return;
+            }
+        };
+
+        self.active_scripts.insert(
+            script_id,
+            ActiveScript {
+                shutdown_tx,
+                _thread: Some(thread_handle),
+            },
+        );
+
+        // UI poller: feed worker events to the editor until the worker exits.
+        let scripts_handle = self.scripts.downgrade();
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(50))
+                    .await;
+
+                let mut events = Vec::new();
+                let mut finished = false;
+                loop {
+                    match event_rx.try_recv() {
+                        Ok(event) => events.push(event),
+                        Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                        Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                            finished = true;
+                            break;
+                        }
+                    }
+                }
+
+                if !events.is_empty() {
+                    let _ = scripts_handle.update(cx, |editor, cx| {
+                        editor.apply_multi_run_events(events, cx);
+                        cx.notify();
+                    });
+                }
+                if !finished {
+                    continue;
+                }
+
+                let summary = scripts_handle
+                    .update(cx, |editor, cx| {
+                        let summary = editor.finish_multi_run(cx);
+                        cx.notify();
+                        summary
+                    })
+                    .ok()
+                    .flatten();
+                let _ = this.update(cx, |ws, cx| {
+                    // StopScript already removed the entry and told the user.
+                    let stopped_by_user = ws.active_scripts.remove(&script_id).is_none();
+                    let Some(summary) = summary else {
+                        return;
+                    };
+                    let failures = summary.failed + summary.timed_out;
+                    ws.finish_ai_script_run(
+                        script_id,
+                        if failures == 0 { "succeeded" } else { "failed" },
+                        cx,
+                    );
+                    ws.update_dashboard_stats(cx);
+
+                    let message = t!(
+                        "activity.script.finished_multi",
+                        name = script_name.as_str(),
+                        succeeded = summary.succeeded,
+                        failed = failures,
+                        total = host_count
+                    )
+                    .to_string();
+                    ws.add_activity_entry(
+                        ActivityEntry::new(ActivityKind::Script, message.clone())
+                            .with_target(script_id.to_string(), script_name.clone())
+                            .with_action(ActivityAction::OpenScript),
+                        cx,
+                    );
+                    if !stopped_by_user {
+                        ws.show_toast(
+                            message,
+                            if failures == 0 {
+                                ToastLevel::Success
+                            } else {
+                                ToastLevel::Error
+                            },
+                            cx,
+                        );
+                    }
+                });
+                break;
+            }
+        })
+        .detach();
+    }
+}
+
+/// Drive a multi-host run over SSH, reporting everything through `events`.
+/// Dropping `events` once it returns tells the UI the run is over.
+async fn run_hosts(
+    hosts: Vec<Connection>,
+    command: String,
+    target: MultiTarget,
+    events: std::sync::mpsc::Sender<MultiRunEvent>,
+    shutdown_rx: mpsc::Receiver<()>,
+) {
+    let timeout = target.timeout_secs.map(Duration::from_secs);
+    let host_events = events.clone();
+    schedule_hosts(
+        hosts,
+        &target,
+        events,
+        shutdown_rx,
+        move |conn, cancel_rx| {
+            let command = command.clone();
+            let events = host_events.clone();
+            async move { run_host(conn, &command, timeout, &events, cancel_rx).await }
+        },
+    )
+    .await;
+}
+
+/// Start hosts through `run_host` as concurrency slots free up, and stop
+/// starting new ones on cancel (or on the first failure in rolling mode).
+/// Returns once every started host is done.
+pub(super) async fn schedule_hosts<F, Fut>(
+    hosts: Vec<Connection>,
+    target: &MultiTarget,
+    events: std::sync::mpsc::Sender<MultiRunEvent>,
+    mut shutdown_rx: mpsc::Receiver<()>,
+    run_host: F,
+) where
+    F: Fn(Connection, watch::Receiver<bool>) -> Fut,
+    Fut: Future<Output = bool> + Send + 'static,
+{
+    let semaphore = std::sync::Arc::new(Semaphore::new(target.effective_concurrency()));
+    let (cancel_tx, cancel_rx) = watch::channel(false);
+    let mut pending: VecDeque<Connection> = hosts.into();
+    let mut tasks = tokio::task::JoinSet::new();
+    let mut stopping = false;
+
+    loop {
+        if stopping {
+            for conn in pending.drain(..) {
+                let _ = events.send(MultiRunEvent::Skipped(conn.id));
+            }
+        }
+        if pending.is_empty() && tasks.is_empty() {
+            break;
+        }
+
+        // Finished hosts are handled before a new one starts, so a rolling
+        // run never starts a host after a failure it has not seen yet.
+        tokio::select! {
+            biased;
+            // A closed channel means the workspace dropped the run: cancel too.
+            _ = shutdown_rx.recv(), if !*cancel_rx.borrow() => {
+                stopping = true;
+                let _ = cancel_tx.send(true);
+            }
+            Some(joined) = tasks.join_next(), if !tasks.is_empty() => {
+                // The host's slot frees up only once its result is counted.
+                let (ok, _permit) = match joined {
+                    Ok((ok, permit)) => (ok, Some(permit)),
+                    Err(_) => (false, None),
+                };
+                if !ok && target.rolling {
+                    stopping = true;
+                }
+            }
+            permit = semaphore.clone().acquire_owned(), if !pending.is_empty() && !stopping => {
+                let Ok(permit) = permit else { break };
+                let Some(conn) = pending.pop_front() else { continue };
+                let host = run_host(conn, cancel_rx.clone());
+                tasks.spawn(async move { (host.await, permit) });
+            }
+            else => break,
+        }
+    }
+}
+
+/// Connect and run `command` on one host. Returns whether it exited 0.
+async fn run_host(
+    conn: Connection,
+    command: &str,
+    timeout: Option<Duration>,
+    events: &std::sync::mpsc::Sender<MultiRunEvent>,
+    mut cancel_rx: watch::Receiver<bool>,
+) -> bool {
+    let id = conn.id;
+    let _ = events.send(MultiRunEvent::Started(id));
+
+    let exec = async {
+        let client = SshClient::new();
+        let session = client
+            .connect(&conn)
+            .await
+            .map_err(|e| format!("SSH connection failed: {}", e))?;
+        let (output_tx, mut output_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+        // Kept alive for the whole exec: a dropped sender reads as a stop.
+        let (_stop_tx, stop_rx) = mpsc::channel::<()>(1);
+        let forward = async {
+            while let Some(data) = output_rx.recv().await {
+                let text = String::from_utf8_lossy(&data).into_owned();
+                let _ = events.send(MultiRunEvent::Output(id, text));
+            }
+        };
+        let (result, ()) = tokio::join!(
+            session.exec_cancellable(command, output_tx, stop_rx),
+            forward
+        );
+        let code = result.map_err(|e| e.to_string())?;
+        Ok::<_, String>(code.map(|c| c as i32))
+    };
+    let timed = async {
+        match timeout {
+            Some(limit) => tokio::time::timeout(limit, exec).await.ok(),
+            None => Some(exec.await),
+        }
+    };
+
+    let outcome = tokio::select! {
+        outcome = timed => outcome,
+        _ = cancel_rx.wait_for(|cancelled| *cancelled) => {
+            let _ = events.send(MultiRunEvent::Cancelled(id));
+            return false;
+        }
+    };
+    match outcome {
+        Some(Ok(code)) => {
+            let _ = events.send(MultiRunEvent::Finished(id, code));
+            code == Some(0)
+        }
+        Some(Err(e)) => {
+            let _ = events.send(MultiRunEvent::Output(id, format!("Error: {}\n", e)));
+            let _ = events.send(MultiRunEvent::Finished(id, None));
+            false
+        }
+        None => {
+            let _ = events.send(MultiRunEvent::TimedOut(id));
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shelldeck_core::models::multi_run::HostSelector;
+    use std::sync::{Arc, Mutex};
+
+    fn hosts(aliases: &[&str]) -> Vec<Connection> {
+        aliases
+            .iter()
+            .map(|alias| {
+                Connection::new_manual(
+                    alias.to_string(),
+                    format!("{}.example", alias),
+                    "root".into(),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn rolling_run_stops_after_the_first_failure() {
+        let hosts = hosts(&["web1", "web2", "web3"]);
+        let ids: Vec<Uuid> = hosts.iter().map(|c| c.id).collect();
+        let mut target = MultiTarget::new(HostSelector::Connections(ids.clone()));
+        target.rolling = true;
+        target.concurrency = 1;
+        let (event_tx, event_rx) = std::sync::mpsc::channel();
+        let (_shutdown_tx, shutdown_rx) = mpsc::channel(1);
+        let started = Arc::new(Mutex::new(Vec::new()));
+
+        let ran = started.clone();
+        schedule_hosts(hosts, &target, event_tx, shutdown_rx, move |conn, _| {
+            ran.lock().unwrap().push(conn.id);
+            async { false }
+        })
+        .await;
+
+        assert_eq!(*started.lock().unwrap(), vec![ids[0]]);
+        let skipped: Vec<Uuid> = event_rx
+            .try_iter()
+            .filter_map(|event| match event {
+                MultiRunEvent::Skipped(id) => Some(id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(skipped, ids[1..]);
+    }
+}
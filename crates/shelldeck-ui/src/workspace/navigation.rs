@@ -117,6 +117,16 @@ impl Workspace {
         // Clear forms if open
         self.connection_form = None;
         self._form_sub = None;
+        self.connection_import = None;
+        self._connection_import_sub = None;
+        self.key_manager = None;
+        self._key_manager_sub = None;
+        self.ssh_doctor = None;
+        self._ssh_doctor_sub = None;
+        self.host_metrics_view = None;
+        self._host_metrics_sub = None;
+        self.log_viewer = None;
+        self._log_viewer_sub = None;
         self.login_form = None;
         self._login_form_sub = None;
         self.post_login_splash = None;
@@ -133,10 +143,24 @@ impl Workspace {
     /// Unlike `shutdown`, this keeps the Workspace itself alive for login.
     pub(super) fn stop_authenticated_runtime(&mut self, cx: &mut Context<Self>) {
         // Stop all active tunnels
+        let stopped: Vec<Uuid> = self.active_tunnels.keys().copied().collect();
         for (fwd_id, tunnel) in self.active_tunnels.drain() {
             tracing::info!("Stopping tunnel for forward {}", fwd_id);
             tunnel.tunnel_handle.stop();
         }
+        for fwd_id in stopped {
+            self.record_tunnel_state(
+                fwd_id,
+                shelldeck_core::models::tunnel_health::TunnelState::Stopped,
+                None,
+                cx,
+            );
+        }
+        // Stop all host metrics collectors
+        let sampled: Vec<Uuid> = self.active_metrics.keys().copied().collect();
+        for conn_id in sampled {
+            self.stop_metrics_collector(conn_id);
+        }
         // Stop all active scripts
         for (script_id, active) in self.active_scripts.drain() {
             tracing::info!("Stopping script {}", script_id);
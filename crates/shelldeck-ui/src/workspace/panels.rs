@@ -128,7 +128,7 @@ impl Workspace {
             .map(|(index, tab)| PanelItem {
                 id: tab.id,
                 label: tab.filename.clone(),
-                detail: tab.path.as_ref().map(|p| p.to_string_lossy().into_owned()),
+                detail: tab.location_label(),
                 icon: "pencil",
                 is_active: index == active_editor_tab,
                 is_live: false,
@@ -165,7 +165,7 @@ impl Workspace {
             SidebarSection::Scripts => {
                 self.active_view = ActiveView::Scripts;
                 self.scripts.update(cx, |editor, cx| {
-                    editor.selected_script = Some(id);
+                    editor.select_script(Some(id), cx);
                     cx.notify();
                 });
             }
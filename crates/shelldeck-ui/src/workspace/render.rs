@@ -690,10 +690,15 @@ impl Render for Workspace {
         // Modal form overlays — render an occluding backdrop at the workspace
         // level so hover/click on elements behind is properly blocked.
         let has_modal = self.connection_form.is_some()
+            || self.connection_import.is_some()
+            || self.key_manager.is_some()
             || self.login_form.is_some()
             || self.onboarding.is_some()
             || self.port_forward_form.is_some()
             || self.script_form.is_some()
+            || self.ssh_doctor.is_some()
+            || self.host_metrics_view.is_some()
+            || self.log_viewer.is_some()
             || self.template_browser.is_some()
             || self.variable_prompt.is_some();
 
@@ -709,6 +714,21 @@ impl Render for Workspace {
             if let Some(ref form) = self.connection_form {
                 modal_layer = modal_layer.child(form.clone());
             }
+            if let Some(ref import) = self.connection_import {
+                modal_layer = modal_layer.child(import.clone());
+            }
+            if let Some(ref manager) = self.key_manager {
+                modal_layer = modal_layer.child(manager.clone());
+            }
+            if let Some(ref doctor) = self.ssh_doctor {
+                modal_layer = modal_layer.child(doctor.clone());
+            }
+            if let Some(ref metrics) = self.host_metrics_view {
+                modal_layer = modal_layer.child(metrics.clone());
+            }
+            if let Some(ref viewer) = self.log_viewer {
+                modal_layer = modal_layer.child(viewer.clone());
+            }
             if let Some(ref form) = self.login_form {
                 modal_layer = modal_layer.child(form.clone());
             }
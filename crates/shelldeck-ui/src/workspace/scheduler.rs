@@ -0,0 +1,719 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{Local, Utc};
+use gpui::*;
+use shelldeck_core::config::activity::{ActivityAction, ActivityEntry, ActivityKind};
+use shelldeck_core::config::keychain;
+use shelldeck_core::config::scheduler_state::SchedulerState;
+use shelldeck_core::models::connection::{Connection, ConnectionStatus};
+use shelldeck_core::models::execution::ExecutionRecord;
+use shelldeck_core::models::managed_site::SiteStatus;
+use shelldeck_core::models::multi_run::MultiTarget;
+use shelldeck_core::models::schedule::{CatchUpPolicy, CronSchedule, TriggerEvent, TriggerKind};
+use shelldeck_core::models::script::{validate_variables, Script, ScriptTarget};
+use shelldeck_core::models::script_runner::{build_command, ScriptCommand};
+use shelldeck_ssh::client::SshClient;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::t;
+
+use super::multi_run::schedule_hosts;
+use super::{TrayNotification, Workspace};
+
+/// How often schedules are checked. Cron resolution is one minute.
+const SCHEDULER_TICK: Duration = Duration::from_secs(30);
+
+/// Unattended runs are killed after this long, unless a multi-host target
+/// sets its own timeout.
+const UNATTENDED_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Outcome of one host of an unattended run.
+struct UnattendedResult {
+    connection_id: Option<Uuid>,
+    exit_code: Option<i32>,
+    output: String,
+    error: Option<String>,
+}
+
+impl Workspace {
+    /// Load the scheduler state, catch up on slots missed while ShellDeck was
+    /// closed, then check schedules periodically. Timers pause while the
+    /// machine sleeps; the first tick after waking sees the gap and applies
+    /// each trigger's catch-up policy.
+    pub fn start_script_scheduler(&mut self, cx: &mut Context<Self>) {
+        if self._scheduler_task.is_some() {
+            return;
+        }
+        self.scheduler_state = SchedulerState::load().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load scheduler state: {}", e);
+            SchedulerState::default()
+        });
+        self.run_due_schedules(cx);
+        self._scheduler_task = Some(cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor().timer(SCHEDULER_TICK).await;
+            if this.update(cx, |ws, cx| ws.run_due_schedules(cx)).is_err() {
+                break;
+            }
+        }));
+    }
+
+    /// Start every schedule trigger with a slot between its last check and
+    /// now. Triggers seen for the first time start counting from now.
+    fn run_due_schedules(&mut self, cx: &mut Context<Self>) {
+        let now = Utc::now();
+        let now_local = now.with_timezone(&Local).naive_local();
+        let scripts = self.scripts.read(cx).scripts.clone();
+        let mut live = Vec::new();
+        let mut changed = false;
+        let mut due_runs = Vec::new();
+
+        for script in &scripts {
+            for trigger in script.triggers.iter().filter(|t| t.enabled) {
+                let TriggerKind::Schedule { cron, catch_up } = &trigger.kind else {
+                    continue;
+                };
+                live.push(trigger.id);
+                let Some(last) = self.scheduler_state.last_checked.insert(trigger.id, now) else {
+                    changed = true;
+                    continue;
+                };
+                let schedule = match CronSchedule::parse(cron) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        tracing::warn!("Invalid schedule on script {}: {}", script.name, e);
+                        continue;
+                    }
+                };
+                let due = schedule.due(last.with_timezone(&Local).naive_local(), now_local);
+                if !due.on_time && due.missed == 0 {
+                    continue;
+                }
+                changed = true;
+                if due.missed > 0 {
+                    let key = match catch_up {
+                        CatchUpPolicy::RunOnce => "activity.schedule.missed_catch_up",
+                        CatchUpPolicy::Skip => "activity.schedule.missed_skipped",
+                    };
+                    self.add_activity_entry(
+                        ActivityEntry::new(
+                            ActivityKind::Script,
+                            t!(key, name = script.name.as_str(), count = due.missed).to_string(),
+                        )
+                        .with_target(script.id.to_string(), script.name.clone())
+                        .with_action(ActivityAction::OpenScript),
+                        cx,
+                    );
+                }
+                if catch_up.should_run(due) {
+                    let reason = if due.on_time {
+                        t!("scheduler.reason.schedule", cron = cron.as_str()).to_string()
+                    } else {
+                        t!("scheduler.reason.catch_up", cron = cron.as_str()).to_string()
+                    };
+                    due_runs.push((script.clone(), reason));
+                }
+            }
+        }
+
+        let tracked = self.scheduler_state.last_checked.len();
+        self.scheduler_state.retain_triggers(&live);
+        changed |= tracked != self.scheduler_state.last_checked.len();
+        if changed {
+            if let Err(e) = self.scheduler_state.save() {
+                tracing::warn!("Failed to save scheduler state: {}", e);
+            }
+        }
+
+        for (script, reason) in due_runs {
+            self.run_triggered_script(script, None, reason, cx);
+        }
+    }
+
+    /// Run every script with a trigger matching `event`, at most once per
+    /// script.
+    pub(super) fn fire_script_triggers(&mut self, event: TriggerEvent, cx: &mut Context<Self>) {
+        let scripts = self.scripts.read(cx).scripts.clone();
+        let mut reason = None;
+        for script in scripts {
+            let Some(trigger) = script.triggers.iter().find(|t| t.matches(&event)) else {
+                continue;
+            };
+            let host = trigger.run_on_event_host.then(|| event.connection_id());
+            let reason = reason
+                .get_or_insert_with(|| self.trigger_event_reason(&event, cx))
+                .clone();
+            self.run_triggered_script(script, host, reason, cx);
+        }
+    }
+
+    /// Fire site triggers when a status check flips a site between up and
+    /// down. A first check that finds the site up is not news.
+    pub(super) fn site_status_changed(
+        &mut self,
+        site_id: Uuid,
+        connection_id: Uuid,
+        previous: &SiteStatus,
+        current: &SiteStatus,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(online) = site_transition(previous, current) {
+            self.fire_script_triggers(
+                TriggerEvent::SiteStatusChanged {
+                    site_id,
+                    connection_id,
+                    online,
+                },
+                cx,
+            );
+        }
+    }
+
+    /// Port forwards and managed sites, labelled, for trigger filters.
+    pub(super) fn trigger_sources(&self, cx: &App) -> (Vec<(Uuid, String)>, Vec<(Uuid, String)>) {
+        let forwards = self
+            .port_forwards
+            .read(cx)
+            .forwards
+            .iter()
+            .map(|f| (f.id, f.label.clone().unwrap_or_else(|| f.description())))
+            .collect();
+        let sites = self
+            .store
+            .managed_sites
+            .iter()
+            .map(|s| (s.id, s.name().to_string()))
+            .collect();
+        (forwards, sites)
+    }
+
+    fn trigger_event_reason(&self, event: &TriggerEvent, cx: &App) -> String {
+        let host = self
+            .connections
+            .iter()
+            .find(|c| c.id == event.connection_id())
+            .map(|c| c.display_name().to_string())
+            .unwrap_or_default();
+        match event {
+            TriggerEvent::Connected { .. } => {
+                t!("scheduler.reason.connected", host = host.as_str()).to_string()
+            }
+            TriggerEvent::Disconnected { .. } => {
+                t!("scheduler.reason.disconnected", host = host.as_str()).to_string()
+            }
+            TriggerEvent::TunnelStarted { forward_id, .. } => {
+                let label = self
+                    .port_forwards
+                    .read(cx)
+                    .forwards
+                    .iter()
+                    .find(|f| f.id == *forward_id)
+                    .map(|f| f.label.clone().unwrap_or_else(|| f.description()))
+                    .unwrap_or_default();
+                t!("scheduler.reason.tunnel_started", label = label.as_str()).to_string()
+            }
+            TriggerEvent::SiteStatusChanged {
+                site_id, online, ..
+            } => {
+                let site = self
+                    .store
+                    .managed_sites
+                    .iter()
+                    .find(|s| s.id == *site_id)
+                    .map(|s| s.name().to_string())
+                    .unwrap_or_default();
+                if *online {
+                    t!("scheduler.reason.site_up", site = site.as_str()).to_string()
+                } else {
+                    t!("scheduler.reason.site_down", site = site.as_str()).to_string()
+                }
+            }
+        }
+    }
+
+    /// Run `script` without user interaction: variables take their defaults
+    /// and stored secrets, output goes to the execution history, and the
+    /// outcome to the activity feed. `host` overrides the script's target.
+    fn run_triggered_script(
+        &mut self,
+        script: Script,
+        host: Option<Uuid>,
+        reason: String,
+        cx: &mut Context<Self>,
+    ) {
+        let script_id = script.id;
+        let script_name = script.name.clone();
+        // Overlapping a run still in progress is not a failure: this slot
+        // is only skipped.
+        if self.active_scripts.contains_key(&script_id) || self.scheduled_runs.contains(&script_id)
+        {
+            tracing::info!(
+                "Skipping unattended run of {}: already running",
+                script_name
+            );
+            self.add_activity_entry(
+                ActivityEntry::new(
+                    ActivityKind::Script,
+                    t!(
+                        "activity.schedule.skipped",
+                        name = script_name.as_str(),
+                        reason = reason.as_str()
+                    )
+                    .to_string(),
+                )
+                .with_detail(t!("scheduler.error.already_running").to_string())
+                .with_target(script_id.to_string(), script_name.clone())
+                .with_action(ActivityAction::OpenScript),
+                cx,
+            );
+            return;
+        }
+
+        let mut timeout = UNATTENDED_TIMEOUT;
+        let mut multi_target = None;
+        let targets: Vec<Option<Connection>> = match (host, &script.target) {
+            (Some(id), _) | (None, ScriptTarget::Remote(id)) => {
+                match self.connections.iter().find(|c| c.id == id) {
+                    Some(conn) => vec![Some(conn.clone())],
+                    None => {
+                        self.report_unattended_failure(
+                            script_id,
+                            &script_name,
+                            &reason,
+                            t!("scheduler.error.connection_missing").to_string(),
+                            cx,
+                        );
+                        return;
+                    }
+                }
+            }
+            (None, ScriptTarget::Local) => vec![None],
+            (None, ScriptTarget::Multi(target)) => {
+                if let Some(secs) = target.timeout_secs {
+                    timeout = Duration::from_secs(secs);
+                }
+                multi_target = Some(target.clone());
+                target
+                    .resolve(&self.connections)
+                    .into_iter()
+                    .map(|c| Some(c.clone()))
+                    .collect()
+            }
+            (None, ScriptTarget::AskOnRun) => {
+                self.report_unattended_failure(
+                    script_id,
+                    &script_name,
+                    &reason,
+                    t!("scheduler.error.ask_on_run").to_string(),
+                    cx,
+                );
+                return;
+            }
+        };
+        if targets.is_empty() {
+            self.report_unattended_failure(
+                script_id,
+                &script_name,
+                &reason,
+                t!("toast.script.multi_no_hosts").to_string(),
+                cx,
+            );
+            return;
+        }
+
+        let variables = script.resolved_variables();
+        let values: HashMap<String, String> = variables
+            .iter()
+            .map(|var| {
+                let value = if var.is_secret() {
+                    keychain::get_script_secret(script_id, &var.name)
+                        .ok()
+                        .flatten()
+                } else {
+                    var.default_value.clone()
+                };
+                (var.name.clone(), value.unwrap_or_default())
+            })
+            .collect();
+        let invalid = validate_variables(&variables, &values);
+        if !invalid.is_empty() {
+            let names: Vec<String> = invalid.into_iter().map(|(name, _)| name).collect();
+            self.report_unattended_failure(
+                script_id,
+                &script_name,
+                &reason,
+                t!("scheduler.error.variables", names = names.join(", ")).to_string(),
+                cx,
+            );
+            return;
+        }
+
+        tracing::info!("Running script {} unattended: {}", script_name, reason);
+        let cmd = build_command(&script, (!values.is_empty()).then_some(&values));
+        let secret_names = script.secret_variable_names();
+        let host_names: HashMap<Uuid, String> = targets
+            .iter()
+            .flatten()
+            .map(|c| (c.id, c.display_name().to_string()))
+            .collect();
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<Vec<UnattendedResult>>();
+        let spawn_result = std::thread::Builder::new()
+            .name(format!("script-unattended-{}", script_id))
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        tracing::error!("Failed to create async runtime: {}", e);
+                        return;
+                    }
+                };
+                let results = rt.block_on(async move {
+                    match multi_target {
+                        Some(target) => {
+                            let hosts = targets.into_iter().flatten().collect();
+                            run_unattended_hosts(hosts, cmd, &target, timeout).await
+                        }
+                        None => {
+                            let mut results = Vec::new();
+                            for target in targets {
+                                results.push(run_unattended(target, &cmd, timeout).await);
+                            }
+                            results
+                        }
+                    }
+                });
+                let _ = done_tx.send(results);
+            });
+        if let Err(e) = spawn_result {
+            self.report_unattended_failure(script_id, &script_name, &reason, e.to_string(), cx);
+            return;
+        }
+
+        self.scheduled_runs.insert(script_id);
+        let updated = self.scripts.update(cx, |editor, cx| {
+            let script = editor.scripts.iter_mut().find(|s| s.id == script_id)?;
+            script.last_run = Some(Utc::now());
+            script.run_count += 1;
+            cx.notify();
+            Some(script.clone())
+        });
+        if let Some(s) = updated {
+            let _ = self.store.update_script(s);
+        }
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let results = loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(500))
+                    .await;
+                match done_rx.try_recv() {
+                    Ok(results) => break results,
+                    Err(std::sync::mpsc::TryRecvError::Empty) => continue,
+                    // The runner thread died without reporting.
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => break Vec::new(),
+                }
+            };
+            let _ = this.update(cx, |ws, cx| {
+                ws.scheduled_runs.remove(&script_id);
+                let mut failures = Vec::new();
+                for result in &results {
+                    let mut record = ExecutionRecord::new(script_id, result.connection_id)
+                        .with_declared_secrets(&values, &secret_names);
+                    record.append_output(&result.output);
+                    if let Some(error) = &result.error {
+                        record.append_output(&format!("Error: {}\n", error));
+                    }
+                    record.finish(result.exit_code.unwrap_or(-1));
+                    if record.failed() {
+                        let host = result
+                            .connection_id
+                            .and_then(|id| host_names.get(&id).cloned())
+                            .unwrap_or_else(|| t!("scheduler.local_host").to_string());
+                        let why = match (&result.error, result.exit_code) {
+                            (Some(error), _) => error.clone(),
+                            (None, code) => {
+                                t!("activity.script.exit_code", code = code.unwrap_or(-1))
+                                    .to_string()
+                            }
+                        };
+                        failures.push(format!("{}: {}", host, why));
+                    }
+                    ws.scripts.update(cx, |editor, cx| {
+                        editor.record_unattended_run(record, cx);
+                    });
+                }
+                if results.is_empty() {
+                    failures.push(t!("scheduler.error.runner_crashed").to_string());
+                }
+
+                if failures.is_empty() {
+                    ws.add_activity_entry(
+                        ActivityEntry::new(
+                            ActivityKind::Script,
+                            t!(
+                                "activity.schedule.succeeded",
+                                name = script_name.as_str(),
+                                reason = reason.as_str()
+                            )
+                            .to_string(),
+                        )
+                        .with_target(script_id.to_string(), script_name.clone())
+                        .with_action(ActivityAction::OpenScript),
+                        cx,
+                    );
+                } else {
+                    ws.report_unattended_failure(
+                        script_id,
+                        &script_name,
+                        &reason,
+                        failures.join("\n"),
+                        cx,
+                    );
+                }
+                ws.update_dashboard_stats(cx);
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Log a failed unattended run and, if enabled, tell the OS.
+    fn report_unattended_failure(
+        &mut self,
+        script_id: Uuid,
+        script_name: &str,
+        reason: &str,
+        detail: String,
+        cx: &mut Context<Self>,
+    ) {
+        tracing::warn!("Unattended run of {} failed: {}", script_name, detail);
+        self.add_activity_entry(
+            ActivityEntry::new(
+                ActivityKind::Error,
+                t!(
+                    "activity.schedule.failed",
+                    name = script_name,
+                    reason = reason
+                )
+                .to_string(),
+            )
+            .with_detail(detail)
+            .with_target(script_id.to_string(), script_name.to_string())
+            .with_action(ActivityAction::OpenScript),
+            cx,
+        );
+        if self.app_config.tray.notify_scheduled_failures {
+            self.emit_tray_notification(TrayNotification::ScheduledRunFailed {
+                name: script_name.to_string(),
+            });
+        }
+    }
+}
+
+/// The trigger event for a connection status change, if it is one.
+/// Only losing an established connection counts as a disconnect; an
+/// `Error` status means it was not closed by the user.
+pub(super) fn connection_transition_event(
+    connection_id: Uuid,
+    previous: &ConnectionStatus,
+    current: &ConnectionStatus,
+) -> Option<TriggerEvent> {
+    match (previous, current) {
+        (ConnectionStatus::Connected, ConnectionStatus::Connected) => None,
+        (_, ConnectionStatus::Connected) => Some(TriggerEvent::Connected { connection_id }),
+        (ConnectionStatus::Connected, current) => Some(TriggerEvent::Disconnected {
+            connection_id,
+            unexpected: matches!(current, ConnectionStatus::Error(_)),
+        }),
+        _ => None,
+    }
+}
+
+/// `Some(online)` when a site status check flipped the site up or down.
+fn site_transition(previous: &SiteStatus, current: &SiteStatus) -> Option<bool> {
+    let up = |status: &SiteStatus| match status {
+        SiteStatus::Online => Some(true),
+        SiteStatus::Offline | SiteStatus::Error(_) => Some(false),
+        SiteStatus::Unknown => None,
+    };
+    let now = up(current)?;
+    match up(previous) {
+        Some(before) if before == now => None,
+        None if now => None,
+        _ => Some(now),
+    }
+}
+
+/// Run `cmd` locally (`target == None`) or over a fresh SSH session,
+/// capturing all output.
+/// Run a multi-host target unattended with the same concurrency and
+/// rolling rules as a manual run. Hosts a rolling run never started are
+/// left out of the results.
+async fn run_unattended_hosts(
+    hosts: Vec<Connection>,
+    cmd: ScriptCommand,
+    target: &MultiTarget,
+    timeout: Duration,
+) -> Vec<UnattendedResult> {
+    let (results_tx, mut results_rx) = mpsc::unbounded_channel();
+    // Skipped hosts need no record, so their events go nowhere.
+    let (events, _) = std::sync::mpsc::channel();
+    // Nothing cancels an unattended run; the sender is kept so the
+    // scheduler does not read its absence as a stop.
+    let (_shutdown_tx, shutdown_rx) = mpsc::channel(1);
+    schedule_hosts(hosts, target, events, shutdown_rx, |conn, _cancel_rx| {
+        let cmd = cmd.clone();
+        let results_tx = results_tx.clone();
+        async move {
+            let result = run_unattended(Some(conn), &cmd, timeout).await;
+            let ok = result.error.is_none() && result.exit_code == Some(0);
+            let _ = results_tx.send(result);
+            ok
+        }
+    })
+    .await;
+    drop(results_tx);
+    let mut results = Vec::new();
+    while let Some(result) = results_rx.recv().await {
+        results.push(result);
+    }
+    results
+}
+
+async fn run_unattended(
+    target: Option<Connection>,
+    cmd: &ScriptCommand,
+    timeout: Duration,
+) -> UnattendedResult {
+    let connection_id = target.as_ref().map(|c| c.id);
+    let run = async {
+        match &target {
+            None => {
+                let output = tokio::process::Command::new(&cmd.local_binary)
+                    .args(&cmd.local_args)
+                    .envs(cmd.env_vars.iter().map(|(k, v)| (k, v)))
+                    .kill_on_drop(true)
+                    .output()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok((output.status.code(), output.stdout, output.stderr))
+            }
+            Some(conn) => {
+                let session = SshClient::new()
+                    .connect(conn)
+                    .await
+                    .map_err(|e| format!("SSH connection failed: {}", e))?;
+                let result = session
+                    .exec(&cmd.ssh_command)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok((
+                    result.exit_code.map(|c| c as i32),
+                    result.stdout,
+                    result.stderr,
+                ))
+            }
+        }
+    };
+
+    match tokio::time::timeout(timeout, run).await {
+        Ok(Ok((exit_code, stdout, stderr))) => {
+            let mut output = String::from_utf8_lossy(&stdout).into_owned();
+            if !stderr.is_empty() {
+                output.push_str("--- stderr ---\n");
+                output.push_str(&String::from_utf8_lossy(&stderr));
+            }
+            UnattendedResult {
+                connection_id,
+                exit_code,
+                output,
+                error: None,
+            }
+        }
+        Ok(Err(error)) => UnattendedResult {
+            connection_id,
+            exit_code: None,
+            output: String::new(),
+            error: Some(error),
+        },
+        Err(_) => UnattendedResult {
+            connection_id,
+            exit_code: None,
+            output: String::new(),
+            error: Some(t!("scheduler.error.timed_out", secs = timeout.as_secs()).to_string()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connection_transitions_map_to_trigger_events() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            connection_transition_event(
+                id,
+                &ConnectionStatus::Connecting,
+                &ConnectionStatus::Connected
+            ),
+            Some(TriggerEvent::Connected { connection_id: id })
+        );
+        assert_eq!(
+            connection_transition_event(
+                id,
+                &ConnectionStatus::Connected,
+                &ConnectionStatus::Error("reset".into())
+            ),
+            Some(TriggerEvent::Disconnected {
+                connection_id: id,
+                unexpected: true
+            })
+        );
+        assert_eq!(
+            connection_transition_event(
+                id,
+                &ConnectionStatus::Connected,
+                &ConnectionStatus::Disconnected
+            ),
+            Some(TriggerEvent::Disconnected {
+                connection_id: id,
+                unexpected: false
+            })
+        );
+        assert_eq!(
+            connection_transition_event(
+                id,
+                &ConnectionStatus::Connecting,
+                &ConnectionStatus::Error("refused".into())
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn site_transitions_ignore_unchanged_and_first_up() {
+        assert_eq!(
+            site_transition(&SiteStatus::Unknown, &SiteStatus::Online),
+            None
+        );
+        assert_eq!(
+            site_transition(&SiteStatus::Unknown, &SiteStatus::Offline),
+            Some(false)
+        );
+        assert_eq!(
+            site_transition(&SiteStatus::Offline, &SiteStatus::Error("x".into())),
+            None
+        );
+        assert_eq!(
+            site_transition(&SiteStatus::Error("x".into()), &SiteStatus::Online),
+            Some(true)
+        );
+    }
+}
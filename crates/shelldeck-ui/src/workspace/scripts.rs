@@ -3,11 +3,14 @@ use gpui::*;
 use shelldeck_core::ai::{create_client, parse_generated_script_draft, AiContext, AiSurface};
 use shelldeck_core::config::activity::{ActivityAction, ActivityEntry, ActivityKind};
 use shelldeck_core::config::cloud_account::AppMode;
+use shelldeck_core::config::execution_history::ExecutionHistoryStore;
 use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::execution::{ExecutionRecord, REDACTED};
 use shelldeck_core::models::script::{ScriptLanguage, ScriptTarget};
 use shelldeck_core::models::script_runner::build_command;
 use shelldeck_core::models::templates::all_templates;
 use shelldeck_ssh::client::SshClient;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 use crate::ai_workflow::{AiNamingKind, AiWorkflowTarget};
@@ -45,6 +48,13 @@ impl Workspace {
                     return;
                 }
 
+                if let ScriptTarget::Multi(target) = &script.target {
+                    self.run_script_multi(script.clone(), target.clone(), HashMap::new(), cx);
+                    self.sync_scripts_to_terminal_toolbar(cx);
+                    cx.notify();
+                    return;
+                }
+
                 tracing::info!("Running script: {}", script.name);
                 let cmd = build_command(script, None);
                 let script_name = script.name.clone();
@@ -55,10 +65,7 @@ impl Workspace {
                 };
 
                 // Create execution record
-                let record = shelldeck_core::models::execution::ExecutionRecord::new(
-                    script_id,
-                    connection_id,
-                );
+                let record = ExecutionRecord::new(script_id, connection_id);
 
                 let display_cmd = if matches!(script.language, ScriptLanguage::Shell) {
                     format!("$ {}", script.body)
@@ -70,6 +77,7 @@ impl Workspace {
                     editor.running_script_id = Some(script_id);
                     editor.execution_output.clear();
                     editor.execution_output.push(display_cmd);
+                    editor.multi_run = None;
                     editor.history.push(record);
                 });
 
@@ -154,6 +162,8 @@ impl Workspace {
                             cx,
                         );
                     }
+                    // Multi-host runs are dispatched before the single-run setup.
+                    ScriptTarget::Multi(_) => {}
                 }
 
                 // Sync favorites/recent to terminal toolbar
@@ -174,10 +184,8 @@ impl Workspace {
                         editor
                             .execution_output
                             .push("[Script cancelled]".to_string());
-                        // Finalize the last execution record
-                        if let Some(record) = editor.history.last_mut() {
-                            record.finish(-1);
-                        }
+                        // Finalize and persist the last execution record
+                        editor.finish_execution(-1, cx);
                         cx.notify();
                     });
 
@@ -294,20 +302,31 @@ impl Workspace {
             }
             ScriptEvent::DeleteScript(id) => {
                 let id = *id;
-                let name = self
+                let (name, secret_names) = self
                     .scripts
                     .read(cx)
                     .scripts
                     .iter()
                     .find(|s| s.id == id)
-                    .map(|s| s.name.clone());
-                self.scripts.update(cx, |editor, _| {
+                    .map(|s| (Some(s.name.clone()), s.secret_variable_names()))
+                    .unwrap_or_default();
+                self.scripts.update(cx, |editor, cx| {
                     editor.scripts.retain(|s| s.id != id);
                     if editor.selected_script == Some(id) {
-                        editor.selected_script = None;
+                        editor.select_script(None, cx);
                     }
                 });
                 let _ = self.store.remove_script(id);
+                if let Err(e) = ExecutionHistoryStore::remove_script(id) {
+                    tracing::warn!("Failed to remove execution history of {}: {}", id, e);
+                }
+                for variable in &secret_names {
+                    if let Err(e) =
+                        shelldeck_core::config::keychain::delete_script_secret(id, variable)
+                    {
+                        tracing::warn!("Failed to remove secret {} of {}: {}", variable, id, e);
+                    }
+                }
                 if let Some(name) = name {
                     self.show_toast(
                         t!("toast.script.deleted", name = name.as_str()).to_string(),
@@ -381,6 +400,55 @@ impl Workspace {
                     cx,
                 );
             }
+            ScriptEvent::RerunExecution(record) => {
+                if self.scripts.read(cx).is_running() {
+                    self.show_toast(
+                        t!("toast.script.already_running").to_string(),
+                        ToastLevel::Warning,
+                        cx,
+                    );
+                    return;
+                }
+                let Some(mut script) = self
+                    .scripts
+                    .read(cx)
+                    .scripts
+                    .iter()
+                    .find(|s| s.id == record.script_id)
+                    .cloned()
+                else {
+                    return;
+                };
+                script.target = match record.connection_id {
+                    Some(cid) => ScriptTarget::Remote(cid),
+                    None => ScriptTarget::Local,
+                };
+
+                if record.variables.is_empty() {
+                    self.handle_script_event(&ScriptEvent::RunScript(script), cx);
+                } else if record.has_redacted_variables() {
+                    // Secrets are never saved: ask for them again, with the
+                    // other values of the run pre-filled.
+                    let variables = script
+                        .resolved_variables()
+                        .into_iter()
+                        .map(|mut var| {
+                            if let Some(value) = record
+                                .variables
+                                .get(&var.name)
+                                .filter(|value| value.as_str() != REDACTED)
+                            {
+                                var.default_value = Some(value.clone());
+                            }
+                            var
+                        })
+                        .collect();
+                    self.show_variable_prompt(script, variables, cx);
+                } else {
+                    let values = record.variables.clone().into_iter().collect();
+                    self.run_script_with_values(script, values, cx);
+                }
+            }
         }
     }
 
@@ -562,10 +630,8 @@ impl Workspace {
                             editor.running_script_id = None;
                             let code = exit_code.unwrap_or(-1);
                             editor.execution_output.push(format!("Exit code: {}", code));
-                            // Finalize execution record
-                            if let Some(record) = editor.history.last_mut() {
-                                record.finish(code);
-                            }
+                            // Finalize and persist execution record
+                            editor.finish_execution(code, cx);
                             cx.notify();
                         });
                         let _ = _this.update(cx, |ws, cx| {
@@ -814,9 +880,7 @@ impl Workspace {
                             editor.running_script_id = None;
                             let code = exit_code.unwrap_or(-1);
                             editor.execution_output.push(format!("Exit code: {}", code));
-                            if let Some(record) = editor.history.last_mut() {
-                                record.finish(code);
-                            }
+                            editor.finish_execution(code, cx);
                             cx.notify();
                         });
                         let _ = _this.update(cx, |ws, cx| {
@@ -991,7 +1055,12 @@ impl Workspace {
         cx: &mut Context<Self>,
     ) {
         let script_clone = script.clone();
-        let prompt = cx.new(|cx| VariablePrompt::new(script_clone, variables, cx));
+        let connections: Vec<(String, String)> = self
+            .connections
+            .iter()
+            .map(|c| (c.display_name().to_string(), c.hostname.clone()))
+            .collect();
+        let prompt = cx.new(|cx| VariablePrompt::new(script_clone, variables, connections, cx));
 
         let sub = cx.subscribe(
             &prompt,
@@ -1021,6 +1090,13 @@ impl Workspace {
         values: std::collections::HashMap<String, String>,
         cx: &mut Context<Self>,
     ) {
+        if let ScriptTarget::Multi(target) = &script.target {
+            self.run_script_multi(script.clone(), target.clone(), values, cx);
+            self.sync_scripts_to_terminal_toolbar(cx);
+            cx.notify();
+            return;
+        }
+
         tracing::info!("Running script with variables: {}", script.name);
         let cmd = build_command(&script, Some(&values));
         let script_name = script.name.clone();
@@ -1030,13 +1106,29 @@ impl Workspace {
             _ => None,
         };
 
-        let record =
-            shelldeck_core::models::execution::ExecutionRecord::new(script_id, connection_id);
+        let secret_names = script.secret_variable_names();
+        let record = ExecutionRecord::new(script_id, connection_id)
+            .with_declared_secrets(&values, &secret_names);
 
         let display_cmd = if matches!(script.language, ScriptLanguage::Shell) {
+            // The echoed command line shows secrets masked, as the history does.
+            let shown: HashMap<String, String> = values
+                .iter()
+                .map(|(name, value)| {
+                    if record.variables.get(name).map(String::as_str) == Some(REDACTED) {
+                        (name.clone(), REDACTED.to_string())
+                    } else {
+                        (name.clone(), value.clone())
+                    }
+                })
+                .collect();
             format!(
                 "$ {}",
-                shelldeck_core::models::script_runner::substitute_variables(&script.body, &values)
+                shelldeck_core::models::script_runner::substitute_variables(
+                    &script.body,
+                    &shown,
+                    &script.language
+                )
             )
         } else {
             format!("$ [{}] {}", script.language.label(), cmd.ssh_command)
@@ -1047,6 +1139,7 @@ impl Workspace {
             editor.running_script_id = Some(script_id);
             editor.execution_output.clear();
             editor.execution_output.push(display_cmd);
+            editor.multi_run = None;
             editor.history.push(record);
             // Store the variable values for display in the variables bar
             editor.last_var_values.insert(script_id, values_for_store);
@@ -1127,6 +1220,8 @@ impl Workspace {
                     cx,
                 );
             }
+            // Multi-host runs are dispatched before the single-run setup.
+            ScriptTarget::Multi(_) => {}
         }
 
         self.sync_scripts_to_terminal_toolbar(cx);
@@ -1153,15 +1248,20 @@ impl Workspace {
         };
         let ai_naming_enabled =
             self.ai_backend_available() && self.app_config.ai.allows(AiSurface::Naming);
+        let (host_groups, host_tags) = self.connection_labels();
+        let (trigger_forwards, trigger_sites) = self.trigger_sources(cx);
         let form = cx.new(|form_cx| {
-            ScriptForm::new(
+            let mut form = ScriptForm::new(
                 connections,
                 ai_enabled,
                 ai_backend,
                 ai_model,
                 ai_naming_enabled,
                 form_cx,
-            )
+            );
+            form.set_host_labels(host_groups, host_tags);
+            form.set_trigger_sources(trigger_forwards, trigger_sites);
+            form
         });
 
         let sub = cx.subscribe(&form, |this, form, event: &ScriptFormEvent, cx| {
@@ -1257,8 +1357,10 @@ impl Workspace {
         };
         let ai_naming_enabled =
             self.ai_backend_available() && self.app_config.ai.allows(AiSurface::Naming);
+        let (host_groups, host_tags) = self.connection_labels();
+        let (trigger_forwards, trigger_sites) = self.trigger_sources(cx);
         let form = cx.new(|form_cx| {
-            ScriptForm::from_script(
+            let mut form = ScriptForm::from_script(
                 &script,
                 connections,
                 ai_enabled,
@@ -1266,7 +1368,10 @@ impl Workspace {
                 ai_model,
                 ai_naming_enabled,
                 form_cx,
-            )
+            );
+            form.set_host_labels(host_groups, host_tags);
+            form.set_trigger_sources(trigger_forwards, trigger_sites);
+            form
         });
 
         let sub = cx.subscribe(&form, |this, form, event: &ScriptFormEvent, cx| {
@@ -1,15 +1,22 @@
+use std::path::PathBuf;
+
 use gpui::prelude::*;
 use gpui::*;
 use shelldeck_core::config::cloud_account::AppMode;
 use shelldeck_core::models::connection::Connection;
-use shelldeck_core::models::server_sync::SyncProfile;
+use shelldeck_core::models::discovery::{self, RelayCommands, SyncCommand};
+use shelldeck_core::models::server_sync::{SyncItemKind, SyncOptions, SyncProfile, SyncStep};
 use shelldeck_ssh::client::SshClient;
+use shelldeck_ssh::session::{RelayEvent, SshSession};
 use uuid::Uuid;
 
 use crate::server_sync_view::{PanelSide, ServerSyncEvent, LOCAL_MACHINE_ID};
+use crate::sidebar::SidebarSection;
 use crate::t;
 use crate::toast::ToastLevel;
 
+use super::database_sync::sync_database;
+use super::wordpress_sync::sync_wordpress_site;
 use super::{ActiveScript, Workspace};
 
 impl Workspace {
@@ -54,6 +61,12 @@ impl Workspace {
                     self.discover_remote_services(conn, panel, cx);
                 }
             }
+            ServerSyncEvent::PlanSync {
+                request_id,
+                profile,
+            } => {
+                self.plan_sync_operation(*request_id, profile.clone(), cx);
+            }
             ServerSyncEvent::StartSync(profile) => {
                 self.start_sync_operation(profile.clone(), cx);
             }
@@ -100,6 +113,55 @@ impl Workspace {
                 // Individual sync command execution — handled as part of start_sync_operation
                 tracing::debug!("ExecSync for item {:?} on op {:?}", item_id, operation_id);
             }
+            ServerSyncEvent::QueueTransfers(items) => self.queue_transfers(items.clone(), cx),
+            ServerSyncEvent::PauseTransfer(id) => self.pause_transfer(*id, cx),
+            ServerSyncEvent::ResumeTransfer(id) => self.resume_transfer(*id, cx),
+            ServerSyncEvent::RetryTransfer(id) => self.retry_transfer(*id, cx),
+            ServerSyncEvent::CancelTransfer(id) => self.cancel_transfer(*id, cx),
+            ServerSyncEvent::ResolveConflict {
+                id,
+                choice,
+                apply_to_all,
+            } => self.resolve_transfer_conflict(*id, *choice, *apply_to_all, cx),
+            ServerSyncEvent::ClearTransfers => self.clear_finished_transfers(cx),
+            ServerSyncEvent::FileOperation {
+                connection_id,
+                operation,
+            } => self.run_file_operation(*connection_id, operation.clone(), cx),
+            ServerSyncEvent::OpenInEditor {
+                connection_id,
+                path,
+                is_dir,
+            } => self.open_in_editor(*connection_id, path.clone(), *is_dir, cx),
+        }
+    }
+
+    /// Show a sync panel entry in the file editor, over SSH for a server.
+    fn open_in_editor(
+        &mut self,
+        connection_id: Uuid,
+        path: String,
+        is_dir: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let connection = if connection_id == LOCAL_MACHINE_ID {
+            None
+        } else {
+            match self.connections.iter().find(|c| c.id == connection_id) {
+                Some(conn) => Some(conn.clone()),
+                None => return,
+            }
+        };
+        let opened = self.file_editor.update(cx, |editor, cx| {
+            editor.open_location(connection, PathBuf::from(path), is_dir, cx)
+        });
+        match opened {
+            Ok(()) => self.activate_dev_section(SidebarSection::FileEditor, cx),
+            Err(e) => self.show_toast(
+                t!("toast.editor.remote_failed", error = e.to_string()).to_string(),
+                ToastLevel::Error,
+                cx,
+            ),
         }
     }
 
@@ -110,8 +172,6 @@ impl Workspace {
         panel: PanelSide,
         cx: &mut Context<Self>,
     ) {
-        use shelldeck_core::models::discovery;
-
         let cmd = discovery::ls_command(&path);
         let fallback_cmd = discovery::ls_command_fallback(&path);
         let path_clone = path.clone();
@@ -234,7 +294,6 @@ impl Workspace {
         panel: PanelSide,
         cx: &mut Context<Self>,
     ) {
-        use shelldeck_core::models::discovery;
         let entries = discovery::list_local_files(&path);
         self.server_sync.update(cx, |view, cx| {
             view.set_file_entries(panel, path, entries);
@@ -244,7 +303,6 @@ impl Workspace {
 
     pub(super) fn start_sync_operation(&mut self, profile: SyncProfile, cx: &mut Context<Self>) {
         use chrono::Utc;
-        use shelldeck_core::models::discovery;
         use shelldeck_core::models::server_sync::*;
 
         let op_id = Uuid::new_v4();
@@ -260,6 +318,7 @@ impl Workspace {
                 total_files: None,
                 current_file: None,
                 error_message: None,
+                step: None,
             })
             .collect();
 
@@ -308,84 +367,19 @@ impl Workspace {
             }
         };
 
-        // Build commands for each item
-        let mut commands: Vec<(Uuid, String)> = Vec::new();
-        for item in &profile.items {
-            if !item.enabled {
-                continue;
-            }
-            let cmd = match &item.kind {
-                SyncItemKind::Directory {
-                    source_path,
-                    dest_path,
-                    exclude_patterns,
-                } => discovery::rsync_command(
-                    source_path,
-                    &dest_conn.user,
-                    &dest_conn.hostname,
-                    dest_path,
-                    &profile.options,
-                    exclude_patterns,
-                ),
-                SyncItemKind::Database {
-                    ref name,
-                    engine,
-                    ref source_credentials,
-                    ref dest_credentials,
-                } => match engine {
-                    DatabaseEngine::Mysql => discovery::mysql_sync_command(
-                        name,
-                        source_credentials,
-                        &dest_conn.user,
-                        &dest_conn.hostname,
-                        dest_credentials,
-                        profile.options.compress,
-                    ),
-                    DatabaseEngine::Postgresql => discovery::pg_sync_command(
-                        name,
-                        source_credentials,
-                        &dest_conn.user,
-                        &dest_conn.hostname,
-                        dest_credentials,
-                        profile.options.compress,
-                    ),
-                },
-                SyncItemKind::NginxSite {
-                    ref site,
-                    ref sync_config,
-                    ref sync_root,
-                } => {
-                    let mut cmds = Vec::new();
-                    if *sync_root && !site.root.is_empty() {
-                        cmds.push(discovery::rsync_command(
-                            &site.root,
-                            &dest_conn.user,
-                            &dest_conn.hostname,
-                            &site.root,
-                            &profile.options,
-                            &[],
-                        ));
-                    }
-                    if *sync_config && !site.config_path.is_empty() {
-                        cmds.push(discovery::rsync_command(
-                            &site.config_path,
-                            &dest_conn.user,
-                            &dest_conn.hostname,
-                            &site.config_path,
-                            &profile.options,
-                            &[],
-                        ));
-                    }
-                    cmds.join(" && ")
-                }
-            };
-            commands.push((item.id, cmd));
-        }
+        let items: Vec<(Uuid, SyncItemKind)> = profile
+            .items
+            .iter()
+            .filter(|item| item.enabled)
+            .map(|item| (item.id, item.kind.clone()))
+            .collect();
+        let options = profile.options.clone();
 
-        let total_items = commands.len();
-        let (shutdown_tx, _shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
+        let total_items = items.len();
+        let (shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
         let (stream_tx, stream_rx) = std::sync::mpsc::channel::<(Uuid, String)>();
         let (done_tx, done_rx) = std::sync::mpsc::channel::<(Uuid, bool)>();
+        let (bytes_tx, bytes_rx) = std::sync::mpsc::channel::<(Uuid, ItemProgress)>();
 
         let thread_handle = std::thread::Builder::new()
             .name(format!("sync-op-{}", op_id))
@@ -403,46 +397,143 @@ impl Workspace {
                 };
 
                 rt.block_on(async move {
+                    let fail_all = |message: String| {
+                        let _ = stream_tx.send((Uuid::nil(), message));
+                        for (item_id, _) in &items {
+                            let _ = done_tx.send((*item_id, false));
+                        }
+                    };
+
                     let client = SshClient::new();
                     let session = match client.connect(&source_conn).await {
                         Ok(s) => s,
                         Err(e) => {
-                            let _ =
-                                stream_tx.send((Uuid::nil(), format!("[sync] SSH Error: {}", e)));
+                            fail_all(format!("[sync] SSH Error: {}", e));
                             return;
                         }
                     };
 
-                    for (item_id, cmd) in &commands {
-                        let _ = stream_tx.send((*item_id, format!("[sync] Running: {}", cmd)));
-
-                        let (output_tx, mut output_rx) =
-                            tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
-
-                        let fwd_tx = stream_tx.clone();
-                        let fwd_item_id = *item_id;
-                        let fwd_task = tokio::spawn(async move {
-                            while let Some(data) = output_rx.recv().await {
-                                let text = String::from_utf8_lossy(&data);
-                                for line in text.lines() {
-                                    let _ = fwd_tx.send((fwd_item_id, line.to_string()));
-                                }
+                    let relay = match options.transport {
+                        SyncTransport::Direct => false,
+                        SyncTransport::Relay => true,
+                        SyncTransport::Auto => {
+                            let probe = discovery::direct_probe_command(
+                                &dest_conn.user,
+                                &dest_conn.hostname,
+                                dest_conn.port,
+                            );
+                            let reachable = session.exec(&probe).await.is_ok_and(|r| {
+                                r.stdout_string().contains(discovery::DIRECT_PROBE_OK)
+                            });
+                            if !reachable {
+                                let _ = stream_tx.send((
+                                    Uuid::nil(),
+                                    format!(
+                                        "[sync] {} cannot reach {} directly",
+                                        source_conn.hostname, dest_conn.hostname
+                                    ),
+                                ));
                             }
-                        });
-
-                        let (_cancel_tx, cancel_rx) = tokio::sync::mpsc::channel::<()>(1);
-                        let result = session.exec_cancellable(cmd, output_tx, cancel_rx).await;
-                        let _ = fwd_task.await;
+                            !reachable
+                        }
+                    };
 
-                        match result {
-                            Ok(_) => {
-                                let _ = done_tx.send((*item_id, true));
-                            }
+                    let _ = stream_tx.send((
+                        Uuid::nil(),
+                        if relay {
+                            "[sync] Transport: relay through ShellDeck".to_string()
+                        } else {
+                            "[sync] Transport: direct".to_string()
+                        },
+                    ));
+                    // WordPress databases always stream through ShellDeck so
+                    // the URL search-replace can run on the way, and stored
+                    // database credentials need a secret file on each side.
+                    let both_sides = items.iter().any(|(_, kind)| {
+                        matches!(kind, SyncItemKind::WordPressSite { .. })
+                            || has_db_credentials(kind)
+                    });
+                    let dest_session = if relay || both_sides {
+                        match client.connect(&dest_conn).await {
+                            Ok(s) => Some(s),
                             Err(e) => {
-                                let _ = stream_tx.send((*item_id, format!("[sync] Error: {}", e)));
-                                let _ = done_tx.send((*item_id, false));
+                                fail_all(format!("[sync] SSH Error: {}", e));
+                                return;
                             }
                         }
+                    } else {
+                        None
+                    };
+
+                    let reporter = RelayReporter {
+                        log: stream_tx.clone(),
+                        bytes: bytes_tx,
+                    };
+                    for (item_id, kind) in &items {
+                        let success = match (kind, &dest_session) {
+                            (SyncItemKind::WordPressSite { .. }, Some(dest)) => {
+                                sync_wordpress_site(
+                                    *item_id,
+                                    kind,
+                                    &session,
+                                    dest,
+                                    &dest_conn,
+                                    relay,
+                                    &options,
+                                    &mut shutdown_rx,
+                                    &reporter,
+                                )
+                                .await
+                            }
+                            (SyncItemKind::Database { .. }, Some(dest))
+                                if has_db_credentials(kind) =>
+                            {
+                                sync_database(
+                                    *item_id,
+                                    kind,
+                                    &session,
+                                    dest,
+                                    &dest_conn,
+                                    relay,
+                                    &options,
+                                    &mut shutdown_rx,
+                                    &reporter,
+                                )
+                                .await
+                            }
+                            (_, Some(dest)) if relay => {
+                                let commands = discovery::sync_item_commands(
+                                    kind,
+                                    &dest_conn.user,
+                                    &dest_conn.hostname,
+                                    dest_conn.port,
+                                    &options,
+                                    true,
+                                );
+                                relay_item(
+                                    *item_id,
+                                    &session,
+                                    dest,
+                                    &relay_commands(commands),
+                                    &options,
+                                    &mut shutdown_rx,
+                                    &reporter,
+                                )
+                                .await
+                            }
+                            _ => {
+                                let commands = discovery::sync_item_commands(
+                                    kind,
+                                    &dest_conn.user,
+                                    &dest_conn.hostname,
+                                    dest_conn.port,
+                                    &options,
+                                    false,
+                                );
+                                run_direct(*item_id, &session, commands, &reporter).await
+                            }
+                        };
+                        let _ = done_tx.send((*item_id, success));
                     }
                 });
             });
@@ -482,6 +573,43 @@ impl Workspace {
                     lines.push((item_id, line));
                 }
 
+                let mut byte_updates = Vec::new();
+                while let Ok(update) = bytes_rx.try_recv() {
+                    byte_updates.push(update);
+                }
+                if !byte_updates.is_empty() {
+                    let _ = sync_handle.update(cx, |view, cx| {
+                        if let Some(ref mut op) = view.active_operation {
+                            for (item_id, update) in &byte_updates {
+                                let Some(prog) =
+                                    op.item_progress.iter_mut().find(|p| p.item_id == *item_id)
+                                else {
+                                    continue;
+                                };
+                                prog.status = SyncOperationStatus::Running;
+                                match *update {
+                                    ItemProgress::Step(step) => {
+                                        prog.step = Some(step);
+                                        prog.bytes_transferred = 0;
+                                        prog.total_bytes = None;
+                                        prog.files_transferred = 0;
+                                        prog.total_files = None;
+                                        prog.current_file = None;
+                                    }
+                                    ItemProgress::Total { files, bytes } => {
+                                        prog.total_files = Some(files);
+                                        prog.total_bytes = Some(bytes);
+                                    }
+                                    ItemProgress::Transferred(bytes) => {
+                                        prog.bytes_transferred = bytes;
+                                    }
+                                }
+                            }
+                        }
+                        cx.notify();
+                    });
+                }
+
                 while let Ok((item_id, success)) = done_rx.try_recv() {
                     all_done.insert(item_id);
                     let status = if success {
@@ -566,3 +694,216 @@ impl Workspace {
         .detach();
     }
 }
+
+fn has_db_credentials(kind: &SyncItemKind) -> bool {
+    matches!(
+        kind,
+        SyncItemKind::Database {
+            source_credentials,
+            dest_credentials,
+            ..
+        } if source_credentials.is_some() || dest_credentials.is_some()
+    )
+}
+
+/// Progress reported by the sync thread besides log lines: byte counts of
+/// relayed items and the step a multi-step item moved on to.
+pub(super) enum ItemProgress {
+    Step(SyncStep),
+    Total { files: u32, bytes: u64 },
+    Transferred(u64),
+}
+
+/// Where the sync thread sends log lines and progress.
+pub(super) struct RelayReporter {
+    pub(super) log: std::sync::mpsc::Sender<(Uuid, String)>,
+    pub(super) bytes: std::sync::mpsc::Sender<(Uuid, ItemProgress)>,
+}
+
+pub(super) fn relay_commands(commands: Vec<SyncCommand>) -> Vec<RelayCommands> {
+    commands
+        .into_iter()
+        .filter_map(|c| match c {
+            SyncCommand::Relay(r) => Some(r),
+            SyncCommand::Direct(_) => None,
+        })
+        .collect()
+}
+
+/// Run an item's direct commands on the source, streaming their output
+/// into the log.
+pub(super) async fn run_direct(
+    item_id: Uuid,
+    source: &SshSession,
+    commands: Vec<SyncCommand>,
+    reporter: &RelayReporter,
+) -> bool {
+    let cmd = commands
+        .into_iter()
+        .filter_map(|c| match c {
+            SyncCommand::Direct(cmd) => Some(cmd),
+            SyncCommand::Relay(_) => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" && ");
+    let _ = reporter
+        .log
+        .send((item_id, format!("[sync] Running: {}", cmd)));
+
+    let (output_tx, mut output_rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+
+    let fwd_tx = reporter.log.clone();
+    let fwd_task = tokio::spawn(async move {
+        while let Some(data) = output_rx.recv().await {
+            let text = String::from_utf8_lossy(&data);
+            for line in text.lines() {
+                let _ = fwd_tx.send((item_id, line.to_string()));
+            }
+        }
+    });
+
+    let (_cancel_tx, cancel_rx) = tokio::sync::mpsc::channel::<()>(1);
+    let result = source.exec_cancellable(&cmd, output_tx, cancel_rx).await;
+    let _ = fwd_task.await;
+
+    match result {
+        Ok(_) => true,
+        Err(e) => {
+            let _ = reporter.log.send((item_id, format!("[sync] Error: {}", e)));
+            false
+        }
+    }
+}
+
+/// Forward a relay's events into the reporter; byte counts are offset by
+/// what earlier relays of the same item already moved.
+pub(super) fn forward_relay_events(
+    item_id: Uuid,
+    offset: u64,
+    reporter: &RelayReporter,
+) -> (
+    tokio::sync::mpsc::UnboundedSender<RelayEvent>,
+    tokio::task::JoinHandle<()>,
+) {
+    let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel::<RelayEvent>();
+    let fwd_log = reporter.log.clone();
+    let fwd_bytes = reporter.bytes.clone();
+    let task = tokio::spawn(async move {
+        while let Some(event) = events_rx.recv().await {
+            match event {
+                RelayEvent::Transferred(bytes) => {
+                    let _ = fwd_bytes.send((item_id, ItemProgress::Transferred(offset + bytes)));
+                }
+                RelayEvent::Output { data, .. } => {
+                    let text = String::from_utf8_lossy(&data);
+                    for line in text.lines() {
+                        let _ = fwd_log.send((item_id, line.to_string()));
+                    }
+                }
+            }
+        }
+    });
+    (events_tx, task)
+}
+
+/// Run the relays of one sync item in order, stopping at the first failure.
+/// Byte counts accumulate across relays so the item's progress stays
+/// monotonic.
+pub(super) async fn relay_item(
+    item_id: Uuid,
+    source: &SshSession,
+    dest: &SshSession,
+    relays: &[RelayCommands],
+    options: &SyncOptions,
+    shutdown_rx: &mut tokio::sync::mpsc::Receiver<()>,
+    reporter: &RelayReporter,
+) -> bool {
+    let log = |line: String| {
+        let _ = reporter.log.send((item_id, line));
+    };
+
+    let mut total_files = 0u32;
+    let mut total_bytes = Some(0u64);
+    for relay in relays {
+        let size = match &relay.size_probe {
+            Some(probe) => source
+                .exec(probe)
+                .await
+                .ok()
+                .and_then(|r| discovery::parse_relay_size(&r.stdout_string())),
+            None => None,
+        };
+        match (size, total_bytes) {
+            (Some((files, bytes)), Some(total)) => {
+                total_files += files;
+                total_bytes = Some(total + bytes);
+            }
+            _ => total_bytes = None,
+        }
+    }
+    if let Some(bytes) = total_bytes {
+        let _ = reporter.bytes.send((
+            item_id,
+            ItemProgress::Total {
+                files: total_files,
+                bytes,
+            },
+        ));
+    }
+
+    if options.dry_run {
+        match total_bytes {
+            Some(bytes) => log(format!(
+                "[sync] Dry run: would relay {} files ({} bytes)",
+                total_files, bytes
+            )),
+            None => log("[sync] Dry run: would relay the database dump".to_string()),
+        }
+        return true;
+    }
+    if options.delete_extra {
+        log("[sync] Delete extra files is not applied in relay mode".to_string());
+    }
+
+    let mut offset = 0u64;
+    for relay in relays {
+        log(format!(
+            "[sync] Relaying: {} | {}",
+            relay.source, relay.dest
+        ));
+
+        let (events_tx, fwd_task) = forward_relay_events(item_id, offset, reporter);
+
+        let result = source
+            .relay_to(
+                &relay.source,
+                dest,
+                &relay.dest,
+                events_tx,
+                shutdown_rx,
+                options.bandwidth_limit,
+            )
+            .await;
+        let _ = fwd_task.await;
+
+        match result {
+            Ok(outcome) if outcome.success() => offset += outcome.bytes,
+            Ok(outcome) if outcome.cancelled => {
+                log("[sync] Relay cancelled".to_string());
+                return false;
+            }
+            Ok(outcome) => {
+                log(format!(
+                    "[sync] Relay failed (source exit {:?}, destination exit {:?})",
+                    outcome.source_exit, outcome.dest_exit
+                ));
+                return false;
+            }
+            Err(e) => {
+                log(format!("[sync] Error: {}", e));
+                return false;
+            }
+        }
+    }
+    true
+}
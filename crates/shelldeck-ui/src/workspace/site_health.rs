@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use gpui::*;
+use shelldeck_core::config::activity::{ActivityEntry, ActivityKind};
+use shelldeck_core::config::site_health::SiteHealthHistory;
+use shelldeck_core::models::site_health::{health_alerts, probe_site, HealthAlert, HealthSample};
+use uuid::Uuid;
+
+use crate::t;
+
+use super::{TrayNotification, Workspace};
+
+/// How often the monitor looks for sites due a probe. Each site is probed
+/// at the configured interval, not at every tick.
+const MONITOR_TICK: Duration = Duration::from_secs(30);
+
+/// Floor on the configured interval, so a typo cannot hammer a site.
+const MIN_PROBE_INTERVAL_SECS: u64 = 30;
+
+impl Workspace {
+    /// Load the probe history and probe monitored sites in the background
+    /// for as long as the workspace lives.
+    pub fn start_site_monitor(&mut self, cx: &mut Context<Self>) {
+        if self._site_monitor_task.is_some() {
+            return;
+        }
+        self.site_health = SiteHealthHistory::load().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load site health history: {}", e);
+            SiteHealthHistory::default()
+        });
+        let history = self.site_health.sites.clone();
+        self.sites.update(cx, |view, cx| {
+            for (site_id, samples) in history {
+                view.set_health_samples(site_id, samples);
+            }
+            cx.notify();
+        });
+        self.probe_due_sites(cx);
+        self._site_monitor_task = Some(cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor().timer(MONITOR_TICK).await;
+            if this.update(cx, |ws, cx| ws.probe_due_sites(cx)).is_err() {
+                break;
+            }
+        }));
+    }
+
+    /// Probe every monitored site whose last probe is older than the
+    /// configured interval.
+    fn probe_due_sites(&mut self, cx: &mut Context<Self>) {
+        let interval_secs = self
+            .app_config
+            .site_health
+            .interval_secs
+            .max(MIN_PROBE_INTERVAL_SECS);
+        let interval = chrono::Duration::seconds(interval_secs as i64);
+        let now = Utc::now();
+        let due: Vec<Uuid> = self
+            .store
+            .managed_sites
+            .iter()
+            .filter(|site| site.monitor.enabled)
+            .filter(|site| {
+                self.site_health
+                    .latest(site.id)
+                    .is_none_or(|last| now - last.checked_at >= interval)
+            })
+            .map(|site| site.id)
+            .collect();
+        for site_id in due {
+            self.probe_site_now(site_id, cx);
+        }
+    }
+
+    /// Probe one site right away, unless a probe of it is already running.
+    pub(super) fn probe_site_now(&mut self, site_id: Uuid, cx: &mut Context<Self>) {
+        if self.site_probes.contains(&site_id) {
+            return;
+        }
+        let Some(site) = self.store.managed_sites.iter().find(|s| s.id == site_id) else {
+            return;
+        };
+        let Some(url) = site.monitor.target_url(site) else {
+            return;
+        };
+        let keyword = site.monitor.keyword.clone();
+        let timeout = Duration::from_secs(self.app_config.site_health.timeout_secs.max(1));
+
+        let (done_tx, done_rx) = std::sync::mpsc::channel::<HealthSample>();
+        let spawn_result = std::thread::Builder::new()
+            .name(format!("site-probe-{}", site_id))
+            .spawn(move || {
+                let _ = done_tx.send(probe_site(&url, keyword.as_deref(), timeout));
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn site probe thread: {}", e);
+            return;
+        }
+        self.site_probes.insert(site_id);
+
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let sample = loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(250))
+                    .await;
+                match done_rx.try_recv() {
+                    Ok(sample) => break Some(sample),
+                    Err(std::sync::mpsc::TryRecvError::Empty) => continue,
+                    Err(std::sync::mpsc::TryRecvError::Disconnected) => break None,
+                }
+            };
+            let _ = this.update(cx, |ws, cx| {
+                ws.site_probes.remove(&site_id);
+                if let Some(sample) = sample {
+                    ws.record_site_probe(site_id, sample, cx);
+                }
+            });
+        })
+        .detach();
+    }
+
+    /// Store a finished probe, update the site's status and raise alerts.
+    pub(super) fn record_site_probe(
+        &mut self,
+        site_id: Uuid,
+        sample: HealthSample,
+        cx: &mut Context<Self>,
+    ) {
+        // The site may have been removed while the probe ran.
+        let Some(site) = self
+            .store
+            .managed_sites
+            .iter_mut()
+            .find(|s| s.id == site_id)
+        else {
+            return;
+        };
+        site.last_checked = Some(sample.checked_at);
+        site.status = sample.site_status();
+        let name = site.name().to_string();
+        let connection_id = site.connection_id;
+
+        let alerts = health_alerts(
+            self.site_health.latest(site_id),
+            &sample,
+            self.app_config.site_health.cert_warning_days,
+        );
+        let status = sample.site_status();
+        self.site_health.record(site_id, sample);
+        let live: Vec<Uuid> = self
+            .store
+            .managed_sites
+            .iter()
+            .filter(|s| s.monitor.enabled)
+            .map(|s| s.id)
+            .collect();
+        self.site_health.retain_sites(&live);
+        if let Err(e) = self.site_health.save() {
+            tracing::warn!("Failed to save site health history: {}", e);
+        }
+
+        let samples = self
+            .site_health
+            .sites
+            .get(&site_id)
+            .cloned()
+            .unwrap_or_default();
+        let checked_at = samples.last().map(|s| s.checked_at);
+        let previous = self.sites.update(cx, |view, cx| {
+            view.set_health_samples(site_id, samples);
+            let site = view.sites.iter_mut().find(|s| s.id == site_id)?;
+            site.last_checked = checked_at;
+            let previous = std::mem::replace(&mut site.status, status.clone());
+            cx.notify();
+            Some(previous)
+        });
+        if let Some(previous) = previous {
+            self.site_status_changed(site_id, connection_id, &previous, &status, cx);
+        }
+
+        for alert in alerts {
+            let (message, notification) = match alert {
+                HealthAlert::Down(failure) => (
+                    t!(
+                        "activity.site_health.down",
+                        name = name.as_str(),
+                        reason = failure.message()
+                    )
+                    .to_string(),
+                    TrayNotification::SiteDown {
+                        name: name.clone(),
+                        reason: failure.message(),
+                    },
+                ),
+                HealthAlert::CertificateExpiring { days } => (
+                    t!(
+                        "activity.site_health.cert_expiring",
+                        name = name.as_str(),
+                        days = days
+                    )
+                    .to_string(),
+                    TrayNotification::CertificateExpiring {
+                        name: name.clone(),
+                        days,
+                    },
+                ),
+            };
+            tracing::warn!("{}", message);
+            self.add_activity_entry(
+                ActivityEntry::new(ActivityKind::Site, message)
+                    .with_target(site_id.to_string(), name.clone()),
+                cx,
+            );
+            if self.app_config.tray.notify_site_health {
+                self.emit_tray_notification(notification);
+            }
+        }
+    }
+}
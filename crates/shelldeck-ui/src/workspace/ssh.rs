@@ -1,10 +1,12 @@
 use gpui::prelude::*;
 use gpui::*;
+use shelldeck_core::config::activity::{ActivityAction, ActivityEntry, ActivityKind};
 use shelldeck_core::models::connection::{Connection, ConnectionStatus};
 use shelldeck_ssh::client::SshClient;
 use shelldeck_terminal::session::{SessionState, TerminalSession};
 use uuid::Uuid;
 
+use crate::ssh_doctor::{SshDoctorEvent, SshDoctorView};
 use crate::t;
 use crate::terminal_view::SplitDirection;
 use crate::toast::ToastLevel;
@@ -39,7 +41,9 @@ impl Workspace {
         status: ConnectionStatus,
         cx: &mut Context<Self>,
     ) {
+        let mut trigger = None;
         if let Some(conn) = self.connections.iter_mut().find(|c| c.id == conn_id) {
+            trigger = super::scheduler::connection_transition_event(conn_id, &conn.status, &status);
             conn.status = status;
         }
         let conns = self.connections.clone();
@@ -55,6 +59,9 @@ impl Workspace {
         });
         self.update_dashboard_stats(cx);
         self.publish_tray_state(cx);
+        if let Some(event) = trigger {
+            self.fire_script_triggers(event, cx);
+        }
     }
 
     /// Initiate an SSH connection to `connection`.
@@ -271,6 +278,12 @@ impl Workspace {
                                 ConnectionStatus::Error(msg.clone()),
                                 cx,
                             );
+                            ws.add_activity_entry(
+                                ActivityEntry::new(ActivityKind::Error, msg.clone())
+                                    .with_target(conn_id.to_string(), title.clone())
+                                    .with_action(ActivityAction::DiagnoseConnection),
+                                cx,
+                            );
                             ws.show_toast(msg, ToastLevel::Error, cx);
                         }
                         SshLifecycleEvent::Ended(end) => {
@@ -479,6 +492,38 @@ impl Workspace {
             cx,
         );
     }
+
+    /// Open the SSH doctor on a connection; it starts a run right away.
+    pub fn show_ssh_doctor(&mut self, conn_id: Uuid, cx: &mut Context<Self>) {
+        if !self.enter_dev_mode(cx) {
+            return;
+        }
+        let Some(conn) = self.connections.iter().find(|c| c.id == conn_id).cloned() else {
+            self.show_toast(
+                t!("toast.deeplink.connection_not_found").to_string(),
+                ToastLevel::Warning,
+                cx,
+            );
+            return;
+        };
+        tracing::info!("Diagnosing connection {}", conn.display_name());
+        let view = cx.new(|view_cx| SshDoctorView::new(conn, view_cx));
+
+        let sub = cx.subscribe(
+            &view,
+            |this, _view, event: &SshDoctorEvent, cx| match event {
+                SshDoctorEvent::Close => {
+                    this.ssh_doctor = None;
+                    this._ssh_doctor_sub = None;
+                    cx.notify();
+                }
+            },
+        );
+
+        self.ssh_doctor = Some(view);
+        self._ssh_doctor_sub = Some(sub);
+        cx.notify();
+    }
 }
 
 #[cfg(test)]
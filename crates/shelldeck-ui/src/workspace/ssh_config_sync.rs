@@ -0,0 +1,202 @@
+use gpui::*;
+use shelldeck_core::config::activity::ActivityKind;
+use shelldeck_core::config::ssh_config::{self, SshConfigImport};
+use shelldeck_core::config::ssh_config_write;
+use shelldeck_core::config::ConfigWatcher;
+use shelldeck_core::models::connection::{Connection, ConnectionSource};
+use shelldeck_core::models::port_forward::PortForward;
+use shelldeck_core::models::tunnel_health::TunnelState;
+
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::Workspace;
+
+impl Workspace {
+    /// Follow ~/.ssh/config and the files it includes, reconciling hosts
+    /// and their forwards whenever they change on disk.
+    pub fn start_ssh_config_watch(&mut self, cx: &mut Context<Self>) {
+        if self._ssh_config_watcher.is_some() {
+            return;
+        }
+        let (changes_tx, mut changes_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = ConfigWatcher::new();
+        if let Err(e) = watcher.start(move || {
+            let _ = changes_tx.send(());
+        }) {
+            tracing::warn!("Not following SSH config changes: {}", e);
+            return;
+        }
+        self._ssh_config_watcher = Some(watcher);
+        self._ssh_config_task = Some(cx.spawn(async move |this, cx: &mut AsyncApp| {
+            while changes_rx.recv().await.is_some() {
+                let import = cx
+                    .background_executor()
+                    .spawn(async { ssh_config::import_ssh_config() })
+                    .await;
+                let import = match import {
+                    Ok(import) => import,
+                    Err(e) => {
+                        // A half-edited file must not drop hosts or forwards.
+                        tracing::warn!("Ignoring SSH config change: {}", e);
+                        continue;
+                    }
+                };
+                if this
+                    .update(cx, |ws, cx| ws.apply_ssh_config(import, cx))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Replace the SSH-config hosts and forwards with a fresh import. Other
+    /// connections stay unless an imported host now takes their alias, and
+    /// forwards whose line is gone are stopped.
+    fn apply_ssh_config(&mut self, import: SshConfigImport, cx: &mut Context<Self>) {
+        let removed = match self.store.sync_ssh_config_forwards(&import.forwards) {
+            Ok(removed) => removed,
+            Err(e) => {
+                tracing::error!("Failed to save SSH config forwards: {}", e);
+                Vec::new()
+            }
+        };
+        for forward_id in &removed {
+            if let Some(active_tunnel) = self.active_tunnels.remove(forward_id) {
+                active_tunnel.tunnel_handle.stop();
+                self.record_tunnel_state(*forward_id, TunnelState::Stopped, None, cx);
+            }
+        }
+
+        let changed = hosts_changed(&self.connections, &import.connections) || !removed.is_empty();
+        let mut merged = import.connections;
+        for conn in &self.connections {
+            if conn.source != ConnectionSource::SshConfig
+                && !merged.iter().any(|c| c.alias == conn.alias)
+            {
+                merged.push(conn.clone());
+            }
+        }
+        // Preserve live status from the current in-memory connections.
+        for m in merged.iter_mut() {
+            if let Some(cur) = self.connections.iter().find(|c| c.id == m.id) {
+                m.status = cur.status.clone();
+            }
+        }
+        self.connections = merged;
+
+        let forwards = self.store.port_forwards.clone();
+        self.port_forwards.update(cx, |pf, cx| {
+            let previous = std::mem::take(&mut pf.forwards);
+            pf.forwards = forwards
+                .into_iter()
+                .map(|mut forward| {
+                    if let Some(live) = previous.iter().find(|p| p.id == forward.id) {
+                        forward.status = live.status;
+                        forward.bytes_sent = live.bytes_sent;
+                        forward.bytes_received = live.bytes_received;
+                    }
+                    forward
+                })
+                .collect();
+            for forward_id in &removed {
+                pf.probes.remove(forward_id);
+                pf.bound_addrs.remove(forward_id);
+            }
+            cx.notify();
+        });
+        self.publish_connections(cx);
+
+        if changed {
+            tracing::info!("SSH config reloaded");
+            self.add_activity(
+                t!("activity.ssh_config_reloaded").to_string(),
+                ActivityKind::Connection,
+                cx,
+            );
+            self.show_toast(
+                t!("toast.ssh_config.reloaded").to_string(),
+                ToastLevel::Info,
+                cx,
+            );
+        }
+    }
+
+    /// Push the connection list to every view that shows it.
+    pub(super) fn publish_connections(&mut self, cx: &mut Context<Self>) {
+        let conns = self.connections.clone();
+        self.sidebar.update(cx, |sidebar, cx| {
+            sidebar.set_connections(conns.clone());
+            cx.notify();
+        });
+        self.server_sync.update(cx, |view, cx| {
+            view.set_connections(conns.clone(), cx);
+        });
+        self.sites.update(cx, |view, _| {
+            view.set_connections(conns);
+        });
+        self.update_dashboard_stats(cx);
+        cx.notify();
+    }
+
+    /// Write a connection read from the SSH config back into the `Host`
+    /// block named `previous_alias`, with its linked forwards.
+    pub(super) fn write_ssh_config_host(
+        &mut self,
+        previous_alias: &str,
+        connection: &Connection,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(root) = ssh_config::default_config_path() else {
+            return;
+        };
+        let forwards: Vec<PortForward> = self
+            .store
+            .port_forwards
+            .iter()
+            .filter(|f| {
+                f.connection_id == connection.id && connection.auto_forwards.contains(&f.id)
+            })
+            .cloned()
+            .collect();
+        match ssh_config_write::write_connection(&root, previous_alias, connection, &forwards) {
+            Ok(path) => {
+                tracing::info!("Saved {} to {}", connection.alias, path.display());
+            }
+            Err(e) => {
+                tracing::error!("Failed to write SSH config: {}", e);
+                self.show_toast(
+                    t!("toast.ssh_config.write_failed", error = e.to_string()).to_string(),
+                    ToastLevel::Error,
+                    cx,
+                );
+            }
+        }
+    }
+}
+
+/// Whether an import differs from the SSH-config hosts on screen.
+fn hosts_changed(current: &[Connection], imported: &[Connection]) -> bool {
+    let current: Vec<&Connection> = current
+        .iter()
+        .filter(|c| c.source == ConnectionSource::SshConfig)
+        .collect();
+    current.len() != imported.len()
+        || imported
+            .iter()
+            .any(|i| !current.iter().any(|c| same_host(c, i)))
+}
+
+fn same_host(a: &Connection, b: &Connection) -> bool {
+    a.id == b.id
+        && a.alias == b.alias
+        && a.hostname == b.hostname
+        && a.port == b.port
+        && a.user == b.user
+        && a.identity_file == b.identity_file
+        && a.proxy_jump == b.proxy_jump
+        && a.forward_agent == b.forward_agent
+        && a.auto_forwards == b.auto_forwards
+}
@@ -0,0 +1,386 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use gpui::*;
+use shelldeck_core::config::activity::ActivityKind;
+use shelldeck_core::config::keychain;
+use shelldeck_core::config::ssh_keys::{
+    authorize_command, generate_key, revoke_command, rotated_key_path, AuthorizedKeysChange, SshKey,
+};
+use shelldeck_core::models::connection::{Connection, ConnectionSource};
+use shelldeck_ssh::client::SshClient;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use crate::key_manager::{
+    HostKeyResult, HostKeyStatus, KeyManagerEvent, KeyManagerView, KeyOperation, KeyReport,
+};
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::Workspace;
+
+/// Hosts worked on at once during a key run.
+const KEY_RUN_CONCURRENCY: usize = 4;
+
+enum KeyRunEvent {
+    /// The replacement key of a rotation exists on disk.
+    Generated(SshKey),
+    Host(Uuid, HostKeyStatus),
+}
+
+impl Workspace {
+    pub fn show_key_manager(&mut self, cx: &mut Context<Self>) {
+        if !self.enter_dev_mode(cx) {
+            return;
+        }
+        let connections = self.connections.clone();
+        let view = cx.new(|view_cx| KeyManagerView::new(connections, view_cx));
+
+        let sub = cx.subscribe(
+            &view,
+            |this, _view, event: &KeyManagerEvent, cx| match event {
+                KeyManagerEvent::Run {
+                    operation,
+                    key,
+                    hosts,
+                } => this.run_key_operation(*operation, key.clone(), hosts, cx),
+                KeyManagerEvent::Close => {
+                    this.key_manager = None;
+                    this._key_manager_sub = None;
+                    cx.notify();
+                }
+            },
+        );
+
+        self.key_manager = Some(view);
+        self._key_manager_sub = Some(sub);
+        cx.notify();
+    }
+
+    /// Deploy, revoke or rotate `key` on `host_ids`. A rotation only moves a
+    /// connection to the new key once the new key has signed in and removed
+    /// the old one, so a host that fails half-way keeps working.
+    fn run_key_operation(
+        &mut self,
+        operation: KeyOperation,
+        key: SshKey,
+        host_ids: &[Uuid],
+        cx: &mut Context<Self>,
+    ) {
+        let hosts: Vec<Connection> = host_ids
+            .iter()
+            .filter_map(|id| self.connections.iter().find(|c| c.id == *id).cloned())
+            .collect();
+        if hosts.is_empty() {
+            return;
+        }
+        let key_name = key.name();
+        let host_count = hosts.len();
+        tracing::info!(
+            "Running {:?} for key {} on {} hosts",
+            operation,
+            key_name,
+            host_count
+        );
+
+        let report = KeyReport {
+            operation,
+            key_name: key_name.clone(),
+            hosts: hosts
+                .iter()
+                .map(|c| HostKeyResult {
+                    connection_id: c.id,
+                    alias: c.display_name().to_string(),
+                    status: HostKeyStatus::Pending,
+                })
+                .collect(),
+        };
+        if let Some(view) = &self.key_manager {
+            view.update(cx, |view, cx| view.start_report(report, cx));
+        }
+
+        let (event_tx, event_rx) = std::sync::mpsc::channel::<KeyRunEvent>();
+        let spawned = std::thread::Builder::new()
+            .name(format!("ssh-key-{}", operation_name(operation)))
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        tracing::error!("Failed to create async runtime: {}", e);
+                        return;
+                    }
+                };
+                rt.block_on(run_key_hosts(operation, key, hosts, event_tx));
+            });
+        if let Err(e) = spawned {
+            tracing::error!("Failed to spawn key run thread: {}", e);
+            if let Some(view) = &self.key_manager {
+                view.update(cx, |view, cx| view.finish_report(cx));
+            }
+            self.show_toast(
+                t!("toast.ssh_key.run_failed", error = e.to_string()).to_string(),
+                ToastLevel::Error,
+                cx,
+            );
+            return;
+        }
+
+        // UI poller: apply host results until the worker exits. The view may
+        // be closed mid-run; identities are still switched and summarised.
+        cx.spawn(async move |this, cx: &mut AsyncApp| {
+            let mut new_key: Option<SshKey> = None;
+            let (mut succeeded, mut failed) = (0usize, 0usize);
+            loop {
+                cx.background_executor()
+                    .timer(Duration::from_millis(50))
+                    .await;
+
+                let mut events = Vec::new();
+                let mut finished = false;
+                loop {
+                    match event_rx.try_recv() {
+                        Ok(event) => events.push(event),
+                        Err(std::sync::mpsc::TryRecvError::Empty) => break,
+                        Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                            finished = true;
+                            break;
+                        }
+                    }
+                }
+
+                for event in events {
+                    match event {
+                        KeyRunEvent::Generated(key) => {
+                            let _ = this.update(cx, |ws, cx| {
+                                if let Some(view) = &ws.key_manager {
+                                    view.update(cx, |view, cx| view.reload_keys(None, cx));
+                                }
+                            });
+                            new_key = Some(key);
+                        }
+                        KeyRunEvent::Host(id, status) => {
+                            match status {
+                                HostKeyStatus::Done(_) | HostKeyStatus::Rotated => succeeded += 1,
+                                HostKeyStatus::Failed(_) => failed += 1,
+                                _ => {}
+                            }
+                            let rotated = status == HostKeyStatus::Rotated;
+                            let replacement = new_key.clone();
+                            let _ = this.update(cx, |ws, cx| {
+                                if let (true, Some(key)) = (rotated, replacement) {
+                                    ws.switch_connection_key(id, &key, cx);
+                                }
+                                if let Some(view) = &ws.key_manager {
+                                    view.update(cx, |view, cx| {
+                                        view.set_host_status(id, status, cx)
+                                    });
+                                }
+                            });
+                        }
+                    }
+                }
+                if !finished {
+                    continue;
+                }
+
+                let _ = this.update(cx, |ws, cx| {
+                    if let Some(view) = &ws.key_manager {
+                        view.update(cx, |view, cx| view.finish_report(cx));
+                    }
+                    let message = match operation {
+                        KeyOperation::Deploy => t!(
+                            "activity.ssh_key.deployed",
+                            key = key_name.as_str(),
+                            succeeded = succeeded,
+                            total = host_count
+                        ),
+                        KeyOperation::Rotate => t!(
+                            "activity.ssh_key.rotated",
+                            key = key_name.as_str(),
+                            succeeded = succeeded,
+                            total = host_count
+                        ),
+                        KeyOperation::Revoke => t!(
+                            "activity.ssh_key.revoked",
+                            key = key_name.as_str(),
+                            succeeded = succeeded,
+                            total = host_count
+                        ),
+                    }
+                    .to_string();
+                    ws.add_activity(message.clone(), ActivityKind::Connection, cx);
+                    ws.show_toast(
+                        message,
+                        if failed == 0 && succeeded == host_count {
+                            ToastLevel::Success
+                        } else {
+                            ToastLevel::Error
+                        },
+                        cx,
+                    );
+                });
+                break;
+            }
+        })
+        .detach();
+    }
+
+    /// Point a connection at a rotated key, in its Host block or the store.
+    fn switch_connection_key(&mut self, id: Uuid, key: &SshKey, cx: &mut Context<Self>) {
+        let Some(idx) = self.connections.iter().position(|c| c.id == id) else {
+            return;
+        };
+        let previous_alias = self.connections[idx].alias.clone();
+        self.connections[idx].identity_file = Some(key.private_path.clone());
+        let conn = self.connections[idx].clone();
+        if conn.source == ConnectionSource::SshConfig {
+            self.write_ssh_config_host(&previous_alias, &conn, cx);
+        } else if let Err(e) = self.store.update_connection(conn) {
+            tracing::error!("Failed to save connection store: {}", e);
+            self.show_toast(
+                t!("toast.connection.save_failed", error = e.to_string()).to_string(),
+                ToastLevel::Error,
+                cx,
+            );
+        }
+        self.publish_connections(cx);
+        if let Some(view) = &self.key_manager {
+            let connections = self.connections.clone();
+            view.update(cx, |view, cx| view.set_connections(connections, cx));
+        }
+    }
+}
+
+fn operation_name(operation: KeyOperation) -> &'static str {
+    match operation {
+        KeyOperation::Deploy => "deploy",
+        KeyOperation::Rotate => "rotate",
+        KeyOperation::Revoke => "revoke",
+    }
+}
+
+/// Create the replacement for `old` next to it, with the same passphrase
+/// and comment.
+fn generate_replacement(old: &SshKey) -> Result<SshKey, String> {
+    let stamp = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let path = rotated_key_path(&old.private_path, &stamp, |p| p.exists());
+    let passphrase = keychain::get_key_passphrase(&old.private_path.to_string_lossy())
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+    generate_key(&path, old.algorithm(), &passphrase, &old.public.comment)
+        .map_err(|e| e.to_string())
+}
+
+async fn run_key_hosts(
+    operation: KeyOperation,
+    key: SshKey,
+    hosts: Vec<Connection>,
+    events: std::sync::mpsc::Sender<KeyRunEvent>,
+) {
+    let new_key = if operation == KeyOperation::Rotate {
+        match generate_replacement(&key) {
+            Ok(new_key) => {
+                let _ = events.send(KeyRunEvent::Generated(new_key.clone()));
+                Some(new_key)
+            }
+            Err(e) => {
+                for conn in &hosts {
+                    let _ =
+                        events.send(KeyRunEvent::Host(conn.id, HostKeyStatus::Failed(e.clone())));
+                }
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    let semaphore = Arc::new(Semaphore::new(KEY_RUN_CONCURRENCY));
+    let mut tasks = tokio::task::JoinSet::new();
+    for conn in hosts {
+        let semaphore = semaphore.clone();
+        let events = events.clone();
+        let key = key.clone();
+        let new_key = new_key.clone();
+        tasks.spawn(async move {
+            let Ok(_permit) = semaphore.acquire_owned().await else {
+                return;
+            };
+            let id = conn.id;
+            let _ = events.send(KeyRunEvent::Host(id, HostKeyStatus::Running));
+            let status = match run_key_host(operation, &key, new_key.as_ref(), conn).await {
+                Ok(status) => status,
+                Err(e) => HostKeyStatus::Failed(e),
+            };
+            let _ = events.send(KeyRunEvent::Host(id, status));
+        });
+    }
+    while tasks.join_next().await.is_some() {}
+}
+
+async fn run_key_host(
+    operation: KeyOperation,
+    key: &SshKey,
+    new_key: Option<&SshKey>,
+    conn: Connection,
+) -> Result<HostKeyStatus, String> {
+    match (operation, new_key) {
+        (KeyOperation::Deploy, _) => {
+            let change = exec_key_command(&conn, &authorize_command(&key.public)).await?;
+            Ok(HostKeyStatus::Done(change))
+        }
+        (KeyOperation::Revoke, _) => {
+            let change = exec_key_command(&conn, &revoke_command(&key.public)).await?;
+            Ok(HostKeyStatus::Done(change))
+        }
+        (KeyOperation::Rotate, Some(new_key)) => {
+            exec_key_command(&conn, &authorize_command(&new_key.public)).await?;
+            // Sign in with the new key before taking the old one away.
+            let rotated = Connection {
+                identity_file: Some(new_key.private_path.clone()),
+                ..conn
+            };
+            exec_key_command(&rotated, &revoke_command(&key.public))
+                .await
+                .map_err(|e| t!("key_manager.error.new_key", error = e.as_str()).to_string())?;
+            Ok(HostKeyStatus::Rotated)
+        }
+        (KeyOperation::Rotate, None) => Err(t!("key_manager.error.no_new_key").to_string()),
+    }
+}
+
+async fn exec_key_command(
+    conn: &Connection,
+    command: &str,
+) -> Result<AuthorizedKeysChange, String> {
+    let session = SshClient::new()
+        .connect(conn)
+        .await
+        .map_err(|e| format!("SSH connection failed: {}", e))?;
+    let result = session.exec(command).await.map_err(|e| e.to_string())?;
+    let change = AuthorizedKeysChange::from_output(&result.stdout_string());
+    match change {
+        Some(change) if result.success() => Ok(change),
+        _ => {
+            let stderr = result.stderr_string();
+            let stderr = stderr.trim();
+            Err(if stderr.is_empty() {
+                t!(
+                    "key_manager.error.exit",
+                    code = result
+                        .exit_code
+                        .map(|c| c.to_string())
+                        .unwrap_or_else(|| "?".to_string())
+                )
+                .to_string()
+            } else {
+                stderr.to_string()
+            })
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use gpui::*;
+use shelldeck_core::ai::ai_line_diff;
+use shelldeck_core::models::server_sync::{SyncOptions, SyncProfile};
+use shelldeck_core::models::sync_plan::{self, ItemPlan, PlanSection, PlanTarget};
+use shelldeck_core::util::shell_escape;
+use shelldeck_ssh::client::SshClient;
+use shelldeck_ssh::session::SshSession;
+use uuid::Uuid;
+
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::Workspace;
+
+impl Workspace {
+    /// List both sides of every enabled item and hand the resulting plan to
+    /// the sync wizard's review step. `request_id` lets the view drop plans
+    /// for a review it has since left.
+    pub(super) fn plan_sync_operation(
+        &mut self,
+        request_id: Uuid,
+        profile: SyncProfile,
+        cx: &mut Context<Self>,
+    ) {
+        let conn = |id: Uuid| self.connections.iter().find(|c| c.id == id).cloned();
+        let (source_conn, dest_conn) = match (
+            conn(profile.source_connection_id),
+            conn(profile.dest_connection_id),
+        ) {
+            (Some(s), Some(d)) => (s, d),
+            _ => {
+                let error = t!("sync.plan.no_connection").to_string();
+                let plans = unplanned(&profile, &error);
+                self.server_sync.update(cx, |view, cx| {
+                    view.set_sync_plans(request_id, plans);
+                    cx.notify();
+                });
+                return;
+            }
+        };
+
+        let (result_tx, result_rx) = std::sync::mpsc::channel::<Vec<ItemPlan>>();
+        let spawn_result = std::thread::Builder::new()
+            .name("sync-plan".to_string())
+            .spawn(move || {
+                let rt = match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt,
+                    Err(e) => {
+                        let _ = result_tx.send(unplanned(&profile, &e.to_string()));
+                        return;
+                    }
+                };
+
+                rt.block_on(async move {
+                    let client = SshClient::new();
+                    let sessions = async {
+                        let source = client.connect(&source_conn).await?;
+                        let dest = client.connect(&dest_conn).await?;
+                        shelldeck_ssh::Result::Ok((source, dest))
+                    };
+                    let (source, dest) = match sessions.await {
+                        Ok(s) => s,
+                        Err(e) => {
+                            let _ = result_tx.send(unplanned(&profile, &e.to_string()));
+                            return;
+                        }
+                    };
+
+                    let mut plans = Vec::new();
+                    for item in profile.items.iter().filter(|i| i.enabled) {
+                        let mut plan = ItemPlan {
+                            item_id: item.id,
+                            ..Default::default()
+                        };
+                        let excludes = sync_plan::item_excludes(&item.kind);
+                        for target in sync_plan::plan_targets(&item.kind) {
+                            match plan_section(&source, &dest, target, &profile.options, excludes)
+                                .await
+                            {
+                                Ok(section) => plan.sections.push(section),
+                                Err(e) => {
+                                    plan.error = Some(e.to_string());
+                                    break;
+                                }
+                            }
+                        }
+                        plans.push(plan);
+                    }
+                    let _ = result_tx.send(plans);
+                });
+            });
+        if let Err(e) = spawn_result {
+            tracing::error!("Failed to spawn sync plan thread: {}", e);
+            self.show_toast(
+                t!("toast.sync.plan_failed", error = e.to_string()).to_string(),
+                ToastLevel::Error,
+                cx,
+            );
+            return;
+        }
+
+        let sync_handle = self.server_sync.downgrade();
+        cx.spawn(async move |_this, cx: &mut AsyncApp| loop {
+            cx.background_executor()
+                .timer(std::time::Duration::from_millis(100))
+                .await;
+            match result_rx.try_recv() {
+                Ok(plans) => {
+                    let _ = sync_handle.update(cx, |view, cx| {
+                        view.set_sync_plans(request_id, plans);
+                        cx.notify();
+                    });
+                    break;
+                }
+                Err(std::sync::mpsc::TryRecvError::Empty) => {}
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => break,
+            }
+        })
+        .detach();
+    }
+}
+
+/// A plan that only carries `error` for every enabled item.
+fn unplanned(profile: &SyncProfile, error: &str) -> Vec<ItemPlan> {
+    profile
+        .items
+        .iter()
+        .filter(|i| i.enabled)
+        .map(|item| ItemPlan {
+            item_id: item.id,
+            sections: Vec::new(),
+            error: Some(error.to_string()),
+        })
+        .collect()
+}
+
+async fn plan_section(
+    source: &SshSession,
+    dest: &SshSession,
+    target: PlanTarget,
+    options: &SyncOptions,
+    excludes: &[String],
+) -> shelldeck_ssh::Result<PlanSection> {
+    let (source_cmd, dest_cmd, excludes) = match &target {
+        PlanTarget::Tree {
+            source_root,
+            dest_root,
+        } => (
+            sync_plan::manifest_command(source_root, options.checksum),
+            sync_plan::manifest_command(dest_root, options.checksum),
+            excludes,
+        ),
+        PlanTarget::File { path } => {
+            let cmd = sync_plan::file_manifest_command(path, options.checksum);
+            (cmd.clone(), cmd, &[][..])
+        }
+    };
+    let source_files = sync_plan::parse_manifest(&source.exec(&source_cmd).await?.stdout_string());
+    let dest_files = sync_plan::parse_manifest(&dest.exec(&dest_cmd).await?.stdout_string());
+
+    let mut entries = sync_plan::plan_entries(&source_files, &dest_files, options, excludes);
+    for entry in entries
+        .iter_mut()
+        .filter(|e| sync_plan::wants_diff(&target, e))
+    {
+        let (source_path, dest_path) = target.entry_paths(&entry.path);
+        let new = source
+            .exec(&format!("cat {}", shell_escape(&source_path)))
+            .await?;
+        let old = dest
+            .exec(&format!("cat {}", shell_escape(&dest_path)))
+            .await?;
+        entry.diff = Some(ai_line_diff(&old.stdout_string(), &new.stdout_string()));
+    }
+    Ok(PlanSection { target, entries })
+}
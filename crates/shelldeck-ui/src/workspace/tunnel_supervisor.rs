@@ -0,0 +1,516 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use gpui::*;
+use shelldeck_core::config::activity::{ActivityAction, ActivityEntry, ActivityKind};
+use shelldeck_core::config::cloud_account::AppMode;
+use shelldeck_core::config::tunnel_history::TunnelHistory;
+use shelldeck_core::models::port_forward::ForwardStatus;
+use shelldeck_core::models::schedule::TriggerEvent;
+use shelldeck_core::models::tunnel_health::TunnelState;
+use shelldeck_ssh::supervisor::{SupervisorEvent, SupervisorOptions, TunnelSupervisor};
+use uuid::Uuid;
+
+use crate::t;
+use crate::toast::ToastLevel;
+
+use super::{ActiveTunnel, TrayNotification, Workspace};
+
+/// How often running forwards are checked for an outage worth notifying.
+const OUTAGE_TICK: Duration = Duration::from_secs(15);
+
+impl Workspace {
+    /// Load the tunnel history, bring up the forwards marked to start with
+    /// ShellDeck, and watch for outages for as long as the workspace lives.
+    pub fn start_tunnel_supervisor(&mut self, cx: &mut Context<Self>) {
+        if self._tunnel_watch_task.is_some() {
+            return;
+        }
+        self.tunnel_history = TunnelHistory::load().unwrap_or_else(|e| {
+            tracing::warn!("Failed to load tunnel history: {}", e);
+            TunnelHistory::default()
+        });
+        let live: Vec<Uuid> = self.store.port_forwards.iter().map(|f| f.id).collect();
+        self.tunnel_history.retain_forwards(&live);
+        // Anything still marked up or down was cut short by the last exit.
+        let interrupted: Vec<Uuid> = self
+            .tunnel_history
+            .forwards
+            .iter()
+            .filter(|(_, history)| history.state() != Some(TunnelState::Stopped))
+            .map(|(id, _)| *id)
+            .collect();
+        for forward_id in interrupted {
+            self.tunnel_history
+                .record(forward_id, TunnelState::Stopped, None);
+        }
+        self.save_tunnel_history();
+        let history = self.tunnel_history.forwards.clone();
+        self.port_forwards.update(cx, |view, cx| {
+            view.history = history;
+            cx.notify();
+        });
+
+        if self.can_access_mode(AppMode::Dev) {
+            let auto_start: Vec<Uuid> = self
+                .store
+                .port_forwards
+                .iter()
+                .filter(|f| f.auto_start)
+                .map(|f| f.id)
+                .collect();
+            for forward_id in auto_start {
+                self.start_forward(forward_id, cx);
+            }
+        }
+
+        self._tunnel_watch_task = Some(cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor().timer(OUTAGE_TICK).await;
+            if this
+                .update(cx, |ws, cx| ws.check_tunnel_outages(cx))
+                .is_err()
+            {
+                break;
+            }
+        }));
+    }
+
+    /// Hand a forward to a supervisor, which keeps it up until it is
+    /// stopped.
+    pub(super) fn start_forward(&mut self, forward_id: Uuid, cx: &mut Context<Self>) {
+        tracing::info!("Start forward requested: {}", forward_id);
+        let forward = self
+            .port_forwards
+            .read(cx)
+            .forwards
+            .iter()
+            .find(|f| f.id == forward_id)
+            .cloned();
+        let Some(forward) = forward else {
+            tracing::error!("Port forward not found: {}", forward_id);
+            self.add_activity(
+                t!("activity.forward_not_found", id = forward_id).to_string(),
+                ActivityKind::Error,
+                cx,
+            );
+            self.show_toast(
+                t!("toast.port_forward.not_found").to_string(),
+                ToastLevel::Error,
+                cx,
+            );
+            return;
+        };
+        if self.active_tunnels.contains_key(&forward_id) {
+            tracing::warn!("Port forward {} is already active", forward_id);
+            return;
+        }
+        // A forward reachable from the network must have been confirmed
+        // as such; no retry would fix that.
+        if let Some(error) = forward.bind_error() {
+            tracing::error!("Refusing to start forward {}: {}", forward_id, error);
+            self.set_forward_status(forward_id, ForwardStatus::Error, cx);
+            self.show_toast(
+                t!("toast.forward.bind_refused", error = error.as_str()).to_string(),
+                ToastLevel::Error,
+                cx,
+            );
+            cx.notify();
+            return;
+        }
+        let Some(connection) = self
+            .connections
+            .iter()
+            .find(|c| c.id == forward.connection_id)
+            .cloned()
+        else {
+            tracing::error!(
+                "Connection {} not found for port forward {}",
+                forward.connection_id,
+                forward_id
+            );
+            self.set_forward_status(forward_id, ForwardStatus::Error, cx);
+            self.add_activity(
+                t!("activity.forward_connection_not_found").to_string(),
+                ActivityKind::Error,
+                cx,
+            );
+            self.show_toast(
+                t!("toast.forward.connection_not_found").to_string(),
+                ToastLevel::Error,
+                cx,
+            );
+            cx.notify();
+            return;
+        };
+
+        let label = forward
+            .label
+            .clone()
+            .unwrap_or_else(|| forward.description());
+        let tunnels = &self.app_config.tunnels;
+        let options = SupervisorOptions {
+            max_backoff: Duration::from_secs(tunnels.max_backoff_secs.max(1)),
+            probe_failures: tunnels.probe_failures,
+            ..SupervisorOptions::default()
+        };
+        let (events_tx, mut events_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (shutdown_tx, shutdown_rx) = tokio::sync::mpsc::channel::<()>(1);
+        let supervisor = TunnelSupervisor::new(connection, forward, options, events_tx);
+        let tunnel_handle = supervisor.handle(shutdown_tx);
+        let traffic = tunnel_handle.traffic.clone();
+        self.port_forwards.update(cx, |view, _| {
+            view.traffic.insert(forward_id, traffic.clone());
+        });
+
+        // A dedicated thread with its own runtime: the supervisor and the
+        // tunnels it starts run on it until the forward is stopped.
+        let thread = std::thread::Builder::new()
+            .name(format!("tunnel-{}", forward_id))
+            .spawn(move || {
+                match tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                {
+                    Ok(rt) => rt.block_on(supervisor.run(shutdown_rx)),
+                    Err(e) => tracing::error!("Failed to create async runtime: {}", e),
+                }
+            });
+        let thread = match thread {
+            Ok(thread) => thread,
+            Err(e) => {
+                tracing::error!("Failed to spawn tunnel thread: {}", e);
+                self.set_forward_status(forward_id, ForwardStatus::Error, cx);
+                self.add_activity(
+                    t!("activity.forward_start_failed", label = label.as_str()).to_string(),
+                    ActivityKind::Error,
+                    cx,
+                );
+                self.show_toast(
+                    t!("toast.forward.start_failed", error = e.to_string()).to_string(),
+                    ToastLevel::Error,
+                    cx,
+                );
+                cx.notify();
+                return;
+            }
+        };
+
+        let run_id = Uuid::new_v4();
+        self.active_tunnels.insert(
+            forward_id,
+            ActiveTunnel {
+                tunnel_handle,
+                run_id,
+                was_up: false,
+                _thread: thread,
+            },
+        );
+        self.set_forward_status(forward_id, ForwardStatus::Reconnecting, cx);
+        self.add_activity_entry(
+            ActivityEntry::new(
+                ActivityKind::Forward,
+                t!("activity.forward_starting", label = label.as_str()).to_string(),
+            )
+            .with_target(forward_id.to_string(), label.clone())
+            .with_action(ActivityAction::OpenForward),
+            cx,
+        );
+        self.show_toast(
+            t!("toast.forward.starting", label = label.as_str()).to_string(),
+            ToastLevel::Info,
+            cx,
+        );
+        self.update_dashboard_stats(cx);
+
+        let mut seen_revision = 0;
+        cx.spawn(async move |this, cx: &mut AsyncApp| loop {
+            cx.background_executor()
+                .timer(Duration::from_millis(250))
+                .await;
+            let revision = traffic.lock().revision();
+            let traffic_changed = revision != seen_revision;
+            seen_revision = revision;
+            let mut events = Vec::new();
+            let mut finished = false;
+            loop {
+                match events_rx.try_recv() {
+                    Ok(event) => events.push(event),
+                    Err(tokio::sync::mpsc::error::TryRecvError::Empty) => break,
+                    Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+            let updated = this.update(cx, |ws, cx| {
+                for event in events {
+                    ws.handle_supervisor_event(forward_id, run_id, event, cx);
+                }
+                if traffic_changed {
+                    ws.refresh_forward_traffic(forward_id, run_id, cx);
+                }
+            });
+            if finished || updated.is_err() {
+                break;
+            }
+        })
+        .detach();
+        cx.notify();
+    }
+
+    fn handle_supervisor_event(
+        &mut self,
+        forward_id: Uuid,
+        run_id: Uuid,
+        event: SupervisorEvent,
+        cx: &mut Context<Self>,
+    ) {
+        // Events from a supervisor that was stopped since are stale.
+        let was_up = match self.active_tunnels.get(&forward_id) {
+            Some(active) if active.run_id == run_id => active.was_up,
+            _ => return,
+        };
+        let (label, connection_id, requested_port) = self
+            .port_forwards
+            .read(cx)
+            .forwards
+            .iter()
+            .find(|f| f.id == forward_id)
+            .map(|f| {
+                (
+                    f.label.clone().unwrap_or_else(|| f.description()),
+                    Some(f.connection_id),
+                    f.local_port,
+                )
+            })
+            .unwrap_or_else(|| (format!("forward {}", forward_id), None, 0));
+
+        match event {
+            SupervisorEvent::Up { local_addr } => {
+                let first = !was_up;
+                if let Some(active) = self.active_tunnels.get_mut(&forward_id) {
+                    active.was_up = true;
+                }
+                if let Some(addr) = local_addr {
+                    let previous = self
+                        .port_forwards
+                        .update(cx, |view, _| view.bound_addrs.insert(forward_id, addr));
+                    // Say so once per port, not on every reconnect to it.
+                    let moved = addr.port() != requested_port
+                        && previous.map(|p| p.port()) != Some(addr.port());
+                    if moved {
+                        self.add_activity_entry(
+                            ActivityEntry::new(
+                                ActivityKind::Forward,
+                                t!(
+                                    "activity.forward_port_moved",
+                                    label = label.as_str(),
+                                    requested = requested_port,
+                                    port = addr.port()
+                                )
+                                .to_string(),
+                            )
+                            .with_target(forward_id.to_string(), label.clone())
+                            .with_action(ActivityAction::OpenForward),
+                            cx,
+                        );
+                        self.show_toast(
+                            t!(
+                                "toast.forward.port_moved",
+                                requested = requested_port,
+                                port = addr.port()
+                            )
+                            .to_string(),
+                            ToastLevel::Warning,
+                            cx,
+                        );
+                    }
+                }
+                self.set_forward_status(forward_id, ForwardStatus::Active, cx);
+                self.record_tunnel_state(forward_id, TunnelState::Up, None, cx);
+                let message = if first {
+                    t!("activity.forward_active", label = label.as_str()).to_string()
+                } else {
+                    t!("activity.forward_reconnected", label = label.as_str()).to_string()
+                };
+                self.add_activity_entry(
+                    ActivityEntry::new(ActivityKind::Forward, message)
+                        .with_target(forward_id.to_string(), label.clone())
+                        .with_action(ActivityAction::OpenForward),
+                    cx,
+                );
+                if first {
+                    self.show_toast(
+                        t!("toast.forward.active", label = label.as_str()).to_string(),
+                        ToastLevel::Success,
+                        cx,
+                    );
+                    if let Some(connection_id) = connection_id {
+                        self.fire_script_triggers(
+                            TriggerEvent::TunnelStarted {
+                                forward_id,
+                                connection_id,
+                            },
+                            cx,
+                        );
+                    }
+                }
+                self.update_dashboard_stats(cx);
+            }
+            SupervisorEvent::Down { reason, retry_in } => {
+                let first_failure = !was_up;
+                self.set_forward_status(forward_id, ForwardStatus::Reconnecting, cx);
+                self.port_forwards.update(cx, |view, _| {
+                    view.probes.remove(&forward_id);
+                });
+                // Retries while already down are not news.
+                if self.record_tunnel_state(forward_id, TunnelState::Down, Some(reason.clone()), cx)
+                {
+                    self.add_activity_entry(
+                        ActivityEntry::new(
+                            ActivityKind::Error,
+                            t!(
+                                "activity.forward_down",
+                                label = label.as_str(),
+                                error = reason.as_str(),
+                                secs = retry_in.as_secs().max(1)
+                            )
+                            .to_string(),
+                        )
+                        .with_target(forward_id.to_string(), label.clone())
+                        .with_action(ActivityAction::OpenForward),
+                        cx,
+                    );
+                    if first_failure {
+                        self.show_toast(
+                            t!("toast.forward.failed", error = reason.as_str()).to_string(),
+                            ToastLevel::Error,
+                            cx,
+                        );
+                    }
+                }
+            }
+            SupervisorEvent::Probe(result) => {
+                if let Err(e) = &result {
+                    tracing::warn!("Probe of forward {} failed: {}", forward_id, e);
+                }
+                self.port_forwards.update(cx, |view, _| {
+                    view.probes.insert(forward_id, result);
+                });
+            }
+        }
+        cx.notify();
+    }
+
+    /// Show the running totals and the traffic panel of a forward after
+    /// new traffic went through it.
+    fn refresh_forward_traffic(&mut self, forward_id: Uuid, run_id: Uuid, cx: &mut Context<Self>) {
+        let Some(active) = self
+            .active_tunnels
+            .get(&forward_id)
+            .filter(|active| active.run_id == run_id)
+        else {
+            return;
+        };
+        let (sent, received) = active.tunnel_handle.total_bytes();
+        self.port_forwards.update(cx, |view, cx| {
+            if let Some(f) = view.forwards.iter_mut().find(|f| f.id == forward_id) {
+                f.bytes_sent = sent;
+                f.bytes_received = received;
+            }
+            cx.notify();
+        });
+    }
+
+    /// Notify once about each supervised forward that has stayed down
+    /// longer than the configured threshold.
+    fn check_tunnel_outages(&mut self, cx: &mut Context<Self>) {
+        let threshold = chrono::Duration::seconds(self.app_config.tunnels.down_alert_secs as i64);
+        let now = Utc::now();
+        let overdue: Vec<(Uuid, i64)> = self
+            .active_tunnels
+            .keys()
+            .filter(|id| !self.tunnel_alerts.contains(id))
+            .filter_map(|id| {
+                let since = self.tunnel_history.get(*id)?.down_since()?;
+                (now - since >= threshold).then_some((*id, (now - since).num_minutes().max(1)))
+            })
+            .collect();
+        for (forward_id, minutes) in overdue {
+            self.tunnel_alerts.insert(forward_id);
+            let label = self
+                .port_forwards
+                .read(cx)
+                .forwards
+                .iter()
+                .find(|f| f.id == forward_id)
+                .map(|f| f.label.clone().unwrap_or_else(|| f.description()))
+                .unwrap_or_else(|| format!("forward {}", forward_id));
+            self.add_activity_entry(
+                ActivityEntry::new(
+                    ActivityKind::Error,
+                    t!(
+                        "activity.forward_still_down",
+                        label = label.as_str(),
+                        minutes = minutes
+                    )
+                    .to_string(),
+                )
+                .with_target(forward_id.to_string(), label.clone())
+                .with_action(ActivityAction::OpenForward),
+                cx,
+            );
+            if self.app_config.tray.notify_tunnel_down {
+                self.emit_tray_notification(TrayNotification::TunnelDown {
+                    name: label,
+                    minutes,
+                });
+            }
+        }
+    }
+
+    /// Record a state change of a forward and show it in the list. Returns
+    /// whether the state changed.
+    pub(super) fn record_tunnel_state(
+        &mut self,
+        forward_id: Uuid,
+        state: TunnelState,
+        reason: Option<String>,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if !self.tunnel_history.record(forward_id, state, reason) {
+            return false;
+        }
+        if state != TunnelState::Down {
+            self.tunnel_alerts.remove(&forward_id);
+        }
+        self.save_tunnel_history();
+        let history = self.tunnel_history.get(forward_id).cloned();
+        self.port_forwards.update(cx, |view, _| {
+            if let Some(history) = history {
+                view.history.insert(forward_id, history);
+            }
+        });
+        true
+    }
+
+    fn save_tunnel_history(&self) {
+        if let Err(e) = self.tunnel_history.save() {
+            tracing::warn!("Failed to save tunnel history: {}", e);
+        }
+    }
+
+    pub(super) fn set_forward_status(
+        &mut self,
+        forward_id: Uuid,
+        status: ForwardStatus,
+        cx: &mut Context<Self>,
+    ) {
+        self.port_forwards.update(cx, |view, cx| {
+            if let Some(f) = view.forwards.iter_mut().find(|f| f.id == forward_id) {
+                f.status = status;
+            }
+            cx.notify();
+        });
+    }
+}
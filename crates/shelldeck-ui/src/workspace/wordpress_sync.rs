@@ -0,0 +1,323 @@
+use shelldeck_core::models::connection::Connection;
+use shelldeck_core::models::db_credentials::DbLogin;
+use shelldeck_core::models::discovery;
+use shelldeck_core::models::server_sync::{SyncItemKind, SyncOptions, SyncStep};
+use shelldeck_core::models::wordpress::{self, SearchReplace, WpConfig};
+use shelldeck_core::util::shell_escape;
+use shelldeck_ssh::session::SshSession;
+use uuid::Uuid;
+
+use super::database_sync::{remove_secret, upload_secret};
+use super::server_sync::{
+    forward_relay_events, relay_commands, relay_item, run_direct, ItemProgress, RelayReporter,
+};
+
+/// Migrate one WordPress site: files, database with the URL search-replace,
+/// `wp-config.php` for the destination database, then caches. Each step is
+/// announced through `reporter` before it starts; the first failing step
+/// ends the item, except the cache flush, which only warns. The file sync
+/// skips `wp-config.php`, so a failed database step leaves the destination
+/// site on its own database.
+#[allow(clippy::too_many_arguments)]
+pub(super) async fn sync_wordpress_site(
+    item_id: Uuid,
+    kind: &SyncItemKind,
+    source: &SshSession,
+    dest: &SshSession,
+    dest_conn: &Connection,
+    relay: bool,
+    options: &SyncOptions,
+    shutdown_rx: &mut tokio::sync::mpsc::Receiver<()>,
+    reporter: &RelayReporter,
+) -> bool {
+    let SyncItemKind::WordPressSite {
+        site,
+        wp_config_path,
+        source_db,
+        dest_db,
+        old_url,
+        new_url,
+        ..
+    } = kind
+    else {
+        return false;
+    };
+    let log = |line: String| {
+        let _ = reporter.log.send((item_id, line));
+    };
+
+    // Profiles keep no database passwords. Both come from the wp-config.php
+    // files.
+    let source_config = match read_file(source, wp_config_path).await {
+        Ok(content) => content,
+        Err(e) => {
+            log(format!("[sync] Cannot read {}: {}", wp_config_path, e));
+            return false;
+        }
+    };
+    let source_db = with_password(source_db, &source_config);
+    let dest_db = match read_file(dest, wp_config_path).await {
+        Ok(content) => with_password(dest_db, &content),
+        Err(_) => dest_db.clone(),
+    };
+    let dest_db = if dest_db.db_password.is_empty() && dest_db.db_user == source_db.db_user {
+        WpConfig {
+            db_password: source_db.db_password.clone(),
+            ..dest_db
+        }
+    } else {
+        dest_db
+    };
+
+    // Files
+    step(reporter, item_id, SyncStep::Files);
+    let commands = discovery::sync_item_commands(
+        kind,
+        &dest_conn.user,
+        &dest_conn.hostname,
+        dest_conn.port,
+        options,
+        relay,
+    );
+    let files_ok = if relay {
+        relay_item(
+            item_id,
+            source,
+            dest,
+            &relay_commands(commands),
+            options,
+            shutdown_rx,
+            reporter,
+        )
+        .await
+    } else {
+        run_direct(item_id, source, commands, reporter).await
+    };
+    if !files_ok {
+        return false;
+    }
+
+    if options.dry_run {
+        log(format!(
+            "[sync] Dry run: would copy database {} to {}",
+            source_db.db_name, dest_db.db_name
+        ));
+        if old_url != new_url {
+            log(format!(
+                "[sync] Dry run: would replace {} with {}",
+                old_url, new_url
+            ));
+        }
+        log(format!(
+            "[sync] Dry run: would point {} at the destination database",
+            wp_config_path
+        ));
+        return true;
+    }
+
+    // The mysql clients read their logins from secret files, not argv.
+    let source_login = match upload_secret(source, &source_db.option_file()).await {
+        Ok(path) => DbLogin::new(None, Some(path)),
+        Err(e) => {
+            log(format!(
+                "[sync] Cannot set up the source database login: {}",
+                e
+            ));
+            return false;
+        }
+    };
+    let dest_login = match upload_secret(dest, &dest_db.option_file()).await {
+        Ok(path) => DbLogin::new(None, Some(path)),
+        Err(e) => {
+            log(format!(
+                "[sync] Cannot set up the destination database login: {}",
+                e
+            ));
+            remove_secret(source, &source_login).await;
+            return false;
+        }
+    };
+
+    let success = migrate_database(
+        item_id,
+        source,
+        dest,
+        (&source_db, &source_login),
+        (&dest_db, &dest_login),
+        (old_url, new_url),
+        options,
+        shutdown_rx,
+        reporter,
+    )
+    .await
+        && update_config(
+            item_id,
+            dest,
+            wp_config_path,
+            &source_config,
+            &dest_db,
+            reporter,
+        )
+        .await;
+
+    if success {
+        // Caches
+        step(reporter, item_id, SyncStep::Caches);
+        match dest
+            .exec(&wordpress::flush_caches_command(
+                &site.root,
+                &dest_db,
+                &dest_login,
+            ))
+            .await
+        {
+            Ok(r) if r.success() => log("[sync] Caches flushed".to_string()),
+            Ok(r) => log(format!(
+                "[sync] Warning: cache flush incomplete: {}",
+                r.stderr_string().trim()
+            )),
+            Err(e) => log(format!("[sync] Warning: cache flush failed: {}", e)),
+        }
+    }
+
+    remove_secret(source, &source_login).await;
+    remove_secret(dest, &dest_login).await;
+    success
+}
+
+fn step(reporter: &RelayReporter, item_id: Uuid, step: SyncStep) {
+    let _ = reporter.bytes.send((item_id, ItemProgress::Step(step)));
+}
+
+async fn read_file(session: &SshSession, path: &str) -> Result<String, String> {
+    match session.exec(&format!("cat {}", shell_escape(path))).await {
+        Ok(r) if r.success() => Ok(r.stdout_string()),
+        Ok(r) => Err(r.stderr_string().trim().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `config` with the password `wp-config.php` holds for the same user.
+fn with_password(config: &WpConfig, content: &str) -> WpConfig {
+    let mut config = config.clone();
+    if config.db_password.is_empty() {
+        if let Some(found) = wordpress::parse_wp_config(content) {
+            if found.db_user == config.db_user {
+                config.db_password = found.db_password;
+            }
+        }
+    }
+    config
+}
+
+/// Stream the dump into the destination with the search-replace applied in
+/// flight.
+#[allow(clippy::too_many_arguments)]
+async fn migrate_database(
+    item_id: Uuid,
+    source: &SshSession,
+    dest: &SshSession,
+    (source_db, source_login): (&WpConfig, &DbLogin),
+    (dest_db, dest_login): (&WpConfig, &DbLogin),
+    (old_url, new_url): (&str, &str),
+    options: &SyncOptions,
+    shutdown_rx: &mut tokio::sync::mpsc::Receiver<()>,
+    reporter: &RelayReporter,
+) -> bool {
+    let log = |line: String| {
+        let _ = reporter.log.send((item_id, line));
+    };
+
+    step(reporter, item_id, SyncStep::Database);
+    let dump = wordpress::wp_dump_command(source_db, source_login);
+    let restore = wordpress::wp_restore_command(dest_db, dest_login);
+    log(format!("[sync] Relaying: {} | {}", dump, restore));
+    let mut replacer = SearchReplace::new(old_url, new_url);
+    let (events_tx, fwd_task) = forward_relay_events(item_id, 0, reporter);
+    let result = source
+        .relay_through(
+            &dump,
+            dest,
+            &restore,
+            events_tx,
+            shutdown_rx,
+            options.bandwidth_limit,
+            &mut |chunk| replacer.feed(chunk),
+        )
+        .await;
+    let _ = fwd_task.await;
+    match result {
+        Ok(outcome) if outcome.success() => {
+            if old_url != new_url {
+                log(format!(
+                    "[sync] Replaced {} occurrences of {} with {}",
+                    replacer.replacements(),
+                    old_url,
+                    new_url
+                ));
+            }
+            true
+        }
+        Ok(outcome) if outcome.cancelled => {
+            log("[sync] Relay cancelled".to_string());
+            false
+        }
+        Ok(outcome) => {
+            log(format!(
+                "[sync] Database copy failed (source exit {:?}, destination exit {:?})",
+                outcome.source_exit, outcome.dest_exit
+            ));
+            false
+        }
+        Err(e) => {
+            log(format!("[sync] Error: {}", e));
+            false
+        }
+    }
+}
+
+/// Write the source's wp-config.php, pointed at the destination database,
+/// to the destination. The content goes over stdin since it holds the
+/// password.
+async fn update_config(
+    item_id: Uuid,
+    dest: &SshSession,
+    wp_config_path: &str,
+    source_config: &str,
+    dest_db: &WpConfig,
+    reporter: &RelayReporter,
+) -> bool {
+    let log = |line: String| {
+        let _ = reporter.log.send((item_id, line));
+    };
+
+    step(reporter, item_id, SyncStep::Config);
+    let rewritten = wordpress::rewrite_wp_config(source_config, dest_db);
+    match dest
+        .exec_with_stdin(
+            &wordpress::write_stdin_command(wp_config_path),
+            rewritten.as_bytes(),
+        )
+        .await
+    {
+        Ok(r) if r.success() => {
+            log(format!(
+                "[sync] Updated {} for database {}",
+                wp_config_path, dest_db.db_name
+            ));
+            true
+        }
+        Ok(r) => {
+            log(format!(
+                "[sync] Cannot write {}: {}",
+                wp_config_path,
+                r.stderr_string().trim()
+            ));
+            false
+        }
+        Err(e) => {
+            log(format!("[sync] Error: {}", e));
+            false
+        }
+    }
+}
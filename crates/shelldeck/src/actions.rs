@@ -13,8 +13,8 @@ pub use shelldeck_ui::command_palette::{
     ApplyTerminalTheme, OpenManageArea, SetAppMode, ToggleCommandPalette,
 };
 pub use shelldeck_ui::terminal_view::{
-    ClearTerminal, CopySelection, PasteClipboard, SplitHorizontal, SplitVertical, ToggleSearch,
-    ToggleSplitFocus, ZoomIn, ZoomOut, ZoomReset,
+    ClearTerminal, CopySelection, PasteClipboard, SplitHorizontal, SplitVertical, ToggleCopyMode,
+    ToggleSearch, ToggleSplitFocus, ZoomIn, ZoomOut, ZoomReset,
 };
 
 /// Register all keyboard shortcuts.
@@ -49,6 +49,8 @@ pub fn register_keybindings(cx: &mut App) {
         KeyBinding::new("secondary-l", ClearTerminal, None),
         // Search: Cmd+F (macOS) / Ctrl+F (Linux/Win) — intercepted before terminal
         KeyBinding::new("secondary-f", ToggleSearch, None),
+        // Keyboard copy mode (vi motions over scrollback): Ctrl+Shift+Space
+        KeyBinding::new("ctrl-shift-space", ToggleCopyMode, None),
         // Zoom: Cmd+=/- (macOS) / Ctrl+=/- (Linux/Win)
         KeyBinding::new("secondary-=", ZoomIn, None),
         KeyBinding::new("secondary--", ZoomOut, None),
@@ -9,7 +9,7 @@ use shelldeck_core::ai::ClippyConfig;
 use shelldeck_core::config::app_config::{AppConfig, CompanionConfig};
 use shelldeck_core::config::deep_link::DeepLink;
 use shelldeck_core::config::single_instance::{self, Acquire};
-use shelldeck_core::config::ssh_config::parse_ssh_config;
+use shelldeck_core::config::ssh_config::import_ssh_config;
 use shelldeck_core::config::store::ConnectionStore;
 use shelldeck_core::models::connection::Connection;
 use shelldeck_ui::theme::ShellDeckColors;
@@ -620,6 +620,12 @@ impl CompanionRoot {
 
         workspace.update(cx, |ws, cx| {
             ws.start_git_polling(window.window_handle(), cx);
+            ws.start_script_scheduler(cx);
+            ws.start_site_monitor(cx);
+            ws.start_backup_scheduler(cx);
+            ws.start_tunnel_supervisor(cx);
+            ws.start_host_metrics(cx);
+            ws.start_ssh_config_watch(cx);
         });
         workspace.read(cx).focus_handle.focus(window);
         workspace.update(cx, |ws, cx| ws.restore_session(cx));
@@ -1655,19 +1661,33 @@ fn merge_workspace_connections(
 /// managed connections that the Dock/tray runtime does not consume.
 fn load_workspace_data() -> (Vec<Connection>, ConnectionStore) {
     tracing::info!("loading deferred Workspace connection data");
-    let ssh_connections = parse_ssh_config().unwrap_or_else(|error| {
-        tracing::warn!("Failed to parse SSH config: {error}");
-        Vec::new()
-    });
+    let ssh_config = import_ssh_config();
+    let (ssh_connections, ssh_forwards) = match ssh_config {
+        Ok(import) => (import.connections, Some(import.forwards)),
+        Err(error) => {
+            tracing::warn!("Failed to parse SSH config: {error}");
+            (Vec::new(), None)
+        }
+    };
     tracing::info!(
         "Loaded {} connections from SSH config",
         ssh_connections.len()
     );
 
-    let store = ConnectionStore::load().unwrap_or_else(|error| {
-        tracing::warn!("Failed to load connection store: {error}");
-        ConnectionStore::default()
-    });
+    let (mut store, store_loaded) = match ConnectionStore::load() {
+        Ok(store) => (store, true),
+        Err(error) => {
+            tracing::warn!("Failed to load connection store: {error}");
+            (ConnectionStore::default(), false)
+        }
+    };
+    // Only a config that parsed may drop the forwards it no longer lists,
+    // and an unreadable store is not overwritten here.
+    if let (Some(forwards), true) = (ssh_forwards, store_loaded) {
+        if let Err(error) = store.sync_ssh_config_forwards(&forwards) {
+            tracing::warn!("Failed to save SSH config forwards: {error}");
+        }
+    }
     tracing::info!(
         "Loaded {} manual connections, {} scripts, {} port forwards",
         store.connections.len(),